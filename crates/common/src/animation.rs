@@ -5,16 +5,50 @@ use crate::{
 };
 use bevy::{
     asset::Handle,
-    prelude::{AnimationGraph, AnimationNodeIndex, Component, Resource, States},
+    gltf::Gltf,
+    prelude::{AnimationGraph, AnimationNodeIndex, Component, Entity, Event, Resource, States},
 };
+use strum_macros::EnumIter;
+
+// Player animations are exported from Blender at this frame rate
+pub const PLAYER_ANIMATION_FPS: f32 = 30.0;
+
+// Index of the clip of every player animation in the player's glb, in the order they are
+// exported from Blender. The animation graph has a node for each of them, in the same order.
+pub const PLAYER_ANIMATION_CLIPS: [(PlayerAnimation, usize); 11] = [
+    (PlayerAnimation::Idle, 0),
+    (PlayerAnimation::Jogging, 1),
+    (PlayerAnimation::OneHandedSlashRightLightAttack, 2),
+    (PlayerAnimation::Running, 3),
+    (PlayerAnimation::UnarmedLeftHeavyAttack, 4),
+    (PlayerAnimation::UnarmedLeftLightAttack, 5),
+    (PlayerAnimation::UnarmedRightHeavyAttack, 6),
+    (PlayerAnimation::UnarmedRightLightAttack, 7),
+    (PlayerAnimation::OneHandedSlashLeftHeavyAttack, 8),
+    (PlayerAnimation::OneHandedSlashLeftLightAttack, 9),
+    (PlayerAnimation::OneHandedSlashRightHeavyAttack, 10),
+];
 
 #[derive(Resource)]
 pub struct AnimationLib {
     pub nodes: Vec<AnimationNodeIndex>,
     pub graph: Handle<AnimationGraph>,
+    pub player_gltf: Handle<Gltf>,
+    // Number of clips in the player's glb, once it has loaded
+    pub player_clip_count: Option<usize>,
+}
+
+impl AnimationLib {
+    pub fn player_node(&self, player_animation: &PlayerAnimation) -> AnimationNodeIndex {
+        let index = match self.player_clip_count {
+            Some(clip_count) => player_animation.clip_index(clip_count),
+            None => player_animation.index(),
+        };
+        self.nodes[index]
+    }
 }
 
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, States)]
+#[derive(Clone, Copy, Debug, Default, EnumIter, Eq, Hash, PartialEq, States)]
 pub enum PlayerAnimation {
     #[default]
     Idle,
@@ -36,37 +70,81 @@ pub enum PlayerAnimation {
 
 impl PlayerAnimation {
     pub fn index(&self) -> usize {
+        PLAYER_ANIMATION_CLIPS
+            .iter()
+            .find(|(pa, _)| pa == self)
+            .map(|(_, index)| *index)
+            .unwrap()
+    }
+
+    // Same as index, but falls back to the default animation
+    // if the glb does not have a clip at that index
+    pub fn clip_index(&self, clip_count: usize) -> usize {
+        let index = self.index();
+        if index < clip_count {
+            index
+        } else {
+            Self::default().index()
+        }
+    }
+
+    // Animations whose clips are missing from a glb with clip_count clips
+    pub fn missing_clips(clip_count: usize) -> Vec<Self> {
+        PLAYER_ANIMATION_CLIPS
+            .iter()
+            .filter(|(_, index)| *index >= clip_count)
+            .map(|(pa, _)| *pa)
+            .collect()
+    }
+
+    // Length of an attack animation's clip in frames, or 0 for animations that are not attacks
+    pub fn attack_frames(&self) -> u32 {
+        match self {
+            Self::UnarmedLeftLightAttack | Self::UnarmedRightLightAttack => 18,
+            Self::UnarmedLeftHeavyAttack | Self::UnarmedRightHeavyAttack => 26,
+            Self::OneHandedSlashLeftLightAttack | Self::OneHandedSlashRightLightAttack => 22,
+            Self::OneHandedSlashLeftHeavyAttack | Self::OneHandedSlashRightHeavyAttack => 32,
+            Self::Idle | Self::Jogging | Self::Running => 0,
+        }
+    }
+
+    // Frames of an attack animation during which its hits land,
+    // so that the wind up and follow through of an attack do not deal damage
+    pub fn hit_window(&self) -> Option<HitWindow> {
         match self {
-            // TODO: fix glb animation ordering when exporting from Blender
-            Self::Idle => 0,
-            Self::Jogging => 1,
-            Self::OneHandedSlashRightLightAttack => 2,
-            Self::Running => 3,
-            Self::UnarmedLeftHeavyAttack => 4,
-            Self::UnarmedLeftLightAttack => 5,
-            Self::UnarmedRightHeavyAttack => 6,
-            Self::UnarmedRightLightAttack => 7,
-            Self::OneHandedSlashLeftHeavyAttack => 7, // TODO
-            Self::OneHandedSlashLeftLightAttack => 7, // TODO
-            Self::OneHandedSlashRightHeavyAttack => 7, // TODO
+            Self::UnarmedLeftLightAttack | Self::UnarmedRightLightAttack => {
+                Some(HitWindow::new(6, 12))
+            }
+            Self::UnarmedLeftHeavyAttack | Self::UnarmedRightHeavyAttack => {
+                Some(HitWindow::new(10, 18))
+            }
+            Self::OneHandedSlashLeftLightAttack | Self::OneHandedSlashRightLightAttack => {
+                Some(HitWindow::new(8, 15))
+            }
+            Self::OneHandedSlashLeftHeavyAttack | Self::OneHandedSlashRightHeavyAttack => {
+                Some(HitWindow::new(12, 22))
+            }
+            Self::Idle | Self::Jogging | Self::Running => None,
         }
     }
 
+    // Hands holding something other than a weapon attack the same as empty hands
     pub fn new_attack_animation(
         attack_type: &AttackType,
         attack_hand: &AttackHand,
         slot: &Option<Item>,
     ) -> Self {
-        match slot {
-            None => match (attack_type, attack_hand) {
-                // unarmed attacks
-                (AttackType::Light, AttackHand::Left) => Self::UnarmedLeftLightAttack,
-                (AttackType::Light, AttackHand::Right) => Self::UnarmedRightLightAttack,
-                (AttackType::Heavy, AttackHand::Left) => Self::UnarmedLeftHeavyAttack,
-                (AttackType::Heavy, AttackHand::Right) => Self::UnarmedRightHeavyAttack,
-            },
-            Some(item) => item.name.player_attack_animation(attack_type, attack_hand),
-        }
+        let weapon_animation = slot
+            .as_ref()
+            .and_then(|item| item.name.player_attack_animation(attack_type, attack_hand));
+
+        weapon_animation.unwrap_or(match (attack_type, attack_hand) {
+            // unarmed attacks
+            (AttackType::Light, AttackHand::Left) => Self::UnarmedLeftLightAttack,
+            (AttackType::Light, AttackHand::Right) => Self::UnarmedRightLightAttack,
+            (AttackType::Heavy, AttackHand::Left) => Self::UnarmedLeftHeavyAttack,
+            (AttackType::Heavy, AttackHand::Right) => Self::UnarmedRightHeavyAttack,
+        })
     }
 
     pub fn is_attack_animation(&self) -> bool {
@@ -96,26 +174,70 @@ impl PlayerAnimation {
     }
 }
 
+// Start frame is inclusive, end frame is exclusive
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct HitWindow {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl HitWindow {
+    pub fn new(start: u32, end: u32) -> Self {
+        Self { start, end }
+    }
+
+    pub fn contains(&self, frame: u32) -> bool {
+        self.start <= frame && frame < self.end
+    }
+}
+
 #[derive(Component)]
 pub struct ContinuousAnimation;
 
+// Plays the next clip in the range each time it is interacted with.
+// The value is the clip that will be played next.
 #[derive(Component)]
 pub struct CyclicAnimation {
     counter: CyclicCounter,
+    // Clip whose final pose the model is put in as soon as it spawns
+    rest_clip: Option<u32>,
 }
 
 impl CyclicAnimation {
     pub fn new(min: u32, max: u32) -> Self {
         Self {
             counter: CyclicCounter::new(min, max),
+            rest_clip: None,
         }
     }
 
-    fn _value(&self) -> u32 {
+    // Starts as if it had already been cycled up to value, resting
+    // at the end of the clip before it instead of playing that clip again
+    pub fn new_at(min: u32, max: u32, value: u32) -> Self {
+        let counter = CyclicCounter::new_at(min, max, value);
+        let value = counter.value();
+        Self {
+            counter,
+            rest_clip: (value != min).then(|| value - 1),
+        }
+    }
+
+    pub fn value(&self) -> u32 {
         self.counter.value()
     }
 
+    pub fn rest_clip(&self) -> Option<u32> {
+        self.rest_clip
+    }
+
     pub fn cycle(&mut self) -> u32 {
         self.counter.cycle()
     }
 }
+
+// Sent when interacting with something cycles its CyclicAnimation, with the clip being played
+#[derive(Debug, Event)]
+pub struct CyclicAnimationCycled {
+    pub entity: Entity,
+    pub clip: u32,
+}
@@ -0,0 +1,29 @@
+use bevy::prelude::{ComputedStates, States};
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, States)]
+pub enum AppState {
+    #[default]
+    MainMenu,
+    // Waits for assets to finish loading before starting the game
+    Loading,
+    InGame,
+    Paused,
+    // Free flying camera with the player frozen in place and the UI hidden
+    Spectating,
+}
+
+// Exists while a game is loaded, whether or not it is paused or being spectated.
+// The world, player, cameras and HUD are spawned on entering it and despawned on leaving it.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct GameLoaded;
+
+impl ComputedStates for GameLoaded {
+    type SourceStates = AppState;
+
+    fn compute(app_state: AppState) -> Option<Self> {
+        match app_state {
+            AppState::InGame | AppState::Paused | AppState::Spectating => Some(Self),
+            AppState::MainMenu | AppState::Loading => None,
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{
+    automap::{
+        cells_in_line_of_sight, pack_cell_xz, pack_chunk_xyz, unpack_cell_xz, unpack_chunk_xyz,
+        RevealedCells, VisitedCells,
+    },
+    world::{world_structure::NO_WORLD_STRUCTURE, Cell, CellWall, Chunk, ChunkCellMarker},
+};
+
+#[test]
+fn test_pack_chunk_xyz_round_trip() {
+    for xyz in [
+        (0, 0, 0),
+        (1, -1, 1),
+        (-20, 7, -3),
+        (1_048_575, -1_048_576, 12),
+    ] {
+        assert_eq!(unpack_chunk_xyz(pack_chunk_xyz(xyz)), xyz);
+    }
+}
+
+#[test]
+fn test_pack_cell_xz_round_trip() {
+    for xz in [(0, 0), (3, 1), (15, 15), (200, 7)] {
+        assert_eq!(unpack_cell_xz(pack_cell_xz(xz)), xz);
+    }
+}
+
+#[test]
+fn test_visited_cells_insert() {
+    let mut visited_cells = VisitedCells::default();
+    let ccm = ChunkCellMarker {
+        chunk_x: -2,
+        chunk_y: -1,
+        chunk_z: 3,
+        x: 1,
+        z: 2,
+    };
+
+    assert!(visited_cells.insert(&ccm));
+    assert!(!visited_cells.insert(&ccm));
+    assert!(visited_cells.contains(&ccm));
+    assert_eq!(visited_cells.len(), 1);
+    assert_eq!(visited_cells.iter().collect::<Vec<_>>(), vec![ccm]);
+    assert_eq!(visited_cells.iter_at_y(0).count(), 0);
+}
+
+// An open 5x5 grid, with walls around the center cell at (2, 2) in every direction but one
+fn line_of_sight_test_cells() -> Vec<Vec<Cell>> {
+    let mut cells = vec![vec![Cell::default(); 5]; 5];
+    // Toward +x, the neighbor's own wall blocks the way
+    cells[2][1].wall_bottom = CellWall::Solid;
+    // Toward -x, one cell further down the corridor
+    cells[2][3].wall_bottom = CellWall::Solid;
+    // Toward -z, gaps in the walls can be seen through
+    cells[3][2].wall_left = CellWall::SolidWithDoorGap;
+    cells[4][2].wall_left = CellWall::SolidWithWindowGap;
+    cells
+}
+
+#[test]
+fn test_cells_in_line_of_sight_stops_at_walls() {
+    let cells = line_of_sight_test_cells();
+
+    let mut seen = cells_in_line_of_sight(&cells, (2, 2));
+    seen.sort();
+    assert_eq!(seen, vec![(2, 0), (2, 1), (2, 2), (2, 3), (2, 4), (3, 2)]);
+}
+
+#[test]
+fn test_cells_in_line_of_sight_sees_through_gaps() {
+    let mut cells = line_of_sight_test_cells();
+    cells[4][2].wall_left = CellWall::Solid;
+
+    let seen = cells_in_line_of_sight(&cells, (2, 2));
+    assert!(seen.contains(&(2, 3)));
+    assert!(!seen.contains(&(2, 4)));
+}
+
+#[test]
+fn test_cells_in_line_of_sight_outside_of_chunk() {
+    let cells = line_of_sight_test_cells();
+    assert!(cells_in_line_of_sight(&cells, (5, 0)).is_empty());
+}
+
+#[test]
+fn test_revealed_cells_reveal_from() {
+    let chunk = Chunk {
+        x: -1,
+        y: 0,
+        z: 2,
+        cells: line_of_sight_test_cells(),
+        world_structure: NO_WORLD_STRUCTURE.to_string(),
+    };
+    let ccm = |x, z| ChunkCellMarker {
+        chunk_x: -1,
+        chunk_y: 0,
+        chunk_z: 2,
+        x,
+        z,
+    };
+
+    let mut revealed_cells = RevealedCells::default();
+    assert!(revealed_cells.reveal_from(&chunk, (2, 2)));
+    assert!(!revealed_cells.reveal_from(&chunk, (2, 2)));
+    assert_eq!(revealed_cells.len(), 6);
+    assert!(revealed_cells.contains(&ccm(3, 2)));
+    assert!(!revealed_cells.contains(&ccm(4, 2)));
+    assert_eq!(revealed_cells.iter_at_y(0).count(), 6);
+    assert_eq!(revealed_cells.iter_at_y(1).count(), 0);
+}
@@ -0,0 +1,202 @@
+#[cfg(test)]
+mod automap_test;
+
+use crate::world::{Cell, CellWall, Chunk, ChunkCellMarker, Side};
+use bevy::prelude::{Component, Event, Resource, Vec2};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+// Each chunk coordinate is packed into 21 bits of a single i64,
+// which supports chunk coordinates in the range of +/- 2^20.
+const CHUNK_COORD_BITS: u32 = 21;
+const CHUNK_COORD_MASK: i64 = (1 << CHUNK_COORD_BITS) - 1;
+
+const CELL_COORD_BITS: u32 = 16;
+const CELL_COORD_MASK: i32 = (1 << CELL_COORD_BITS) - 1;
+
+pub fn pack_chunk_xyz((x, y, z): (i64, i64, i64)) -> i64 {
+    ((x & CHUNK_COORD_MASK) << (CHUNK_COORD_BITS * 2))
+        | ((y & CHUNK_COORD_MASK) << CHUNK_COORD_BITS)
+        | (z & CHUNK_COORD_MASK)
+}
+
+pub fn unpack_chunk_xyz(packed: i64) -> (i64, i64, i64) {
+    let sign_extend = |v: i64| (v << (64 - CHUNK_COORD_BITS)) >> (64 - CHUNK_COORD_BITS);
+    (
+        sign_extend((packed >> (CHUNK_COORD_BITS * 2)) & CHUNK_COORD_MASK),
+        sign_extend((packed >> CHUNK_COORD_BITS) & CHUNK_COORD_MASK),
+        sign_extend(packed & CHUNK_COORD_MASK),
+    )
+}
+
+pub fn pack_cell_xz((x, z): (usize, usize)) -> i32 {
+    ((x as i32 & CELL_COORD_MASK) << CELL_COORD_BITS) | (z as i32 & CELL_COORD_MASK)
+}
+
+pub fn unpack_cell_xz(packed: i32) -> (usize, usize) {
+    (
+        ((packed >> CELL_COORD_BITS) & CELL_COORD_MASK) as usize,
+        (packed & CELL_COORD_MASK) as usize,
+    )
+}
+
+fn cell_key(ccm: &ChunkCellMarker) -> (i64, i32) {
+    (pack_chunk_xyz(ccm.chunk_xyz()), pack_cell_xz(ccm.cell_xz()))
+}
+
+fn cell_from_key((chunk_key, cell_key): (i64, i32)) -> ChunkCellMarker {
+    let (chunk_x, chunk_y, chunk_z) = unpack_chunk_xyz(chunk_key);
+    let (x, z) = unpack_cell_xz(cell_key);
+    ChunkCellMarker {
+        chunk_x,
+        chunk_y,
+        chunk_z,
+        x,
+        z,
+    }
+}
+
+// Door and window gaps can be seen through, whether or not there is a door in them
+fn is_see_through(wall: &CellWall) -> bool {
+    *wall != CellWall::Solid
+}
+
+// A cheap approximation of line of sight from a cell: the cell itself, and every cell
+// straight down a corridor from it in each direction until a solid wall. Stops at the
+// edges of the chunk.
+pub fn cells_in_line_of_sight(cells: &[Vec<Cell>], (x, z): (usize, usize)) -> Vec<(usize, usize)> {
+    if cells.get(z).and_then(|row| row.get(x)).is_none() {
+        return Vec::new();
+    }
+
+    let mut seen = vec![(x, z)];
+
+    for side in [Side::Top, Side::Bottom, Side::Left, Side::Right] {
+        let (mut curr_x, mut curr_z) = (x, z);
+
+        loop {
+            let (nei_x, nei_z) = match side {
+                Side::Top => (curr_x.checked_sub(1), Some(curr_z)),
+                Side::Bottom => (Some(curr_x + 1), Some(curr_z)),
+                Side::Left => (Some(curr_x), curr_z.checked_sub(1)),
+                Side::Right => (Some(curr_x), Some(curr_z + 1)),
+                Side::Up | Side::Down => break,
+            };
+            let Some((nei_x, nei_z)) = nei_x.zip(nei_z) else {
+                break;
+            };
+            let Some(nei) = cells.get(nei_z).and_then(|row| row.get(nei_x)) else {
+                break;
+            };
+
+            // Each cell has walls of its own, so the neighbor's wall has to be see-through too
+            if !is_see_through(cells[curr_z][curr_x].wall(side))
+                || !is_see_through(nei.wall(side.opposite()))
+            {
+                break;
+            }
+
+            seen.push((nei_x, nei_z));
+            (curr_x, curr_z) = (nei_x, nei_z);
+        }
+    }
+
+    seen
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Resource, Serialize)]
+pub struct VisitedCells(HashSet<(i64, i32)>);
+
+impl VisitedCells {
+    // Returns true if the cell had not been visited before
+    pub fn insert(&mut self, ccm: &ChunkCellMarker) -> bool {
+        self.0.insert(cell_key(ccm))
+    }
+
+    pub fn contains(&self, ccm: &ChunkCellMarker) -> bool {
+        self.0.contains(&cell_key(ccm))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ChunkCellMarker> + '_ {
+        self.0.iter().map(|&key| cell_from_key(key))
+    }
+
+    pub fn iter_at_y(&self, y: i64) -> impl Iterator<Item = ChunkCellMarker> + '_ {
+        self.iter().filter(move |ccm| ccm.chunk_y == y)
+    }
+}
+
+// Cells that have been seen from a visited cell, which are shown on the map without
+// having been visited. Saved separately from the visited cells.
+#[derive(Clone, Debug, Default, Deserialize, Resource, Serialize)]
+pub struct RevealedCells(HashSet<(i64, i32)>);
+
+impl RevealedCells {
+    // Reveals every cell in line of sight of a cell of the chunk.
+    // Returns true if any of them had not been revealed before.
+    pub fn reveal_from(&mut self, chunk: &Chunk, (x, z): (usize, usize)) -> bool {
+        let mut revealed = false;
+
+        for (seen_x, seen_z) in cells_in_line_of_sight(&chunk.cells, (x, z)) {
+            let ccm = ChunkCellMarker {
+                chunk_x: chunk.x,
+                chunk_y: chunk.y,
+                chunk_z: chunk.z,
+                x: seen_x,
+                z: seen_z,
+            };
+            revealed |= self.0.insert(cell_key(&ccm));
+        }
+
+        revealed
+    }
+
+    pub fn contains(&self, ccm: &ChunkCellMarker) -> bool {
+        self.0.contains(&cell_key(ccm))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter_at_y(&self, y: i64) -> impl Iterator<Item = ChunkCellMarker> + '_ {
+        self.0
+            .iter()
+            .map(|&key| cell_from_key(key))
+            .filter(move |ccm| ccm.chunk_y == y)
+    }
+}
+
+#[derive(Event)]
+pub struct VisitedCellsChanged;
+
+#[derive(Default, Resource)]
+pub struct AutomapView {
+    // World-space (x, z) position shown at the center of the map
+    pub pan: Vec2,
+    pub y: i64,
+}
+
+#[derive(Component)]
+pub struct AutomapCanvas;
+
+#[derive(Component)]
+pub struct AutomapOrigin;
+
+#[derive(Component)]
+pub struct AutomapYLevelButton(pub i64);
+
+#[derive(Component)]
+pub struct AutomapYLevelText;
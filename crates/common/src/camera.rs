@@ -1,7 +1,49 @@
-use bevy::prelude::Component;
+use bevy::prelude::{Component, Vec3};
+
+pub const CAMERA_SHAKE_SECS: f32 = 0.4;
+pub const CAMERA_SHAKE_MAX_MAGNITUDE: f32 = 0.15;
 
 #[derive(Component)]
 pub struct MainCamera;
 
 #[derive(Component)]
 pub struct AltCamera;
+
+// Random offset added on top of the main camera's position after it follows the player.
+// The offset applied last frame is kept so it can be taken back out before the next one,
+// so shaking never moves the camera itself.
+#[derive(Component, Debug, Default)]
+pub struct CameraShake {
+    pub remaining_secs: f32,
+    pub magnitude: f32,
+    pub applied_offset: Vec3,
+}
+
+impl CameraShake {
+    // Restarts the shake, adding onto whatever is left of the current one
+    pub fn add(&mut self, magnitude: f32) {
+        self.magnitude = (self.current_magnitude() + magnitude).min(CAMERA_SHAKE_MAX_MAGNITUDE);
+        self.remaining_secs = CAMERA_SHAKE_SECS;
+    }
+
+    pub fn current_magnitude(&self) -> f32 {
+        self.magnitude * camera_shake_decay(self.remaining_secs)
+    }
+
+    pub fn tick(&mut self, delta_secs: f32) {
+        self.remaining_secs = (self.remaining_secs - delta_secs).max(0.0);
+        if self.remaining_secs == 0.0 {
+            self.magnitude = 0.0;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.remaining_secs = 0.0;
+        self.magnitude = 0.0;
+    }
+}
+
+// Fraction of the shake's magnitude left, easing out so it settles down smoothly instead of stopping
+pub fn camera_shake_decay(remaining_secs: f32) -> f32 {
+    (remaining_secs / CAMERA_SHAKE_SECS).clamp(0.0, 1.0).powi(2)
+}
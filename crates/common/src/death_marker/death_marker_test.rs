@@ -0,0 +1,62 @@
+use crate::death_marker::{DeathMarkers, MAX_DEATH_MARKERS};
+use bevy::prelude::Vec3;
+
+#[test]
+fn test_death_markers_keep_most_recent() {
+    let mut death_markers = DeathMarkers::default();
+
+    for i in 0..MAX_DEATH_MARKERS {
+        let (marker, removed) = death_markers.record((i as i64, 0, 0), Vec3::ZERO);
+        assert_eq!(marker.id, i as u64);
+        assert!(removed.is_empty());
+    }
+
+    let (marker, removed) = death_markers.record((-1, 0, 0), Vec3::ONE);
+    assert_eq!(removed, vec![0]);
+    assert_eq!(death_markers.len(), MAX_DEATH_MARKERS);
+    assert_eq!(death_markers.iter().last(), Some(&marker));
+    assert_eq!(death_markers.in_chunk((0, 0, 0)).count(), 0);
+    assert_eq!(death_markers.in_chunk((-1, 0, 0)).count(), 1);
+}
+
+#[test]
+fn test_death_markers_with_limit() {
+    let mut death_markers = DeathMarkers::with_limit(1);
+
+    death_markers.record((0, 0, 0), Vec3::ZERO);
+    let (marker, removed) = death_markers.record((0, 0, 0), Vec3::X);
+
+    assert_eq!(removed, vec![0]);
+    assert_eq!(
+        death_markers.in_chunk((0, 0, 0)).collect::<Vec<_>>(),
+        vec![&marker]
+    );
+}
+
+#[test]
+fn test_death_markers_remove() {
+    let mut death_markers = DeathMarkers::default();
+    let (marker, _) = death_markers.record((0, 0, 0), Vec3::ZERO);
+
+    assert!(death_markers.remove(marker.id));
+    assert!(!death_markers.remove(marker.id));
+    assert!(death_markers.is_empty());
+
+    // Ids are not reused after removal
+    let (next, _) = death_markers.record((0, 0, 0), Vec3::ZERO);
+    assert_ne!(next.id, marker.id);
+}
+
+#[test]
+fn test_death_markers_serde_round_trip() {
+    let mut death_markers = DeathMarkers::default();
+    death_markers.record((1, -2, 3), Vec3::new(1.5, 0.0, -2.5));
+    death_markers.record((0, 0, 0), Vec3::ZERO);
+
+    let json = serde_json::to_string(&death_markers).unwrap();
+    let read: DeathMarkers = serde_json::from_str(&json).unwrap();
+    assert_eq!(read, death_markers);
+
+    let read: DeathMarkers = serde_json::from_str("{}").unwrap();
+    assert_eq!(read, DeathMarkers::default());
+}
@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod death_marker_test;
+
+use bevy::prelude::{Component, Event, Resource, Vec3};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+// Only the most recent deaths are marked, so that the save does not keep growing
+pub const MAX_DEATH_MARKERS: usize = 3;
+// Markers disappear once the player comes back within this distance of them
+pub const DEATH_MARKER_CLEAR_RADIUS: f32 = 1.5;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct DeathMarker {
+    pub id: u64,
+    pub chunk_xyz: (i64, i64, i64),
+    pub translation: (f32, f32, f32),
+}
+
+// Where the player last died, oldest first
+#[derive(Clone, Debug, Deserialize, PartialEq, Resource, Serialize)]
+pub struct DeathMarkers {
+    #[serde(default)]
+    markers: VecDeque<DeathMarker>,
+    #[serde(default)]
+    next_id: u64,
+    #[serde(skip, default = "default_limit")]
+    limit: usize,
+}
+
+fn default_limit() -> usize {
+    MAX_DEATH_MARKERS
+}
+
+impl Default for DeathMarkers {
+    fn default() -> Self {
+        Self::with_limit(MAX_DEATH_MARKERS)
+    }
+}
+
+impl DeathMarkers {
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            markers: VecDeque::new(),
+            next_id: 0,
+            limit,
+        }
+    }
+
+    // Returns the new marker, along with the id of any older markers pushed out by it
+    pub fn record(
+        &mut self,
+        chunk_xyz: (i64, i64, i64),
+        translation: Vec3,
+    ) -> (DeathMarker, Vec<u64>) {
+        let marker = DeathMarker {
+            id: self.next_id,
+            chunk_xyz,
+            translation: translation.into(),
+        };
+        self.next_id += 1;
+        self.markers.push_back(marker.clone());
+
+        let mut removed = Vec::new();
+        while self.markers.len() > self.limit {
+            if let Some(oldest) = self.markers.pop_front() {
+                removed.push(oldest.id);
+            }
+        }
+
+        (marker, removed)
+    }
+
+    // Returns true if there was a marker with the id
+    pub fn remove(&mut self, id: u64) -> bool {
+        let len = self.markers.len();
+        self.markers.retain(|marker| marker.id != id);
+        self.markers.len() != len
+    }
+
+    pub fn in_chunk(&self, chunk_xyz: (i64, i64, i64)) -> impl Iterator<Item = &DeathMarker> {
+        self.markers
+            .iter()
+            .filter(move |marker| marker.chunk_xyz == chunk_xyz)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DeathMarker> {
+        self.markers.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.markers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.markers.is_empty()
+    }
+}
+
+// Glowing marker spawned where the player died, with the id of its DeathMarker
+#[derive(Component, Debug)]
+pub struct DeathMarkerEcho(pub u64);
+
+#[derive(Event)]
+pub struct DeathMarkersChanged;
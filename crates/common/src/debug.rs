@@ -1,4 +1,7 @@
-use bevy::prelude::Component;
+use crate::error::Error;
+use bevy::prelude::{ButtonInput, Component, KeyCode, MouseButton, Resource, Vec2};
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, fs, path::Path, time::Duration};
 
 #[derive(Component)]
 pub struct PositionMenu;
@@ -17,3 +20,211 @@ pub struct CompassArm;
 
 #[derive(Component)]
 pub struct CompassHand(pub f32);
+
+#[derive(Component)]
+pub struct StatsMenu;
+
+#[derive(Component)]
+pub struct StatsMenuText;
+
+// Numbers for tuning render distance and colliders, shown by the stats menu
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DebugStats {
+    pub fps: Option<f64>,
+    pub chunks: usize,
+    pub entities: usize,
+    pub colliders: usize,
+    pub world_data_chunks: usize,
+    pub should_not_happens: u64,
+    pub chunk_spawn_avg: Option<Duration>,
+    pub chunk_spawn_max: Option<Duration>,
+}
+
+impl DebugStats {
+    pub fn format(&self) -> String {
+        let fps = self.fps.map_or("-".to_owned(), |fps| format!("{:.0}", fps));
+        let chunk_spawn = match (self.chunk_spawn_avg, self.chunk_spawn_max) {
+            (Some(avg), Some(max)) => format!(
+                "avg {:.2}ms / max {:.2}ms",
+                avg.as_secs_f64() * 1000.0,
+                max.as_secs_f64() * 1000.0
+            ),
+            _ => "-".to_owned(),
+        };
+        format!(
+            concat!(
+                "FPS: {}\nChunks: {}\nEntities: {}\nColliders: {}\n",
+                "World Data Chunks: {}\nShould Not Happens: {}\nChunk Spawn: {}"
+            ),
+            fps,
+            self.chunks,
+            self.entities,
+            self.colliders,
+            self.world_data_chunks,
+            self.should_not_happens,
+            chunk_spawn
+        )
+    }
+}
+
+// How many of the latest chunk spawns ChunkSpawnTimings keeps
+pub const CHUNK_SPAWN_TIMINGS_LEN: usize = 64;
+
+// How long the latest chunks took to spawn, for spotting hitches in the stats menu.
+// Only covers queuing up their entities, since the commands are applied later on.
+#[derive(Clone, Debug, Default, Resource)]
+pub struct ChunkSpawnTimings(VecDeque<Duration>);
+
+impl ChunkSpawnTimings {
+    // Drops the oldest timing once there are CHUNK_SPAWN_TIMINGS_LEN of them
+    pub fn record(&mut self, duration: Duration) {
+        if self.0.len() == CHUNK_SPAWN_TIMINGS_LEN {
+            self.0.pop_front();
+        }
+        self.0.push_back(duration);
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn avg(&self) -> Option<Duration> {
+        let total: Duration = self.0.iter().sum();
+        (!self.is_empty()).then(|| total / self.len() as u32)
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.0.iter().max().copied()
+    }
+}
+
+// Bumped whenever the layout of an input recording changes, so old recordings are refused
+pub const INPUT_RECORDING_VERSION: u32 = 1;
+
+// Every key the game reacts to, by its bit in InputFrame::keys
+pub const RECORDED_KEYS: [KeyCode; 22] = [
+    KeyCode::KeyW,
+    KeyCode::KeyA,
+    KeyCode::KeyS,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyR,
+    KeyCode::KeyQ,
+    KeyCode::KeyM,
+    KeyCode::KeyB,
+    KeyCode::KeyL,
+    KeyCode::KeyO,
+    KeyCode::ShiftLeft,
+    KeyCode::Escape,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::KeyG,
+];
+
+pub const RECORDED_MOUSE_BUTTONS: [MouseButton; 3] =
+    [MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+
+// Input of a single frame, with pressed keys and buttons packed into bits to keep recordings small
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct InputFrame {
+    #[serde(rename = "k")]
+    pub keys: u32,
+    #[serde(rename = "m")]
+    pub mouse_buttons: u8,
+    #[serde(rename = "d")]
+    pub mouse_delta: (f32, f32),
+    // Kept in nanoseconds so the frame time replays exactly
+    #[serde(rename = "t")]
+    pub delta_nanos: u64,
+}
+
+impl InputFrame {
+    pub fn new(
+        keys: &ButtonInput<KeyCode>,
+        mouse_buttons: &ButtonInput<MouseButton>,
+        mouse_delta: Vec2,
+        delta: Duration,
+    ) -> Self {
+        Self {
+            keys: pack_pressed(&RECORDED_KEYS, keys) as u32,
+            mouse_buttons: pack_pressed(&RECORDED_MOUSE_BUTTONS, mouse_buttons) as u8,
+            mouse_delta: mouse_delta.into(),
+            delta_nanos: delta.as_nanos() as u64,
+        }
+    }
+
+    pub fn is_key_pressed(&self, i: usize) -> bool {
+        self.keys & (1 << i) != 0
+    }
+
+    pub fn is_mouse_button_pressed(&self, i: usize) -> bool {
+        self.mouse_buttons & (1 << i) != 0
+    }
+
+    pub fn mouse_delta(&self) -> Vec2 {
+        self.mouse_delta.into()
+    }
+
+    pub fn delta(&self) -> Duration {
+        Duration::from_nanos(self.delta_nanos)
+    }
+}
+
+fn pack_pressed<T>(recorded: &[T], input: &ButtonInput<T>) -> u64
+where
+    T: Copy + Eq + std::hash::Hash + Send + Sync + 'static,
+{
+    recorded
+        .iter()
+        .enumerate()
+        .filter(|(_, button)| input.pressed(**button))
+        .fold(0, |bits, (i, _)| bits | (1 << i))
+}
+
+// Every frame of input from the moment the game loaded, along with the world it was played in
+#[derive(Clone, Debug, Deserialize, PartialEq, Resource, Serialize)]
+pub struct InputRecording {
+    pub version: u32,
+    pub world_seed: u32,
+    pub frames: Vec<InputFrame>,
+}
+
+impl InputRecording {
+    pub fn new(world_seed: u32) -> Self {
+        Self {
+            version: INPUT_RECORDING_VERSION,
+            world_seed,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let content = fs::read_to_string(path)?;
+        let recording: Self =
+            serde_json::from_str(&content).map_err(|err| Error::Parsing(err.to_string()))?;
+
+        if recording.version != INPUT_RECORDING_VERSION {
+            return Err(Error::Parsing(format!(
+                "input recording version {} is not supported, expected {}",
+                recording.version, INPUT_RECORDING_VERSION
+            )));
+        }
+        Ok(recording)
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let content = serde_json::to_string(self).map_err(|err| Error::Parsing(err.to_string()))?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
@@ -7,6 +7,12 @@ pub enum Error {
     IO(std::io::Error),
     #[error("parsing error: {0}")]
     Parsing(String),
+    #[error("obj error on line {line}: {msg}")]
+    Obj { line: usize, msg: String },
+    #[error("invalid world structure field `{field}`: {msg}")]
+    WorldStructure { field: String, msg: String },
+    #[error("invalid world dimensions: {0}")]
+    WorldDims(String),
     #[error("error occurred while saving")]
     Saving,
     #[error("error occurred while loading")]
@@ -1,7 +1,188 @@
-use bevy::prelude::Component;
+use bevy::prelude::{Component, Event, Resource};
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
+
+#[derive(Clone, Copy, Debug, Deserialize, Display, EnumIter, Eq, Hash, PartialEq, Serialize)]
+pub enum HudWidget {
+    HealthBar,
+    StaminaBar,
+    StatusEffects,
+    Buffs,
+    StructureCompass,
+    LightFuelGauge,
+}
+
+impl HudWidget {
+    pub fn display_key(&self) -> &'static str {
+        match self {
+            Self::HealthBar => "hud.widget.health_bar",
+            Self::StaminaBar => "hud.widget.stamina_bar",
+            Self::StatusEffects => "hud.widget.status_effects",
+            Self::Buffs => "hud.widget.buffs",
+            Self::StructureCompass => "hud.widget.structure_compass",
+            Self::LightFuelGauge => "hud.widget.light_fuel_gauge",
+        }
+    }
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Display, EnumIter, Eq, Hash, PartialEq, Serialize,
+)]
+pub enum HudAnchor {
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl HudAnchor {
+    pub fn display_key(&self) -> &'static str {
+        match self {
+            Self::TopLeft => "hud.anchor.top_left",
+            Self::TopRight => "hud.anchor.top_right",
+            Self::BottomLeft => "hud.anchor.bottom_left",
+            Self::BottomRight => "hud.anchor.bottom_right",
+        }
+    }
+
+    // Cycles clockwise through the corners of the screen
+    pub fn next(&self) -> Self {
+        match self {
+            Self::TopLeft => Self::TopRight,
+            Self::TopRight => Self::BottomRight,
+            Self::BottomRight => Self::BottomLeft,
+            Self::BottomLeft => Self::TopLeft,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct HudWidgetLayout {
+    pub widget: HudWidget,
+    pub enabled: bool,
+    pub anchor: HudAnchor,
+    // Pixels away from the anchor corner, along the x and y axes
+    pub offset: (f32, f32),
+}
+
+impl HudWidgetLayout {
+    pub fn new(widget: HudWidget) -> Self {
+        Self {
+            widget,
+            enabled: true,
+            anchor: HudAnchor::default(),
+            offset: (0.0, 0.0),
+        }
+    }
+}
+
+// Widgets sharing an anchor are stacked in the order they appear in
+#[derive(Clone, Debug, Deserialize, PartialEq, Resource, Serialize)]
+pub struct HudLayout {
+    pub widgets: Vec<HudWidgetLayout>,
+}
+
+impl Default for HudLayout {
+    fn default() -> Self {
+        Self {
+            widgets: HudWidget::iter().map(HudWidgetLayout::new).collect(),
+        }
+    }
+}
+
+impl HudLayout {
+    // Layouts saved before a widget existed get it appended with its default layout
+    pub fn fill_missing_widgets(&mut self) {
+        for widget in HudWidget::iter() {
+            if !self.widgets.iter().any(|w| w.widget == widget) {
+                self.widgets.push(HudWidgetLayout::new(widget));
+            }
+        }
+    }
+
+    pub fn get_mut(&mut self, widget: &HudWidget) -> Option<&mut HudWidgetLayout> {
+        self.widgets.iter_mut().find(|w| w.widget == *widget)
+    }
+}
+
+#[derive(Event)]
+pub struct HudLayoutChanged;
+
+#[derive(Component)]
+pub struct HudRoot;
 
 #[derive(Component)]
 pub struct HealthBar;
 
 #[derive(Component)]
 pub struct StaminaBar;
+
+#[derive(Component)]
+pub struct StatusEffectIcons;
+
+// Temporary regen modifiers from consumables
+#[derive(Component)]
+pub struct BuffIcons;
+
+#[derive(Component)]
+pub struct StructureCompass;
+
+#[derive(Component)]
+pub struct StructureCompassNeedle;
+
+#[derive(Component)]
+pub struct StructureCompassText;
+
+// Fuel left in the player's light, only shown while the light fuel setting is on
+#[derive(Component)]
+pub struct LightFuelGauge;
+
+// Center screen crosshair, kept outside of the hud layout since it can not be moved
+#[derive(Component)]
+pub struct Crosshair;
+
+// Colored piece of the crosshair, either the dot or one of the lines of the cross
+#[derive(Component)]
+pub struct CrosshairPart;
+
+// Bar below the crosshair that fills up while an attack is charging,
+// and flashes for the frames left after the charge is cancelled
+#[derive(Component, Default)]
+pub struct AttackChargeBar {
+    pub flash_frames_left: u32,
+}
+
+// Text below the crosshair, showing which of several similarly looked at interactables is pending
+#[derive(Component)]
+pub struct InteractionPrompt;
+
+// Shown while a matching pair of weapons is equipped
+#[derive(Component)]
+pub struct PairBonusIcon;
+
+// Full screen tint that flashes when the player is hurt by a hazard, fading out over the frames left
+#[derive(Component, Default)]
+pub struct DamageFlash {
+    pub frames_left: u32,
+}
+
+// Red tint around the edges of the screen when the player takes health damage,
+// fading out over the seconds left
+#[derive(Component, Default)]
+pub struct DamageVignette {
+    pub intensity: f32,
+    pub secs_left: f32,
+}
+
+// One of the stacked borders making up the vignette, which add up towards the edges of the screen
+#[derive(Component)]
+pub struct DamageVignetteRing;
+
+// Nearest world structure chunk, cached for the active chunk it was calculated from
+#[derive(Default, Resource)]
+pub struct NearestStructure {
+    pub from_chunk: Option<(i64, i64, i64)>,
+    pub chunk: Option<(i64, i64, i64)>,
+}
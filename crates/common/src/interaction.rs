@@ -1,12 +1,134 @@
-use bevy::prelude::{Component, Entity, Event, States};
+use crate::world::{WorldDims, DEFAULT_CELL_SIZE};
+use bevy::prelude::{Component, Entity, Event, Handle, Resource, StandardMaterial, States};
 
-#[derive(Component)]
+// Reach of each kind of interactable, in cells of DEFAULT_CELL_SIZE
+pub const ITEM_INTERACTABLE_RANGE: f32 = 1.8;
+pub const CONTAINER_INTERACTABLE_RANGE: f32 = 2.0;
+pub const DOOR_INTERACTABLE_RANGE: f32 = 2.0;
+pub const WINDOW_INTERACTABLE_RANGE: f32 = 2.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InteractableKind {
+    Item,
+    Container,
+    Door,
+    Window,
+    // Reach in world units as is, not scaled with the cell size, for odd cases
+    Range(f32),
+}
+
+impl InteractableKind {
+    pub fn base_range(&self) -> f32 {
+        match self {
+            Self::Item => ITEM_INTERACTABLE_RANGE,
+            Self::Container => CONTAINER_INTERACTABLE_RANGE,
+            Self::Door => DOOR_INTERACTABLE_RANGE,
+            Self::Window => WINDOW_INTERACTABLE_RANGE,
+            Self::Range(range) => *range,
+        }
+    }
+
+    // Reach in world units, for cells of the given size
+    pub fn range(&self, cell_size: f32) -> f32 {
+        match self {
+            Self::Range(range) => *range,
+            _ => self.base_range() * (cell_size / DEFAULT_CELL_SIZE),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Component, Debug, PartialEq)]
 pub struct Interactable {
-    pub range: f32,
+    pub kind: InteractableKind,
+}
+
+impl Interactable {
+    pub fn new(kind: InteractableKind) -> Self {
+        Self { kind }
+    }
+
+    pub fn with_range(range: f32) -> Self {
+        Self::new(InteractableKind::Range(range))
+    }
+
+    pub fn range(&self, world_dims: &WorldDims) -> f32 {
+        self.kind.range(world_dims.cell_size())
+    }
 }
 
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq, States)]
 pub struct PendingInteraction(pub Option<Entity>);
 
+// Interactables in range and in line of sight, ordered from the one looked at most directly
+#[derive(Clone, Debug, Default, Resource)]
+pub struct InteractionCandidates {
+    pub entities: Vec<Entity>,
+    // How many of the first entities score close enough to the first one to be cycled through
+    pub similar: usize,
+    // Index of the pending interactable within entities
+    pub selected: usize,
+}
+
 #[derive(Event)]
 pub struct PendingInteractionExecuted(pub Entity);
+
+// Sent instead of PendingInteractionExecuted when the pending interactable is Locked
+#[derive(Event)]
+pub struct LockedInteraction(pub Entity);
+
+// Placed on mesh entities of the pending interactable while they are highlighted,
+// holding the material to restore once it is no longer the pending interactable
+#[derive(Component)]
+pub struct HighlightedMaterial(pub Handle<StandardMaterial>);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InputClaim {
+    Interaction,
+    Attack,
+}
+
+// Arbitrates input that both interacting and attacking can react to, so that a single press
+// doesn't do both. Interacting claims the input for the frame it happens on, and also keeps
+// attacks from starting for a few frames after, in case the same press is still being held.
+// Attacks hold onto their claim for as long as they are charging up.
+#[derive(Debug, Default, Resource)]
+pub struct InputIntent {
+    claim: Option<InputClaim>,
+    attack_suppressed_frames: u32,
+}
+
+impl InputIntent {
+    pub fn can_interact(&self) -> bool {
+        self.claim != Some(InputClaim::Attack)
+    }
+
+    pub fn claim_interaction(&mut self, attack_suppressed_frames: u32) {
+        self.claim = Some(InputClaim::Interaction);
+        self.attack_suppressed_frames = attack_suppressed_frames;
+    }
+
+    pub fn can_attack(&self) -> bool {
+        self.claim != Some(InputClaim::Interaction) && self.attack_suppressed_frames == 0
+    }
+
+    // Claims the input while an attack is charging up, and releases it once it isn't
+    pub fn set_attack_claim(&mut self, charging: bool) {
+        if charging {
+            self.claim = Some(InputClaim::Attack);
+        } else if self.claim == Some(InputClaim::Attack) {
+            self.claim = None;
+        }
+    }
+
+    pub fn claim(&self) -> Option<InputClaim> {
+        self.claim
+    }
+
+    // Interaction claims only last for the frame they were made on
+    pub fn tick(&mut self) {
+        if self.claim == Some(InputClaim::Interaction) {
+            self.claim = None;
+        }
+        self.attack_suppressed_frames = self.attack_suppressed_frames.saturating_sub(1);
+    }
+}
@@ -1,8 +1,23 @@
-use crate::{inventory::item::Item, player::attack::AttackHand};
+use crate::{
+    inventory::item::{Item, ItemType, PairBonus, SavedItem},
+    player::{attack::AttackHand, DmgType},
+};
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
 use strum_macros::{Display, EnumIter};
 
+// Fraction of speed lost per unit of equipped weight
+const SPEED_PENALTY_PER_WEIGHT: f32 = 0.02;
+// No amount of weight slows the player down by more than this
+const MAX_SPEED_PENALTY: f32 = 0.3;
+// Extra stamina drained per tick of sprinting, per unit of equipped weight
+const SPRINT_DRAIN_PER_WEIGHT: f32 = 0.1;
+// Extra stamina that every attack costs, per unit of equipped weight
+const ATTACK_STAMINA_COST_PER_WEIGHT: f32 = 1.0;
+// Stamina drained per tick of sprinting with nothing equipped
+pub const BASE_SPRINT_STAMINA_DRAIN: f32 = 1.0;
+
 #[derive(
     Clone, Component, Copy, Debug, Deserialize, Display, EnumIter, Eq, Hash, Serialize, PartialEq,
 )]
@@ -12,6 +27,13 @@ pub enum EquipmentSlotName {
 }
 
 impl EquipmentSlotName {
+    pub fn display_key(&self) -> &'static str {
+        match self {
+            Self::LeftHand => "equipment.left_hand",
+            Self::RightHand => "equipment.right_hand",
+        }
+    }
+
     pub fn matches_target(&self, name: &Name) -> bool {
         name.to_string()
             == match self {
@@ -58,12 +80,62 @@ impl From<&AttackHand> for EquipmentSlotName {
     }
 }
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+impl From<&EquipmentSlotName> for AttackHand {
+    fn from(value: &EquipmentSlotName) -> Self {
+        match value {
+            EquipmentSlotName::LeftHand => Self::Left,
+            EquipmentSlotName::RightHand => Self::Right,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Reflect)]
 pub struct Equipment {
     left_hand: Option<Item>,
     right_hand: Option<Item>,
 }
 
+// Equipment as it is written to saves
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SavedEquipment {
+    #[serde(default)]
+    left_hand: Option<SavedItem>,
+    #[serde(default)]
+    right_hand: Option<SavedItem>,
+}
+
+impl From<&Equipment> for SavedEquipment {
+    fn from(equipment: &Equipment) -> Self {
+        Self {
+            left_hand: equipment.left_hand.as_ref().map(SavedItem::from),
+            right_hand: equipment.right_hand.as_ref().map(SavedItem::from),
+        }
+    }
+}
+
+impl SavedEquipment {
+    // Items that no longer exist are left out, and returned alongside the equipment
+    pub fn restore(&self) -> (Equipment, Vec<SavedItem>) {
+        let mut equipment = Equipment::default();
+        let mut unrestored = Vec::new();
+
+        for (slot, saved) in [
+            (&mut equipment.left_hand, &self.left_hand),
+            (&mut equipment.right_hand, &self.right_hand),
+        ] {
+            let Some(saved) = saved else {
+                continue;
+            };
+            match saved.restore() {
+                Some(item) => *slot = Some(item),
+                None => unrestored.push(saved.clone()),
+            }
+        }
+
+        (equipment, unrestored)
+    }
+}
+
 impl Equipment {
     pub fn at(&self, name: &EquipmentSlotName) -> &Option<Item> {
         match name {
@@ -93,4 +165,70 @@ impl Equipment {
         let slot_b = self.at_mut(b);
         *slot_b = slot_a_clone;
     }
+
+    // Both hands can only attack together when there is a weapon in each of them
+    pub fn is_dual_wielding(&self) -> bool {
+        [&self.left_hand, &self.right_hand].iter().all(|slot| {
+            slot.as_ref()
+                .is_some_and(|item| matches!(item.name.item_type(), ItemType::Weapon))
+        })
+    }
+
+    // Bonus of the weapon that is in both hands, if it has one
+    pub fn pair_bonus(&self) -> Option<PairBonus> {
+        match (&self.left_hand, &self.right_hand) {
+            (Some(left), Some(right)) if left.name == right.name => left.name.pair_bonus(),
+            _ => None,
+        }
+    }
+}
+
+// Penalties from the weight of everything equipped, and the bonus of a matching pair of weapons.
+// Recomputed as a whole from the equipment whenever the inventory changes, so swapping weapons
+// never stacks penalties or bonuses.
+#[derive(Clone, Copy, Debug, PartialEq, Resource)]
+pub struct EquipmentStats {
+    pub weight: f32,
+    pub speed_multiplier: f32,
+    pub sprint_stamina_drain: f32,
+    pub attack_stamina_cost: f32,
+    pub pair_bonus: Option<PairBonus>,
+}
+
+impl Default for EquipmentStats {
+    fn default() -> Self {
+        Self::from_weight(0.0)
+    }
+}
+
+impl EquipmentStats {
+    pub fn from_equipment(equipment: &Equipment) -> Self {
+        let weight = EquipmentSlotName::iter()
+            .filter_map(|slot_name| equipment.at(&slot_name).as_ref())
+            .map(|item| item.name.weight())
+            .sum();
+        let mut equipment_stats = Self::from_weight(weight);
+
+        if let Some(pair_bonus) = equipment.pair_bonus() {
+            equipment_stats.speed_multiplier *= pair_bonus.speed_multiplier();
+            equipment_stats.pair_bonus = Some(pair_bonus);
+        }
+        equipment_stats
+    }
+
+    fn from_weight(weight: f32) -> Self {
+        Self {
+            weight,
+            speed_multiplier: 1.0 - (weight * SPEED_PENALTY_PER_WEIGHT).min(MAX_SPEED_PENALTY),
+            sprint_stamina_drain: BASE_SPRINT_STAMINA_DRAIN + weight * SPRINT_DRAIN_PER_WEIGHT,
+            attack_stamina_cost: weight * ATTACK_STAMINA_COST_PER_WEIGHT,
+            pair_bonus: None,
+        }
+    }
+
+    // Resists that the player gets from the equipment, in place of the ones it got before
+    pub fn dmg_resists(&self) -> Vec<(DmgType, f32)> {
+        self.pair_bonus
+            .map_or_else(Vec::new, |pair_bonus| pair_bonus.dmg_resists())
+    }
 }
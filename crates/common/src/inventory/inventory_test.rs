@@ -1,4 +1,12 @@
-use crate::inventory::item::{Item, ItemName};
+use crate::{
+    inventory::{
+        equipment::{Equipment, EquipmentSlotName, EquipmentStats},
+        item::{ConsumableEffect, ConsumableStat, Item, ItemName, ItemType, SavedItem},
+        DropAmount, Inventory, MergeSwapOutcome, SavedInventory,
+    },
+    utils::{debug::thread_should_not_happen_count, rng::rng_from_xyz_seed},
+};
+use rand::Rng;
 use strum::IntoEnumIterator;
 
 #[test]
@@ -9,7 +17,7 @@ fn test_item_merge_with_no_overflow() {
 
         let rem_item = item_1.merge(item_2);
 
-        if item_name._is_stackable() {
+        if item_name.is_stackable() {
             assert_eq!(item_1.amt, 9);
             assert_eq!(rem_item.is_none(), true);
         } else {
@@ -43,4 +51,484 @@ fn test_item_merge_with_diff_names() {
 
     assert_eq!(item_1.amt, 20);
     assert_eq!(rem_item, Some(Item::new(ItemName::Cotton, 10)));
+    assert_eq!(thread_should_not_happen_count(), 1);
+}
+
+#[test]
+fn test_inventory_insert_into_empty_inventory() {
+    let mut inventory = Inventory::default();
+
+    let rem_item = inventory.insert(Item::new(ItemName::Coal, 10));
+
+    assert_eq!(rem_item, None);
+    assert_eq!(inventory.slots[0], Some(Item::new(ItemName::Coal, 10)));
+    assert!(inventory.slots[1..].iter().all(Option::is_none));
+}
+
+#[test]
+fn test_inventory_insert_tops_up_later_partial_stack() {
+    let mut inventory = Inventory::default();
+    inventory.slots[5] = Some(Item::new(ItemName::Coal, 10));
+
+    let rem_item = inventory.insert(Item::new(ItemName::Coal, 5));
+
+    // The empty slots before the partial stack are left alone
+    assert_eq!(rem_item, None);
+    assert_eq!(inventory.slots[5], Some(Item::new(ItemName::Coal, 15)));
+    assert!(inventory.slots[..5].iter().all(Option::is_none));
+}
+
+#[test]
+fn test_inventory_insert_tops_up_multiple_partial_stacks() {
+    let ma = ItemName::Coal.max_amt();
+    let mut inventory = Inventory::default();
+    inventory.slots[2] = Some(Item::new(ItemName::Coal, ma - 3));
+    inventory.slots[4] = Some(Item::new(ItemName::Cotton, 1));
+    inventory.slots[7] = Some(Item::new(ItemName::Coal, ma - 5));
+
+    let rem_item = inventory.insert(Item::new(ItemName::Coal, 10));
+
+    assert_eq!(rem_item, None);
+    assert_eq!(inventory.slots[2], Some(Item::new(ItemName::Coal, ma)));
+    assert_eq!(inventory.slots[4], Some(Item::new(ItemName::Cotton, 1)));
+    assert_eq!(inventory.slots[7], Some(Item::new(ItemName::Coal, ma)));
+    // Only the remainder starts a new stack, in the first empty slot
+    assert_eq!(inventory.slots[0], Some(Item::new(ItemName::Coal, 2)));
+    assert_eq!(inventory.slots.iter().flatten().count(), 4);
+}
+
+#[test]
+fn test_inventory_insert_returns_remainder_when_full() {
+    let ma = ItemName::Coal.max_amt();
+    let mut inventory = Inventory::default();
+    for slot in inventory.slots.iter_mut() {
+        *slot = Some(Item::new(ItemName::Cotton, 1));
+    }
+    inventory.slots[3] = Some(Item::new(ItemName::Coal, ma - 4));
+
+    let rem_item = inventory.insert(Item::new(ItemName::Coal, 10));
+
+    assert_eq!(rem_item, Some(Item::new(ItemName::Coal, 6)));
+    assert_eq!(inventory.slots[3], Some(Item::new(ItemName::Coal, ma)));
+}
+
+#[test]
+fn test_inventory_merge_swap_at_outcomes() {
+    let ma = ItemName::Coal.max_amt();
+    let mut inventory = Inventory::default();
+    inventory.slots[0] = Some(Item::new(ItemName::Coal, 10));
+    inventory.slots[1] = Some(Item::new(ItemName::Cotton, 1));
+
+    // Onto an empty slot
+    assert_eq!(inventory.merge_swap_at(0, 2), MergeSwapOutcome::Moved);
+    assert_eq!(inventory.slots[0], None);
+    assert_eq!(inventory.slots[2], Some(Item::new(ItemName::Coal, 10)));
+
+    // From an empty slot
+    assert_eq!(inventory.merge_swap_at(0, 2), MergeSwapOutcome::Moved);
+    assert_eq!(inventory.slots[0], Some(Item::new(ItemName::Coal, 10)));
+    assert_eq!(inventory.slots[2], None);
+
+    assert_eq!(inventory.merge_swap_at(0, 1), MergeSwapOutcome::Swapped);
+    assert_eq!(inventory.slots[0], Some(Item::new(ItemName::Cotton, 1)));
+    assert_eq!(inventory.slots[1], Some(Item::new(ItemName::Coal, 10)));
+
+    inventory.slots[3] = Some(Item::new(ItemName::Coal, 5));
+    assert_eq!(
+        inventory.merge_swap_at(3, 1),
+        MergeSwapOutcome::Merged { remainder: 0 }
+    );
+    assert_eq!(inventory.slots[1], Some(Item::new(ItemName::Coal, 15)));
+    assert_eq!(inventory.slots[3], None);
+
+    // The remainder stays behind in the source slot
+    inventory.slots[4] = Some(Item::new(ItemName::Coal, ma - 3));
+    assert_eq!(
+        inventory.merge_swap_at(1, 4),
+        MergeSwapOutcome::Merged { remainder: 12 }
+    );
+    assert_eq!(inventory.slots[1], Some(Item::new(ItemName::Coal, 12)));
+    assert_eq!(inventory.slots[4], Some(Item::new(ItemName::Coal, ma)));
+
+    // Dropping a stack back onto its own slot or moving between empty slots does nothing
+    assert_eq!(inventory.merge_swap_at(1, 1), MergeSwapOutcome::Noop);
+    assert_eq!(inventory.slots[1], Some(Item::new(ItemName::Coal, 12)));
+    assert_eq!(inventory.merge_swap_at(5, 6), MergeSwapOutcome::Noop);
+}
+
+#[test]
+fn test_inventory_unequip_to() {
+    let name = EquipmentSlotName::LeftHand;
+    let mut inventory = Inventory::default();
+    *inventory.equipment.at_mut(&name) = Some(Item::new(ItemName::Katana, 1));
+    inventory.slots[1] = Some(Item::new(ItemName::Coal, 1));
+
+    // Occupied slots are left alone
+    assert!(!inventory.unequip_to(1, &name));
+    assert_eq!(inventory.slots[1], Some(Item::new(ItemName::Coal, 1)));
+    assert!(inventory.equipment.at(&name).is_some());
+
+    assert!(inventory.unequip_to(2, &name));
+    assert_eq!(inventory.slots[2], Some(Item::new(ItemName::Katana, 1)));
+    assert!(inventory.equipment.at(&name).is_none());
+
+    // Nothing left to unequip
+    assert!(!inventory.unequip_to(3, &name));
+    assert_eq!(inventory.slots[3], None);
+}
+
+#[test]
+fn test_item_name_consumable_effect() {
+    for item_name in ItemName::iter() {
+        let is_consumable = matches!(item_name.item_type(), ItemType::Consumable);
+        assert_eq!(item_name.consumable_effect().is_some(), is_consumable);
+    }
+}
+
+#[test]
+fn test_consumable_effect_amt_scales_with_max_value() {
+    let effect = ItemName::HealthPotion.consumable_effect().unwrap();
+
+    assert_eq!(
+        effect,
+        ConsumableEffect::Instant(ConsumableStat::Health, 35.0)
+    );
+    assert_eq!(effect.amt(100.0), 35.0);
+    assert_eq!(effect.amt(200.0), 70.0);
+
+    let effect = ItemName::StaminaPoison.consumable_effect().unwrap();
+
+    assert_eq!(effect.stat(), ConsumableStat::Stamina);
+    assert!((effect.amt(200.0) + 60.0).abs() < 0.001);
+}
+
+#[test]
+fn test_regen_consumables_change_one_percent_of_max_per_tick() {
+    for (item_name, sign) in [
+        (ItemName::HealthRegenPotion, 1.0),
+        (ItemName::StaminaRegenPotion, 1.0),
+        (ItemName::HealthRegenPoison, -1.0),
+        (ItemName::StaminaRegenPoison, -1.0),
+    ] {
+        let effect = item_name.consumable_effect().unwrap();
+        let ConsumableEffect::Regen(_, _, durr) = effect else {
+            panic!("{} should regen over time", item_name);
+        };
+        // 10 seconds at 60 ticks per second
+        assert_eq!(durr, 600, "{}", item_name);
+
+        for max_value in [100.0, 250.0] {
+            let per_tick = effect.amt(max_value);
+            assert!(
+                (per_tick - sign * max_value / 100.0).abs() < 0.001,
+                "{}: {} per tick out of {}",
+                item_name,
+                per_tick,
+                max_value
+            );
+        }
+    }
+}
+
+#[test]
+fn test_saved_inventory_round_trip() {
+    let mut inventory = Inventory::default();
+    inventory.slots[0] = Some(Item::new(ItemName::Coal, 3));
+    inventory.slots[7] = Some(Item::new(ItemName::HealthPotion, 2));
+    inventory.slots[15] = Some(Item::new(ItemName::Cotton, 1));
+    *inventory.equipment.at_mut(&EquipmentSlotName::RightHand) =
+        Some(Item::new(ItemName::Katana, 1));
+
+    let json = serde_json::to_string(&SavedInventory::from(&inventory)).unwrap();
+    let saved: SavedInventory = serde_json::from_str(&json).unwrap();
+    let (restored, unrestored) = saved.restore();
+
+    assert!(unrestored.is_empty());
+    // Every item comes back in the same slot it was saved from
+    assert_eq!(restored.slots, inventory.slots);
+    assert_eq!(restored.equipment.at(&EquipmentSlotName::LeftHand), &None);
+    assert_eq!(
+        restored.equipment.at(&EquipmentSlotName::RightHand),
+        &Some(Item::new(ItemName::Katana, 1))
+    );
+}
+
+#[test]
+fn test_saved_inventory_skips_unknown_items() {
+    let json = r#"{
+        "slots": [
+            {"name": "Coal", "amt": 3},
+            null,
+            {"name": "Banana", "amt": 2},
+            {"name": "Cotton", "amt": 1}
+        ],
+        "equipment": {
+            "left_hand": {"name": "Lightsaber", "amt": 1},
+            "right_hand": {"name": "Katana", "amt": 1}
+        }
+    }"#;
+    let saved: SavedInventory = serde_json::from_str(json).unwrap();
+    let (restored, unrestored) = saved.restore();
+
+    assert_eq!(restored.slots[0], Some(Item::new(ItemName::Coal, 3)));
+    assert_eq!(restored.slots[2], None);
+    assert_eq!(restored.slots[3], Some(Item::new(ItemName::Cotton, 1)));
+    assert_eq!(restored.equipment.at(&EquipmentSlotName::LeftHand), &None);
+    assert_eq!(
+        restored.equipment.at(&EquipmentSlotName::RightHand),
+        &Some(Item::new(ItemName::Katana, 1))
+    );
+
+    assert_eq!(unrestored.len(), 2);
+    assert!(unrestored.contains(&SavedItem {
+        name: "Banana".to_owned(),
+        amt: 2
+    }));
+    assert!(unrestored.contains(&SavedItem {
+        name: "Lightsaber".to_owned(),
+        amt: 1
+    }));
+}
+
+#[test]
+fn test_saved_inventory_skips_missing_slots() {
+    let mut slots = vec![None; 20];
+    slots[18] = Some(SavedItem {
+        name: "Coal".to_owned(),
+        amt: 1,
+    });
+    let saved = SavedInventory {
+        slots,
+        ..Default::default()
+    };
+
+    let (restored, unrestored) = saved.restore();
+    assert!(restored.slots.iter().all(|slot| slot.is_none()));
+    assert_eq!(unrestored.len(), 1);
+}
+
+#[test]
+fn test_inventory_sort_and_consolidate() {
+    let mut inventory = Inventory::default();
+    inventory.slots[1] = Some(Item::new(ItemName::Flint, 10));
+    inventory.slots[3] = Some(Item::new(ItemName::HealthPotion, 2));
+    inventory.slots[4] = Some(Item::new(ItemName::Flint, 60));
+    inventory.slots[7] = Some(Item::new(ItemName::Katana, 1));
+    inventory.slots[9] = Some(Item::new(ItemName::Coal, 5));
+    inventory.slots[12] = Some(Item::new(ItemName::Broadsword, 1));
+    *inventory.equipment.at_mut(&EquipmentSlotName::RightHand) =
+        Some(Item::new(ItemName::Katana, 1));
+
+    inventory.sort_and_consolidate();
+
+    assert_eq!(
+        inventory.slots[..6],
+        [
+            Some(Item::new(ItemName::Broadsword, 1)),
+            Some(Item::new(ItemName::Katana, 1)),
+            Some(Item::new(ItemName::HealthPotion, 2)),
+            Some(Item::new(ItemName::Coal, 5)),
+            Some(Item::new(ItemName::Flint, 64)),
+            Some(Item::new(ItemName::Flint, 6)),
+        ]
+    );
+    assert!(inventory.slots[6..].iter().all(Option::is_none));
+    assert_eq!(
+        inventory.equipment.at(&EquipmentSlotName::RightHand),
+        &Some(Item::new(ItemName::Katana, 1))
+    );
+}
+
+#[test]
+fn test_inventory_sort_and_consolidate_never_loses_items() {
+    let totals = |inventory: &Inventory| {
+        ItemName::iter()
+            .map(|name| {
+                inventory
+                    .slots
+                    .iter()
+                    .flatten()
+                    .filter(|item| item.name == name)
+                    .map(|item| item.amt as u32)
+                    .sum::<u32>()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    for seed in 0..200 {
+        let mut rng = rng_from_xyz_seed(seed, 0, 0, 0);
+        let mut inventory = Inventory::default();
+        for slot in inventory.slots.iter_mut() {
+            if rng.gen_bool(0.7) {
+                let name = ItemName::choose(&mut rng);
+                *slot = Some(Item::new(name, rng.gen_range(1..=name.max_amt())));
+            }
+        }
+
+        let before = totals(&inventory);
+        let occupied = inventory.slots.iter().flatten().count();
+        inventory.sort_and_consolidate();
+
+        assert_eq!(totals(&inventory), before, "seed: {}", seed);
+        assert!(inventory.slots.iter().flatten().count() <= occupied);
+        assert!(inventory
+            .slots
+            .iter()
+            .skip_while(|slot| slot.is_some())
+            .all(Option::is_none));
+    }
+}
+
+#[test]
+fn test_equipment_stats_from_equipment() {
+    let mut equipment = Equipment::default();
+    assert_eq!(
+        EquipmentStats::from_equipment(&equipment),
+        EquipmentStats::default()
+    );
+
+    *equipment.at_mut(&EquipmentSlotName::LeftHand) = Some(Item::new(ItemName::Katana, 1));
+    let katana = EquipmentStats::from_equipment(&equipment);
+    assert_eq!(katana.weight, ItemName::Katana.weight());
+
+    // Weight of both hands adds up
+    *equipment.at_mut(&EquipmentSlotName::RightHand) = Some(Item::new(ItemName::Broadsword, 1));
+    let both = EquipmentStats::from_equipment(&equipment);
+    assert_eq!(
+        both.weight,
+        ItemName::Katana.weight() + ItemName::Broadsword.weight()
+    );
+    assert!(both.speed_multiplier < katana.speed_multiplier);
+    assert!(both.speed_multiplier > 0.0);
+    assert!(both.sprint_stamina_drain > katana.sprint_stamina_drain);
+    assert!(both.attack_stamina_cost > katana.attack_stamina_cost);
+}
+
+#[test]
+fn test_pair_bonus_only_for_matching_weapons() {
+    let mut equipment = Equipment::default();
+    *equipment.at_mut(&EquipmentSlotName::LeftHand) = Some(Item::new(ItemName::Broadsword, 1));
+    assert_eq!(equipment.pair_bonus(), None);
+
+    *equipment.at_mut(&EquipmentSlotName::RightHand) = Some(Item::new(ItemName::Katana, 1));
+    assert_eq!(equipment.pair_bonus(), None);
+    assert!(EquipmentStats::from_equipment(&equipment)
+        .dmg_resists()
+        .is_empty());
+
+    *equipment.at_mut(&EquipmentSlotName::RightHand) = Some(Item::new(ItemName::Broadsword, 1));
+    assert_eq!(equipment.pair_bonus(), ItemName::Broadsword.pair_bonus());
+    let stats = EquipmentStats::from_equipment(&equipment);
+    assert_eq!(stats.pair_bonus, ItemName::Broadsword.pair_bonus());
+    assert_eq!(stats.dmg_resists().len(), 1);
+
+    // Items that are not weapons have no bonus, even in pairs
+    for slot_name in EquipmentSlotName::iter() {
+        *equipment.at_mut(&slot_name) = Some(Item::new(ItemName::Cotton, 1));
+    }
+    assert_eq!(equipment.pair_bonus(), None);
+}
+
+#[test]
+fn test_take_throwable() {
+    let mut inventory = Inventory::default();
+    assert!(inventory.take_throwable().is_none());
+
+    inventory.slots[0] = Some(Item::new(ItemName::Katana, 1));
+    inventory.slots[1] = Some(Item::new(ItemName::Cotton, 5));
+    inventory.slots[2] = Some(Item::new(ItemName::Flint, 2));
+    inventory.slots[3] = Some(Item::new(ItemName::Coal, 1));
+
+    let thrown = inventory.take_throwable().unwrap();
+    assert_eq!(thrown.name, ItemName::Flint);
+    assert_eq!(thrown.amt, 1);
+    assert_eq!(inventory.slots[2].unwrap().amt, 1);
+
+    // The last one of a stack empties its slot
+    inventory.take_throwable();
+    assert!(inventory.slots[2].is_none());
+    assert_eq!(inventory.take_throwable().unwrap().name, ItemName::Coal);
+    assert!(inventory.take_throwable().is_none());
+
+    // Nothing else was touched
+    assert_eq!(inventory.slots[0].unwrap().name, ItemName::Katana);
+    assert_eq!(inventory.slots[1].unwrap().amt, 5);
+}
+
+#[test]
+fn test_take_key() {
+    let mut inventory = Inventory::default();
+    inventory.slots[0] = Some(Item::new(ItemName::Flint, 3));
+    assert!(inventory.take_key(1).is_none());
+
+    inventory.slots[4] = Some(Item::new(ItemName::Key, 1));
+    // Higher tier locks need a better key than there is
+    assert!(inventory.take_key(2).is_none());
+    assert_eq!(inventory.take_key(1), Some(Item::new(ItemName::Key, 1)));
+    assert!(inventory.slots[4].is_none());
+    assert!(inventory.take_key(1).is_none());
+    assert_eq!(inventory.slots[0], Some(Item::new(ItemName::Flint, 3)));
+}
+
+#[test]
+fn test_item_name_choose_never_picks_keys() {
+    for seed in 0..200 {
+        let mut rng = rng_from_xyz_seed(seed, 0, 0, 0);
+        assert!(ItemName::choose(&mut rng).key_tier().is_none());
+    }
+}
+
+#[test]
+fn test_take_from_slot() {
+    let mut inventory = Inventory::default();
+    inventory.slots[0] = Some(Item::new(ItemName::HealthPotion, 64));
+
+    // One, then half of what is left, then all of the rest
+    assert_eq!(
+        inventory.take_from_slot(0, 1),
+        Some(Item::new(ItemName::HealthPotion, 1))
+    );
+    assert_eq!(inventory.slots[0].unwrap().amt, 63);
+
+    let half = DropAmount::Half.of(63);
+    assert_eq!(
+        inventory.take_from_slot(0, half),
+        Some(Item::new(ItemName::HealthPotion, 32))
+    );
+    assert_eq!(inventory.slots[0].unwrap().amt, 31);
+
+    assert_eq!(
+        inventory.take_from_slot(0, DropAmount::Stack.of(31)),
+        Some(Item::new(ItemName::HealthPotion, 31))
+    );
+    assert!(inventory.slots[0].is_none());
+
+    // Nothing to take
+    assert!(inventory.take_from_slot(0, 1).is_none());
+    inventory.slots[1] = Some(Item::new(ItemName::Coal, 3));
+    assert!(inventory.take_from_slot(1, 0).is_none());
+    assert_eq!(inventory.slots[1].unwrap().amt, 3);
+
+    // Never more than there is
+    assert_eq!(
+        inventory.take_from_slot(1, 10),
+        Some(Item::new(ItemName::Coal, 3))
+    );
+    assert!(inventory.slots[1].is_none());
+}
+
+#[test]
+fn test_drop_amount() {
+    assert_eq!(DropAmount::from_modifiers(false, false), DropAmount::Stack);
+    assert_eq!(DropAmount::from_modifiers(true, false), DropAmount::One);
+    assert_eq!(DropAmount::from_modifiers(false, true), DropAmount::Half);
+    assert_eq!(DropAmount::from_modifiers(true, true), DropAmount::One);
+
+    assert_eq!(DropAmount::Stack.of(64), 64);
+    assert_eq!(DropAmount::One.of(64), 1);
+    assert_eq!(DropAmount::Half.of(64), 32);
+    // Rounds up, so that a single item can still be dropped
+    assert_eq!(DropAmount::Half.of(1), 1);
+    assert_eq!(DropAmount::Half.of(5), 3);
+    assert_eq!(DropAmount::One.of(0), 0);
 }
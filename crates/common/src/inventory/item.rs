@@ -1,7 +1,8 @@
 use crate::{
     animation::PlayerAnimation,
-    interaction::Interactable,
+    interaction::{Interactable, InteractableKind},
     inventory::equipment::EquipmentSlotName,
+    localization::Localization,
     player::{
         attack::{AttackHand, AttackType},
         DmgType,
@@ -14,13 +15,128 @@ use serde::{Deserialize, Serialize};
 use strum::{EnumCount, VariantArray};
 use strum_macros::{Display, EnumCount, EnumIter, VariantArray};
 
-const ITEM_INTERACTABLE_RANGE: f32 = 1.8;
+// 10 seconds at 60 ticks per second
+const CONSUMABLE_REGEN_DURATION: u32 = 600;
+
+// Keys are the last ItemNames, and are left out of ItemName::choose
+const KEY_COUNT: usize = 1;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum ItemType {
     Consumable,
     RawMaterial,
     Weapon,
+    Key,
+}
+
+impl ItemType {
+    // Where items of this type go when the inventory is sorted
+    pub fn sort_order(&self) -> u8 {
+        match self {
+            Self::Weapon => 0,
+            Self::Consumable => 1,
+            Self::RawMaterial => 2,
+            Self::Key => 3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
+pub enum ConsumableStat {
+    Health,
+    Stamina,
+}
+
+impl ConsumableStat {
+    fn display(&self, localization: &Localization) -> String {
+        localization.tr(
+            match self {
+                Self::Health => "stat.health",
+                Self::Stamina => "stat.stamina",
+            },
+            &[],
+        )
+    }
+}
+
+// Amounts are percentages of the max value of the stat, so they keep up if max values change
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConsumableEffect {
+    // Restores the percentage right away, or drains it if negative
+    Instant(ConsumableStat, f32),
+    // Adds a temporary regen modifier of the percentage per tick, for a number of ticks
+    Regen(ConsumableStat, f32, u32),
+}
+
+// Bonus for holding the same weapon in both hands
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PairBonus {
+    // Multiplies the player's speed
+    Speed(f32),
+    // Flat resist against Slash damage
+    SlashResist(f32),
+}
+
+impl PairBonus {
+    pub fn display_key(&self) -> &'static str {
+        match self {
+            Self::Speed(_) => "hud.pair_bonus.speed",
+            Self::SlashResist(_) => "hud.pair_bonus.slash_resist",
+        }
+    }
+
+    pub fn speed_multiplier(&self) -> f32 {
+        match self {
+            Self::Speed(multiplier) => *multiplier,
+            Self::SlashResist(_) => 1.0,
+        }
+    }
+
+    pub fn dmg_resists(&self) -> Vec<(DmgType, f32)> {
+        match self {
+            Self::Speed(_) => Vec::new(),
+            Self::SlashResist(amt) => vec![(DmgType::Slash, *amt)],
+        }
+    }
+}
+
+impl ConsumableEffect {
+    pub fn stat(&self) -> ConsumableStat {
+        match self {
+            Self::Instant(stat, _) | Self::Regen(stat, _, _) => *stat,
+        }
+    }
+
+    // Converts the percentage into an amount for a stat with the given max value
+    pub fn amt(&self, max_value: f32) -> f32 {
+        match self {
+            Self::Instant(_, percent) | Self::Regen(_, percent, _) => percent / 100.0 * max_value,
+        }
+    }
+
+    pub fn description(&self, localization: &Localization) -> String {
+        match self {
+            Self::Instant(stat, percent) if *percent >= 0.0 => localization.tr(
+                "effect.restores",
+                &[("percent", percent), ("stat", &stat.display(localization))],
+            ),
+            Self::Instant(stat, percent) => localization.tr(
+                "effect.drains",
+                &[
+                    ("percent", &-percent),
+                    ("stat", &stat.display(localization)),
+                ],
+            ),
+            Self::Regen(stat, percent, durr) => localization.tr(
+                "effect.regen",
+                &[
+                    ("percent", &format!("{:+}", percent)),
+                    ("stat", &stat.display(localization)),
+                    ("ticks", durr),
+                ],
+            ),
+        }
+    }
 }
 
 #[derive(
@@ -34,6 +150,7 @@ pub enum ItemType {
     EnumIter,
     Eq,
     PartialEq,
+    Reflect,
     Serialize,
     VariantArray,
 )]
@@ -56,11 +173,15 @@ pub enum ItemName {
     // Weapons
     Broadsword,
     Katana,
+
+    // Keys
+    Key,
 }
 
 impl ItemName {
+    // Never a key, since those only come from their own, rarer roll in loot tables
     pub fn choose(rng: &mut StdRng) -> Self {
-        let i = rng.gen_range(0..Self::COUNT);
+        let i = rng.gen_range(0..Self::COUNT - KEY_COUNT);
         Self::VARIANTS[i].to_owned()
     }
 
@@ -76,24 +197,86 @@ impl ItemName {
             | Self::HealthRegenPoison
             | Self::StaminaRegenPoison => ItemType::Consumable,
             Self::Broadsword | Self::Katana => ItemType::Weapon,
+            Self::Key => ItemType::Key,
         }
     }
 
     pub fn max_amt(&self) -> u16 {
         match self.item_type() {
-            ItemType::Consumable | ItemType::RawMaterial => 64,
+            ItemType::Consumable | ItemType::RawMaterial | ItemType::Key => 64,
             ItemType::Weapon => 1,
         }
     }
 
-    pub fn _is_stackable(&self) -> bool {
+    pub fn is_stackable(&self) -> bool {
         self.max_amt() > 1
     }
 
+    // Small and hard enough to throw, to make a noise or to hurt a little
+    pub fn is_throwable(&self) -> bool {
+        matches!(self, Self::Coal | Self::Flint)
+    }
+
     pub fn is_equipable_at(&self, _: &EquipmentSlotName) -> bool {
         match self.item_type() {
             ItemType::Weapon => true,
-            ItemType::Consumable | ItemType::RawMaterial => false,
+            ItemType::Consumable | ItemType::RawMaterial | ItemType::Key => false,
+        }
+    }
+
+    pub fn consumable_effect(&self) -> Option<ConsumableEffect> {
+        use ConsumableEffect::{Instant, Regen};
+        use ConsumableStat::{Health, Stamina};
+
+        match self {
+            Self::HealthPotion => Some(Instant(Health, 35.0)),
+            Self::StaminaPotion => Some(Instant(Stamina, 35.0)),
+            Self::HealthRegenPotion => Some(Regen(Health, 1.0, CONSUMABLE_REGEN_DURATION)),
+            Self::StaminaRegenPotion => Some(Regen(Stamina, 1.0, CONSUMABLE_REGEN_DURATION)),
+            Self::HealthPoison => Some(Instant(Health, -30.0)),
+            Self::StaminaPoison => Some(Instant(Stamina, -30.0)),
+            Self::HealthRegenPoison => Some(Regen(Health, -1.0, CONSUMABLE_REGEN_DURATION)),
+            Self::StaminaRegenPoison => Some(Regen(Stamina, -1.0, CONSUMABLE_REGEN_DURATION)),
+            Self::Coal
+            | Self::Cotton
+            | Self::Flint
+            | Self::Broadsword
+            | Self::Katana
+            | Self::Key => None,
+        }
+    }
+
+    // Highest Locked tier that the item opens, for keys
+    pub fn key_tier(&self) -> Option<u8> {
+        match self {
+            Self::Key => Some(1),
+            _ => None,
+        }
+    }
+
+    // Percentage of the player's light fuel refilled by using one of the item
+    pub fn light_fuel(&self) -> Option<f32> {
+        match self {
+            Self::Coal => Some(50.0),
+            _ => None,
+        }
+    }
+
+    // Only counts towards EquipmentStats while equipped
+    pub fn weight(&self) -> f32 {
+        match self {
+            Self::Broadsword => 3.0,
+            Self::Katana => 1.5,
+            _ => 0.1,
+        }
+    }
+
+    // Only applies while the same weapon is equipped in both hands
+    pub fn pair_bonus(&self) -> Option<PairBonus> {
+        match self {
+            Self::Katana => Some(PairBonus::Speed(1.1)),
+            Self::Broadsword => Some(PairBonus::SlashResist(5.0)),
+            _ => None,
         }
     }
 
@@ -113,35 +296,53 @@ impl ItemName {
         self.base_dmg()
     }
 
+    // Key of the name shown in the UI, since the strum Display is only meant for logs
+    pub fn display_key(&self) -> &'static str {
+        match self {
+            Self::Coal => "item.coal",
+            Self::Cotton => "item.cotton",
+            Self::Flint => "item.flint",
+            Self::HealthPotion => "item.health_potion",
+            Self::StaminaPotion => "item.stamina_potion",
+            Self::HealthRegenPotion => "item.health_regen_potion",
+            Self::StaminaRegenPotion => "item.stamina_regen_potion",
+            Self::HealthPoison => "item.health_poison",
+            Self::StaminaPoison => "item.stamina_poison",
+            Self::HealthRegenPoison => "item.health_regen_poison",
+            Self::StaminaRegenPoison => "item.stamina_regen_poison",
+            Self::Broadsword => "item.broadsword",
+            Self::Katana => "item.katana",
+            Self::Key => "item.key",
+        }
+    }
+
+    pub fn ui_image_path(&self) -> &'static str {
+        match self {
+            Self::Coal => "embedded://images/coal.png",
+            Self::Cotton => "embedded://images/cotton.png",
+            Self::Flint => "embedded://images/flint.png",
+            Self::HealthPotion => "embedded://images/health_potion.png",
+            Self::StaminaPotion => "embedded://images/stamina_potion.png",
+            Self::HealthRegenPotion => "embedded://images/health_regen_potion.png",
+            Self::StaminaRegenPotion => "embedded://images/stamina_regen_potion.png",
+            Self::HealthPoison => "embedded://images/health_poison.png",
+            Self::StaminaPoison => "embedded://images/stamina_poison.png",
+            Self::HealthRegenPoison => "embedded://images/health_regen_poison.png",
+            Self::StaminaRegenPoison => "embedded://images/stamina_regen_poison.png",
+            Self::Broadsword => "embedded://images/broadsword.png",
+            Self::Katana => "embedded://images/katana.png",
+            Self::Key => "embedded://images/key.png",
+        }
+    }
+
     pub fn ui_image(&self, asset_server: &Res<AssetServer>) -> UiImage {
         UiImage {
-            texture: match self {
-                Self::Coal => asset_server.load("embedded://images/coal.png"),
-                Self::Cotton => asset_server.load("embedded://images/cotton.png"),
-                Self::Flint => asset_server.load("embedded://images/flint.png"),
-                Self::HealthPotion => asset_server.load("embedded://images/health_potion.png"),
-                Self::StaminaPotion => asset_server.load("embedded://images/stamina_potion.png"),
-                Self::HealthRegenPotion => {
-                    asset_server.load("embedded://images/health_regen_potion.png")
-                }
-                Self::StaminaRegenPotion => {
-                    asset_server.load("embedded://images/stamina_regen_potion.png")
-                }
-                Self::HealthPoison => asset_server.load("embedded://images/health_poison.png"),
-                Self::StaminaPoison => asset_server.load("embedded://images/stamina_poison.png"),
-                Self::HealthRegenPoison => {
-                    asset_server.load("embedded://images/health_regen_poison.png")
-                }
-                Self::StaminaRegenPoison => {
-                    asset_server.load("embedded://images/stamina_regen_poison.png")
-                }
-                Self::Broadsword => asset_server.load("embedded://images/broadsword.png"),
-                Self::Katana => asset_server.load("embedded://images/katana.png"),
-            },
+            texture: asset_server.load(self.ui_image_path()),
             ..default()
         }
     }
 
+    // Items without a 3d model are drawn in the world with a fallback model instead
     pub fn model_path(&self) -> Option<impl Into<AssetPath>> {
         match self {
             Self::Broadsword => {
@@ -150,20 +351,42 @@ impl ItemName {
             Self::Katana => {
                 Some(GltfAssetLabel::Scene(0).from_asset("embedded://models/katana.glb"))
             }
-            _ => {
-                should_not_happen!("expected ItemName with a 3d model, but got: {}", self);
-                None
-            }
+            Self::Coal
+            | Self::Cotton
+            | Self::Flint
+            | Self::HealthPotion
+            | Self::StaminaPotion
+            | Self::HealthRegenPotion
+            | Self::StaminaRegenPotion
+            | Self::HealthPoison
+            | Self::StaminaPoison
+            | Self::HealthRegenPoison
+            | Self::StaminaRegenPoison
+            | Self::Key => None,
+        }
+    }
+
+    // Base color of the fallback model for items without a 3d model
+    pub fn fallback_model_color(&self) -> Color {
+        match self.consumable_effect().map(|effect| effect.stat()) {
+            Some(ConsumableStat::Health) => Color::srgb(0.8, 0.1, 0.1),
+            Some(ConsumableStat::Stamina) => Color::srgb(0.1, 0.7, 0.2),
+            None => match self.item_type() {
+                ItemType::RawMaterial => Color::srgb(0.45, 0.4, 0.35),
+                ItemType::Key => Color::srgb(0.85, 0.7, 0.2),
+                ItemType::Consumable | ItemType::Weapon => Color::srgb(0.75, 0.75, 0.8),
+            },
         }
     }
 
+    // None for items that are not weapons
     pub fn player_attack_animation(
         &self,
         attack_type: &AttackType,
         attack_hand: &AttackHand,
-    ) -> PlayerAnimation {
+    ) -> Option<PlayerAnimation> {
         match self {
-            Self::Broadsword | &Self::Katana => match (attack_type, attack_hand) {
+            Self::Broadsword | &Self::Katana => Some(match (attack_type, attack_hand) {
                 (AttackType::Light, AttackHand::Left) => {
                     PlayerAnimation::OneHandedSlashLeftLightAttack
                 }
@@ -176,7 +399,7 @@ impl ItemName {
                 (AttackType::Heavy, AttackHand::Right) => {
                     PlayerAnimation::OneHandedSlashRightHeavyAttack
                 }
-            },
+            }),
             Self::Coal
             | Self::Cotton
             | Self::Flint
@@ -187,23 +410,54 @@ impl ItemName {
             | Self::HealthPoison
             | Self::StaminaPoison
             | Self::HealthRegenPoison
-            | Self::StaminaRegenPoison => {
-                should_not_happen!(
-                    "{:?} is not a weapon, and therefore does not have an attack animation",
-                    self
-                );
-                PlayerAnimation::default()
-            }
+            | Self::StaminaRegenPoison
+            | Self::Key => None,
         }
     }
 }
 
-#[derive(Clone, Copy, Component, Debug, Deserialize, Eq, PartialEq, Serialize)]
+// The visible part of an item in the world, either its 3d model or the fallback model
+#[derive(Component)]
+pub struct ItemModel;
+
+// Shows the item's ui image on a quad that always faces the camera
+#[derive(Component)]
+pub struct ItemBillboard;
+
+#[derive(Clone, Copy, Component, Debug, Deserialize, Eq, PartialEq, Reflect, Serialize)]
+#[reflect(Component)]
 pub struct Item {
     pub name: ItemName,
     pub amt: u16,
 }
 
+// Item as it is written to saves. The name is kept as a string, so that a save holding an item
+// that has since been renamed or removed can still be read, minus that item.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SavedItem {
+    pub name: String,
+    pub amt: u16,
+}
+
+impl From<&Item> for SavedItem {
+    fn from(item: &Item) -> Self {
+        Self {
+            name: item.name.to_string(),
+            amt: item.amt,
+        }
+    }
+}
+
+impl SavedItem {
+    // None if the item no longer exists
+    pub fn restore(&self) -> Option<Item> {
+        ItemName::VARIANTS
+            .iter()
+            .find(|name| name.to_string() == self.name)
+            .map(|name| Item::new(*name, self.amt))
+    }
+}
+
 impl Item {
     pub fn new(name: ItemName, amt: u16) -> Self {
         Self { name, amt }
@@ -224,6 +478,10 @@ impl Item {
         self.name.max_amt()
     }
 
+    pub fn is_full(&self) -> bool {
+        self.amt >= self.max_amt()
+    }
+
     pub fn merge(&mut self, item: Item) -> Option<Self> {
         if self.name != item.name {
             should_not_happen!("attempting to merge 2 items with different ItemNames");
@@ -243,9 +501,7 @@ impl Item {
     }
 
     pub fn interactable() -> Interactable {
-        Interactable {
-            range: ITEM_INTERACTABLE_RANGE,
-        }
+        Interactable::new(InteractableKind::Item)
     }
 
     pub fn ui_image(&self, asset_server: &Res<AssetServer>) -> UiImage {
@@ -260,16 +516,14 @@ impl Item {
     // The first value is an optional Item, which represents the byproduct (output) of the original item being used.
     // The second value is a bool that indicates whether or not the original item was mutated.
     pub fn _use(&mut self) -> (Option<Self>, bool) {
-        match self.name.item_type() {
-            ItemType::Consumable => {
-                if self.amt == 0 {
-                    return (None, false);
-                }
-                self.amt -= 1;
-                return (Some(self.clone_with_amt(1)), true);
-            }
-            _ => (None, false),
+        let is_usable = matches!(self.name.item_type(), ItemType::Consumable)
+            || self.name.light_fuel().is_some();
+        if !is_usable || self.amt == 0 {
+            return (None, false);
         }
+
+        self.amt -= 1;
+        (Some(self.clone_with_amt(1)), true)
     }
 
     pub fn is_equipable_at(&self, name: &EquipmentSlotName) -> bool {
@@ -6,8 +6,8 @@ mod inventory_test;
 
 use crate::{
     inventory::{
-        equipment::{Equipment, EquipmentSlotName},
-        item::Item,
+        equipment::{Equipment, EquipmentSlotName, SavedEquipment},
+        item::{Item, ItemName, SavedItem},
     },
     should_not_happen,
     world::ChunkCellMarker,
@@ -17,38 +17,47 @@ use serde::{Deserialize, Serialize};
 
 const INVENTORY_MAX_SIZE: usize = 16;
 
-#[derive(Clone, Debug, Default, Deserialize, Resource, Serialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MergeSwapOutcome {
+    // One of the slots was empty
+    Moved,
+    // Whatever did not fit in the target stack is left in the source slot
+    Merged { remainder: u16 },
+    // Two different items traded places
+    Swapped,
+    Noop,
+}
+
+#[derive(Clone, Debug, Default, Reflect, Resource)]
+#[reflect(Resource)]
 pub struct Inventory {
     pub slots: [Option<Item>; INVENTORY_MAX_SIZE],
     pub equipment: Equipment,
 }
 
 impl Inventory {
+    // Tops up every existing stack of the same item before starting new stacks in empty slots.
+    // Returns whatever did not fit.
     pub fn insert(&mut self, item: Item) -> Option<Item> {
-        let mut temp_item = item.clone();
+        let mut temp_item = item;
 
-        for slot in self.slots.iter_mut() {
-            if let Some(i) = slot {
-                if i.name == item.name {
-                    let rem_item = i.merge(temp_item);
-                    if let Some(ri) = rem_item {
-                        temp_item = ri;
-                        continue;
-                    } else {
-                        return None;
-                    }
+        for i in self.slots.iter_mut().flatten() {
+            if i.name == item.name {
+                match i.merge(temp_item) {
+                    Some(ri) => temp_item = ri,
+                    None => return None,
                 }
             }
+        }
 
+        for slot in self.slots.iter_mut() {
             if slot.is_none() {
                 let mut new_item = item.clone_with_amt(0);
                 let rem_item = new_item.merge(temp_item);
                 *slot = Some(new_item);
-                if let Some(ri) = rem_item {
-                    temp_item = ri;
-                    continue;
-                } else {
-                    return None;
+                match rem_item {
+                    Some(ri) => temp_item = ri,
+                    None => return None,
                 }
             }
         }
@@ -56,22 +65,70 @@ impl Inventory {
         Some(temp_item)
     }
 
-    pub fn merge_swap_at(&mut self, a: usize, b: usize) {
+    // Moves the item in slot a onto slot b, merging it into b if they are the same item
+    pub fn merge_swap_at(&mut self, a: usize, b: usize) -> MergeSwapOutcome {
+        if a == b {
+            return MergeSwapOutcome::Noop;
+        }
+
         let slot_a_clone = self.slots[a].clone();
 
         match (slot_a_clone, &mut self.slots[b]) {
-            (Some(item_a), Some(item_b)) => {
-                // Check if the two stacks have the same ItemName, and if so merge them.
-                if item_a.name == item_b.name {
-                    let rem_items = item_b.merge(item_a);
-                    self.slots[a] = rem_items.to_owned();
-                    return;
-                }
+            (None, None) => return MergeSwapOutcome::Noop,
+            // Check if the two stacks have the same ItemName, and if so merge them.
+            (Some(item_a), Some(item_b)) if item_a.name == item_b.name => {
+                let rem_items = item_b.merge(item_a);
+                self.slots[a] = rem_items.to_owned();
+                return MergeSwapOutcome::Merged {
+                    remainder: rem_items.map_or(0, |item| item.amt),
+                };
             }
             _ => (),
         };
 
-        self.slots.swap(a, b)
+        let outcome = if self.slots[a].is_some() && self.slots[b].is_some() {
+            MergeSwapOutcome::Swapped
+        } else {
+            MergeSwapOutcome::Moved
+        };
+
+        self.slots.swap(a, b);
+        outcome
+    }
+
+    // Merges partial stacks of the same item together, then orders the slots by item type
+    // and name, leaving the empty slots at the end. The equipment slots are left alone.
+    pub fn sort_and_consolidate(&mut self) {
+        let mut totals: Vec<(ItemName, u32)> = Vec::new();
+        for item in self.slots.iter().flatten() {
+            match totals.iter_mut().find(|(name, _)| *name == item.name) {
+                Some((_, total)) => *total += item.amt as u32,
+                None => totals.push((item.name, item.amt as u32)),
+            }
+        }
+
+        totals.sort_by_key(|(name, _)| (name.item_type().sort_order(), name.to_string()));
+
+        let mut items = Vec::new();
+        for (name, mut total) in totals {
+            let max_amt = name.max_amt() as u32;
+            while total > 0 {
+                let amt = total.min(max_amt);
+                items.push(Item::new(name, amt as u16));
+                total -= amt;
+            }
+        }
+
+        // Only possible if a slot was somehow holding more than a full stack
+        if items.len() > self.slots.len() {
+            should_not_happen!("sorted inventory does not fit: {} stacks", items.len());
+            return;
+        }
+
+        let mut items = items.into_iter();
+        for slot in self.slots.iter_mut() {
+            *slot = items.next();
+        }
     }
 
     pub fn use_at(&mut self, i: usize) -> (Option<Item>, bool) {
@@ -89,6 +146,48 @@ impl Inventory {
         (None, false)
     }
 
+    // Takes up to amt of the item in a slot, emptying the slot if none of it is left
+    pub fn take_from_slot(&mut self, i: usize, amt: u16) -> Option<Item> {
+        let Some(slot) = self.slots.get_mut(i) else {
+            should_not_happen!("indexing inventory out of bounds: {}", i);
+            return None;
+        };
+        let item = slot.as_mut()?;
+        let taken = amt.min(item.amt);
+        if taken == 0 {
+            return None;
+        }
+
+        item.amt -= taken;
+        let taken_item = item.clone_with_amt(taken);
+        if item.amt == 0 {
+            *slot = None;
+        }
+        Some(taken_item)
+    }
+
+    // Takes one of the first stack of something throwable, if there is one
+    pub fn take_throwable(&mut self) -> Option<Item> {
+        let i = self
+            .slots
+            .iter()
+            .position(|slot| slot.is_some_and(|item| item.name.is_throwable()))?;
+        self.take_from_slot(i, 1)
+    }
+
+    // Takes one of the lowest tier key that still opens locks of the given tier, if there is one
+    pub fn take_key(&mut self, tier: u8) -> Option<Item> {
+        let i = self
+            .slots
+            .iter()
+            .enumerate()
+            .filter_map(|(i, slot)| Some((i, slot.as_ref()?.name.key_tier()?)))
+            .filter(|(_, key_tier)| *key_tier >= tier)
+            .min_by_key(|(_, key_tier)| *key_tier)?
+            .0;
+        self.take_from_slot(i, 1)
+    }
+
     pub fn is_equipable_at(&self, i: usize, name: &EquipmentSlotName) -> bool {
         match self.slots.get(i) {
             Some(slot) => slot.is_none() || slot.as_ref().unwrap().is_equipable_at(&name),
@@ -123,6 +222,70 @@ impl Inventory {
         }
         false
     }
+
+    // Moves the item in an equipment slot into an empty inventory slot
+    pub fn unequip_to(&mut self, i: usize, name: &EquipmentSlotName) -> bool {
+        match self.slots.get_mut(i) {
+            Some(slot) if slot.is_none() => match self.equipment.at_mut(name).take() {
+                Some(item) => {
+                    *slot = Some(item);
+                    true
+                }
+                None => false,
+            },
+            Some(_) => false,
+            None => {
+                should_not_happen!("indexing inventory out of bounds: {}", i);
+                false
+            }
+        }
+    }
+}
+
+// Inventory as it is written to saves, with each item saved at the index of its slot
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SavedInventory {
+    #[serde(default)]
+    pub slots: Vec<Option<SavedItem>>,
+    #[serde(default)]
+    pub equipment: SavedEquipment,
+}
+
+impl From<&Inventory> for SavedInventory {
+    fn from(inventory: &Inventory) -> Self {
+        Self {
+            slots: inventory
+                .slots
+                .iter()
+                .map(|slot| slot.as_ref().map(SavedItem::from))
+                .collect(),
+            equipment: SavedEquipment::from(&inventory.equipment),
+        }
+    }
+}
+
+impl SavedInventory {
+    // Items that no longer exist, or whose slot no longer exists,
+    // are left out and returned alongside the inventory
+    pub fn restore(&self) -> (Inventory, Vec<SavedItem>) {
+        let (equipment, mut unrestored) = self.equipment.restore();
+        let mut inventory = Inventory {
+            equipment,
+            ..default()
+        };
+
+        for (i, saved) in self.slots.iter().enumerate() {
+            let Some(saved) = saved else {
+                continue;
+            };
+            match (inventory.slots.get_mut(i), saved.restore()) {
+                (Some(slot), Some(item)) => *slot = Some(item),
+                _ => unrestored.push(saved.clone()),
+            }
+        }
+
+        (inventory, unrestored)
+    }
 }
 
 #[derive(Event)]
@@ -131,12 +294,54 @@ pub struct InventoryChanged;
 #[derive(Event)]
 pub struct ItemUsed(pub Item, pub Entity);
 
+// With however much of the item was dropped
 #[derive(Event)]
 pub struct PlayerDroppedItem(pub Item);
 
+// How much of a stack is dropped out of the menu, picked by the modifier held while dropping it
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DropAmount {
+    #[default]
+    Stack,
+    One,
+    Half,
+}
+
+impl DropAmount {
+    // Ctrl wins when both are held
+    pub fn from_modifiers(ctrl: bool, shift: bool) -> Self {
+        match (ctrl, shift) {
+            (true, _) => Self::One,
+            (false, true) => Self::Half,
+            (false, false) => Self::Stack,
+        }
+    }
+
+    // Half of an odd stack rounds up, so that there is always something to drop
+    pub fn of(&self, amt: u16) -> u16 {
+        match self {
+            Self::Stack => amt,
+            Self::One => amt.min(1),
+            Self::Half => amt.div_ceil(2),
+        }
+    }
+}
+
+// Sent with however much of an item made it into the inventory when picking it up
+#[derive(Event)]
+pub struct ItemPickedUp(pub Item);
+
+// Sent whenever any of an item is picked up out of a container, along with whatever is left
 #[derive(Event)]
 pub struct ItemRemovedFromOCItemContainer {
     pub ccm: ChunkCellMarker,
     pub _item: Item,
     pub _entity: Entity,
+    pub rem_item: Option<Item>,
+}
+
+#[derive(Event)]
+pub struct DroppedItemPickedUp {
+    pub id: u64,
+    pub rem_item: Option<Item>,
 }
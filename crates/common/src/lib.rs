@@ -1,15 +1,23 @@
 pub mod animation;
+pub mod app_state;
+pub mod automap;
 pub mod camera;
 pub mod cursor;
+pub mod death_marker;
 pub mod error;
 pub mod hud;
 pub mod interaction;
 pub mod inventory;
+pub mod loading;
+pub mod localization;
+pub mod main_menu;
 pub mod menu;
 pub mod meshes;
 pub mod player;
 pub mod save;
 pub mod settings;
+pub mod spectator;
+pub mod stats;
 pub mod utils;
 pub mod world;
 
@@ -0,0 +1,57 @@
+use bevy::prelude::{Component, Resource, UntypedHandle};
+
+// Handles of the assets that have to finish loading before a game is started.
+// Holding on to them also keeps the assets from being unloaded between games.
+#[derive(Default, Resource)]
+pub struct PendingAssets(pub Vec<UntypedHandle>);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PendingAssetState {
+    Loading,
+    Loaded,
+    Failed,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LoadingProgress {
+    pub loaded: usize,
+    pub failed: usize,
+    pub total: usize,
+}
+
+impl LoadingProgress {
+    pub fn new(states: impl IntoIterator<Item = PendingAssetState>) -> Self {
+        let mut progress = Self::default();
+        for state in states {
+            progress.total += 1;
+            match state {
+                PendingAssetState::Loading => {}
+                PendingAssetState::Loaded => progress.loaded += 1,
+                PendingAssetState::Failed => progress.failed += 1,
+            }
+        }
+        progress
+    }
+
+    // Failed assets count as done, so a missing file cannot keep the game from starting
+    pub fn done(&self) -> usize {
+        self.loaded + self.failed
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done() == self.total
+    }
+
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        self.done() as f32 / self.total as f32
+    }
+}
+
+#[derive(Component)]
+pub struct LoadingProgressBar;
+
+#[derive(Component)]
+pub struct LoadingProgressText;
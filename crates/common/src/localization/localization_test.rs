@@ -0,0 +1,53 @@
+use crate::{
+    inventory::item::ItemName,
+    localization::{Language, Localization},
+};
+use std::collections::HashMap;
+use strum::IntoEnumIterator;
+
+#[test]
+fn test_every_item_name_has_english_key() {
+    let localization = Localization::new(Language::English);
+
+    for item_name in ItemName::iter() {
+        assert!(
+            localization.has_key(item_name.display_key()),
+            "missing English key for {}",
+            item_name
+        );
+    }
+}
+
+#[test]
+fn test_languages_share_keys() {
+    let keys = |language: Language| {
+        let mut keys: Vec<String> =
+            serde_json::from_str::<HashMap<String, String>>(language.file_contents())
+                .unwrap()
+                .into_keys()
+                .collect();
+        keys.sort();
+        keys
+    };
+
+    let english_keys = keys(Language::English);
+    let mut language = Language::English.next();
+    while language != Language::English {
+        assert_eq!(keys(language), english_keys, "{:?}", language);
+        language = language.next();
+    }
+}
+
+#[test]
+fn test_tr_args_and_missing_keys() {
+    let localization = Localization::new(Language::English);
+
+    assert_eq!(
+        localization.tr(
+            "popup.picked_up",
+            &[("amt", &3), ("item", &localization.tr("item.coal", &[]))]
+        ),
+        "Picked up (3) Coal"
+    );
+    assert_eq!(localization.tr("not.a.key", &[]), "not.a.key");
+}
@@ -0,0 +1,85 @@
+#[cfg(test)]
+mod localization_test;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Reflect, Serialize)]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Language {
+    // Languages are always listed by their own name, so they can be found again
+    // by someone who cannot read the current one
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::English => "English",
+            Self::Spanish => "Español",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Self::English => Self::Spanish,
+            Self::Spanish => Self::English,
+        }
+    }
+
+    // Flat maps of keys to strings, under assets/lang
+    pub fn file_contents(&self) -> &'static str {
+        match self {
+            Self::English => include_str!("../../../../assets/lang/en.json"),
+            Self::Spanish => include_str!("../../../../assets/lang/es.json"),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Localization {
+    language: Language,
+    strings: HashMap<String, String>,
+}
+
+impl Default for Localization {
+    fn default() -> Self {
+        Self::new(Language::default())
+    }
+}
+
+impl Localization {
+    pub fn new(language: Language) -> Self {
+        let strings = serde_json::from_str(language.file_contents()).unwrap_or_else(|err| {
+            warn!("Error parsing {:?} language file: {}", language, err);
+            HashMap::new()
+        });
+
+        Self { language, strings }
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    pub fn has_key(&self, key: &str) -> bool {
+        self.strings.contains_key(key)
+    }
+
+    // Looks up the string for a key, replacing each {name} in it with its arg.
+    // Missing keys fall back to the key itself, so a bad key shows up on screen.
+    pub fn tr(&self, key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        let Some(s) = self.strings.get(key) else {
+            warn!("Missing {:?} localization key: {}", self.language, key);
+            return key.to_owned();
+        };
+
+        let mut s = s.clone();
+        for (name, value) in args {
+            s = s.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        s
+    }
+}
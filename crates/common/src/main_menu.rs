@@ -0,0 +1,40 @@
+use bevy::prelude::{Component, Resource};
+
+#[derive(Clone, Copy, Component, Debug, Eq, PartialEq)]
+pub enum MainMenuButton {
+    NewGame,
+    Continue,
+    Quit,
+}
+
+impl MainMenuButton {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::NewGame => "New Game",
+            Self::Continue => "Continue",
+            Self::Quit => "Quit",
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct NewGameSeedText;
+
+// Seed typed in on the main menu. A random seed is used if left empty.
+#[derive(Default, Resource)]
+pub struct NewGameSeed(pub String);
+
+#[derive(Clone, Copy, Component, Debug, Eq, PartialEq)]
+pub enum PauseMenuButton {
+    Resume,
+    MainMenu,
+}
+
+impl PauseMenuButton {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Resume => "Resume",
+            Self::MainMenu => "Main Menu",
+        }
+    }
+}
@@ -1,11 +1,16 @@
-use crate::inventory::equipment::EquipmentSlotName;
-use bevy::prelude::{Component, States, Visibility};
+use crate::{
+    hud::HudWidget,
+    inventory::{equipment::EquipmentSlotName, DropAmount, Inventory},
+};
+use bevy::prelude::{Component, Entity, Event, Resource, States, Visibility};
 use std::fmt;
 
 #[derive(Clone, Component, Debug, Default, Eq, Hash, PartialEq)]
 pub enum MenuTab {
     #[default]
     Inventory,
+    Map,
+    Stats,
     Settings,
 }
 
@@ -13,11 +18,24 @@ impl fmt::Display for MenuTab {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Inventory => write!(f, "Inventory"),
+            Self::Map => write!(f, "Map"),
+            Self::Stats => write!(f, "Stats"),
             Self::Settings => write!(f, "Settings"),
         }
     }
 }
 
+impl MenuTab {
+    pub fn display_key(&self) -> &'static str {
+        match self {
+            Self::Inventory => "menu.tab.inventory",
+            Self::Map => "menu.tab.map",
+            Self::Stats => "menu.tab.stats",
+            Self::Settings => "menu.tab.settings",
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq, States)]
 pub struct MenuOpen(pub bool);
 
@@ -35,21 +53,152 @@ pub enum Dragging {
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq, States)]
 pub struct DragState(pub Dragging);
 
+// Sent when a dragged item is released over the world, outside of the menu, to drop it there.
+// Drags that end anywhere else are either moved into a slot or canceled.
+// Whatever is not dropped stays in the slot it was dragged from.
+#[derive(Debug, Event)]
+pub struct DraggedItemDropped(pub Dragging, pub DropAmount);
+
 #[derive(Component)]
 pub struct Menu;
 
+// Element of the menu that can be focused from the keyboard. Focus moves between them
+// in order of their index, which stays the same when the menu content is respawned.
+#[derive(Clone, Copy, Component, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Focusable(pub u32);
+
+// Index of the Focusable that is focused, if any
+#[derive(Debug, Default, Resource)]
+pub struct FocusedUiElement(pub Option<Focusable>);
+
+impl FocusedUiElement {
+    // Moves to the next or previous index, wrapping around at either end so focus
+    // never leaves the menu. Starts at either end when nothing that exists is focused.
+    pub fn step(&mut self, indices: &[Focusable], forward: bool) {
+        if indices.is_empty() {
+            self.0 = None;
+            return;
+        }
+
+        let last = indices.len() - 1;
+        let i = match self
+            .0
+            .and_then(|focused| indices.iter().position(|f| *f == focused))
+        {
+            Some(i) if forward => (i + 1) % indices.len(),
+            Some(i) => i.checked_sub(1).unwrap_or(last),
+            None if forward => 0,
+            None => last,
+        };
+        self.0 = Some(indices[i]);
+    }
+}
+
+// Sent when Enter is pressed on the focused element, which counts as pressing it
+#[derive(Debug, Event)]
+pub struct UiElementActivated(pub Entity);
+
 #[derive(Component)]
 pub struct MenuContent;
 
 #[derive(Component)]
 pub struct RenderDistButton(pub u32);
 
+#[derive(Component)]
+pub struct PhysicsDistButton(pub u32);
+
+// Moves the setting by a number of steps when pressed
+#[derive(Component)]
+pub struct FovButton(pub i32);
+
+#[derive(Component)]
+pub struct FovText;
+
+// Moves the setting by a number of steps when pressed
+#[derive(Component)]
+pub struct MouseSensitivityButton(pub i32);
+
+#[derive(Component)]
+pub struct MouseSensitivityText;
+
+// Moves the setting by a number of steps when pressed
+#[derive(Component)]
+pub struct UiScaleButton(pub i32);
+
+#[derive(Component)]
+pub struct UiScaleText;
+
+// Moves the setting by a number of steps when pressed
+#[derive(Component)]
+pub struct AutosaveButton(pub i32);
+
+#[derive(Component)]
+pub struct AutosaveText;
+
+#[derive(Component)]
+pub struct StructureCompassButton;
+
+#[derive(Component)]
+pub struct SpectatorModeButton;
+
+#[derive(Component)]
+pub struct DamageEffectsButton;
+
+#[derive(Component)]
+pub struct HitEffectsButton;
+
+#[derive(Component)]
+pub struct AutoPickUpButton;
+
+#[derive(Component)]
+pub struct CrosshairStyleButton;
+
+#[derive(Component)]
+pub struct CrosshairSizeButton;
+
+#[derive(Component)]
+pub struct DecorDensityButton;
+
+#[derive(Component)]
+pub struct LightFuelModeButton;
+
+#[derive(Component)]
+pub struct MovementStyleButton;
+
+#[derive(Component)]
+pub struct DifficultyButton;
+
+#[derive(Component)]
+pub struct LanguageButton;
+
+#[derive(Component)]
+pub struct HudWidgetToggleButton(pub HudWidget);
+
+#[derive(Component)]
+pub struct HudWidgetAnchorButton(pub HudWidget);
+
+#[derive(Component)]
+pub struct InventorySortButton;
+
 #[derive(Component)]
 pub struct InventorySlot(pub usize);
 
 #[derive(Component)]
 pub struct EquipmentSlot(pub EquipmentSlotName);
 
+// Name of the item in the equipment slot, shown under it
+#[derive(Component)]
+pub struct EquipmentSlotItemText(pub EquipmentSlotName);
+
+// Image of the item in an inventory or equipment slot
+#[derive(Component)]
+pub struct SlotItemImage;
+
+// Inventory as it was when the inventory menu content was last spawned or updated,
+// so that only the slots whose item changed since then are updated
+#[derive(Debug, Default, Resource)]
+pub struct RenderedInventory(pub Option<Inventory>);
+
 #[derive(Component)]
 pub struct ItemImageCursorFollower;
 
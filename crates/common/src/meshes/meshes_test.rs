@@ -0,0 +1,173 @@
+use crate::{
+    error::Error,
+    meshes::obj::{mesh_from_obj_str, normalize_obj_str},
+};
+use bevy::render::mesh::{Mesh, VertexAttributeValues};
+
+const TRIANGLE_VERTICES: &str = "o Triangle
+v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 0.0 -1.0
+vt 0.0 0.0
+vt 1.0 0.0
+vt 0.0 1.0
+vn 0.0 1.0 0.0
+";
+
+fn uvs(mesh: &Mesh) -> Vec<[f32; 2]> {
+    match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+        Some(VertexAttributeValues::Float32x2(uvs)) => uvs.clone(),
+        _ => panic!("expected uvs"),
+    }
+}
+
+fn normals(mesh: &Mesh) -> Vec<[f32; 3]> {
+    match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+        Some(VertexAttributeValues::Float32x3(normals)) => normals.clone(),
+        _ => panic!("expected normals"),
+    }
+}
+
+#[test]
+fn test_mesh_from_obj_str_quad_and_triangle() {
+    let mesh = mesh_from_obj_str(include_str!("fixtures/quad_and_triangle.obj")).unwrap();
+
+    // 4 vertices for the quad + 3 for the triangle
+    assert_eq!(mesh.count_vertices(), 7);
+    // Quad is split into 2 triangles, plus the triangle itself
+    assert_eq!(mesh.indices().unwrap().len(), 9);
+}
+
+#[test]
+fn test_mesh_from_obj_str_pentagon_fan() {
+    let content = "o Pentagon
+v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 1.5 0.0 1.0
+v 0.5 0.0 1.5
+v -0.5 0.0 1.0
+vn 0.0 1.0 0.0
+vt 0.0 0.0
+f 1/1/1 2/1/1 3/1/1 4/1/1 5/1/1
+";
+
+    let mesh = mesh_from_obj_str(content).unwrap();
+    let indices: Vec<usize> = mesh.indices().unwrap().iter().collect();
+
+    assert_eq!(mesh.count_vertices(), 5);
+    assert_eq!(indices, vec![0, 1, 2, 0, 2, 3, 0, 3, 4]);
+}
+
+#[test]
+fn test_mesh_from_obj_str_error_line() {
+    let content = "o Broken
+v 0.0 0.0 0.0
+v 1.0 0.0
+vn 0.0 1.0 0.0
+";
+
+    match mesh_from_obj_str(content) {
+        Err(Error::Obj { line, .. }) => assert_eq!(line, 3),
+        _ => panic!("expected Error::Obj"),
+    }
+}
+
+#[test]
+fn test_normalize_obj_str_face_v() {
+    let mesh = mesh_from_obj_str(&format!("{}f 1 2 3\n", TRIANGLE_VERTICES)).unwrap();
+
+    assert_eq!(mesh.count_vertices(), 3);
+    assert_eq!(uvs(&mesh), vec![[0.0, 0.0]; 3]);
+    // Computed from the winding order of the face
+    assert_eq!(normals(&mesh), vec![[0.0, 1.0, 0.0]; 3]);
+}
+
+#[test]
+fn test_normalize_obj_str_face_v_vt() {
+    let mesh = mesh_from_obj_str(&format!("{}f 1/1 2/2 3/3\n", TRIANGLE_VERTICES)).unwrap();
+
+    assert_eq!(uvs(&mesh), vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]);
+    assert_eq!(normals(&mesh), vec![[0.0, 1.0, 0.0]; 3]);
+}
+
+#[test]
+fn test_normalize_obj_str_face_v_vn() {
+    let mesh = mesh_from_obj_str(&format!("{}f 1//1 2//1 3//1\n", TRIANGLE_VERTICES)).unwrap();
+
+    assert_eq!(uvs(&mesh), vec![[0.0, 0.0]; 3]);
+    assert_eq!(normals(&mesh), vec![[0.0, 1.0, 0.0]; 3]);
+}
+
+#[test]
+fn test_normalize_obj_str_face_v_vt_vn() {
+    let content = format!("{}f 1/1/1 2/2/1 3/3/1\n", TRIANGLE_VERTICES);
+
+    assert_eq!(normalize_obj_str(&content).unwrap(), content.trim_end());
+    assert_eq!(mesh_from_obj_str(&content).unwrap().count_vertices(), 3);
+}
+
+#[test]
+fn test_normalize_obj_str_negative_indices() {
+    let content = format!("{}f -3/-3/-1 -2/-2/-1 -1/-1/-1\n", TRIANGLE_VERTICES);
+
+    assert_eq!(
+        normalize_obj_str(&content).unwrap().lines().last(),
+        Some("f 1/1/1 2/2/1 3/3/1")
+    );
+    assert_eq!(
+        uvs(&mesh_from_obj_str(&content).unwrap()),
+        vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]
+    );
+}
+
+#[test]
+fn test_normalize_obj_str_negative_index_out_of_range() {
+    let content = format!("{}f -4 -2 -1\n", TRIANGLE_VERTICES);
+
+    match normalize_obj_str(&content) {
+        Err(Error::Obj { line, .. }) => assert_eq!(line, 9),
+        _ => panic!("expected Error::Obj"),
+    }
+}
+
+#[test]
+fn test_normalize_obj_str_mixed_forms() {
+    let content = "# Exported without uvs on some faces\r
+o Mixed\r
+v 0.0 0.0 0.0\r
+v 1.0 0.0 0.0\r
+v 1.0 0.0 -1.0\r
+v 0.0 0.0 -1.0\r
+vt 0.5 0.5\r
+vn 0.0 -1.0 0.0\r
+f 1 2 3\r
+f 1/1 3/1 4/1\r
+# Faces can also be declared with normals but no uvs\r
+f 1//1 2//1 3//1\r
+f -4/-1/-1 -2/-1/-1 -1/-1/-1\r
+";
+
+    let mesh = mesh_from_obj_str(content).unwrap();
+
+    assert_eq!(mesh.count_vertices(), 12);
+    assert_eq!(
+        uvs(&mesh),
+        [
+            [[0.0, 0.0]; 3],
+            [[0.5, 0.5]; 3],
+            [[0.0, 0.0]; 3],
+            [[0.5, 0.5]; 3]
+        ]
+        .concat()
+    );
+    assert_eq!(
+        normals(&mesh),
+        [
+            [[0.0, 1.0, 0.0]; 3],
+            [[0.0, 1.0, 0.0]; 3],
+            [[0.0, -1.0, 0.0]; 3],
+            [[0.0, -1.0, 0.0]; 3],
+        ]
+        .concat()
+    );
+}
@@ -1,3 +1,8 @@
+pub mod obj;
+
+#[cfg(test)]
+mod meshes_test;
+
 use bevy::prelude::Mesh;
 use bevy_mesh_obj::mesh_from_obj;
 
@@ -5,17 +10,17 @@ use bevy_mesh_obj::mesh_from_obj;
 // to gen meshes at compile time
 
 pub fn new_staircase_mesh() -> Mesh {
-    mesh_from_obj!("../../../assets/meshes/staircase.obj")
+    mesh_from_obj!("../../../../assets/meshes/staircase.obj")
 }
 
 pub fn new_stairs_mesh() -> Mesh {
-    mesh_from_obj!("../../../assets/meshes/stairs.obj")
+    mesh_from_obj!("../../../../assets/meshes/stairs.obj")
 }
 
 pub fn new_wall_with_door_gap_mesh() -> Mesh {
-    mesh_from_obj!("../../../assets/meshes/wall_with_door_gap.obj")
+    mesh_from_obj!("../../../../assets/meshes/wall_with_door_gap.obj")
 }
 
 pub fn new_wall_with_window_gap_mesh() -> Mesh {
-    mesh_from_obj!("../../../assets/meshes/wall_with_window_gap.obj")
+    mesh_from_obj!("../../../../assets/meshes/wall_with_window_gap.obj")
 }
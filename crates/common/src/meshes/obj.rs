@@ -0,0 +1,266 @@
+use crate::error::Error;
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+    },
+};
+use bevy_mesh_obj::obj::{Obj3D, Vertex};
+
+// Loads .obj files at runtime, as opposed to mesh_from_obj!(),
+// which embeds them into the binary at compile time
+pub struct ObjAssetPlugin;
+
+impl Plugin for ObjAssetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset_loader::<ObjAssetLoader>();
+    }
+}
+
+#[derive(Default)]
+pub struct ObjAssetLoader;
+
+impl AssetLoader for ObjAssetLoader {
+    type Asset = Mesh;
+    type Settings = ();
+    type Error = Error;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let content = String::from_utf8(bytes).map_err(|err| Error::Parsing(err.to_string()))?;
+        mesh_from_obj_str(&content)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["obj"]
+    }
+}
+
+pub fn mesh_from_obj_str(content: &str) -> Result<Mesh, Error> {
+    let objs = parse_obj_str(content)?;
+
+    match objs.as_slice() {
+        [obj] => mesh_from_obj3d(obj),
+        _ => Err(Error::Parsing(format!(
+            "expected .obj file to contain 1 object, but found {} objects instead",
+            objs.len(),
+        ))),
+    }
+}
+
+// Obj3D::parse_string() only accepts faces in the v/vt/vn form with positive indices,
+// so faces in the v, v/vt and v//vn forms are rewritten to reference a default (0, 0) uv
+// and a computed face normal, which are appended to the end of the file. Negative indices
+// are resolved relative to the number of elements declared so far. Each input line maps
+// to exactly one output line, so line numbers in errors still match the original file.
+pub fn normalize_obj_str(content: &str) -> Result<String, Error> {
+    let all_lines: Vec<Vec<&str>> = content
+        .lines()
+        .map(|line| line.split_whitespace().collect())
+        .collect();
+
+    let count_token = |token: &str| {
+        all_lines
+            .iter()
+            .filter(|tokens| tokens.first() == Some(&token))
+            .count()
+    };
+    let default_uv_index = count_token("vt") + 1;
+    let first_face_normal_index = count_token("vn") + 1;
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut vertices: Vec<Vec3> = Vec::new();
+    let mut uv_count = 0;
+    let mut normal_count = 0;
+    let mut needs_default_uv = false;
+    let mut face_normals: Vec<Vec3> = Vec::new();
+
+    for (i, tokens) in all_lines.iter().enumerate() {
+        let obj_err = |msg: String| Error::Obj { line: i + 1, msg };
+
+        match tokens.first() {
+            Some(&"v") => {
+                let v = Vertex::try_from(&tokens[1..]).map_err(|err| obj_err(err.to_string()))?;
+                vertices.push(Vec3::from_array(v.to_arr()));
+            }
+            Some(&"vt") => uv_count += 1,
+            Some(&"vn") => normal_count += 1,
+            Some(&"f") => {
+                let mut face_defs = Vec::new();
+                let mut face_normal_index = None;
+
+                for fd_str in &tokens[1..] {
+                    let parts: Vec<&str> = fd_str.split('/').collect();
+                    if parts.len() > 3 {
+                        return Err(obj_err(format!("invalid face definition: {}", fd_str)));
+                    }
+
+                    let index_at = |n: usize, count: usize| -> Result<Option<usize>, Error> {
+                        match parts.get(n).filter(|s| !s.is_empty()) {
+                            Some(s) => resolve_obj_index(s, count).map(Some).map_err(obj_err),
+                            None => Ok(None),
+                        }
+                    };
+
+                    let Some(v) = index_at(0, vertices.len())? else {
+                        return Err(obj_err(format!("invalid face definition: {}", fd_str)));
+                    };
+
+                    let vt = match index_at(1, uv_count)? {
+                        Some(vt) => vt,
+                        None => {
+                            needs_default_uv = true;
+                            default_uv_index
+                        }
+                    };
+
+                    let vn = match index_at(2, normal_count)? {
+                        Some(vn) => vn,
+                        None => match face_normal_index {
+                            Some(vn) => vn,
+                            None => {
+                                let normal =
+                                    calc_face_normal(&vertices, &tokens[1..]).ok_or_else(|| {
+                                        obj_err(
+                                            "face references a vertex that does not exist"
+                                                .to_owned(),
+                                        )
+                                    })?;
+                                face_normals.push(normal);
+
+                                let vn = first_face_normal_index + face_normals.len() - 1;
+                                face_normal_index = Some(vn);
+                                vn
+                            }
+                        },
+                    };
+
+                    face_defs.push(format!("{}/{}/{}", v, vt, vn));
+                }
+
+                lines.push(format!("f {}", face_defs.join(" ")));
+                continue;
+            }
+            // Comments are dropped, but leave an empty line to keep the line count unchanged
+            Some(t) if t.starts_with('#') => {
+                lines.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+
+        lines.push(tokens.join(" "));
+    }
+
+    if needs_default_uv {
+        lines.push("vt 0 0".to_owned());
+    }
+    for normal in face_normals {
+        lines.push(format!("vn {} {} {}", normal.x, normal.y, normal.z));
+    }
+
+    Ok(lines.join("\n"))
+}
+
+// Resolves a 1-based (or negative, relative) .obj index to a positive 1-based index
+fn resolve_obj_index(s: &str, count: usize) -> Result<usize, String> {
+    let index = s.parse::<i64>().map_err(|err| err.to_string())?;
+
+    let resolved = if index < 0 {
+        count as i64 + index + 1
+    } else {
+        index
+    };
+
+    if resolved < 1 {
+        return Err(format!("index out of range: {}", index));
+    }
+
+    Ok(resolved as usize)
+}
+
+// Calculates the normal of a face from its first 3 vertices
+fn calc_face_normal(vertices: &[Vec3], fd_strs: &[&str]) -> Option<Vec3> {
+    let positions = fd_strs
+        .iter()
+        .take(3)
+        .map(|fd_str| {
+            let v_str = fd_str.split('/').next()?;
+            let index = resolve_obj_index(v_str, vertices.len()).ok()?;
+            vertices.get(index - 1).copied()
+        })
+        .collect::<Option<Vec<Vec3>>>()?;
+
+    match positions.as_slice() {
+        [a, b, c] => Some((*b - *a).cross(*c - *a).normalize_or_zero()),
+        _ => Some(Vec3::ZERO),
+    }
+}
+
+fn parse_obj_str(content: &str) -> Result<Vec<Obj3D>, Error> {
+    let content = normalize_obj_str(content)?;
+
+    Obj3D::parse_string(content.clone()).map_err(|err| {
+        // Obj3D::parse_string() does not report which line failed, so find the
+        // first line at which parsing everything up to and including it fails
+        let lines: Vec<&str> = content.split('\n').collect();
+        let line = (1..=lines.len())
+            .find(|n| Obj3D::parse_string(lines[..*n].join("\n")).is_err())
+            .unwrap_or(lines.len());
+
+        Error::Obj {
+            line,
+            msg: err.to_string(),
+        }
+    })
+}
+
+pub fn mesh_from_obj3d(obj: &Obj3D) -> Result<Mesh, Error> {
+    let mut positions = Vec::new();
+    let mut uv = Vec::new();
+    let mut normals = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    for face in &obj.faces {
+        let first = positions.len() as u32;
+
+        for fd in &face.face_defs {
+            let (Some(v), Some(vt), Some(vn)) = (
+                obj.vertices.get(fd.vertex_index),
+                obj.uv_textures.get(fd.uv_texture_index),
+                obj.normals.get(fd.normal_index),
+            ) else {
+                return Err(Error::Parsing(format!(
+                    "face definition references an index that does not exist: {}",
+                    fd,
+                )));
+            };
+
+            positions.push(v.to_arr());
+            uv.push(vt.to_arr());
+            normals.push(vn.to_arr());
+        }
+
+        // Faces with more than 3 vertices are triangulated as a fan around their first vertex
+        for i in 1..face.face_defs.len().saturating_sub(1) as u32 {
+            indices.extend([first, first + i, first + i + 1]);
+        }
+    }
+
+    Ok(Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uv)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_indices(Indices::U32(indices)))
+}
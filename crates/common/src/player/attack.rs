@@ -1,5 +1,8 @@
-use crate::utils::IncrCounter;
-use bevy::prelude::{Component, Entity, Resource};
+use crate::{
+    animation::{PlayerAnimation, PLAYER_ANIMATION_FPS},
+    utils::IncrCounter,
+};
+use bevy::prelude::{Component, Entity, Event, Resource};
 
 #[derive(Component)]
 pub struct EntitiesHit(pub Vec<Entity>);
@@ -10,63 +13,309 @@ impl EntitiesHit {
     }
 }
 
+// Tracks how far into its animation the current attack is
+#[derive(Clone, Copy, Debug, Default, PartialEq, Resource)]
+pub struct AttackProgress {
+    elapsed_secs: f32,
+}
+
+impl AttackProgress {
+    pub fn reset(&mut self) {
+        self.elapsed_secs = 0.0;
+    }
+
+    pub fn tick(&mut self, delta_secs: f32) {
+        self.elapsed_secs += delta_secs;
+    }
+
+    pub fn frame(&self) -> u32 {
+        (self.elapsed_secs * PLAYER_ANIMATION_FPS) as u32
+    }
+
+    pub fn is_in_hit_window(&self, animation: &PlayerAnimation) -> bool {
+        animation
+            .hit_window()
+            .is_some_and(|hit_window| hit_window.contains(self.frame()))
+    }
+}
+
+// What letting go of the attack buttons turned into
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AttackRelease {
+    Single(AttackType, AttackHand),
+    Dual(AttackType),
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct HandCharge {
+    counter: IncrCounter,
+    // Set once the hand has been let go, but is waiting on the other hand to be let go
+    // too so that both can attack together, along with the ticks it has waited
+    released: Option<(AttackType, u32)>,
+}
+
+// Each hand charges up on its own, so that both can be held down at once when dual wielding
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Resource)]
 pub struct AttackChargeUp {
     light_attack_frames: u32,
     heavy_attack_frames: u32,
-    attack_hand: Option<AttackHand>,
-    counter: IncrCounter,
+    // Ticks a let go hand waits on the other hand before attacking alone
+    dual_release_window: u32,
+    left: Option<HandCharge>,
+    right: Option<HandCharge>,
+    // Hand whose button cancelled the last charge, which can not start charging until it is let go
+    cancel_held: Option<AttackHand>,
 }
 
 impl AttackChargeUp {
     pub fn new(
         light_attack_frames: u32,
         heavy_attack_frames: u32,
-        attack_hand: Option<AttackHand>,
+        dual_release_window: u32,
     ) -> Self {
         Self {
             light_attack_frames,
             heavy_attack_frames,
-            attack_hand,
-            counter: IncrCounter::new((light_attack_frames + heavy_attack_frames) as i32, -1),
+            dual_release_window,
+            left: None,
+            right: None,
+            cancel_held: None,
         }
     }
 
-    pub fn tick(&mut self) -> i32 {
-        self.attack_hand.map_or(0, |_| self.counter.tick())
+    fn hand(&self, attack_hand: &AttackHand) -> &Option<HandCharge> {
+        match attack_hand {
+            AttackHand::Left => &self.left,
+            AttackHand::Right => &self.right,
+        }
     }
 
-    pub fn is_charging_hand(&self, attack_hand: &AttackHand) -> bool {
-        match self.attack_hand {
-            Some(h) => &h == attack_hand,
-            None => false,
+    fn hand_mut(&mut self, attack_hand: &AttackHand) -> &mut Option<HandCharge> {
+        match attack_hand {
+            AttackHand::Left => &mut self.left,
+            AttackHand::Right => &mut self.right,
+        }
+    }
+
+    fn attack_type(&self, counter: &IncrCounter) -> AttackType {
+        if counter.get_value() <= self.heavy_attack_frames as i32 {
+            AttackType::Heavy
+        } else {
+            AttackType::Light
         }
     }
 
-    fn reset(&mut self) {
-        self.attack_hand = None;
-        self.counter = IncrCounter::new(
-            (self.light_attack_frames + self.heavy_attack_frames) as i32,
-            -1,
-        )
+    // Starts charging up a hand over, without touching the other hand.
+    // Does nothing while the hand's button is still held down from cancelling.
+    pub fn start(&mut self, attack_hand: &AttackHand) {
+        if self.cancel_held == Some(*attack_hand) {
+            return;
+        }
+        let frames = (self.light_attack_frames + self.heavy_attack_frames) as i32;
+        *self.hand_mut(attack_hand) = Some(HandCharge {
+            counter: IncrCounter::new(frames, -1),
+            released: None,
+        });
+    }
+
+    // Counts a tick of a hand being held down
+    pub fn tick(&mut self, attack_hand: &AttackHand) -> i32 {
+        match self.hand_mut(attack_hand) {
+            Some(charge) if charge.released.is_none() => charge.counter.tick(),
+            _ => 0,
+        }
+    }
+
+    pub fn is_charging(&self) -> bool {
+        self.left.is_some() || self.right.is_some()
+    }
+
+    // Also true while a let go hand is waiting on the other hand
+    pub fn is_charging_hand(&self, attack_hand: &AttackHand) -> bool {
+        self.hand(attack_hand).is_some()
+    }
+
+    pub fn reset(&mut self) {
+        self.left = None;
+        self.right = None;
     }
 
-    pub fn reset_to(&mut self, attack_hand: AttackHand) {
+    pub fn reset_hand(&mut self, attack_hand: &AttackHand) {
+        *self.hand_mut(attack_hand) = None;
+    }
+
+    // Drops every charging hand without attacking. cancel_hand is the hand whose button
+    // did the cancelling, if any, which has to be let go before it can charge up itself.
+    // Returns whether anything was charging.
+    pub fn cancel(&mut self, cancel_hand: Option<AttackHand>) -> bool {
+        if !self.is_charging() {
+            return false;
+        }
         self.reset();
-        self.attack_hand = Some(attack_hand);
+        self.cancel_held = cancel_hand;
+        true
+    }
+
+    // Called whenever a hand's button is not held down
+    pub fn release_cancel(&mut self, attack_hand: &AttackHand) {
+        if self.cancel_held == Some(*attack_hand) {
+            self.cancel_held = None;
+        }
     }
 
-    pub fn release(&mut self) -> AttackType {
-        self.attack_hand.map_or(AttackType::Light, |_| {
-            let attack_type = if self.counter.get_value() <= self.heavy_attack_frames as i32 {
-                AttackType::Heavy
-            } else {
-                AttackType::Light
+    // How far along the furthest charged hand is towards a heavy attack, from 0 to 1
+    pub fn charge_fraction(&self) -> Option<f32> {
+        let total = (self.light_attack_frames + self.heavy_attack_frames) as i32;
+        [&self.left, &self.right]
+            .into_iter()
+            .flatten()
+            .map(|charge| {
+                let ticks = (total - charge.counter.get_value()) as f32;
+                (ticks / self.light_attack_frames.max(1) as f32).min(1.0)
+            })
+            .reduce(f32::max)
+    }
+
+    // Lets go of a hand. When dual wielding while the other hand is still charging, this
+    // waits on the other hand instead, and attacks with both if it is let go in time.
+    // Dual attacks are only heavy when both hands were charged up long enough.
+    pub fn release(&mut self, attack_hand: &AttackHand, dual_wield: bool) -> Option<AttackRelease> {
+        let charge = self.hand(attack_hand).as_ref()?;
+        if charge.released.is_some() {
+            return None;
+        }
+        let attack_type = self.attack_type(&charge.counter);
+
+        if dual_wield {
+            match self.hand(&attack_hand.other()) {
+                Some(HandCharge {
+                    released: Some((other_attack_type, _)),
+                    ..
+                }) => {
+                    let dual_attack_type = match (attack_type, *other_attack_type) {
+                        (AttackType::Heavy, AttackType::Heavy) => AttackType::Heavy,
+                        _ => AttackType::Light,
+                    };
+                    self.reset();
+                    return Some(AttackRelease::Dual(dual_attack_type));
+                }
+                Some(HandCharge { released: None, .. }) => {
+                    if let Some(charge) = self.hand_mut(attack_hand) {
+                        charge.released = Some((attack_type, 0));
+                    }
+                    return None;
+                }
+                None => {}
+            }
+        }
+
+        self.reset_hand(attack_hand);
+        Some(AttackRelease::Single(attack_type, *attack_hand))
+    }
+
+    // Counts a tick of waiting for any let go hand, which attacks alone
+    // once the other hand has not been let go within the window
+    pub fn tick_release_window(&mut self) -> Option<AttackRelease> {
+        let window = self.dual_release_window;
+
+        for attack_hand in [AttackHand::Left, AttackHand::Right] {
+            let Some((attack_type, ticks)) = self
+                .hand_mut(&attack_hand)
+                .as_mut()
+                .and_then(|charge| charge.released.as_mut())
+            else {
+                continue;
             };
 
-            self.reset();
-            attack_type
-        })
+            *ticks += 1;
+            if *ticks >= window {
+                let attack_type = *attack_type;
+                self.reset_hand(&attack_hand);
+                return Some(AttackRelease::Single(attack_type, attack_hand));
+            }
+        }
+
+        None
+    }
+}
+
+// How long before an attack ends that pressing an attack button still swings again after it
+pub const BUFFERED_ATTACK_SECS: f32 = 0.3;
+
+// An attack button pressed during an attack, which would otherwise be lost to it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Resource)]
+pub struct BufferedAttack {
+    pressed: Option<(AttackHand, f32)>,
+}
+
+impl BufferedAttack {
+    // Only the latest press is kept
+    pub fn record(&mut self, attack_hand: AttackHand, now_secs: f32) {
+        self.pressed = Some((attack_hand, now_secs));
+    }
+
+    // Empties the buffer, giving back the hand pressed if it was pressed recently enough
+    pub fn take(&mut self, now_secs: f32) -> Option<AttackHand> {
+        let (attack_hand, pressed_secs) = self.pressed.take()?;
+        (now_secs - pressed_secs <= BUFFERED_ATTACK_SECS).then_some(attack_hand)
+    }
+
+    pub fn clear(&mut self) {
+        self.pressed = None;
+    }
+
+    pub fn is_buffered(&self) -> bool {
+        self.pressed.is_some()
+    }
+}
+
+// Sent when a charging attack is dropped without swinging
+#[derive(Event)]
+pub struct AttackChargeCancelled;
+
+// Briefly keeps a hand from attacking after the weapon in it changes
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Resource)]
+pub struct WeaponSwapCooldown {
+    frames: u32,
+    left: IncrCounter,
+    right: IncrCounter,
+}
+
+impl WeaponSwapCooldown {
+    pub fn new(frames: u32) -> Self {
+        Self {
+            frames,
+            left: IncrCounter::new(0, -1),
+            right: IncrCounter::new(0, -1),
+        }
+    }
+
+    fn counter_mut(&mut self, attack_hand: &AttackHand) -> &mut IncrCounter {
+        match attack_hand {
+            AttackHand::Left => &mut self.left,
+            AttackHand::Right => &mut self.right,
+        }
+    }
+
+    pub fn start(&mut self, attack_hand: &AttackHand) {
+        let frames = self.frames as i32;
+        *self.counter_mut(attack_hand) = IncrCounter::new(frames, -1);
+    }
+
+    pub fn tick(&mut self) {
+        self.left.tick();
+        self.right.tick();
+    }
+
+    pub fn frames_remaining(&self, attack_hand: &AttackHand) -> u32 {
+        match attack_hand {
+            AttackHand::Left => self.left.get_value() as u32,
+            AttackHand::Right => self.right.get_value() as u32,
+        }
+    }
+
+    pub fn is_cooling_down(&self, attack_hand: &AttackHand) -> bool {
+        self.frames_remaining(attack_hand) > 0
     }
 }
 
@@ -83,3 +332,12 @@ pub enum AttackHand {
     Left,
     Right,
 }
+
+impl AttackHand {
+    pub fn other(&self) -> Self {
+        match self {
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use bevy::prelude::{Component, Entity, Event, Resource, Vec3};
+
+pub const IMPACT_VFX_SECS: f32 = 0.4;
+pub const IMPACT_VFX_MAX_SCALE: f32 = 3.0;
+pub const IMPACT_DEBRIS_GRAVITY: f32 = 9.8;
+
+// Only the first few hits of a swing get an impact, so that sweeping
+// through a crowd does not flood the screen or freeze the swing
+pub const MAX_IMPACTS_PER_SWING: u32 = 3;
+
+pub const HIT_STOP_FRAMES: u32 = 3;
+pub const HIT_STOP_SPEED: f32 = 0.05;
+
+// Sent when one of the player's attacks lands, with roughly where it did
+#[derive(Debug, Event)]
+pub struct AttackImpact {
+    pub position: Vec3,
+    pub target: Entity,
+}
+
+// Counts the impacts of the current swing, along with the frames left of any hit-stop
+#[derive(Clone, Copy, Debug, Default, PartialEq, Resource)]
+pub struct SwingImpacts {
+    impacts: u32,
+    hit_stop_frames_left: u32,
+}
+
+impl SwingImpacts {
+    pub fn reset(&mut self) {
+        self.impacts = 0;
+    }
+
+    // Returns true if the impact is under the cap for this swing, and starts a hit-stop if so
+    pub fn register(&mut self) -> bool {
+        if self.impacts >= MAX_IMPACTS_PER_SWING {
+            return false;
+        }
+
+        self.impacts += 1;
+        self.hit_stop_frames_left = HIT_STOP_FRAMES;
+        true
+    }
+
+    // Playback speed of the attack for this frame
+    pub fn hit_stop_speed(&self) -> f32 {
+        if self.hit_stop_frames_left > 0 {
+            HIT_STOP_SPEED
+        } else {
+            1.0
+        }
+    }
+
+    pub fn tick_hit_stop(&mut self) {
+        self.hit_stop_frames_left = self.hit_stop_frames_left.saturating_sub(1);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ImpactVfxFrame {
+    pub scale: f32,
+    pub alpha: f32,
+}
+
+// Translucent sphere that expands and fades out from the point of an impact
+#[derive(Clone, Copy, Component, Debug, Default, PartialEq)]
+pub struct ImpactVfx {
+    age_secs: f32,
+}
+
+impl ImpactVfx {
+    // Returns None once the effect is over, and should be despawned
+    pub fn tick(&mut self, delta_secs: f32) -> Option<ImpactVfxFrame> {
+        self.age_secs += delta_secs;
+        if self.age_secs >= IMPACT_VFX_SECS {
+            return None;
+        }
+
+        let t = self.age_secs / IMPACT_VFX_SECS;
+        Some(ImpactVfxFrame {
+            scale: 1.0 + t * (IMPACT_VFX_MAX_SCALE - 1.0),
+            alpha: 1.0 - t,
+        })
+    }
+}
+
+// Small cuboid thrown out of an impact, which falls until the effect is over
+#[derive(Clone, Copy, Component, Debug, Default, PartialEq)]
+pub struct ImpactDebris {
+    pub velocity: Vec3,
+    age_secs: f32,
+}
+
+impl ImpactDebris {
+    pub fn new(velocity: Vec3) -> Self {
+        Self {
+            velocity,
+            age_secs: 0.0,
+        }
+    }
+
+    // Moves the translation along, and returns false once the debris should be despawned
+    pub fn tick(&mut self, delta_secs: f32, translation: &mut Vec3) -> bool {
+        self.age_secs += delta_secs;
+        if self.age_secs >= IMPACT_VFX_SECS {
+            return false;
+        }
+
+        self.velocity.y -= IMPACT_DEBRIS_GRAVITY * delta_secs;
+        *translation += self.velocity * delta_secs;
+        true
+    }
+}
@@ -0,0 +1,74 @@
+use bevy::prelude::{Component, Event};
+
+// Seconds a full tank of fuel lasts
+pub const LIGHT_FUEL_BURN_SECS: f32 = 600.0;
+// Below this fraction of its fuel, the light starts dimming
+pub const LIGHT_DIM_START: f32 = 0.25;
+// How bright the light still is once it runs out of fuel, so the player is never left in total darkness
+pub const LIGHT_MIN_BRIGHTNESS: f32 = 0.1;
+
+// Fuel of the player's spotlight, only burnt when the light fuel setting is on.
+// The full intensity and range are kept so that the light can be dimmed and restored.
+#[derive(Clone, Copy, Component, Debug, PartialEq)]
+pub struct LightFuel {
+    pub value: f32,
+    pub max_value: f32,
+    pub full_intensity: f32,
+    pub full_range: f32,
+}
+
+impl LightFuel {
+    pub fn new(full_intensity: f32, full_range: f32) -> Self {
+        Self {
+            value: 100.0,
+            max_value: 100.0,
+            full_intensity,
+            full_range,
+        }
+    }
+
+    pub fn fraction(&self) -> f32 {
+        if self.max_value <= 0.0 {
+            return 0.0;
+        }
+        (self.value / self.max_value).clamp(0.0, 1.0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.value <= 0.0
+    }
+
+    pub fn burn(&mut self, secs: f32) {
+        self.value = (self.value - secs * self.max_value / LIGHT_FUEL_BURN_SECS).max(0.0);
+    }
+
+    // Amount is a percentage of the max fuel
+    pub fn refuel(&mut self, percent: f32) {
+        self.value = (self.value + percent / 100.0 * self.max_value).min(self.max_value);
+    }
+
+    pub fn brightness(&self) -> f32 {
+        light_brightness(self.fraction())
+    }
+
+    pub fn intensity(&self) -> f32 {
+        self.full_intensity * self.brightness()
+    }
+
+    pub fn range(&self) -> f32 {
+        self.full_range * self.brightness()
+    }
+}
+
+// Full brightness down to LIGHT_DIM_START, then fading linearly towards LIGHT_MIN_BRIGHTNESS
+pub fn light_brightness(fraction: f32) -> f32 {
+    if fraction >= LIGHT_DIM_START {
+        return 1.0;
+    }
+    let t = fraction.max(0.0) / LIGHT_DIM_START;
+    LIGHT_MIN_BRIGHTNESS + (1.0 - LIGHT_MIN_BRIGHTNESS) * t
+}
+
+// Adds a percentage of the max fuel to the player's light
+#[derive(Event)]
+pub struct RefuelLight(pub f32);
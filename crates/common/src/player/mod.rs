@@ -1,34 +1,144 @@
 pub mod attack;
+pub mod impact;
+pub mod light;
+pub mod status_effect;
+pub mod swing_trail;
+pub mod throw;
 
-use crate::utils::{IncrCounter, _min_max_or_betw};
+#[cfg(test)]
+mod player_test;
+
+use crate::utils::{_min_max_or_betw, IncrCounter};
 use attack::{AttackHand, AttackType};
 use bevy::{
-    prelude::{Component, Entity, Event, States},
+    prelude::{Component, Entity, Event, Quat, ReflectComponent, Resource, States, Vec3},
     reflect::Reflect,
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, f32::consts::PI};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
+pub const FORWARD_SPEED_MULTIPLIER: f32 = 1.0;
+pub const STRAFE_SPEED_MULTIPLIER: f32 = 0.8;
+pub const BACKWARD_SPEED_MULTIPLIER: f32 = 0.6;
+
+// Regen, temp modifiers and anything else counted in ticks is ticked this many times a second,
+// no matter the frame rate. Durations in ticks were tuned at 60 fps, back when they ticked every frame.
+pub const PLAYER_TICK_HZ: f64 = 60.0;
+
 #[derive(Component)]
 pub struct Player;
 
+// Everything needed to spawn a player. The default is the player that a new game starts with.
+#[derive(Clone, Debug, PartialEq, Resource)]
+pub struct PlayerSpawnConfig {
+    pub collider_half_extents: Vec3,
+    pub max_health: f32,
+    pub health_regen: f32,
+    pub max_stamina: f32,
+    pub stamina_regen: f32,
+    pub walking_speed: f32,
+    pub sprinting_speed: f32,
+    pub spawn_position: Vec3,
+    pub gravity_scale: f32,
+}
+
+impl Default for PlayerSpawnConfig {
+    fn default() -> Self {
+        Self {
+            collider_half_extents: Vec3::new(0.4, 0.85, 0.4),
+            max_health: 100.0,
+            health_regen: 0.1,
+            max_stamina: 100.0,
+            stamina_regen: 1.0,
+            walking_speed: 200.0,
+            sprinting_speed: 400.0,
+            spawn_position: Vec3::new(2.0, 1.0, 2.0),
+            gravity_scale: 2.0,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq, States)]
 pub enum PlayerState {
     #[default]
     Walking,
     Sprinting,
     Attacking(AttackType, AttackHand),
+    // Both hands swinging the weapons in them at once
+    DualAttacking(AttackType),
 }
 
 impl PlayerState {
     pub fn is_ground_movement(&self) -> bool {
         *self == Self::Walking || *self == Self::Sprinting
     }
+
+    pub fn is_attacking(&self) -> bool {
+        matches!(self, Self::Attacking(..) | Self::DualAttacking(..))
+    }
+
+    pub fn is_attacking_with(&self, attack_hand: &AttackHand) -> bool {
+        match self {
+            Self::Attacking(_, h) => h == attack_hand,
+            Self::DualAttacking(_) => true,
+            Self::Walking | Self::Sprinting => false,
+        }
+    }
+
+    pub fn attack_type(&self) -> Option<AttackType> {
+        match self {
+            Self::Attacking(attack_type, _) | Self::DualAttacking(attack_type) => {
+                Some(*attack_type)
+            }
+            Self::Walking | Self::Sprinting => None,
+        }
+    }
 }
 
 #[derive(Debug, Event)]
-pub struct TakeDamage(pub Vec<(DmgType, f32)>, pub Entity);
+pub struct TakeDamage {
+    pub amounts: Vec<(DmgType, f32)>,
+    pub target: Entity,
+    // Whoever dealt the damage, if anyone did
+    pub source: Option<Entity>,
+}
+
+impl TakeDamage {
+    // Combined amount of every damage type that hurts health
+    pub fn total_health_dmg(&self) -> f32 {
+        self.amounts
+            .iter()
+            .filter(|(dmg_type, _)| *dmg_type != DmgType::Stamina)
+            .map(|(_, amt)| amt)
+            .sum()
+    }
+
+    // How strongly to show the player being hurt, from 0.0 to 1.0, where taking
+    // a quarter or more of max health at once is as strong as it gets
+    pub fn feedback_intensity(&self, max_health: f32) -> f32 {
+        if max_health <= 0.0 {
+            return 0.0;
+        }
+        (self.total_health_dmg() / max_health * 4.0).clamp(0.0, 1.0)
+    }
+}
+
+// Sent when damage takes a killable entity's health to zero
+#[derive(Debug, Event)]
+pub struct EntityKilled {
+    pub target: Entity,
+    pub source: Option<Entity>,
+}
+
+// Sent when TakeDamage lowers an entity's health, with the health it actually lost
+// once immunity, resists and the difficulty have been taken into account
+#[derive(Debug, Event)]
+pub struct DamageApplied {
+    pub target: Entity,
+    pub source: Option<Entity>,
+    pub amt: f32,
+}
 
 #[derive(Event)]
 pub struct HealHealth(pub f32, pub Entity);
@@ -39,11 +149,69 @@ pub struct HealStamina(pub f32, pub Entity);
 #[derive(Component, Reflect)]
 pub struct Speed(pub f32);
 
+// Radians per second, so that turning all the way around takes 0.15 seconds
+pub const SNAPPED_TURN_SPEED: f32 = PI / 0.15;
+
+// Rotation that the player turns toward over a few frames with snapped movement,
+// instead of facing the direction of movement straight away
+#[derive(Clone, Copy, Component, Debug, Default, PartialEq)]
+pub struct FacingTarget(Option<Quat>);
+
+impl FacingTarget {
+    pub fn get(&self) -> Option<Quat> {
+        self.0
+    }
+
+    pub fn set(&mut self, target: Quat) {
+        self.0 = Some(target);
+    }
+
+    pub fn clear(&mut self) {
+        self.0 = None;
+    }
+
+    // Turns a rotation toward the target at SNAPPED_TURN_SPEED, and lets go of the target
+    // once it is reached
+    pub fn turn(&mut self, rotation: Quat, delta_secs: f32) -> Quat {
+        let Some(target) = self.0 else {
+            return rotation;
+        };
+
+        let angle = rotation.angle_between(target);
+        let max_angle = SNAPPED_TURN_SPEED * delta_secs;
+        if angle <= max_angle {
+            self.0 = None;
+            return target;
+        }
+        rotation.slerp(target, max_angle / angle)
+    }
+}
+
+// Scales speed by the angle between the direction of movement and the direction being faced:
+// full speed when moving forward, blending to the strafe speed when moving sideways
+// and to the backward speed when moving backwards. Only the x and z axes are considered.
+pub fn calc_direction_speed_multiplier(direction: Vec3, forward: Vec3) -> f32 {
+    let direction = Vec3::new(direction.x, 0.0, direction.z).normalize_or_zero();
+    let forward = Vec3::new(forward.x, 0.0, forward.z).normalize_or_zero();
+
+    if direction == Vec3::ZERO || forward == Vec3::ZERO {
+        return FORWARD_SPEED_MULTIPLIER;
+    }
+
+    let cos = direction.dot(forward);
+    if cos >= 0.0 {
+        STRAFE_SPEED_MULTIPLIER + (FORWARD_SPEED_MULTIPLIER - STRAFE_SPEED_MULTIPLIER) * cos
+    } else {
+        STRAFE_SPEED_MULTIPLIER + (BACKWARD_SPEED_MULTIPLIER - STRAFE_SPEED_MULTIPLIER) * -cos
+    }
+}
+
 // TODO: create derive macro for Regenerator
 pub trait Regenerator {
     fn get_base_regen(&mut self) -> f32;
     fn get_static_modifiers(&mut self) -> &mut Vec<f32>;
     fn get_temp_modifiers(&mut self) -> &mut Vec<TempAmt>;
+    fn temp_modifiers(&self) -> &[TempAmt];
     fn do_regen(&mut self);
 
     fn _add_static_modifier(&mut self, amt: f32) {
@@ -87,6 +255,10 @@ macro_rules! regenerator_impl {
                 &mut self.temp_regen_modifiers
             }
 
+            fn temp_modifiers(&self) -> &[TempAmt] {
+                &self.temp_regen_modifiers
+            }
+
             fn do_regen(&mut self) {
                 self.value = _min_max_or_betw(0.0, self.max_value, self.value + self.get_regen());
             }
@@ -116,6 +288,7 @@ pub trait HealModifier {
     fn get_static_total(&self) -> f32;
     fn get_temp_total(&self) -> f32;
     fn get_total(&self) -> f32;
+    fn add_temp_modifier(&mut self, amt: f32, durr: u32);
     fn tick_temp_modifiers(&mut self);
 }
 
@@ -150,6 +323,10 @@ macro_rules! heal_modifier_impl {
                 self.get_base_total() + self.get_static_total() + self.get_temp_total()
             }
 
+            fn add_temp_modifier(&mut self, amt: f32, durr: u32) {
+                self.temp_modifiers.push(TempAmt::new(amt, durr));
+            }
+
             fn tick_temp_modifiers(&mut self) {
                 self.temp_modifiers.retain_mut(|tm| tm.tick() != 0);
             }
@@ -157,6 +334,7 @@ macro_rules! heal_modifier_impl {
     };
 }
 
+#[derive(Reflect)]
 pub struct HealHealthModifier {
     base_modifiers: Vec<f32>,
     static_modifiers: Vec<f32>,
@@ -165,6 +343,7 @@ pub struct HealHealthModifier {
 
 heal_modifier_impl!(HealHealthModifier);
 
+#[derive(Reflect)]
 pub struct HealStaminaModifier {
     base_modifiers: Vec<f32>,
     static_modifiers: Vec<f32>,
@@ -173,7 +352,8 @@ pub struct HealStaminaModifier {
 
 heal_modifier_impl!(HealStaminaModifier);
 
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct Health {
     pub value: f32,
     pub max_value: f32,
@@ -199,7 +379,8 @@ impl Health {
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct Stamina {
     pub value: f32,
     pub max_value: f32,
@@ -231,7 +412,8 @@ pub struct DmgTarget;
 #[derive(Component)]
 pub struct Killable;
 
-#[derive(Clone, Debug, EnumIter, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, EnumIter, Eq, Hash, PartialEq, Reflect)]
+#[reflect(Hash, PartialEq)]
 pub enum DmgType {
     Blunt,
     Slash,
@@ -242,11 +424,14 @@ pub enum DmgType {
     Stamina,
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct DmgResist {
     base_resists: HashMap<DmgType, Vec<f32>>,
     static_resists: HashMap<DmgType, Vec<f32>>,
     temp_resists: HashMap<DmgType, Vec<TempAmt>>,
+    // Set from the equipment as a whole, rather than added onto
+    equipment_resists: HashMap<DmgType, f32>,
 }
 
 impl Default for DmgResist {
@@ -273,6 +458,7 @@ impl DmgResist {
             base_resists: hm.clone(),
             static_resists: hm.clone(),
             temp_resists,
+            equipment_resists: HashMap::new(),
         }
     }
 
@@ -284,6 +470,13 @@ impl DmgResist {
         self.temp_resists.get_mut(dmg_type).unwrap().push(amt);
     }
 
+    pub fn set_equipment_resists(&mut self, resists: Vec<(DmgType, f32)>) {
+        self.equipment_resists.clear();
+        for (dmg_type, amt) in resists {
+            *self.equipment_resists.entry(dmg_type).or_insert(0.0) += amt;
+        }
+    }
+
     fn get_base_resist(&self, dmg_type: &DmgType) -> f32 {
         self.base_resists[&dmg_type]
             .iter()
@@ -302,10 +495,15 @@ impl DmgResist {
             .fold(0.0, |acc, curr| acc + curr.amt)
     }
 
+    fn get_equipment_resist(&self, dmg_type: &DmgType) -> f32 {
+        self.equipment_resists.get(dmg_type).copied().unwrap_or(0.0)
+    }
+
     pub fn get_resist(&self, dmg_type: &DmgType) -> f32 {
         self.get_base_resist(dmg_type)
             + self.get_static_resist(dmg_type)
             + self.get_temp_resist(dmg_type)
+            + self.get_equipment_resist(dmg_type)
     }
 
     pub fn tick_temp_resists(&mut self) {
@@ -321,20 +519,28 @@ pub struct DmgImmune {
 }
 
 impl DmgImmune {
-    fn _new(frames: Option<u32>) -> Self {
+    // Immune forever if frames is None
+    pub fn new(frames: Option<u32>) -> Self {
         Self {
             counter: frames.map(|f| IncrCounter::new(f as i32, -1)),
         }
     }
 
     pub fn tick(&mut self) {
-        self.counter.map(|mut c| c.tick());
+        if let Some(counter) = self.counter.as_mut() {
+            counter.tick();
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.counter.is_some_and(|c| c.get_value() == 0)
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Reflect)]
 pub struct TempAmt {
     amt: f32,
+    durr: u32,
     counter: IncrCounter,
 }
 
@@ -342,6 +548,7 @@ impl TempAmt {
     fn new(amt: f32, durr: u32) -> Self {
         Self {
             amt,
+            durr,
             counter: IncrCounter::new(durr as i32, -1),
         }
     }
@@ -349,4 +556,17 @@ impl TempAmt {
     fn tick(&mut self) -> i32 {
         self.counter.tick()
     }
+
+    pub fn amt(&self) -> f32 {
+        self.amt
+    }
+
+    // Total duration in ticks
+    pub fn durr(&self) -> u32 {
+        self.durr
+    }
+
+    pub fn remaining(&self) -> u32 {
+        self.counter.get_value().max(0) as u32
+    }
 }
@@ -0,0 +1,747 @@
+use crate::{
+    animation::{
+        CyclicAnimation, HitWindow, PlayerAnimation, PLAYER_ANIMATION_CLIPS, PLAYER_ANIMATION_FPS,
+    },
+    inventory::item::{Item, ItemName, ItemType},
+    player::{
+        attack::{
+            AttackChargeUp, AttackHand, AttackProgress, AttackRelease, AttackType, BufferedAttack,
+            WeaponSwapCooldown, BUFFERED_ATTACK_SECS,
+        },
+        calc_direction_speed_multiplier,
+        impact::{
+            ImpactDebris, ImpactVfx, SwingImpacts, HIT_STOP_FRAMES, HIT_STOP_SPEED,
+            IMPACT_VFX_SECS, MAX_IMPACTS_PER_SWING,
+        },
+        light::{light_brightness, LightFuel, LIGHT_DIM_START, LIGHT_MIN_BRIGHTNESS},
+        status_effect::{ActiveStatusEffects, StatusEffect},
+        swing_trail::{
+            build_swing_trail_ribbon, SwingTrail, SwingTrailSample, SWING_TRAIL_FADE_SECS,
+            SWING_TRAIL_MAX_SEGMENTS,
+        },
+        throw::{
+            throw_origin, throw_velocity, Thrown, THROWN_ITEM_LIFETIME_SECS,
+            THROWN_ITEM_REST_SPEED, THROW_LIFT_SPEED, THROW_SPAWN_DISTANCE, THROW_SPEED,
+        },
+        DmgImmune, DmgType, FacingTarget, Health, Regenerator, BACKWARD_SPEED_MULTIPLIER,
+        FORWARD_SPEED_MULTIPLIER, SNAPPED_TURN_SPEED, STRAFE_SPEED_MULTIPLIER,
+    },
+};
+use bevy::prelude::{GlobalTransform, Quat, Transform, Vec3};
+use std::f32::consts::FRAC_PI_2;
+use strum::IntoEnumIterator;
+
+#[test]
+fn test_status_effect_from_dmg() {
+    assert_eq!(
+        StatusEffect::from_dmg(&DmgType::Fire, 10.0),
+        Some(StatusEffect::Burning)
+    );
+    assert_eq!(
+        StatusEffect::from_dmg(&DmgType::Poison, 30.0),
+        Some(StatusEffect::Poisoned)
+    );
+    assert_eq!(
+        StatusEffect::from_dmg(&DmgType::Ice, 5.0),
+        Some(StatusEffect::Chilled)
+    );
+    assert_eq!(StatusEffect::from_dmg(&DmgType::Fire, 9.9), None);
+    assert_eq!(StatusEffect::from_dmg(&DmgType::Slash, 100.0), None);
+    assert_eq!(StatusEffect::from_dmg(&DmgType::Stamina, 100.0), None);
+}
+
+#[test]
+fn test_status_effect_dmg_per_frame_does_not_reapply() {
+    for status_effect in StatusEffect::iter() {
+        if let Some((dmg_type, amt)) = status_effect.dmg_per_frame() {
+            assert_eq!(StatusEffect::from_dmg(&dmg_type, amt), None);
+        }
+    }
+}
+
+#[test]
+fn test_active_status_effects_apply_and_refresh() {
+    let mut ase = ActiveStatusEffects::new();
+    let durr = StatusEffect::Poisoned.duration();
+
+    assert!(ase.apply(StatusEffect::Poisoned));
+    assert_eq!(ase.remaining(&StatusEffect::Poisoned), Some(durr));
+
+    ase.tick();
+    ase.tick();
+    assert_eq!(ase.remaining(&StatusEffect::Poisoned), Some(durr - 2));
+
+    // Re-applying refreshes the duration, but does not stack the magnitude
+    assert!(!ase.apply(StatusEffect::Poisoned));
+    assert_eq!(ase.remaining(&StatusEffect::Poisoned), Some(durr));
+    assert_eq!(
+        ase.heal_health_modifier(),
+        StatusEffect::Poisoned.heal_health_modifier()
+    );
+}
+
+#[test]
+fn test_active_status_effects_expiry() {
+    let mut ase = ActiveStatusEffects::new();
+    ase.apply(StatusEffect::Burning);
+
+    for _ in 0..StatusEffect::Burning.duration() {
+        assert!(ase.tick().is_empty());
+    }
+
+    assert_eq!(ase.tick(), vec![StatusEffect::Burning]);
+    assert!(!ase.contains(&StatusEffect::Burning));
+    assert!(ase.is_empty());
+}
+
+#[test]
+fn test_active_status_effects_chilled_speed_restore() {
+    let mut ase = ActiveStatusEffects::new();
+    assert_eq!(ase.speed_multiplier(), 1.0);
+
+    ase.apply(StatusEffect::Chilled);
+    ase.apply(StatusEffect::Chilled);
+    assert_eq!(
+        ase.speed_multiplier(),
+        StatusEffect::Chilled.speed_multiplier()
+    );
+
+    let mut expired = Vec::new();
+    while expired.is_empty() {
+        expired = ase.tick();
+    }
+
+    assert_eq!(expired, vec![StatusEffect::Chilled]);
+    assert_eq!(ase.speed_multiplier(), 1.0);
+}
+
+#[test]
+fn test_calc_direction_speed_multiplier() {
+    let forward = Vec3::NEG_Z;
+
+    for (direction, expected) in [
+        (Vec3::NEG_Z, FORWARD_SPEED_MULTIPLIER),
+        (Vec3::X, STRAFE_SPEED_MULTIPLIER),
+        (Vec3::NEG_X, STRAFE_SPEED_MULTIPLIER),
+        (Vec3::Z, BACKWARD_SPEED_MULTIPLIER),
+        // No movement
+        (Vec3::ZERO, FORWARD_SPEED_MULTIPLIER),
+        // Vertical components are ignored
+        (Vec3::new(0.0, 5.0, -1.0), FORWARD_SPEED_MULTIPLIER),
+        (Vec3::new(0.0, -5.0, 1.0), BACKWARD_SPEED_MULTIPLIER),
+    ] {
+        let multiplier = calc_direction_speed_multiplier(direction, forward);
+        assert!(
+            (multiplier - expected).abs() < 0.0001,
+            "direction {} expected {} but got {}",
+            direction,
+            expected,
+            multiplier,
+        );
+    }
+}
+
+#[test]
+fn test_calc_direction_speed_multiplier_diagonals() {
+    let forward = Vec3::new(0.0, -0.3, -1.0);
+
+    let forward_diagonal = calc_direction_speed_multiplier(Vec3::new(1.0, 0.0, -1.0), forward);
+    assert!(forward_diagonal > STRAFE_SPEED_MULTIPLIER);
+    assert!(forward_diagonal < FORWARD_SPEED_MULTIPLIER);
+
+    let backward_diagonal = calc_direction_speed_multiplier(Vec3::new(-1.0, 0.0, 1.0), forward);
+    assert!(backward_diagonal > BACKWARD_SPEED_MULTIPLIER);
+    assert!(backward_diagonal < STRAFE_SPEED_MULTIPLIER);
+}
+
+#[test]
+fn test_regenerator_temp_modifiers_remaining() {
+    let mut health = Health::new(50.0, 100.0, 0.0);
+    health.add_temp_modifier(1.0, 3);
+    health.add_temp_modifier(-0.5, 5);
+
+    let remaining: Vec<(f32, u32, u32)> = health
+        .temp_modifiers()
+        .iter()
+        .map(|tm| (tm.amt(), tm.remaining(), tm.durr()))
+        .collect();
+    assert_eq!(remaining, vec![(1.0, 3, 3), (-0.5, 5, 5)]);
+
+    // Modifiers are removed on the tick after their remaining ticks reach 0
+    for _ in 0..4 {
+        health.tick_temp_modifiers();
+    }
+
+    let remaining: Vec<(f32, u32)> = health
+        .temp_modifiers()
+        .iter()
+        .map(|tm| (tm.amt(), tm.remaining()))
+        .collect();
+    assert_eq!(remaining, vec![(-0.5, 1)]);
+}
+
+#[test]
+fn test_hit_window_boundaries() {
+    let hit_window = HitWindow::new(6, 12);
+
+    assert!(!hit_window.contains(5));
+    assert!(hit_window.contains(6));
+    assert!(hit_window.contains(11));
+    assert!(!hit_window.contains(12));
+}
+
+#[test]
+fn test_attack_progress_only_hits_inside_hit_window() {
+    let frame_secs = 1.0 / PLAYER_ANIMATION_FPS;
+
+    for animation in [
+        PlayerAnimation::Idle,
+        PlayerAnimation::UnarmedLeftLightAttack,
+        PlayerAnimation::UnarmedRightHeavyAttack,
+        PlayerAnimation::OneHandedSlashLeftLightAttack,
+        PlayerAnimation::OneHandedSlashRightHeavyAttack,
+    ] {
+        let mut attack_progress = AttackProgress::default();
+        let mut hit_frames = Vec::new();
+
+        for _ in 0..60 {
+            if attack_progress.is_in_hit_window(&animation) {
+                hit_frames.push(attack_progress.frame());
+            }
+            // Ticked slightly past each frame, so float error never lands on the previous one
+            attack_progress.tick(frame_secs * 1.001);
+        }
+
+        let expected: Vec<u32> = animation.hit_window().map_or(Vec::new(), |hit_window| {
+            (hit_window.start..hit_window.end).collect()
+        });
+        assert_eq!(hit_frames, expected, "{:?}", animation);
+    }
+}
+
+#[test]
+fn test_player_animation_clip_indices() {
+    let clip_count = PLAYER_ANIMATION_CLIPS.len();
+    assert_eq!(PlayerAnimation::iter().count(), clip_count);
+
+    let mut indices: Vec<usize> = PlayerAnimation::iter().map(|pa| pa.index()).collect();
+    assert!(indices.iter().all(|index| *index < clip_count));
+    indices.sort();
+    indices.dedup();
+    assert_eq!(indices.len(), clip_count);
+
+    assert!(PlayerAnimation::missing_clips(clip_count).is_empty());
+    for pa in PlayerAnimation::iter() {
+        assert_eq!(pa.clip_index(clip_count), pa.index());
+    }
+
+    // A glb with fewer clips falls back to the default animation for the missing ones
+    let clip_count = 8;
+    let missing = PlayerAnimation::missing_clips(clip_count);
+    assert!(!missing.is_empty());
+    for pa in PlayerAnimation::iter() {
+        assert!(pa.clip_index(clip_count) < clip_count);
+        if missing.contains(&pa) {
+            assert_eq!(
+                pa.clip_index(clip_count),
+                PlayerAnimation::default().index()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_every_attack_has_an_animation() {
+    let slots = ItemName::iter()
+        .map(|name| Some(Item::new(name, 1)))
+        .chain([None]);
+
+    for slot in slots {
+        let is_weapon = slot
+            .as_ref()
+            .is_some_and(|item| matches!(item.name.item_type(), ItemType::Weapon));
+
+        for attack_type in [AttackType::Light, AttackType::Heavy] {
+            for attack_hand in [AttackHand::Left, AttackHand::Right] {
+                let pa = PlayerAnimation::new_attack_animation(&attack_type, &attack_hand, &slot);
+                assert!(pa.is_attack_animation(), "{:?}", slot);
+                assert!(pa.attack_frames() > 0, "{:?}", pa);
+                assert!(
+                    pa.hit_window().unwrap().end <= pa.attack_frames(),
+                    "{:?}",
+                    pa
+                );
+
+                // Anything other than a weapon is swung with the same animation as an empty hand
+                let unarmed =
+                    PlayerAnimation::new_attack_animation(&attack_type, &attack_hand, &None);
+                assert_eq!(pa == unarmed, !is_weapon, "{:?}", slot);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_attack_progress_reset() {
+    let mut attack_progress = AttackProgress::default();
+    attack_progress.tick(1.0);
+    assert_eq!(attack_progress.frame(), PLAYER_ANIMATION_FPS as u32);
+
+    attack_progress.reset();
+    assert_eq!(attack_progress.frame(), 0);
+    assert!(!attack_progress.is_in_hit_window(&PlayerAnimation::UnarmedLeftLightAttack));
+}
+
+#[test]
+fn test_weapon_swap_cooldown() {
+    let mut cooldown = WeaponSwapCooldown::new(3);
+    assert!(!cooldown.is_cooling_down(&AttackHand::Left));
+    assert!(!cooldown.is_cooling_down(&AttackHand::Right));
+
+    cooldown.start(&AttackHand::Left);
+    for remaining in [3, 2, 1] {
+        assert_eq!(cooldown.frames_remaining(&AttackHand::Left), remaining);
+        assert!(cooldown.is_cooling_down(&AttackHand::Left));
+        assert!(!cooldown.is_cooling_down(&AttackHand::Right));
+        cooldown.tick();
+    }
+
+    assert!(!cooldown.is_cooling_down(&AttackHand::Left));
+
+    // Ticking past the end of the cooldown keeps it at 0
+    cooldown.tick();
+    assert_eq!(cooldown.frames_remaining(&AttackHand::Left), 0);
+}
+
+#[test]
+fn test_attack_charge_up_hands_charge_independently() {
+    let mut charge_up = AttackChargeUp::new(2, 3, 4);
+    charge_up.start(&AttackHand::Left);
+    charge_up.start(&AttackHand::Right);
+
+    // Only the left hand is held long enough for a heavy attack
+    for _ in 0..2 {
+        charge_up.tick(&AttackHand::Left);
+    }
+
+    assert_eq!(
+        charge_up.release(&AttackHand::Left, false),
+        Some(AttackRelease::Single(AttackType::Heavy, AttackHand::Left))
+    );
+    assert!(!charge_up.is_charging_hand(&AttackHand::Left));
+    assert!(charge_up.is_charging_hand(&AttackHand::Right));
+
+    assert_eq!(
+        charge_up.release(&AttackHand::Right, false),
+        Some(AttackRelease::Single(AttackType::Light, AttackHand::Right))
+    );
+    assert!(!charge_up.is_charging());
+
+    // Letting go of a hand that was never pressed does nothing
+    assert_eq!(charge_up.release(&AttackHand::Left, false), None);
+}
+
+#[test]
+fn test_attack_charge_up_dual_release_window() {
+    let mut charge_up = AttackChargeUp::new(2, 3, 4);
+    charge_up.start(&AttackHand::Left);
+    charge_up.start(&AttackHand::Right);
+
+    // The left hand waits on the right hand, which is let go just in time
+    assert_eq!(charge_up.release(&AttackHand::Left, true), None);
+    for _ in 0..3 {
+        assert_eq!(charge_up.tick_release_window(), None);
+    }
+    assert_eq!(
+        charge_up.release(&AttackHand::Right, true),
+        Some(AttackRelease::Dual(AttackType::Light))
+    );
+    assert!(!charge_up.is_charging());
+
+    // This time the right hand is held past the window, so the left hand attacks alone
+    charge_up.start(&AttackHand::Left);
+    charge_up.start(&AttackHand::Right);
+    assert_eq!(charge_up.release(&AttackHand::Left, true), None);
+    for _ in 0..3 {
+        assert_eq!(charge_up.tick_release_window(), None);
+    }
+    assert_eq!(
+        charge_up.tick_release_window(),
+        Some(AttackRelease::Single(AttackType::Light, AttackHand::Left))
+    );
+    assert!(charge_up.is_charging_hand(&AttackHand::Right));
+    assert_eq!(
+        charge_up.release(&AttackHand::Right, true),
+        Some(AttackRelease::Single(AttackType::Light, AttackHand::Right))
+    );
+}
+
+#[test]
+fn test_attack_charge_up_dual_attack_type() {
+    let mut charge_up = AttackChargeUp::new(2, 3, 4);
+
+    // Heavy only when both hands were charged up long enough
+    for (left_ticks, right_ticks, expected) in [
+        (2, 2, AttackType::Heavy),
+        (2, 0, AttackType::Light),
+        (0, 0, AttackType::Light),
+    ] {
+        charge_up.start(&AttackHand::Left);
+        charge_up.start(&AttackHand::Right);
+        for _ in 0..left_ticks {
+            charge_up.tick(&AttackHand::Left);
+        }
+        for _ in 0..right_ticks {
+            charge_up.tick(&AttackHand::Right);
+        }
+
+        assert_eq!(charge_up.release(&AttackHand::Right, true), None);
+        assert_eq!(
+            charge_up.release(&AttackHand::Left, true),
+            Some(AttackRelease::Dual(expected))
+        );
+    }
+}
+
+#[test]
+fn test_attack_charge_up_cancel() {
+    let mut charge_up = AttackChargeUp::new(2, 3, 4);
+
+    // Nothing to cancel
+    assert!(!charge_up.cancel(Some(AttackHand::Right)));
+    charge_up.start(&AttackHand::Right);
+    assert!(charge_up.is_charging_hand(&AttackHand::Right));
+
+    assert!(charge_up.cancel(None));
+    charge_up.start(&AttackHand::Left);
+    assert_eq!(charge_up.charge_fraction(), Some(0.0));
+    charge_up.tick(&AttackHand::Left);
+    assert_eq!(charge_up.charge_fraction(), Some(0.5));
+
+    // The cancelling hand cannot charge up until its button is let go
+    assert!(charge_up.cancel(Some(AttackHand::Right)));
+    assert!(!charge_up.is_charging());
+    assert_eq!(charge_up.charge_fraction(), None);
+    assert_eq!(charge_up.release(&AttackHand::Left, false), None);
+
+    charge_up.start(&AttackHand::Right);
+    assert!(!charge_up.is_charging());
+    charge_up.release_cancel(&AttackHand::Left);
+    charge_up.start(&AttackHand::Right);
+    assert!(!charge_up.is_charging());
+    charge_up.release_cancel(&AttackHand::Right);
+    charge_up.start(&AttackHand::Right);
+    assert!(charge_up.is_charging_hand(&AttackHand::Right));
+}
+
+#[test]
+fn test_buffered_attack_accepts_recent_press() {
+    let mut buffered_attack = BufferedAttack::default();
+    assert_eq!(buffered_attack.take(0.0), None);
+
+    buffered_attack.record(AttackHand::Left, 1.0);
+    buffered_attack.record(AttackHand::Right, 1.1);
+    assert!(buffered_attack.is_buffered());
+    assert_eq!(buffered_attack.take(1.3), Some(AttackHand::Right));
+
+    // Taken only once
+    assert!(!buffered_attack.is_buffered());
+    assert_eq!(buffered_attack.take(1.3), None);
+}
+
+#[test]
+fn test_buffered_attack_expires() {
+    let mut buffered_attack = BufferedAttack::default();
+
+    buffered_attack.record(AttackHand::Left, 1.0);
+    assert_eq!(buffered_attack.take(1.0 + BUFFERED_ATTACK_SECS * 2.0), None);
+
+    // An expired press is dropped, rather than kept around
+    assert!(!buffered_attack.is_buffered());
+
+    buffered_attack.record(AttackHand::Left, 2.0);
+    buffered_attack.clear();
+    assert_eq!(buffered_attack.take(2.0), None);
+}
+
+#[test]
+fn test_dmg_immune_expiry() {
+    let mut dmg_immune = DmgImmune::new(Some(2));
+    assert!(!dmg_immune.is_expired());
+
+    dmg_immune.tick();
+    assert!(!dmg_immune.is_expired());
+    dmg_immune.tick();
+    assert!(dmg_immune.is_expired());
+
+    // Immunity without a duration never runs out
+    let mut dmg_immune = DmgImmune::new(None);
+    for _ in 0..100 {
+        dmg_immune.tick();
+    }
+    assert!(!dmg_immune.is_expired());
+}
+
+#[test]
+fn test_light_brightness_curve() {
+    // Full brightness until the fuel runs low
+    for fraction in [1.0, 0.5, LIGHT_DIM_START] {
+        assert_eq!(light_brightness(fraction), 1.0);
+    }
+
+    // Then dims steadily down to the min
+    let mut prev = 1.0;
+    for fraction in [0.2, 0.15, 0.1, 0.05, 0.0] {
+        let brightness = light_brightness(fraction);
+        assert!(brightness < prev);
+        prev = brightness;
+    }
+    assert_eq!(light_brightness(0.0), LIGHT_MIN_BRIGHTNESS);
+    assert!(
+        (light_brightness(LIGHT_DIM_START / 2.0) - (1.0 + LIGHT_MIN_BRIGHTNESS) / 2.0).abs() < 1e-6
+    );
+}
+
+#[test]
+fn test_light_fuel_burn_and_refuel() {
+    let mut fuel = LightFuel::new(1000.0, 20.0);
+    assert_eq!(fuel.intensity(), 1000.0);
+    assert_eq!(fuel.range(), 20.0);
+
+    // Never burns below empty
+    fuel.burn(f32::MAX);
+    assert!(fuel.is_empty());
+    assert_eq!(fuel.intensity(), 1000.0 * LIGHT_MIN_BRIGHTNESS);
+    assert_eq!(fuel.range(), 20.0 * LIGHT_MIN_BRIGHTNESS);
+
+    fuel.refuel(50.0);
+    assert_eq!(fuel.fraction(), 0.5);
+    assert_eq!(fuel.intensity(), 1000.0);
+
+    // Never refuels past full
+    fuel.refuel(80.0);
+    assert_eq!(fuel.fraction(), 1.0);
+}
+
+#[test]
+fn test_only_light_fuel_items_are_used_up_besides_consumables() {
+    for item_name in ItemName::iter() {
+        let mut item = Item::new(item_name, 2);
+        let (output, was_mutated) = item._use();
+
+        let is_usable = matches!(item_name.item_type(), ItemType::Consumable)
+            || item_name.light_fuel().is_some();
+        assert_eq!(was_mutated, is_usable);
+        assert_eq!(output.is_some(), is_usable);
+    }
+    assert!(ItemName::Coal.light_fuel().is_some());
+}
+
+#[test]
+fn test_impact_vfx_expands_fades_and_expires() {
+    let mut vfx = ImpactVfx::default();
+
+    let first = vfx.tick(IMPACT_VFX_SECS * 0.25).unwrap();
+    let second = vfx.tick(IMPACT_VFX_SECS * 0.25).unwrap();
+    assert!(second.scale > first.scale);
+    assert!(second.alpha < first.alpha);
+
+    assert!(vfx.tick(IMPACT_VFX_SECS * 0.5).is_none());
+}
+
+#[test]
+fn test_impact_debris_falls_and_expires() {
+    let mut debris = ImpactDebris::new(Vec3::new(1.0, 0.0, 0.0));
+    let mut translation = Vec3::ZERO;
+
+    assert!(debris.tick(0.1, &mut translation));
+    assert!(translation.x > 0.0);
+    assert!(translation.y < 0.0);
+
+    assert!(!debris.tick(IMPACT_VFX_SECS, &mut translation));
+}
+
+#[test]
+fn test_swing_impacts_cap_and_hit_stop() {
+    let mut swing_impacts = SwingImpacts::default();
+    assert_eq!(swing_impacts.hit_stop_speed(), 1.0);
+
+    for _ in 0..MAX_IMPACTS_PER_SWING {
+        assert!(swing_impacts.register());
+    }
+    assert!(!swing_impacts.register());
+
+    // The hit-stop lasts a few frames, then the swing goes on at full speed
+    for _ in 0..HIT_STOP_FRAMES {
+        assert_eq!(swing_impacts.hit_stop_speed(), HIT_STOP_SPEED);
+        swing_impacts.tick_hit_stop();
+    }
+    assert_eq!(swing_impacts.hit_stop_speed(), 1.0);
+
+    // The next swing starts counting again
+    swing_impacts.reset();
+    assert!(swing_impacts.register());
+}
+
+#[test]
+fn test_cyclic_animation_new_at() {
+    let cyclic_animation = CyclicAnimation::new(3, 5);
+    assert_eq!(cyclic_animation.value(), 3);
+    assert_eq!(cyclic_animation.rest_clip(), None);
+
+    // Starting at the first clip is the same as starting fresh
+    assert_eq!(CyclicAnimation::new_at(3, 5, 3).rest_clip(), None);
+
+    let mut cyclic_animation = CyclicAnimation::new_at(3, 5, 4);
+    assert_eq!(cyclic_animation.value(), 4);
+    assert_eq!(cyclic_animation.rest_clip(), Some(3));
+    assert_eq!(cyclic_animation.cycle(), 4);
+    assert_eq!(cyclic_animation.cycle(), 5);
+    assert_eq!(cyclic_animation.cycle(), 3);
+
+    // Out of range values are clamped
+    let cyclic_animation = CyclicAnimation::new_at(3, 5, 9);
+    assert_eq!(cyclic_animation.value(), 5);
+    assert_eq!(cyclic_animation.rest_clip(), Some(4));
+}
+
+fn swing_trail_sample(x: f32) -> SwingTrailSample {
+    SwingTrailSample {
+        base: Vec3::new(x, 0.0, 0.0),
+        tip: Vec3::new(x, 1.0, 0.0),
+        age_secs: 0.0,
+    }
+}
+
+#[test]
+fn test_build_swing_trail_ribbon_segments() {
+    let samples: Vec<_> = (0..4).map(|i| swing_trail_sample(i as f32)).collect();
+    let ribbon = build_swing_trail_ribbon(&samples);
+
+    assert_eq!(ribbon.segments(), 3);
+    assert_eq!(ribbon.positions.len(), 8);
+    assert_eq!(ribbon.colors.len(), 8);
+    assert_eq!(ribbon.indices.len(), 18);
+    assert!(ribbon
+        .indices
+        .iter()
+        .all(|i| (*i as usize) < ribbon.positions.len()));
+
+    // Fades out toward the oldest end
+    assert_eq!(ribbon.colors[0][3], 0.0);
+    assert_eq!(ribbon.colors[7][3], 1.0);
+}
+
+#[test]
+fn test_build_swing_trail_ribbon_degenerate() {
+    assert!(build_swing_trail_ribbon(&[]).is_empty());
+    assert!(build_swing_trail_ribbon(&[swing_trail_sample(0.0)]).is_empty());
+
+    // No movement between frames adds no segments
+    let still = [swing_trail_sample(0.0); 5];
+    assert!(build_swing_trail_ribbon(&still).is_empty());
+
+    let samples = [
+        swing_trail_sample(0.0),
+        swing_trail_sample(0.0),
+        swing_trail_sample(1.0),
+        swing_trail_sample(1.0),
+    ];
+    let ribbon = build_swing_trail_ribbon(&samples);
+    assert_eq!(ribbon.segments(), 1);
+    assert_eq!(ribbon.positions.len(), 4);
+}
+
+#[test]
+fn test_swing_trail_bounded_and_fades() {
+    let mut swing_trail = SwingTrail::default();
+    for i in 0..100 {
+        swing_trail.push(Vec3::new(i as f32, 0.0, 0.0), Vec3::new(i as f32, 1.0, 0.0));
+    }
+
+    let samples: Vec<_> = swing_trail.samples().copied().collect();
+    assert_eq!(samples.len(), SWING_TRAIL_MAX_SEGMENTS + 1);
+    assert_eq!(
+        build_swing_trail_ribbon(&samples).segments(),
+        SWING_TRAIL_MAX_SEGMENTS
+    );
+
+    // Aged samples fade, then are dropped
+    swing_trail.tick(SWING_TRAIL_FADE_SECS / 2.0);
+    let samples: Vec<_> = swing_trail.samples().copied().collect();
+    let ribbon = build_swing_trail_ribbon(&samples);
+    assert_eq!(ribbon.colors.last().unwrap()[3], 0.5);
+
+    swing_trail.tick(SWING_TRAIL_FADE_SECS);
+    assert!(swing_trail.is_empty());
+}
+
+#[test]
+fn test_throw_velocity_follows_camera_aim() {
+    let camera_gt = GlobalTransform::from(
+        Transform::from_xyz(1.0, 2.0, 3.0).looking_at(Vec3::new(1.0, 2.0, -10.0), Vec3::Y),
+    );
+
+    let velocity = throw_velocity(&camera_gt);
+    assert!((velocity - Vec3::new(0.0, THROW_LIFT_SPEED, -THROW_SPEED)).length() < 0.001);
+
+    let origin = throw_origin(&camera_gt);
+    assert!((origin - Vec3::new(1.0, 2.0, 3.0 - THROW_SPAWN_DISTANCE)).length() < 0.001);
+
+    // Aiming up throws higher, and less far
+    let up_gt =
+        GlobalTransform::from(Transform::default().looking_at(Vec3::new(0.0, 1.0, -1.0), Vec3::Y));
+    let up_velocity = throw_velocity(&up_gt);
+    assert!(up_velocity.y > velocity.y);
+    assert!(up_velocity.z > velocity.z);
+}
+
+#[test]
+fn test_thrown_item_expires() {
+    let mut thrown = Thrown::default();
+
+    assert!(thrown.tick(THROWN_ITEM_LIFETIME_SECS * 0.5));
+    assert!(thrown.tick(THROWN_ITEM_LIFETIME_SECS * 0.25));
+    assert!(!thrown.tick(THROWN_ITEM_LIFETIME_SECS * 0.25));
+}
+
+#[test]
+fn test_thrown_item_lands_once_and_rests() {
+    let mut thrown = Thrown::default();
+
+    // Slow in the air is not at rest, like at the top of an arc
+    assert!(!thrown.is_at_rest(0.0));
+
+    assert!(thrown.land());
+    assert!(!thrown.land());
+    assert!(thrown.has_landed());
+
+    assert!(!thrown.is_at_rest(THROWN_ITEM_REST_SPEED * 2.0));
+    assert!(thrown.is_at_rest(THROWN_ITEM_REST_SPEED * 0.5));
+}
+
+#[test]
+fn test_facing_target_turns_gradually() {
+    let mut facing_target = FacingTarget::default();
+    let start = Quat::IDENTITY;
+    assert_eq!(facing_target.turn(start, 1.0), start);
+
+    // A quarter turn takes a few frames
+    let target = Quat::from_rotation_y(FRAC_PI_2);
+    facing_target.set(target);
+    let delta_secs = FRAC_PI_2 / SNAPPED_TURN_SPEED / 3.0;
+
+    let rotation = facing_target.turn(start, delta_secs);
+    assert!((rotation.angle_between(target) - FRAC_PI_2 * 2.0 / 3.0).abs() < 0.001);
+    assert_eq!(facing_target.get(), Some(target));
+
+    let rotation = facing_target.turn(rotation, delta_secs);
+    let rotation = facing_target.turn(rotation, delta_secs * 1.01);
+    assert_eq!(rotation, target);
+    assert_eq!(facing_target.get(), None);
+
+    // Cleared targets are let go of straight away
+    facing_target.set(start);
+    facing_target.clear();
+    assert_eq!(facing_target.turn(target, delta_secs), target);
+}
@@ -0,0 +1,140 @@
+use crate::{player::DmgType, utils::IncrCounter};
+use bevy::prelude::Component;
+use std::collections::HashMap;
+use strum_macros::{Display, EnumIter};
+
+#[derive(Clone, Copy, Debug, Display, EnumIter, Eq, Hash, PartialEq)]
+pub enum StatusEffect {
+    Burning,
+    Poisoned,
+    Chilled,
+}
+
+impl StatusEffect {
+    pub fn from_dmg(dmg_type: &DmgType, amt: f32) -> Option<Self> {
+        let status_effect = match dmg_type {
+            DmgType::Fire => Self::Burning,
+            DmgType::Poison => Self::Poisoned,
+            DmgType::Ice => Self::Chilled,
+            _ => return None,
+        };
+
+        if amt >= status_effect.dmg_threshold() {
+            Some(status_effect)
+        } else {
+            None
+        }
+    }
+
+    pub fn display_key(&self) -> &'static str {
+        match self {
+            Self::Burning => "status_effect.burning",
+            Self::Poisoned => "status_effect.poisoned",
+            Self::Chilled => "status_effect.chilled",
+        }
+    }
+
+    // Damage at or above this amount applies the status effect.
+    // Must stay above dmg_per_frame() so damage over time does not re-apply itself.
+    pub fn dmg_threshold(&self) -> f32 {
+        match self {
+            Self::Burning => 10.0,
+            Self::Poisoned => 5.0,
+            Self::Chilled => 5.0,
+        }
+    }
+
+    // Duration in frames
+    pub fn duration(&self) -> u32 {
+        match self {
+            Self::Burning => 180,
+            Self::Poisoned => 600,
+            Self::Chilled => 240,
+        }
+    }
+
+    pub fn dmg_per_frame(&self) -> Option<(DmgType, f32)> {
+        match self {
+            Self::Burning => Some((DmgType::Fire, 0.1)),
+            Self::Poisoned => Some((DmgType::Poison, 0.02)),
+            Self::Chilled => None,
+        }
+    }
+
+    pub fn heal_health_modifier(&self) -> f32 {
+        match self {
+            Self::Poisoned => -10.0,
+            Self::Burning | Self::Chilled => 0.0,
+        }
+    }
+
+    pub fn speed_multiplier(&self) -> f32 {
+        match self {
+            Self::Chilled => 0.5,
+            Self::Burning | Self::Poisoned => 1.0,
+        }
+    }
+}
+
+#[derive(Component, Default)]
+pub struct ActiveStatusEffects(HashMap<StatusEffect, IncrCounter>);
+
+impl ActiveStatusEffects {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Re-applying an active status effect refreshes its duration without stacking its magnitude.
+    // Returns true if the status effect was not already active.
+    pub fn apply(&mut self, status_effect: StatusEffect) -> bool {
+        self.0
+            .insert(
+                status_effect,
+                IncrCounter::new(status_effect.duration() as i32, -1),
+            )
+            .is_none()
+    }
+
+    // Returns the status effects that expired during this tick
+    pub fn tick(&mut self) -> Vec<StatusEffect> {
+        let mut expired = Vec::new();
+
+        self.0.retain(|status_effect, counter| {
+            if counter.tick() == 0 {
+                expired.push(*status_effect);
+                return false;
+            }
+            true
+        });
+
+        expired
+    }
+
+    pub fn contains(&self, status_effect: &StatusEffect) -> bool {
+        self.0.contains_key(status_effect)
+    }
+
+    pub fn remaining(&self, status_effect: &StatusEffect) -> Option<u32> {
+        self.0
+            .get(status_effect)
+            .map(|counter| counter.get_value() as u32)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StatusEffect> {
+        self.0.keys()
+    }
+
+    pub fn heal_health_modifier(&self) -> f32 {
+        self.iter()
+            .fold(0.0, |acc, curr| acc + curr.heal_health_modifier())
+    }
+
+    pub fn speed_multiplier(&self) -> f32 {
+        self.iter()
+            .fold(1.0, |acc, curr| acc * curr.speed_multiplier())
+    }
+}
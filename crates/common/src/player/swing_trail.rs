@@ -0,0 +1,142 @@
+use crate::player::DmgType;
+use bevy::prelude::{Color, Component, Vec3};
+use std::collections::VecDeque;
+
+// Segments of the ribbon, between this many plus one samples. Older samples are dropped.
+pub const SWING_TRAIL_MAX_SEGMENTS: usize = 32;
+pub const SWING_TRAIL_FADE_SECS: f32 = 0.25;
+// Distance from the base of a weapon to its tip, along the up axis of its model
+pub const SWING_TRAIL_WEAPON_LENGTH: f32 = 1.0;
+// Samples closer than this to the previous one, at both the base and the tip, add no segment
+pub const SWING_TRAIL_MIN_MOVEMENT: f32 = 0.001;
+
+pub fn swing_trail_color(dmg_type: &DmgType) -> Color {
+    match dmg_type {
+        DmgType::Blunt => Color::srgb(0.8, 0.7, 0.5),
+        DmgType::Slash => Color::srgb(0.85, 0.9, 1.0),
+        DmgType::Pierce => Color::srgb(0.9, 0.9, 0.7),
+        DmgType::Fire => Color::srgb(1.0, 0.5, 0.1),
+        DmgType::Ice => Color::srgb(0.5, 0.8, 1.0),
+        DmgType::Poison => Color::srgb(0.4, 0.9, 0.3),
+        DmgType::Stamina => Color::srgb(0.9, 0.8, 0.2),
+    }
+}
+
+// Child of an equipped weapon, holding the mesh of its swing trail
+#[derive(Component)]
+pub struct SwingTrailMesh;
+
+// Where the base and the tip of a weapon were, some time ago
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SwingTrailSample {
+    pub base: Vec3,
+    pub tip: Vec3,
+    pub age_secs: f32,
+}
+
+impl SwingTrailSample {
+    fn moved_from(&self, other: &Self) -> bool {
+        self.base.distance(other.base) >= SWING_TRAIL_MIN_MOVEMENT
+            || self.tip.distance(other.tip) >= SWING_TRAIL_MIN_MOVEMENT
+    }
+}
+
+// Ring buffer of where an equipped weapon has been during the current attack
+#[derive(Clone, Component, Debug, Default, PartialEq)]
+pub struct SwingTrail {
+    samples: VecDeque<SwingTrailSample>,
+}
+
+impl SwingTrail {
+    pub fn push(&mut self, base: Vec3, tip: Vec3) {
+        if self.samples.len() > SWING_TRAIL_MAX_SEGMENTS {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(SwingTrailSample {
+            base,
+            tip,
+            age_secs: 0.0,
+        });
+    }
+
+    // Ages every sample, and drops the ones that have faded out
+    pub fn tick(&mut self, delta_secs: f32) {
+        for sample in self.samples.iter_mut() {
+            sample.age_secs += delta_secs;
+        }
+        self.samples
+            .retain(|sample| sample.age_secs < SWING_TRAIL_FADE_SECS);
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    // Oldest first
+    pub fn samples(&self) -> impl Iterator<Item = &SwingTrailSample> {
+        self.samples.iter()
+    }
+}
+
+// Mesh attributes of a swing trail, as a triangle list
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SwingTrailRibbon {
+    pub positions: Vec<[f32; 3]>,
+    // White, so that the color comes from the material, and only the alpha fades
+    pub colors: Vec<[f32; 4]>,
+    pub indices: Vec<u32>,
+}
+
+impl SwingTrailRibbon {
+    pub fn segments(&self) -> usize {
+        self.indices.len() / 6
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+// Extrudes a ribbon between the base and the tip of each sample, oldest first, with two
+// triangles for each segment between samples. The ribbon fades out toward its oldest end,
+// and as the samples age. Samples that did not move from the one before are skipped.
+pub fn build_swing_trail_ribbon(samples: &[SwingTrailSample]) -> SwingTrailRibbon {
+    let mut kept: Vec<&SwingTrailSample> = Vec::with_capacity(samples.len());
+    for sample in samples {
+        if kept.last().is_none_or(|last| sample.moved_from(last)) {
+            kept.push(sample);
+        }
+    }
+
+    if kept.len() < 2 {
+        return SwingTrailRibbon::default();
+    }
+
+    let mut ribbon = SwingTrailRibbon::default();
+    let last = (kept.len() - 1) as f32;
+
+    for (i, sample) in kept.iter().enumerate() {
+        let along = i as f32 / last;
+        let fade = (1.0 - sample.age_secs / SWING_TRAIL_FADE_SECS).clamp(0.0, 1.0);
+        let color = [1.0, 1.0, 1.0, along * fade];
+
+        ribbon.positions.push(sample.base.to_array());
+        ribbon.positions.push(sample.tip.to_array());
+        ribbon.colors.push(color);
+        ribbon.colors.push(color);
+
+        if i > 0 {
+            let (prev_base, prev_tip) = (2 * (i as u32 - 1), 2 * (i as u32 - 1) + 1);
+            let (base, tip) = (2 * i as u32, 2 * i as u32 + 1);
+            ribbon
+                .indices
+                .extend([prev_base, prev_tip, tip, prev_base, tip, base]);
+        }
+    }
+
+    ribbon
+}
@@ -0,0 +1,56 @@
+use bevy::prelude::{Component, Entity, Event, GlobalTransform, Vec3};
+
+pub const THROW_SPEED: f32 = 10.0;
+// Added on top of the aim, so that a throw arcs a little instead of dipping right away
+pub const THROW_LIFT_SPEED: f32 = 1.5;
+// Thrown items start this far in front of the camera, to keep clear of the player's collider
+pub const THROW_SPAWN_DISTANCE: f32 = 0.6;
+pub const THROWN_ITEM_DMG: f32 = 2.0;
+pub const THROWN_ITEM_LIFETIME_SECS: f32 = 10.0;
+// Thrown items that landed and slowed down below this are considered at rest
+pub const THROWN_ITEM_REST_SPEED: f32 = 0.05;
+
+// Initial velocity of an item thrown along the aim of a camera
+pub fn throw_velocity(camera_gt: &GlobalTransform) -> Vec3 {
+    camera_gt.forward() * THROW_SPEED + Vec3::Y * THROW_LIFT_SPEED
+}
+
+// Where an item thrown along the aim of a camera starts out
+pub fn throw_origin(camera_gt: &GlobalTransform) -> Vec3 {
+    camera_gt.translation() + camera_gt.forward() * THROW_SPAWN_DISTANCE
+}
+
+// An item in flight, or lying where it landed until it is despawned
+#[derive(Clone, Copy, Component, Debug, Default, PartialEq)]
+pub struct Thrown {
+    age_secs: f32,
+    landed: bool,
+}
+
+impl Thrown {
+    // Returns false once the item has been around for long enough, and should be despawned
+    pub fn tick(&mut self, delta_secs: f32) -> bool {
+        self.age_secs += delta_secs;
+        self.age_secs < THROWN_ITEM_LIFETIME_SECS
+    }
+
+    // Returns true only the first time, so that an item makes its noise once
+    pub fn land(&mut self) -> bool {
+        !std::mem::replace(&mut self.landed, true)
+    }
+
+    pub fn has_landed(&self) -> bool {
+        self.landed
+    }
+
+    pub fn is_at_rest(&self, speed: f32) -> bool {
+        self.landed && speed < THROWN_ITEM_REST_SPEED
+    }
+}
+
+// Sent wherever something makes a noise that could draw attention
+#[derive(Debug, Event)]
+pub struct NoiseEvent {
+    pub position: Vec3,
+    pub source: Option<Entity>,
+}
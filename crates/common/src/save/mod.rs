@@ -0,0 +1,129 @@
+#[cfg(test)]
+mod save_test;
+
+use crate::{
+    automap::{RevealedCells, VisitedCells},
+    death_marker::DeathMarkers,
+    hud::HudLayout,
+    inventory::{item::SavedItem, SavedInventory},
+    settings::GameSettings,
+    stats::PlayerStats,
+    world::{data::WorldData, WorldConfig, WorldSeed},
+};
+use bevy::prelude::{Event, Resource};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// Saves asked for within this long of the last one are dropped
+pub const SAVE_DEBOUNCE: Duration = Duration::from_secs(2);
+
+#[derive(Default, Deserialize, Serialize)]
+pub struct GameSave {
+    pub game_settings: GameSettings,
+    pub inventory: SavedInventory,
+    pub world_data: WorldData,
+    pub visited_cells: VisitedCells,
+    pub revealed_cells: RevealedCells,
+    pub world_seed: WorldSeed,
+    pub world_config: WorldConfig,
+    pub hud_layout: HudLayout,
+    pub player_stats: PlayerStats,
+    pub death_markers: DeathMarkers,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub struct GameSaveRead {
+    pub game_settings: Option<GameSettings>,
+    pub inventory: Option<SavedInventory>,
+    pub world_data: Option<WorldData>,
+    pub visited_cells: Option<VisitedCells>,
+    pub revealed_cells: Option<RevealedCells>,
+    pub world_seed: Option<WorldSeed>,
+    pub world_config: Option<WorldConfig>,
+    pub hud_layout: Option<HudLayout>,
+    pub player_stats: Option<PlayerStats>,
+    pub death_markers: Option<DeathMarkers>,
+}
+
+#[derive(Event)]
+pub struct WorldDataChanged;
+
+// Asks for the game to be saved. Every save goes through here, so that they are debounced
+// together. Ordered by which one is kept when several are waiting on the debounce.
+#[derive(Clone, Copy, Debug, Eq, Event, Ord, PartialEq, PartialOrd)]
+pub enum SaveRequested {
+    // Something that is saved changed, which is saved without a popup
+    Changed,
+    Auto,
+    Manual,
+}
+
+impl SaveRequested {
+    pub fn popup_key(&self) -> Option<&'static str> {
+        match self {
+            Self::Changed => None,
+            Self::Auto => Some("popup.autosaved"),
+            Self::Manual => Some("popup.game_saved"),
+        }
+    }
+}
+
+// Keeps saves at least SAVE_DEBOUNCE apart. Requests that come in too soon after the last save
+// wait until then, so that nothing that changed in between is lost.
+#[derive(Debug, Default, Resource)]
+pub struct SaveDebounce {
+    last_save: Option<Duration>,
+    pending: Option<SaveRequested>,
+}
+
+impl SaveDebounce {
+    pub fn request(&mut self, save_requested: SaveRequested) {
+        self.pending = self.pending.max(Some(save_requested));
+    }
+
+    // The request to save at this time, if there is one and it is no longer held back by the
+    // last save, which it then becomes
+    pub fn take_due(&mut self, now: Duration) -> Option<SaveRequested> {
+        if self
+            .last_save
+            .is_some_and(|last_save| now.saturating_sub(last_save) < SAVE_DEBOUNCE)
+        {
+            return None;
+        }
+        let save_requested = self.pending.take()?;
+        self.last_save = Some(now);
+        Some(save_requested)
+    }
+}
+
+// Time played since the last autosave
+#[derive(Debug, Default, Resource)]
+pub struct AutosaveTimer {
+    elapsed: Duration,
+}
+
+impl AutosaveTimer {
+    // Whether an autosave is due. Once the interval is up it waits for as long as it is
+    // held off, and is due the first time it no longer is.
+    pub fn tick(&mut self, delta: Duration, interval: Duration, held_off: bool) -> bool {
+        self.elapsed += delta;
+        if held_off || self.elapsed < interval {
+            return false;
+        }
+        self.elapsed = Duration::ZERO;
+        true
+    }
+}
+
+// Anything from the save that could not be restored when it was loaded,
+// to let the player know once the game starts
+#[derive(Debug, Default, Resource)]
+pub struct SaveLoadReport {
+    pub unrestored_items: Vec<SavedItem>,
+}
+
+impl SaveLoadReport {
+    pub fn is_empty(&self) -> bool {
+        self.unrestored_items.is_empty()
+    }
+}
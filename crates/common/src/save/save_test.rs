@@ -0,0 +1,107 @@
+use crate::{
+    save::{AutosaveTimer, SaveDebounce, SaveRequested, SAVE_DEBOUNCE},
+    settings::AutosaveMinutes,
+};
+use std::time::Duration;
+
+const INTERVAL: Duration = Duration::from_secs(120);
+const FRAME: Duration = Duration::from_millis(500);
+
+#[test]
+fn test_saves_are_not_repeated_within_the_debounce() {
+    let mut save_debounce = SaveDebounce::default();
+    let start = Duration::from_secs(30);
+    assert_eq!(save_debounce.take_due(start), None);
+
+    save_debounce.request(SaveRequested::Changed);
+    assert_eq!(save_debounce.take_due(start), Some(SaveRequested::Changed));
+    assert_eq!(save_debounce.take_due(start + FRAME), None);
+
+    // Held back until the debounce is over, rather than dropped
+    save_debounce.request(SaveRequested::Changed);
+    assert_eq!(save_debounce.take_due(start + FRAME), None);
+    assert_eq!(save_debounce.take_due(start + SAVE_DEBOUNCE - FRAME), None);
+    assert_eq!(
+        save_debounce.take_due(start + SAVE_DEBOUNCE),
+        Some(SaveRequested::Changed)
+    );
+
+    // Counted from the last save that went through, not the last one asked for
+    save_debounce.request(SaveRequested::Manual);
+    assert_eq!(save_debounce.take_due(start + SAVE_DEBOUNCE + FRAME), None);
+}
+
+#[test]
+fn test_waiting_saves_are_saved_once_with_a_popup_if_any_has_one() {
+    let mut save_debounce = SaveDebounce::default();
+    save_debounce.request(SaveRequested::Changed);
+    save_debounce.take_due(Duration::ZERO);
+
+    save_debounce.request(SaveRequested::Auto);
+    save_debounce.request(SaveRequested::Changed);
+    save_debounce.request(SaveRequested::Manual);
+    save_debounce.request(SaveRequested::Changed);
+    assert_eq!(
+        save_debounce.take_due(SAVE_DEBOUNCE),
+        Some(SaveRequested::Manual)
+    );
+    assert_eq!(save_debounce.take_due(SAVE_DEBOUNCE * 2), None);
+    assert_eq!(SaveRequested::Changed.popup_key(), None);
+}
+
+#[test]
+fn test_first_save_is_never_debounced() {
+    let mut save_debounce = SaveDebounce::default();
+    save_debounce.request(SaveRequested::Auto);
+    assert_eq!(
+        save_debounce.take_due(Duration::ZERO),
+        Some(SaveRequested::Auto)
+    );
+}
+
+#[test]
+fn test_autosave_is_due_once_per_interval() {
+    let mut autosave_timer = AutosaveTimer::default();
+    let frames = (INTERVAL.as_millis() / FRAME.as_millis()) as usize;
+
+    for _ in 1..frames {
+        assert!(!autosave_timer.tick(FRAME, INTERVAL, false));
+    }
+    assert!(autosave_timer.tick(FRAME, INTERVAL, false));
+
+    // Starts over after each one
+    assert!(!autosave_timer.tick(FRAME, INTERVAL, false));
+    assert!(!autosave_timer.tick(INTERVAL - FRAME * 2, INTERVAL, false));
+    assert!(autosave_timer.tick(FRAME, INTERVAL, false));
+}
+
+#[test]
+fn test_held_off_autosave_is_deferred_until_it_no_longer_is() {
+    let mut autosave_timer = AutosaveTimer::default();
+    assert!(!autosave_timer.tick(INTERVAL - FRAME, INTERVAL, true));
+    assert!(!autosave_timer.tick(FRAME, INTERVAL, true));
+    assert!(!autosave_timer.tick(INTERVAL * 3, INTERVAL, true));
+
+    assert!(autosave_timer.tick(FRAME, INTERVAL, false));
+    // Only once, rather than once for every interval it was held off for
+    assert!(!autosave_timer.tick(FRAME, INTERVAL, false));
+}
+
+#[test]
+fn test_autosave_interval_change_applies_right_away() {
+    let mut autosave_timer = AutosaveTimer::default();
+    assert!(!autosave_timer.tick(INTERVAL / 2, INTERVAL, false));
+    assert!(autosave_timer.tick(FRAME, INTERVAL / 4, false));
+}
+
+#[test]
+fn test_autosave_interval_defaults_to_two_minutes() {
+    assert_eq!(
+        AutosaveMinutes::default().interval(),
+        Duration::from_secs(120)
+    );
+    assert_eq!(
+        AutosaveMinutes::new(0.0).interval(),
+        Duration::from_secs_f32(AutosaveMinutes::MIN * 60.0)
+    );
+}
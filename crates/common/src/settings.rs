@@ -1,19 +1,420 @@
-use bevy::prelude::{Event, States};
+use crate::localization::Language;
+use bevy::{
+    prelude::{Event, States},
+    reflect::Reflect,
+};
 use serde::{Deserialize, Serialize};
+use std::{
+    hash::{Hash, Hasher},
+    time::Duration,
+};
 
-#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize, States)]
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Reflect, Serialize, States,
+)]
 pub struct GameSettings {
     pub chunk_render_dist: ChunkRenderDist,
+    #[serde(default)]
+    pub chunk_physics_dist: ChunkPhysicsDist,
+    #[serde(default)]
+    pub show_structure_compass: bool,
+    // Spectator mode is always available in debug builds
+    #[serde(default)]
+    pub enable_spectator_mode: bool,
+    #[serde(default)]
+    pub crosshair_style: CrosshairStyle,
+    #[serde(default)]
+    pub crosshair_size: CrosshairSize,
+    #[serde(default)]
+    pub decor_density: DecorDensity,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    #[serde(default)]
+    pub language: Language,
+    // Turns off the damage vignette and camera shake when the player is hurt
+    #[serde(default)]
+    pub disable_damage_effects: bool,
+    // Turns off the impact effect and hit-stop when the player's attacks land
+    #[serde(default)]
+    pub disable_hit_effects: bool,
+    // Items lying on the ground are picked up just by walking over them
+    #[serde(default)]
+    pub auto_pick_up_items: bool,
+    #[serde(default)]
+    pub fov_degrees: FovDegrees,
+    #[serde(default)]
+    pub mouse_sensitivity: MouseSensitivity,
+    #[serde(default)]
+    pub ui_scale: UiScaleFactor,
+    #[serde(default)]
+    pub light_fuel: LightFuelMode,
+    #[serde(default)]
+    pub movement_style: MovementStyle,
+    #[serde(default)]
+    pub autosave_minutes: AutosaveMinutes,
 }
 
+impl GameSettings {
+    // Chunks within this distance of the active chunk have colliders, which is never further
+    // than they are rendered
+    pub fn physics_dist(&self) -> ChunkRenderDist {
+        let rd = self.chunk_render_dist;
+        let pd = self.chunk_physics_dist.0;
+        ChunkRenderDist(rd.0.min(pd), rd.1.min(pd), rd.2.min(pd))
+    }
+}
+
+// Sent when either the render distance or the physics distance changes
 #[derive(Event)]
 pub struct RenderDistChanged;
 
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+// Setting that holds an f32, which is always kept between a min and a max. Eq and Hash are
+// implemented by hand so that GameSettings can still be a state, since the value is never NaN.
+macro_rules! clamped_f32_setting {
+    ($t:ident, $min:expr, $max:expr, $step:expr, $default:expr) => {
+        #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Reflect, Serialize)]
+        #[serde(from = "f32", into = "f32")]
+        pub struct $t(f32);
+
+        impl $t {
+            pub const MIN: f32 = $min;
+            pub const MAX: f32 = $max;
+            pub const STEP: f32 = $step;
+
+            pub fn new(value: f32) -> Self {
+                if value.is_nan() {
+                    return Self::default();
+                }
+                Self(value.clamp(Self::MIN, Self::MAX))
+            }
+
+            pub fn get(&self) -> f32 {
+                self.0
+            }
+
+            // Moves the value by a number of steps, snapping it to the nearest step along the way
+            pub fn step(&self, steps: i32) -> Self {
+                Self::new(((self.0 / Self::STEP).round() + steps as f32) * Self::STEP)
+            }
+        }
+
+        impl Default for $t {
+            fn default() -> Self {
+                Self($default)
+            }
+        }
+
+        impl Eq for $t {}
+
+        impl Hash for $t {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.to_bits().hash(state);
+            }
+        }
+
+        impl From<f32> for $t {
+            fn from(value: f32) -> Self {
+                Self::new(value)
+            }
+        }
+
+        impl From<$t> for f32 {
+            fn from(value: $t) -> Self {
+                value.0
+            }
+        }
+    };
+}
+
+// Vertical field of view of the camera, in degrees. The default is the one bevy's cameras start with.
+clamped_f32_setting!(FovDegrees, 30.0, 90.0, 5.0, 45.0);
+
+// Multiplies how fast the camera turns when the mouse is moved
+clamped_f32_setting!(MouseSensitivity, 0.2, 3.0, 0.1, 1.0);
+
+// Multiplies the size of everything in the menus and hud
+clamped_f32_setting!(UiScaleFactor, 0.75, 2.0, 0.25, 1.0);
+
+// Minutes of play between autosaves
+clamped_f32_setting!(AutosaveMinutes, 0.5, 10.0, 0.5, 2.0);
+
+impl AutosaveMinutes {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs_f32(self.0 * 60.0)
+    }
+}
+
+// Every chunk within render distance of the active chunk is spawned, which is
+// (2 * dist - 1) chunks along each axis. The count grows with the cube of the distance:
+// 8 is already 3375 chunks, while 50 would be close to a million and hang the app.
+pub const MAX_CHUNK_RENDER_DIST: u32 = 8;
+
+// Clamped to MAX_CHUNK_RENDER_DIST whenever it is read from a save
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Reflect, Serialize)]
+#[serde(from = "(u32, u32, u32)", into = "(u32, u32, u32)")]
 pub struct ChunkRenderDist(pub u32, pub u32, pub u32);
 
+impl ChunkRenderDist {
+    pub fn clamped(&self, max: u32) -> Self {
+        Self(self.0.min(max), self.1.min(max), self.2.min(max))
+    }
+
+    // Whether a chunk is among the ones within this distance of another chunk,
+    // like the ones make_nei_chunks_xyz gives
+    pub fn covers(&self, center: (i64, i64, i64), xyz: (i64, i64, i64)) -> bool {
+        (xyz.0 - center.0).unsigned_abs() < self.0 as u64
+            && (xyz.1 - center.1).unsigned_abs() < self.1 as u64
+            && (xyz.2 - center.2).unsigned_abs() < self.2 as u64
+    }
+}
+
 impl Default for ChunkRenderDist {
     fn default() -> Self {
         Self(1, 1, 1)
     }
 }
+
+impl From<(u32, u32, u32)> for ChunkRenderDist {
+    fn from((x, y, z): (u32, u32, u32)) -> Self {
+        Self(x, y, z).clamped(MAX_CHUNK_RENDER_DIST)
+    }
+}
+
+impl From<ChunkRenderDist> for (u32, u32, u32) {
+    fn from(value: ChunkRenderDist) -> Self {
+        (value.0, value.1, value.2)
+    }
+}
+
+pub const DEFAULT_CHUNK_PHYSICS_DIST: u32 = 2;
+
+// Chunks further away than this keep their colliders aside, since nothing can touch them.
+// Only ever used clamped to the render distance, through GameSettings::physics_dist.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Reflect, Serialize)]
+#[serde(from = "u32", into = "u32")]
+pub struct ChunkPhysicsDist(pub u32);
+
+impl Default for ChunkPhysicsDist {
+    fn default() -> Self {
+        Self(DEFAULT_CHUNK_PHYSICS_DIST)
+    }
+}
+
+impl From<u32> for ChunkPhysicsDist {
+    fn from(value: u32) -> Self {
+        // Nothing could be stood on at 0
+        Self(value.clamp(1, MAX_CHUNK_RENDER_DIST))
+    }
+}
+
+impl From<ChunkPhysicsDist> for u32 {
+    fn from(value: ChunkPhysicsDist) -> Self {
+        value.0
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Reflect, Serialize)]
+pub enum CrosshairStyle {
+    #[default]
+    Dot,
+    Cross,
+}
+
+impl CrosshairStyle {
+    pub fn display_key(&self) -> &'static str {
+        match self {
+            Self::Dot => "settings.crosshair_style.dot",
+            Self::Cross => "settings.crosshair_style.cross",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Self::Dot => Self::Cross,
+            Self::Cross => Self::Dot,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Reflect, Serialize)]
+pub enum CrosshairSize {
+    Small,
+    #[default]
+    Medium,
+    Large,
+}
+
+impl CrosshairSize {
+    pub fn display_key(&self) -> &'static str {
+        match self {
+            Self::Small => "settings.crosshair_size.small",
+            Self::Medium => "settings.crosshair_size.medium",
+            Self::Large => "settings.crosshair_size.large",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Self::Small => Self::Medium,
+            Self::Medium => Self::Large,
+            Self::Large => Self::Small,
+        }
+    }
+
+    // Diameter of the dot, or length of each line of the cross
+    pub fn px(&self) -> f32 {
+        match self {
+            Self::Small => 4.0,
+            Self::Medium => 6.0,
+            Self::Large => 10.0,
+        }
+    }
+}
+
+// How many ambient props (rubble, cobwebs, wall cracks) are spawned in each cell
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Reflect, Serialize)]
+pub enum DecorDensity {
+    Off,
+    #[default]
+    Low,
+    High,
+}
+
+impl DecorDensity {
+    pub fn display_key(&self) -> &'static str {
+        match self {
+            Self::Off => "settings.decor_density.off",
+            Self::Low => "settings.decor_density.low",
+            Self::High => "settings.decor_density.high",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Self::Off => Self::Low,
+            Self::Low => Self::High,
+            Self::High => Self::Off,
+        }
+    }
+}
+
+// Whether the player's light burns fuel, and if Coal is used up on its own once the fuel runs out
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Reflect, Serialize)]
+pub enum LightFuelMode {
+    #[default]
+    Off,
+    Manual,
+    Auto,
+}
+
+impl LightFuelMode {
+    pub fn display_key(&self) -> &'static str {
+        match self {
+            Self::Off => "settings.light_fuel.off",
+            Self::Manual => "settings.light_fuel.manual",
+            Self::Auto => "settings.light_fuel.auto",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Self::Off => Self::Manual,
+            Self::Manual => Self::Auto,
+            Self::Auto => Self::Off,
+        }
+    }
+
+    pub fn is_on(&self) -> bool {
+        *self != Self::Off
+    }
+}
+
+// Snapped movement goes along the 8 compass directions of the world, whichever way the camera
+// faces, and turns the player to face them over a few frames, for more of a dungeon crawler feel
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Reflect, Serialize)]
+pub enum MovementStyle {
+    #[default]
+    Free,
+    Snapped,
+}
+
+impl MovementStyle {
+    pub fn display_key(&self) -> &'static str {
+        match self {
+            Self::Free => "settings.movement_style.free",
+            Self::Snapped => "settings.movement_style.snapped",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Self::Free => Self::Snapped,
+            Self::Snapped => Self::Free,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Reflect, Serialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Easy => "Easy",
+            Self::Normal => "Normal",
+            Self::Hard => "Hard",
+        }
+    }
+
+    pub fn display_key(&self) -> &'static str {
+        match self {
+            Self::Easy => "settings.difficulty.easy",
+            Self::Normal => "settings.difficulty.normal",
+            Self::Hard => "settings.difficulty.hard",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Self::Easy => Self::Normal,
+            Self::Normal => Self::Hard,
+            Self::Hard => Self::Easy,
+        }
+    }
+
+    pub fn modifiers(&self) -> DifficultyModifiers {
+        match self {
+            Self::Easy => DifficultyModifiers {
+                player_dmg_taken: 0.5,
+                spawn_prob: 0.75,
+                player_regen: 1.5,
+            },
+            Self::Normal => DifficultyModifiers {
+                player_dmg_taken: 1.0,
+                spawn_prob: 1.0,
+                player_regen: 1.0,
+            },
+            Self::Hard => DifficultyModifiers {
+                player_dmg_taken: 1.5,
+                spawn_prob: 1.25,
+                player_regen: 0.5,
+            },
+        }
+    }
+}
+
+// Multipliers that each difficulty applies on top of the normal game
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DifficultyModifiers {
+    // Damage the player takes
+    pub player_dmg_taken: f32,
+    // Chance of cell specials (and enemies, once they exist) spawning in a chunk
+    pub spawn_prob: f64,
+    // Base health and stamina regen of the player
+    pub player_regen: f32,
+}
@@ -0,0 +1,15 @@
+use bevy::prelude::{Component, Transform, Visibility};
+
+// Added to the main camera while it is detached from the player
+#[derive(Component)]
+pub struct SpectatorCamera {
+    pub yaw: f32,
+    pub pitch: f32,
+    pub speed: f32,
+    // Restored when reattaching, so the camera ends up exactly where it left off
+    pub attached_transform: Transform,
+}
+
+// Visibility a UI root node had before it was hidden for spectating
+#[derive(Component)]
+pub struct HiddenBySpectator(pub Visibility);
@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod stats_test;
+
+use crate::automap::pack_chunk_xyz;
+use bevy::prelude::{Event, Resource, Vec3};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+// Anything moving the player further than this in a single frame is a teleport,
+// like respawning or loading into the world, and is not counted as traveled
+pub const MAX_TRAVEL_STEP: f32 = 5.0;
+
+// Statistics about the current save, only ever added to while playing
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Resource, Serialize)]
+pub struct PlayerStats {
+    #[serde(default)]
+    pub distance_traveled: f32,
+    #[serde(default)]
+    chunks_visited: HashSet<i64>,
+    #[serde(default)]
+    pub chests_opened: u32,
+    #[serde(default)]
+    pub items_picked_up: u32,
+    #[serde(default)]
+    pub items_dropped: u32,
+    #[serde(default)]
+    pub damage_dealt: f32,
+    #[serde(default)]
+    pub damage_taken: f32,
+}
+
+impl PlayerStats {
+    // Returns true if the step was counted
+    pub fn record_movement(&mut self, from: Vec3, to: Vec3) -> bool {
+        let step = from.distance(to);
+        if step == 0.0 || step > MAX_TRAVEL_STEP {
+            return false;
+        }
+
+        self.distance_traveled += step;
+        true
+    }
+
+    // Returns true if the chunk had not been visited before
+    pub fn visit_chunk(&mut self, chunk_xyz: (i64, i64, i64)) -> bool {
+        self.chunks_visited.insert(pack_chunk_xyz(chunk_xyz))
+    }
+
+    pub fn chunks_visited(&self) -> usize {
+        self.chunks_visited.len()
+    }
+
+    pub fn format_distance(&self) -> String {
+        if self.distance_traveled < 1000.0 {
+            format!("{:.0} m", self.distance_traveled)
+        } else {
+            format!("{:.2} km", self.distance_traveled / 1000.0)
+        }
+    }
+
+    pub fn format_damage(amt: f32) -> String {
+        format!("{:.0}", amt)
+    }
+}
+
+// Sent whenever a statistic changes enough to be worth saving.
+// Distance traveled changes every frame and is saved along with the rest.
+#[derive(Event)]
+pub struct PlayerStatsChanged;
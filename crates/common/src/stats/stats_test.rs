@@ -0,0 +1,64 @@
+use crate::stats::{PlayerStats, MAX_TRAVEL_STEP};
+use bevy::prelude::Vec3;
+
+#[test]
+fn test_record_movement_ignores_teleports() {
+    let mut stats = PlayerStats::default();
+
+    assert!(stats.record_movement(Vec3::ZERO, Vec3::new(3.0, 0.0, 4.0)));
+    assert_eq!(stats.distance_traveled, 5.0);
+
+    // Standing still is not a step
+    assert!(!stats.record_movement(Vec3::ONE, Vec3::ONE));
+
+    // Anything over the threshold is a teleport
+    let far = Vec3::new(MAX_TRAVEL_STEP + 0.1, 0.0, 0.0);
+    assert!(!stats.record_movement(Vec3::ZERO, far));
+    assert_eq!(stats.distance_traveled, 5.0);
+}
+
+#[test]
+fn test_visit_chunk_counts_unique_chunks() {
+    let mut stats = PlayerStats::default();
+
+    assert!(stats.visit_chunk((0, 0, 0)));
+    assert!(stats.visit_chunk((-1, 0, 2)));
+    assert!(!stats.visit_chunk((0, 0, 0)));
+    assert_eq!(stats.chunks_visited(), 2);
+}
+
+#[test]
+fn test_format_distance() {
+    let mut stats = PlayerStats {
+        distance_traveled: 412.4,
+        ..Default::default()
+    };
+    assert_eq!(stats.format_distance(), "412 m");
+
+    stats.distance_traveled = 1234.5;
+    assert_eq!(stats.format_distance(), "1.23 km");
+}
+
+#[test]
+fn test_player_stats_serde_round_trip() {
+    let mut stats = PlayerStats {
+        distance_traveled: 1520.5,
+        chests_opened: 3,
+        items_picked_up: 12,
+        items_dropped: 2,
+        damage_dealt: 240.0,
+        damage_taken: 35.5,
+        ..Default::default()
+    };
+    stats.visit_chunk((1, 0, -1));
+    stats.visit_chunk((2, -1, 0));
+
+    let json = serde_json::to_string(&stats).unwrap();
+    assert_eq!(serde_json::from_str::<PlayerStats>(&json).unwrap(), stats);
+
+    // Saves from before a statistic existed start it at zero
+    let stats: PlayerStats = serde_json::from_str(r#"{"chests_opened":4}"#).unwrap();
+    assert_eq!(stats.chests_opened, 4);
+    assert_eq!(stats.chunks_visited(), 0);
+    assert_eq!(stats.distance_traveled, 0.0);
+}
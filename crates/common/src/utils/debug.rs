@@ -1,9 +1,90 @@
+use std::{
+    cell::Cell,
+    env,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+// Setting this to anything but 0 or false makes should_not_happen panic, in tests and debug builds
+pub const PANIC_ON_SHOULD_NOT_HAPPEN_VAR: &str = "DUNGEON_MAZE_PANIC_ON_SHOULD_NOT_HAPPEN";
+
+// Every should_not_happen so far, for the debug stats menu
+pub static SHOULD_NOT_HAPPEN_COUNT: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    // Tests run alongside each other on their own threads, and some of them make things that
+    // should not happen happen on purpose, so tests only look at the ones on their own thread
+    static THREAD_SHOULD_NOT_HAPPEN_COUNT: Cell<u64> = const { Cell::new(0) };
+}
+
 #[macro_export]
 macro_rules! should_not_happen {
     ($($arg:tt)*) => {
-        #[cfg(debug_assertions)]
-        {
-            warn!("[Should not happen] {}", format!($($arg)*));
+        $crate::utils::debug::report_should_not_happen(&format!($($arg)*))
+    };
+}
+
+// Same as should_not_happen, but only logs the first time through a WarnOnce,
+// for systems that would otherwise repeat the same warning every frame
+#[macro_export]
+macro_rules! should_not_happen_once {
+    ($warn_once:expr, $($arg:tt)*) => {
+        if $warn_once.should_warn() {
+            $crate::should_not_happen!($($arg)*);
         }
     };
 }
+
+// Fails a test if anything that should not happen did on the test's thread. Systems only count
+// toward it when their schedule runs on the same thread, with ExecutorKind::SingleThreaded.
+#[macro_export]
+macro_rules! assert_no_should_not_happens {
+    () => {
+        assert_eq!(
+            $crate::utils::debug::thread_should_not_happen_count(),
+            0,
+            "something that should not happen did"
+        )
+    };
+}
+
+pub fn should_not_happen_count() -> u64 {
+    SHOULD_NOT_HAPPEN_COUNT.load(Ordering::Relaxed)
+}
+
+pub fn thread_should_not_happen_count() -> u64 {
+    THREAD_SHOULD_NOT_HAPPEN_COUNT.get()
+}
+
+pub fn report_should_not_happen(message: &str) {
+    THREAD_SHOULD_NOT_HAPPEN_COUNT.set(THREAD_SHOULD_NOT_HAPPEN_COUNT.get() + 1);
+    let panics = cfg!(any(test, debug_assertions))
+        && panics_on_should_not_happen(env::var(PANIC_ON_SHOULD_NOT_HAPPEN_VAR).ok().as_deref());
+    record_should_not_happen(message, panics, &SHOULD_NOT_HAPPEN_COUNT);
+}
+
+// Value of PANIC_ON_SHOULD_NOT_HAPPEN_VAR, if it is set
+pub fn panics_on_should_not_happen(var: Option<&str>) -> bool {
+    var.is_some_and(|var| !matches!(var.trim(), "" | "0" | "false"))
+}
+
+// Counted either way, so that a panic caught by a test still shows up in the count
+pub fn record_should_not_happen(message: &str, panics: bool, count: &AtomicU64) {
+    count.fetch_add(1, Ordering::Relaxed);
+    if panics {
+        panic!("[Should not happen] {}", message);
+    }
+    bevy::log::error!("[Should not happen] {}", message);
+}
+
+// Meant to be a system's Local, so that each system warns once on its own
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct WarnOnce {
+    warned: bool,
+}
+
+impl WarnOnce {
+    // Only true the first time it is called
+    pub fn should_warn(&mut self) -> bool {
+        !std::mem::replace(&mut self.warned, true)
+    }
+}
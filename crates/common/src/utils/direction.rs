@@ -0,0 +1,25 @@
+use bevy::prelude::Vec3;
+use std::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_4};
+
+// Going around from +x toward +z, an eighth of a turn at a time
+const COMPASS_DIRECTIONS: [Vec3; 8] = [
+    Vec3::new(1.0, 0.0, 0.0),
+    Vec3::new(FRAC_1_SQRT_2, 0.0, FRAC_1_SQRT_2),
+    Vec3::new(0.0, 0.0, 1.0),
+    Vec3::new(-FRAC_1_SQRT_2, 0.0, FRAC_1_SQRT_2),
+    Vec3::new(-1.0, 0.0, 0.0),
+    Vec3::new(-FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2),
+    Vec3::new(0.0, 0.0, -1.0),
+    Vec3::new(FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2),
+];
+
+// Nearest of the 8 compass directions along the world x and z axes, as a unit vector.
+// The y axis is ignored, and anything without an x or z (or that isn't finite) snaps to zero.
+pub fn snap_to_compass(direction: Vec3) -> Vec3 {
+    if !direction.is_finite() || (direction.x == 0.0 && direction.z == 0.0) {
+        return Vec3::ZERO;
+    }
+
+    let eighths = (direction.z.atan2(direction.x) / FRAC_PI_4).round() as i32;
+    COMPASS_DIRECTIONS[eighths.rem_euclid(8) as usize]
+}
@@ -18,20 +18,17 @@ pub fn get_n_parent(mut curr_entity: Entity, parent_query: &Query<&Parent>, mut
 pub fn incr_betw_transforms(t1: Transform, t2: Transform, frames_betw: usize) -> Vec<Transform> {
     let mut transforms = vec![t1];
     for i in 0..frames_betw {
-        let translation = Vec3::lerp(
-            t1.translation,
-            t2.translation,
-            i as f32 / frames_betw as f32,
-        );
-        let rotation = Quat::slerp(t1.rotation, t2.rotation, i as f32 / frames_betw as f32);
-        let scale = Vec3::lerp(t1.scale, t2.scale, i as f32 / frames_betw as f32);
-
-        transforms.push(Transform {
-            translation,
-            rotation,
-            scale,
-        });
+        transforms.push(lerp_transforms(t1, t2, i as f32 / frames_betw as f32));
     }
     transforms.push(t2);
     transforms
 }
+
+// Translation and scale are interpolated linearly, and rotation spherically
+pub fn lerp_transforms(t1: Transform, t2: Transform, s: f32) -> Transform {
+    Transform {
+        translation: Vec3::lerp(t1.translation, t2.translation, s),
+        rotation: Quat::slerp(t1.rotation, t2.rotation, s),
+        scale: Vec3::lerp(t1.scale, t2.scale, s),
+    }
+}
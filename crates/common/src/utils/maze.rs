@@ -2,7 +2,7 @@ use crate::{
     utils::rng::seed_to_rng,
     world::{Cell, CellSpecial, CellWall},
 };
-use bevy::utils::default;
+use bevy::{log::info_span, utils::default};
 use rand::{rngs::StdRng, Rng};
 use std::collections::HashSet;
 
@@ -14,6 +14,8 @@ fn _maze_from_seed(seed: u32, height: usize, width: usize) -> Maze {
 }
 
 pub fn maze_from_rng(rng: &mut StdRng, height: usize, width: usize) -> Maze {
+    let _span = info_span!("maze_from_rng", height, width).entered();
+
     let mut maze: Maze = vec![
         vec![
             Cell {
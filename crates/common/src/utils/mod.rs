@@ -1,4 +1,5 @@
 pub mod debug;
+pub mod direction;
 pub mod entity;
 pub mod io;
 pub mod maze;
@@ -8,16 +9,33 @@ pub mod rng;
 #[cfg(test)]
 pub mod utils_test;
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+use bevy::reflect::Reflect;
+use thiserror;
+
+#[derive(thiserror::Error, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IncrCounterError {
+    #[error("expected non-zero incr")]
+    ZeroIncr,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Reflect)]
 pub struct IncrCounter {
     value: i32,
     incr: i32,
 }
 
 impl IncrCounter {
+    // Panics on a zero incr, which would never reach 0. Use try_new for an incr
+    // that does not come from the code itself.
     pub fn new(value: i32, incr: i32) -> Self {
-        assert_ne!(incr, 0, "expected non-zero incr");
-        Self { value, incr }
+        Self::try_new(value, incr).unwrap()
+    }
+
+    pub fn try_new(value: i32, incr: i32) -> Result<Self, IncrCounterError> {
+        if incr == 0 {
+            return Err(IncrCounterError::ZeroIncr);
+        }
+        Ok(Self { value, incr })
     }
 
     pub fn tick(&mut self) -> i32 {
@@ -50,6 +68,15 @@ impl CyclicCounter {
         }
     }
 
+    // Starts at curr instead of min, clamped to the range
+    pub fn new_at(min: u32, max: u32, curr: u32) -> Self {
+        Self {
+            curr: curr.clamp(min, max),
+            min,
+            max,
+        }
+    }
+
     pub fn value(&self) -> u32 {
         self.curr
     }
@@ -106,23 +133,41 @@ pub fn _min_max_or_betw<T: PartialOrd>(min: T, max: T, betw: T) -> T {
     betw
 }
 
-pub fn find_exactly_one<I, P>(iterable: I, predicate: P) -> Option<I::Item>
+#[derive(thiserror::Error, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FindOneError {
+    #[error("expected exactly one match, found none")]
+    Zero,
+    #[error("expected exactly one match, found {0}")]
+    Multiple(usize),
+}
+
+pub fn find_one<I, P>(iterable: I, predicate: P) -> Result<I::Item, FindOneError>
 where
     I: IntoIterator,
     P: FnMut(&I::Item) -> bool,
 {
     let mut items = iterable.into_iter().filter(predicate);
-    let item = items.next();
-    if items.next().is_none() {
-        return item;
+    let item = items.next().ok_or(FindOneError::Zero)?;
+    match items.count() {
+        0 => Ok(item),
+        rest => Err(FindOneError::Multiple(rest + 1)),
     }
-    None
 }
 
+pub fn find_exactly_one<I, P>(iterable: I, predicate: P) -> Option<I::Item>
+where
+    I: IntoIterator,
+    P: FnMut(&I::Item) -> bool,
+{
+    find_one(iterable, predicate).ok()
+}
+
+// Only for invariants the code itself upholds. Anything derived from
+// user input or assets should go through find_one instead.
 pub fn must_find_exactly_one<I, P>(iterable: I, predicate: P) -> I::Item
 where
     I: IntoIterator,
     P: FnMut(&I::Item) -> bool,
 {
-    find_exactly_one(iterable, predicate).unwrap()
+    find_one(iterable, predicate).unwrap()
 }
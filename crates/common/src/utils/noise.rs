@@ -1,20 +1,16 @@
+use bevy::math::Vec3;
 use noise::{NoiseFn, Perlin};
 
-const PERLIN_SCALE: f64 = 0.08; // Decreasing this value increases average biome size generated
+// Decreasing this value increases average biome size generated.
+// Sampled per world unit, so a 16 unit chunk spans roughly the same
+// distance through the noise as it did when it was sampled once per chunk.
+const PERLIN_SCALE: f64 = 0.005;
 
-pub fn noise_from_xyz_seed(
-    seed: u32,
-    x: i64,
-    y: i64,
-    z: i64,
-    chunk_size: f32,
-    cell_size: f32,
-) -> f64 {
+pub fn noise_at_world_pos(seed: u32, pos: Vec3) -> f64 {
     let perlin = Perlin::new(seed);
-    let noise_xy = perlin.get([x as f64 * PERLIN_SCALE, y as f64 * PERLIN_SCALE]);
-    let noise_yz = perlin.get([y as f64 * PERLIN_SCALE, z as f64 * PERLIN_SCALE])
-        / (chunk_size / cell_size) as f64;
-    let noise_zx = perlin.get([z as f64 * PERLIN_SCALE, x as f64 * PERLIN_SCALE]);
-    let noise_xyz = (noise_xy + noise_yz + noise_zx) / 3.0;
-    noise_xyz
+    perlin.get([
+        pos.x as f64 * PERLIN_SCALE,
+        pos.y as f64 * PERLIN_SCALE,
+        pos.z as f64 * PERLIN_SCALE,
+    ])
 }
@@ -1,4 +1,21 @@
-use crate::utils::find_exactly_one;
+use crate::{
+    assert_no_should_not_happens, should_not_happen,
+    utils::{
+        debug::{
+            panics_on_should_not_happen, record_should_not_happen, thread_should_not_happen_count,
+            WarnOnce, PANIC_ON_SHOULD_NOT_HAPPEN_VAR,
+        },
+        direction::snap_to_compass,
+        find_exactly_one, find_one, FindOneError, IncrCounter, IncrCounterError,
+    },
+};
+use bevy::prelude::Vec3;
+use std::{
+    env,
+    f32::consts::FRAC_1_SQRT_2,
+    panic,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 #[test]
 fn test_find_exactly_one() {
@@ -26,3 +43,121 @@ fn test_find_exactly_one() {
     // Predicate matches the last element only
     assert_eq!(find_exactly_one(vec![1, 2, 3], |n| *n == 3), Some(3));
 }
+
+#[test]
+fn test_find_one() {
+    assert_eq!(find_one(vec![1, 2, 3], |n| *n == 2), Ok(2));
+    assert_eq!(
+        find_one(vec![1, 3, 5], |n| *n == 2),
+        Err(FindOneError::Zero)
+    );
+    assert_eq!(
+        find_one::<Vec<i32>, _>(vec![], |n| *n == 2),
+        Err(FindOneError::Zero)
+    );
+
+    // Every match is counted, not just the first two
+    assert_eq!(
+        find_one(vec![2, 1, 2, 3, 2], |n| *n == 2),
+        Err(FindOneError::Multiple(3))
+    );
+}
+
+#[test]
+fn test_incr_counter_try_new() {
+    assert_eq!(IncrCounter::try_new(3, 0), Err(IncrCounterError::ZeroIncr));
+
+    let mut counter = IncrCounter::try_new(3, -1).unwrap();
+    assert_eq!(counter, IncrCounter::new(3, -1));
+    assert_eq!(counter.tick(), 3);
+    assert_eq!(counter.get_value(), 2);
+}
+
+#[test]
+fn test_warn_once() {
+    let mut warn_once = WarnOnce::default();
+    assert!(warn_once.should_warn());
+    assert!(!warn_once.should_warn());
+    assert!(!warn_once.should_warn());
+}
+
+#[test]
+fn test_should_not_happen_env_toggle() {
+    assert!(!panics_on_should_not_happen(None));
+    for off in ["", "0", "false", " false "] {
+        assert!(!panics_on_should_not_happen(Some(off)), "{:?}", off);
+    }
+    for on in ["1", "true", "yes"] {
+        assert!(panics_on_should_not_happen(Some(on)), "{:?}", on);
+    }
+}
+
+#[test]
+fn test_should_not_happen_logs_and_counts() {
+    let count = AtomicU64::new(0);
+    record_should_not_happen("first", false, &count);
+    record_should_not_happen("second", false, &count);
+    assert_eq!(count.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn test_should_not_happen_panics_when_enabled() {
+    let count = AtomicU64::new(0);
+    let result = panic::catch_unwind(|| record_should_not_happen("boom", true, &count));
+
+    let message = result.unwrap_err();
+    assert_eq!(
+        message.downcast_ref::<String>().map(String::as_str),
+        Some("[Should not happen] boom")
+    );
+    // Counted before panicking
+    assert_eq!(count.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_assert_no_should_not_happens() {
+    assert_no_should_not_happens!();
+
+    // Only checked when the env toggle is off, since it would panic here otherwise
+    if !panics_on_should_not_happen(env::var(PANIC_ON_SHOULD_NOT_HAPPEN_VAR).ok().as_deref()) {
+        should_not_happen!("on purpose: {}", 1);
+        assert_eq!(thread_should_not_happen_count(), 1);
+        assert!(panic::catch_unwind(|| assert_no_should_not_happens!()).is_err());
+    }
+}
+
+#[test]
+fn test_snap_to_compass() {
+    let diagonal = FRAC_1_SQRT_2;
+
+    for (direction, expected) in [
+        // Already along a compass direction, whatever the length
+        (Vec3::X, Vec3::X),
+        (Vec3::new(0.0, 0.0, -3.0), Vec3::NEG_Z),
+        (Vec3::new(2.0, 0.0, 2.0), Vec3::new(diagonal, 0.0, diagonal)),
+        (
+            Vec3::new(-1.0, 0.0, 1.0),
+            Vec3::new(-diagonal, 0.0, diagonal),
+        ),
+        // Nearest of the 8
+        (Vec3::new(1.0, 0.0, 0.3), Vec3::X),
+        (Vec3::new(1.0, 0.0, 0.6), Vec3::new(diagonal, 0.0, diagonal)),
+        (Vec3::new(-0.2, 0.0, -1.0), Vec3::NEG_Z),
+        (
+            Vec3::new(-1.0, 0.0, -0.9),
+            Vec3::new(-diagonal, 0.0, -diagonal),
+        ),
+        (Vec3::new(0.9, 0.0, -0.1), Vec3::X),
+        // Either side of straight back along x
+        (Vec3::new(-1.0, 0.0, 0.1), Vec3::NEG_X),
+        (Vec3::new(-1.0, 0.0, -0.1), Vec3::NEG_X),
+        // The vertical component is ignored
+        (Vec3::new(0.0, 5.0, 1.0), Vec3::Z),
+        // Nothing to snap
+        (Vec3::ZERO, Vec3::ZERO),
+        (Vec3::Y, Vec3::ZERO),
+        (Vec3::new(f32::NAN, 0.0, 1.0), Vec3::ZERO),
+    ] {
+        assert_eq!(snap_to_compass(direction), expected, "{}", direction);
+    }
+}
@@ -1,11 +1,21 @@
-use crate::inventory::item::Item;
+use crate::{
+    inventory::item::Item,
+    world::{CellMutation, Chunk, Side},
+};
 use bevy::prelude::Resource;
 use serde::{
     de::{self, MapAccess, Visitor},
     Deserialize, Deserializer, Serialize, Serializer,
 };
+use serde_json::Value;
 use std::{collections::HashMap, fmt};
 
+// Whether the treasure chest in a cell was last left open
+pub const CHEST_OPENED_FLAG: &str = "chest_opened";
+pub const CHEST_UNLOCKED_FLAG: &str = "chest_unlocked";
+// Followed by the side of the cell that the window is on, like window_broken_top
+pub const WINDOW_BROKEN_FLAG: &str = "window_broken";
+
 macro_rules! serialize_impl {
     ($t:ty, $child_t:ty, $prop_name:ident, $closure:expr) => {
         impl Serialize for $t {
@@ -13,9 +23,13 @@ macro_rules! serialize_impl {
             where
                 S: Serializer,
             {
-                // Convert the HashMap keys to strings
-                let stringified: HashMap<String, &$child_t> =
-                    self.$prop_name.iter().map($closure).collect();
+                // Convert the HashMap keys to strings, leaving out anything with nothing saved
+                let stringified: HashMap<String, &$child_t> = self
+                    .$prop_name
+                    .iter()
+                    .filter(|(_, value)| !value.is_empty())
+                    .map($closure)
+                    .collect();
 
                 // Serialize the stringified HashMap
                 stringified.serialize(serializer)
@@ -100,6 +114,11 @@ fn parse_3d_key(parts: &[&str]) -> Result<(i64, i64, i64), String> {
 }
 
 impl WorldData {
+    // Chunks that have anything saved about them
+    pub fn chunks_count(&self) -> usize {
+        self.chunks.len()
+    }
+
     pub fn at_chunk(&self, xyz: (i64, i64, i64)) -> Option<&ChunkData> {
         self.chunks.get(&xyz)
     }
@@ -140,6 +159,98 @@ impl WorldData {
         }
         chunk_data.cells.get_mut(&xz).unwrap()
     }
+
+    fn dropped_items_iter(&self) -> impl Iterator<Item = &DroppedItemData> {
+        self.chunks
+            .values()
+            .flat_map(|chunk_data| chunk_data.cells.values())
+            .flat_map(|cell_data| cell_data.dropped_items.iter())
+    }
+
+    pub fn dropped_items_count(&self) -> usize {
+        self.dropped_items_iter().count()
+    }
+
+    // Dropped item ids are assigned in increasing order, so the lowest id is the oldest
+    pub fn oldest_dropped_item_id(&self) -> Option<u64> {
+        self.dropped_items_iter().map(|did| did.id).min()
+    }
+
+    pub fn add_dropped_item(
+        &mut self,
+        xyz: (i64, i64, i64),
+        xz: (usize, usize),
+        item: Item,
+        translation: (f32, f32, f32),
+    ) -> u64 {
+        let id = self
+            .dropped_items_iter()
+            .map(|did| did.id + 1)
+            .max()
+            .unwrap_or(0);
+
+        self.at_cell_or_create_mut(xyz, xz)
+            .dropped_items
+            .push(DroppedItemData {
+                id,
+                item,
+                translation,
+            });
+
+        id
+    }
+
+    pub fn dropped_item_mut(&mut self, id: u64) -> Option<&mut DroppedItemData> {
+        self.chunks
+            .values_mut()
+            .flat_map(|chunk_data| chunk_data.cells.values_mut())
+            .flat_map(|cell_data| cell_data.dropped_items.iter_mut())
+            .find(|did| did.id == id)
+    }
+
+    // Returns false if the cell already had the mutation
+    pub fn add_cell_mutation(
+        &mut self,
+        xyz: (i64, i64, i64),
+        xz: (usize, usize),
+        mutation: CellMutation,
+    ) -> bool {
+        let mutations = &mut self.at_cell_or_create_mut(xyz, xz).mutations;
+        if mutations.contains(&mutation) {
+            return false;
+        }
+        mutations.push(mutation);
+        true
+    }
+
+    // Chunks are always generated from scratch, so this is applied to every generated chunk
+    pub fn apply_cell_mutations(&self, chunk: &mut Chunk) {
+        let Some(chunk_data) = self.at_chunk((chunk.x, chunk.y, chunk.z)) else {
+            return;
+        };
+
+        for (&(x, z), cell_data) in chunk_data.cells.iter() {
+            let Some(cell) = chunk.cells.get_mut(z).and_then(|row| row.get_mut(x)) else {
+                continue;
+            };
+            for mutation in cell_data.mutations.iter() {
+                mutation.apply(cell);
+            }
+        }
+    }
+
+    pub fn remove_dropped_item(&mut self, id: u64) -> Option<DroppedItemData> {
+        for cell_data in self
+            .chunks
+            .values_mut()
+            .flat_map(|chunk_data| chunk_data.cells.values_mut())
+        {
+            if let Some(i) = cell_data.dropped_items.iter().position(|did| did.id == id) {
+                return Some(cell_data.dropped_items.remove(i));
+            }
+        }
+        None
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -173,6 +284,12 @@ impl ChunkData {
         None
     }
 
+    pub fn dropped_items(&self) -> impl Iterator<Item = &DroppedItemData> {
+        self.cells
+            .values()
+            .flat_map(|cell_data| cell_data.dropped_items.iter())
+    }
+
     fn _at_cell_mut(&mut self, xz: (usize, usize)) -> Option<&mut CellData> {
         if let Some(cell_data) = self.cells.get_mut(&xz) {
             return Some(cell_data);
@@ -186,14 +303,164 @@ impl ChunkData {
         }
         self.cells.get_mut(&xz).unwrap()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.values().all(CellData::is_empty)
+    }
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(from = "CellDataRead")]
 pub struct CellData {
     pub treasure_chest_data: TreasureChestData,
+    pub dropped_items: Vec<DroppedItemData>,
+    pub mutations: Vec<CellMutation>,
+    // Anything else about the cell, for modded or future content without a field of its own
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    flags: HashMap<String, Value>,
 }
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+impl CellData {
+    // Cells with nothing different from how they were generated are left out of saves
+    pub fn is_empty(&self) -> bool {
+        self.treasure_chest_data == TreasureChestData::default()
+            && self.dropped_items.is_empty()
+            && self.mutations.is_empty()
+            && self.flags.is_empty()
+    }
+
+    pub fn get_flag(&self, key: &str) -> Option<&Value> {
+        self.flags.get(key)
+    }
+
+    pub fn set_flag(&mut self, key: impl Into<String>, value: impl Into<Value>) {
+        self.flags.insert(key.into(), value.into());
+    }
+
+    pub fn remove_flag(&mut self, key: &str) -> Option<Value> {
+        self.flags.remove(key)
+    }
+
+    pub fn chest_opened(&self) -> bool {
+        self.get_flag(CHEST_OPENED_FLAG)
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+    }
+
+    // Closed is the default, so it is not kept as a flag
+    pub fn set_chest_opened(&mut self, opened: bool) {
+        if opened {
+            self.set_flag(CHEST_OPENED_FLAG, true);
+        } else {
+            self.remove_flag(CHEST_OPENED_FLAG);
+        }
+    }
+
+    pub fn chest_unlocked(&self) -> bool {
+        self.get_flag(CHEST_UNLOCKED_FLAG)
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+    }
+
+    // Chests that were generated without a lock are never written to as unlocked
+    pub fn set_chest_unlocked(&mut self, unlocked: bool) {
+        if unlocked {
+            self.set_flag(CHEST_UNLOCKED_FLAG, true);
+        } else {
+            self.remove_flag(CHEST_UNLOCKED_FLAG);
+        }
+    }
+
+    pub fn window_broken(&self, side: Side) -> bool {
+        self.get_flag(&window_broken_flag(side))
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+    }
+
+    pub fn set_window_broken(&mut self, side: Side, broken: bool) {
+        if broken {
+            self.set_flag(window_broken_flag(side), true);
+        } else {
+            self.remove_flag(&window_broken_flag(side));
+        }
+    }
+}
+
+// CellData as it is saved, including anything that has since moved into its flags
+#[derive(Deserialize)]
+struct CellDataRead {
+    #[serde(default)]
+    treasure_chest_data: TreasureChestDataRead,
+    #[serde(default)]
+    dropped_items: Vec<DroppedItemData>,
+    #[serde(default)]
+    mutations: Vec<CellMutation>,
+    #[serde(default)]
+    flags: HashMap<String, Value>,
+}
+
+impl From<CellDataRead> for CellData {
+    fn from(cell_data_read: CellDataRead) -> Self {
+        let TreasureChestDataRead {
+            treasure_chest_data,
+            opened,
+        } = cell_data_read.treasure_chest_data;
+
+        let mut cell_data = Self {
+            treasure_chest_data,
+            dropped_items: cell_data_read.dropped_items,
+            mutations: cell_data_read.mutations,
+            flags: cell_data_read.flags,
+        };
+        if opened {
+            cell_data.set_chest_opened(true);
+        }
+        cell_data
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub struct TreasureChestData {
+    // Whatever is left in the chest, once anything has been picked up from it.
+    // Chests that were never picked up from spawn with the item they were generated with.
     pub item: Option<Item>,
+    // Chests in saves from before this existed were only ever written to once emptied
+    #[serde(default = "default_picked_from")]
+    pub picked_from: bool,
+}
+
+// Saves from before CHEST_OPENED_FLAG kept whether the chest was left open in here
+#[derive(Default, Deserialize)]
+struct TreasureChestDataRead {
+    #[serde(flatten)]
+    treasure_chest_data: TreasureChestData,
+    #[serde(default)]
+    opened: bool,
+}
+
+fn window_broken_flag(side: Side) -> String {
+    format!("{}_{}", WINDOW_BROKEN_FLAG, side.to_string().to_lowercase())
+}
+
+fn default_picked_from() -> bool {
+    true
+}
+
+impl TreasureChestData {
+    pub fn set_remaining(&mut self, rem_item: Option<Item>) {
+        self.item = rem_item;
+        self.picked_from = true;
+    }
+
+    // The item to spawn in the chest, or None if the generated item should be spawned instead
+    pub fn remaining(&self) -> Option<Option<Item>> {
+        self.picked_from.then_some(self.item)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DroppedItemData {
+    pub id: u64,
+    pub item: Item,
+    pub translation: (f32, f32, f32),
 }
@@ -0,0 +1,103 @@
+use crate::world::{
+    Cell, CellWall, Chunk, ChunkCellMarker, Side, WorldDims, DEFAULT_CELL_SIZE, WALL_THICKNESS,
+};
+use bevy::{
+    prelude::{GlobalTransform, Vec3},
+    utils::HashMap,
+};
+
+// Gaps in walls, in cells of DEFAULT_CELL_SIZE, measured along the wall from its middle
+// and up from the floor. Matches the wall_with_door_gap and wall_with_window_gap meshes.
+pub const DOOR_GAP_HALF_WIDTH: f32 = 0.5;
+pub const DOOR_GAP_HEIGHT: f32 = DEFAULT_CELL_SIZE / 2.0;
+pub const WINDOW_GAP_HALF_WIDTH: f32 = 0.5;
+pub const WINDOW_GAP_BOTTOM: f32 = DEFAULT_CELL_SIZE / 4.0;
+pub const WINDOW_GAP_TOP: f32 = DEFAULT_CELL_SIZE / 2.0;
+
+// Answers whether a world position is inside of a wall, floor or ceiling from the cells
+// alone, without a physics query. Chunks are generated the first time they are needed,
+// unless they were inserted already.
+pub struct WorldGeometry<F: FnMut(u32, (i64, i64, i64)) -> Chunk> {
+    chunks: HashMap<(u32, (i64, i64, i64)), Chunk>,
+    gen_chunk: F,
+}
+
+impl<F: FnMut(u32, (i64, i64, i64)) -> Chunk> WorldGeometry<F> {
+    pub fn new(gen_chunk: F) -> Self {
+        Self {
+            chunks: HashMap::new(),
+            gen_chunk,
+        }
+    }
+
+    // For chunks that were generated already, like the ones that are spawned
+    pub fn insert_chunk(&mut self, seed: u32, chunk: Chunk) {
+        self.chunks
+            .insert((seed, (chunk.x, chunk.y, chunk.z)), chunk);
+    }
+
+    pub fn remove_chunk(&mut self, seed: u32, xyz: (i64, i64, i64)) -> Option<Chunk> {
+        self.chunks.remove(&(seed, xyz))
+    }
+
+    fn cell(&mut self, seed: u32, ccm: &ChunkCellMarker) -> Option<&Cell> {
+        let xyz = ccm.chunk_xyz();
+        let gen_chunk = &mut self.gen_chunk;
+        let chunk = self
+            .chunks
+            .entry((seed, xyz))
+            .or_insert_with(|| gen_chunk(seed, xyz));
+        chunk.cells.get(ccm.z)?.get(ccm.x)
+    }
+
+    // Precise to within WALL_THICKNESS. Doors and windows that are spawned in wall gaps,
+    // and anything else inside of a cell, like stairs, do not count as solid.
+    pub fn is_position_solid(&mut self, seed: u32, pos: Vec3, dims: &WorldDims) -> bool {
+        let ccm =
+            ChunkCellMarker::from_global_transform(&GlobalTransform::from_translation(pos), dims);
+        let offset = (pos - ccm.world_position(dims)) / (dims.cell_size() / DEFAULT_CELL_SIZE);
+
+        self.cell(seed, &ccm)
+            .is_some_and(|cell| is_cell_offset_solid(cell, offset))
+    }
+}
+
+// Offset from the center of a cell's floor, in cells of DEFAULT_CELL_SIZE
+pub fn is_cell_offset_solid(cell: &Cell, offset: Vec3) -> bool {
+    let half_cell_size = DEFAULT_CELL_SIZE / 2.0;
+
+    if cell.floor == CellWall::Solid && offset.y < WALL_THICKNESS {
+        return true;
+    }
+    if cell.ceiling == CellWall::Solid && offset.y > DEFAULT_CELL_SIZE - WALL_THICKNESS {
+        return true;
+    }
+
+    // Cells are stored from +x to -x (and +z to -z), and Top walls are on the +x side
+    // of a cell, Left walls on the +z side
+    [
+        (Side::Top, offset.x, offset.z),
+        (Side::Bottom, -offset.x, offset.z),
+        (Side::Left, offset.z, offset.x),
+        (Side::Right, -offset.z, offset.x),
+    ]
+    .into_iter()
+    .any(|(side, depth, along)| {
+        depth > half_cell_size - WALL_THICKNESS
+            && is_wall_solid_at(cell.wall(side), along, offset.y)
+    })
+}
+
+fn is_wall_solid_at(wall: &CellWall, along: f32, height: f32) -> bool {
+    match wall {
+        CellWall::None => false,
+        CellWall::Solid => true,
+        CellWall::SolidWithDoorGap => {
+            along.abs() >= DOOR_GAP_HALF_WIDTH || height >= DOOR_GAP_HEIGHT
+        }
+        CellWall::SolidWithWindowGap => {
+            along.abs() >= WINDOW_GAP_HALF_WIDTH
+                || !(WINDOW_GAP_BOTTOM..WINDOW_GAP_TOP).contains(&height)
+        }
+    }
+}
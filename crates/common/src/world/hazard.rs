@@ -0,0 +1,81 @@
+use crate::player::DmgType;
+use bevy::{
+    prelude::{Component, Entity, Event},
+    utils::HashMap,
+};
+
+// Floor hazard covering a whole cell, damaging any entity with health that stands in it
+#[derive(Clone, Copy, Component, Debug, Eq, PartialEq)]
+pub enum Hazard {
+    SpikeTrap,
+    PoisonPool,
+}
+
+impl Hazard {
+    pub fn dmg(&self) -> Vec<(DmgType, f32)> {
+        match self {
+            Self::SpikeTrap => vec![(DmgType::Pierce, 4.0)],
+            // Enough to apply the poisoned status effect, which does the rest of the damage
+            Self::PoisonPool => vec![(DmgType::Poison, 5.0)],
+        }
+    }
+
+    // Frames between hits while an entity stays inside,
+    // or None to only hit once each time an entity steps in
+    pub fn tick_interval(&self) -> Option<u32> {
+        match self {
+            Self::SpikeTrap => Some(45),
+            Self::PoisonPool => None,
+        }
+    }
+}
+
+// Entities currently inside of a hazard, and the frames since each of them was last hit
+#[derive(Component, Debug, Default)]
+pub struct HazardOccupants(HashMap<Entity, u32>);
+
+impl HazardOccupants {
+    // Replaces the occupants with the entities that are inside this frame,
+    // and returns the ones that should be hit. Entities are hit as soon as they step in,
+    // then again every interval frames for as long as they stay inside.
+    pub fn tick(
+        &mut self,
+        inside: impl IntoIterator<Item = Entity>,
+        interval: Option<u32>,
+    ) -> Vec<Entity> {
+        let mut occupants = HashMap::new();
+        let mut hit = Vec::new();
+
+        for entity in inside {
+            if occupants.contains_key(&entity) {
+                continue;
+            }
+
+            let frames = match self.0.get(&entity) {
+                None => 0,
+                Some(frames) => match interval {
+                    Some(interval) if frames + 1 >= interval => 0,
+                    _ => frames.saturating_add(1),
+                },
+            };
+
+            if frames == 0 {
+                hit.push(entity);
+            }
+            occupants.insert(entity, frames);
+        }
+
+        self.0 = occupants;
+        hit
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.0.contains_key(&entity)
+    }
+}
+
+// Sent alongside the TakeDamage event whenever a hazard hits something
+#[derive(Debug, Event)]
+pub struct HazardDamaged {
+    pub target: Entity,
+}
@@ -1,20 +1,304 @@
 pub mod data;
+pub mod geometry;
+pub mod hazard;
+pub mod nav;
+pub mod particle;
 pub mod world_structure;
 
-use crate::utils::{rng::rng_from_str, CyclicCounter};
+#[cfg(test)]
+mod world_test;
+
+use crate::{
+    error::Error,
+    utils::{
+        entity::lerp_transforms,
+        rng::{rng_from_str, rng_from_xyz_seed},
+        CyclicCounter, IncrCounter,
+    },
+    world::world_structure::WorldStructureRegistry,
+};
 use bevy::{
     ecs::system::EntityCommands,
     prelude::{
-        default, Bundle, ChildBuilder, Commands, Component, GlobalTransform, States, Transform,
+        default, Bundle, ChildBuilder, Children, Commands, Component, Entity, Event,
+        GlobalTransform, Query, Resource, States, Transform, Vec3,
     },
+    reflect::Reflect,
+    tasks::Task,
     utils::HashMap,
 };
-use rand::rngs::StdRng;
+use rand::{rngs::StdRng, Rng};
 use serde::{Deserialize, Serialize};
+use std::{iter, time::Duration};
 use strum_macros::{Display, EnumIter};
-use world_structure::WorldStructureName;
 
-#[derive(Display)]
+// Probability of a chunk containing a world structure, once far enough away from the origin
+pub const WORLD_STRUCTURE_GEN_PROB: f64 = 0.18;
+// Probability of a chunk near the origin containing a world structure,
+// which keeps the area around spawn from being cluttered with them
+pub const WORLD_STRUCTURE_NEAR_GEN_PROB: f64 = 0.02;
+// Chunks up to this many chunks away from the origin on every axis use the near probability
+pub const WORLD_STRUCTURE_RAMP_START_DIST: i64 = 2;
+// Chunks at least this many chunks away from the origin on any axis use the full probability,
+// with the probability ramping up linearly in between
+pub const WORLD_STRUCTURE_RAMP_END_DIST: i64 = 6;
+pub const DEFAULT_WORLD_SEED: u32 = 123456;
+pub const DEFAULT_CHUNK_SIZE: f32 = 16.0;
+pub const DEFAULT_CELL_SIZE: f32 = 4.0;
+// Thickness of walls, floors and ceilings, in cells of DEFAULT_CELL_SIZE
+pub const WALL_THICKNESS: f32 = 0.1;
+pub const DEFAULT_SURFACE_LEVEL: i64 = 1;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Resource, Serialize)]
+pub struct WorldSeed(pub u32);
+
+impl Default for WorldSeed {
+    fn default() -> Self {
+        Self(DEFAULT_WORLD_SEED)
+    }
+}
+
+// Settings that a world is generated with besides its seed, saved along with it
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Resource, Serialize)]
+pub struct WorldConfig {
+    // Chunks at or above this y level have no ceilings, opening them up to the sky.
+    // None keeps every level enclosed, which is how worlds were generated before.
+    pub surface_level: Option<i64>,
+    // Whether the origin chunk is always the starting room world structure. Worlds saved
+    // before it existed keep generating the origin chunk like any other.
+    #[serde(default)]
+    pub starting_room: bool,
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        Self {
+            surface_level: Some(DEFAULT_SURFACE_LEVEL),
+            starting_room: true,
+        }
+    }
+}
+
+impl WorldConfig {
+    pub fn enclosed() -> Self {
+        Self {
+            surface_level: None,
+            starting_room: false,
+        }
+    }
+}
+
+pub fn is_surface_level(surface_level: Option<i64>, y: i64) -> bool {
+    surface_level.is_some_and(|level| y >= level)
+}
+
+// Size of chunks and cells in world units. Chunks are squares of grid_size x grid_size cells,
+// and each y level of chunks is a single cell tall.
+#[derive(Clone, Copy, Debug, PartialEq, Resource)]
+pub struct WorldDims {
+    chunk_size: f32,
+    cell_size: f32,
+}
+
+impl WorldDims {
+    pub fn new(chunk_size: f32, cell_size: f32) -> Result<Self, Error> {
+        if !(chunk_size.is_finite() && chunk_size > 0.0 && cell_size.is_finite() && cell_size > 0.0)
+        {
+            return Err(Error::WorldDims(format!(
+                "expected positive chunk size ({}) and cell size ({})",
+                chunk_size, cell_size
+            )));
+        }
+        if chunk_size % cell_size != 0.0 {
+            return Err(Error::WorldDims(format!(
+                "expected chunk size ({}) to be divisible by cell size ({})",
+                chunk_size, cell_size
+            )));
+        }
+        if chunk_size / cell_size < 2.0 {
+            return Err(Error::WorldDims(format!(
+                "expected chunk size ({}) to fit at least 2 cells of size {}",
+                chunk_size, cell_size
+            )));
+        }
+
+        Ok(Self {
+            chunk_size,
+            cell_size,
+        })
+    }
+
+    pub fn chunk_size(&self) -> f32 {
+        self.chunk_size
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    // Number of cells along each side of a chunk
+    pub fn grid_size(&self) -> usize {
+        (self.chunk_size / self.cell_size) as usize
+    }
+}
+
+impl Default for WorldDims {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            cell_size: DEFAULT_CELL_SIZE,
+        }
+    }
+}
+
+// Probability of the chunk at x, y, z containing a world structure,
+// based on how many chunks away from the origin it is along its furthest axis
+pub fn structure_prob_at(x: i64, y: i64, z: i64) -> f64 {
+    let dist = x.abs().max(y.abs()).max(z.abs());
+    if dist <= WORLD_STRUCTURE_RAMP_START_DIST {
+        return WORLD_STRUCTURE_NEAR_GEN_PROB;
+    }
+    if dist >= WORLD_STRUCTURE_RAMP_END_DIST {
+        return WORLD_STRUCTURE_GEN_PROB;
+    }
+
+    let t = (dist - WORLD_STRUCTURE_RAMP_START_DIST) as f64
+        / (WORLD_STRUCTURE_RAMP_END_DIST - WORLD_STRUCTURE_RAMP_START_DIST) as f64;
+    WORLD_STRUCTURE_NEAR_GEN_PROB + (WORLD_STRUCTURE_GEN_PROB - WORLD_STRUCTURE_NEAR_GEN_PROB) * t
+}
+
+// Only the probability depends on the distance, the rng is sampled the same way everywhere,
+// so chunks past the ramp have the same world structures that they always had
+pub fn chunk_has_world_structure(seed: u32, x: i64, y: i64, z: i64) -> bool {
+    roll_structure_origin(seed, x, y, z).is_some()
+}
+
+// Some if the chunk at x, y, z passes its roll for a world structure, holding its priority
+// over other origins nearby, which is drawn from the same rng right after the roll
+pub fn roll_structure_origin(seed: u32, x: i64, y: i64, z: i64) -> Option<u64> {
+    let mut rng = rng_from_xyz_seed(seed, x, y, z);
+    rng.gen_bool(structure_prob_at(x, y, z)).then(|| rng.gen())
+}
+
+// Rolls that have already been made. Neighboring origins are checked against
+// mostly the same chunks, so this keeps each chunk to a single roll.
+pub struct StructureRolls {
+    seed: u32,
+    rolls: HashMap<(i64, i64, i64), Option<u64>>,
+}
+
+impl StructureRolls {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            seed,
+            rolls: HashMap::new(),
+        }
+    }
+
+    pub fn get(&mut self, (x, y, z): (i64, i64, i64)) -> Option<u64> {
+        *self
+            .rolls
+            .entry((x, y, z))
+            .or_insert_with(|| roll_structure_origin(self.seed, x, y, z))
+    }
+}
+
+// Decides which of two origins that are too close together gets to keep its world structure.
+// The lower priority goes first, then the lower coordinates for the rare tie.
+pub fn has_origin_priority(
+    (a, a_priority): ((i64, i64, i64), u64),
+    (b, b_priority): ((i64, i64, i64), u64),
+) -> bool {
+    (a_priority, a) < (b_priority, b)
+}
+
+// Whether a world structure originates at x, y, z. Besides passing its own roll, no other chunk
+// that passes its roll can be within min_origin_dist chunks of it on every axis while having
+// priority over it. This is decided the same way from every chunk that looks at it, so
+// two origins that are too close together never both end up with a world structure.
+pub fn structure_origin_allowed(
+    rolls: &mut StructureRolls,
+    x: i64,
+    y: i64,
+    z: i64,
+    min_origin_dist: i64,
+) -> bool {
+    let Some(priority) = rolls.get((x, y, z)) else {
+        return false;
+    };
+
+    let d = min_origin_dist - 1;
+    for _x in x - d..=x + d {
+        for _y in y - d..=y + d {
+            for _z in z - d..=z + d {
+                let xyz = (_x, _y, _z);
+                if rolls
+                    .get(xyz)
+                    .is_some_and(|p| has_origin_priority((xyz, p), ((x, y, z), priority)))
+                {
+                    return false;
+                }
+            }
+        }
+    }
+
+    true
+}
+
+// Scans outwards in square rings on the x and z axes (at the same y level as origin)
+// for the closest chunk that a world structure originates in, up to max_radius chunks away.
+// Origins are spaced out the same way as when chunks are generated, so every one it finds
+// actually has a world structure.
+pub fn nearest_structure_chunk(
+    seed: u32,
+    origin: (i64, i64, i64),
+    max_radius: u32,
+    world_structures: &WorldStructureRegistry,
+) -> Option<(i64, i64, i64)> {
+    // Nothing to choose from, so no chunk gets a world structure
+    if world_structures.total_weight() <= 0.0 {
+        return None;
+    }
+
+    let min_origin_dist = world_structures.min_origin_dist();
+    let mut rolls = StructureRolls::new(seed);
+    let (x, y, z) = origin;
+    let max_radius = max_radius as i64;
+    let dist_sq = |(_x, _, _z): (i64, i64, i64)| (_x - x).pow(2) + (_z - z).pow(2);
+
+    let mut nearest: Option<(i64, i64, i64)> = None;
+
+    for r in 0..=max_radius {
+        // Chunks in ring r are at least r chunks away, so once the nearest
+        // chunk found so far is closer than that, no further ring can beat it
+        if let Some(n) = nearest {
+            if dist_sq(n) <= r * r {
+                break;
+            }
+        }
+
+        for dx in -r..=r {
+            for dz in -r..=r {
+                if dx.abs() != r && dz.abs() != r {
+                    continue;
+                }
+
+                let xyz = (x + dx, y, z + dz);
+                if !structure_origin_allowed(&mut rolls, xyz.0, xyz.1, xyz.2, min_origin_dist) {
+                    continue;
+                }
+
+                if nearest.is_none_or(|n| dist_sq(xyz) < dist_sq(n)) {
+                    nearest = Some(xyz);
+                }
+            }
+        }
+    }
+
+    nearest
+}
+
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
 pub enum Side {
     Top,
     Bottom,
@@ -24,7 +308,24 @@ pub enum Side {
     Down,
 }
 
-#[derive(Clone, Component, Debug, Default, Deserialize, Serialize)]
+impl Side {
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::Top => Self::Bottom,
+            Self::Bottom => Self::Top,
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+        }
+    }
+}
+
+// Marks the wall, floor or ceiling entities of a cell with the side of the cell they are on
+#[derive(Clone, Copy, Component, Debug)]
+pub struct CellWallSide(pub Side);
+
+#[derive(Clone, Component, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub struct Cell {
     pub wall_top: CellWall,
     pub wall_bottom: CellWall,
@@ -41,6 +342,15 @@ pub struct Cell {
     pub window_left: bool,
     pub window_right: bool,
     pub special: CellSpecial,
+    // Low railings along open edges that would otherwise drop off of a floor
+    #[serde(default)]
+    pub railing_top: bool,
+    #[serde(default)]
+    pub railing_bottom: bool,
+    #[serde(default)]
+    pub railing_left: bool,
+    #[serde(default)]
+    pub railing_right: bool,
 }
 
 impl Cell {
@@ -50,8 +360,88 @@ impl Cell {
             ..default()
         }
     }
+
+    pub fn wall(&self, side: Side) -> &CellWall {
+        match side {
+            Side::Top => &self.wall_top,
+            Side::Bottom => &self.wall_bottom,
+            Side::Left => &self.wall_left,
+            Side::Right => &self.wall_right,
+            Side::Up => &self.ceiling,
+            Side::Down => &self.floor,
+        }
+    }
+
+    pub fn railing(&self, side: Side) -> bool {
+        match side {
+            Side::Top => self.railing_top,
+            Side::Bottom => self.railing_bottom,
+            Side::Left => self.railing_left,
+            Side::Right => self.railing_right,
+            Side::Up | Side::Down => false,
+        }
+    }
+
+    pub fn set_railing(&mut self, side: Side, railing: bool) {
+        match side {
+            Side::Top => self.railing_top = railing,
+            Side::Bottom => self.railing_bottom = railing,
+            Side::Left => self.railing_left = railing,
+            Side::Right => self.railing_right = railing,
+            Side::Up | Side::Down => (),
+        }
+    }
 }
 
+// Puts railings along the edges of floored cells that lead straight into a cell without a floor,
+// unless a wall is already there. Cells with stairs are left open, since they are the way up
+// or down, and so are cells that a staircase in the chunk below comes up through. Edges of the
+// chunk are left alone, since what is on the other side is not known.
+pub fn add_railings(cells: &mut [Vec<Cell>], cells_below: Option<&[Vec<Cell>]>) {
+    let grid_size = cells.len();
+
+    for z in 0..grid_size {
+        for x in 0..grid_size {
+            if cells[z][x].floor == CellWall::None {
+                continue;
+            }
+
+            for side in [Side::Top, Side::Bottom, Side::Left, Side::Right] {
+                let (nei_x, nei_z) = match side {
+                    Side::Top => (x.checked_sub(1), Some(z)),
+                    Side::Bottom => (Some(x + 1), Some(z)),
+                    Side::Left => (Some(x), z.checked_sub(1)),
+                    Side::Right => (Some(x), Some(z + 1)),
+                    Side::Up | Side::Down => continue,
+                };
+                let (Some(nei_x), Some(nei_z)) = (nei_x, nei_z) else {
+                    continue;
+                };
+                let Some(nei) = cells.get(nei_z).and_then(|row| row.get(nei_x)) else {
+                    continue;
+                };
+
+                let stairs_arrive = cells_below
+                    .and_then(|below| below.get(nei_z))
+                    .and_then(|row| row.get(nei_x))
+                    .is_some_and(|cell_below| cell_below.special == CellSpecial::Staircase);
+
+                let open_drop = nei.floor == CellWall::None
+                    && !matches!(nei.special, CellSpecial::Staircase | CellSpecial::Stairs)
+                    && !stairs_arrive
+                    && *cells[z][x].wall(side) == CellWall::None;
+                if open_drop {
+                    cells[z][x].set_railing(side, true);
+                }
+            }
+        }
+    }
+}
+
+// Marks the railing entities of a cell with the side of the cell they are on
+#[derive(Clone, Copy, Component, Debug)]
+pub struct Railing(pub Side);
+
 #[derive(Clone, Debug, Default, Deserialize, Display, Eq, PartialEq, Serialize)]
 pub enum CellWall {
     #[default]
@@ -69,6 +459,8 @@ pub enum CellSpecial {
     TreasureChest,
     Staircase,
     Stairs, // Stairs currently run from -x to +x (going from base to peak)
+    SpikeTrap,
+    PoisonPool,
 }
 
 impl CellSpecial {
@@ -79,20 +471,87 @@ impl CellSpecial {
             Self::TreasureChest => 0.38,
             Self::Staircase => 0.18,
             Self::Stairs => 0.18,
+            Self::SpikeTrap => 0.06,
+            Self::PoisonPool => 0.06,
+        }
+    }
+
+    pub fn is_hazard(&self) -> bool {
+        matches!(self, Self::SpikeTrap | Self::PoisonPool)
+    }
+}
+
+// Changes made to a cell after it was generated, like a wall being dug out. They are
+// stored in WorldData and applied on top of the generated cell whenever it is spawned.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum CellMutation {
+    RemoveWallTop,
+    RemoveWallBottom,
+    RemoveWallLeft,
+    RemoveWallRight,
+    RemoveFloor,
+    RemoveCeiling,
+}
+
+impl CellMutation {
+    pub fn from_side(side: Side) -> Self {
+        match side {
+            Side::Top => Self::RemoveWallTop,
+            Side::Bottom => Self::RemoveWallBottom,
+            Side::Left => Self::RemoveWallLeft,
+            Side::Right => Self::RemoveWallRight,
+            Side::Up => Self::RemoveCeiling,
+            Side::Down => Self::RemoveFloor,
+        }
+    }
+
+    // Doors and windows are removed along with the wall they are in
+    pub fn apply(&self, cell: &mut Cell) {
+        match self {
+            Self::RemoveWallTop => {
+                cell.wall_top = CellWall::None;
+                cell.door_top = false;
+                cell.window_top = false;
+            }
+            Self::RemoveWallBottom => {
+                cell.wall_bottom = CellWall::None;
+                cell.door_bottom = false;
+                cell.window_bottom = false;
+            }
+            Self::RemoveWallLeft => {
+                cell.wall_left = CellWall::None;
+                cell.door_left = false;
+                cell.window_left = false;
+            }
+            Self::RemoveWallRight => {
+                cell.wall_right = CellWall::None;
+                cell.door_right = false;
+                cell.window_right = false;
+            }
+            Self::RemoveFloor => cell.floor = CellWall::None,
+            Self::RemoveCeiling => cell.ceiling = CellWall::None,
         }
     }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+// Sent to mutate a cell, which is recorded in WorldData and respawns the cell if it is spawned
+#[derive(Clone, Debug, Event)]
+pub struct CellMutated {
+    pub ccm: ChunkCellMarker,
+    pub mutation: CellMutation,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Chunk {
     pub x: i64,
     pub y: i64,
     pub z: i64,
     pub cells: Vec<Vec<Cell>>,
-    pub world_structure: WorldStructureName,
+    // Name of the world structure that this is the origin chunk of, otherwise NO_WORLD_STRUCTURE
+    pub world_structure: String,
 }
 
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, States)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Reflect, States)]
 pub struct ActiveChunk(pub i64, pub i64, pub i64);
 
 impl ActiveChunk {
@@ -104,6 +563,75 @@ impl ActiveChunk {
 #[derive(Component)]
 pub struct ChunkMarker(pub (i64, i64, i64));
 
+// Name of the world structure that a spawned chunk is part of, or NO_WORLD_STRUCTURE
+#[derive(Component)]
+pub struct ChunkWorldStructure(pub String);
+
+// Directional light that stands in for the sun while on the surface level
+#[derive(Component)]
+pub struct SkyLight;
+
+// Sent once a chunk's whole entity hierarchy has been spawned, so other systems
+// can attach things to it without polling for new ChunkMarkers
+#[derive(Event)]
+pub struct ChunkSpawned {
+    pub entity: Entity,
+    pub xyz: (i64, i64, i64),
+    pub chunk: Chunk,
+}
+
+#[derive(Event)]
+pub struct ChunkDespawned {
+    pub xyz: (i64, i64, i64),
+}
+
+// Entity of every spawned chunk, kept up to date from ChunkSpawned and ChunkDespawned events,
+// so that a specific chunk can be found without going through every ChunkMarker
+#[derive(Debug, Default, Resource)]
+pub struct ChunkIndex(HashMap<(i64, i64, i64), Entity>);
+
+impl ChunkIndex {
+    pub fn entity(&self, xyz: (i64, i64, i64)) -> Option<Entity> {
+        self.0.get(&xyz).copied()
+    }
+
+    pub fn insert(&mut self, xyz: (i64, i64, i64), entity: Entity) {
+        self.0.insert(xyz, entity);
+    }
+
+    pub fn remove(&mut self, xyz: (i64, i64, i64)) -> Option<Entity> {
+        self.0.remove(&xyz)
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = (i64, i64, i64)> + '_ {
+        self.0.keys().copied()
+    }
+
+    // Cells are spawned as direct children of their chunk
+    pub fn cell_entity(
+        &self,
+        ccm: &ChunkCellMarker,
+        children_query: &Query<&Children>,
+        ccm_query: &Query<&ChunkCellMarker>,
+    ) -> Option<Entity> {
+        let children = children_query.get(self.entity(ccm.chunk_xyz())?).ok()?;
+        children
+            .iter()
+            .copied()
+            .find(|child| ccm_query.get(*child).is_ok_and(|c| c == ccm))
+    }
+}
+
+// Chunks that fall out of render distance are kept for a few frames before being despawned,
+// so they do not need to be respawned if they come back into render distance right away
+#[derive(Component)]
+pub struct StaleChunk(pub IncrCounter);
+
+// Chunks that are being generated off of the main thread, and are spawned once their task finishes.
+// Dropping a task cancels it, so chunks that are no longer needed are removed from here.
+#[derive(Default, Resource)]
+pub struct PendingChunks(pub HashMap<(i64, i64, i64), Task<Chunk>>);
+
 #[derive(Clone, Component, Debug, Default, Eq, Hash, PartialEq)]
 pub struct ChunkCellMarker {
     pub chunk_x: i64,
@@ -114,28 +642,40 @@ pub struct ChunkCellMarker {
 }
 
 impl ChunkCellMarker {
-    pub fn from_global_transform(gt: &GlobalTransform, chunk_size: f32, cell_size: f32) -> Self {
-        let tl = gt.translation();
+    pub fn new(chunk_xyz: (i64, i64, i64), cell_xz: (usize, usize)) -> Self {
+        let ((chunk_x, chunk_y, chunk_z), (x, z)) = (chunk_xyz, cell_xz);
+        Self {
+            chunk_x,
+            chunk_y,
+            chunk_z,
+            x,
+            z,
+        }
+    }
 
-        let grid_size_minus_one = (chunk_size / cell_size) - 1.0;
+    pub fn from_global_transform(gt: &GlobalTransform, dims: &WorldDims) -> Self {
+        let tl = gt.translation();
+        let (chunk_size, cell_size) = (dims.chunk_size(), dims.cell_size());
+        let grid_size = dims.grid_size() as i64;
         let half_chunk_size = chunk_size / 2.0;
 
-        // Calculate the offset for centering at (0, 0, 0)
-        let offset_x = tl.x + half_chunk_size;
-        let offset_z = tl.z + half_chunk_size;
-
-        // Calculate chunk coordinates
-        let chunk_x = (offset_x / chunk_size).floor() as i64;
+        // Index of the cell along an axis counting across the whole world, with chunk (0, 0, 0)
+        // centered at the origin. Splitting it into a chunk and a local index with integer math
+        // keeps the two consistent on both sides of zero, where float rounding could otherwise
+        // put a position on a chunk's edge into the wrong chunk, or past its last cell.
+        let split = |pos: f32| {
+            let world_index = ((pos + half_chunk_size) / cell_size).floor() as i64;
+            let local = world_index.rem_euclid(grid_size);
+            // Cells are stored from +x to -x (and +z to -z)
+            (
+                world_index.div_euclid(grid_size),
+                (grid_size - 1 - local) as usize,
+            )
+        };
+
+        let (chunk_x, x) = split(tl.x);
+        let (chunk_z, z) = split(tl.z);
         let chunk_y = (tl.y / cell_size).floor() as i64;
-        let chunk_z = (offset_z / chunk_size).floor() as i64;
-
-        // Calculate local position within the chunk
-        let x = (grid_size_minus_one
-            - ((offset_x - (chunk_x as f32 * chunk_size)) / cell_size).floor())
-            as usize;
-        let z = (grid_size_minus_one
-            - ((offset_z - (chunk_z as f32 * chunk_size)) / cell_size).floor())
-            as usize;
 
         Self {
             chunk_x,
@@ -146,6 +686,22 @@ impl ChunkCellMarker {
         }
     }
 
+    // World position of the center of the cell's floor, matching the translations
+    // of the chunk and cell bundles. The inverse of from_global_transform.
+    pub fn to_world_position(&self, chunk_size: f32, cell_size: f32) -> Vec3 {
+        let grid_size = chunk_size / cell_size;
+        let floor_pos = |index: usize| (grid_size / 2.0 - index as f32 - 0.5) * cell_size;
+        Vec3::new(
+            self.chunk_x as f32 * chunk_size + floor_pos(self.x),
+            self.chunk_y as f32 * cell_size,
+            self.chunk_z as f32 * chunk_size + floor_pos(self.z),
+        )
+    }
+
+    pub fn world_position(&self, dims: &WorldDims) -> Vec3 {
+        self.to_world_position(dims.chunk_size(), dims.cell_size())
+    }
+
     pub fn to_rng(&self) -> StdRng {
         rng_from_str(format!(
             "{},{},{}_{},{}",
@@ -160,55 +716,156 @@ impl ChunkCellMarker {
     pub fn cell_xz(&self) -> (usize, usize) {
         (self.x, self.z)
     }
+
+    // The cell on the other side of the given side of this one, which may be in another chunk.
+    // Cells are stored from +x to -x (and +z to -z), and Top walls are on the +x side
+    // of a cell, Left walls on the +z side.
+    pub fn neighbor(&self, side: Side, grid_size: usize) -> Self {
+        let mut nei = self.clone();
+        let last = grid_size - 1;
+
+        match side {
+            Side::Top if self.x == 0 => (nei.chunk_x, nei.x) = (self.chunk_x + 1, last),
+            Side::Top => nei.x -= 1,
+            Side::Bottom if self.x == last => (nei.chunk_x, nei.x) = (self.chunk_x - 1, 0),
+            Side::Bottom => nei.x += 1,
+            Side::Left if self.z == 0 => (nei.chunk_z, nei.z) = (self.chunk_z + 1, last),
+            Side::Left => nei.z -= 1,
+            Side::Right if self.z == last => (nei.chunk_z, nei.z) = (self.chunk_z - 1, 0),
+            Side::Right => nei.z += 1,
+            Side::Up => nei.chunk_y += 1,
+            Side::Down => nei.chunk_y -= 1,
+        }
+
+        nei
+    }
 }
 
+// Seconds per transform when converting lists of per-frame transforms into keyframes
+pub const CYCLIC_TRANSFORM_FRAME_SECS: f32 = 1.0 / 60.0;
+
+// Cycles between animations, each made up of keyframes. The seconds paired with
+// a keyframe are how long it takes to get there from the previous keyframe.
 #[derive(Component)]
 pub struct CyclicTransform {
     counter: CyclicCounter,
-    transforms: HashMap<u32, Vec<Transform>>,
-    index: Option<usize>,
+    keyframes: HashMap<u32, Vec<(Transform, f32)>>,
+    // Seconds into the current animation, or None if it is not playing
+    elapsed_secs: Option<f32>,
 }
 
 impl CyclicTransform {
-    fn new(transforms: Vec<Vec<Transform>>) -> Self {
-        let mut hm: HashMap<u32, Vec<Transform>> = HashMap::new();
-        for (i, v) in transforms.iter().enumerate() {
+    fn from_keyframes(keyframes: Vec<Vec<(Transform, f32)>>) -> Self {
+        let mut hm: HashMap<u32, Vec<(Transform, f32)>> = HashMap::new();
+        for (i, v) in keyframes.iter().enumerate() {
             hm.insert(i as u32, v.clone());
         }
 
         Self {
-            counter: CyclicCounter::new(0, (transforms.len() - 1) as u32),
-            transforms: hm,
-            index: None,
+            counter: CyclicCounter::new(0, (keyframes.len() - 1) as u32),
+            keyframes: hm,
+            elapsed_secs: None,
         }
     }
 
-    pub fn new_cycled(transforms: Vec<Vec<Transform>>) -> Self {
-        let mut ct = Self::new(transforms);
+    pub fn new_cycled_from_keyframes(keyframes: Vec<Vec<(Transform, f32)>>) -> Self {
+        let mut ct = Self::from_keyframes(keyframes);
         ct.counter.cycle();
         ct
     }
 
+    // Every transform becomes a keyframe lasting one frame at 60 fps
+    pub fn new_cycled(transforms: Vec<Vec<Transform>>) -> Self {
+        let keyframes = transforms
+            .into_iter()
+            .map(|v| {
+                let durrs = iter::once(0.0).chain(iter::repeat(CYCLIC_TRANSFORM_FRAME_SECS));
+                v.into_iter().zip(durrs).collect()
+            })
+            .collect();
+
+        Self::new_cycled_from_keyframes(keyframes)
+    }
+
     pub fn cycle(&mut self) -> u32 {
-        self.index = Some(0);
+        self.elapsed_secs = Some(0.0);
         self.counter.cycle()
     }
 
-    pub fn tick(&mut self) -> Option<&Transform> {
-        if let Some(i) = self.index {
-            let transforms = self.transforms.get(&self.counter.value()).unwrap();
-            let next_index = i + 1;
-            let max_index = transforms.len() - 1;
+    // Whether the animation for a cycle value is the current one and has finished playing
+    pub fn is_in_state(&self, n: u32) -> bool {
+        self.counter.value() == n && self.elapsed_secs.is_none()
+    }
+
+    pub fn tick(&mut self, delta: Duration) -> Option<Transform> {
+        let elapsed_secs = self.elapsed_secs? + delta.as_secs_f32();
+        let keyframes = self.keyframes.get(&self.counter.value()).unwrap();
+
+        let mut start = keyframes.first()?.0;
+        let mut secs = 0.0;
+        for (end, durr) in keyframes.iter() {
+            if elapsed_secs < secs + durr {
+                self.elapsed_secs = Some(elapsed_secs);
+                return Some(lerp_transforms(start, *end, (elapsed_secs - secs) / durr));
+            }
+            start = *end;
+            secs += durr;
+        }
+
+        // The final keyframe is always landed on exactly, however long the last frame was
+        self.elapsed_secs = None;
+        Some(start)
+    }
+}
+
+// Cycles a CyclicTransform again once it has rested in its open state for a number of frames,
+// but never while something is inside of the sensor region, which is in the parent's local space
+#[derive(Component)]
+pub struct AutoClose {
+    pub after_frames: u32,
+    pub open_state: u32,
+    pub sensor_min: Vec3,
+    pub sensor_max: Vec3,
+    // Frames spent in the open state, or None if it is not open
+    frames: Option<u32>,
+}
+
+impl AutoClose {
+    pub fn new(after_frames: u32, open_state: u32, sensor_min: Vec3, sensor_max: Vec3) -> Self {
+        Self {
+            after_frames,
+            open_state,
+            sensor_min,
+            sensor_max,
+            frames: None,
+        }
+    }
+
+    pub fn sensor_contains(&self, point: Vec3) -> bool {
+        point.cmpge(self.sensor_min).all() && point.cmple(self.sensor_max).all()
+    }
 
-            self.index = if next_index > max_index {
-                None
-            } else {
-                Some(next_index)
-            };
+    // Returns true once it is time to close. Leaving the open state some other way,
+    // like being closed by hand, cancels the count. Being blocked only holds off closing,
+    // so it closes as soon as whatever was blocking it moves out of the way.
+    pub fn tick(&mut self, is_open: bool, blocked: bool) -> bool {
+        if !is_open {
+            self.frames = None;
+            return false;
+        }
 
-            return transforms.get(i);
+        let frames = self.frames.get_or_insert(0);
+        *frames = frames.saturating_add(1);
+        if *frames < self.after_frames || blocked {
+            return false;
         }
-        None
+
+        self.frames = None;
+        true
+    }
+
+    pub fn is_counting(&self) -> bool {
+        self.frames.is_some()
     }
 }
 
@@ -230,3 +887,49 @@ impl EntitySpawner for ChildBuilder<'_> {
 
 #[derive(Component)]
 pub struct OCItemContainer;
+
+// Keeps a container shut until a key of at least the same tier is used on it
+#[derive(Clone, Copy, Component, Debug, Eq, PartialEq)]
+pub struct Locked {
+    pub tier: u8,
+}
+
+impl Locked {
+    pub fn opens_with(&self, key_tier: u8) -> bool {
+        key_tier >= self.tier
+    }
+}
+
+// Glass of a window on a side of its cell, which breaks once it has taken enough damage
+#[derive(Clone, Copy, Component, Debug)]
+pub struct WindowPane(pub Side);
+
+// Sent once a key has been used up on a Locked container, which is no longer Locked
+#[derive(Event)]
+pub struct ContainerUnlocked(pub Entity);
+
+// Marks an item that was dropped by the player, with the id of its DroppedItemData
+#[derive(Component)]
+pub struct DroppedItem(pub u64);
+
+// Keeps an item from being picked up automatically, for a number of frames and then for as long
+// as the player stays close enough to pick it up, so that items are not picked right back up
+// after being dropped, or retried every frame while they do not fit in the inventory
+#[derive(Component, Debug)]
+pub struct PickUpGrace(pub u32);
+
+impl PickUpGrace {
+    // Returns whether the grace period is over
+    pub fn tick(&mut self, in_range: bool) -> bool {
+        self.0 = self.0.saturating_sub(1);
+        self.0 == 0 && !in_range
+    }
+}
+
+// Purely visual props scattered around a cell. They have no colliders.
+#[derive(Clone, Copy, Component, Debug, Eq, Hash, PartialEq)]
+pub enum Decor {
+    Rubble,
+    Cobweb,
+    Crack,
+}
@@ -0,0 +1,217 @@
+use crate::world::{Cell, CellSpecial, CellWall, Chunk, ChunkCellMarker, Side, WorldDims};
+use bevy::{prelude::Vec3, utils::HashMap};
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+const HORIZONTAL_SIDES: [Side; 4] = [Side::Top, Side::Bottom, Side::Left, Side::Right];
+// Cost of walking into a neighboring cell
+const WALK_COST: u32 = 1;
+// Cost of falling or climbing into a neighboring cell one y level up or down,
+// which is one cell across and one cell up or down
+const CLIMB_COST: u32 = 2;
+
+// Navigation graph over the cells of the chunks around an origin chunk, where every cell
+// with a floor is a node. Chunks are only generated once a search reaches them, and chunks
+// further than max_chunk_radius from the origin on any axis are never generated at all,
+// which bounds how much a single search can cost.
+pub struct NavGraph<F: FnMut((i64, i64, i64)) -> Chunk> {
+    dims: WorldDims,
+    origin: (i64, i64, i64),
+    max_chunk_radius: u32,
+    chunks: HashMap<(i64, i64, i64), Chunk>,
+    gen_chunk: F,
+}
+
+impl<F: FnMut((i64, i64, i64)) -> Chunk> NavGraph<F> {
+    pub fn new(
+        dims: WorldDims,
+        origin: (i64, i64, i64),
+        max_chunk_radius: u32,
+        gen_chunk: F,
+    ) -> Self {
+        Self {
+            dims,
+            origin,
+            max_chunk_radius,
+            chunks: HashMap::new(),
+            gen_chunk,
+        }
+    }
+
+    fn in_range(&self, (x, y, z): (i64, i64, i64)) -> bool {
+        let (ox, oy, oz) = self.origin;
+        let r = self.max_chunk_radius as i64;
+        (x - ox).abs() <= r && (y - oy).abs() <= r && (z - oz).abs() <= r
+    }
+
+    fn cell(&mut self, ccm: &ChunkCellMarker) -> Option<Cell> {
+        let xyz = ccm.chunk_xyz();
+        if !self.in_range(xyz) {
+            return None;
+        }
+
+        let chunk = self
+            .chunks
+            .entry(xyz)
+            .or_insert_with(|| (self.gen_chunk)(xyz));
+        chunk.cells.get(ccm.z)?.get(ccm.x).cloned()
+    }
+
+    fn is_node(&mut self, ccm: &ChunkCellMarker) -> bool {
+        self.cell(ccm)
+            .is_some_and(|cell| cell.floor == CellWall::Solid)
+    }
+
+    // Cells on the same y level that can be walked into from a cell, with or without a floor
+    fn walkable_neighbors(
+        &mut self,
+        ccm: &ChunkCellMarker,
+        cell: &Cell,
+    ) -> Vec<(ChunkCellMarker, Cell)> {
+        let grid_size = self.dims.grid_size();
+        let mut neighbors = Vec::new();
+
+        for side in HORIZONTAL_SIDES {
+            if !is_passable(cell.wall(side)) {
+                continue;
+            }
+
+            // Each cell has walls of its own, so the neighbor's wall has to be passable too
+            let nei = ccm.neighbor(side, grid_size);
+            if let Some(nei_cell) = self.cell(&nei) {
+                if is_passable(nei_cell.wall(side.opposite())) {
+                    neighbors.push((nei, nei_cell));
+                }
+            }
+        }
+
+        neighbors
+    }
+
+    // Nodes that can be reached from a node, along with the cost of getting there
+    fn edges(&mut self, ccm: &ChunkCellMarker) -> Vec<(ChunkCellMarker, u32)> {
+        let Some(cell) = self.cell(ccm) else {
+            return Vec::new();
+        };
+        let mut edges = Vec::new();
+
+        for (nei, nei_cell) in self.walkable_neighbors(ccm, &cell) {
+            if nei_cell.floor == CellWall::Solid {
+                edges.push((nei, WALK_COST));
+                continue;
+            }
+
+            // Walking into a hole in the floor drops down into the cell below it,
+            // which is a one way trip
+            let below = nei.neighbor(Side::Down, self.dims.grid_size());
+            if self.is_node(&below) {
+                edges.push((below, CLIMB_COST));
+            }
+        }
+
+        // Stairs lead up through a hole in the ceiling, into the cell above them. That cell has
+        // no floor of its own, so stepping off of the stairs is what actually reaches a node.
+        // Walking back down onto the stairs is covered by dropping into the hole above them.
+        let is_stairs = matches!(cell.special, CellSpecial::Staircase | CellSpecial::Stairs);
+        if is_stairs && cell.ceiling == CellWall::None {
+            let above = ccm.neighbor(Side::Up, self.dims.grid_size());
+            if let Some(above_cell) = self.cell(&above) {
+                for (nei, nei_cell) in self.walkable_neighbors(&above, &above_cell) {
+                    if nei_cell.floor == CellWall::Solid {
+                        edges.push((nei, CLIMB_COST));
+                    }
+                }
+            }
+        }
+
+        edges
+    }
+
+    // Position of a cell counted in cells from the world origin, increasing towards +x and +z
+    fn global_cell(&self, ccm: &ChunkCellMarker) -> (i64, i64, i64) {
+        let grid_size = self.dims.grid_size() as i64;
+        (
+            ccm.chunk_x * grid_size - ccm.x as i64,
+            ccm.chunk_y,
+            ccm.chunk_z * grid_size - ccm.z as i64,
+        )
+    }
+
+    // Every edge moves at most one cell along each axis for each point of cost,
+    // so the manhattan distance never overestimates the cost left to reach the goal
+    fn heuristic(&self, a: &ChunkCellMarker, b: &ChunkCellMarker) -> u32 {
+        let (ax, ay, az) = self.global_cell(a);
+        let (bx, by, bz) = self.global_cell(b);
+        ((ax - bx).abs() + (ay - by).abs() + (az - bz).abs()) as u32
+    }
+
+    // Shortest path between two cells with A*, including both of them,
+    // or None if there is no path between them inside of the search radius
+    pub fn find_cell_path(
+        &mut self,
+        start: &ChunkCellMarker,
+        goal: &ChunkCellMarker,
+    ) -> Option<Vec<ChunkCellMarker>> {
+        if !self.is_node(start) || !self.is_node(goal) {
+            return None;
+        }
+
+        let key = |ccm: &ChunkCellMarker| (ccm.chunk_xyz(), ccm.cell_xz());
+
+        let mut open = BinaryHeap::new();
+        let mut costs: HashMap<ChunkCellMarker, u32> = HashMap::new();
+        let mut came_from: HashMap<ChunkCellMarker, ChunkCellMarker> = HashMap::new();
+
+        open.push(Reverse((self.heuristic(start, goal), key(start))));
+        costs.insert(start.clone(), 0);
+
+        while let Some(Reverse((estimate, (chunk_xyz, cell_xz)))) = open.pop() {
+            let ccm = ChunkCellMarker::new(chunk_xyz, cell_xz);
+            if ccm == *goal {
+                let mut path = vec![ccm];
+                while let Some(prev) = came_from.get(path.last().unwrap()) {
+                    path.push(prev.clone());
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            let cost = costs[&ccm];
+            // Stale entry for a node that has since been reached more cheaply
+            if estimate > cost + self.heuristic(&ccm, goal) {
+                continue;
+            }
+
+            for (nei, edge_cost) in self.edges(&ccm) {
+                let nei_cost = cost + edge_cost;
+                if costs.get(&nei).is_some_and(|c| *c <= nei_cost) {
+                    continue;
+                }
+
+                open.push(Reverse((nei_cost + self.heuristic(&nei, goal), key(&nei))));
+                costs.insert(nei.clone(), nei_cost);
+                came_from.insert(nei, ccm.clone());
+            }
+        }
+
+        None
+    }
+
+    // Same as find_cell_path, but as waypoints at the center of each cell's floor
+    pub fn find_path(
+        &mut self,
+        start: &ChunkCellMarker,
+        goal: &ChunkCellMarker,
+    ) -> Option<Vec<Vec3>> {
+        let path = self.find_cell_path(start, goal)?;
+        Some(
+            path.iter()
+                .map(|ccm| ccm.world_position(&self.dims))
+                .collect(),
+        )
+    }
+}
+
+// Door gaps are passable whether the door in them is open or not
+fn is_passable(wall: &CellWall) -> bool {
+    matches!(wall, CellWall::None | CellWall::SolidWithDoorGap)
+}
@@ -0,0 +1,168 @@
+use crate::world::ActiveChunk;
+use bevy::prelude::{Component, Handle, Mesh, StandardMaterial, Vec3};
+use rand::{rngs::StdRng, Rng};
+
+const DUST_FALL_SPEED: f32 = 0.25;
+const DUST_DRIFT_SPEED: f32 = 0.08;
+const DRIP_GRAVITY: f32 = 9.8;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ParticleKind {
+    // Drifts down slowly from anywhere across the ceiling
+    Dust,
+    // Falls from a single spot, leaving a ring behind where it lands
+    Drip,
+}
+
+impl ParticleKind {
+    pub fn pool_size(&self) -> usize {
+        match self {
+            Self::Dust => 12,
+            Self::Drip => 3,
+        }
+    }
+
+    fn secs_between_particles(&self, rng: &mut StdRng) -> f32 {
+        match self {
+            Self::Dust => rng.gen_range(0.8..2.0),
+            Self::Drip => rng.gen_range(1.5..4.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Particle {
+    pub position: Vec3,
+    pub alive: bool,
+    velocity: Vec3,
+}
+
+// CPU side particle emitter hanging from an open ceiling, in the local space of its cell.
+// Particles come from a small fixed pool and are reused once they reach the floor,
+// so the entities that draw them never have to be spawned or despawned.
+#[derive(Component)]
+pub struct ParticleEmitter {
+    pub kind: ParticleKind,
+    pub chunk_xyz: (i64, i64, i64),
+    // Where particles start from, with dust starting anywhere within spread of it on x and z
+    origin: Vec3,
+    spread: f32,
+    // Particles are gone once they fall below this
+    floor_y: f32,
+    particles: Vec<Particle>,
+    secs_until_next: f32,
+    rng: StdRng,
+}
+
+impl ParticleEmitter {
+    pub fn new(
+        kind: ParticleKind,
+        chunk_xyz: (i64, i64, i64),
+        origin: Vec3,
+        spread: f32,
+        floor_y: f32,
+        rng: StdRng,
+    ) -> Self {
+        Self {
+            kind,
+            chunk_xyz,
+            origin,
+            spread,
+            floor_y,
+            particles: vec![Particle::default(); kind.pool_size()],
+            secs_until_next: 0.0,
+            rng,
+        }
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    pub fn floor_y(&self) -> f32 {
+        self.floor_y
+    }
+
+    // Moves every particle along and lets out a new one once it is time to,
+    // then returns the positions on the floor where any drips landed
+    pub fn tick(&mut self, delta_secs: f32) -> Vec<Vec3> {
+        let mut landed = Vec::new();
+
+        for particle in self.particles.iter_mut().filter(|p| p.alive) {
+            if self.kind == ParticleKind::Drip {
+                particle.velocity.y -= DRIP_GRAVITY * delta_secs;
+            }
+            particle.position += particle.velocity * delta_secs;
+
+            if particle.position.y <= self.floor_y {
+                particle.alive = false;
+                if self.kind == ParticleKind::Drip {
+                    landed.push(particle.position.with_y(self.floor_y));
+                }
+            }
+        }
+
+        self.secs_until_next -= delta_secs;
+        if self.secs_until_next <= 0.0 {
+            self.secs_until_next = self.kind.secs_between_particles(&mut self.rng);
+
+            // Skipped when the whole pool is still falling
+            if let Some(i) = self.particles.iter().position(|p| !p.alive) {
+                self.particles[i] = self.new_particle();
+            }
+        }
+
+        landed
+    }
+
+    fn new_particle(&mut self) -> Particle {
+        let (position, velocity) = match self.kind {
+            ParticleKind::Dust => (
+                self.origin
+                    + Vec3::new(
+                        self.rng.gen_range(-self.spread..=self.spread),
+                        0.0,
+                        self.rng.gen_range(-self.spread..=self.spread),
+                    ),
+                Vec3::new(
+                    self.rng.gen_range(-DUST_DRIFT_SPEED..=DUST_DRIFT_SPEED),
+                    -DUST_FALL_SPEED * self.rng.gen_range(0.5..1.0),
+                    self.rng.gen_range(-DUST_DRIFT_SPEED..=DUST_DRIFT_SPEED),
+                ),
+            ),
+            ParticleKind::Drip => (self.origin, Vec3::ZERO),
+        };
+
+        Particle {
+            position,
+            alive: true,
+            velocity,
+        }
+    }
+}
+
+// Emitters are only worth updating close to the player,
+// in the active chunk or any of the chunks touching it
+pub fn is_near_active_chunk(chunk_xyz: (i64, i64, i64), active_chunk: &ActiveChunk) -> bool {
+    let (x, y, z) = chunk_xyz;
+    (x - active_chunk.0).abs() <= 1
+        && (y - active_chunk.1).abs() <= 1
+        && (z - active_chunk.2).abs() <= 1
+}
+
+// Draws the particle at this index of its parent emitter's pool
+#[derive(Component)]
+pub struct EmitterParticle(pub usize);
+
+// Ring left on the floor by a landed drip, which grows until it is gone
+#[derive(Component, Default)]
+pub struct DripRing {
+    pub age_secs: f32,
+}
+
+// Shared by every ring a drip emitter leaves behind
+#[derive(Component)]
+pub struct DripRingHandles {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<StandardMaterial>,
+}
@@ -1,80 +1,465 @@
-use crate::world::Chunk;
-use bevy::prelude::{Asset, TypePath};
+use crate::{
+    error::Error,
+    inventory::item::{Item, ItemName},
+    should_not_happen,
+    utils::find_one,
+    world::{add_railings, Cell, Chunk},
+};
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+};
 use rand::{rngs::StdRng, Rng};
 use serde::{Deserialize, Serialize};
-use strum::{IntoEnumIterator, VariantArray};
-use strum_macros::{Display, EnumIter, VariantArray};
+use std::{fs, path::Path, sync::Arc};
 
-#[derive(Asset, Clone, Deserialize, Serialize, TypePath)]
+// Chunks that are not part of a world structure are labeled with this instead of a name
+pub const NO_WORLD_STRUCTURE: &str = "None";
+// Generated at the origin chunk of new worlds instead of leaving it up to chance. Its weight
+// is 0, so that it is never picked anywhere else.
+pub const STARTING_ROOM: &str = "StartingRoom";
+
+// Compiled in, for when the world structures folder has not been loaded
+fn starting_room_fallback() -> Option<WorldStructure> {
+    let content = include_str!("../../../../assets/world_structures/StartingRoom.json");
+    serde_json::from_str(content)
+        .inspect_err(|err| warn!("Error parsing the compiled in starting room: {}", err))
+        .ok()
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct WorldStructure {
+    // Files written before names and radii were stored in them are named after the file,
+    // and get the smallest radius that covers all of their chunks
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub radius: u32,
+    // Relative to the weights of the other world structures
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    // Extra chunks kept empty between this and any other world structure
+    #[serde(default)]
+    pub spacing: u32,
+    #[serde(default)]
+    pub theme: Option<StructureTheme>,
     pub chunks: Vec<Chunk>,
 }
 
-#[derive(
-    Clone,
-    Debug,
-    Default,
-    Deserialize,
-    Display,
-    EnumIter,
-    Eq,
-    Hash,
-    PartialEq,
-    Serialize,
-    VariantArray,
-)]
-pub enum WorldStructureName {
-    #[default]
-    None,
-    EmptySpace1,
-    FilledWithChairs1,
-    House1,
-    StairsAltar1,
-    StaircaseTower2,
+// Sets the chunks of a world structure apart from the rest of the maze.
+// Colors are linear rgb, and anything left out looks the same as everywhere else.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct StructureTheme {
+    // Tints the wall textures
+    #[serde(default)]
+    pub wall_color: Option<[f32; 3]>,
+    #[serde(default)]
+    pub floor_color: Option<[f32; 3]>,
+    // Treasure chests inside of the structure hold one of these instead of any random item
+    #[serde(default)]
+    pub loot: Vec<ItemName>,
+}
+
+impl StructureTheme {
+    pub fn wall_color(&self) -> Option<Color> {
+        self.wall_color.map(|[r, g, b]| Color::linear_rgb(r, g, b))
+    }
+
+    pub fn floor_color(&self) -> Option<Color> {
+        self.floor_color.map(|[r, g, b]| Color::linear_rgb(r, g, b))
+    }
+
+    // None if the theme leaves the loot up to chance
+    pub fn choose_loot(&self, rng: &mut StdRng) -> Option<Item> {
+        if self.loot.is_empty() {
+            return None;
+        }
+
+        let name = self.loot[rng.gen_range(0..self.loot.len())];
+        let amt = rng.gen_range(1..=3).min(name.max_amt());
+        Some(Item::new(name, amt))
+    }
 }
 
-impl WorldStructureName {
-    pub fn radius(&self) -> u32 {
-        match self {
-            Self::None => 0,
-            Self::EmptySpace1 | Self::FilledWithChairs1 | Self::House1 | Self::StairsAltar1 => 1,
-            Self::StaircaseTower2 => 2,
+impl WorldStructure {
+    pub fn calc_radius(&self) -> u32 {
+        self.chunks
+            .iter()
+            .map(|chunk| chunk.x.abs().max(chunk.y.abs()).max(chunk.z.abs()) as u32 + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Checks that the structure has a name and a non-negative weight, that every chunk has
+    // a grid_size x grid_size grid of cells, that no two chunks share coordinates, that
+    // every chunk is within the radius of the structure, and that exactly one chunk
+    // (the origin, at 0,0,0) is labeled with the name of the structure
+    pub fn validate(&self, grid_size: usize) -> Result<(), Error> {
+        let err = |field: String, msg: String| Error::WorldStructure { field, msg };
+        let name = &self.name;
+
+        if name.is_empty() || name == NO_WORLD_STRUCTURE {
+            return Err(err(
+                "name".to_owned(),
+                format!("expected a name other than \"{}\"", name),
+            ));
+        }
+
+        if self.weight < 0.0 {
+            return Err(err(
+                "weight".to_owned(),
+                format!("expected a weight of at least 0, found {}", self.weight),
+            ));
+        }
+
+        let mut origin_indexes = Vec::new();
+
+        for (i, chunk) in self.chunks.iter().enumerate() {
+            if chunk.cells.len() != grid_size {
+                return Err(err(
+                    format!("chunks[{}].cells", i),
+                    format!("expected {} rows, found {}", grid_size, chunk.cells.len()),
+                ));
+            }
+            for (j, row) in chunk.cells.iter().enumerate() {
+                if row.len() != grid_size {
+                    return Err(err(
+                        format!("chunks[{}].cells[{}]", i, j),
+                        format!("expected {} cells, found {}", grid_size, row.len()),
+                    ));
+                }
+            }
+
+            if let Some(k) = self.chunks[..i]
+                .iter()
+                .position(|ch| (ch.x, ch.y, ch.z) == (chunk.x, chunk.y, chunk.z))
+            {
+                return Err(err(
+                    format!("chunks[{}]", i),
+                    format!(
+                        "coordinates ({}, {}, {}) are already used by chunks[{}]",
+                        chunk.x, chunk.y, chunk.z, k
+                    ),
+                ));
+            }
+
+            let offset = chunk.x.abs().max(chunk.y.abs()).max(chunk.z.abs());
+            if offset >= self.radius as i64 {
+                return Err(err(
+                    format!("chunks[{}]", i),
+                    format!(
+                        "coordinates ({}, {}, {}) are outside of the radius ({}) of {}",
+                        chunk.x, chunk.y, chunk.z, self.radius, name
+                    ),
+                ));
+            }
+
+            if chunk.world_structure == *name {
+                origin_indexes.push(i);
+            } else if chunk.world_structure != NO_WORLD_STRUCTURE {
+                return Err(err(
+                    format!("chunks[{}].world_structure", i),
+                    format!(
+                        "expected {} or {}, found {}",
+                        name, NO_WORLD_STRUCTURE, chunk.world_structure
+                    ),
+                ));
+            }
+        }
+
+        match origin_indexes.as_slice() {
+            [i] => {
+                let origin = &self.chunks[*i];
+                if (origin.x, origin.y, origin.z) != (0, 0, 0) {
+                    return Err(err(
+                        format!("chunks[{}]", i),
+                        format!(
+                            "expected the origin chunk to be at (0, 0, 0), found ({}, {}, {})",
+                            origin.x, origin.y, origin.z
+                        ),
+                    ));
+                }
+                Ok(())
+            }
+            _ => Err(err(
+                "chunks".to_owned(),
+                format!(
+                    "expected exactly 1 chunk labeled {}, found {}",
+                    name,
+                    origin_indexes.len()
+                ),
+            )),
+        }
+    }
+
+    // Every chunk of the structure, moved so that its origin chunk is at x, y, z.
+    // Each of them is labeled with the name of the structure, so that they all get its theme.
+    pub fn gen_chunks(&self, x: i64, y: i64, z: i64) -> Vec<Chunk> {
+        self.chunks
+            .iter()
+            .map(|chunk| {
+                let mut chunk = Chunk {
+                    x: chunk.x + x,
+                    y: chunk.y + y,
+                    z: chunk.z + z,
+                    world_structure: self.name.clone(),
+                    ..chunk.clone()
+                };
+                let cells_below = self.cells_below(&chunk, x, y, z);
+                add_railings(&mut chunk.cells, cells_below);
+                chunk
+            })
+            .collect()
+    }
+
+    // None if the structure does not have exactly one origin chunk, which validate catches
+    // for structures loaded from files, but not for ones put together some other way
+    pub fn gen_origin_chunk(&self, x: i64, y: i64, z: i64) -> Option<Chunk> {
+        let origin = match find_one(&self.chunks, |chunk| chunk.world_structure == self.name) {
+            Ok(origin) => origin,
+            Err(err) => {
+                should_not_happen!("origin chunk of world structure {}: {}", self.name, err);
+                return None;
+            }
+        };
+
+        let mut chunk = Chunk {
+            x: origin.x + x,
+            y: origin.y + y,
+            z: origin.z + z,
+            ..origin.clone()
+        };
+        let cells_below = self.cells_below(&chunk, x, y, z);
+        add_railings(&mut chunk.cells, cells_below);
+        Some(chunk)
+    }
+
+    // Cells of the structure's chunk right below a chunk that was moved by x, y, z, if any,
+    // for finding where staircases come up from below
+    fn cells_below(&self, chunk: &Chunk, x: i64, y: i64, z: i64) -> Option<&[Vec<Cell>]> {
+        let xyz_below = (chunk.x - x, chunk.y - y - 1, chunk.z - z);
+        self.chunks
+            .iter()
+            .find(|ch| (ch.x, ch.y, ch.z) == xyz_below)
+            .map(|ch| ch.cells.as_slice())
+    }
+}
+
+// Parses a world structure file, which holds either a single world structure or a list of them.
+// A single world structure without a name is named after the file, for files that were
+// written before names were stored in them.
+pub fn parse_world_structures(
+    content: &str,
+    file_stem: &str,
+    grid_size: usize,
+) -> Result<Vec<WorldStructure>, Error> {
+    let value: serde_json::Value =
+        serde_json::from_str(content).map_err(|err| Error::Parsing(err.to_string()))?;
+
+    let mut world_structures = if value.is_array() {
+        serde_json::from_value::<Vec<WorldStructure>>(value)
+    } else {
+        serde_json::from_value::<WorldStructure>(value).map(|mut ws| {
+            if ws.name.is_empty() {
+                ws.name = file_stem.to_owned();
+            }
+            vec![ws]
+        })
+    }
+    .map_err(|err| Error::Parsing(err.to_string()))?;
+
+    for (i, ws) in world_structures.iter_mut().enumerate() {
+        if ws.radius == 0 {
+            ws.radius = ws.calc_radius();
+        }
+
+        ws.validate(grid_size).map_err(|err| match err {
+            Error::WorldStructure { field, msg } => Error::WorldStructure {
+                field: format!("[{}].{}", i, field),
+                msg,
+            },
+            err => err,
+        })?;
+    }
+
+    for (i, ws) in world_structures.iter().enumerate() {
+        if world_structures[..i]
+            .iter()
+            .any(|other| other.name == ws.name)
+        {
+            return Err(Error::WorldStructure {
+                field: format!("[{}].name", i),
+                msg: format!("{} is already used by another world structure", ws.name),
+            });
         }
     }
 
-    pub fn max_radius() -> u32 {
-        Self::iter().map(|ws| ws.radius()).max().unwrap_or(0)
+    Ok(world_structures)
+}
+
+// Every world structure in a single .json file
+#[derive(Asset, Clone, TypePath)]
+pub struct WorldStructureFile(pub Vec<WorldStructure>);
+
+// Loads world structure files at runtime, so that adding a world structure
+// only takes dropping a new .json file into the world_structures folder
+pub struct WorldStructureAssetPlugin {
+    pub grid_size: usize,
+}
+
+impl Plugin for WorldStructureAssetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<WorldStructureFile>()
+            .register_asset_loader(WorldStructureAssetLoader {
+                grid_size: self.grid_size,
+            });
+    }
+}
+
+pub struct WorldStructureAssetLoader {
+    grid_size: usize,
+}
+
+impl AssetLoader for WorldStructureAssetLoader {
+    type Asset = WorldStructureFile;
+    type Settings = ();
+    type Error = Error;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let content = String::from_utf8(bytes).map_err(|err| Error::Parsing(err.to_string()))?;
+        let file_stem = load_context
+            .path()
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+
+        parse_world_structures(&content, file_stem, self.grid_size).map(WorldStructureFile)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["json"]
     }
+}
+
+// Every world structure that chunks can be generated from, sorted by name,
+// so that the same seed always picks the same world structures
+// no matter what order their files were loaded in. They are shared behind an Arc, so that
+// handing the registry to chunk generation tasks does not copy every world structure.
+#[derive(Clone, Debug, Default, Resource)]
+pub struct WorldStructureRegistry(Arc<[WorldStructure]>);
+
+impl WorldStructureRegistry {
+    pub fn new(world_structures: impl IntoIterator<Item = WorldStructure>) -> Self {
+        let mut world_structures: Vec<WorldStructure> = world_structures.into_iter().collect();
+        world_structures.sort_by(|a, b| a.name.cmp(&b.name));
+        world_structures.dedup_by(|a, b| {
+            let is_duplicate = a.name == b.name;
+            if is_duplicate {
+                warn!("Ignoring duplicate world structure named {}", a.name);
+            }
+            is_duplicate
+        });
 
-    pub fn weight(&self) -> f32 {
-        match self {
-            Self::None => 0.0,
-            Self::EmptySpace1 => 3.0,
-            Self::FilledWithChairs1 => 1.0,
-            Self::House1 => 3.0,
-            Self::StairsAltar1 => 4.0,
-            Self::StaircaseTower2 => 4.0,
+        Self(world_structures.into())
+    }
+
+    pub fn from_assets(files: &Assets<WorldStructureFile>) -> Self {
+        Self::new(files.iter().flat_map(|(_, file)| file.0.iter().cloned()))
+    }
+
+    // Reads every .json file in a directory, for when there is no asset server to load them
+    pub fn from_dir(dir: impl AsRef<Path>, grid_size: usize) -> Result<Self, Error> {
+        let mut paths: Vec<_> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+
+        let mut world_structures = Vec::new();
+
+        for path in paths {
+            let content = fs::read_to_string(&path)?;
+            let file_stem = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default();
+
+            let parsed = parse_world_structures(&content, file_stem, grid_size)
+                .map_err(|err| Error::Parsing(format!("{}: {}", path.display(), err)))?;
+            world_structures.extend(parsed);
         }
+
+        Ok(Self::new(world_structures))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &WorldStructure> {
+        self.0.iter()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&WorldStructure> {
+        self.0.iter().find(|ws| ws.name == name)
     }
 
-    pub fn total_weight() -> f32 {
-        Self::iter().fold(0.0, |acc, curr| acc + curr.weight())
+    // Falls back on the compiled in starting room when its file has not been loaded, although
+    // its theme is still only found once it is. None if it does not fit chunks of grid_size.
+    pub fn starting_room(&self, grid_size: usize) -> Option<WorldStructure> {
+        self.get(STARTING_ROOM)
+            .cloned()
+            .or_else(starting_room_fallback)
+            .filter(|ws| ws.validate(grid_size).is_ok())
     }
 
-    pub fn choose(rng: &mut StdRng) -> Self {
-        let variants = Self::VARIANTS;
+    // Theme of the world structure that a chunk is part of, if any
+    pub fn theme(&self, world_structure: &str) -> Option<&StructureTheme> {
+        self.get(world_structure).and_then(|ws| ws.theme.as_ref())
+    }
+
+    pub fn max_radius(&self) -> u32 {
+        self.0.iter().map(|ws| ws.radius).max().unwrap_or(0)
+    }
+
+    pub fn max_spacing(&self) -> u32 {
+        self.0.iter().map(|ws| ws.spacing).max().unwrap_or(0)
+    }
+
+    // Origins any closer than this could have their world structures touching. The largest
+    // radius and spacing are used, so that spacing origins out does not depend on which
+    // world structure each of them gets.
+    pub fn min_origin_dist(&self) -> i64 {
+        (self.max_radius() * 2 + self.max_spacing()) as i64
+    }
+
+    pub fn total_weight(&self) -> f32 {
+        self.0.iter().fold(0.0, |acc, curr| acc + curr.weight)
+    }
+
+    pub fn choose(&self, rng: &mut StdRng) -> Option<&WorldStructure> {
+        let total_weight = self.total_weight();
+        if total_weight <= 0.0 {
+            return None;
+        }
 
-        let weights: Vec<f32> = variants.iter().map(|ws| ws.weight()).collect();
-        let rand_weight = rng.gen_range(0.0..Self::total_weight());
+        let rand_weight = rng.gen_range(0.0..total_weight);
 
         let mut cumulative_weight = 0.0;
-        for (index, &weight) in weights.iter().enumerate() {
-            cumulative_weight += weight;
+        for ws in self.0.iter() {
+            cumulative_weight += ws.weight;
             if rand_weight < cumulative_weight {
-                return variants[index].clone();
+                return Some(ws);
             }
         }
 
-        Self::default()
+        None
     }
 }
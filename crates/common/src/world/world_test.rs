@@ -0,0 +1,1410 @@
+use crate::{
+    error::Error,
+    inventory::item::{Item, ItemName},
+    utils::rng::rng_from_xyz_seed,
+    world::{
+        add_railings, chunk_has_world_structure,
+        data::{
+            CellData, TreasureChestData, WorldData, CHEST_OPENED_FLAG, CHEST_UNLOCKED_FLAG,
+            WINDOW_BROKEN_FLAG,
+        },
+        geometry::WorldGeometry,
+        has_origin_priority,
+        hazard::{Hazard, HazardOccupants},
+        nav::NavGraph,
+        nearest_structure_chunk, roll_structure_origin, structure_origin_allowed,
+        structure_prob_at,
+        world_structure::{
+            parse_world_structures, WorldStructure, WorldStructureRegistry, NO_WORLD_STRUCTURE,
+            STARTING_ROOM,
+        },
+        AutoClose, Cell, CellMutation, CellSpecial, CellWall, Chunk, ChunkCellMarker,
+        CyclicTransform, Locked, PickUpGrace, Side, StructureRolls, WorldDims,
+        CYCLIC_TRANSFORM_FRAME_SECS, DEFAULT_CELL_SIZE, WORLD_STRUCTURE_GEN_PROB,
+        WORLD_STRUCTURE_NEAR_GEN_PROB, WORLD_STRUCTURE_RAMP_END_DIST,
+    },
+};
+use bevy::{prelude::*, utils::HashMap};
+use rand::Rng;
+use std::{f32::consts::PI, time::Duration};
+
+const GRID_SIZE: usize = 4;
+
+fn new_chunk(x: i64, y: i64, z: i64, world_structure: &str) -> Chunk {
+    Chunk {
+        x,
+        y,
+        z,
+        cells: vec![vec![Cell::default(); GRID_SIZE]; GRID_SIZE],
+        world_structure: world_structure.to_owned(),
+    }
+}
+
+fn new_world_structure(name: &str, radius: u32, chunks: Vec<Chunk>) -> WorldStructure {
+    WorldStructure {
+        name: name.to_owned(),
+        radius,
+        weight: 1.0,
+        spacing: 0,
+        theme: None,
+        chunks,
+    }
+}
+
+// Origins are never too close together for these, so every chunk that passes its roll is one
+fn unspaced_structures() -> WorldStructureRegistry {
+    WorldStructureRegistry::new([new_world_structure(
+        "single",
+        0,
+        vec![new_chunk(0, 0, 0, "single")],
+    )])
+}
+
+fn validation_err_field(ws: &WorldStructure) -> String {
+    match ws.validate(GRID_SIZE) {
+        Err(Error::WorldStructure { field, .. }) => field,
+        _ => panic!("expected Error::WorldStructure"),
+    }
+}
+
+// Finds the first seed for which pred is true
+fn find_seed(pred: impl Fn(u32) -> bool) -> u32 {
+    (0..10_000)
+        .find(|seed| pred(*seed))
+        .expect("expected to find a matching seed")
+}
+
+fn brute_force_nearest_dist_sq(seed: u32, origin: (i64, i64, i64), max_radius: i64) -> Option<i64> {
+    let (x, y, z) = origin;
+    let mut nearest = None;
+
+    for _x in x - max_radius..=x + max_radius {
+        for _z in z - max_radius..=z + max_radius {
+            if chunk_has_world_structure(seed, _x, y, _z) {
+                let d = (_x - x).pow(2) + (_z - z).pow(2);
+                if nearest.is_none_or(|n| d < n) {
+                    nearest = Some(d);
+                }
+            }
+        }
+    }
+
+    nearest
+}
+
+#[test]
+fn test_structure_prob_at() {
+    // Flat near the origin, on every axis
+    for xyz in [(0, 0, 0), (2, 0, 0), (0, -2, 0), (-2, 1, 2)] {
+        assert_eq!(
+            structure_prob_at(xyz.0, xyz.1, xyz.2),
+            WORLD_STRUCTURE_NEAR_GEN_PROB
+        );
+    }
+
+    // Ramps up in between, going by the furthest axis
+    let halfway = (WORLD_STRUCTURE_NEAR_GEN_PROB + WORLD_STRUCTURE_GEN_PROB) / 2.0;
+    assert!((structure_prob_at(4, 0, 0) - halfway).abs() < 1e-9);
+    assert!((structure_prob_at(1, -4, 3) - halfway).abs() < 1e-9);
+    let mut prev = WORLD_STRUCTURE_NEAR_GEN_PROB;
+    for dist in 3..WORLD_STRUCTURE_RAMP_END_DIST {
+        let prob = structure_prob_at(0, 0, dist);
+        assert!(prob > prev && prob < WORLD_STRUCTURE_GEN_PROB);
+        prev = prob;
+    }
+
+    // Then flat again far away
+    for xyz in [(6, 0, 0), (0, 0, -6), (100, -50, 3)] {
+        assert_eq!(
+            structure_prob_at(xyz.0, xyz.1, xyz.2),
+            WORLD_STRUCTURE_GEN_PROB
+        );
+    }
+}
+
+#[test]
+fn test_chunk_has_world_structure_is_deterministic() {
+    for seed in 0..20 {
+        for xyz in [(0, 0, 0), (3, 1, -2), (-5, 0, 4), (40, -3, -17)] {
+            let has = chunk_has_world_structure(seed, xyz.0, xyz.1, xyz.2);
+            for _ in 0..3 {
+                assert_eq!(chunk_has_world_structure(seed, xyz.0, xyz.1, xyz.2), has);
+            }
+        }
+    }
+
+    // Far away chunks sample the rng exactly the same way as before the ramp existed
+    for seed in 0..20 {
+        for xyz in [(6, 0, 0), (40, -3, -17), (-100, 2, 9)] {
+            let mut rng = rng_from_xyz_seed(seed, xyz.0, xyz.1, xyz.2);
+            assert_eq!(
+                chunk_has_world_structure(seed, xyz.0, xyz.1, xyz.2),
+                rng.gen_bool(WORLD_STRUCTURE_GEN_PROB)
+            );
+        }
+    }
+
+    // Structures are rarer around spawn than far away from it
+    let count = |x: i64| {
+        (0..500)
+            .filter(|seed| chunk_has_world_structure(*seed, x, 0, 0))
+            .count()
+    };
+    assert!(count(0) < count(WORLD_STRUCTURE_RAMP_END_DIST));
+}
+
+#[test]
+fn test_nearest_structure_chunk_at_origin() {
+    let seed = find_seed(|s| chunk_has_world_structure(s, 3, 1, -2));
+    assert_eq!(
+        nearest_structure_chunk(seed, (3, 1, -2), 5, &unspaced_structures()),
+        Some((3, 1, -2))
+    );
+}
+
+#[test]
+fn test_nearest_structure_chunk_adjacent() {
+    // No structure at the origin, but one directly next to it. Searches away from the
+    // world origin, where structures are rare enough that hardly any seed would match.
+    let seed = find_seed(|s| {
+        !chunk_has_world_structure(s, 10, 0, 10) && chunk_has_world_structure(s, 11, 0, 10)
+    });
+
+    let nearest = nearest_structure_chunk(seed, (10, 0, 10), 5, &unspaced_structures()).unwrap();
+    assert_eq!(nearest.1, 0);
+    assert_eq!((nearest.0 - 10).pow(2) + (nearest.2 - 10).pow(2), 1);
+}
+
+#[test]
+fn test_nearest_structure_chunk_none_within_radius() {
+    let seed =
+        find_seed(|s| (-1..=1).all(|x| (-1..=1).all(|z| !chunk_has_world_structure(s, x, 0, z))));
+
+    assert_eq!(
+        nearest_structure_chunk(seed, (0, 0, 0), 0, &unspaced_structures()),
+        None
+    );
+    assert_eq!(
+        nearest_structure_chunk(seed, (0, 0, 0), 1, &unspaced_structures()),
+        None
+    );
+}
+
+#[test]
+fn test_nearest_structure_chunk_matches_brute_force() {
+    for seed in 0..50 {
+        for origin in [(0, 0, 0), (7, -2, -11), (-30, 4, 25)] {
+            let expected = brute_force_nearest_dist_sq(seed, origin, 4);
+            let actual = nearest_structure_chunk(seed, origin, 4, &unspaced_structures())
+                .map(|(x, _, z)| (x - origin.0).pow(2) + (z - origin.2).pow(2));
+
+            assert_eq!(actual, expected, "seed: {}, origin: {:?}", seed, origin);
+        }
+    }
+}
+
+#[test]
+fn test_nearest_structure_chunk_skips_suppressed_origins() {
+    let world_structures = WorldStructureRegistry::new([new_world_structure(
+        "wide",
+        1,
+        vec![new_chunk(0, 0, 0, "wide")],
+    )]);
+    let min_origin_dist = world_structures.min_origin_dist();
+    let origin = (20, 0, 20);
+    let allowed = |seed, (x, y, z)| {
+        structure_origin_allowed(&mut StructureRolls::new(seed), x, y, z, min_origin_dist)
+    };
+
+    // The closest chunk to pass its roll loses out to another origin nearby
+    let seed = find_seed(|s| {
+        chunk_has_world_structure(s, origin.0, origin.1, origin.2) && !allowed(s, origin)
+    });
+    let nearest = nearest_structure_chunk(seed, origin, 6, &world_structures).unwrap();
+    assert_ne!(nearest, origin);
+    assert!(allowed(seed, nearest));
+
+    // Nothing to choose from, so nothing is generated either
+    assert_eq!(
+        nearest_structure_chunk(seed, origin, 6, &WorldStructureRegistry::default()),
+        None
+    );
+}
+
+#[test]
+fn test_structure_origin_allowed_suppresses_one_of_two_close_origins() {
+    let (a, b) = ((20, 0, 20), (21, 0, 20));
+    let seed = find_seed(|s| {
+        roll_structure_origin(s, a.0, a.1, a.2).is_some()
+            && roll_structure_origin(s, b.0, b.1, b.2).is_some()
+    });
+
+    let a_priority = roll_structure_origin(seed, a.0, a.1, a.2).unwrap();
+    let b_priority = roll_structure_origin(seed, b.0, b.1, b.2).unwrap();
+    let loser = if has_origin_priority((a, a_priority), (b, b_priority)) {
+        b
+    } else {
+        a
+    };
+
+    // Far enough apart on their own, both keep their world structures
+    for xyz in [a, b] {
+        let mut rolls = StructureRolls::new(seed);
+        assert!(structure_origin_allowed(&mut rolls, xyz.0, xyz.1, xyz.2, 1));
+    }
+
+    // Too close together, the one without priority loses out, no matter which chunk
+    // is generating and which origins it has already looked at
+    for perspective in [a, b, (22, 0, 20), (20, 1, 19)] {
+        let mut rolls = StructureRolls::new(seed);
+        rolls.get(perspective);
+        assert!(!structure_origin_allowed(
+            &mut rolls, loser.0, loser.1, loser.2, 2
+        ));
+    }
+}
+
+#[test]
+fn test_structure_origins_are_spaced_out() {
+    let min_origin_dist = 3;
+
+    for seed in 0..20 {
+        let mut rolls = StructureRolls::new(seed);
+        let mut origins = Vec::new();
+        for x in 10..30 {
+            for z in 10..30 {
+                if structure_origin_allowed(&mut rolls, x, 0, z, min_origin_dist) {
+                    origins.push((x, z));
+                }
+            }
+        }
+
+        for (i, a) in origins.iter().enumerate() {
+            for b in origins.iter().skip(i + 1) {
+                assert!(
+                    (a.0 - b.0).abs().max((a.1 - b.1).abs()) >= min_origin_dist,
+                    "seed: {}, origins: {:?} and {:?}",
+                    seed,
+                    a,
+                    b
+                );
+            }
+
+            // Deciding from scratch gives the same answer as with all the cached rolls
+            assert!(structure_origin_allowed(
+                &mut StructureRolls::new(seed),
+                a.0,
+                0,
+                a.1,
+                min_origin_dist
+            ));
+        }
+    }
+}
+
+#[test]
+fn test_world_structure_validate_ok() {
+    let ws = new_world_structure(
+        "Tower",
+        2,
+        vec![
+            new_chunk(0, -1, 0, NO_WORLD_STRUCTURE),
+            new_chunk(0, 0, 0, "Tower"),
+            new_chunk(0, 1, 0, NO_WORLD_STRUCTURE),
+        ],
+    );
+
+    assert!(ws.validate(GRID_SIZE).is_ok());
+}
+
+#[test]
+fn test_world_structure_validate_name_and_weight() {
+    let mut ws = new_world_structure(NO_WORLD_STRUCTURE, 1, vec![new_chunk(0, 0, 0, "House")]);
+    assert_eq!(validation_err_field(&ws), "name");
+
+    ws.name = "House".to_owned();
+    ws.weight = -1.0;
+    assert_eq!(validation_err_field(&ws), "weight");
+}
+
+#[test]
+fn test_world_structure_validate_grid_size() {
+    let mut ws = new_world_structure("House", 1, vec![new_chunk(0, 0, 0, "House")]);
+    ws.chunks[0].cells[1].pop();
+
+    assert_eq!(validation_err_field(&ws), "chunks[0].cells[1]");
+
+    ws.chunks[0].cells.pop();
+    assert_eq!(validation_err_field(&ws), "chunks[0].cells");
+}
+
+#[test]
+fn test_world_structure_validate_duplicate_coords() {
+    let ws = new_world_structure(
+        "Tower",
+        2,
+        vec![
+            new_chunk(0, 0, 0, "Tower"),
+            new_chunk(0, 1, 0, NO_WORLD_STRUCTURE),
+            new_chunk(0, 1, 0, NO_WORLD_STRUCTURE),
+        ],
+    );
+
+    assert_eq!(validation_err_field(&ws), "chunks[2]");
+}
+
+#[test]
+fn test_world_structure_validate_radius() {
+    // A radius of 1 only covers the origin chunk
+    let ws = new_world_structure(
+        "House",
+        1,
+        vec![
+            new_chunk(0, 0, 0, "House"),
+            new_chunk(1, 0, 0, NO_WORLD_STRUCTURE),
+        ],
+    );
+
+    assert_eq!(validation_err_field(&ws), "chunks[1]");
+}
+
+#[test]
+fn test_world_structure_validate_origin_chunk() {
+    let no_origin = new_world_structure("Tower", 2, vec![new_chunk(0, 0, 0, NO_WORLD_STRUCTURE)]);
+    assert_eq!(validation_err_field(&no_origin), "chunks");
+
+    let offset_origin = new_world_structure("Tower", 2, vec![new_chunk(0, 1, 0, "Tower")]);
+    assert_eq!(validation_err_field(&offset_origin), "chunks[0]");
+
+    let other_label = new_world_structure(
+        "Tower",
+        2,
+        vec![new_chunk(0, 0, 0, "Tower"), new_chunk(0, 1, 0, "House")],
+    );
+    assert_eq!(
+        validation_err_field(&other_label),
+        "chunks[1].world_structure"
+    );
+}
+
+fn chunk_json(x: i64, y: i64, z: i64, world_structure: &str) -> String {
+    serde_json::to_string(&new_chunk(x, y, z, world_structure)).unwrap()
+}
+
+#[test]
+fn test_parse_legacy_world_structure_file() {
+    // Written before names, radii and weights were stored in world structure files
+    let content = format!(
+        r#"{{ "chunks": [{}, {}, {}] }}"#,
+        chunk_json(0, -1, 0, NO_WORLD_STRUCTURE),
+        chunk_json(0, 0, 0, "Tower"),
+        chunk_json(0, 1, 0, NO_WORLD_STRUCTURE),
+    );
+
+    let world_structures = parse_world_structures(&content, "Tower", GRID_SIZE).unwrap();
+    assert_eq!(world_structures.len(), 1);
+    assert_eq!(world_structures[0].name, "Tower");
+    assert_eq!(world_structures[0].radius, 2);
+    assert_eq!(world_structures[0].weight, 1.0);
+
+    // The file name only fills in a missing name
+    assert!(parse_world_structures(&content, "House", GRID_SIZE).is_err());
+}
+
+#[test]
+fn test_parse_world_structure_list() {
+    let content = format!(
+        r#"[
+            {{ "name": "House", "radius": 1, "weight": 2.5, "chunks": [{}] }},
+            {{ "name": "Tower", "chunks": [{}, {}] }}
+        ]"#,
+        chunk_json(0, 0, 0, "House"),
+        chunk_json(0, 0, 0, "Tower"),
+        chunk_json(0, 1, 0, NO_WORLD_STRUCTURE),
+    );
+
+    let world_structures = parse_world_structures(&content, "Buildings", GRID_SIZE).unwrap();
+    let summary: Vec<(&str, u32, f32)> = world_structures
+        .iter()
+        .map(|ws| (ws.name.as_str(), ws.radius, ws.weight))
+        .collect();
+    assert_eq!(summary, vec![("House", 1, 2.5), ("Tower", 2, 1.0)]);
+
+    // Structures in a list are never named after the file
+    let unnamed = format!(
+        r#"[{{ "chunks": [{}] }}]"#,
+        chunk_json(0, 0, 0, "Buildings")
+    );
+    match parse_world_structures(&unnamed, "Buildings", GRID_SIZE) {
+        Err(Error::WorldStructure { field, .. }) => assert_eq!(field, "[0].name"),
+        _ => panic!("expected Error::WorldStructure"),
+    }
+
+    let duplicate = format!(
+        r#"[{{ "name": "House", "chunks": [{}] }}, {{ "name": "House", "chunks": [{}] }}]"#,
+        chunk_json(0, 0, 0, "House"),
+        chunk_json(0, 0, 0, "House"),
+    );
+    match parse_world_structures(&duplicate, "Buildings", GRID_SIZE) {
+        Err(Error::WorldStructure { field, .. }) => assert_eq!(field, "[1].name"),
+        _ => panic!("expected Error::WorldStructure"),
+    }
+}
+
+#[test]
+fn test_world_structure_registry_is_order_independent() {
+    let house = new_world_structure("House", 1, vec![new_chunk(0, 0, 0, "House")]);
+    let tower = new_world_structure(
+        "Tower",
+        2,
+        vec![
+            new_chunk(0, 0, 0, "Tower"),
+            new_chunk(0, 1, 0, NO_WORLD_STRUCTURE),
+        ],
+    );
+    let altar = new_world_structure("Altar", 1, vec![new_chunk(0, 0, 0, "Altar")]);
+
+    let a = WorldStructureRegistry::new([house.clone(), tower.clone(), altar.clone()]);
+    let b = WorldStructureRegistry::new([tower, altar, house]);
+
+    let names: Vec<&str> = a.iter().map(|ws| ws.name.as_str()).collect();
+    assert_eq!(names, vec!["Altar", "House", "Tower"]);
+    assert_eq!(a.max_radius(), 2);
+    assert_eq!(a.total_weight(), 3.0);
+    assert_eq!(a.max_spacing(), 0);
+    assert_eq!(a.min_origin_dist(), 4);
+
+    for seed in 0..100 {
+        let chosen_a = a.choose(&mut rng_from_xyz_seed(seed, 1, 2, 3)).unwrap();
+        let chosen_b = b.choose(&mut rng_from_xyz_seed(seed, 1, 2, 3)).unwrap();
+        assert_eq!(chosen_a.name, chosen_b.name);
+    }
+
+    let empty = WorldStructureRegistry::default();
+    assert!(empty.choose(&mut rng_from_xyz_seed(0, 0, 0, 0)).is_none());
+    assert_eq!(empty.max_radius(), 0);
+}
+
+#[test]
+fn test_starting_room_fallback() {
+    // Used as is when it was loaded
+    let mut loaded = new_world_structure(STARTING_ROOM, 1, vec![new_chunk(0, 0, 0, STARTING_ROOM)]);
+    loaded.weight = 0.0;
+    let registry = WorldStructureRegistry::new([loaded.clone()]);
+    let starting_room = registry.starting_room(GRID_SIZE).unwrap();
+    assert_eq!(starting_room.chunks, loaded.chunks);
+
+    // Compiled in otherwise, and never picked at random
+    let starting_room = WorldStructureRegistry::default()
+        .starting_room(GRID_SIZE)
+        .unwrap();
+    assert_eq!(starting_room.name, STARTING_ROOM);
+    assert_eq!(starting_room.weight, 0.0);
+    let origin = starting_room.gen_origin_chunk(0, 0, 0).unwrap();
+    let chests = origin
+        .cells
+        .iter()
+        .flatten()
+        .filter(|cell| cell.special == CellSpecial::TreasureChest)
+        .count();
+    assert_eq!(chests, 1);
+
+    assert!(WorldStructureRegistry::default()
+        .starting_room(GRID_SIZE * 2)
+        .is_none());
+}
+
+#[test]
+fn test_world_structure_gen_chunks() {
+    let ws = new_world_structure(
+        "Tower",
+        2,
+        vec![
+            new_chunk(0, -1, 0, NO_WORLD_STRUCTURE),
+            new_chunk(0, 0, 0, "Tower"),
+        ],
+    );
+
+    let origin = ws.gen_origin_chunk(3, -2, 5).unwrap();
+    assert_eq!((origin.x, origin.y, origin.z), (3, -2, 5));
+    assert_eq!(origin.world_structure, "Tower");
+
+    let xyzs: Vec<(i64, i64, i64)> = ws
+        .gen_chunks(3, -2, 5)
+        .iter()
+        .map(|chunk| (chunk.x, chunk.y, chunk.z))
+        .collect();
+    assert_eq!(xyzs, vec![(3, -3, 5), (3, -2, 5)]);
+
+    // Every chunk is labeled, not just the origin
+    for chunk in ws.gen_chunks(3, -2, 5) {
+        assert_eq!(chunk.world_structure, "Tower");
+    }
+}
+
+#[test]
+fn test_add_railings_along_drops() {
+    // A floored row along the top, with a hole below it, stairs down in the middle
+    // and a staircase coming up through the hole from the chunk below
+    let mut cells = vec![vec![Cell::default(); GRID_SIZE]; GRID_SIZE];
+    for row in cells.iter_mut() {
+        row[0].floor = CellWall::Solid;
+    }
+    cells[1][1].special = CellSpecial::Staircase;
+    cells[2][0].wall_bottom = CellWall::Solid;
+    let mut cells_below = vec![vec![Cell::default(); GRID_SIZE]; GRID_SIZE];
+    cells_below[3][1].special = CellSpecial::Staircase;
+    add_railings(&mut cells, Some(&cells_below));
+
+    for (z, row) in cells.iter().enumerate() {
+        let cell = &row[0];
+        // Open to the hole, except where there are stairs, stairs come up or a wall is in the way
+        assert_eq!(cell.railing(Side::Bottom), z == 0, "{}", z);
+        // Nothing is known past the edge of the chunk, and floored neighbors are safe
+        assert!(!cell.railing(Side::Top));
+        assert!(!cell.railing(Side::Left));
+        assert!(!cell.railing(Side::Right));
+    }
+
+    // Cells without a floor get none
+    for row in &cells {
+        for cell in &row[1..] {
+            assert!(!cell.railing(Side::Top) && !cell.railing(Side::Bottom));
+            assert!(!cell.railing(Side::Left) && !cell.railing(Side::Right));
+        }
+    }
+}
+
+#[test]
+fn test_cell_railings_default_when_missing() {
+    let cell: Cell = serde_json::from_str(
+        r#"{"wall_top": "None", "wall_bottom": "None", "wall_left": "None", "wall_right": "None", "floor": "Solid", "ceiling": "None", "door_top": false, "door_bottom": false, "door_left": false, "door_right": false, "window_top": false, "window_bottom": false, "window_left": false, "window_right": false, "special": "None"}"#,
+    )
+    .unwrap();
+    assert_eq!(cell, Cell::new_floored());
+}
+
+fn new_door_like_transform() -> CyclicTransform {
+    let start = Transform::from_xyz(0.0, 0.0, 0.0);
+    let end = Transform::from_xyz(2.0, 4.0, 0.0)
+        .with_rotation(Quat::from_rotation_y(PI / 2.0))
+        .with_scale(Vec3::splat(3.0));
+
+    CyclicTransform::new_cycled_from_keyframes(vec![
+        vec![(start, 0.0), (end, 1.0)],
+        vec![(end, 0.0), (start, 1.0)],
+    ])
+}
+
+#[test]
+fn test_world_structure_gen_origin_chunk_without_one_origin() {
+    // Never validated, so nothing caught that these do not have exactly one origin
+    let ws = new_world_structure("Tower", 2, vec![new_chunk(0, 0, 0, NO_WORLD_STRUCTURE)]);
+    assert!(ws.gen_origin_chunk(3, -2, 5).is_none());
+
+    let ws = new_world_structure(
+        "Tower",
+        2,
+        vec![new_chunk(0, 0, 0, "Tower"), new_chunk(0, 1, 0, "Tower")],
+    );
+    assert!(ws.gen_origin_chunk(3, -2, 5).is_none());
+}
+
+#[test]
+fn test_cyclic_transform_interpolates() {
+    let mut ct = new_door_like_transform();
+    assert_eq!(ct.tick(Duration::from_millis(100)), None);
+
+    ct.cycle();
+    let mid = ct.tick(Duration::from_millis(500)).unwrap();
+    assert!(mid.translation.abs_diff_eq(Vec3::new(1.0, 2.0, 0.0), 1e-5));
+    assert!(mid.scale.abs_diff_eq(Vec3::splat(2.0), 1e-5));
+    assert!(mid
+        .rotation
+        .abs_diff_eq(Quat::from_rotation_y(PI / 4.0), 1e-5));
+}
+
+#[test]
+fn test_cyclic_transform_lands_on_final_keyframe() {
+    let end = Transform::from_xyz(2.0, 4.0, 0.0)
+        .with_rotation(Quat::from_rotation_y(PI / 2.0))
+        .with_scale(Vec3::splat(3.0));
+
+    // A single frame far longer than the whole animation
+    let mut ct = new_door_like_transform();
+    ct.cycle();
+    assert_eq!(ct.tick(Duration::from_secs(5)), Some(end));
+    assert_eq!(ct.tick(Duration::from_millis(16)), None);
+
+    // Cycling back plays the reverse animation
+    ct.cycle();
+    assert_eq!(ct.tick(Duration::from_secs(1)), Some(Transform::IDENTITY));
+    assert_eq!(ct.tick(Duration::from_millis(16)), None);
+}
+
+#[test]
+fn test_cyclic_transform_duration_independent_of_frame_rate() {
+    for frame_millis in [7, 16, 33, 90] {
+        let mut ct = new_door_like_transform();
+        ct.cycle();
+
+        let mut frames = 0;
+        while ct.tick(Duration::from_millis(frame_millis)).is_some() {
+            frames += 1;
+        }
+
+        // The last frame lands at or just past one second
+        assert_eq!(frames, 1000_u64.div_ceil(frame_millis));
+    }
+}
+
+#[test]
+fn test_cyclic_transform_new_cycled() {
+    let transforms: Vec<Transform> = (0..4)
+        .map(|i| Transform::from_xyz(i as f32, 0.0, 0.0))
+        .collect();
+    let mut ct = CyclicTransform::new_cycled(vec![transforms.clone(), transforms]);
+    ct.cycle();
+
+    // Every transform after the first takes a single frame to reach
+    let frame = Duration::from_secs_f32(CYCLIC_TRANSFORM_FRAME_SECS);
+    let half_frame = frame / 2;
+    let t = ct.tick(half_frame).unwrap();
+    assert!((t.translation.x - 0.5).abs() < 1e-4);
+
+    let mut last = t;
+    while let Some(t) = ct.tick(frame) {
+        last = t;
+    }
+    assert_eq!(last.translation.x, 3.0);
+}
+
+#[test]
+fn test_cyclic_transform_is_in_state() {
+    let mut ct = new_door_like_transform();
+    assert!(ct.is_in_state(1));
+
+    // Not in the new state until its animation has finished playing
+    ct.cycle();
+    assert!(!ct.is_in_state(0) && !ct.is_in_state(1));
+    ct.tick(Duration::from_millis(500));
+    assert!(!ct.is_in_state(0));
+    ct.tick(Duration::from_secs(1));
+    assert!(ct.is_in_state(0));
+}
+
+fn new_auto_close(after_frames: u32) -> AutoClose {
+    AutoClose::new(after_frames, 0, Vec3::splat(-1.0), Vec3::splat(1.0))
+}
+
+#[test]
+fn test_auto_close_after_frames() {
+    let mut auto_close = new_auto_close(3);
+
+    // Nothing is counted while closed
+    for _ in 0..5 {
+        assert!(!auto_close.tick(false, false));
+    }
+    assert!(!auto_close.is_counting());
+
+    assert!(!auto_close.tick(true, false));
+    assert!(!auto_close.tick(true, false));
+    assert!(auto_close.tick(true, false));
+    assert!(!auto_close.is_counting());
+}
+
+#[test]
+fn test_auto_close_canceled_when_closed_early() {
+    let mut auto_close = new_auto_close(3);
+
+    auto_close.tick(true, false);
+    auto_close.tick(true, false);
+    assert!(auto_close.is_counting());
+
+    // Closed by hand before the time was up, so opening it again starts over
+    assert!(!auto_close.tick(false, false));
+    assert!(!auto_close.is_counting());
+    assert!(!auto_close.tick(true, false));
+    assert!(!auto_close.tick(true, false));
+    assert!(auto_close.tick(true, false));
+}
+
+#[test]
+fn test_auto_close_waits_while_blocked() {
+    let mut auto_close = new_auto_close(2);
+
+    for _ in 0..10 {
+        assert!(!auto_close.tick(true, true));
+    }
+    assert!(auto_close.is_counting());
+    assert!(auto_close.tick(true, false));
+}
+
+#[test]
+fn test_auto_close_sensor_contains() {
+    let auto_close = new_auto_close(1);
+
+    assert!(auto_close.sensor_contains(Vec3::ZERO));
+    assert!(auto_close.sensor_contains(Vec3::new(1.0, -1.0, 0.5)));
+    assert!(!auto_close.sensor_contains(Vec3::new(1.5, 0.0, 0.0)));
+    assert!(!auto_close.sensor_contains(Vec3::new(0.0, 0.0, -2.0)));
+}
+
+#[test]
+fn test_world_dims() {
+    let dims = WorldDims::default();
+    assert_eq!(dims.grid_size(), GRID_SIZE);
+
+    let dims = WorldDims::new(32.0, 4.0).unwrap();
+    assert_eq!(dims.grid_size(), 8);
+
+    for (chunk_size, cell_size) in [(16.0, 3.0), (4.0, 4.0), (0.0, 4.0), (16.0, -4.0)] {
+        assert!(matches!(
+            WorldDims::new(chunk_size, cell_size),
+            Err(Error::WorldDims(_))
+        ));
+    }
+}
+
+#[test]
+fn test_chunk_cell_marker_from_global_transform() {
+    for (dims, translation, expected) in [
+        (
+            WorldDims::default(),
+            Vec3::new(2.0, 1.0, 2.0),
+            ((0, 0, 0), (1, 1)),
+        ),
+        (
+            WorldDims::default(),
+            Vec3::new(9.0, -1.0, -9.0),
+            ((1, -1, -1), (3, 0)),
+        ),
+        (
+            WorldDims::new(32.0, 4.0).unwrap(),
+            Vec3::new(9.0, 5.0, -9.0),
+            ((0, 1, 0), (1, 6)),
+        ),
+    ] {
+        let ccm = ChunkCellMarker::from_global_transform(
+            &GlobalTransform::from_translation(translation),
+            &dims,
+        );
+        assert_eq!(
+            (ccm.chunk_xyz(), ccm.cell_xz()),
+            expected,
+            "{:?}",
+            translation
+        );
+    }
+}
+
+#[test]
+fn test_chunk_cell_marker_world_position_round_trip() {
+    for dims in [
+        WorldDims::default(),
+        WorldDims::new(32.0, 4.0).unwrap(),
+        WorldDims::new(6.0, 3.0).unwrap(),
+    ] {
+        let grid_size = dims.grid_size();
+        for chunk_x in -3..=3 {
+            for chunk_y in -3..=3 {
+                for chunk_z in -3..=3 {
+                    for x in 0..grid_size {
+                        for z in 0..grid_size {
+                            let ccm = ChunkCellMarker::new((chunk_x, chunk_y, chunk_z), (x, z));
+                            let pos = ccm.to_world_position(dims.chunk_size(), dims.cell_size());
+                            assert_eq!(pos, ccm.world_position(&dims));
+
+                            // Both the center of the cell and just inside each of its edges
+                            let half = dims.cell_size() / 2.0 - 0.001;
+                            for offset in [
+                                Vec3::ZERO,
+                                Vec3::new(half, 0.0, half),
+                                Vec3::new(-half, 0.0, -half),
+                            ] {
+                                assert_eq!(
+                                    ChunkCellMarker::from_global_transform(
+                                        &GlobalTransform::from_translation(pos + offset),
+                                        &dims,
+                                    ),
+                                    ccm,
+                                    "{:?} {:?}",
+                                    pos,
+                                    offset
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_chunk_cell_marker_from_global_transform_on_chunk_edges() {
+    let dims = WorldDims::default();
+    let half_chunk_size = dims.chunk_size() / 2.0;
+    let last = dims.grid_size() - 1;
+
+    for chunk_x in -3..=3 {
+        // The edge on the +x side of a chunk is the first cell of the next chunk over
+        let edge = chunk_x as f32 * dims.chunk_size() + half_chunk_size;
+        let ccm = ChunkCellMarker::from_global_transform(
+            &GlobalTransform::from_translation(Vec3::new(edge, 0.0, 0.0)),
+            &dims,
+        );
+        assert_eq!((ccm.chunk_x, ccm.x), (chunk_x + 1, last), "{}", edge);
+
+        let ccm = ChunkCellMarker::from_global_transform(
+            &GlobalTransform::from_translation(Vec3::new(edge - 0.0001, 0.0, 0.0)),
+            &dims,
+        );
+        assert_eq!((ccm.chunk_x, ccm.x), (chunk_x, 0), "{}", edge);
+    }
+}
+
+// Chunk with a ceiling and floor in every cell, and walls of every kind around cell (1, 1)
+fn geometry_test_chunk(xyz: (i64, i64, i64), grid_size: usize) -> Chunk {
+    let mut cells = vec![
+        vec![
+            Cell {
+                ceiling: CellWall::Solid,
+                ..Cell::new_floored()
+            };
+            grid_size
+        ];
+        grid_size
+    ];
+    let cell = &mut cells[1][1];
+    cell.wall_top = CellWall::Solid;
+    cell.wall_left = CellWall::SolidWithDoorGap;
+    cell.wall_right = CellWall::SolidWithWindowGap;
+
+    Chunk {
+        x: xyz.0,
+        y: xyz.1,
+        z: xyz.2,
+        cells,
+        world_structure: NO_WORLD_STRUCTURE.to_string(),
+    }
+}
+
+#[test]
+fn test_world_geometry_is_position_solid() {
+    for (dims, chunk_xyz) in [
+        (WorldDims::default(), (0, 0, 0)),
+        (WorldDims::default(), (-2, -1, 3)),
+        (WorldDims::new(32.0, 8.0).unwrap(), (-1, 2, -1)),
+    ] {
+        let mut generated = Vec::new();
+        let mut geometry = WorldGeometry::new(|seed, xyz| {
+            generated.push((seed, xyz));
+            geometry_test_chunk(xyz, dims.grid_size())
+        });
+
+        let center = ChunkCellMarker::new(chunk_xyz, (1, 1)).world_position(&dims);
+        let scale = dims.cell_size() / DEFAULT_CELL_SIZE;
+
+        // Offsets from the center of the floor, in cells of DEFAULT_CELL_SIZE
+        for (offset, expected) in [
+            // Middle of the cell
+            (Vec3::new(0.0, 2.0, 0.0), false),
+            // Floor and ceiling
+            (Vec3::new(0.5, 0.05, -0.5), true),
+            (Vec3::new(0.5, 3.95, -0.5), true),
+            // Solid wall on the +x side, and no wall on the -x side
+            (Vec3::new(1.95, 2.0, 0.0), true),
+            (Vec3::new(1.8, 2.0, 0.0), false),
+            (Vec3::new(-1.95, 2.0, 0.0), false),
+            // Door gap on the +z side, and the wall above and beside it
+            (Vec3::new(0.0, 1.0, 1.95), false),
+            (Vec3::new(0.0, 3.0, 1.95), true),
+            (Vec3::new(1.0, 1.0, 1.95), true),
+            // Window gap on the -z side, and the wall below it
+            (Vec3::new(0.0, 1.5, -1.95), false),
+            (Vec3::new(0.0, 0.5, -1.95), true),
+        ] {
+            assert_eq!(
+                geometry.is_position_solid(7, center + offset * scale, &dims),
+                expected,
+                "{:?} {:?}",
+                chunk_xyz,
+                offset
+            );
+        }
+
+        // A cell without any walls in a chunk that was inserted rather than generated
+        let other_xyz = (chunk_xyz.0 + 1, chunk_xyz.1, chunk_xyz.2);
+        let mut other_chunk = geometry_test_chunk(other_xyz, dims.grid_size());
+        other_chunk.cells[1][1] = Cell::default();
+        geometry.insert_chunk(7, other_chunk);
+        let other_center = ChunkCellMarker::new(other_xyz, (1, 1)).world_position(&dims);
+        assert!(!geometry.is_position_solid(7, other_center + Vec3::Y * 0.01, &dims));
+
+        drop(geometry);
+        assert_eq!(generated, vec![(7, chunk_xyz)]);
+    }
+}
+
+#[test]
+fn test_hazard_occupants_tick_interval() {
+    let mut world = World::new();
+    let a = world.spawn_empty().id();
+    let b = world.spawn_empty().id();
+
+    let interval = Hazard::SpikeTrap.tick_interval();
+    let n = interval.unwrap();
+    let mut occupants = HazardOccupants::default();
+
+    // Hit as soon as they step in, then once every interval frames
+    assert_eq!(occupants.tick([a], interval), vec![a]);
+    for _ in 1..n {
+        assert!(occupants.tick([a], interval).is_empty());
+    }
+    assert_eq!(occupants.tick([a, b], interval), vec![a, b]);
+    assert!(occupants.tick([a, b], interval).is_empty());
+
+    // Stepping out and back in hits again straight away
+    assert!(occupants.tick([b], interval).is_empty());
+    assert!(!occupants.contains(a));
+    assert_eq!(occupants.tick([a, b], interval), vec![a]);
+}
+
+#[test]
+fn test_hazard_occupants_once_per_entry() {
+    let mut world = World::new();
+    let a = world.spawn_empty().id();
+
+    let interval = Hazard::PoisonPool.tick_interval();
+    assert_eq!(interval, None);
+    let mut occupants = HazardOccupants::default();
+
+    assert_eq!(occupants.tick([a, a], interval), vec![a]);
+    for _ in 0..1000 {
+        assert!(occupants.tick([a], interval).is_empty());
+    }
+
+    assert!(occupants.tick([], interval).is_empty());
+    assert_eq!(occupants.tick([a], interval), vec![a]);
+}
+
+#[test]
+fn test_chunk_cell_marker_neighbor() {
+    let ccm = ChunkCellMarker {
+        chunk_x: 0,
+        chunk_y: 0,
+        chunk_z: 0,
+        x: 0,
+        z: GRID_SIZE - 1,
+    };
+
+    // Crossing into the neighboring chunks
+    let top = ccm.neighbor(Side::Top, GRID_SIZE);
+    assert_eq!(
+        (top.chunk_xyz(), top.cell_xz()),
+        ((1, 0, 0), (GRID_SIZE - 1, GRID_SIZE - 1))
+    );
+    let right = ccm.neighbor(Side::Right, GRID_SIZE);
+    assert_eq!((right.chunk_xyz(), right.cell_xz()), ((0, 0, -1), (0, 0)));
+
+    // Staying inside of the same chunk
+    let bottom = ccm.neighbor(Side::Bottom, GRID_SIZE);
+    assert_eq!(
+        (bottom.chunk_xyz(), bottom.cell_xz()),
+        ((0, 0, 0), (1, GRID_SIZE - 1))
+    );
+    let up = ccm.neighbor(Side::Up, GRID_SIZE);
+    assert_eq!((up.chunk_xyz(), up.cell_xz()), ((0, 1, 0), ccm.cell_xz()));
+
+    for side in [
+        Side::Top,
+        Side::Bottom,
+        Side::Left,
+        Side::Right,
+        Side::Up,
+        Side::Down,
+    ] {
+        assert_eq!(
+            ccm.neighbor(side, GRID_SIZE)
+                .neighbor(side.opposite(), GRID_SIZE),
+            ccm
+        );
+    }
+}
+
+#[test]
+fn test_cell_mutations_survive_save_and_load() {
+    let mut world_data = WorldData::default();
+    assert!(world_data.add_cell_mutation((1, 0, -1), (2, 3), CellMutation::RemoveWallLeft));
+    assert!(world_data.add_cell_mutation((1, 0, -1), (2, 3), CellMutation::RemoveFloor));
+    assert!(!world_data.add_cell_mutation((1, 0, -1), (2, 3), CellMutation::RemoveFloor));
+
+    let json = serde_json::to_string(&world_data).unwrap();
+    let world_data: WorldData = serde_json::from_str(&json).unwrap();
+
+    let mut chunk = new_chunk(1, 0, -1, NO_WORLD_STRUCTURE);
+    for cell in chunk.cells.iter_mut().flatten() {
+        *cell = Cell {
+            wall_left: CellWall::SolidWithDoorGap,
+            door_left: true,
+            ..Cell::new_floored()
+        };
+    }
+    world_data.apply_cell_mutations(&mut chunk);
+
+    let mutated = &chunk.cells[3][2];
+    assert_eq!(mutated.wall_left, CellWall::None);
+    assert!(!mutated.door_left);
+    assert_eq!(mutated.floor, CellWall::None);
+
+    // Every other cell is left alone
+    let mutated_cells = chunk
+        .cells
+        .iter()
+        .flatten()
+        .filter(|cell| cell.floor == CellWall::None)
+        .count();
+    assert_eq!(mutated_cells, 1);
+
+    // Mutations of other chunks do not apply
+    let mut other_chunk = new_chunk(0, 0, 0, NO_WORLD_STRUCTURE);
+    world_data.apply_cell_mutations(&mut other_chunk);
+    assert_eq!(other_chunk, new_chunk(0, 0, 0, NO_WORLD_STRUCTURE));
+}
+
+// Chunk of floored cells that are walled off from each other on every side
+fn new_sealed_chunk(x: i64, y: i64, z: i64) -> Chunk {
+    let cell = Cell {
+        wall_top: CellWall::Solid,
+        wall_bottom: CellWall::Solid,
+        wall_left: CellWall::Solid,
+        wall_right: CellWall::Solid,
+        ceiling: CellWall::Solid,
+        ..Cell::new_floored()
+    };
+    Chunk {
+        cells: vec![vec![cell; GRID_SIZE]; GRID_SIZE],
+        ..new_chunk(x, y, z, NO_WORLD_STRUCTURE)
+    }
+}
+
+fn cell_mut<'a>(
+    chunks: &'a mut HashMap<(i64, i64, i64), Chunk>,
+    ccm: &ChunkCellMarker,
+) -> &'a mut Cell {
+    &mut chunks.get_mut(&ccm.chunk_xyz()).unwrap().cells[ccm.z][ccm.x]
+}
+
+// Knocks out the walls on both sides of the passage between a cell and its neighbor
+fn carve(
+    chunks: &mut HashMap<(i64, i64, i64), Chunk>,
+    ccm: &ChunkCellMarker,
+    side: Side,
+) -> ChunkCellMarker {
+    CellMutation::from_side(side).apply(cell_mut(chunks, ccm));
+    let nei = ccm.neighbor(side, GRID_SIZE);
+    CellMutation::from_side(side.opposite()).apply(cell_mut(chunks, &nei));
+    nei
+}
+
+// Chunks that are not set up by a test are sealed off
+fn new_nav_graph(
+    chunks: HashMap<(i64, i64, i64), Chunk>,
+    max_chunk_radius: u32,
+) -> NavGraph<impl FnMut((i64, i64, i64)) -> Chunk> {
+    let dims = WorldDims::new(GRID_SIZE as f32 * 4.0, 4.0).unwrap();
+    NavGraph::new(dims, (0, 0, 0), max_chunk_radius, move |(x, y, z)| {
+        chunks
+            .get(&(x, y, z))
+            .cloned()
+            .unwrap_or_else(|| new_sealed_chunk(x, y, z))
+    })
+}
+
+#[test]
+fn test_nav_path_within_chunk() {
+    let mut chunks = HashMap::new();
+    chunks.insert((0, 0, 0), new_sealed_chunk(0, 0, 0));
+
+    let start = ChunkCellMarker::new((0, 0, 0), (0, 0));
+    let mut cells = vec![start.clone()];
+    for side in [Side::Bottom, Side::Bottom, Side::Right, Side::Right] {
+        let next = carve(&mut chunks, cells.last().unwrap(), side);
+        cells.push(next);
+    }
+    // A longer way around leading to the same place
+    let mut ccm = start.clone();
+    for side in [
+        Side::Right,
+        Side::Right,
+        Side::Right,
+        Side::Bottom,
+        Side::Bottom,
+        Side::Left,
+    ] {
+        ccm = carve(&mut chunks, &ccm, side);
+    }
+    assert_eq!(ccm.cell_xz(), (2, 2));
+    let goal = cells.last().unwrap().clone();
+    assert_eq!(goal.cell_xz(), (2, 2));
+
+    let mut nav_graph = new_nav_graph(chunks, 0);
+    assert_eq!(nav_graph.find_cell_path(&start, &goal), Some(cells.clone()));
+
+    let dims = WorldDims::new(GRID_SIZE as f32 * 4.0, 4.0).unwrap();
+    let waypoints: Vec<Vec3> = cells.iter().map(|c| c.world_position(&dims)).collect();
+    assert_eq!(nav_graph.find_path(&start, &goal), Some(waypoints));
+}
+
+#[test]
+fn test_nav_path_across_chunk_border() {
+    let mut chunks = HashMap::new();
+    chunks.insert((0, 0, 0), new_sealed_chunk(0, 0, 0));
+    chunks.insert((1, 0, 0), new_sealed_chunk(1, 0, 0));
+
+    let start = ChunkCellMarker::new((0, 0, 0), (1, 1));
+    let mut cells = vec![start.clone()];
+    for side in [Side::Top, Side::Top, Side::Top] {
+        let next = carve(&mut chunks, cells.last().unwrap(), side);
+        cells.push(next);
+    }
+    let goal = cells.last().unwrap().clone();
+    assert_eq!(goal, ChunkCellMarker::new((1, 0, 0), (GRID_SIZE - 2, 1)));
+
+    let mut nav_graph = new_nav_graph(chunks.clone(), 1);
+    assert_eq!(nav_graph.find_cell_path(&start, &goal), Some(cells));
+    // And back again
+    assert!(nav_graph.find_cell_path(&goal, &start).is_some());
+
+    // Chunks outside of the search radius are never reached
+    let mut nav_graph = new_nav_graph(chunks, 0);
+    assert_eq!(nav_graph.find_cell_path(&start, &goal), None);
+}
+
+#[test]
+fn test_nav_path_through_stairs_and_holes() {
+    let mut chunks = HashMap::new();
+    chunks.insert((0, 0, 0), new_sealed_chunk(0, 0, 0));
+    chunks.insert((0, 1, 0), new_sealed_chunk(0, 1, 0));
+
+    let stairs = ChunkCellMarker::new((0, 0, 0), (1, 1));
+    let above = stairs.neighbor(Side::Up, GRID_SIZE);
+    cell_mut(&mut chunks, &stairs).special = CellSpecial::Staircase;
+    CellMutation::RemoveCeiling.apply(cell_mut(&mut chunks, &stairs));
+    CellMutation::RemoveFloor.apply(cell_mut(&mut chunks, &above));
+    let goal = carve(&mut chunks, &above, Side::Left);
+
+    let mut nav_graph = new_nav_graph(chunks, 1);
+    assert_eq!(
+        nav_graph.find_cell_path(&stairs, &goal),
+        Some(vec![stairs.clone(), goal.clone()])
+    );
+    // Dropping back down through the hole lands on the stairs
+    assert_eq!(
+        nav_graph.find_cell_path(&goal, &stairs),
+        Some(vec![goal, stairs])
+    );
+}
+
+#[test]
+fn test_nav_path_none_between_sealed_rooms() {
+    let mut chunks = HashMap::new();
+    chunks.insert((0, 0, 0), new_sealed_chunk(0, 0, 0));
+
+    // Two rooms, with a window between them that cannot be walked through
+    let start = ChunkCellMarker::new((0, 0, 0), (0, 0));
+    let room_a = carve(&mut chunks, &start, Side::Bottom);
+    let goal = room_a.neighbor(Side::Bottom, GRID_SIZE);
+    cell_mut(&mut chunks, &room_a).wall_bottom = CellWall::SolidWithWindowGap;
+    cell_mut(&mut chunks, &goal).wall_top = CellWall::SolidWithWindowGap;
+    carve(&mut chunks, &goal, Side::Right);
+
+    let mut nav_graph = new_nav_graph(chunks.clone(), 2);
+    assert_eq!(nav_graph.find_cell_path(&start, &goal), None);
+
+    // Door gaps are fine though
+    cell_mut(&mut chunks, &room_a).wall_bottom = CellWall::SolidWithDoorGap;
+    cell_mut(&mut chunks, &goal).wall_top = CellWall::SolidWithDoorGap;
+    let mut nav_graph = new_nav_graph(chunks, 2);
+    assert_eq!(
+        nav_graph.find_cell_path(&start, &goal).map(|p| p.len()),
+        Some(3)
+    );
+}
+
+#[test]
+fn test_pick_up_grace_tick() {
+    let mut grace = PickUpGrace(2);
+    assert!(!grace.tick(false));
+    assert!(grace.tick(false));
+
+    // Lasts for as long as the player stays in range once the frames are up
+    let mut grace = PickUpGrace(1);
+    assert!(!grace.tick(true));
+    assert!(!grace.tick(true));
+    assert!(grace.tick(false));
+}
+
+#[test]
+fn test_treasure_chest_data_remaining() {
+    let mut treasure_chest_data = TreasureChestData::default();
+    assert_eq!(treasure_chest_data.remaining(), None);
+
+    treasure_chest_data.set_remaining(Some(Item::new(ItemName::Coal, 2)));
+    assert_eq!(
+        treasure_chest_data.remaining(),
+        Some(Some(Item::new(ItemName::Coal, 2)))
+    );
+    treasure_chest_data.set_remaining(None);
+    assert_eq!(treasure_chest_data.remaining(), Some(None));
+
+    // Old saves only ever had chest data once a chest was emptied
+    let cell_data: CellData =
+        serde_json::from_str(r#"{"treasure_chest_data":{"item":null}}"#).unwrap();
+    assert_eq!(cell_data.treasure_chest_data.remaining(), Some(None));
+
+    let json = serde_json::to_string(&CellData::default()).unwrap();
+    let cell_data: CellData = serde_json::from_str(&json).unwrap();
+    assert_eq!(cell_data.treasure_chest_data.remaining(), None);
+}
+
+#[test]
+fn test_cell_flags_survive_save_and_load() {
+    let mut world_data = WorldData::default();
+    let cell_data = world_data.at_cell_or_create_mut((1, 0, -1), (2, 3));
+    cell_data.set_flag("lever_pulled", true);
+    cell_data.set_flag("visits", 3);
+    cell_data.set_flag("owner", "goblin");
+    cell_data.set_flag("path", serde_json::json!([1, 2, 3]));
+    cell_data.set_flag("removed", 1.5);
+    assert_eq!(
+        cell_data.remove_flag("removed"),
+        Some(serde_json::json!(1.5))
+    );
+
+    let json = serde_json::to_string(&world_data).unwrap();
+    let world_data: WorldData = serde_json::from_str(&json).unwrap();
+    let cell_data = world_data.at_cell((1, 0, -1), (2, 3)).unwrap();
+
+    assert_eq!(
+        cell_data.get_flag("lever_pulled"),
+        Some(&serde_json::json!(true))
+    );
+    assert_eq!(cell_data.get_flag("visits"), Some(&serde_json::json!(3)));
+    assert_eq!(
+        cell_data.get_flag("owner"),
+        Some(&serde_json::json!("goblin"))
+    );
+    assert_eq!(
+        cell_data.get_flag("path"),
+        Some(&serde_json::json!([1, 2, 3]))
+    );
+    assert_eq!(cell_data.get_flag("removed"), None);
+}
+
+#[test]
+fn test_chest_opened_flag() {
+    let mut cell_data = CellData::default();
+    assert!(!cell_data.chest_opened());
+
+    cell_data.set_chest_opened(true);
+    assert!(cell_data.chest_opened());
+    assert_eq!(
+        cell_data.get_flag(CHEST_OPENED_FLAG),
+        Some(&serde_json::json!(true))
+    );
+
+    cell_data.set_chest_opened(false);
+    assert!(!cell_data.chest_opened());
+    assert!(cell_data.is_empty());
+
+    // Saves from before the flag kept it with the rest of the chest data
+    let cell_data: CellData = serde_json::from_str(
+        r#"{"treasure_chest_data":{"item":null,"picked_from":false,"opened":true}}"#,
+    )
+    .unwrap();
+    assert!(cell_data.chest_opened());
+    assert_eq!(cell_data.treasure_chest_data, TreasureChestData::default());
+}
+
+#[test]
+fn test_chest_unlocked_flag_survives_a_save() {
+    let mut world_data = WorldData::default();
+    let cell_data = world_data.at_cell_or_create_mut((0, 0, 0), (2, 3));
+    assert!(!cell_data.chest_unlocked());
+
+    cell_data.set_chest_unlocked(true);
+    assert_eq!(
+        cell_data.get_flag(CHEST_UNLOCKED_FLAG),
+        Some(&serde_json::json!(true))
+    );
+
+    let json = serde_json::to_string(&world_data).unwrap();
+    let mut world_data: WorldData = serde_json::from_str(&json).unwrap();
+    let cell_data = world_data.at_cell_or_create_mut((0, 0, 0), (2, 3));
+    assert!(cell_data.chest_unlocked());
+    assert!(!cell_data.chest_opened());
+
+    // Locked again is the default, so nothing is left to save
+    cell_data.set_chest_unlocked(false);
+    assert!(cell_data.is_empty());
+}
+
+#[test]
+fn test_window_broken_flag_is_kept_per_side() {
+    let mut world_data = WorldData::default();
+    let cell_data = world_data.at_cell_or_create_mut((0, 0, 0), (1, 1));
+    cell_data.set_window_broken(Side::Left, true);
+    assert_eq!(
+        cell_data.get_flag(&format!("{}_left", WINDOW_BROKEN_FLAG)),
+        Some(&serde_json::json!(true))
+    );
+
+    let json = serde_json::to_string(&world_data).unwrap();
+    let mut world_data: WorldData = serde_json::from_str(&json).unwrap();
+    let cell_data = world_data.at_cell_or_create_mut((0, 0, 0), (1, 1));
+    assert!(cell_data.window_broken(Side::Left));
+    assert!(!cell_data.window_broken(Side::Right));
+
+    cell_data.set_window_broken(Side::Left, false);
+    assert!(cell_data.is_empty());
+}
+
+#[test]
+fn test_locked_opens_with_keys_of_its_tier_or_higher() {
+    let locked = Locked { tier: 2 };
+    assert!(!locked.opens_with(1));
+    assert!(locked.opens_with(2));
+    assert!(locked.opens_with(3));
+}
+
+#[test]
+fn test_empty_cells_are_not_saved() {
+    let mut world_data = WorldData::default();
+    world_data
+        .at_cell_or_create_mut((0, 0, 0), (1, 1))
+        .set_flag("lever_pulled", true);
+    world_data
+        .at_cell_or_create_mut((0, 0, 0), (1, 1))
+        .remove_flag("lever_pulled");
+    world_data
+        .at_cell_or_create_mut((0, 0, 0), (2, 2))
+        .set_chest_opened(true);
+    world_data
+        .at_cell_or_create_mut((0, 0, 1), (1, 1))
+        .set_chest_opened(false);
+    assert_eq!(world_data.chunks_count(), 2);
+
+    let json = serde_json::to_string(&world_data).unwrap();
+    let world_data: WorldData = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(world_data.chunks_count(), 1);
+    assert!(world_data.at_cell((0, 0, 0), (1, 1)).is_none());
+    assert!(world_data
+        .at_cell((0, 0, 0), (2, 2))
+        .is_some_and(CellData::chest_opened));
+
+    let json = serde_json::to_string(&WorldData::default()).unwrap();
+    assert_eq!(json, "{}");
+}
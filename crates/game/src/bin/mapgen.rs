@@ -0,0 +1,158 @@
+// Renders chunk maps without launching the game, to make tuning world generation easier.
+// Usage: cargo run -p dungeon_maze_game --bin mapgen -- [OPTIONS]
+
+use dungeon_maze_common::{
+    settings::Difficulty,
+    world::{
+        world_structure::WorldStructureRegistry, WorldConfig, WorldDims, DEFAULT_CELL_SIZE,
+        DEFAULT_CHUNK_SIZE, DEFAULT_SURFACE_LEVEL, DEFAULT_WORLD_SEED,
+    },
+};
+use dungeon_maze_game::plugins::world::map_render::{
+    gen_map_levels, map_to_html, map_to_png, MapArea, MAP_MARKERS,
+};
+use std::{env, fs, process};
+
+const USAGE: &str = "Usage: mapgen [OPTIONS]
+
+Options:
+    --seed <SEED>           World seed [default: 123456]
+    --difficulty <NAME>     easy, normal, or hard [default: normal]
+    --surface-level <Y>     Lowest y level that is open to the sky, or none [default: 1]
+    --center <X,Y,Z>        Center chunk [default: 0,0,0]
+    --radius <RADIUS>       Chunks to render on each side of the center chunk [default: 1]
+    --y-levels <Y,...>      Comma separated y levels to render [default: y of the center chunk]
+    --chunk-size <SIZE>     Size of chunks in world units [default: 16]
+    --cell-size <SIZE>      Size of cells in world units [default: 4]
+    --html <PATH>           Path of the html map to write [default: map.html]
+    --png <PATH>            Path of a png map to also write
+    --world-structures <DIR>
+                            Directory of world structure files [default: assets/world_structures]
+    -h, --help              Print this message";
+
+struct Args {
+    area: MapArea,
+    html_path: String,
+    png_path: Option<String>,
+    world_structures_dir: String,
+}
+
+fn parse_list<T: std::str::FromStr>(value: &str, flag: &str) -> Result<Vec<T>, String> {
+    value
+        .split(',')
+        .map(|s| s.trim().parse::<T>())
+        .collect::<Result<Vec<T>, _>>()
+        .map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut seed = DEFAULT_WORLD_SEED;
+    let mut difficulty = Difficulty::default();
+    let mut surface_level = Some(DEFAULT_SURFACE_LEVEL);
+    let mut center = (0, 0, 0);
+    let mut radius = 1;
+    let mut y_levels: Option<Vec<i64>> = None;
+    let mut chunk_size = DEFAULT_CHUNK_SIZE;
+    let mut cell_size = DEFAULT_CELL_SIZE;
+    let mut html_path = "map.html".to_owned();
+    let mut png_path = None;
+    let mut world_structures_dir = "assets/world_structures".to_owned();
+
+    let mut args = args.skip(1);
+    while let Some(flag) = args.next() {
+        if flag == "-h" || flag == "--help" {
+            println!("{}", USAGE);
+            process::exit(0);
+        }
+
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {}", flag))?;
+        let invalid = || format!("invalid value for {}: {}", flag, value);
+
+        match flag.as_str() {
+            "--seed" => seed = value.parse().map_err(|_| invalid())?,
+            "--difficulty" => {
+                difficulty = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard]
+                    .into_iter()
+                    .find(|d| d.label().eq_ignore_ascii_case(&value))
+                    .ok_or_else(invalid)?
+            }
+            "--surface-level" => {
+                surface_level = match value.as_str() {
+                    "none" => None,
+                    _ => Some(value.parse().map_err(|_| invalid())?),
+                }
+            }
+            "--center" => match parse_list::<i64>(&value, &flag)?[..] {
+                [x, y, z] => center = (x, y, z),
+                _ => return Err(invalid()),
+            },
+            "--radius" => radius = value.parse().map_err(|_| invalid())?,
+            "--y-levels" => y_levels = Some(parse_list(&value, &flag)?),
+            "--chunk-size" => chunk_size = value.parse().map_err(|_| invalid())?,
+            "--cell-size" => cell_size = value.parse().map_err(|_| invalid())?,
+            "--html" => html_path = value,
+            "--png" => png_path = Some(value),
+            "--world-structures" => world_structures_dir = value,
+            _ => return Err(format!("unknown option: {}", flag)),
+        }
+    }
+
+    let dims = WorldDims::new(chunk_size, cell_size).map_err(|err| err.to_string())?;
+
+    Ok(Args {
+        area: MapArea {
+            seed,
+            difficulty,
+            // Mapped the same as a new game, starting room and all
+            world_config: WorldConfig {
+                surface_level,
+                ..WorldConfig::default()
+            },
+            dims,
+            center,
+            radius,
+            y_levels: y_levels.unwrap_or_else(|| vec![center.1]),
+        },
+        html_path,
+        png_path,
+        world_structures_dir,
+    })
+}
+
+fn main() {
+    let args = parse_args(env::args()).unwrap_or_else(|err| {
+        eprintln!("{}\n\n{}", err, USAGE);
+        process::exit(2);
+    });
+
+    let world_structures =
+        WorldStructureRegistry::from_dir(&args.world_structures_dir, args.area.dims.grid_size())
+            .unwrap_or_else(|err| {
+                eprintln!("Error reading {}: {}", args.world_structures_dir, err);
+                process::exit(1);
+            });
+
+    let levels = gen_map_levels(&args.area, &world_structures);
+
+    if let Err(err) = fs::write(&args.html_path, map_to_html(&args.area, &levels)) {
+        eprintln!("Error writing {}: {}", args.html_path, err);
+        process::exit(1);
+    }
+    println!("Wrote {}", args.html_path);
+
+    if let Some(png_path) = args.png_path {
+        if let Err(err) = map_to_png(&levels).save(&png_path) {
+            eprintln!("Error writing {}: {}", png_path, err);
+            process::exit(1);
+        }
+        println!("Wrote {}", png_path);
+
+        // Png maps have no room for a legend, so it is printed instead
+        for marker in MAP_MARKERS {
+            let [r, g, b] = marker.color();
+            println!("  #{:02x}{:02x}{:02x} {}", r, g, b, marker.label());
+        }
+    }
+}
@@ -1,5 +1 @@
 pub mod plugins;
-
-dungeon_maze_proc_macros::proc_parse_world_structures!();
-
-pub const SEED: u32 = 123456;
@@ -2,34 +2,42 @@ use bevy::prelude::*;
 use bevy_embedded_assets::EmbeddedAssetPlugin;
 use bevy_rapier3d::prelude::*;
 use bevy_text_popup::TextPopupPlugin;
+use dungeon_maze_common::{
+    player::PLAYER_TICK_HZ,
+    world::{WorldDims, DEFAULT_CELL_SIZE, DEFAULT_CHUNK_SIZE},
+};
 use dungeon_maze_game::plugins::{
-    animation::AnimationPlugin,
-    camera::CameraPlugin,
-    cursor::CursorPlugin,
-    hud::HudPlugin,
-    interaction::InteractionPlugin,
-    inventory::InventoryPlugin,
-    menu::MenuPlugin,
-    player::PlayerPlugin,
-    save::GameSavePlugin,
-    settings::SettingsPlugin,
-    world::{WorldPlugin, CELL_SIZE, CHUNK_SIZE},
+    animation::AnimationPlugin, automap::AutomapPlugin, camera::CameraPlugin, cursor::CursorPlugin,
+    death_marker::DeathMarkerPlugin, hud::HudPlugin, interaction::InteractionPlugin,
+    inventory::InventoryPlugin, loading::LoadingPlugin, main_menu::MainMenuPlugin,
+    menu::MenuPlugin, player::PlayerPlugin, save::GameSavePlugin, settings::SettingsPlugin,
+    spectator::SpectatorPlugin, stats::StatsPlugin, throw::ThrowPlugin, world::WorldPlugin,
 };
+use std::env;
 
 #[cfg(debug_assertions)]
 use dungeon_maze_game::plugins::debug::DebugPlugin;
 
+// Reads the value following a flag such as --chunk-size 32
+fn arg_value(args: &[String], flag: &str) -> Option<f32> {
+    let i = args.iter().position(|arg| arg == flag)?;
+    args.get(i + 1)?.parse().ok()
+}
+
 fn main() {
-    assert_eq!(
-        CHUNK_SIZE % CELL_SIZE,
-        0.0,
-        "expected chunk size ({}) to be divisible by cell size ({})",
-        CHUNK_SIZE,
-        CELL_SIZE,
-    );
+    let args: Vec<String> = env::args().collect();
+    let world_dims = WorldDims::new(
+        arg_value(&args, "--chunk-size").unwrap_or(DEFAULT_CHUNK_SIZE),
+        arg_value(&args, "--cell-size").unwrap_or(DEFAULT_CELL_SIZE),
+    )
+    .unwrap_or_else(|err| panic!("{}", err));
 
     let mut app = App::new();
 
+    app.insert_resource(world_dims);
+    // Every FixedUpdate system in the game ticks at the player's tick rate
+    app.insert_resource(Time::<Fixed>::from_hz(PLAYER_TICK_HZ));
+
     app.add_plugins((EmbeddedAssetPlugin::default(), DefaultPlugins));
 
     app.add_plugins((
@@ -46,9 +54,19 @@ fn main() {
         GameSavePlugin,
         WorldPlugin,
         HudPlugin,
+        AutomapPlugin,
         #[cfg(debug_assertions)]
         DebugPlugin,
     ));
 
+    app.add_plugins((
+        StatsPlugin,
+        DeathMarkerPlugin,
+        MainMenuPlugin,
+        LoadingPlugin,
+        SpectatorPlugin,
+        ThrowPlugin,
+    ));
+
     app.run();
 }
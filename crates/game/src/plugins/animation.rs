@@ -1,33 +1,63 @@
-use bevy::{animation::animate_targets, prelude::*};
+use bevy::{animation::animate_targets, asset::AssetPath, gltf::Gltf, prelude::*};
 use dungeon_maze_common::{
-    animation::{AnimationLib, ContinuousAnimation, CyclicAnimation, PlayerAnimation},
+    animation::{
+        AnimationLib, ContinuousAnimation, CyclicAnimation, CyclicAnimationCycled, PlayerAnimation,
+        PLAYER_ANIMATION_CLIPS,
+    },
+    app_state::AppState,
     interaction::{Interactable, PendingInteractionExecuted},
     inventory::Inventory,
-    player::PlayerState,
+    player::{attack::AttackHand, PlayerState},
     utils::entity::get_n_parent,
 };
 use std::time::Duration;
 
 const TRANSITION_DURATION: Duration = Duration::from_millis(250);
+const PLAYER_MODEL_PATH: &str = "embedded://models/man.glb";
+// How far before the end of a clip models are posed when spawned at rest. Sampling exactly
+// at the end of a clip counts as finished, and leaves the model in its own pose instead.
+const REST_POSE_SECS_BEFORE_END: f32 = 0.001;
 
 pub struct AnimationPlugin;
 
 impl Plugin for AnimationPlugin {
     fn build(&self, app: &mut App) {
         app.init_state::<PlayerAnimation>()
+            .add_event::<CyclicAnimationCycled>()
             .add_systems(Startup, setup_animations)
+            .add_systems(
+                Update,
+                validate_player_animation_clips.run_if(on_event::<AssetEvent<Gltf>>()),
+            )
             .add_systems(
                 Update,
                 (
                     play_continuous_animations.before(animate_targets),
+                    pose_cyclic_animations_at_rest.before(animate_targets),
                     handle_cyclic_interaction_animations,
                     change_player_animation,
                     on_finish_attack_animation,
-                ),
+                )
+                    .run_if(in_state(AppState::InGame)),
             );
     }
 }
 
+// In the same order as the nodes of the animation graph
+pub fn animation_clip_paths() -> Vec<AssetPath<'static>> {
+    let mut paths: Vec<AssetPath<'static>> = PLAYER_ANIMATION_CLIPS
+        .iter()
+        .map(|(_, index)| GltfAssetLabel::Animation(*index).from_asset(PLAYER_MODEL_PATH))
+        .collect();
+
+    paths.extend([
+        GltfAssetLabel::Animation(1).from_asset("embedded://models/treasure_chest.glb"), // open
+        GltfAssetLabel::Animation(0).from_asset("embedded://models/treasure_chest.glb"), // close
+    ]);
+
+    paths
+}
+
 fn setup_animations(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
@@ -37,28 +67,9 @@ fn setup_animations(
     let mut graph = AnimationGraph::new();
     let nodes = graph
         .add_clips(
-            [
-                GltfAssetLabel::Animation(PlayerAnimation::Idle.index())
-                    .from_asset("embedded://models/man.glb"), // idle
-                GltfAssetLabel::Animation(PlayerAnimation::Jogging.index())
-                    .from_asset("embedded://models/man.glb"), // jogging
-                GltfAssetLabel::Animation(PlayerAnimation::OneHandedSlashRightLightAttack.index())
-                    .from_asset("embedded://models/man.glb"), // slash right light attack
-                GltfAssetLabel::Animation(PlayerAnimation::Running.index())
-                    .from_asset("embedded://models/man.glb"), // running
-                GltfAssetLabel::Animation(PlayerAnimation::UnarmedLeftHeavyAttack.index())
-                    .from_asset("embedded://models/man.glb"), // unarmed left heavy attack
-                GltfAssetLabel::Animation(PlayerAnimation::UnarmedLeftLightAttack.index())
-                    .from_asset("embedded://models/man.glb"), // unarmed left light attack
-                GltfAssetLabel::Animation(PlayerAnimation::UnarmedRightHeavyAttack.index())
-                    .from_asset("embedded://models/man.glb"), // unarmed right heavy attack
-                GltfAssetLabel::Animation(PlayerAnimation::UnarmedRightLightAttack.index())
-                    .from_asset("embedded://models/man.glb"), // unarmed right light attack
-                GltfAssetLabel::Animation(1).from_asset("embedded://models/treasure_chest.glb"), // open
-                GltfAssetLabel::Animation(0).from_asset("embedded://models/treasure_chest.glb"), // close
-            ]
-            .into_iter()
-            .map(|path| asset_server.load(path)),
+            animation_clip_paths()
+                .into_iter()
+                .map(|path| asset_server.load(path)),
             1.0,
             graph.root,
         )
@@ -68,9 +79,44 @@ fn setup_animations(
     commands.insert_resource(AnimationLib {
         nodes,
         graph: graphs.add(graph),
+        player_gltf: asset_server.load(PLAYER_MODEL_PATH),
+        player_clip_count: None,
     });
 }
 
+// Animations without a clip in the player's glb fall back to the default animation,
+// rather than playing whichever clip happens to be at their index
+fn validate_player_animation_clips(
+    mut event_reader: EventReader<AssetEvent<Gltf>>,
+    mut animation_lib: ResMut<AnimationLib>,
+    gltfs: Res<Assets<Gltf>>,
+) {
+    for event in event_reader.read() {
+        let AssetEvent::LoadedWithDependencies { id } = event else {
+            continue;
+        };
+        if *id != animation_lib.player_gltf.id() {
+            continue;
+        }
+        let Some(gltf) = gltfs.get(*id) else {
+            continue;
+        };
+
+        let clip_count = gltf.animations.len();
+        for player_animation in PlayerAnimation::missing_clips(clip_count) {
+            error!(
+                "{} has no clip at index {} for {:?} ({} clips found), falling back to {:?}",
+                PLAYER_MODEL_PATH,
+                player_animation.index(),
+                player_animation,
+                clip_count,
+                PlayerAnimation::default(),
+            );
+        }
+        animation_lib.player_clip_count = Some(clip_count);
+    }
+}
+
 fn play_continuous_animations(
     mut commands: Commands,
     mut animation_player_query: Query<Entity, Added<AnimationPlayer>>,
@@ -91,9 +137,49 @@ fn play_continuous_animations(
     }
 }
 
+// Puts models with a rest clip in the final pose of that clip, without playing through it
+fn pose_cyclic_animations_at_rest(
+    mut commands: Commands,
+    mut animation_player_query: Query<(Entity, &mut AnimationPlayer), Added<AnimationPlayer>>,
+    cyclic_animation_query: Query<&CyclicAnimation>,
+    parent_query: Query<&Parent>,
+    animation_lib: Res<AnimationLib>,
+    graphs: Res<Assets<AnimationGraph>>,
+    clips: Res<Assets<AnimationClip>>,
+) {
+    for (entity, mut animation_player) in &mut animation_player_query {
+        let Some(rest_clip) = cyclic_animation_query
+            .get(get_n_parent(entity, &parent_query, 3))
+            .ok()
+            .and_then(|cyclic_animation| cyclic_animation.rest_clip())
+        else {
+            continue;
+        };
+
+        let node = animation_lib.nodes[rest_clip as usize];
+        let duration = graphs
+            .get(&animation_lib.graph)
+            .and_then(|graph| graph.get(node))
+            .and_then(|graph_node| graph_node.clip.as_ref())
+            .and_then(|clip| clips.get(clip))
+            .map(|clip| clip.duration());
+
+        // Plays through from the start if the clip has not loaded yet
+        let active_animation = animation_player.play(node);
+        if let Some(duration) = duration {
+            active_animation
+                .seek_to((duration - REST_POSE_SECS_BEFORE_END).max(0.0))
+                .pause();
+        }
+
+        commands.entity(entity).insert(animation_lib.graph.clone());
+    }
+}
+
 fn handle_cyclic_interaction_animations(
     mut commands: Commands,
     mut event_reader: EventReader<PendingInteractionExecuted>,
+    mut event_writer: EventWriter<CyclicAnimationCycled>,
     mut animation_player_query: Query<(Entity, &mut AnimationPlayer)>,
     mut cyclic_animation_query: Query<&mut CyclicAnimation, With<Interactable>>,
     parent_query: Query<&Parent>,
@@ -108,6 +194,10 @@ fn handle_cyclic_interaction_animations(
 
             if let Ok(mut cyclic_animation) = cyclic_animation_query.get_mut(parent) {
                 let c = cyclic_animation.cycle();
+                event_writer.send(CyclicAnimationCycled {
+                    entity: parent,
+                    clip: c,
+                });
 
                 animation_player.stop_all();
                 animation_player
@@ -138,32 +228,37 @@ fn change_player_animation(
 
         match ps {
             PlayerState::Walking | PlayerState::Sprinting => {
-                let i = if is_moving {
+                let new_pa = if is_moving {
                     if *ps == PlayerState::Walking && *pa != PlayerAnimation::Jogging {
-                        next_player_animation.set(PlayerAnimation::Jogging);
-                        PlayerAnimation::Jogging.index()
+                        PlayerAnimation::Jogging
                     } else if *ps == PlayerState::Sprinting && *pa != PlayerAnimation::Running {
-                        next_player_animation.set(PlayerAnimation::Running);
-                        PlayerAnimation::Running.index()
+                        PlayerAnimation::Running
                     } else {
                         continue;
                     }
                 } else if *pa != PlayerAnimation::Idle {
-                    next_player_animation.set(PlayerAnimation::Idle);
-                    PlayerAnimation::Idle.index()
+                    PlayerAnimation::Idle
                 } else {
                     continue;
                 };
+                next_player_animation.set(new_pa);
 
                 transitions
                     .play(
                         &mut animation_player,
-                        animation_lib.nodes[i],
+                        animation_lib.player_node(&new_pa),
                         TRANSITION_DURATION,
                     )
                     .repeat();
             }
-            PlayerState::Attacking(attack_type, attack_hand) => {
+            PlayerState::Attacking(..) | PlayerState::DualAttacking(..) => {
+                // The player has no clip for swinging with both hands yet,
+                // so dual attacks use the right hand's animation
+                let (attack_type, attack_hand) = match player_state.get() {
+                    PlayerState::DualAttacking(attack_type) => (attack_type, &AttackHand::Right),
+                    PlayerState::Attacking(attack_type, attack_hand) => (attack_type, attack_hand),
+                    PlayerState::Walking | PlayerState::Sprinting => continue,
+                };
                 let slot = inventory.equipment.at(&attack_hand.into());
                 if pa.is_matching_attack_animation(attack_type, attack_hand, slot) {
                     continue;
@@ -174,7 +269,7 @@ fn change_player_animation(
 
                 transitions.play(
                     &mut animation_player,
-                    animation_lib.nodes[new_pa.index()],
+                    animation_lib.player_node(&new_pa),
                     TRANSITION_DURATION,
                 );
             }
@@ -0,0 +1,447 @@
+use crate::plugins::world::{bundle::cell::cell_world_position, chunk_from_xyz_seed};
+use bevy::prelude::*;
+use dungeon_maze_common::{
+    app_state::AppState,
+    automap::*,
+    menu::{ActiveMenuTab, MenuOpen, MenuTab},
+    player::Player,
+    settings::Difficulty,
+    should_not_happen_once,
+    utils::debug::WarnOnce,
+    world::{
+        data::WorldData, world_structure::WorldStructureRegistry, CellWall, Chunk, ChunkCellMarker,
+        WorldConfig, WorldDims, WorldSeed,
+    },
+};
+use std::collections::HashMap;
+
+const AUTOMAP_CELL_PX: f32 = 16.0;
+const AUTOMAP_WALL_PX: f32 = 2.0;
+const AUTOMAP_PLAYER_MARKER_PX: f32 = 8.0;
+const AUTOMAP_PAN_SPEED: f32 = 40.0;
+const AUTOMAP_VISITED_WALL_COLOR: Color = Color::WHITE;
+const AUTOMAP_VISITED_CELL_COLOR: Color = Color::linear_rgba(0.3, 0.3, 0.3, 1.0);
+const AUTOMAP_REVEALED_WALL_COLOR: Color = Color::linear_rgba(0.5, 0.5, 0.5, 1.0);
+const AUTOMAP_REVEALED_CELL_COLOR: Color = Color::linear_rgba(0.12, 0.12, 0.12, 1.0);
+// Visited cells further than this many chunks from the center of the map are not drawn
+const AUTOMAP_VIEW_RADIUS_CHUNKS: f32 = 8.0;
+
+pub struct AutomapPlugin;
+
+impl Plugin for AutomapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VisitedCells>()
+            .init_resource::<RevealedCells>()
+            .init_resource::<AutomapView>()
+            .add_event::<VisitedCellsChanged>()
+            .add_systems(
+                Update,
+                (
+                    record_visited_cells,
+                    (
+                        pan_automap,
+                        change_automap_y_level,
+                        center_automap_on_player,
+                        redraw_automap,
+                    )
+                        .chain()
+                        .run_if(
+                            in_state(MenuOpen(true))
+                                .and_then(in_state(ActiveMenuTab(MenuTab::Map))),
+                        ),
+                )
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+// Only walls are drawn and seen through, and they do not depend on difficulty
+fn automap_chunk(
+    xyz: (i64, i64, i64),
+    world_seed: &WorldSeed,
+    world_config: &WorldConfig,
+    world_dims: &WorldDims,
+    world_structures: &WorldStructureRegistry,
+    world_data: &WorldData,
+) -> Chunk {
+    let mut chunk = chunk_from_xyz_seed(
+        world_seed.0,
+        Difficulty::default(),
+        world_config,
+        world_dims,
+        world_structures,
+        xyz.0,
+        xyz.1,
+        xyz.2,
+    );
+    world_data.apply_cell_mutations(&mut chunk);
+    chunk
+}
+
+// Visiting a cell also reveals the cells that can be seen from it
+fn record_visited_cells(
+    mut event_writer: EventWriter<VisitedCellsChanged>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    mut visited_cells: ResMut<VisitedCells>,
+    mut revealed_cells: ResMut<RevealedCells>,
+    mut prev_ccm: Local<Option<ChunkCellMarker>>,
+    mut prev_chunk: Local<Option<Chunk>>,
+    world_seed: Res<WorldSeed>,
+    world_config: Res<WorldConfig>,
+    world_dims: Res<WorldDims>,
+    world_structures: Res<WorldStructureRegistry>,
+    world_data: Res<WorldData>,
+    mut warn_once: Local<WarnOnce>,
+) {
+    let gt = match player_query.get_single() {
+        Ok(gt) => gt,
+        Err(err) => {
+            should_not_happen_once!(warn_once, "retrieving player: {}", err);
+            return;
+        }
+    };
+    let ccm = ChunkCellMarker::from_global_transform(gt, &world_dims);
+
+    if prev_ccm.as_ref() == Some(&ccm) {
+        return;
+    }
+
+    if visited_cells.insert(&ccm) {
+        // Cells in the same chunk as the last one do not need it to be generated again
+        let xyz = ccm.chunk_xyz();
+        let chunk = match prev_chunk.take() {
+            Some(chunk) if (chunk.x, chunk.y, chunk.z) == xyz && !world_data.is_changed() => chunk,
+            _ => automap_chunk(
+                xyz,
+                &world_seed,
+                &world_config,
+                &world_dims,
+                &world_structures,
+                &world_data,
+            ),
+        };
+        revealed_cells.reveal_from(&chunk, ccm.cell_xz());
+        *prev_chunk = Some(chunk);
+
+        event_writer.send(VisitedCellsChanged);
+    }
+    *prev_ccm = Some(ccm);
+}
+
+pub fn spawn_automap_menu_content(child_builder: &mut ChildBuilder) {
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                "Map",
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        ..default()
+    });
+
+    // Y level selector
+    child_builder
+        .spawn(NodeBundle {
+            style: Style {
+                display: Display::Flex,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(10.0),
+                margin: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            for (delta, label) in [(-1, "<"), (1, ">")] {
+                if delta > 0 {
+                    parent.spawn((
+                        AutomapYLevelText,
+                        TextBundle {
+                            text: Text {
+                                sections: vec![TextSection::new(
+                                    "",
+                                    TextStyle {
+                                        font_size: 16.0,
+                                        color: Color::WHITE,
+                                        ..default()
+                                    },
+                                )],
+                                ..default()
+                            },
+                            ..default()
+                        },
+                    ));
+                }
+
+                parent
+                    .spawn((
+                        AutomapYLevelButton(delta),
+                        ButtonBundle {
+                            style: Style {
+                                display: Display::Flex,
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                height: Val::Px(20.0),
+                                width: Val::Px(20.0),
+                                ..default()
+                            },
+                            background_color: Color::WHITE.into(),
+                            ..default()
+                        },
+                    ))
+                    .with_children(|grandparent| {
+                        grandparent.spawn(TextBundle {
+                            text: Text {
+                                sections: vec![TextSection::new(
+                                    label,
+                                    TextStyle {
+                                        font_size: 20.0,
+                                        color: Color::BLACK,
+                                        ..default()
+                                    },
+                                )],
+                                ..default()
+                            },
+                            ..default()
+                        });
+                    });
+            }
+        });
+
+    // Map canvas
+    child_builder
+        .spawn((
+            AutomapCanvas,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Relative,
+                    flex_grow: 1.0,
+                    width: Val::Percent(100.0),
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                background_color: Color::linear_rgba(0.05, 0.05, 0.05, 1.0).into(),
+                ..default()
+            },
+            Name::new("Automap Canvas"),
+        ))
+        .with_children(|parent| {
+            // Zero-sized node at the center of the canvas that cells are positioned relative to
+            parent.spawn((
+                AutomapOrigin,
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Percent(50.0),
+                        top: Val::Percent(50.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                Name::new("Automap Origin"),
+            ));
+        });
+}
+
+fn pan_automap(
+    keys: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    mut automap_view: ResMut<AutomapView>,
+) {
+    let mut direction = Vec2::ZERO;
+
+    for (key, d) in [
+        (KeyCode::ArrowUp, Vec2::NEG_Y),
+        (KeyCode::ArrowDown, Vec2::Y),
+        (KeyCode::ArrowLeft, Vec2::NEG_X),
+        (KeyCode::ArrowRight, Vec2::X),
+    ] {
+        if keys.pressed(key) {
+            direction += d;
+        }
+    }
+
+    if direction != Vec2::ZERO {
+        automap_view.pan += direction.normalize() * AUTOMAP_PAN_SPEED * time.delta_seconds();
+    }
+}
+
+fn change_automap_y_level(
+    button_query: Query<(&AutomapYLevelButton, &Interaction), Changed<Interaction>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut automap_view: ResMut<AutomapView>,
+) {
+    for (button, interaction) in button_query.iter() {
+        if *interaction == Interaction::Pressed {
+            automap_view.y += button.0;
+        }
+    }
+
+    if keys.just_pressed(KeyCode::PageUp) {
+        automap_view.y += 1;
+    }
+    if keys.just_pressed(KeyCode::PageDown) {
+        automap_view.y -= 1;
+    }
+}
+
+fn center_automap_on_player(
+    added_canvas_query: Query<(), Added<AutomapCanvas>>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    mut automap_view: ResMut<AutomapView>,
+    world_dims: Res<WorldDims>,
+) {
+    if added_canvas_query.is_empty() {
+        return;
+    }
+
+    if let Ok(gt) = player_query.get_single() {
+        let tl = gt.translation();
+        automap_view.pan = Vec2::new(tl.x, tl.z);
+        automap_view.y = ChunkCellMarker::from_global_transform(gt, &world_dims).chunk_y;
+    }
+}
+
+fn redraw_automap(
+    mut commands: Commands,
+    origin_query: Query<Entity, With<AutomapOrigin>>,
+    added_origin_query: Query<(), Added<AutomapOrigin>>,
+    mut y_level_text_query: Query<&mut Text, With<AutomapYLevelText>>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    visited_cells: Res<VisitedCells>,
+    revealed_cells: Res<RevealedCells>,
+    automap_view: Res<AutomapView>,
+    world_seed: Res<WorldSeed>,
+    world_config: Res<WorldConfig>,
+    world_dims: Res<WorldDims>,
+    world_structures: Res<WorldStructureRegistry>,
+    world_data: Res<WorldData>,
+) {
+    if added_origin_query.is_empty()
+        && !visited_cells.is_changed()
+        && !revealed_cells.is_changed()
+        && !automap_view.is_changed()
+        && !world_data.is_changed()
+    {
+        return;
+    }
+
+    let Ok(origin_entity) = origin_query.get_single() else {
+        return;
+    };
+
+    for mut text in y_level_text_query.iter_mut() {
+        for section in text.sections.iter_mut() {
+            section.value = format!("Y: {}", automap_view.y);
+        }
+    }
+
+    let scale = AUTOMAP_CELL_PX / world_dims.cell_size();
+    let view_radius = AUTOMAP_VIEW_RADIUS_CHUNKS * world_dims.chunk_size();
+    // Chunks are regenerated on demand instead of being stored alongside the visited cells
+    let mut chunks: HashMap<(i64, i64, i64), Chunk> = HashMap::new();
+
+    let mut entity_commands = commands.entity(origin_entity);
+    entity_commands.despawn_descendants();
+    entity_commands.with_children(|parent| {
+        // Cells that were never revealed are left blank, and revealed ones that were not
+        // visited are dimmed. Visited cells are also revealed, unless they were visited
+        // before cells were revealed at all.
+        let visited = visited_cells
+            .iter_at_y(automap_view.y)
+            .map(|ccm| (ccm, true));
+        let revealed = revealed_cells
+            .iter_at_y(automap_view.y)
+            .filter(|ccm| !visited_cells.contains(ccm))
+            .map(|ccm| (ccm, false));
+
+        for (ccm, is_visited) in visited.chain(revealed) {
+            let position = cell_world_position(&world_dims, ccm.chunk_xyz(), ccm.cell_xz());
+            let center = Vec2::new(position.x, position.z);
+            if center.distance(automap_view.pan) > view_radius {
+                continue;
+            }
+
+            let chunk = chunks.entry(ccm.chunk_xyz()).or_insert_with(|| {
+                automap_chunk(
+                    ccm.chunk_xyz(),
+                    &world_seed,
+                    &world_config,
+                    &world_dims,
+                    &world_structures,
+                    &world_data,
+                )
+            });
+            let Some(cell) = chunk.cells.get(ccm.z).and_then(|row| row.get(ccm.x)) else {
+                continue;
+            };
+
+            let wall_px = |wall: &CellWall| {
+                Val::Px(if *wall == CellWall::None {
+                    0.0
+                } else {
+                    AUTOMAP_WALL_PX
+                })
+            };
+
+            let offset = (center - automap_view.pan) * scale;
+
+            // Top walls are on the +x side of a cell, and Left walls are on the +z side
+            parent.spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(offset.x - AUTOMAP_CELL_PX / 2.0),
+                    top: Val::Px(offset.y - AUTOMAP_CELL_PX / 2.0),
+                    height: Val::Px(AUTOMAP_CELL_PX),
+                    width: Val::Px(AUTOMAP_CELL_PX),
+                    border: UiRect {
+                        left: wall_px(&cell.wall_bottom),
+                        right: wall_px(&cell.wall_top),
+                        top: wall_px(&cell.wall_right),
+                        bottom: wall_px(&cell.wall_left),
+                    },
+                    ..default()
+                },
+                border_color: if is_visited {
+                    AUTOMAP_VISITED_WALL_COLOR
+                } else {
+                    AUTOMAP_REVEALED_WALL_COLOR
+                }
+                .into(),
+                background_color: if is_visited {
+                    AUTOMAP_VISITED_CELL_COLOR
+                } else {
+                    AUTOMAP_REVEALED_CELL_COLOR
+                }
+                .into(),
+                ..default()
+            });
+        }
+
+        if let Ok(gt) = player_query.get_single() {
+            let tl = gt.translation();
+            let ccm = ChunkCellMarker::from_global_transform(gt, &world_dims);
+            if ccm.chunk_y == automap_view.y {
+                let offset = (Vec2::new(tl.x, tl.z) - automap_view.pan) * scale;
+                parent.spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(offset.x - AUTOMAP_PLAYER_MARKER_PX / 2.0),
+                        top: Val::Px(offset.y - AUTOMAP_PLAYER_MARKER_PX / 2.0),
+                        height: Val::Px(AUTOMAP_PLAYER_MARKER_PX),
+                        width: Val::Px(AUTOMAP_PLAYER_MARKER_PX),
+                        ..default()
+                    },
+                    background_color: Color::linear_rgba(0.8, 0.1, 0.1, 1.0).into(),
+                    z_index: ZIndex::Local(1),
+                    ..default()
+                });
+            }
+        }
+    });
+}
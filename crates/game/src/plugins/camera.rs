@@ -5,9 +5,14 @@ use bevy_rapier3d::{
 };
 use bevy_third_person_camera::*;
 use dungeon_maze_common::{
-    camera::{AltCamera, MainCamera},
-    player::Player,
+    app_state::{AppState, GameLoaded},
+    camera::{AltCamera, CameraShake, MainCamera, CAMERA_SHAKE_MAX_MAGNITUDE},
+    player::{Health, Player, TakeDamage},
+    settings::GameSettings,
+    should_not_happen_once,
+    utils::debug::WarnOnce,
 };
+use rand::Rng;
 
 const CAMERA_ZOOM_MIN: f32 = 0.1;
 const CAMERA_ZOOM_MAX: f32 = 3.0;
@@ -21,31 +26,58 @@ pub struct CameraPlugin;
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(ThirdPersonCameraPlugin)
-            .add_systems(Startup, (spawn_main_camera, spawn_alt_camera))
-            .add_systems(Update, switch_cameras)
+            .add_systems(OnEnter(GameLoaded), (spawn_main_camera, spawn_alt_camera))
+            .add_event::<TakeDamage>()
+            .add_systems(
+                Update,
+                (switch_cameras, shake_camera_on_damage).run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(Update, apply_camera_settings)
+            .add_systems(
+                PostUpdate,
+                (
+                    undo_camera_shake.before(CameraSyncSet),
+                    apply_camera_shake
+                        .after(CameraSyncSet)
+                        .before(TransformSystem::TransformPropagate),
+                ),
+            )
             .configure_sets(PostUpdate, CameraSyncSet.after(PhysicsSet::StepSimulation));
     }
 }
 
-fn spawn_main_camera(mut commands: Commands) {
+fn projection(game_settings: &GameSettings) -> Projection {
+    Projection::Perspective(PerspectiveProjection {
+        fov: game_settings.fov_degrees.get().to_radians(),
+        ..default()
+    })
+}
+
+fn sensitivity(game_settings: &GameSettings) -> Vec2 {
+    Vec2::splat(CAMERA_SENSITIVITY * game_settings.mouse_sensitivity.get())
+}
+
+fn spawn_main_camera(mut commands: Commands, game_settings: Res<State<GameSettings>>) {
     let main_camera_bundle = (
         MainCamera,
-        Camera3dBundle::default(),
+        CameraShake::default(),
+        Camera3dBundle {
+            projection: projection(game_settings.get()),
+            ..default()
+        },
         ThirdPersonCamera {
             zoom: Zoom::new(CAMERA_ZOOM_MIN, CAMERA_ZOOM_MAX),
-            sensitivity: Vec2 {
-                x: CAMERA_SENSITIVITY,
-                y: CAMERA_SENSITIVITY,
-            },
+            sensitivity: sensitivity(game_settings.get()),
             ..default()
         },
+        StateScoped(GameLoaded),
         Name::new("Main Camera"),
     );
 
     commands.spawn(main_camera_bundle);
 }
 
-fn spawn_alt_camera(mut commands: Commands) {
+fn spawn_alt_camera(mut commands: Commands, game_settings: Res<State<GameSettings>>) {
     let alt_camera_bundle = (
         AltCamera,
         Camera3dBundle {
@@ -53,8 +85,10 @@ fn spawn_alt_camera(mut commands: Commands) {
                 is_active: false,
                 ..default()
             },
+            projection: projection(game_settings.get()),
             ..default()
         },
+        StateScoped(GameLoaded),
         Name::new("Alt Camera"),
     );
 
@@ -69,10 +103,21 @@ fn switch_cameras(
     >,
     player_query: Query<(Entity, &GlobalTransform), With<Player>>,
     rapier_context: Res<RapierContext>,
+    mut warn_once: Local<WarnOnce>,
 ) {
-    let (mut main_camera, main_camera_gl_transform) = main_camera_query.get_single_mut().unwrap();
-    let (mut alt_camera, mut alt_camera_transform) = alt_camera_query.get_single_mut().unwrap();
-    let (player_entity, player_gl_transform) = player_query.get_single().unwrap();
+    let (
+        Ok((mut main_camera, main_camera_gl_transform)),
+        Ok((mut alt_camera, mut alt_camera_transform)),
+        Ok((player_entity, player_gl_transform)),
+    ) = (
+        main_camera_query.get_single_mut(),
+        alt_camera_query.get_single_mut(),
+        player_query.get_single(),
+    )
+    else {
+        should_not_happen_once!(warn_once, "retrieving cameras and player");
+        return;
+    };
 
     let main_camera_translation = main_camera_gl_transform.translation();
     let player_translation = player_gl_transform.translation();
@@ -105,12 +150,96 @@ fn switch_cameras(
     deactivate_camera(&mut alt_camera);
 }
 
-fn activate_camera(camera: &mut Camera) {
+// The alt camera gets the same field of view, so switching between the two is seamless
+pub fn apply_camera_settings(
+    mut event_reader: EventReader<StateTransitionEvent<GameSettings>>,
+    mut projection_query: Query<&mut Projection, Or<(With<MainCamera>, With<AltCamera>)>>,
+    mut third_person_camera_query: Query<&mut ThirdPersonCamera, With<MainCamera>>,
+    game_settings: Res<State<GameSettings>>,
+) {
+    if event_reader.read().count() == 0 {
+        return;
+    }
+
+    for mut projection in projection_query.iter_mut() {
+        if let Projection::Perspective(perspective) = projection.as_mut() {
+            perspective.fov = game_settings.get().fov_degrees.get().to_radians();
+        }
+    }
+    for mut third_person_camera in third_person_camera_query.iter_mut() {
+        third_person_camera.sensitivity = sensitivity(game_settings.get());
+    }
+}
+
+pub fn activate_camera(camera: &mut Camera) {
     camera.is_active = true;
     camera.order = 1;
 }
 
-fn deactivate_camera(camera: &mut Camera) {
+pub fn deactivate_camera(camera: &mut Camera) {
     camera.is_active = false;
     camera.order = 0;
 }
+
+pub fn shake_camera_on_damage(
+    mut event_reader: EventReader<TakeDamage>,
+    mut camera_query: Query<&mut CameraShake, With<MainCamera>>,
+    player_query: Query<&Health, With<Player>>,
+    game_settings: Res<State<GameSettings>>,
+) {
+    if game_settings.get().disable_damage_effects {
+        event_reader.clear();
+        for mut camera_shake in camera_query.iter_mut() {
+            camera_shake.clear();
+        }
+        return;
+    }
+
+    for event in event_reader.read() {
+        let Ok(health) = player_query.get(event.target) else {
+            continue;
+        };
+
+        let intensity = event.feedback_intensity(health.max_value);
+        if intensity <= 0.0 {
+            continue;
+        }
+
+        for mut camera_shake in camera_query.iter_mut() {
+            camera_shake.add(intensity * CAMERA_SHAKE_MAX_MAGNITUDE);
+        }
+    }
+}
+
+// The third person camera usually overwrites the translation every frame anyways,
+// but not when it has no target to follow
+pub fn undo_camera_shake(mut camera_query: Query<(&mut Transform, &mut CameraShake)>) {
+    for (mut transform, mut camera_shake) in camera_query.iter_mut() {
+        transform.translation -= camera_shake.applied_offset;
+        camera_shake.applied_offset = Vec3::ZERO;
+    }
+}
+
+pub fn apply_camera_shake(
+    mut camera_query: Query<(&mut Transform, &mut CameraShake)>,
+    time: Res<Time>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (mut transform, mut camera_shake) in camera_query.iter_mut() {
+        camera_shake.tick(time.delta_seconds());
+
+        let magnitude = camera_shake.current_magnitude();
+        if magnitude <= 0.0 {
+            continue;
+        }
+
+        // Only shakes across the view, so the camera never moves into or away from the player
+        let offset = (transform.right() * rng.gen_range(-1.0..=1.0)
+            + transform.up() * rng.gen_range(-1.0..=1.0))
+            * magnitude;
+
+        transform.translation += offset;
+        camera_shake.applied_offset = offset;
+    }
+}
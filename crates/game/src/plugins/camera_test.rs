@@ -0,0 +1,223 @@
+use crate::plugins::camera::{
+    apply_camera_settings, apply_camera_shake, shake_camera_on_damage, undo_camera_shake,
+};
+use bevy::{prelude::*, state::app::StatesPlugin, time::TimeUpdateStrategy};
+use bevy_third_person_camera::ThirdPersonCamera;
+use dungeon_maze_common::{
+    camera::{camera_shake_decay, AltCamera, CameraShake, MainCamera, CAMERA_SHAKE_SECS},
+    player::{DmgType, Health, Player, TakeDamage},
+    settings::{FovDegrees, GameSettings, MouseSensitivity},
+};
+use std::time::Duration;
+
+fn new_test_app() -> App {
+    let mut app = App::new();
+
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            50,
+        )))
+        .add_event::<TakeDamage>()
+        .init_state::<GameSettings>()
+        .add_systems(Update, shake_camera_on_damage)
+        .add_systems(PostUpdate, (undo_camera_shake, apply_camera_shake).chain());
+
+    app
+}
+
+fn spawn_camera_and_player(app: &mut App) -> (Entity, Entity) {
+    let camera = app
+        .world_mut()
+        .spawn((MainCamera, CameraShake::default(), Transform::default()))
+        .id();
+    let player = app
+        .world_mut()
+        .spawn((Player, Health::new(100.0, 100.0, 0.0)))
+        .id();
+    (camera, player)
+}
+
+fn hit(app: &mut App, target: Entity, amounts: Vec<(DmgType, f32)>) {
+    app.world_mut().send_event(TakeDamage {
+        amounts,
+        target,
+        source: None,
+    });
+}
+
+fn camera_shake(app: &App, camera: Entity) -> &CameraShake {
+    app.world().get::<CameraShake>(camera).unwrap()
+}
+
+#[test]
+fn test_camera_shake_decay() {
+    assert_eq!(camera_shake_decay(CAMERA_SHAKE_SECS), 1.0);
+    assert_eq!(camera_shake_decay(CAMERA_SHAKE_SECS * 2.0), 1.0);
+    assert_eq!(camera_shake_decay(0.0), 0.0);
+    assert_eq!(camera_shake_decay(-1.0), 0.0);
+
+    // Eases out, so it has already settled down most of the way by halfway through
+    let halfway = camera_shake_decay(CAMERA_SHAKE_SECS / 2.0);
+    assert!(halfway > 0.0 && halfway < 0.5);
+}
+
+#[test]
+fn test_camera_shake_ticks_down_to_nothing() {
+    let mut camera_shake = CameraShake::default();
+    assert_eq!(camera_shake.current_magnitude(), 0.0);
+
+    camera_shake.add(0.1);
+    let magnitude = camera_shake.current_magnitude();
+    assert_eq!(magnitude, 0.1);
+
+    camera_shake.tick(CAMERA_SHAKE_SECS / 4.0);
+    assert!(camera_shake.current_magnitude() < magnitude);
+
+    camera_shake.tick(CAMERA_SHAKE_SECS);
+    assert_eq!(camera_shake.remaining_secs, 0.0);
+    assert_eq!(camera_shake.current_magnitude(), 0.0);
+}
+
+#[test]
+fn test_camera_shake_is_capped() {
+    let mut camera_shake = CameraShake::default();
+    for _ in 0..10 {
+        camera_shake.add(1.0);
+    }
+    assert!(camera_shake.current_magnitude() <= 0.15);
+}
+
+#[test]
+fn test_camera_shake_does_not_move_camera() {
+    let mut app = new_test_app();
+    let (camera, player) = spawn_camera_and_player(&mut app);
+    app.update();
+
+    hit(&mut app, player, vec![(DmgType::Slash, 25.0)]);
+    app.update();
+
+    assert!(camera_shake(&app, camera).current_magnitude() > 0.0);
+    let transform = app.world().get::<Transform>(camera).unwrap();
+    assert_ne!(transform.translation, Vec3::ZERO);
+
+    // Nothing else moves the camera here, so it ends up right back where it started
+    for _ in 0..20 {
+        app.update();
+    }
+
+    assert_eq!(camera_shake(&app, camera).current_magnitude(), 0.0);
+    let transform = app.world().get::<Transform>(camera).unwrap();
+    assert_eq!(transform.translation, Vec3::ZERO);
+}
+
+#[test]
+fn test_camera_shake_ignores_stamina_damage_and_others() {
+    let mut app = new_test_app();
+    let (camera, player) = spawn_camera_and_player(&mut app);
+    app.update();
+
+    hit(&mut app, player, vec![(DmgType::Stamina, 50.0)]);
+    let other = app.world_mut().spawn(Health::new(100.0, 100.0, 0.0)).id();
+    hit(&mut app, other, vec![(DmgType::Slash, 50.0)]);
+    app.update();
+
+    assert_eq!(camera_shake(&app, camera).current_magnitude(), 0.0);
+}
+
+#[test]
+fn test_camera_shake_disabled_in_settings() {
+    let mut app = new_test_app();
+    let (camera, player) = spawn_camera_and_player(&mut app);
+
+    let mut game_settings = *app.world().resource::<State<GameSettings>>().get();
+    game_settings.disable_damage_effects = true;
+    app.world_mut()
+        .resource_mut::<NextState<GameSettings>>()
+        .set(game_settings);
+    app.update();
+
+    hit(&mut app, player, vec![(DmgType::Slash, 50.0)]);
+    app.update();
+
+    assert_eq!(camera_shake(&app, camera).current_magnitude(), 0.0);
+}
+
+fn set_camera_settings(app: &mut App, fov_degrees: f32, mouse_sensitivity: f32) {
+    let mut game_settings = *app.world().resource::<State<GameSettings>>().get();
+    game_settings.fov_degrees = FovDegrees::new(fov_degrees);
+    game_settings.mouse_sensitivity = MouseSensitivity::new(mouse_sensitivity);
+    app.world_mut()
+        .resource_mut::<NextState<GameSettings>>()
+        .set(game_settings);
+    app.update();
+}
+
+fn fov(app: &App, camera: Entity) -> f32 {
+    match app.world().get::<Projection>(camera).unwrap() {
+        Projection::Perspective(perspective) => perspective.fov,
+        Projection::Orthographic(_) => panic!("expected a perspective projection"),
+    }
+}
+
+#[test]
+fn test_camera_settings_applied() {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .init_state::<GameSettings>()
+        .add_systems(Update, apply_camera_settings);
+
+    let main_camera = app
+        .world_mut()
+        .spawn((
+            MainCamera,
+            Projection::default(),
+            ThirdPersonCamera::default(),
+        ))
+        .id();
+    let alt_camera = app
+        .world_mut()
+        .spawn((AltCamera, Projection::default()))
+        .id();
+
+    set_camera_settings(&mut app, 60.0, 1.0);
+    assert!((fov(&app, main_camera) - 60f32.to_radians()).abs() < 0.0001);
+    assert_eq!(fov(&app, alt_camera), fov(&app, main_camera));
+    let sensitivity = app
+        .world()
+        .get::<ThirdPersonCamera>(main_camera)
+        .unwrap()
+        .sensitivity;
+
+    set_camera_settings(&mut app, 60.0, 2.0);
+    let doubled = app
+        .world()
+        .get::<ThirdPersonCamera>(main_camera)
+        .unwrap()
+        .sensitivity;
+    assert_eq!(doubled, sensitivity * 2.0);
+}
+
+#[test]
+fn test_camera_settings_clamped() {
+    // Saves from before these settings existed get the defaults, which match the old behavior
+    let game_settings: GameSettings =
+        serde_json::from_str(r#"{"chunk_render_dist": [1, 1, 1]}"#).unwrap();
+    assert_eq!(game_settings.fov_degrees.get(), 45.0);
+    assert_eq!(game_settings.mouse_sensitivity.get(), 1.0);
+
+    let game_settings: GameSettings = serde_json::from_str(
+        r#"{"chunk_render_dist": [1, 1, 1], "fov_degrees": 500.0, "mouse_sensitivity": -3.0}"#,
+    )
+    .unwrap();
+    assert_eq!(game_settings.fov_degrees.get(), FovDegrees::MAX);
+    assert_eq!(game_settings.mouse_sensitivity.get(), MouseSensitivity::MIN);
+
+    // Stepping past either end stays at that end
+    assert_eq!(game_settings.fov_degrees.step(1).get(), FovDegrees::MAX);
+    assert_eq!(
+        game_settings.mouse_sensitivity.step(-1).get(),
+        MouseSensitivity::MIN
+    );
+    assert_eq!(FovDegrees::default().step(-3).get(), 30.0);
+    assert!((MouseSensitivity::default().step(5).get() - 1.5).abs() < 0.0001);
+}
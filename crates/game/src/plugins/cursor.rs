@@ -6,10 +6,20 @@ pub struct CursorPlugin;
 impl Plugin for CursorPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<CursorPosition>()
+            .init_resource::<UiScale>()
             .add_systems(Update, (update_cursor_position, cursor_follower_movement));
     }
 }
 
+// The cursor is in logical window pixels, which bevy already converts the physical ones to,
+// while ui nodes are sized in pixels that are then multiplied by the ui scale
+pub fn cursor_to_ui_position(cursor_position: Vec2, ui_scale: f32) -> Vec2 {
+    if ui_scale <= 0.0 {
+        return cursor_position;
+    }
+    cursor_position / ui_scale
+}
+
 fn update_cursor_position(
     mut event_reader: EventReader<CursorMoved>,
     mut cursor_position: ResMut<CursorPosition>,
@@ -22,9 +32,11 @@ fn update_cursor_position(
 fn cursor_follower_movement(
     mut cursor_follower_query: Query<&mut Style, With<CursorFollower>>,
     cursor_position: Res<CursorPosition>,
+    ui_scale: Res<UiScale>,
 ) {
+    let position = cursor_to_ui_position(cursor_position.0, ui_scale.0);
     for mut style in cursor_follower_query.iter_mut() {
-        style.left = Val::Px(cursor_position.0.x);
-        style.top = Val::Px(cursor_position.0.y);
+        style.left = Val::Px(position.x);
+        style.top = Val::Px(position.y);
     }
 }
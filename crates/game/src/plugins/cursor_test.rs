@@ -0,0 +1,37 @@
+use crate::plugins::cursor::{cursor_to_ui_position, CursorPlugin};
+use bevy::prelude::*;
+use dungeon_maze_common::cursor::{CursorFollower, CursorPosition};
+
+#[test]
+fn test_cursor_to_ui_position() {
+    let cursor_position = Vec2::new(300.0, 150.0);
+
+    assert_eq!(cursor_to_ui_position(cursor_position, 1.0), cursor_position);
+    assert_eq!(
+        cursor_to_ui_position(cursor_position, 2.0),
+        Vec2::new(150.0, 75.0)
+    );
+    assert_eq!(
+        cursor_to_ui_position(cursor_position, 0.75),
+        Vec2::new(400.0, 200.0)
+    );
+}
+
+#[test]
+fn test_cursor_follower_respects_ui_scale() {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, CursorPlugin))
+        .add_event::<CursorMoved>()
+        .insert_resource(UiScale(2.0));
+    let follower = app
+        .world_mut()
+        .spawn((CursorFollower, NodeBundle::default()))
+        .id();
+
+    app.insert_resource(CursorPosition(Vec2::new(300.0, 150.0)));
+    app.update();
+
+    let style = app.world().get::<Style>(follower).unwrap();
+    assert_eq!(style.left, Val::Px(150.0));
+    assert_eq!(style.top, Val::Px(75.0));
+}
@@ -0,0 +1,172 @@
+use crate::plugins::world::bundle::chunk::chunk_world_position;
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use dungeon_maze_common::{
+    app_state::AppState,
+    death_marker::{
+        DeathMarker, DeathMarkerEcho, DeathMarkers, DeathMarkersChanged, DEATH_MARKER_CLEAR_RADIUS,
+    },
+    player::{EntityKilled, Player},
+    world::{ChunkCellMarker, ChunkIndex, ChunkSpawned, WorldDims},
+};
+
+const DEATH_MARKER_RADIUS: f32 = 0.15;
+const DEATH_MARKER_HEIGHT: f32 = 0.6;
+const DEATH_MARKER_COLOR: Color = Color::srgb(0.55, 0.75, 1.0);
+const DEATH_MARKER_LIGHT_INTENSITY: f32 = 20_000.0;
+const DEATH_MARKER_LIGHT_RANGE: f32 = 3.0;
+
+pub struct DeathMarkerPlugin;
+
+impl Plugin for DeathMarkerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DeathMarkers>()
+            .add_event::<DeathMarkersChanged>()
+            .add_systems(
+                Update,
+                restore_death_markers
+                    .run_if(in_state(AppState::InGame).or_else(in_state(AppState::Spectating))),
+            )
+            .add_systems(
+                Update,
+                (record_player_deaths, clear_nearby_death_markers)
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+// Marks where the player died, and spawns the marker right away if its chunk already is
+pub fn record_player_deaths(
+    mut commands: Commands,
+    mut event_reader: EventReader<EntityKilled>,
+    mut event_writer: EventWriter<DeathMarkersChanged>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    echo_query: Query<(Entity, &DeathMarkerEcho)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut death_markers: ResMut<DeathMarkers>,
+    chunk_index: Res<ChunkIndex>,
+    world_dims: Res<WorldDims>,
+) {
+    for event in event_reader.read() {
+        let Ok(gt) = player_query.get(event.target) else {
+            continue;
+        };
+
+        let ccm = ChunkCellMarker::from_global_transform(gt, &world_dims);
+        let (marker, removed) = death_markers.record(ccm.chunk_xyz(), gt.translation());
+
+        for (entity, echo) in echo_query.iter() {
+            if removed.contains(&echo.0) {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+
+        if let Some(chunk_entity) = chunk_index.entity(marker.chunk_xyz) {
+            commands.entity(chunk_entity).with_children(|parent| {
+                spawn_death_marker_bundle(
+                    parent,
+                    &marker,
+                    &mut meshes,
+                    &mut materials,
+                    &world_dims,
+                );
+            });
+        }
+
+        event_writer.send(DeathMarkersChanged);
+    }
+}
+
+pub fn restore_death_markers(
+    mut commands: Commands,
+    mut event_reader: EventReader<ChunkSpawned>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    death_markers: Res<DeathMarkers>,
+    world_dims: Res<WorldDims>,
+) {
+    for event in event_reader.read() {
+        let Some(mut entity_commands) = commands.get_entity(event.entity) else {
+            continue;
+        };
+
+        entity_commands.with_children(|parent| {
+            for marker in death_markers.in_chunk(event.xyz) {
+                spawn_death_marker_bundle(parent, marker, &mut meshes, &mut materials, &world_dims);
+            }
+        });
+    }
+}
+
+pub fn clear_nearby_death_markers(
+    mut commands: Commands,
+    mut event_writer: EventWriter<DeathMarkersChanged>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    echo_query: Query<(Entity, &DeathMarkerEcho, &GlobalTransform)>,
+    mut death_markers: ResMut<DeathMarkers>,
+) {
+    let Ok(player_gt) = player_query.get_single() else {
+        return;
+    };
+
+    let mut changed = false;
+    for (entity, echo, gt) in echo_query.iter() {
+        // Markers float above the floor, so only the distance along the floor counts
+        let offset = gt.translation() - player_gt.translation();
+        if offset.xz().length() > DEATH_MARKER_CLEAR_RADIUS {
+            continue;
+        }
+
+        commands.entity(entity).despawn_recursive();
+        changed |= death_markers.remove(echo.0);
+    }
+
+    if changed {
+        event_writer.send(DeathMarkersChanged);
+    }
+}
+
+// Spawned as a child of the chunk the marker is in, without a collider
+pub fn spawn_death_marker_bundle<'a>(
+    child_builder: &'a mut ChildBuilder,
+    marker: &DeathMarker,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    world_dims: &WorldDims,
+) -> EntityCommands<'a> {
+    let translation = Vec3::from(marker.translation)
+        - chunk_world_position(world_dims, marker.chunk_xyz)
+        + Vec3::Y * DEATH_MARKER_HEIGHT;
+
+    let mut entity_commands = child_builder.spawn((
+        DeathMarkerEcho(marker.id),
+        PbrBundle {
+            mesh: meshes.add(Sphere::new(DEATH_MARKER_RADIUS)),
+            material: materials.add(StandardMaterial {
+                base_color: DEATH_MARKER_COLOR,
+                emissive: LinearRgba::from(DEATH_MARKER_COLOR) * 4.0,
+                ..default()
+            }),
+            transform: Transform::from_translation(translation),
+            ..default()
+        },
+        Name::new("Death Marker"),
+    ));
+
+    entity_commands.with_children(|parent| {
+        parent.spawn((
+            PointLightBundle {
+                point_light: PointLight {
+                    color: DEATH_MARKER_COLOR,
+                    intensity: DEATH_MARKER_LIGHT_INTENSITY,
+                    range: DEATH_MARKER_LIGHT_RANGE,
+                    ..default()
+                },
+                ..default()
+            },
+            Name::new("Death Marker Light"),
+        ));
+    });
+
+    entity_commands
+}
@@ -0,0 +1,118 @@
+use crate::plugins::death_marker::DeathMarkerPlugin;
+use bevy::{prelude::*, state::app::StatesPlugin};
+use dungeon_maze_common::{
+    app_state::AppState,
+    death_marker::{DeathMarkerEcho, DeathMarkers, DEATH_MARKER_CLEAR_RADIUS},
+    player::{EntityKilled, Player},
+    world::{world_structure::NO_WORLD_STRUCTURE, Chunk, ChunkIndex, ChunkSpawned, WorldDims},
+};
+
+fn new_test_app() -> App {
+    let mut app = App::new();
+
+    app.add_plugins((
+        MinimalPlugins,
+        StatesPlugin,
+        AssetPlugin {
+            watch_for_changes_override: Some(false),
+            ..default()
+        },
+        DeathMarkerPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<StandardMaterial>()
+    .init_resource::<WorldDims>()
+    .init_resource::<ChunkIndex>()
+    .add_event::<EntityKilled>()
+    .add_event::<ChunkSpawned>()
+    .insert_state(AppState::InGame);
+
+    app
+}
+
+fn echo_count(app: &mut App) -> usize {
+    let world = app.world_mut();
+    world.query::<&DeathMarkerEcho>().iter(world).count()
+}
+
+fn kill(app: &mut App, player: Entity) {
+    app.world_mut().send_event(EntityKilled {
+        target: player,
+        source: None,
+    });
+    app.update();
+}
+
+#[test]
+fn test_player_death_is_marked_and_restored_with_chunk() {
+    let mut app = new_test_app();
+    let player = app
+        .world_mut()
+        .spawn((
+            Player,
+            GlobalTransform::from_translation(Vec3::new(-10.0, 0.0, 3.0)),
+        ))
+        .id();
+
+    kill(&mut app, player);
+    let markers = app.world().resource::<DeathMarkers>();
+    assert_eq!(markers.len(), 1);
+    let marker = markers.iter().next().unwrap().clone();
+    assert_eq!(marker.chunk_xyz, (-1, 0, 0));
+    // The chunk was not spawned, so there is nothing to see yet
+    assert_eq!(echo_count(&mut app), 0);
+
+    // Other entities being killed are not marked
+    let enemy = app.world_mut().spawn_empty().id();
+    kill(&mut app, enemy);
+    assert_eq!(app.world().resource::<DeathMarkers>().len(), 1);
+
+    let chunk_entity = app.world_mut().spawn(SpatialBundle::default()).id();
+    app.world_mut().send_event(ChunkSpawned {
+        entity: chunk_entity,
+        xyz: (-1, 0, 0),
+        chunk: Chunk {
+            x: -1,
+            y: 0,
+            z: 0,
+            cells: Vec::new(),
+            world_structure: NO_WORLD_STRUCTURE.to_string(),
+        },
+    });
+    app.update();
+    assert_eq!(echo_count(&mut app), 1);
+}
+
+#[test]
+fn test_death_markers_cleared_near_player() {
+    let mut app = new_test_app();
+    let player = app
+        .world_mut()
+        .spawn((Player, GlobalTransform::default()))
+        .id();
+    kill(&mut app, player);
+
+    let echo_at = |app: &mut App, translation: Vec3| {
+        app.world_mut()
+            .spawn((
+                DeathMarkerEcho(0),
+                GlobalTransform::from_translation(translation),
+            ))
+            .id()
+    };
+
+    // Too far away along the floor
+    let far = echo_at(
+        &mut app,
+        Vec3::new(DEATH_MARKER_CLEAR_RADIUS + 1.0, 0.0, 0.0),
+    );
+    app.update();
+    assert!(app.world().get_entity(far).is_some());
+    assert_eq!(app.world().resource::<DeathMarkers>().len(), 1);
+
+    // Height above the floor does not count
+    let near = echo_at(&mut app, Vec3::new(0.5, 2.0, 0.5));
+    app.update();
+    assert!(app.world().get_entity(near).is_none());
+    assert!(app.world().resource::<DeathMarkers>().is_empty());
+}
@@ -1,15 +1,45 @@
-use crate::plugins::world::{CELL_SIZE, CHUNK_SIZE};
-use bevy::prelude::*;
-use bevy_inspector_egui::quick::WorldInspectorPlugin;
+use crate::plugins::{input_replay::InputReplayPlugin, world::map_render::chunks_to_html};
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    ecs::entity::Entities,
+    prelude::*,
+    window::PrimaryWindow,
+};
+use bevy_inspector_egui::{
+    bevy_egui::{egui, EguiContext, EguiPlugin},
+    quick::WorldInspectorPlugin,
+    reflect_inspector::ui_for_value,
+    DefaultInspectorConfigPlugin,
+};
 use bevy_rapier3d::prelude::*;
 use dungeon_maze_common::{
+    app_state::{AppState, GameLoaded},
     camera::MainCamera,
     debug::*,
-    player::{DmgResist, DmgTarget, DmgType, Health, Killable, Player, PlayerState},
-    utils::contains_any,
-    world::ChunkCellMarker,
+    player::{DmgResist, DmgTarget, DmgType, Health, Killable, Player, PlayerState, Stamina},
+    should_not_happen_once,
+    utils::{
+        contains_any,
+        debug::{should_not_happen_count, WarnOnce},
+    },
+    world::{
+        data::WorldData, Cell, CellMutated, CellMutation, CellWallSide, Chunk, ChunkCellMarker,
+        ChunkMarker, ChunkWorldStructure, WorldDims,
+    },
 };
-use std::{env, f32::consts::PI};
+use std::{
+    env,
+    f32::consts::PI,
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// How far past the player walls can be broken from
+const BREAK_WALL_REACH: f32 = 3.0;
+// Counting every entity and collider is not free, so the stats are only refreshed this often
+const STATS_REFRESH_SECS: f32 = 0.25;
+const DUMP_CHUNKS_KEY: KeyCode = KeyCode::F9;
 
 pub struct DebugPlugin;
 
@@ -20,10 +50,34 @@ impl Plugin for DebugPlugin {
 
         let specified = |s: &str| contains_any(&args, &[s, "a"]);
 
+        app.add_systems(
+            Update,
+            dump_loaded_chunks.run_if(in_state(AppState::InGame)),
+        );
+
+        if let Some(path) = arg_path(&args, "record") {
+            app.add_plugins(InputReplayPlugin::Record(path));
+        } else if let Some(path) = arg_path(&args, "replay") {
+            app.add_plugins(InputReplayPlugin::Replay(path));
+        }
+
         if specified("world") {
             app.add_plugins(WorldInspectorPlugin::new());
         }
 
+        if specified("stats") {
+            if !app.is_plugin_added::<DefaultInspectorConfigPlugin>() {
+                app.add_plugins(DefaultInspectorConfigPlugin);
+            }
+            if !app.is_plugin_added::<EguiPlugin>() {
+                app.add_plugins(EguiPlugin);
+            }
+            if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+                app.add_plugins(FrameTimeDiagnosticsPlugin);
+            }
+            app.add_systems(Update, player_stats_ui.run_if(in_state(AppState::InGame)));
+        }
+
         if specified("rapier") {
             app.add_plugins(RapierDebugRenderPlugin {
                 enabled: true,
@@ -35,45 +89,113 @@ impl Plugin for DebugPlugin {
         if specified("fly") {
             app.add_systems(
                 Update,
-                player_flight_movement.run_if(in_state(PlayerState::Walking)),
+                player_flight_movement
+                    .run_if(in_state(PlayerState::Walking).and_then(in_state(AppState::InGame))),
             );
         }
 
+        if specified("dig") {
+            app.add_systems(Update, break_facing_wall.run_if(in_state(AppState::InGame)));
+        }
+
         if specified("enemy") {
-            app.add_systems(Startup, spawn_test_enemy);
+            app.add_systems(OnEnter(GameLoaded), spawn_test_enemy);
         }
 
         let position_arg = specified("position");
         let compass_arg = specified("compass");
+        let stats_arg = specified("stats");
 
-        if position_arg || compass_arg {
-            app.add_systems(Startup, spawn_ui_overlay);
+        if position_arg || compass_arg || stats_arg {
+            app.add_systems(OnEnter(GameLoaded), spawn_ui_overlay);
         }
 
         if position_arg {
-            app.add_systems(Startup, spawn_player_position_ui.after(spawn_ui_overlay))
-                .add_systems(Update, update_player_position_ui);
+            app.add_systems(
+                OnEnter(GameLoaded),
+                spawn_player_position_ui.after(spawn_ui_overlay),
+            )
+            .add_systems(
+                Update,
+                update_player_position_ui.run_if(in_state(AppState::InGame)),
+            );
         }
 
         if compass_arg {
-            app.add_systems(Startup, spawn_compass_ui.after(spawn_ui_overlay))
-                .add_systems(Update, update_compass_ui);
+            app.add_systems(
+                OnEnter(GameLoaded),
+                spawn_compass_ui.after(spawn_ui_overlay),
+            )
+            .add_systems(Update, update_compass_ui.run_if(in_state(AppState::InGame)));
+        }
+
+        if stats_arg {
+            app.add_systems(OnEnter(GameLoaded), spawn_stats_ui.after(spawn_ui_overlay))
+                .add_systems(Update, update_stats_ui.run_if(in_state(AppState::InGame)));
         }
     }
 }
 
+// Path following an argument such as `record input.json`
+fn arg_path(args: &[&str], arg: &str) -> Option<PathBuf> {
+    let i = args.iter().position(|a| *a == arg)?;
+    args.get(i + 1).map(PathBuf::from)
+}
+
+// Live view of the player's health, stamina and state, where health and stamina can be edited
+fn player_stats_ui(world: &mut World) {
+    let Ok(egui_context) = world
+        .query_filtered::<&mut EguiContext, With<PrimaryWindow>>()
+        .get_single(world)
+    else {
+        return;
+    };
+    let mut egui_context = egui_context.clone();
+
+    let player_state = world.resource::<State<PlayerState>>().get().clone();
+    let type_registry = world.resource::<AppTypeRegistry>().0.clone();
+    let type_registry = type_registry.read();
+
+    let Ok((mut health, mut stamina)) = world
+        .query_filtered::<(&mut Health, &mut Stamina), With<Player>>()
+        .get_single_mut(world)
+    else {
+        return;
+    };
+
+    egui::Window::new("Player Stats").show(egui_context.get_mut(), |ui| {
+        ui.label(format!("State: {:?}", player_state));
+
+        // Only marks the components as changed when they were actually edited
+        ui.collapsing("Health", |ui| {
+            if ui_for_value(health.bypass_change_detection(), ui, &type_registry) {
+                health.set_changed();
+            }
+        });
+        ui.collapsing("Stamina", |ui| {
+            if ui_for_value(stamina.bypass_change_detection(), ui, &type_registry) {
+                stamina.set_changed();
+            }
+        });
+    });
+}
+
 fn player_flight_movement(
     camera_query: Query<&Transform, With<MainCamera>>,
     mut player_query: Query<&mut Transform, With<Player>>,
     keys: Res<ButtonInput<KeyCode>>,
     time: Res<Time>,
+    mut warn_once: Local<WarnOnce>,
 ) {
-    for mut player_transform in player_query.iter_mut() {
-        let camera_transform = match camera_query.get_single() {
-            Ok(ct) => ct,
-            Err(err) => Err(format!("Error retrieving camera: {}", err)).unwrap(),
-        };
+    let camera_transform = match camera_query.get_single() {
+        Ok(ct) => ct,
+        Err(err) => {
+            should_not_happen_once!(warn_once, "retrieving camera: {}", err);
+            return;
+        }
+    };
 
+    for mut player_transform in player_query.iter_mut() {
         let mut direction = Vec3::ZERO;
 
         // Up
@@ -93,6 +215,106 @@ fn player_flight_movement(
     }
 }
 
+// Removes the wall, floor or ceiling that the camera is looking at, along with the one
+// facing it from the neighboring cell, since each cell has walls of its own
+fn break_facing_wall(
+    mut event_writer: EventWriter<CellMutated>,
+    camera_query: Query<&GlobalTransform, With<MainCamera>>,
+    player_query: Query<(Entity, &GlobalTransform), With<Player>>,
+    wall_query: Query<(&CellWallSide, &Parent)>,
+    ccm_query: Query<&ChunkCellMarker>,
+    rapier_context: Res<RapierContext>,
+    keys: Res<ButtonInput<KeyCode>>,
+    world_dims: Res<WorldDims>,
+) {
+    if !keys.just_pressed(KeyCode::KeyB) {
+        return;
+    }
+
+    let (Ok(camera_gt), Ok((player_entity, player_gt))) =
+        (camera_query.get_single(), player_query.get_single())
+    else {
+        return;
+    };
+
+    let origin = camera_gt.translation();
+    let reach = origin.distance(player_gt.translation()) + BREAK_WALL_REACH;
+    let Some((hit_entity, _)) = rapier_context.cast_ray(
+        origin,
+        *camera_gt.forward(),
+        reach,
+        true,
+        QueryFilter::new()
+            .exclude_sensors()
+            .exclude_collider(player_entity),
+    ) else {
+        return;
+    };
+
+    let Ok((wall_side, parent)) = wall_query.get(hit_entity) else {
+        return;
+    };
+    let Ok(ccm) = ccm_query.get(parent.get()) else {
+        return;
+    };
+
+    let side = wall_side.0;
+    for (ccm, side) in [
+        (ccm.clone(), side),
+        (ccm.neighbor(side, world_dims.grid_size()), side.opposite()),
+    ] {
+        event_writer.send(CellMutated {
+            ccm,
+            mutation: CellMutation::from_side(side),
+        });
+    }
+}
+
+// Writes every loaded chunk to an html map in the working directory,
+// built from the cells that were actually spawned rather than regenerated from the seed
+fn dump_loaded_chunks(
+    chunk_query: Query<(&ChunkMarker, &ChunkWorldStructure, &Children)>,
+    cell_query: Query<(&Cell, &ChunkCellMarker)>,
+    keys: Res<ButtonInput<KeyCode>>,
+    world_dims: Res<WorldDims>,
+) {
+    if !keys.just_pressed(DUMP_CHUNKS_KEY) {
+        return;
+    }
+
+    let grid_size = world_dims.grid_size();
+    let mut chunks: Vec<Chunk> = chunk_query
+        .iter()
+        .map(|(chunk_marker, chunk_world_structure, children)| {
+            let (x, y, z) = chunk_marker.0;
+            let mut cells = vec![vec![Cell::default(); grid_size]; grid_size];
+            for (cell, ccm) in cell_query.iter_many(children.iter()) {
+                cells[ccm.z][ccm.x] = cell.clone();
+            }
+            Chunk {
+                x,
+                y,
+                z,
+                cells,
+                world_structure: chunk_world_structure.0.clone(),
+            }
+        })
+        .collect();
+    // Highest y level first
+    chunks.sort_by_key(|chunk| -chunk.y);
+
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let path = format!("chunks_{}.html", secs);
+    let heading = format!("<h1>{} loaded chunks</h1>\n", chunks.len());
+
+    match fs::write(&path, chunks_to_html(&heading, &chunks)) {
+        Ok(_) => info!("Wrote {}", path),
+        Err(err) => warn!("Error writing {}: {}", path, err),
+    }
+}
+
 fn spawn_test_enemy(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
     let mesh: Mesh = Cuboid::new(1.0, 2.0, 1.0).into();
 
@@ -112,6 +334,7 @@ fn spawn_test_enemy(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
         dmg_resist,
         DmgTarget,
         Killable,
+        StateScoped(GameLoaded),
         Name::new("Static Cuboid"),
     ));
 }
@@ -132,6 +355,7 @@ fn spawn_ui_overlay(mut commands: Commands) {
             ..default()
         },
         UIOverlay,
+        StateScoped(GameLoaded),
         Name::new("UI Overlay"),
     ));
 }
@@ -189,9 +413,17 @@ fn spawn_player_position_ui(
 fn update_player_position_ui(
     player_query: Query<&GlobalTransform, With<Player>>,
     mut position_menu_text_query: Query<&mut Text, With<PositionMenuText>>,
+    world_dims: Res<WorldDims>,
+    mut warn_once: Local<WarnOnce>,
 ) {
-    let gt = player_query.get_single().unwrap();
-    let ccm = ChunkCellMarker::from_global_transform(gt, CHUNK_SIZE, CELL_SIZE);
+    let gt = match player_query.get_single() {
+        Ok(gt) => gt,
+        Err(err) => {
+            should_not_happen_once!(warn_once, "retrieving player: {}", err);
+            return;
+        }
+    };
+    let ccm = ChunkCellMarker::from_global_transform(gt, &world_dims);
 
     for mut text in position_menu_text_query.iter_mut() {
         for section in text.sections.iter_mut() {
@@ -203,6 +435,91 @@ fn update_player_position_ui(
     }
 }
 
+fn spawn_stats_ui(mut commands: Commands, ui_overlay_query: Query<Entity, With<UIOverlay>>) {
+    let entity = ui_overlay_query.get_single().unwrap();
+
+    commands.entity(entity).with_children(|parent| {
+        parent
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        padding: UiRect::new(
+                            Val::Px(8.0),
+                            Val::Px(8.0),
+                            Val::Px(8.0),
+                            Val::Px(8.0),
+                        ),
+                        ..default()
+                    },
+                    background_color: Color::WHITE.into(),
+                    ..default()
+                },
+                StatsMenu,
+                Name::new("Stats Menu"),
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    TextBundle {
+                        text: Text {
+                            sections: vec![TextSection::new(
+                                DebugStats::default().format(),
+                                TextStyle {
+                                    font_size: 16.0,
+                                    color: Color::BLACK,
+                                    ..default()
+                                },
+                            )],
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    StatsMenuText,
+                    Name::new("Stats Menu Text"),
+                ));
+            });
+    });
+}
+
+pub fn update_stats_ui(
+    mut secs_since_refresh: Local<Option<f32>>,
+    mut stats_menu_text_query: Query<&mut Text, With<StatsMenuText>>,
+    chunk_query: Query<(), With<ChunkMarker>>,
+    collider_query: Query<(), With<Collider>>,
+    entities: &Entities,
+    diagnostics: Option<Res<DiagnosticsStore>>,
+    world_data: Res<WorldData>,
+    chunk_spawn_timings: Res<ChunkSpawnTimings>,
+    time: Res<Time>,
+) {
+    // Refreshed right away the first time
+    let secs = secs_since_refresh.get_or_insert(STATS_REFRESH_SECS);
+    *secs += time.delta_seconds();
+    if *secs < STATS_REFRESH_SECS {
+        return;
+    }
+    *secs = 0.0;
+
+    let stats = DebugStats {
+        fps: diagnostics
+            .as_ref()
+            .and_then(|diagnostics| diagnostics.get(&FrameTimeDiagnosticsPlugin::FPS))
+            .and_then(|fps| fps.smoothed()),
+        chunks: chunk_query.iter().count(),
+        entities: entities.len() as usize,
+        colliders: collider_query.iter().count(),
+        world_data_chunks: world_data.chunks_count(),
+        should_not_happens: should_not_happen_count(),
+        chunk_spawn_avg: chunk_spawn_timings.avg(),
+        chunk_spawn_max: chunk_spawn_timings.max(),
+    };
+
+    for mut text in stats_menu_text_query.iter_mut() {
+        for section in text.sections.iter_mut() {
+            section.value = stats.format();
+        }
+    }
+}
+
 fn spawn_compass_ui(mut commands: Commands, ui_overlay_query: Query<Entity, With<UIOverlay>>) {
     let entity = ui_overlay_query.get_single().unwrap();
 
@@ -308,10 +625,16 @@ fn update_compass_ui(
     player_query: Query<&GlobalTransform, With<Player>>,
     mut compass_query: Query<&mut Transform, With<Compass>>,
     mut compass_hands_query: Query<(&CompassHand, &mut Transform), Without<Compass>>,
+    mut warn_once: Local<WarnOnce>,
 ) {
-    let camera_gl_transform = camera_query.get_single().unwrap();
-    let player_gl_transform = player_query.get_single().unwrap();
-    let mut compass_transform = compass_query.get_single_mut().unwrap();
+    let (Ok(camera_gl_transform), Ok(player_gl_transform), Ok(mut compass_transform)) = (
+        camera_query.get_single(),
+        player_query.get_single(),
+        compass_query.get_single_mut(),
+    ) else {
+        should_not_happen_once!(warn_once, "retrieving camera, player and compass");
+        return;
+    };
 
     let diff = player_gl_transform.translation() - camera_gl_transform.translation();
     let angle = diff.x.atan2(diff.z); // atan2 gives angle in the range (-PI, PI)
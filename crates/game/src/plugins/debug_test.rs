@@ -0,0 +1,52 @@
+use dungeon_maze_common::debug::{ChunkSpawnTimings, DebugStats, CHUNK_SPAWN_TIMINGS_LEN};
+use std::time::Duration;
+
+#[test]
+fn test_debug_stats_format() {
+    let stats = DebugStats {
+        fps: Some(59.6),
+        chunks: 27,
+        entities: 1234,
+        colliders: 456,
+        world_data_chunks: 3,
+        should_not_happens: 2,
+        chunk_spawn_avg: Some(Duration::from_micros(1250)),
+        chunk_spawn_max: Some(Duration::from_millis(4)),
+    };
+
+    assert_eq!(
+        stats.format(),
+        "FPS: 60\nChunks: 27\nEntities: 1234\nColliders: 456\nWorld Data Chunks: 3\nShould Not Happens: 2\nChunk Spawn: avg 1.25ms / max 4.00ms"
+    );
+}
+
+#[test]
+fn test_debug_stats_format_without_fps() {
+    // FPS is not known until a few frames have been measured
+    let stats = DebugStats::default();
+
+    assert_eq!(
+        stats.format(),
+        "FPS: -\nChunks: 0\nEntities: 0\nColliders: 0\nWorld Data Chunks: 0\nShould Not Happens: 0\nChunk Spawn: -"
+    );
+}
+
+#[test]
+fn test_chunk_spawn_timings_keep_only_the_latest() {
+    let mut timings = ChunkSpawnTimings::default();
+    assert_eq!(timings.avg(), None);
+    assert_eq!(timings.max(), None);
+
+    // The slowest one is the first, so it is the first to go
+    timings.record(Duration::from_millis(100));
+    for _ in 1..CHUNK_SPAWN_TIMINGS_LEN {
+        timings.record(Duration::from_millis(2));
+    }
+    assert_eq!(timings.len(), CHUNK_SPAWN_TIMINGS_LEN);
+    assert_eq!(timings.max(), Some(Duration::from_millis(100)));
+
+    timings.record(Duration::from_millis(2));
+    assert_eq!(timings.len(), CHUNK_SPAWN_TIMINGS_LEN);
+    assert_eq!(timings.max(), Some(Duration::from_millis(2)));
+    assert_eq!(timings.avg(), Some(Duration::from_millis(2)));
+}
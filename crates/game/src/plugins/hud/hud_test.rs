@@ -0,0 +1,408 @@
+use crate::plugins::hud::{spawn_hud_widget, HudPlugin};
+use bevy::{prelude::*, state::app::StatesPlugin, time::TimeUpdateStrategy};
+use dungeon_maze_common::{
+    app_state::{AppState, GameLoaded},
+    hud::*,
+    interaction::{InteractionCandidates, PendingInteraction},
+    inventory::{equipment::EquipmentStats, item::ItemName},
+    localization::Localization,
+    menu::MenuOpen,
+    player::{
+        attack::{AttackChargeCancelled, AttackChargeUp, AttackHand, AttackType},
+        DmgType, Health, Player, PlayerState, TakeDamage,
+    },
+    settings::{CrosshairStyle, GameSettings},
+    world::{
+        hazard::HazardDamaged, world_structure::WorldStructureRegistry, ActiveChunk, WorldDims,
+        WorldSeed,
+    },
+};
+use std::time::Duration;
+use strum::IntoEnumIterator;
+
+fn new_test_app() -> App {
+    let mut app = App::new();
+
+    app.add_plugins((MinimalPlugins, HierarchyPlugin, StatesPlugin, HudPlugin))
+        .init_resource::<WorldSeed>()
+        .init_resource::<WorldStructureRegistry>()
+        .init_resource::<Localization>()
+        .init_resource::<InteractionCandidates>()
+        .init_resource::<WorldDims>()
+        .init_resource::<EquipmentStats>()
+        .insert_resource(AttackChargeUp::new(10, 15, 6))
+        .init_state::<GameSettings>()
+        .init_state::<ActiveChunk>()
+        .init_state::<MenuOpen>()
+        .init_state::<PlayerState>()
+        .init_state::<PendingInteraction>()
+        .insert_state(AppState::InGame)
+        .add_computed_state::<GameLoaded>();
+
+    app
+}
+
+fn count<C: Component>(app: &mut App) -> usize {
+    let world = app.world_mut();
+    world.query_filtered::<(), With<C>>().iter(world).count()
+}
+
+#[test]
+fn test_every_hud_widget_spawns() {
+    let mut app = new_test_app();
+
+    for widget in HudWidget::iter() {
+        let anchor_node = app
+            .world_mut()
+            .commands()
+            .spawn(NodeBundle::default())
+            .with_children(|anchor_node| spawn_hud_widget(&widget, anchor_node))
+            .id();
+        app.world_mut().flush();
+
+        let children = app.world().get::<Children>(anchor_node);
+        assert!(children.is_some_and(|children| !children.is_empty()));
+    }
+
+    app.update();
+}
+
+#[test]
+fn test_default_hud_layout() {
+    let mut app = new_test_app();
+    app.update();
+
+    // Every widget is stacked under a single top left root, same as before layouts existed
+    assert_eq!(count::<HudRoot>(&mut app), 1);
+    assert_eq!(count::<HealthBar>(&mut app), 1);
+    assert_eq!(count::<StaminaBar>(&mut app), 1);
+    assert_eq!(count::<StatusEffectIcons>(&mut app), 1);
+    assert_eq!(count::<BuffIcons>(&mut app), 1);
+    assert_eq!(count::<StructureCompass>(&mut app), 1);
+    assert_eq!(count::<LightFuelGauge>(&mut app), 1);
+}
+
+#[test]
+fn test_hud_rebuilt_on_layout_changed() {
+    let mut app = new_test_app();
+    app.update();
+
+    let mut hud_layout = HudLayout::default();
+    hud_layout.get_mut(&HudWidget::HealthBar).unwrap().enabled = false;
+    hud_layout
+        .get_mut(&HudWidget::StructureCompass)
+        .unwrap()
+        .anchor = HudAnchor::BottomRight;
+    app.insert_resource(hud_layout);
+    app.world_mut().send_event(HudLayoutChanged);
+    app.update();
+
+    assert_eq!(count::<HudRoot>(&mut app), 2);
+    assert_eq!(count::<HealthBar>(&mut app), 0);
+    assert_eq!(count::<StaminaBar>(&mut app), 1);
+    assert_eq!(count::<StructureCompass>(&mut app), 1);
+}
+
+fn crosshair(app: &mut App) -> (Visibility, Vec3, Vec<Color>) {
+    let world = app.world_mut();
+    let (visibility, transform) = world
+        .query_filtered::<(&Visibility, &Transform), With<Crosshair>>()
+        .single(world);
+    let (visibility, scale) = (*visibility, transform.scale);
+    let colors = world
+        .query_filtered::<&BackgroundColor, With<CrosshairPart>>()
+        .iter(world)
+        .map(|background_color| background_color.0)
+        .collect();
+    (visibility, scale, colors)
+}
+
+#[test]
+fn test_crosshair_follows_game_settings() {
+    let mut app = new_test_app();
+    app.update();
+
+    assert_eq!(count::<Crosshair>(&mut app), 1);
+    assert_eq!(count::<CrosshairPart>(&mut app), 1);
+
+    let mut game_settings = *app.world().resource::<State<GameSettings>>().get();
+    game_settings.crosshair_style = CrosshairStyle::Cross;
+    app.world_mut()
+        .resource_mut::<NextState<GameSettings>>()
+        .set(game_settings);
+    app.update();
+
+    assert_eq!(count::<Crosshair>(&mut app), 1);
+    assert_eq!(count::<CrosshairPart>(&mut app), 4);
+}
+
+#[test]
+fn test_crosshair_reacts_to_game_state() {
+    let mut app = new_test_app();
+    app.update();
+
+    let (visibility, scale, colors) = crosshair(&mut app);
+    assert_eq!(visibility, Visibility::Inherited);
+    assert_eq!(scale, Vec3::ONE);
+    let default_color = colors[0];
+
+    // A pending interactable changes the color
+    let entity = app.world_mut().spawn_empty().id();
+    app.world_mut()
+        .resource_mut::<NextState<PendingInteraction>>()
+        .set(PendingInteraction(Some(entity)));
+    app.update();
+
+    let (_, _, colors) = crosshair(&mut app);
+    assert_ne!(colors[0], default_color);
+
+    // Attacking enlarges the crosshair
+    app.world_mut()
+        .resource_mut::<NextState<PlayerState>>()
+        .set(PlayerState::Attacking(AttackType::Light, AttackHand::Left));
+    app.update();
+
+    let (_, scale, _) = crosshair(&mut app);
+    assert!(scale.x > 1.0 && scale.y > 1.0);
+
+    // Opening the menu hides it
+    app.world_mut()
+        .resource_mut::<NextState<MenuOpen>>()
+        .set(MenuOpen(true));
+    app.update();
+
+    let (visibility, _, _) = crosshair(&mut app);
+    assert_eq!(visibility, Visibility::Hidden);
+}
+
+fn interaction_prompt(app: &mut App) -> String {
+    let world = app.world_mut();
+    let text = world
+        .query_filtered::<&Text, With<InteractionPrompt>>()
+        .single(world);
+    text.sections[0].value.clone()
+}
+
+#[test]
+fn test_interaction_prompt_shows_cycle_position() {
+    let mut app = new_test_app();
+    app.update();
+
+    assert_eq!(interaction_prompt(&mut app), "");
+
+    let entities = (0..3).map(|_| app.world_mut().spawn_empty().id()).collect();
+    app.insert_resource(InteractionCandidates {
+        entities,
+        similar: 3,
+        selected: 1,
+    });
+    app.update();
+
+    assert!(interaction_prompt(&mut app).starts_with("2/3"));
+
+    // A single similar candidate has nothing to cycle through
+    app.world_mut()
+        .resource_mut::<InteractionCandidates>()
+        .similar = 1;
+    app.update();
+
+    assert_eq!(interaction_prompt(&mut app), "");
+}
+
+fn damage_flash_alpha(app: &mut App) -> f32 {
+    let world = app.world_mut();
+    world
+        .query_filtered::<&BackgroundColor, With<DamageFlash>>()
+        .single(world)
+        .0
+        .alpha()
+}
+
+#[test]
+fn test_damage_flash_on_hazard_damage() {
+    let mut app = new_test_app();
+    app.update();
+
+    assert_eq!(damage_flash_alpha(&mut app), 0.0);
+
+    // Hazards hurting anything other than the player do not flash
+    let other = app.world_mut().spawn_empty().id();
+    app.world_mut().send_event(HazardDamaged { target: other });
+    app.update();
+
+    assert_eq!(damage_flash_alpha(&mut app), 0.0);
+
+    let player = app.world_mut().spawn(Player).id();
+    app.world_mut().send_event(HazardDamaged { target: player });
+    app.update();
+
+    let alpha = damage_flash_alpha(&mut app);
+    assert!(alpha > 0.0);
+
+    // Then fades out
+    app.update();
+    assert!(damage_flash_alpha(&mut app) < alpha);
+    for _ in 0..100 {
+        app.update();
+    }
+    assert_eq!(damage_flash_alpha(&mut app), 0.0);
+}
+
+fn damage_vignette_alphas(app: &mut App) -> Vec<f32> {
+    let world = app.world_mut();
+    world
+        .query_filtered::<&BorderColor, With<DamageVignetteRing>>()
+        .iter(world)
+        .map(|border_color| border_color.0.alpha())
+        .collect()
+}
+
+fn damage_vignette_alpha(app: &mut App) -> f32 {
+    damage_vignette_alphas(app)[0]
+}
+
+fn hit(app: &mut App, target: Entity, amounts: Vec<(DmgType, f32)>) {
+    app.world_mut().send_event(TakeDamage {
+        amounts,
+        target,
+        source: None,
+    });
+    app.update();
+}
+
+#[test]
+fn test_damage_vignette_scales_with_damage() {
+    let mut app = new_test_app();
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+        50,
+    )));
+    app.update();
+
+    assert!(damage_vignette_alphas(&mut app)
+        .iter()
+        .all(|alpha| *alpha == 0.0));
+
+    let player = app
+        .world_mut()
+        .spawn((Player, Health::new(100.0, 100.0, 0.0)))
+        .id();
+
+    // Stamina damage does not count
+    hit(&mut app, player, vec![(DmgType::Stamina, 50.0)]);
+    assert_eq!(damage_vignette_alpha(&mut app), 0.0);
+
+    hit(&mut app, player, vec![(DmgType::Slash, 5.0)]);
+    let small = damage_vignette_alpha(&mut app);
+    assert!(small > 0.0);
+
+    // Fades out over half a second
+    app.update();
+    assert!(damage_vignette_alpha(&mut app) < small);
+    for _ in 0..10 {
+        app.update();
+    }
+    assert_eq!(damage_vignette_alpha(&mut app), 0.0);
+
+    hit(&mut app, player, vec![(DmgType::Slash, 20.0)]);
+    let large = damage_vignette_alpha(&mut app);
+    assert!(large > small);
+
+    // Capped no matter how much damage is taken
+    for _ in 0..10 {
+        app.update();
+    }
+    hit(&mut app, player, vec![(DmgType::Slash, 1000.0)]);
+    assert!(damage_vignette_alpha(&mut app) <= 0.15);
+}
+
+#[test]
+fn test_damage_vignette_disabled_in_settings() {
+    let mut app = new_test_app();
+    app.update();
+
+    let mut game_settings = *app.world().resource::<State<GameSettings>>().get();
+    game_settings.disable_damage_effects = true;
+    app.world_mut()
+        .resource_mut::<NextState<GameSettings>>()
+        .set(game_settings);
+    app.update();
+
+    let player = app
+        .world_mut()
+        .spawn((Player, Health::new(100.0, 100.0, 0.0)))
+        .id();
+    hit(&mut app, player, vec![(DmgType::Slash, 50.0)]);
+
+    assert_eq!(damage_vignette_alpha(&mut app), 0.0);
+}
+
+fn attack_charge_bar(app: &mut App) -> (Visibility, Val, Color) {
+    let world = app.world_mut();
+    let (visibility, style, background_color) = world
+        .query_filtered::<(&Visibility, &Style, &BackgroundColor), With<AttackChargeBar>>()
+        .single(world);
+    (*visibility, style.width, background_color.0)
+}
+
+#[test]
+fn test_attack_charge_bar_flashes_on_cancel() {
+    let mut app = new_test_app();
+    app.update();
+
+    let (visibility, _, charging_color) = attack_charge_bar(&mut app);
+    assert_eq!(visibility, Visibility::Hidden);
+
+    let mut attack_charge_up = app.world_mut().resource_mut::<AttackChargeUp>();
+    attack_charge_up.start(&AttackHand::Left);
+    attack_charge_up.tick(&AttackHand::Left);
+    app.update();
+
+    let (visibility, width, _) = attack_charge_bar(&mut app);
+    assert_eq!(visibility, Visibility::Inherited);
+    assert!(matches!(width, Val::Px(px) if px > 0.0));
+
+    app.world_mut()
+        .resource_mut::<AttackChargeUp>()
+        .cancel(None);
+    app.world_mut().send_event(AttackChargeCancelled);
+    app.update();
+
+    let (visibility, _, color) = attack_charge_bar(&mut app);
+    assert_eq!(visibility, Visibility::Inherited);
+    assert_ne!(color, charging_color);
+
+    // Hidden again once the flash is over
+    for _ in 0..100 {
+        app.update();
+    }
+    let (visibility, _, _) = attack_charge_bar(&mut app);
+    assert_eq!(visibility, Visibility::Hidden);
+}
+
+fn pair_bonus_icon(app: &mut App) -> (Visibility, String) {
+    let world = app.world_mut();
+    let (visibility, text) = world
+        .query_filtered::<(&Visibility, &Text), With<PairBonusIcon>>()
+        .single(world);
+    (*visibility, text.sections[0].value.clone())
+}
+
+#[test]
+fn test_pair_bonus_icon_shown_while_a_bonus_is_active() {
+    let mut app = new_test_app();
+    app.update();
+    assert_eq!(pair_bonus_icon(&mut app).0, Visibility::Hidden);
+
+    let pair_bonus = ItemName::Katana.pair_bonus().unwrap();
+    app.world_mut().resource_mut::<EquipmentStats>().pair_bonus = Some(pair_bonus);
+    app.update();
+
+    let (visibility, text) = pair_bonus_icon(&mut app);
+    assert_eq!(visibility, Visibility::Inherited);
+    let localization = app.world().resource::<Localization>();
+    assert_eq!(text, localization.tr(pair_bonus.display_key(), &[]));
+
+    app.world_mut().resource_mut::<EquipmentStats>().pair_bonus = None;
+    app.update();
+    assert_eq!(pair_bonus_icon(&mut app).0, Visibility::Hidden);
+}
@@ -0,0 +1,1119 @@
+use bevy::prelude::*;
+use dungeon_maze_common::{
+    app_state::{AppState, GameLoaded},
+    camera::MainCamera,
+    hud::*,
+    interaction::{InteractionCandidates, PendingInteraction},
+    inventory::equipment::EquipmentStats,
+    localization::Localization,
+    menu::MenuOpen,
+    player::{
+        attack::{AttackChargeCancelled, AttackChargeUp},
+        light::LightFuel,
+        status_effect::{ActiveStatusEffects, StatusEffect},
+        Health, Player, PlayerState, Regenerator, Stamina, TakeDamage, TempAmt,
+    },
+    settings::{CrosshairStyle, GameSettings},
+    world::{
+        hazard::HazardDamaged, nearest_structure_chunk, world_structure::WorldStructureRegistry,
+        ActiveChunk, WorldDims, WorldSeed,
+    },
+};
+use strum::IntoEnumIterator;
+
+#[cfg(test)]
+mod hud_test;
+
+const HEALTH_BAR_MAX_WIDTH: f32 = 300.0;
+const STAMINA_BAR_MAX_WIDTH: f32 = 300.0;
+const LIGHT_FUEL_GAUGE_MAX_WIDTH: f32 = 150.0;
+const STATUS_EFFECT_ICON_WIDTH: f32 = 70.0;
+const BUFF_ICON_WIDTH: f32 = 90.0;
+const STRUCTURE_COMPASS_SIZE: f32 = 80.0;
+// How many chunks away from the active chunk to search for world structures
+const STRUCTURE_COMPASS_MAX_RADIUS: u32 = 8;
+const CROSSHAIR_LINE_THICKNESS: f32 = 2.0;
+// Gap between the center of the screen and each line of the cross
+const CROSSHAIR_CROSS_GAP: f32 = 3.0;
+const CROSSHAIR_ATTACKING_SCALE: f32 = 1.5;
+const CROSSHAIR_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.8);
+const CROSSHAIR_INTERACTABLE_COLOR: Color = Color::srgba(1.0, 0.85, 0.2, 0.9);
+// Distance from the center of the screen to the top of the interaction prompt
+const INTERACTION_PROMPT_OFFSET: f32 = 24.0;
+// Distance from the center of the screen to the top of the attack charge bar
+const ATTACK_CHARGE_BAR_OFFSET: f32 = 14.0;
+const ATTACK_CHARGE_BAR_MAX_WIDTH: f32 = 40.0;
+const ATTACK_CHARGE_BAR_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.8);
+const ATTACK_CHARGE_BAR_CANCEL_COLOR: Color = Color::srgba(1.0, 0.2, 0.2, 0.9);
+const ATTACK_CHARGE_BAR_FLASH_FRAMES: u32 = 12;
+const DAMAGE_FLASH_FRAMES: u32 = 20;
+const DAMAGE_FLASH_MAX_ALPHA: f32 = 0.35;
+const DAMAGE_VIGNETTE_FADE_SECS: f32 = 0.5;
+// Each ring is a border this much thicker than the one inside of it
+const DAMAGE_VIGNETTE_RING_WIDTH: f32 = 3.0;
+const DAMAGE_VIGNETTE_RINGS: usize = 6;
+// Alpha of each ring at full intensity, so the very edges of the screen are at most
+// about 0.6 alpha once every ring is stacked on top of each other
+const DAMAGE_VIGNETTE_RING_ALPHA: f32 = 0.15;
+const PAIR_BONUS_ICON_COLOR: Color = Color::srgba(0.75, 0.6, 0.15, 0.85);
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NearestStructure>()
+            .init_resource::<HudLayout>()
+            .add_event::<HudLayoutChanged>()
+            .add_event::<HazardDamaged>()
+            .add_event::<TakeDamage>()
+            .add_event::<AttackChargeCancelled>()
+            .add_systems(
+                OnEnter(GameLoaded),
+                (
+                    spawn_hud,
+                    spawn_crosshair,
+                    spawn_interaction_prompt,
+                    spawn_attack_charge_bar,
+                    spawn_damage_flash,
+                    spawn_damage_vignette,
+                    spawn_pair_bonus_icon,
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    rebuild_hud,
+                    rebuild_crosshair,
+                    update_crosshair.after(rebuild_crosshair),
+                    update_interaction_prompt,
+                    update_attack_charge_bar,
+                    update_damage_flash,
+                    update_damage_vignette,
+                    update_health_bar,
+                    update_stamina_bar,
+                    update_light_fuel_gauge,
+                    update_status_effect_icons,
+                    update_buff_icons,
+                    update_pair_bonus_icon,
+                    (update_nearest_structure, update_structure_compass).chain(),
+                )
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+fn spawn_hud(mut commands: Commands, hud_layout: Res<HudLayout>) {
+    spawn_hud_layout(&mut commands, &hud_layout);
+}
+
+fn rebuild_hud(
+    mut commands: Commands,
+    mut event_reader: EventReader<HudLayoutChanged>,
+    hud_root_query: Query<Entity, With<HudRoot>>,
+    hud_layout: Res<HudLayout>,
+) {
+    if event_reader.read().count() == 0 {
+        return;
+    }
+
+    for entity in hud_root_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_hud_layout(&mut commands, &hud_layout);
+}
+
+// Spawns one root node per anchor corner, with that anchor's enabled widgets stacked inside it
+fn spawn_hud_layout(commands: &mut Commands, hud_layout: &HudLayout) {
+    for anchor in HudAnchor::iter() {
+        let widgets: Vec<&HudWidgetLayout> = hud_layout
+            .widgets
+            .iter()
+            .filter(|w| w.enabled && w.anchor == anchor)
+            .collect();
+        if widgets.is_empty() {
+            continue;
+        }
+
+        commands
+            .spawn((
+                HudRoot,
+                NodeBundle {
+                    style: hud_anchor_style(&anchor),
+                    ..default()
+                },
+                StateScoped(GameLoaded),
+                Name::new(format!("HUD {}", anchor)),
+            ))
+            .with_children(|parent| {
+                for widget_layout in widgets {
+                    parent
+                        .spawn(NodeBundle {
+                            style: hud_offset_style(&anchor, widget_layout.offset),
+                            ..default()
+                        })
+                        .with_children(|anchor_node| {
+                            spawn_hud_widget(&widget_layout.widget, anchor_node)
+                        });
+                }
+            });
+    }
+}
+
+fn spawn_crosshair(mut commands: Commands, game_settings: Res<State<GameSettings>>) {
+    spawn_crosshair_node(&mut commands, game_settings.get());
+}
+
+fn rebuild_crosshair(
+    mut commands: Commands,
+    mut event_reader: EventReader<StateTransitionEvent<GameSettings>>,
+    crosshair_query: Query<Entity, With<Crosshair>>,
+    game_settings: Res<State<GameSettings>>,
+) {
+    if event_reader.read().count() == 0 {
+        return;
+    }
+
+    for entity in crosshair_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_crosshair_node(&mut commands, game_settings.get());
+}
+
+fn spawn_crosshair_node(commands: &mut Commands, game_settings: &GameSettings) {
+    let size = game_settings.crosshair_size.px();
+    // Rects of each part, relative to the top left of a square fitting the whole crosshair
+    let (extent, parts, border_radius) = match game_settings.crosshair_style {
+        CrosshairStyle::Dot => (
+            size,
+            vec![Rect::new(0.0, 0.0, size, size)],
+            BorderRadius::MAX,
+        ),
+        CrosshairStyle::Cross => {
+            let extent = (size + CROSSHAIR_CROSS_GAP) * 2.0;
+            let (near, far) = (size + CROSSHAIR_CROSS_GAP * 2.0, extent);
+            let (mid_start, mid_end) = (
+                (extent - CROSSHAIR_LINE_THICKNESS) / 2.0,
+                (extent + CROSSHAIR_LINE_THICKNESS) / 2.0,
+            );
+            let parts = vec![
+                Rect::new(0.0, mid_start, size, mid_end),
+                Rect::new(near, mid_start, far, mid_end),
+                Rect::new(mid_start, 0.0, mid_end, size),
+                Rect::new(mid_start, near, mid_end, far),
+            ];
+            (extent, parts, BorderRadius::ZERO)
+        }
+    };
+
+    commands
+        .spawn((
+            Crosshair,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    display: Display::Flex,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    height: Val::Percent(100.0),
+                    width: Val::Percent(100.0),
+                    ..default()
+                },
+                ..default()
+            },
+            StateScoped(GameLoaded),
+            Name::new("Crosshair"),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        height: Val::Px(extent),
+                        width: Val::Px(extent),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|grandparent| {
+                    for rect in parts {
+                        grandparent.spawn((
+                            CrosshairPart,
+                            NodeBundle {
+                                style: Style {
+                                    position_type: PositionType::Absolute,
+                                    left: Val::Px(rect.min.x),
+                                    top: Val::Px(rect.min.y),
+                                    width: Val::Px(rect.width()),
+                                    height: Val::Px(rect.height()),
+                                    ..default()
+                                },
+                                background_color: CROSSHAIR_COLOR.into(),
+                                border_radius,
+                                ..default()
+                            },
+                        ));
+                    }
+                });
+        });
+}
+
+fn update_crosshair(
+    mut crosshair_query: Query<(&mut Visibility, &mut Transform), With<Crosshair>>,
+    mut part_query: Query<&mut BackgroundColor, With<CrosshairPart>>,
+    menu_open: Res<State<MenuOpen>>,
+    player_state: Res<State<PlayerState>>,
+    pending_interaction: Res<State<PendingInteraction>>,
+) {
+    // The crosshair is centered on the screen, so scaling it does not move it
+    let scale = match player_state.get() {
+        PlayerState::Attacking(..) | PlayerState::DualAttacking(..) => CROSSHAIR_ATTACKING_SCALE,
+        PlayerState::Walking | PlayerState::Sprinting => 1.0,
+    };
+    for (mut visibility, mut transform) in crosshair_query.iter_mut() {
+        *visibility = if menu_open.get().0 {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        transform.scale = Vec3::new(scale, scale, 1.0);
+    }
+
+    let color = match pending_interaction.get().0 {
+        Some(_) => CROSSHAIR_INTERACTABLE_COLOR,
+        None => CROSSHAIR_COLOR,
+    };
+    for mut background_color in part_query.iter_mut() {
+        background_color.0 = color;
+    }
+}
+
+fn spawn_interaction_prompt(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    display: Display::Flex,
+                    justify_content: JustifyContent::Center,
+                    top: Val::Percent(50.0),
+                    width: Val::Percent(100.0),
+                    margin: UiRect::top(Val::Px(INTERACTION_PROMPT_OFFSET)),
+                    ..default()
+                },
+                ..default()
+            },
+            StateScoped(GameLoaded),
+            Name::new("Interaction Prompt"),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                InteractionPrompt,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 14.0,
+                        color: CROSSHAIR_INTERACTABLE_COLOR,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+}
+
+fn update_interaction_prompt(
+    mut prompt_query: Query<&mut Text, With<InteractionPrompt>>,
+    menu_open: Res<State<MenuOpen>>,
+    candidates: Res<InteractionCandidates>,
+    localization: Res<Localization>,
+) {
+    // Only shown when there is more than one interactable to cycle through
+    let prompt = if menu_open.get().0 || candidates.similar < 2 {
+        String::new()
+    } else {
+        localization.tr(
+            "hud.interaction.cycle",
+            &[
+                ("index", &(candidates.selected + 1)),
+                ("count", &candidates.similar),
+            ],
+        )
+    };
+
+    for mut text in prompt_query.iter_mut() {
+        for section in text.sections.iter_mut() {
+            if section.value != prompt {
+                section.value = prompt.clone();
+            }
+        }
+    }
+}
+
+fn spawn_pair_bonus_icon(mut commands: Commands) {
+    commands.spawn((
+        PairBonusIcon,
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 12.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(12.0),
+                bottom: Val::Px(12.0),
+                padding: UiRect::all(Val::Px(4.0)),
+                ..default()
+            },
+            background_color: PAIR_BONUS_ICON_COLOR.into(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        StateScoped(GameLoaded),
+        Name::new("Pair Bonus Icon"),
+    ));
+}
+
+fn update_pair_bonus_icon(
+    mut icon_query: Query<(&mut Text, &mut Visibility), With<PairBonusIcon>>,
+    equipment_stats: Res<EquipmentStats>,
+    localization: Res<Localization>,
+) {
+    let label = equipment_stats
+        .pair_bonus
+        .map(|pair_bonus| localization.tr(pair_bonus.display_key(), &[]));
+
+    for (mut text, mut visibility) in icon_query.iter_mut() {
+        visibility.set_if_neq(match label {
+            Some(_) => Visibility::Inherited,
+            None => Visibility::Hidden,
+        });
+        if let Some(label) = &label {
+            if text.sections[0].value != *label {
+                text.sections[0].value = label.clone();
+            }
+        }
+    }
+}
+
+fn spawn_attack_charge_bar(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    display: Display::Flex,
+                    justify_content: JustifyContent::Center,
+                    top: Val::Percent(50.0),
+                    width: Val::Percent(100.0),
+                    margin: UiRect::top(Val::Px(ATTACK_CHARGE_BAR_OFFSET)),
+                    ..default()
+                },
+                ..default()
+            },
+            StateScoped(GameLoaded),
+            Name::new("Attack Charge Bar"),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                AttackChargeBar::default(),
+                NodeBundle {
+                    style: Style {
+                        height: Val::Px(3.0),
+                        width: Val::Px(0.0),
+                        ..default()
+                    },
+                    visibility: Visibility::Hidden,
+                    background_color: ATTACK_CHARGE_BAR_COLOR.into(),
+                    ..default()
+                },
+            ));
+        });
+}
+
+// Hidden unless an attack is charging, or was just cancelled
+fn update_attack_charge_bar(
+    mut event_reader: EventReader<AttackChargeCancelled>,
+    mut bar_query: Query<(
+        &mut AttackChargeBar,
+        &mut Style,
+        &mut Visibility,
+        &mut BackgroundColor,
+    )>,
+    attack_charge_up: Res<AttackChargeUp>,
+) {
+    let cancelled = event_reader.read().count() > 0;
+    let charge_fraction = attack_charge_up.charge_fraction();
+
+    for (mut bar, mut style, mut visibility, mut background_color) in bar_query.iter_mut() {
+        if cancelled {
+            bar.flash_frames_left = ATTACK_CHARGE_BAR_FLASH_FRAMES;
+        }
+
+        if bar.flash_frames_left > 0 && charge_fraction.is_none() {
+            // Blinks on and off every few frames until the flash is over
+            let on = (bar.flash_frames_left / 3) % 2 == 0;
+            bar.flash_frames_left -= 1;
+            *visibility = if on {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+            background_color.0 = ATTACK_CHARGE_BAR_CANCEL_COLOR;
+            continue;
+        }
+        bar.flash_frames_left = 0;
+
+        match charge_fraction {
+            Some(fraction) => {
+                *visibility = Visibility::Inherited;
+                style.width = Val::Px(fraction * ATTACK_CHARGE_BAR_MAX_WIDTH);
+                background_color.0 = ATTACK_CHARGE_BAR_COLOR;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+fn spawn_damage_flash(mut commands: Commands) {
+    commands.spawn((
+        DamageFlash::default(),
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::NONE),
+            ..default()
+        },
+        StateScoped(GameLoaded),
+        Name::new("Damage Flash"),
+    ));
+}
+
+fn update_damage_flash(
+    mut event_reader: EventReader<HazardDamaged>,
+    mut flash_query: Query<(&mut DamageFlash, &mut BackgroundColor)>,
+    player_query: Query<(), With<Player>>,
+    game_settings: Res<State<GameSettings>>,
+) {
+    let player_hit = event_reader
+        .read()
+        .any(|event| player_query.contains(event.target))
+        && !game_settings.get().disable_damage_effects;
+
+    for (mut flash, mut background_color) in flash_query.iter_mut() {
+        if player_hit {
+            flash.frames_left = DAMAGE_FLASH_FRAMES;
+        } else if flash.frames_left > 0 {
+            flash.frames_left -= 1;
+        } else {
+            continue;
+        }
+
+        let alpha = DAMAGE_FLASH_MAX_ALPHA * flash.frames_left as f32 / DAMAGE_FLASH_FRAMES as f32;
+        background_color.0 = Color::srgba(0.8, 0.0, 0.0, alpha);
+    }
+}
+
+fn spawn_damage_vignette(mut commands: Commands) {
+    commands
+        .spawn((
+            DamageVignette::default(),
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                ..default()
+            },
+            StateScoped(GameLoaded),
+            Name::new("Damage Vignette"),
+        ))
+        .with_children(|parent| {
+            for i in 1..=DAMAGE_VIGNETTE_RINGS {
+                parent.spawn((
+                    DamageVignetteRing,
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                            border: UiRect::all(Val::VMin(DAMAGE_VIGNETTE_RING_WIDTH * i as f32)),
+                            ..default()
+                        },
+                        border_color: BorderColor(Color::NONE),
+                        ..default()
+                    },
+                ));
+            }
+        });
+}
+
+fn update_damage_vignette(
+    mut event_reader: EventReader<TakeDamage>,
+    mut vignette_query: Query<(&mut DamageVignette, &Children)>,
+    mut ring_query: Query<&mut BorderColor, With<DamageVignetteRing>>,
+    player_query: Query<&Health, With<Player>>,
+    game_settings: Res<State<GameSettings>>,
+    time: Res<Time>,
+) {
+    let disabled = game_settings.get().disable_damage_effects;
+    let intensity = event_reader
+        .read()
+        .filter_map(|event| {
+            let health = player_query.get(event.target).ok()?;
+            Some(event.feedback_intensity(health.max_value))
+        })
+        .fold(0.0, f32::max);
+
+    for (mut vignette, children) in vignette_query.iter_mut() {
+        if intensity > 0.0 && !disabled {
+            let current = vignette.intensity * vignette.secs_left / DAMAGE_VIGNETTE_FADE_SECS;
+            vignette.intensity = intensity.max(current);
+            vignette.secs_left = DAMAGE_VIGNETTE_FADE_SECS;
+        } else if vignette.secs_left > 0.0 {
+            vignette.secs_left = if disabled {
+                0.0
+            } else {
+                (vignette.secs_left - time.delta_seconds()).max(0.0)
+            };
+        } else {
+            continue;
+        }
+
+        let alpha = DAMAGE_VIGNETTE_RING_ALPHA * vignette.intensity * vignette.secs_left
+            / DAMAGE_VIGNETTE_FADE_SECS;
+        let mut iter = ring_query.iter_many_mut(children);
+        while let Some(mut border_color) = iter.fetch_next() {
+            border_color.0 = Color::srgba(0.8, 0.0, 0.0, alpha);
+        }
+    }
+}
+
+fn hud_anchor_style(anchor: &HudAnchor) -> Style {
+    let margin = Val::Px(10.0);
+    let (top, bottom) = match anchor {
+        HudAnchor::TopLeft | HudAnchor::TopRight => (margin, Val::Auto),
+        HudAnchor::BottomLeft | HudAnchor::BottomRight => (Val::Auto, margin),
+    };
+    let (left, right, align_items) = match anchor {
+        HudAnchor::TopLeft | HudAnchor::BottomLeft => (margin, Val::Auto, AlignItems::Start),
+        HudAnchor::TopRight | HudAnchor::BottomRight => (Val::Auto, margin, AlignItems::End),
+    };
+
+    Style {
+        position_type: PositionType::Absolute,
+        display: Display::Flex,
+        flex_direction: FlexDirection::Column,
+        align_items,
+        top,
+        bottom,
+        left,
+        right,
+        ..default()
+    }
+}
+
+// Offsets push widgets away from their anchor corner without affecting the other widgets
+fn hud_offset_style(anchor: &HudAnchor, (x, y): (f32, f32)) -> Style {
+    let (top, bottom) = match anchor {
+        HudAnchor::TopLeft | HudAnchor::TopRight => (Val::Px(y), Val::Auto),
+        HudAnchor::BottomLeft | HudAnchor::BottomRight => (Val::Auto, Val::Px(y)),
+    };
+    let (left, right) = match anchor {
+        HudAnchor::TopLeft | HudAnchor::BottomLeft => (Val::Px(x), Val::Auto),
+        HudAnchor::TopRight | HudAnchor::BottomRight => (Val::Auto, Val::Px(x)),
+    };
+
+    Style {
+        position_type: PositionType::Relative,
+        top,
+        bottom,
+        left,
+        right,
+        ..default()
+    }
+}
+
+pub fn spawn_hud_widget(widget: &HudWidget, anchor_node: &mut ChildBuilder) {
+    match widget {
+        HudWidget::HealthBar => spawn_health_bar(anchor_node),
+        HudWidget::StaminaBar => spawn_stamina_bar(anchor_node),
+        HudWidget::StatusEffects => spawn_status_effect_icons(anchor_node),
+        HudWidget::Buffs => spawn_buff_icons(anchor_node),
+        HudWidget::StructureCompass => spawn_structure_compass(anchor_node),
+        HudWidget::LightFuelGauge => spawn_light_fuel_gauge(anchor_node),
+    }
+}
+
+fn spawn_health_bar(anchor_node: &mut ChildBuilder) {
+    anchor_node.spawn((
+        HealthBar,
+        NodeBundle {
+            style: Style {
+                height: Val::Px(30.0),
+                width: Val::Px(HEALTH_BAR_MAX_WIDTH),
+                margin: UiRect::all(Val::Px(4.0)),
+                ..default()
+            },
+            background_color: Color::linear_rgb(0.6, 0.2, 0.2).into(),
+            ..default()
+        },
+    ));
+}
+
+fn spawn_stamina_bar(anchor_node: &mut ChildBuilder) {
+    anchor_node.spawn((
+        StaminaBar,
+        NodeBundle {
+            style: Style {
+                height: Val::Px(30.0),
+                width: Val::Px(STAMINA_BAR_MAX_WIDTH),
+                margin: UiRect::all(Val::Px(4.0)),
+                ..default()
+            },
+            background_color: Color::linear_rgb(0.2, 0.6, 0.2).into(),
+            ..default()
+        },
+    ));
+}
+
+fn spawn_light_fuel_gauge(anchor_node: &mut ChildBuilder) {
+    anchor_node.spawn((
+        LightFuelGauge,
+        NodeBundle {
+            style: Style {
+                display: Display::None,
+                height: Val::Px(12.0),
+                width: Val::Px(LIGHT_FUEL_GAUGE_MAX_WIDTH),
+                margin: UiRect::all(Val::Px(4.0)),
+                ..default()
+            },
+            background_color: Color::linear_rgb(0.9, 0.6, 0.1).into(),
+            ..default()
+        },
+    ));
+}
+
+fn spawn_status_effect_icons(anchor_node: &mut ChildBuilder) {
+    anchor_node.spawn((
+        StatusEffectIcons,
+        NodeBundle {
+            style: Style {
+                display: Display::Flex,
+                column_gap: Val::Px(6.0),
+                margin: UiRect::all(Val::Px(4.0)),
+                ..default()
+            },
+            ..default()
+        },
+    ));
+}
+
+fn spawn_buff_icons(anchor_node: &mut ChildBuilder) {
+    anchor_node.spawn((
+        BuffIcons,
+        NodeBundle {
+            style: Style {
+                display: Display::Flex,
+                column_gap: Val::Px(6.0),
+                margin: UiRect::all(Val::Px(4.0)),
+                ..default()
+            },
+            ..default()
+        },
+    ));
+}
+
+fn spawn_structure_compass(anchor_node: &mut ChildBuilder) {
+    anchor_node
+        .spawn((
+            StructureCompass,
+            NodeBundle {
+                style: Style {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::all(Val::Px(4.0)),
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            Name::new("Structure Compass"),
+        ))
+        .with_children(|compass| {
+            compass
+                .spawn(NodeBundle {
+                    style: Style {
+                        display: Display::Flex,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        height: Val::Px(STRUCTURE_COMPASS_SIZE),
+                        width: Val::Px(STRUCTURE_COMPASS_SIZE),
+                        ..default()
+                    },
+                    border_radius: BorderRadius::all(Val::Percent(50.0)),
+                    background_color: Color::linear_rgba(0.1, 0.1, 0.1, 0.6).into(),
+                    ..default()
+                })
+                .with_children(|dial| {
+                    // The needle points up (away from the camera) when unrotated,
+                    // with the red half pointing towards the structure
+                    dial.spawn((
+                        StructureCompassNeedle,
+                        NodeBundle {
+                            style: Style {
+                                display: Display::Flex,
+                                flex_direction: FlexDirection::Column,
+                                height: Val::Px(STRUCTURE_COMPASS_SIZE * 0.8),
+                                width: Val::Px(6.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                    ))
+                    .with_children(|needle| {
+                        for color in [
+                            Color::linear_rgb(0.8, 0.1, 0.1),
+                            Color::linear_rgb(0.9, 0.9, 0.9),
+                        ] {
+                            needle.spawn(NodeBundle {
+                                style: Style {
+                                    height: Val::Percent(50.0),
+                                    width: Val::Percent(100.0),
+                                    ..default()
+                                },
+                                background_color: color.into(),
+                                ..default()
+                            });
+                        }
+                    });
+                });
+
+            compass.spawn((
+                StructureCompassText,
+                TextBundle {
+                    text: Text {
+                        sections: vec![TextSection::new(
+                            "",
+                            TextStyle {
+                                font_size: 14.0,
+                                color: Color::WHITE,
+                                ..default()
+                            },
+                        )],
+                        ..default()
+                    },
+                    style: Style {
+                        margin: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn update_health_bar(
+    player_health_query: Query<&Health, With<Player>>,
+    mut health_bar_query: Query<&mut Style, With<HealthBar>>,
+) {
+    for health in player_health_query.iter() {
+        for mut style in health_bar_query.iter_mut() {
+            style.width = Val::Px(health.value / health.max_value * HEALTH_BAR_MAX_WIDTH);
+        }
+    }
+}
+
+fn update_stamina_bar(
+    player_stamina_query: Query<&Stamina, With<Player>>,
+    mut stamina_bar_query: Query<&mut Style, With<StaminaBar>>,
+) {
+    for stamina in player_stamina_query.iter() {
+        for mut style in stamina_bar_query.iter_mut() {
+            style.width = Val::Px(stamina.value / stamina.max_value * STAMINA_BAR_MAX_WIDTH);
+        }
+    }
+}
+
+fn update_light_fuel_gauge(
+    light_fuel_query: Query<&LightFuel>,
+    mut light_fuel_gauge_query: Query<&mut Style, With<LightFuelGauge>>,
+    game_settings: Res<State<GameSettings>>,
+) {
+    let is_on = game_settings.get().light_fuel.is_on();
+
+    for fuel in light_fuel_query.iter() {
+        for mut style in light_fuel_gauge_query.iter_mut() {
+            style.display = if is_on { Display::Flex } else { Display::None };
+            style.width = Val::Px(fuel.fraction() * LIGHT_FUEL_GAUGE_MAX_WIDTH);
+        }
+    }
+}
+
+fn status_effect_color(status_effect: &StatusEffect) -> Color {
+    match status_effect {
+        StatusEffect::Burning => Color::linear_rgb(0.8, 0.3, 0.1),
+        StatusEffect::Poisoned => Color::linear_rgb(0.4, 0.7, 0.1),
+        StatusEffect::Chilled => Color::linear_rgb(0.3, 0.6, 0.9),
+    }
+}
+
+fn update_status_effect_icons(
+    mut commands: Commands,
+    player_query: Query<&ActiveStatusEffects, (With<Player>, Changed<ActiveStatusEffects>)>,
+    icons_query: Query<Entity, With<StatusEffectIcons>>,
+    localization: Res<Localization>,
+) {
+    let Ok(active_status_effects) = player_query.get_single() else {
+        return;
+    };
+
+    for entity in icons_query.iter() {
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.despawn_descendants();
+        entity_commands.with_children(|parent| {
+            for status_effect in StatusEffect::iter() {
+                let Some(remaining) = active_status_effects.remaining(&status_effect) else {
+                    continue;
+                };
+
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            display: Display::Flex,
+                            flex_direction: FlexDirection::Column,
+                            width: Val::Px(STATUS_EFFECT_ICON_WIDTH),
+                            ..default()
+                        },
+                        background_color: status_effect_color(&status_effect).into(),
+                        ..default()
+                    })
+                    .with_children(|icon| {
+                        icon.spawn(TextBundle {
+                            text: Text {
+                                sections: vec![TextSection::new(
+                                    localization.tr(status_effect.display_key(), &[]),
+                                    TextStyle {
+                                        font_size: 12.0,
+                                        color: Color::WHITE,
+                                        ..default()
+                                    },
+                                )],
+                                ..default()
+                            },
+                            style: Style {
+                                margin: UiRect::all(Val::Px(2.0)),
+                                ..default()
+                            },
+                            ..default()
+                        });
+
+                        // Remaining time bar
+                        icon.spawn(NodeBundle {
+                            style: Style {
+                                height: Val::Px(4.0),
+                                width: Val::Px(
+                                    remaining as f32 / status_effect.duration() as f32
+                                        * STATUS_EFFECT_ICON_WIDTH,
+                                ),
+                                ..default()
+                            },
+                            background_color: Color::WHITE.into(),
+                            ..default()
+                        });
+                    });
+            }
+        });
+    }
+}
+
+fn buff_color(temp_amt: &TempAmt) -> Color {
+    if temp_amt.amt() >= 0.0 {
+        Color::linear_rgb(0.2, 0.6, 0.3)
+    } else {
+        Color::linear_rgb(0.5, 0.2, 0.6)
+    }
+}
+
+fn update_buff_icons(
+    mut commands: Commands,
+    player_query: Query<(&Health, &Stamina), With<Player>>,
+    icons_query: Query<(Entity, Option<&Children>), With<BuffIcons>>,
+    localization: Res<Localization>,
+) {
+    let Ok((health, stamina)) = player_query.get_single() else {
+        return;
+    };
+
+    let health_label = localization.tr("hud.buff.health_regen", &[]);
+    let stamina_label = localization.tr("hud.buff.stamina_regen", &[]);
+    let buffs: Vec<(&str, &TempAmt)> = health
+        .temp_modifiers()
+        .iter()
+        .map(|tm| (health_label.as_str(), tm))
+        .chain(
+            stamina
+                .temp_modifiers()
+                .iter()
+                .map(|tm| (stamina_label.as_str(), tm)),
+        )
+        .collect();
+
+    for (entity, children) in icons_query.iter() {
+        // Nothing to clear or redraw
+        if buffs.is_empty() && children.is_none_or(|c| c.is_empty()) {
+            continue;
+        }
+
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.despawn_descendants();
+        entity_commands.with_children(|parent| {
+            for (label, temp_amt) in buffs.iter() {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            display: Display::Flex,
+                            flex_direction: FlexDirection::Column,
+                            width: Val::Px(BUFF_ICON_WIDTH),
+                            ..default()
+                        },
+                        background_color: buff_color(temp_amt).into(),
+                        ..default()
+                    })
+                    .with_children(|icon| {
+                        icon.spawn(TextBundle {
+                            text: Text {
+                                sections: vec![TextSection::new(
+                                    format!(
+                                        "{} {}",
+                                        label,
+                                        if temp_amt.amt() >= 0.0 { "+" } else { "-" }
+                                    ),
+                                    TextStyle {
+                                        font_size: 12.0,
+                                        color: Color::WHITE,
+                                        ..default()
+                                    },
+                                )],
+                                ..default()
+                            },
+                            style: Style {
+                                margin: UiRect::all(Val::Px(2.0)),
+                                ..default()
+                            },
+                            ..default()
+                        });
+
+                        // Remaining time bar
+                        icon.spawn(NodeBundle {
+                            style: Style {
+                                height: Val::Px(4.0),
+                                width: Val::Px(
+                                    temp_amt.remaining() as f32 / temp_amt.durr().max(1) as f32
+                                        * BUFF_ICON_WIDTH,
+                                ),
+                                ..default()
+                            },
+                            background_color: Color::WHITE.into(),
+                            ..default()
+                        });
+                    });
+            }
+        });
+    }
+}
+
+// Only searches for a new nearest structure when the active chunk, world seed
+// or world structures change
+fn update_nearest_structure(
+    active_chunk: Res<State<ActiveChunk>>,
+    world_seed: Res<WorldSeed>,
+    world_structures: Res<WorldStructureRegistry>,
+    mut nearest_structure: ResMut<NearestStructure>,
+) {
+    let ActiveChunk(x, y, z) = *active_chunk.get();
+    if nearest_structure.from_chunk == Some((x, y, z))
+        && !world_seed.is_changed()
+        && !world_structures.is_changed()
+    {
+        return;
+    }
+
+    *nearest_structure = NearestStructure {
+        from_chunk: Some((x, y, z)),
+        chunk: nearest_structure_chunk(
+            world_seed.0,
+            (x, y, z),
+            STRUCTURE_COMPASS_MAX_RADIUS,
+            &world_structures,
+        ),
+    };
+}
+
+fn update_structure_compass(
+    mut compass_query: Query<&mut Visibility, With<StructureCompass>>,
+    mut needle_query: Query<&mut Transform, With<StructureCompassNeedle>>,
+    mut text_query: Query<&mut Text, With<StructureCompassText>>,
+    camera_query: Query<&GlobalTransform, With<MainCamera>>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    game_settings: Res<State<GameSettings>>,
+    nearest_structure: Res<NearestStructure>,
+    localization: Res<Localization>,
+    world_dims: Res<WorldDims>,
+) {
+    let show = game_settings.get().show_structure_compass;
+    for mut visibility in compass_query.iter_mut() {
+        *visibility = if show {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+    if !show {
+        return;
+    }
+
+    let distance_text = match (nearest_structure.from_chunk, nearest_structure.chunk) {
+        (Some((fx, _, fz)), Some((x, _, z))) => {
+            let dist = (((x - fx).pow(2) + (z - fz).pow(2)) as f32).sqrt();
+            localization.tr("hud.compass.near", &[("dist", &dist.round())])
+        }
+        _ => localization.tr(
+            "hud.compass.far",
+            &[("dist", &STRUCTURE_COMPASS_MAX_RADIUS)],
+        ),
+    };
+    for mut text in text_query.iter_mut() {
+        for section in text.sections.iter_mut() {
+            section.value = distance_text.clone();
+        }
+    }
+
+    let (Some((x, _, z)), Ok(camera_gt), Ok(player_gt)) = (
+        nearest_structure.chunk,
+        camera_query.get_single(),
+        player_query.get_single(),
+    ) else {
+        return;
+    };
+
+    for mut transform in needle_query.iter_mut() {
+        let chunk_size = world_dims.chunk_size();
+        let target = Vec3::new(x as f32 * chunk_size, 0.0, z as f32 * chunk_size);
+        let diff = (target - player_gt.translation()).with_y(0.0);
+        let forward = camera_gt.forward().with_y(0.0);
+        let right = camera_gt.right().with_y(0.0);
+
+        // Positive rotations on the z axis are clockwise in UI space, since y points down
+        let angle = diff.dot(right).atan2(diff.dot(forward));
+        transform.rotation = Quat::from_rotation_z(angle);
+    }
+}
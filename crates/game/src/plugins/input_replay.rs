@@ -0,0 +1,189 @@
+use bevy::{
+    app::AppExit,
+    input::{mouse::MouseMotion, InputSystem},
+    prelude::*,
+    time::TimeUpdateStrategy,
+};
+use dungeon_maze_common::{
+    app_state::GameLoaded,
+    debug::{InputFrame, InputRecording, RECORDED_KEYS, RECORDED_MOUSE_BUTTONS},
+    world::WorldSeed,
+};
+use std::path::PathBuf;
+
+// Records every frame of input once the game has loaded, or plays a recording back in its place.
+// Replaying in the same world with the same frame times moves the player exactly as before,
+// for reproducing bugs that depend on exact movement.
+pub enum InputReplayPlugin {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+impl Plugin for InputReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MouseMotion>();
+
+        match self {
+            Self::Record(path) => {
+                app.insert_resource(InputRecorder {
+                    path: path.clone(),
+                    recording: None,
+                })
+                .add_systems(OnEnter(GameLoaded), start_input_recording)
+                .add_systems(
+                    PreUpdate,
+                    record_input_frame
+                        .after(InputSystem)
+                        .run_if(in_state(GameLoaded)),
+                )
+                .add_systems(Last, write_input_recording.run_if(on_event::<AppExit>()));
+            }
+            Self::Replay(path) => {
+                let recording = InputRecording::read(path).unwrap_or_else(|err| {
+                    panic!("reading input recording {}: {}", path.display(), err)
+                });
+
+                app.insert_resource(InputPlayback {
+                    recording,
+                    frame: 0,
+                })
+                .add_systems(OnEnter(GameLoaded), start_input_playback)
+                .add_systems(
+                    PreUpdate,
+                    play_input_frame
+                        .after(InputSystem)
+                        .run_if(in_state(GameLoaded)),
+                );
+            }
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct InputRecorder {
+    path: PathBuf,
+    recording: Option<InputRecording>,
+}
+
+#[derive(Resource)]
+pub struct InputPlayback {
+    recording: InputRecording,
+    frame: usize,
+}
+
+impl InputPlayback {
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.recording.frames.len()
+    }
+}
+
+fn start_input_recording(mut recorder: ResMut<InputRecorder>, world_seed: Res<WorldSeed>) {
+    recorder.recording = Some(InputRecording::new(world_seed.0));
+}
+
+fn record_input_frame(
+    mut event_reader: EventReader<MouseMotion>,
+    mut recorder: ResMut<InputRecorder>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    time: Res<Time>,
+) {
+    let mouse_delta = event_reader.read().map(|event| event.delta).sum();
+    if let Some(recording) = recorder.recording.as_mut() {
+        recording.frames.push(InputFrame::new(
+            &keys,
+            &mouse_buttons,
+            mouse_delta,
+            time.delta(),
+        ));
+    }
+}
+
+fn write_input_recording(recorder: Res<InputRecorder>) {
+    let Some(recording) = recorder.recording.as_ref() else {
+        return;
+    };
+
+    match recording.write(&recorder.path) {
+        Ok(()) => info!(
+            "Wrote {} frames of input to {}",
+            recording.frames.len(),
+            recorder.path.display()
+        ),
+        Err(err) => error!(
+            "Failed to write input recording to {}: {}",
+            recorder.path.display(),
+            err
+        ),
+    }
+}
+
+// Frame times are set one frame ahead, since time is updated before any input is played
+fn start_input_playback(
+    mut time_update_strategy: ResMut<TimeUpdateStrategy>,
+    playback: Res<InputPlayback>,
+    world_seed: Res<WorldSeed>,
+) {
+    if world_seed.0 != playback.recording.world_seed {
+        warn!(
+            "Replaying input recorded with seed {} in a world with seed {}, which will not match",
+            playback.recording.world_seed, world_seed.0
+        );
+    }
+
+    if let Some(frame) = playback.recording.frames.first() {
+        *time_update_strategy = manual_duration(frame);
+    }
+}
+
+fn play_input_frame(
+    mut event_writer: EventWriter<MouseMotion>,
+    mut playback: ResMut<InputPlayback>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut mouse_buttons: ResMut<ButtonInput<MouseButton>>,
+    mut time_update_strategy: ResMut<TimeUpdateStrategy>,
+) {
+    let Some(frame) = playback.recording.frames.get(playback.frame) else {
+        return;
+    };
+
+    for (i, key) in RECORDED_KEYS.iter().enumerate() {
+        set_pressed(&mut keys, *key, frame.is_key_pressed(i));
+    }
+    for (i, button) in RECORDED_MOUSE_BUTTONS.iter().enumerate() {
+        set_pressed(
+            &mut mouse_buttons,
+            *button,
+            frame.is_mouse_button_pressed(i),
+        );
+    }
+
+    let mouse_delta = frame.mouse_delta();
+    if mouse_delta != Vec2::ZERO {
+        event_writer.send(MouseMotion { delta: mouse_delta });
+    }
+
+    playback.frame += 1;
+    *time_update_strategy = match playback.recording.frames.get(playback.frame) {
+        Some(next_frame) => manual_duration(next_frame),
+        None => {
+            info!("Input replay finished");
+            TimeUpdateStrategy::Automatic
+        }
+    };
+}
+
+fn set_pressed<T>(input: &mut ButtonInput<T>, button: T, pressed: bool)
+where
+    T: Copy + Eq + std::hash::Hash + Send + Sync + 'static,
+{
+    if pressed && !input.pressed(button) {
+        input.press(button);
+    } else if !pressed && input.pressed(button) {
+        input.release(button);
+    }
+}
+
+fn manual_duration(frame: &InputFrame) -> TimeUpdateStrategy {
+    TimeUpdateStrategy::ManualDuration(frame.delta())
+}
@@ -0,0 +1,142 @@
+use crate::plugins::input_replay::{InputPlayback, InputReplayPlugin};
+use bevy::{
+    app::AppExit, input::mouse::MouseMotion, prelude::*, state::app::StatesPlugin,
+    time::TimeUpdateStrategy,
+};
+use dungeon_maze_common::{
+    app_state::{AppState, GameLoaded},
+    debug::{InputRecording, INPUT_RECORDING_VERSION},
+    player::Player,
+    world::WorldSeed,
+};
+use std::{env, fs, path::PathBuf, time::Duration};
+
+// Stands in for the player's movement, reacting to held keys, buttons and mouse motion
+fn move_test_player(
+    mut event_reader: EventReader<MouseMotion>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    time: Res<Time>,
+) {
+    let turn: f32 = event_reader.read().map(|event| event.delta.x).sum();
+    let delta_secs = time.delta_seconds();
+
+    for mut transform in player_query.iter_mut() {
+        transform.rotate_y(turn * 0.01);
+        if keys.pressed(KeyCode::KeyW) {
+            let forward = transform.forward();
+            transform.translation += forward * 3.0 * delta_secs;
+        }
+        if keys.pressed(KeyCode::KeyD) {
+            let right = transform.right();
+            transform.translation += right * 2.0 * delta_secs;
+        }
+        if mouse_buttons.pressed(MouseButton::Left) {
+            transform.translation.y += delta_secs;
+        }
+    }
+}
+
+fn new_test_app(plugin: InputReplayPlugin) -> App {
+    let mut app = App::new();
+
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .init_resource::<ButtonInput<KeyCode>>()
+        .init_resource::<ButtonInput<MouseButton>>()
+        .init_resource::<WorldSeed>()
+        .insert_state(AppState::InGame)
+        .add_computed_state::<GameLoaded>()
+        .add_plugins(plugin)
+        .add_systems(Update, move_test_player);
+
+    app.world_mut().spawn((Player, Transform::default()));
+    app
+}
+
+fn player_transform(app: &mut App) -> Transform {
+    let world = app.world_mut();
+    *world
+        .query_filtered::<&Transform, With<Player>>()
+        .single(world)
+}
+
+fn recording_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("dungeon_maze_{}_{}.json", name, std::process::id()))
+}
+
+#[test]
+fn test_replayed_input_moves_player_the_same() {
+    let path = recording_path("replay");
+
+    let mut record_app = new_test_app(InputReplayPlugin::Record(path.clone()));
+    record_app.update();
+
+    let frames: [(&[KeyCode], bool, f32, u64); 6] = [
+        (&[KeyCode::KeyW], false, 0.0, 16),
+        (&[KeyCode::KeyW, KeyCode::KeyD], false, 12.0, 17),
+        (&[KeyCode::KeyD], true, 0.0, 33),
+        (&[], true, -30.0, 16),
+        (&[KeyCode::KeyW], false, 5.0, 20),
+        (&[], false, 0.0, 16),
+    ];
+    for (pressed, mouse_pressed, turn, millis) in frames {
+        let world = record_app.world_mut();
+        let mut keys = world.resource_mut::<ButtonInput<KeyCode>>();
+        keys.release_all();
+        for key in pressed {
+            keys.press(*key);
+        }
+
+        let mut mouse_buttons = world.resource_mut::<ButtonInput<MouseButton>>();
+        if mouse_pressed {
+            mouse_buttons.press(MouseButton::Left);
+        } else {
+            mouse_buttons.release(MouseButton::Left);
+        }
+
+        if turn != 0.0 {
+            world.send_event(MouseMotion {
+                delta: Vec2::new(turn, 0.0),
+            });
+        }
+        world.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            millis,
+        )));
+        record_app.update();
+    }
+
+    // The recording is written once the app exits
+    record_app.world_mut().send_event(AppExit::Success);
+    record_app.update();
+    let recorded_transform = player_transform(&mut record_app);
+
+    let recording = InputRecording::read(&path).unwrap();
+    assert_eq!(recording.version, INPUT_RECORDING_VERSION);
+    // The update that loads the game and the update that exits it are recorded as well
+    assert_eq!(recording.frames.len(), frames.len() + 2);
+
+    let mut replay_app = new_test_app(InputReplayPlugin::Replay(path.clone()));
+    for _ in 0..recording.frames.len() + 1 {
+        replay_app.update();
+    }
+
+    assert!(replay_app.world().resource::<InputPlayback>().is_finished());
+    assert_ne!(recorded_transform, Transform::default());
+    assert_eq!(player_transform(&mut replay_app), recorded_transform);
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_input_recording_rejects_other_versions() {
+    let path = recording_path("version");
+
+    let mut recording = InputRecording::new(7);
+    recording.version = INPUT_RECORDING_VERSION + 1;
+    recording.write(&path).unwrap();
+
+    assert!(InputRecording::read(&path).is_err());
+
+    fs::remove_file(path).unwrap();
+}
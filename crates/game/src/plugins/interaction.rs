@@ -1,65 +1,293 @@
 use bevy::prelude::*;
-use dungeon_maze_common::{interaction::*, player::Player};
+use bevy_rapier3d::{pipeline::QueryFilter, plugin::RapierContext};
+use dungeon_maze_common::{
+    app_state::AppState,
+    camera::MainCamera,
+    interaction::*,
+    player::{Player, PlayerState},
+    should_not_happen_once,
+    utils::debug::WarnOnce,
+    world::{Locked, WorldDims},
+};
+use std::{cmp::Ordering, collections::HashSet};
+
+const HIGHLIGHT_EMISSIVE: LinearRgba = LinearRgba::rgb(0.6, 0.5, 0.2);
+// Height above the center of the player that line of sight to interactables is checked from
+const PLAYER_EYE_HEIGHT: f32 = 0.6;
+// Angle in radians from the best scored interactable, within which other interactables
+// are too close to tell apart by looking, so they can be cycled through instead
+const SIMILAR_SCORE_ANGLE: f32 = 0.2;
+const CYCLE_INTERACTION_KEY: KeyCode = KeyCode::KeyR;
+// Frames after interacting during which attacks cannot start charging up
+const INTERACTION_ATTACK_SUPPRESS_FRAMES: u32 = 10;
 
 pub struct InteractionPlugin;
 
 impl Plugin for InteractionPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<PendingInteractionExecuted>()
+            .add_event::<LockedInteraction>()
             .init_state::<PendingInteraction>()
+            .init_resource::<InteractionCandidates>()
+            .init_resource::<InputIntent>()
+            .add_systems(First, tick_input_intent)
             .add_systems(
                 Update,
-                (update_pending_interaction, execute_pending_interaction),
+                (
+                    (update_pending_interaction, cycle_pending_interaction).chain(),
+                    execute_pending_interaction,
+                    highlight_pending_interactable,
+                )
+                    .run_if(in_state(AppState::InGame)),
             );
     }
 }
 
+// How far off an interactable is from where the player is looking, as the angle in radians
+// between the look ray and the direction to the interactable, and the distance to it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InteractionScore {
+    pub angle: f32,
+    pub dist: f32,
+}
+
+impl InteractionScore {
+    // Lower angles come first, with the distance only breaking ties
+    pub fn compare(&self, other: &Self) -> Ordering {
+        self.angle
+            .total_cmp(&other.angle)
+            .then(self.dist.total_cmp(&other.dist))
+    }
+}
+
+pub fn score_interactable(origin: Vec3, forward: Vec3, target: Vec3) -> InteractionScore {
+    let diff = target - origin;
+    InteractionScore {
+        angle: if diff == Vec3::ZERO {
+            0.0
+        } else {
+            forward.angle_between(diff)
+        },
+        dist: diff.length(),
+    }
+}
+
 fn update_pending_interaction(
     interactables_query: Query<(Entity, &Interactable, &GlobalTransform)>,
-    player_query: Query<&GlobalTransform, With<Player>>,
+    player_query: Query<(Entity, &GlobalTransform), With<Player>>,
+    camera_query: Query<&GlobalTransform, (With<MainCamera>, Without<Player>)>,
+    parent_query: Query<&Parent>,
+    rapier_context: Res<RapierContext>,
+    world_dims: Res<WorldDims>,
     pending_interaction: Res<State<PendingInteraction>>,
     mut next_pending_interaction: ResMut<NextState<PendingInteraction>>,
+    mut candidates: ResMut<InteractionCandidates>,
+    mut warn_once: Local<WarnOnce>,
 ) {
-    let player_gl_transform = player_query.get_single().expect("Error retrieving player");
+    let (player_entity, player_gl_transform) = match player_query.get_single() {
+        Ok(player) => player,
+        Err(err) => {
+            should_not_happen_once!(warn_once, "retrieving player: {}", err);
+            return;
+        }
+    };
     let curr_entity = pending_interaction.get().0;
+    let eye = player_gl_transform.translation() + Vec3::Y * PLAYER_EYE_HEIGHT;
 
-    // Check if player is in range of any interactables
-    let mut closest_entity: Option<(Entity, f32)> = None;
+    // Interactables are scored along the camera's forward ray, so that looking down at an item
+    // on the floor picks it over a chest at eye level. Without a camera, every interactable is
+    // looked at equally and the closest one is picked.
+    let look_ray = camera_query.get_single().ok().map(|camera_gl_transform| {
+        (
+            camera_gl_transform.translation(),
+            camera_gl_transform.forward(),
+        )
+    });
 
-    for (entity, interactable, ibl_gl_transform) in interactables_query.iter() {
-        let dist = player_gl_transform
-            .translation()
-            .distance(ibl_gl_transform.translation());
+    let mut scored: Vec<(Entity, InteractionScore)> = interactables_query
+        .iter()
+        .filter(|(entity, interactable, ibl_gl_transform)| {
+            player_gl_transform
+                .translation()
+                .distance(ibl_gl_transform.translation())
+                <= interactable.range(&world_dims)
+                && in_line_of_sight(
+                    eye,
+                    *entity,
+                    ibl_gl_transform.translation(),
+                    player_entity,
+                    &parent_query,
+                    &rapier_context,
+                )
+        })
+        .map(|(entity, _, ibl_gl_transform)| {
+            let translation = ibl_gl_transform.translation();
+            let score = match look_ray {
+                Some((origin, forward)) => score_interactable(origin, *forward, translation),
+                None => InteractionScore {
+                    angle: 0.0,
+                    dist: player_gl_transform.translation().distance(translation),
+                },
+            };
+            (entity, score)
+        })
+        .collect();
+    scored.sort_by(|(_, a), (_, b)| a.compare(b));
 
-        if dist <= interactable.range
-            && (closest_entity.is_none() || dist < closest_entity.unwrap().1)
-        {
-            closest_entity = Some((entity, dist));
-        }
+    let similar = scored.first().map_or(0, |(_, best)| {
+        scored
+            .iter()
+            .take_while(|(_, score)| score.angle - best.angle <= SIMILAR_SCORE_ANGLE)
+            .count()
+    });
+    let entities: Vec<Entity> = scored.into_iter().map(|(entity, _)| entity).collect();
+
+    // Stay on an interactable that was cycled to, for as long as it can still be cycled to
+    let selected = curr_entity
+        .and_then(|curr| entities[..similar].iter().position(|e| *e == curr))
+        .unwrap_or(0);
+
+    match entities.get(selected) {
+        Some(entity) => next_pending_interaction.set(PendingInteraction(Some(*entity))),
+        // Change back to none if no interactables in range
+        None if curr_entity.is_some() => next_pending_interaction.set(PendingInteraction(None)),
+        None => {}
     }
 
-    if let Some((entity, _)) = closest_entity {
-        next_pending_interaction.set(PendingInteraction(Some(entity)));
+    *candidates = InteractionCandidates {
+        entities,
+        similar,
+        selected,
+    };
+}
 
+fn cycle_pending_interaction(
+    mut next_pending_interaction: ResMut<NextState<PendingInteraction>>,
+    mut candidates: ResMut<InteractionCandidates>,
+    keys: Res<ButtonInput<KeyCode>>,
+) {
+    if !keys.just_pressed(CYCLE_INTERACTION_KEY) || candidates.similar < 2 {
         return;
     }
 
-    // Change back to none if no interactables in range
-    if curr_entity.is_some() {
-        next_pending_interaction.set(PendingInteraction(None));
+    candidates.selected = (candidates.selected + 1) % candidates.similar;
+    let entity = candidates.entities[candidates.selected];
+    next_pending_interaction.set(PendingInteraction(Some(entity)));
+}
+
+// Whether nothing solid is between the player's eyes and an interactable. Colliders of the
+// interactable's ancestors (a chest holding an item) or descendants (an item inside of a chest)
+// do not block it, since they are part of the same thing.
+fn in_line_of_sight(
+    eye: Vec3,
+    entity: Entity,
+    translation: Vec3,
+    player_entity: Entity,
+    parent_query: &Query<&Parent>,
+    rapier_context: &RapierContext,
+) -> bool {
+    let dist = eye.distance(translation);
+    if dist == 0.0 {
+        return true;
     }
+
+    let Some((hit_entity, _)) = rapier_context.cast_ray(
+        eye,
+        (translation - eye) / dist,
+        dist,
+        true,
+        QueryFilter::new()
+            .exclude_sensors()
+            .exclude_collider(player_entity),
+    ) else {
+        return true;
+    };
+
+    hit_entity == entity
+        || parent_query
+            .iter_ancestors(entity)
+            .any(|ancestor| ancestor == hit_entity)
+        || parent_query
+            .iter_ancestors(hit_entity)
+            .any(|ancestor| ancestor == entity)
 }
 
-fn execute_pending_interaction(
+fn tick_input_intent(mut input_intent: ResMut<InputIntent>) {
+    input_intent.tick();
+}
+
+pub fn execute_pending_interaction(
     mut event_writer: EventWriter<PendingInteractionExecuted>,
+    mut locked_event_writer: EventWriter<LockedInteraction>,
+    locked_query: Query<(), With<Locked>>,
     pending_interaction: Res<State<PendingInteraction>>,
+    player_state: Res<State<PlayerState>>,
+    mut input_intent: ResMut<InputIntent>,
     keys: Res<ButtonInput<KeyCode>>,
 ) {
     if !keys.just_pressed(KeyCode::KeyE) {
         return;
     }
 
+    // Chests and doors can't be opened mid swing
+    if !player_state.get().is_ground_movement() || !input_intent.can_interact() {
+        return;
+    }
+
     if let Some(entity) = pending_interaction.get().0 {
-        event_writer.send(PendingInteractionExecuted(entity));
+        // Whatever handles the lock sends PendingInteractionExecuted itself, once it is unlocked
+        if locked_query.contains(entity) {
+            locked_event_writer.send(LockedInteraction(entity));
+        } else {
+            event_writer.send(PendingInteractionExecuted(entity));
+        }
+        input_intent.claim_interaction(INTERACTION_ATTACK_SUPPRESS_FRAMES);
+    }
+}
+
+// Runs every frame rather than only when the pending interactable changes,
+// because scenes (doors, windows, etc.) spawn their meshes a few frames after the entity itself
+fn highlight_pending_interactable(
+    mut commands: Commands,
+    mut highlighted_query: Query<(Entity, &HighlightedMaterial, &mut Handle<StandardMaterial>)>,
+    mut material_query: Query<&mut Handle<StandardMaterial>, Without<HighlightedMaterial>>,
+    children_query: Query<&Children>,
+    pending_interaction: Res<State<PendingInteraction>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let targeted: HashSet<Entity> = match pending_interaction.get().0 {
+        // If the pending interactable was despawned, it has no descendants left to highlight
+        Some(entity) => std::iter::once(entity)
+            .chain(children_query.iter_descendants(entity))
+            .collect(),
+        None => HashSet::new(),
+    };
+
+    // Restore meshes that are no longer targeted
+    for (entity, highlighted_material, mut handle) in highlighted_query.iter_mut() {
+        if !targeted.contains(&entity) {
+            *handle = highlighted_material.0.clone();
+            commands.entity(entity).remove::<HighlightedMaterial>();
+        }
+    }
+
+    for entity in targeted {
+        let Ok(mut handle) = material_query.get_mut(entity) else {
+            continue;
+        };
+        let Some(material) = materials.get(handle.id()) else {
+            continue;
+        };
+
+        // Materials loaded from scenes are shared between entities, so highlight a copy
+        let highlight_material = StandardMaterial {
+            emissive: material.emissive + HIGHLIGHT_EMISSIVE,
+            ..material.clone()
+        };
+
+        commands
+            .entity(entity)
+            .insert(HighlightedMaterial(handle.clone()));
+        *handle = materials.add(highlight_material);
     }
 }
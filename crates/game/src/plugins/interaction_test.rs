@@ -0,0 +1,370 @@
+use crate::plugins::{
+    interaction::{execute_pending_interaction, score_interactable, InteractionPlugin},
+    player::charge_up_and_release_attack,
+};
+use bevy::{prelude::*, scene::ScenePlugin, state::app::StatesPlugin};
+use bevy_rapier3d::prelude::*;
+use dungeon_maze_common::{
+    app_state::AppState,
+    interaction::{
+        InputClaim, InputIntent, Interactable, InteractableKind, InteractionCandidates,
+        LockedInteraction, PendingInteraction, PendingInteractionExecuted,
+    },
+    inventory::{equipment::EquipmentStats, Inventory},
+    player::{
+        attack::{
+            AttackChargeCancelled, AttackChargeUp, AttackHand, AttackType, BufferedAttack,
+            WeaponSwapCooldown,
+        },
+        Player, PlayerState,
+    },
+    world::{Locked, WorldDims, DEFAULT_CELL_SIZE, DEFAULT_CHUNK_SIZE},
+};
+
+fn new_test_app() -> App {
+    let mut app = App::new();
+
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin {
+            watch_for_changes_override: Some(false),
+            ..default()
+        },
+        ScenePlugin,
+        TransformPlugin,
+        HierarchyPlugin,
+        StatesPlugin,
+        RapierPhysicsPlugin::<NoUserData>::default(),
+        InteractionPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<StandardMaterial>()
+    .init_resource::<ButtonInput<KeyCode>>()
+    .init_resource::<ButtonInput<MouseButton>>()
+    .init_resource::<Inventory>()
+    .init_resource::<EquipmentStats>()
+    .init_resource::<WorldDims>()
+    .insert_resource(AttackChargeUp::new(10, 15, 6))
+    .insert_resource(WeaponSwapCooldown::new(0))
+    .init_resource::<BufferedAttack>()
+    .init_state::<PlayerState>()
+    .insert_state(AppState::InGame)
+    .add_event::<AttackChargeCancelled>()
+    .add_systems(
+        Update,
+        charge_up_and_release_attack.after(execute_pending_interaction),
+    );
+
+    app.world_mut().spawn((
+        Player,
+        Collider::cuboid(0.4, 0.85, 0.4),
+        TransformBundle::default(),
+    ));
+
+    app
+}
+
+fn spawn_chest(app: &mut App, translation: Vec3) -> Entity {
+    app.world_mut()
+        .spawn((
+            Interactable::new(InteractableKind::Container),
+            Collider::cuboid(0.3, 0.3, 0.3),
+            TransformBundle::from_transform(Transform::from_translation(translation)),
+        ))
+        .id()
+}
+
+fn spawn_wall(app: &mut App, translation: Vec3) {
+    app.world_mut().spawn((
+        Collider::cuboid(0.05, 2.0, 2.0),
+        TransformBundle::from_transform(Transform::from_translation(translation)),
+    ));
+}
+
+fn press_interact(app: &mut App) -> Vec<Entity> {
+    // Gives physics a few frames to pick up new colliders, and the pending interaction to settle
+    for _ in 0..3 {
+        app.update();
+    }
+
+    interact(app)
+}
+
+fn interact(app: &mut App) -> Vec<Entity> {
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::KeyE);
+    app.update();
+
+    let events = app.world().resource::<Events<PendingInteractionExecuted>>();
+    events.get_reader().read(events).map(|e| e.0).collect()
+}
+
+fn executed_interactions(app: &App) -> usize {
+    let events = app.world().resource::<Events<PendingInteractionExecuted>>();
+    events.get_reader().read(events).count()
+}
+
+fn is_charging(app: &App) -> bool {
+    app.world().resource::<AttackChargeUp>().is_charging()
+}
+
+fn pending_interaction(app: &App) -> Option<Entity> {
+    app.world().resource::<State<PendingInteraction>>().get().0
+}
+
+#[test]
+fn test_interaction_in_line_of_sight() {
+    let mut app = new_test_app();
+    let chest = spawn_chest(&mut app, Vec3::new(1.5, 0.0, 0.0));
+
+    assert_eq!(press_interact(&mut app), vec![chest]);
+    assert_eq!(pending_interaction(&app), Some(chest));
+}
+
+#[test]
+fn test_locked_interactable_is_not_executed() {
+    let mut app = new_test_app();
+    let chest = spawn_chest(&mut app, Vec3::new(1.5, 0.0, 0.0));
+    app.world_mut().entity_mut(chest).insert(Locked { tier: 1 });
+
+    // The chest is still picked, it just does not open without a key
+    assert!(press_interact(&mut app).is_empty());
+    assert_eq!(pending_interaction(&app), Some(chest));
+    let events = app.world().resource::<Events<LockedInteraction>>();
+    let locked = events
+        .get_reader()
+        .read(events)
+        .map(|e| e.0)
+        .collect::<Vec<_>>();
+    assert_eq!(locked, vec![chest]);
+}
+
+#[test]
+fn test_no_interaction_through_wall() {
+    let mut app = new_test_app();
+    spawn_chest(&mut app, Vec3::new(1.5, 0.0, 0.0));
+    spawn_wall(&mut app, Vec3::new(0.8, 0.0, 0.0));
+
+    assert!(press_interact(&mut app).is_empty());
+    assert_eq!(pending_interaction(&app), None);
+}
+
+#[test]
+fn test_item_inside_chest_is_not_blocked_by_chest() {
+    let mut app = new_test_app();
+    // Close enough for the item inside to be within item reach
+    let chest = spawn_chest(&mut app, Vec3::new(1.6, 0.0, 0.0));
+    app.world_mut().entity_mut(chest).remove::<Interactable>();
+
+    let item = app
+        .world_mut()
+        .spawn((
+            Interactable::new(InteractableKind::Item),
+            Collider::cuboid(0.1, 0.1, 0.1),
+            TransformBundle::from_transform(Transform::from_xyz(0.0, 0.1, 0.0)),
+        ))
+        .set_parent(chest)
+        .id();
+
+    assert_eq!(press_interact(&mut app), vec![item]);
+}
+
+#[test]
+fn test_interactable_range_scales_with_cell_size() {
+    let kinds = [
+        InteractableKind::Item,
+        InteractableKind::Container,
+        InteractableKind::Door,
+        InteractableKind::Window,
+    ];
+    for kind in kinds {
+        assert_eq!(
+            kind.range(DEFAULT_CELL_SIZE),
+            kind.base_range(),
+            "{:?}",
+            kind
+        );
+        assert_eq!(
+            kind.range(DEFAULT_CELL_SIZE * 2.0),
+            kind.base_range() * 2.0,
+            "{:?}",
+            kind
+        );
+        assert_eq!(
+            kind.range(DEFAULT_CELL_SIZE / 2.0),
+            kind.base_range() / 2.0,
+            "{:?}",
+            kind
+        );
+    }
+    assert!(InteractableKind::Item.base_range() < InteractableKind::Container.base_range());
+
+    // Raw ranges are left as they are
+    let raw = Interactable::with_range(3.0);
+    assert_eq!(raw.kind.base_range(), 3.0);
+    assert_eq!(raw.kind.range(DEFAULT_CELL_SIZE * 2.0), 3.0);
+}
+
+#[test]
+fn test_interaction_reach_follows_world_dims() {
+    let mut app = new_test_app();
+    let chest = spawn_chest(&mut app, Vec3::new(3.0, 0.0, 0.0));
+    for _ in 0..3 {
+        app.update();
+    }
+    assert_eq!(pending_interaction(&app), None);
+
+    // Twice the cell size, twice the reach
+    app.insert_resource(WorldDims::new(DEFAULT_CHUNK_SIZE * 2.0, DEFAULT_CELL_SIZE * 2.0).unwrap());
+    for _ in 0..3 {
+        app.update();
+    }
+    assert_eq!(pending_interaction(&app), Some(chest));
+}
+
+#[test]
+fn test_score_prefers_what_is_looked_at() {
+    let camera = Transform::from_xyz(0.0, 1.6, 0.0);
+    let floor_item = Vec3::new(0.0, 0.0, -1.0);
+    let chest = Vec3::new(0.0, 1.4, -2.0);
+
+    // Looking straight ahead picks the chest at eye level, even though the item is closer
+    let ahead = camera.looking_to(Vec3::NEG_Z, Vec3::Y);
+    let item_score = score_interactable(ahead.translation, *ahead.forward(), floor_item);
+    let chest_score = score_interactable(ahead.translation, *ahead.forward(), chest);
+    assert!(item_score.dist < chest_score.dist);
+    assert!(chest_score.compare(&item_score).is_lt());
+
+    // Looking down at the floor picks the item
+    let down = camera.looking_at(floor_item, Vec3::Y);
+    let item_score = score_interactable(down.translation, *down.forward(), floor_item);
+    let chest_score = score_interactable(down.translation, *down.forward(), chest);
+    assert!(item_score.angle.abs() < 1e-3);
+    assert!(item_score.compare(&chest_score).is_lt());
+}
+
+#[test]
+fn test_score_breaks_ties_by_distance() {
+    let near = score_interactable(Vec3::ZERO, Vec3::NEG_Z, Vec3::new(0.0, 0.0, -1.0));
+    let far = score_interactable(Vec3::ZERO, Vec3::NEG_Z, Vec3::new(0.0, 0.0, -3.0));
+
+    assert_eq!(near.angle, far.angle);
+    assert!(near.compare(&far).is_lt());
+    assert!(far.compare(&near).is_gt());
+}
+
+#[test]
+fn test_cycle_through_similar_candidates() {
+    let mut app = new_test_app();
+    let near_chest = spawn_chest(&mut app, Vec3::new(1.2, 0.0, 0.0));
+    let far_chest = spawn_chest(&mut app, Vec3::new(-1.5, 0.0, 0.0));
+
+    for _ in 0..3 {
+        app.update();
+    }
+
+    // Without a camera every candidate is looked at equally, so the closest one is pending
+    assert_eq!(pending_interaction(&app), Some(near_chest));
+    let candidates = app.world().resource::<InteractionCandidates>();
+    assert_eq!(candidates.entities, vec![near_chest, far_chest]);
+    assert_eq!(candidates.similar, 2);
+    assert_eq!(candidates.selected, 0);
+
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::KeyR);
+    app.update();
+    // There is no input plugin to clear the press at the end of the frame
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .clear_just_pressed(KeyCode::KeyR);
+    app.update();
+    app.update();
+
+    // The cycled to candidate stays pending
+    assert_eq!(pending_interaction(&app), Some(far_chest));
+    assert_eq!(app.world().resource::<InteractionCandidates>().selected, 1);
+}
+
+#[test]
+fn test_interact_click_does_not_start_charge() {
+    let mut app = new_test_app();
+    let chest = spawn_chest(&mut app, Vec3::new(1.5, 0.0, 0.0));
+
+    for _ in 0..3 {
+        app.update();
+    }
+
+    // The same frame's click goes to the chest, not to a left hand attack
+    app.world_mut()
+        .resource_mut::<ButtonInput<MouseButton>>()
+        .press(MouseButton::Left);
+    assert_eq!(interact(&mut app), vec![chest]);
+    assert!(!is_charging(&app));
+    assert_eq!(
+        app.world().resource::<InputIntent>().claim(),
+        Some(InputClaim::Interaction)
+    );
+
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .reset_all();
+
+    // A click right after interacting is still meant for the chest
+    let mut mouse = app.world_mut().resource_mut::<ButtonInput<MouseButton>>();
+    mouse.reset_all();
+    mouse.press(MouseButton::Left);
+    app.update();
+    assert!(!is_charging(&app));
+
+    // Then attacks work again
+    for _ in 0..10 {
+        app.world_mut()
+            .resource_mut::<ButtonInput<MouseButton>>()
+            .reset_all();
+        app.update();
+    }
+    app.world_mut()
+        .resource_mut::<ButtonInput<MouseButton>>()
+        .press(MouseButton::Left);
+    app.update();
+    assert!(is_charging(&app));
+    assert_eq!(
+        app.world().resource::<InputIntent>().claim(),
+        Some(InputClaim::Attack)
+    );
+}
+
+#[test]
+fn test_attack_state_blocks_interaction() {
+    let mut app = new_test_app();
+    spawn_chest(&mut app, Vec3::new(1.5, 0.0, 0.0));
+
+    app.world_mut()
+        .resource_mut::<NextState<PlayerState>>()
+        .set(PlayerState::Attacking(AttackType::Light, AttackHand::Left));
+    assert!(press_interact(&mut app).is_empty());
+
+    // Charging up an attack holds onto the input too
+    app.world_mut()
+        .resource_mut::<NextState<PlayerState>>()
+        .set(PlayerState::Walking);
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .reset_all();
+    app.world_mut()
+        .resource_mut::<ButtonInput<MouseButton>>()
+        .press(MouseButton::Left);
+    app.update();
+    app.world_mut()
+        .resource_mut::<ButtonInput<MouseButton>>()
+        .clear();
+    assert!(is_charging(&app));
+
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::KeyE);
+    app.update();
+    assert_eq!(executed_interactions(&app), 0);
+}
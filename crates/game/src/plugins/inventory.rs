@@ -1,123 +1,276 @@
-use crate::plugins::world::{CELL_SIZE, CHUNK_SIZE};
-use bevy::{prelude::*, ui::RelativeCursorPosition};
+use bevy::{ecs::system::SystemParam, prelude::*};
 use bevy_text_popup::{TextPopupEvent, TextPopupLocation, TextPopupTimeout};
 use dungeon_maze_common::{
-    interaction::{Interactable, PendingInteractionExecuted},
+    app_state::AppState,
+    interaction::{Interactable, LockedInteraction, PendingInteractionExecuted},
     inventory::{
-        item::Item, Inventory, InventoryChanged, ItemRemovedFromOCItemContainer, ItemUsed,
-        PlayerDroppedItem,
+        equipment::Equipment, item::Item, DroppedItemPickedUp, Inventory, InventoryChanged,
+        ItemPickedUp, ItemRemovedFromOCItemContainer, ItemUsed, PlayerDroppedItem,
     },
-    menu::{DragState, Dragging, Menu},
+    localization::Localization,
+    menu::{DraggedItemDropped, Dragging},
+    player::Player,
+    settings::GameSettings,
     utils::entity::get_n_parent,
-    world::{ChunkCellMarker, OCItemContainer},
+    world::{
+        ChunkCellMarker, ContainerUnlocked, DroppedItem, Locked, OCItemContainer, PickUpGrace,
+        WorldDims,
+    },
 };
 
+// Distance from the player that items on the ground are automatically picked up within
+const AUTO_PICK_UP_RADIUS: f32 = 1.2;
+// Keeps a pile of items from flooding the screen with popups all at once
+const AUTO_PICK_UP_MAX_PER_FRAME: usize = 2;
+
 pub struct InventoryPlugin;
 
 impl Plugin for InventoryPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<Inventory>()
+        app.register_type::<Inventory>()
+            .register_type::<Item>()
+            .register_type::<Equipment>()
+            .init_resource::<Inventory>()
             .add_event::<InventoryChanged>()
             .add_event::<ItemUsed>()
             .add_event::<PlayerDroppedItem>()
             .add_event::<ItemRemovedFromOCItemContainer>()
-            .add_systems(Update, (pick_up_items, drop_dragged_item));
+            .add_event::<DroppedItemPickedUp>()
+            .add_event::<ItemPickedUp>()
+            .add_event::<DraggedItemDropped>()
+            .add_systems(
+                Update,
+                (
+                    unlock_locked_containers,
+                    pick_up_items,
+                    auto_pick_up_items,
+                    drop_dragged_item,
+                )
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+// Everything needed to move an item from the world into the inventory,
+// shared by picking items up through interactions and automatically
+#[derive(SystemParam)]
+pub struct ItemPickUp<'w, 's> {
+    commands: Commands<'w, 's>,
+    inv_event_writer: EventWriter<'w, InventoryChanged>,
+    irm_event_writer: EventWriter<'w, ItemRemovedFromOCItemContainer>,
+    dipu_event_writer: EventWriter<'w, DroppedItemPickedUp>,
+    ipu_event_writer: EventWriter<'w, ItemPickedUp>,
+    popup_event_writer: EventWriter<'w, TextPopupEvent>,
+    item_query:
+        Query<'w, 's, (&'static mut Item, Option<&'static DroppedItem>), With<Interactable>>,
+    parent_query: Query<'w, 's, &'static Parent>,
+    container_query: Query<'w, 's, &'static GlobalTransform, With<OCItemContainer>>,
+    inventory: ResMut<'w, Inventory>,
+    localization: Res<'w, Localization>,
+    world_dims: Res<'w, WorldDims>,
+}
+
+impl ItemPickUp<'_, '_> {
+    // Inserts as much of an item as fits into the inventory, and despawns it if all of it did.
+    // Returns whether all of it did.
+    pub fn try_pick_up(&mut self, entity: Entity) -> bool {
+        let Ok((mut item, dropped_item)) = self.item_query.get_mut(entity) else {
+            return false;
+        };
+        // Already picked up earlier this frame, and waiting to be despawned
+        if item.amt == 0 {
+            return false;
+        }
+
+        let content = self.localization.tr(
+            "popup.picked_up",
+            &[
+                ("amt", &item.amt),
+                ("item", &self.localization.tr(item.name.display_key(), &[])),
+            ],
+        );
+        self.inv_event_writer.send(InventoryChanged);
+        self.popup_event_writer.send(TextPopupEvent {
+            content,
+            location: TextPopupLocation::BottomLeft,
+            timeout: TextPopupTimeout::Seconds(4),
+            ..default()
+        });
+
+        let rem_item = self.inventory.insert(*item);
+        let picked_up_amt = item.amt - rem_item.map_or(0, |rem_item| rem_item.amt);
+        if picked_up_amt > 0 {
+            self.ipu_event_writer
+                .send(ItemPickedUp(Item::new(item.name, picked_up_amt)));
+        }
+
+        // Check if item was inside of a container
+        let parent_entity = get_n_parent(entity, &self.parent_query, 1);
+        if let Ok(gt) = self.container_query.get(parent_entity) {
+            self.irm_event_writer.send(ItemRemovedFromOCItemContainer {
+                ccm: ChunkCellMarker::from_global_transform(gt, &self.world_dims),
+                _item: *item,
+                _entity: parent_entity,
+                rem_item,
+            });
+        }
+
+        match rem_item {
+            Some(rem_item) => {
+                if let Some(di) = dropped_item {
+                    self.dipu_event_writer.send(DroppedItemPickedUp {
+                        id: di.0,
+                        rem_item: Some(rem_item),
+                    });
+                }
+
+                *item = rem_item;
+                false
+            }
+            None => {
+                if let Some(di) = dropped_item {
+                    self.dipu_event_writer.send(DroppedItemPickedUp {
+                        id: di.0,
+                        rem_item: None,
+                    });
+                }
+
+                item.amt = 0;
+                self.commands.entity(entity).despawn_recursive();
+                true
+            }
+        }
     }
 }
 
 pub fn pick_up_items(
-    mut commands: Commands,
     mut event_reader: EventReader<PendingInteractionExecuted>,
+    mut item_pick_up: ItemPickUp,
+) {
+    for event in event_reader.read() {
+        item_pick_up.try_pick_up(event.0);
+    }
+}
+
+// Uses up a key on a locked container, and opens it as if it had never been locked
+pub fn unlock_locked_containers(
+    mut commands: Commands,
+    mut event_reader: EventReader<LockedInteraction>,
+    mut pie_event_writer: EventWriter<PendingInteractionExecuted>,
+    mut cu_event_writer: EventWriter<ContainerUnlocked>,
     mut inv_event_writer: EventWriter<InventoryChanged>,
-    mut irm_event_writer: EventWriter<ItemRemovedFromOCItemContainer>,
     mut popup_event_writer: EventWriter<TextPopupEvent>,
-    mut item_query: Query<(Entity, &mut Item), With<Interactable>>,
-    parent_query: Query<&Parent>,
-    container_query: Query<&GlobalTransform, With<OCItemContainer>>,
+    locked_query: Query<&Locked>,
     mut inventory: ResMut<Inventory>,
+    localization: Res<Localization>,
+    mut unlocked: Local<Vec<Entity>>,
 ) {
+    // Locked is only removed once commands are applied, so the same container could show up
+    // more than once in the same frame
+    unlocked.clear();
+
     for event in event_reader.read() {
-        for (entity, mut item) in item_query.iter_mut() {
-            if entity == event.0 {
-                let content = format!("Picked up ({}) {}", item.amt, item.name);
-                let send_events = || {
-                    inv_event_writer.send(InventoryChanged);
-                    popup_event_writer.send(TextPopupEvent {
-                        content,
-                        location: TextPopupLocation::BottomLeft,
-                        timeout: TextPopupTimeout::Seconds(4),
-                        ..default()
-                    });
-                };
-
-                match inventory.insert(item.clone()) {
-                    Some(rem_item) => {
-                        *item = rem_item;
-                        send_events();
-                    }
-                    None => {
-                        // Check if item was inside of a container
-                        let parent_entity = get_n_parent(entity, &parent_query, 1);
-                        if let Ok(gt) = container_query.get(parent_entity) {
-                            irm_event_writer.send(ItemRemovedFromOCItemContainer {
-                                ccm: ChunkCellMarker::from_global_transform(
-                                    gt, CHUNK_SIZE, CELL_SIZE,
-                                ),
-                                _item: item.clone(),
-                                _entity: parent_entity,
-                            });
-                        }
-
-                        item.amt = 0;
-                        send_events();
-
-                        commands.entity(entity).despawn_recursive();
-                    }
-                }
+        let Ok(locked) = locked_query.get(event.0) else {
+            continue;
+        };
+        if unlocked.contains(&event.0) {
+            continue;
+        }
 
-                break;
+        let content = match inventory.take_key(locked.tier) {
+            Some(key) => {
+                commands.entity(event.0).remove::<Locked>();
+                unlocked.push(event.0);
+                inv_event_writer.send(InventoryChanged);
+                cu_event_writer.send(ContainerUnlocked(event.0));
+                pie_event_writer.send(PendingInteractionExecuted(event.0));
+                localization.tr(
+                    "popup.unlocked",
+                    &[("item", &localization.tr(key.name.display_key(), &[]))],
+                )
             }
+            None => localization.tr("popup.locked", &[]),
+        };
+        popup_event_writer.send(TextPopupEvent {
+            content,
+            location: TextPopupLocation::BottomLeft,
+            timeout: TextPopupTimeout::Seconds(4),
+            ..default()
+        });
+    }
+}
+
+pub fn auto_pick_up_items(
+    mut commands: Commands,
+    mut item_pick_up: ItemPickUp,
+    mut item_query: Query<
+        (Entity, &GlobalTransform, Option<&mut PickUpGrace>),
+        (With<Item>, With<Interactable>),
+    >,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    parent_query: Query<&Parent>,
+    container_query: Query<(), With<OCItemContainer>>,
+    game_settings: Res<State<GameSettings>>,
+) {
+    if !game_settings.get().auto_pick_up_items {
+        return;
+    }
+    let Ok(player_gt) = player_query.get_single() else {
+        return;
+    };
+
+    let mut picked_up = 0;
+    for (entity, gt, grace) in item_query.iter_mut() {
+        // Items inside of chests are only ever picked up by hand
+        if container_query.contains(get_n_parent(entity, &parent_query, 1)) {
+            continue;
+        }
+
+        let in_range = gt.translation().distance(player_gt.translation()) <= AUTO_PICK_UP_RADIUS;
+        if let Some(mut grace) = grace {
+            if grace.tick(in_range) {
+                commands.entity(entity).remove::<PickUpGrace>();
+            }
+            continue;
+        }
+
+        if !in_range || picked_up == AUTO_PICK_UP_MAX_PER_FRAME {
+            continue;
+        }
+
+        picked_up += 1;
+        if !item_pick_up.try_pick_up(entity) {
+            // Whatever did not fit waits until the player walks away and comes back
+            commands.entity(entity).insert(PickUpGrace(0));
         }
     }
 }
 
+// Equipment only ever holds single items, so all of it is dropped no matter the amount
 pub fn drop_dragged_item(
-    mut event_reader: EventReader<StateTransitionEvent<DragState>>,
+    mut event_reader: EventReader<DraggedItemDropped>,
     mut inv_event_writer: EventWriter<InventoryChanged>,
     mut pdi_event_writer: EventWriter<PlayerDroppedItem>,
-    menu_query: Query<&RelativeCursorPosition, With<Menu>>,
     mut inventory: ResMut<Inventory>,
 ) {
-    for event in event_reader.read() {
-        if let Ok(rel_cursor_position) = menu_query.get_single() {
-            if rel_cursor_position.mouse_over() {
-                continue;
+    for DraggedItemDropped(dragging, drop_amount) in event_reader.read() {
+        let item = match dragging {
+            Dragging::InventorySlot(i) => {
+                let amt = inventory
+                    .slots
+                    .get(*i)
+                    .copied()
+                    .flatten()
+                    .map_or(0, |item| item.amt);
+                inventory.take_from_slot(*i, drop_amount.of(amt))
             }
+            Dragging::EquipmentSlot(name) => inventory.equipment.at_mut(name).take(),
+            Dragging::None => None,
+        };
 
-            if let Some(prev_dragging_slot) = &event.exited {
-                match prev_dragging_slot.0 {
-                    Dragging::InventorySlot(i) => {
-                        if let Some(slot) = inventory.slots.get_mut(i) {
-                            if let Some(item) = slot {
-                                pdi_event_writer.send(PlayerDroppedItem(item.clone()));
-                                inv_event_writer.send(InventoryChanged);
-                                *slot = None;
-                            }
-                        }
-                    }
-                    Dragging::EquipmentSlot(name) => {
-                        let slot = inventory.equipment.at_mut(&name);
-                        if let Some(item) = slot {
-                            pdi_event_writer.send(PlayerDroppedItem(item.clone()));
-                            inv_event_writer.send(InventoryChanged);
-                            *slot = None;
-                        }
-                    }
-                    Dragging::None => {}
-                }
-            }
+        if let Some(item) = item {
+            pdi_event_writer.send(PlayerDroppedItem(item));
+            inv_event_writer.send(InventoryChanged);
         }
-
-        break;
     }
 }
@@ -0,0 +1,274 @@
+use crate::plugins::inventory::InventoryPlugin;
+use bevy::{prelude::*, state::app::StatesPlugin};
+use bevy_text_popup::TextPopupEvent;
+use dungeon_maze_common::{
+    app_state::AppState,
+    interaction::{LockedInteraction, PendingInteractionExecuted},
+    inventory::{
+        item::{Item, ItemName},
+        Inventory, ItemRemovedFromOCItemContainer,
+    },
+    localization::Localization,
+    player::Player,
+    settings::GameSettings,
+    world::{ContainerUnlocked, Locked, OCItemContainer, PickUpGrace, WorldDims},
+};
+
+fn new_test_app(auto_pick_up_items: bool) -> App {
+    let mut app = App::new();
+
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        HierarchyPlugin,
+        StatesPlugin,
+        InventoryPlugin,
+    ))
+    .add_event::<TextPopupEvent>()
+    .add_event::<PendingInteractionExecuted>()
+    .add_event::<LockedInteraction>()
+    .add_event::<ContainerUnlocked>()
+    .init_resource::<Localization>()
+    .init_resource::<WorldDims>()
+    .insert_state(GameSettings {
+        auto_pick_up_items,
+        ..default()
+    })
+    .insert_state(AppState::InGame);
+
+    app.world_mut().spawn((Player, TransformBundle::default()));
+    // Lets the player's global transform propagate before any items are spawned
+    app.update();
+
+    app
+}
+
+fn spawn_item(app: &mut App, translation: Vec3) -> Entity {
+    app.world_mut()
+        .spawn((
+            Item::new(ItemName::Coal, 1),
+            Item::interactable(),
+            TransformBundle::from_transform(Transform::from_translation(translation)),
+        ))
+        .id()
+}
+
+fn exists(app: &App, entity: Entity) -> bool {
+    app.world().get_entity(entity).is_some()
+}
+
+fn coal_amt(app: &App) -> u16 {
+    app.world()
+        .resource::<Inventory>()
+        .slots
+        .iter()
+        .flatten()
+        .filter(|item| item.name == ItemName::Coal)
+        .map(|item| item.amt)
+        .sum()
+}
+
+#[test]
+fn test_auto_pick_up_items_nearby() {
+    let mut app = new_test_app(true);
+
+    let near = (0..3)
+        .map(|i| spawn_item(&mut app, Vec3::new(0.2 * i as f32, 0.0, 0.5)))
+        .collect::<Vec<_>>();
+    let far = spawn_item(&mut app, Vec3::new(3.0, 0.0, 0.0));
+    let container = app
+        .world_mut()
+        .spawn((OCItemContainer, TransformBundle::default()))
+        .id();
+    let in_container = spawn_item(&mut app, Vec3::ZERO);
+    app.world_mut()
+        .entity_mut(container)
+        .add_child(in_container);
+    app.update();
+
+    // Only a few at a time
+    assert_eq!(coal_amt(&app), 2);
+    app.update();
+    assert_eq!(coal_amt(&app), 3);
+    assert!(near.iter().all(|entity| !exists(&app, *entity)));
+
+    for _ in 0..5 {
+        app.update();
+    }
+    assert_eq!(coal_amt(&app), 3);
+    assert!(exists(&app, far));
+    assert!(exists(&app, in_container));
+}
+
+#[test]
+fn test_auto_pick_up_disabled_in_settings() {
+    let mut app = new_test_app(false);
+
+    let item = spawn_item(&mut app, Vec3::ZERO);
+    app.update();
+    app.update();
+
+    assert_eq!(coal_amt(&app), 0);
+    assert!(exists(&app, item));
+}
+
+#[test]
+fn test_auto_pick_up_waits_out_grace() {
+    let mut app = new_test_app(true);
+
+    let item = spawn_item(&mut app, Vec3::ZERO);
+    app.world_mut().entity_mut(item).insert(PickUpGrace(3));
+
+    // Standing on top of the item keeps it from being picked up, even after the frames are up
+    for _ in 0..10 {
+        app.update();
+    }
+    assert!(exists(&app, item));
+    assert_eq!(coal_amt(&app), 0);
+
+    // Until the player walks away and comes back
+    let world = app.world_mut();
+    let player = world.query_filtered::<Entity, With<Player>>().single(world);
+    app.world_mut()
+        .entity_mut(player)
+        .insert(Transform::from_xyz(5.0, 0.0, 0.0));
+    app.update();
+    app.update();
+    assert!(app.world().get::<PickUpGrace>(item).is_none());
+
+    app.world_mut()
+        .entity_mut(player)
+        .insert(Transform::IDENTITY);
+    app.update();
+    app.update();
+    assert!(!exists(&app, item));
+    assert_eq!(coal_amt(&app), 1);
+}
+
+#[test]
+fn test_auto_pick_up_full_inventory() {
+    let mut app = new_test_app(true);
+    app.world_mut().resource_mut::<Inventory>().slots = [Some(Item::new(ItemName::Cotton, 1)); 16];
+
+    let mut popup_reader = app
+        .world()
+        .resource::<Events<TextPopupEvent>>()
+        .get_reader();
+    let mut popups = 0;
+
+    let item = spawn_item(&mut app, Vec3::ZERO);
+    for _ in 0..5 {
+        app.update();
+        let events = app.world().resource::<Events<TextPopupEvent>>();
+        popups += popup_reader.read(events).count();
+    }
+
+    // Tried once, then left alone instead of being retried every frame
+    assert!(exists(&app, item));
+    assert!(app.world().get::<PickUpGrace>(item).is_some());
+    assert_eq!(popups, 1);
+}
+
+#[test]
+fn test_pick_up_from_container_reports_remainder() {
+    let mut app = new_test_app(false);
+    let max_amt = ItemName::Coal.max_amt();
+    let mut slots = [Some(Item::new(ItemName::Cotton, 1)); 16];
+    slots[0] = Some(Item::new(ItemName::Coal, max_amt - 1));
+    app.world_mut().resource_mut::<Inventory>().slots = slots;
+
+    let container = app
+        .world_mut()
+        .spawn((OCItemContainer, TransformBundle::default()))
+        .id();
+    let item = app
+        .world_mut()
+        .spawn((
+            Item::new(ItemName::Coal, 3),
+            Item::interactable(),
+            TransformBundle::default(),
+        ))
+        .id();
+    app.world_mut().entity_mut(container).add_child(item);
+    app.update();
+
+    let mut reader = app
+        .world()
+        .resource::<Events<ItemRemovedFromOCItemContainer>>()
+        .get_reader();
+    let mut pick_up = |app: &mut App| {
+        app.world_mut().send_event(PendingInteractionExecuted(item));
+        app.update();
+        let events = app
+            .world()
+            .resource::<Events<ItemRemovedFromOCItemContainer>>();
+        reader
+            .read(events)
+            .map(|event| event.rem_item)
+            .collect::<Vec<_>>()
+    };
+
+    // Only one fits, so the rest is left in the container
+    assert_eq!(pick_up(&mut app), vec![Some(Item::new(ItemName::Coal, 2))]);
+    assert!(exists(&app, item));
+
+    app.world_mut().resource_mut::<Inventory>().slots[1] = None;
+    assert_eq!(pick_up(&mut app), vec![None]);
+    assert!(!exists(&app, item));
+}
+
+#[test]
+fn test_locked_container_uses_up_one_key() {
+    let mut app = new_test_app(false);
+    let container = app
+        .world_mut()
+        .spawn((
+            OCItemContainer,
+            Locked { tier: 1 },
+            TransformBundle::default(),
+        ))
+        .id();
+    app.update();
+
+    let interact = |app: &mut App, times: usize| {
+        for _ in 0..times {
+            app.world_mut().send_event(LockedInteraction(container));
+        }
+        app.update();
+        let executed = app
+            .world_mut()
+            .resource_mut::<Events<PendingInteractionExecuted>>()
+            .drain()
+            .count();
+        let unlocked = app
+            .world_mut()
+            .resource_mut::<Events<ContainerUnlocked>>()
+            .drain()
+            .count();
+        let popups = app
+            .world_mut()
+            .resource_mut::<Events<TextPopupEvent>>()
+            .drain()
+            .count();
+        (executed, unlocked, popups)
+    };
+
+    // Without a key, it only says that it is locked
+    assert_eq!(interact(&mut app, 1), (0, 0, 1));
+    assert!(app.world().get::<Locked>(container).is_some());
+
+    app.world_mut().resource_mut::<Inventory>().slots[3] = Some(Item::new(ItemName::Key, 2));
+    assert_eq!(interact(&mut app, 2), (1, 1, 1));
+    assert!(app.world().get::<Locked>(container).is_none());
+    assert_eq!(
+        app.world().resource::<Inventory>().slots[3],
+        Some(Item::new(ItemName::Key, 1))
+    );
+
+    // Nothing left to unlock
+    assert_eq!(interact(&mut app, 1), (0, 0, 0));
+    assert_eq!(
+        app.world().resource::<Inventory>().slots[3],
+        Some(Item::new(ItemName::Key, 1))
+    );
+}
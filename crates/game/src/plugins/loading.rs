@@ -0,0 +1,200 @@
+use crate::plugins::{
+    animation::animation_clip_paths,
+    world::{bundle::cell::WALL_TEXTURE_PATHS, WORLD_STRUCTURES_FOLDER},
+};
+use bevy::{
+    asset::{LoadState, LoadedFolder},
+    prelude::*,
+};
+use dungeon_maze_common::{
+    animation::PlayerAnimation,
+    app_state::AppState,
+    inventory::item::ItemName,
+    loading::{
+        LoadingProgress, LoadingProgressBar, LoadingProgressText, PendingAssetState, PendingAssets,
+    },
+};
+use strum::IntoEnumIterator;
+
+const LOADING_BAR_WIDTH: f32 = 400.0;
+const LOADING_BAR_HEIGHT: f32 = 20.0;
+
+// Models of cell specials, doors and windows, which are all spawned from their first scene
+const SCENE_MODEL_PATHS: [&str; 4] = [
+    "embedded://models/chair.glb",
+    "embedded://models/door.glb",
+    "embedded://models/treasure_chest.glb",
+    "embedded://models/window.glb",
+];
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingAssets>()
+            .add_systems(Startup, load_pending_assets)
+            .add_systems(OnEnter(AppState::Loading), spawn_loading_screen)
+            .add_systems(
+                Update,
+                update_loading_progress.run_if(in_state(AppState::Loading)),
+            );
+    }
+}
+
+fn load_pending_assets(asset_server: Res<AssetServer>, mut pending_assets: ResMut<PendingAssets>) {
+    let mut handles: Vec<UntypedHandle> = Vec::new();
+
+    // Spawned from the scene with the same index as the idle animation,
+    // same as in spawn_player_with_config
+    let handle: Handle<Scene> = asset_server.load(
+        GltfAssetLabel::Scene(PlayerAnimation::Idle.index())
+            .from_asset("embedded://models/man.glb"),
+    );
+    handles.push(handle.untyped());
+
+    for path in SCENE_MODEL_PATHS {
+        let handle: Handle<Scene> = asset_server.load(GltfAssetLabel::Scene(0).from_asset(path));
+        handles.push(handle.untyped());
+    }
+
+    for path in animation_clip_paths() {
+        let handle: Handle<AnimationClip> = asset_server.load(path);
+        handles.push(handle.untyped());
+    }
+
+    for item_name in ItemName::iter() {
+        let handle: Handle<Image> = asset_server.load(item_name.ui_image_path());
+        handles.push(handle.untyped());
+
+        if let Some(path) = item_name.model_path() {
+            let handle: Handle<Scene> = asset_server.load(path);
+            handles.push(handle.untyped());
+        }
+    }
+
+    for path in WALL_TEXTURE_PATHS {
+        let handle: Handle<Image> = asset_server.load(path);
+        handles.push(handle.untyped());
+    }
+
+    // World structures have to be in the registry before any chunks are generated
+    let handle: Handle<LoadedFolder> = asset_server.load_folder(WORLD_STRUCTURES_FOLDER);
+    handles.push(handle.untyped());
+
+    pending_assets.0 = handles;
+}
+
+fn pending_asset_state(asset_server: &AssetServer, handle: &UntypedHandle) -> PendingAssetState {
+    match asset_server.get_load_state(handle.id()) {
+        Some(LoadState::Loaded) => PendingAssetState::Loaded,
+        Some(LoadState::Failed(_)) => PendingAssetState::Failed,
+        Some(LoadState::NotLoaded) | Some(LoadState::Loading) | None => PendingAssetState::Loading,
+    }
+}
+
+fn spawn_loading_screen(mut commands: Commands) {
+    commands.spawn((
+        Camera2dBundle::default(),
+        StateScoped(AppState::Loading),
+        Name::new("Loading Screen Camera"),
+    ));
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(10.0),
+                    height: Val::Percent(100.0),
+                    width: Val::Percent(100.0),
+                    ..default()
+                },
+                background_color: Color::BLACK.into(),
+                ..default()
+            },
+            StateScoped(AppState::Loading),
+            Name::new("Loading Screen"),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                LoadingProgressText,
+                TextBundle::from_section(
+                    "Loading...",
+                    TextStyle {
+                        font_size: 24.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        height: Val::Px(LOADING_BAR_HEIGHT),
+                        width: Val::Px(LOADING_BAR_WIDTH),
+                        border: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: Color::WHITE.into(),
+                    ..default()
+                })
+                .with_children(|grandparent| {
+                    grandparent.spawn((
+                        LoadingProgressBar,
+                        NodeBundle {
+                            style: Style {
+                                height: Val::Percent(100.0),
+                                width: Val::Percent(0.0),
+                                ..default()
+                            },
+                            background_color: Color::WHITE.into(),
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+fn update_loading_progress(
+    mut bar_query: Query<&mut Style, With<LoadingProgressBar>>,
+    mut text_query: Query<&mut Text, With<LoadingProgressText>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    asset_server: Res<AssetServer>,
+    pending_assets: Res<PendingAssets>,
+) {
+    let states: Vec<PendingAssetState> = pending_assets
+        .0
+        .iter()
+        .map(|handle| pending_asset_state(&asset_server, handle))
+        .collect();
+    let progress = LoadingProgress::new(states.iter().copied());
+
+    for mut style in bar_query.iter_mut() {
+        style.width = Val::Percent(progress.fraction() * 100.0);
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("Loading... {}/{}", progress.done(), progress.total);
+    }
+
+    if !progress.is_done() {
+        return;
+    }
+
+    // The game still starts, just without whatever failed to load
+    for (handle, state) in pending_assets.0.iter().zip(states) {
+        if state == PendingAssetState::Failed {
+            error!(
+                "Failed to load asset: {}",
+                handle
+                    .path()
+                    .map_or("unknown path".to_owned(), |path| path.to_string())
+            );
+        }
+    }
+
+    next_app_state.set(AppState::InGame);
+}
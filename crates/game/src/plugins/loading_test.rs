@@ -0,0 +1,27 @@
+use dungeon_maze_common::loading::{LoadingProgress, PendingAssetState};
+
+#[test]
+fn test_loading_progress_done_when_nothing_is_loading() {
+    use PendingAssetState::*;
+
+    // Still waiting on an asset
+    let progress = LoadingProgress::new([Loaded, Failed, Loading, Loaded]);
+    assert_eq!(progress.loaded, 2);
+    assert_eq!(progress.failed, 1);
+    assert_eq!(progress.total, 4);
+    assert_eq!(progress.fraction(), 0.75);
+    assert!(!progress.is_done());
+
+    // Failed assets do not keep the game from starting
+    let progress = LoadingProgress::new([Loaded, Failed, Failed]);
+    assert!(progress.is_done());
+    assert_eq!(progress.fraction(), 1.0);
+
+    let progress = LoadingProgress::new([Loaded, Loaded]);
+    assert!(progress.is_done());
+
+    // Nothing to load
+    let progress = LoadingProgress::new([]);
+    assert!(progress.is_done());
+    assert_eq!(progress.fraction(), 1.0);
+}
@@ -0,0 +1,357 @@
+use crate::plugins::save::save_file_exists;
+use bevy::{
+    input::keyboard::{Key, KeyboardInput},
+    prelude::*,
+    window::{CursorGrabMode, PrimaryWindow},
+};
+use bevy_rapier3d::plugin::RapierConfiguration;
+use bevy_third_person_camera::ThirdPersonCamera;
+use dungeon_maze_common::{
+    app_state::{AppState, GameLoaded},
+    automap::{RevealedCells, VisitedCells},
+    death_marker::DeathMarkers,
+    inventory::Inventory,
+    main_menu::*,
+    menu::{DragState, Dragging, MenuOpen},
+    save::{SaveLoadReport, WorldDataChanged},
+    stats::PlayerStats,
+    world::{data::WorldData, WorldConfig, WorldSeed},
+};
+
+const MAX_SEED_LEN: usize = 10;
+
+pub struct MainMenuPlugin;
+
+impl Plugin for MainMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<AppState>()
+            .add_computed_state::<GameLoaded>()
+            .enable_state_scoped_entities::<AppState>()
+            .enable_state_scoped_entities::<GameLoaded>()
+            .init_resource::<NewGameSeed>()
+            .add_systems(OnEnter(AppState::MainMenu), spawn_main_menu)
+            .add_systems(OnEnter(AppState::Paused), (spawn_pause_menu, pause_game))
+            .add_systems(OnExit(AppState::Paused), resume_game)
+            .add_systems(
+                Update,
+                (
+                    (type_new_game_seed, handle_main_menu_buttons)
+                        .run_if(in_state(AppState::MainMenu)),
+                    handle_pause_menu_buttons.run_if(in_state(AppState::Paused)),
+                    toggle_paused.run_if(in_state(GameLoaded)),
+                ),
+            );
+    }
+}
+
+fn spawn_main_menu(
+    mut commands: Commands,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    new_game_seed: Res<NewGameSeed>,
+) {
+    // The cursor is left locked by the camera when returning from a game
+    if let Ok(mut window) = window_query.get_single_mut() {
+        window.cursor.grab_mode = CursorGrabMode::None;
+        window.cursor.visible = true;
+    }
+
+    commands.spawn((
+        Camera2dBundle::default(),
+        StateScoped(AppState::MainMenu),
+        Name::new("Main Menu Camera"),
+    ));
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(10.0),
+                    height: Val::Percent(100.0),
+                    width: Val::Percent(100.0),
+                    ..default()
+                },
+                background_color: Color::BLACK.into(),
+                ..default()
+            },
+            StateScoped(AppState::MainMenu),
+            Name::new("Main Menu"),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text {
+                    sections: vec![TextSection::new(
+                        "Dungeon Maze",
+                        TextStyle {
+                            font_size: 48.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    )],
+                    ..default()
+                },
+                style: Style {
+                    margin: UiRect::bottom(Val::Px(20.0)),
+                    ..default()
+                },
+                ..default()
+            });
+
+            spawn_menu_button(
+                parent,
+                MainMenuButton::NewGame,
+                MainMenuButton::NewGame.label(),
+            );
+
+            parent.spawn((
+                NewGameSeedText,
+                TextBundle {
+                    text: Text {
+                        sections: vec![TextSection::new(
+                            seed_text(&new_game_seed),
+                            TextStyle {
+                                font_size: 16.0,
+                                color: Color::WHITE,
+                                ..default()
+                            },
+                        )],
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+
+            if save_file_exists() {
+                spawn_menu_button(
+                    parent,
+                    MainMenuButton::Continue,
+                    MainMenuButton::Continue.label(),
+                );
+            }
+
+            spawn_menu_button(parent, MainMenuButton::Quit, MainMenuButton::Quit.label());
+        });
+}
+
+fn spawn_pause_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(10.0),
+                    height: Val::Percent(100.0),
+                    width: Val::Percent(100.0),
+                    ..default()
+                },
+                background_color: Color::linear_rgba(0.0, 0.0, 0.0, 0.6).into(),
+                z_index: ZIndex::Global(1),
+                ..default()
+            },
+            StateScoped(AppState::Paused),
+            Name::new("Pause Menu"),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text {
+                    sections: vec![TextSection::new(
+                        "Paused",
+                        TextStyle {
+                            font_size: 36.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    )],
+                    ..default()
+                },
+                ..default()
+            });
+
+            for button in [PauseMenuButton::Resume, PauseMenuButton::MainMenu] {
+                spawn_menu_button(parent, button, button.label());
+            }
+        });
+}
+
+fn spawn_menu_button(child_builder: &mut ChildBuilder, button: impl Component, label: &str) {
+    child_builder
+        .spawn((
+            button,
+            ButtonBundle {
+                style: Style {
+                    display: Display::Flex,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    height: Val::Px(40.0),
+                    width: Val::Px(200.0),
+                    ..default()
+                },
+                background_color: Color::WHITE.into(),
+                ..default()
+            },
+            Name::new(format!("{} Button", label)),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text {
+                    sections: vec![TextSection::new(
+                        label,
+                        TextStyle {
+                            font_size: 20.0,
+                            color: Color::BLACK,
+                            ..default()
+                        },
+                    )],
+                    ..default()
+                },
+                ..default()
+            });
+        });
+}
+
+fn seed_text(new_game_seed: &NewGameSeed) -> String {
+    if new_game_seed.0.is_empty() {
+        "Seed: random (type digits to set)".to_owned()
+    } else {
+        format!("Seed: {}", new_game_seed.0)
+    }
+}
+
+fn type_new_game_seed(
+    mut event_reader: EventReader<KeyboardInput>,
+    mut text_query: Query<&mut Text, With<NewGameSeedText>>,
+    mut new_game_seed: ResMut<NewGameSeed>,
+) {
+    for event in event_reader.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Character(c) if c.chars().all(|c| c.is_ascii_digit()) => {
+                let seed = format!("{}{}", new_game_seed.0, c);
+                if seed.len() <= MAX_SEED_LEN && seed.parse::<u32>().is_ok() {
+                    new_game_seed.0 = seed;
+                }
+            }
+            Key::Backspace => {
+                new_game_seed.0.pop();
+            }
+            _ => continue,
+        }
+
+        for mut text in text_query.iter_mut() {
+            for section in text.sections.iter_mut() {
+                section.value = seed_text(&new_game_seed);
+            }
+        }
+    }
+}
+
+fn handle_main_menu_buttons(
+    mut commands: Commands,
+    mut wd_event_writer: EventWriter<WorldDataChanged>,
+    mut app_exit_event_writer: EventWriter<AppExit>,
+    button_query: Query<(&MainMenuButton, &Interaction), Changed<Interaction>>,
+    mut new_game_seed: ResMut<NewGameSeed>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    for (button, interaction) in button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            MainMenuButton::NewGame => {
+                let seed = new_game_seed.0.parse().unwrap_or_else(|_| rand::random());
+
+                commands.insert_resource(WorldSeed(seed));
+                commands.insert_resource(WorldConfig::default());
+                commands.insert_resource(Inventory::default());
+                commands.insert_resource(SaveLoadReport::default());
+                commands.insert_resource(WorldData::default());
+                commands.insert_resource(VisitedCells::default());
+                commands.insert_resource(RevealedCells::default());
+                commands.insert_resource(PlayerStats::default());
+                commands.insert_resource(DeathMarkers::default());
+                // Overwrite the previous save right away
+                wd_event_writer.send(WorldDataChanged);
+
+                new_game_seed.0.clear();
+                next_app_state.set(AppState::Loading);
+            }
+            MainMenuButton::Continue => next_app_state.set(AppState::Loading),
+            MainMenuButton::Quit => {
+                app_exit_event_writer.send(AppExit::Success);
+            }
+        }
+    }
+}
+
+fn handle_pause_menu_buttons(
+    button_query: Query<(&PauseMenuButton, &Interaction), Changed<Interaction>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    for (button, interaction) in button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            PauseMenuButton::Resume => next_app_state.set(AppState::InGame),
+            PauseMenuButton::MainMenu => next_app_state.set(AppState::MainMenu),
+        }
+    }
+}
+
+fn toggle_paused(
+    keys: Res<ButtonInput<KeyCode>>,
+    app_state: Res<State<AppState>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    drag_state: Res<State<DragState>>,
+    menu_open: Res<State<MenuOpen>>,
+) {
+    // Escape cancels dragging items in the menu instead
+    if keys.just_pressed(KeyCode::Escape) && drag_state.get().0 == Dragging::None {
+        match app_state.get() {
+            // Closes the menu instead
+            AppState::InGame if menu_open.get().0 => {}
+            AppState::InGame => next_app_state.set(AppState::Paused),
+            AppState::Paused => next_app_state.set(AppState::InGame),
+            AppState::MainMenu | AppState::Loading | AppState::Spectating => {}
+        }
+    }
+}
+
+fn pause_game(
+    mut camera_query: Query<&mut ThirdPersonCamera>,
+    mut time: ResMut<Time<Virtual>>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    time.pause();
+    rapier_config.physics_pipeline_active = false;
+
+    for mut camera in camera_query.iter_mut() {
+        camera.cursor_lock_active = false;
+    }
+}
+
+fn resume_game(
+    mut camera_query: Query<&mut ThirdPersonCamera>,
+    mut time: ResMut<Time<Virtual>>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    time.unpause();
+    rapier_config.physics_pipeline_active = true;
+
+    for mut camera in camera_query.iter_mut() {
+        camera.cursor_lock_active = true;
+    }
+}
@@ -1,20 +1,48 @@
+use crate::plugins::{
+    automap::spawn_automap_menu_content, cursor::cursor_to_ui_position,
+    stats::spawn_stats_menu_content,
+};
 use bevy::{prelude::*, ui::RelativeCursorPosition};
+use bevy_text_popup::{TextPopupEvent, TextPopupLocation, TextPopupTimeout};
 use dungeon_maze_common::{
+    app_state::{AppState, GameLoaded},
     cursor::{CursorFollower, CursorPosition},
+    hud::{HudLayout, HudLayoutChanged, HudWidgetLayout},
     inventory::{
-        equipment::EquipmentSlotName, item::ItemName, Inventory, InventoryChanged, ItemUsed,
+        equipment::EquipmentSlotName,
+        item::{ConsumableEffect, ConsumableStat, Item},
+        DropAmount, Inventory, InventoryChanged, ItemUsed, MergeSwapOutcome,
     },
+    localization::Localization,
     menu::*,
     player::{
+        attack::{AttackHand, WeaponSwapCooldown},
+        light::RefuelLight,
         DmgType, HealHealth, HealStamina, Health, Player, PlayerState, Regenerator, Stamina,
         TakeDamage,
     },
-    settings::{ChunkRenderDist, GameSettings, RenderDistChanged},
+    settings::{
+        ChunkPhysicsDist, ChunkRenderDist, GameSettings, RenderDistChanged, MAX_CHUNK_RENDER_DIST,
+    },
     should_not_happen,
-    utils::entity::get_n_parent,
+    stats::PlayerStats,
+    utils::{entity::get_n_parent, find_one, FindOneError},
 };
+use std::ops::RangeInclusive;
 use strum::IntoEnumIterator;
 
+const EQUIPMENT_SLOT_COOLDOWN_BORDER_COLOR: Color = Color::srgb(0.4, 0.4, 0.4);
+const FULL_STACK_AMT_COLOR: Color = Color::srgb(1.0, 0.75, 0.2);
+const DRAGGED_SLOT_ITEM_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.3);
+const FOCUS_OUTLINE_COLOR: Color = Color::srgb(1.0, 0.85, 0.0);
+const FOCUS_OUTLINE_WIDTH: Val = Val::Px(3.0);
+// Focus moves through the content of the active tab first, then the tab bar along the bottom
+const INVENTORY_SLOT_FOCUS_START: u32 = 0;
+const EQUIPMENT_SLOT_FOCUS_START: u32 = 100;
+const RENDER_DIST_BUTTON_FOCUS_START: u32 = 0;
+const PHYSICS_DIST_BUTTON_FOCUS_START: u32 = 10;
+const MENU_TAB_FOCUS_START: u32 = 1000;
+
 pub struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
@@ -22,6 +50,24 @@ impl Plugin for MenuPlugin {
         app.init_state::<MenuOpen>()
             .init_state::<ActiveMenuTab>()
             .init_state::<DragState>()
+            .init_resource::<FocusedUiElement>()
+            .init_resource::<RenderedInventory>()
+            .add_event::<UiElementActivated>()
+            .add_systems(
+                Update,
+                (
+                    navigate_menu_with_keyboard
+                        .before(change_active_menu_tab)
+                        .before(change_render_dist)
+                        .before(start_drag_inventory_item)
+                        .before(start_drag_equipment_item)
+                        .before(stop_drag_item)
+                        .run_if(in_state(MenuOpen(true))),
+                    update_focus_outline,
+                    ghost_dragged_slot_item,
+                )
+                    .run_if(in_state(AppState::InGame)),
+            )
             .add_systems(
                 Update,
                 (
@@ -29,26 +75,66 @@ impl Plugin for MenuPlugin {
                     change_active_menu_tab,
                     manage_menu_content,
                     update_inventory_menu_content,
+                    sort_inventory,
                     change_menu_tabs_background_color,
                     change_render_dist,
                     change_render_dist_buttons_background_color,
+                    toggle_structure_compass,
+                    update_structure_compass_button_text,
+                    toggle_spectator_mode,
+                    update_spectator_mode_button_text,
+                    (
+                        change_camera_settings,
+                        update_camera_settings_text,
+                        change_ui_scale,
+                        update_ui_scale_text,
+                        toggle_damage_effects,
+                        update_damage_effects_button_text,
+                        toggle_hit_effects,
+                        update_hit_effects_button_text,
+                        toggle_auto_pick_up,
+                        update_auto_pick_up_button_text,
+                        change_crosshair,
+                        update_crosshair_buttons_text,
+                        change_decor_density,
+                        update_decor_density_button_text,
+                        (
+                            change_light_fuel_mode,
+                            update_light_fuel_mode_button_text,
+                            change_movement_style,
+                            update_movement_style_button_text,
+                            change_autosave_interval,
+                            update_autosave_interval_text,
+                        ),
+                        change_difficulty,
+                        update_difficulty_button_text,
+                        change_language,
+                        update_menu_tabs_text.run_if(resource_changed::<Localization>),
+                    ),
+                    change_hud_layout,
+                    update_hud_layout_buttons_text,
                     update_visible_on_parent_hover,
+                    change_equipment_slots_border_color,
                     use_inventory_item,
                     handle_item_used,
                     update_item_image_cursor_follower,
-                ),
+                )
+                    .run_if(in_state(AppState::InGame)),
             )
             .add_systems(
                 Update,
                 (
                     start_drag_inventory_item,
                     start_drag_equipment_item,
+                    cancel_drag_item,
                     stop_drag_item,
                 )
-                    .run_if(in_state(PlayerState::Walking)),
+                    .run_if(in_state(PlayerState::Walking).and_then(in_state(AppState::InGame))),
             )
+            .add_event::<DraggedItemDropped>()
+            .add_systems(OnExit(GameLoaded), close_menu)
             .add_systems(OnEnter(MenuOpen(true)), spawn_menu)
-            .add_systems(OnExit(MenuOpen(true)), despawn_menu);
+            .add_systems(OnExit(MenuOpen(true)), (despawn_menu, clear_focus));
     }
 }
 
@@ -62,28 +148,29 @@ fn toggle_menu_open(
     }
 }
 
+fn close_menu(mut next_menu_open: ResMut<NextState<MenuOpen>>) {
+    next_menu_open.set(MenuOpen(false));
+}
+
 fn spawn_menu(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     inventory: Res<Inventory>,
     active_menu_tab: Res<State<ActiveMenuTab>>,
     game_settings: Res<State<GameSettings>>,
+    hud_layout: Res<HudLayout>,
+    player_stats: Res<PlayerStats>,
+    mut rendered_inventory: ResMut<RenderedInventory>,
+    localization: Res<Localization>,
 ) {
+    rendered_inventory.0 = Some(inventory.clone());
+
     commands
         .spawn((
             Menu,
             RelativeCursorPosition::default(),
             NodeBundle {
-                style: Style {
-                    position_type: PositionType::Absolute,
-                    right: Val::Px(0.0),
-                    height: Val::Percent(80.0),
-                    width: Val::Percent(20.0),
-                    display: Display::Flex,
-                    flex_direction: FlexDirection::Column,
-                    justify_content: JustifyContent::SpaceBetween,
-                    ..default()
-                },
+                style: menu_style(&active_menu_tab.get().0),
                 background_color: Color::BLACK.into(),
                 ..default()
             },
@@ -107,10 +194,22 @@ fn spawn_menu(
                     },
                 ))
                 .with_children(|grandparent| match active_menu_tab.get().0 {
-                    MenuTab::Inventory => {
-                        spawn_inventory_menu_content(grandparent, &asset_server, &inventory)
+                    MenuTab::Inventory => spawn_inventory_menu_content(
+                        grandparent,
+                        &asset_server,
+                        &inventory,
+                        &localization,
+                    ),
+                    MenuTab::Map => spawn_automap_menu_content(grandparent),
+                    MenuTab::Stats => {
+                        spawn_stats_menu_content(grandparent, &player_stats, &localization)
                     }
-                    MenuTab::Settings => spawn_settings_menu_content(grandparent, &game_settings),
+                    MenuTab::Settings => spawn_settings_menu_content(
+                        grandparent,
+                        &game_settings,
+                        &hud_layout,
+                        &localization,
+                    ),
                 });
 
             parent
@@ -124,44 +223,169 @@ fn spawn_menu(
                     ..default()
                 })
                 .with_children(|grandparent| {
-                    for tab in [MenuTab::Inventory, MenuTab::Settings] {
-                        grandparent.spawn((
-                            ButtonBundle {
-                                style: Style {
-                                    height: Val::Percent(100.0),
-                                    width: Val::Percent(20.0),
+                    for (i, tab) in [
+                        MenuTab::Inventory,
+                        MenuTab::Map,
+                        MenuTab::Stats,
+                        MenuTab::Settings,
+                    ]
+                    .into_iter()
+                    .enumerate()
+                    {
+                        let text = localization.tr(tab.display_key(), &[]);
+
+                        grandparent
+                            .spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        display: Display::Flex,
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        height: Val::Percent(100.0),
+                                        width: Val::Percent(20.0),
+                                        ..default()
+                                    },
+                                    background_color: get_tab_background_color(
+                                        &tab,
+                                        active_menu_tab.get(),
+                                    ),
                                     ..default()
                                 },
-                                background_color: get_tab_background_color(
-                                    &tab,
-                                    active_menu_tab.get(),
-                                ),
-                                ..default()
-                            },
-                            Name::new(format!("Menu Tab {}", tab)),
-                            tab,
-                        ));
+                                Name::new(format!("Menu Tab {}", tab)),
+                                Focusable(MENU_TAB_FOCUS_START + i as u32),
+                                tab,
+                            ))
+                            .with_children(|button| {
+                                button.spawn(TextBundle::from_section(
+                                    text,
+                                    TextStyle {
+                                        font_size: 16.0,
+                                        color: Color::WHITE,
+                                        ..default()
+                                    },
+                                ));
+                            });
                     }
                 });
         });
 }
 
+fn menu_style(tab: &MenuTab) -> Style {
+    let (height, width) = match tab {
+        MenuTab::Map => (Val::Percent(100.0), Val::Percent(100.0)),
+        MenuTab::Inventory | MenuTab::Stats | MenuTab::Settings => {
+            (Val::Percent(80.0), Val::Percent(20.0))
+        }
+    };
+
+    Style {
+        position_type: PositionType::Absolute,
+        right: Val::Px(0.0),
+        height,
+        width,
+        display: Display::Flex,
+        flex_direction: FlexDirection::Column,
+        justify_content: JustifyContent::SpaceBetween,
+        ..default()
+    }
+}
+
 fn despawn_menu(mut commands: Commands, menu_query: Query<Entity, With<Menu>>) {
     let menu_entity = menu_query.get_single().unwrap();
     commands.entity(menu_entity).despawn_recursive();
 }
 
+fn clear_focus(mut focused: ResMut<FocusedUiElement>) {
+    focused.0 = None;
+}
+
+// Tab and Shift+Tab move focus, and so do the arrow keys except on the map, where they
+// pan it instead. Enter presses the focused element, and Escape closes the menu.
+pub fn navigate_menu_with_keyboard(
+    mut event_writer: EventWriter<UiElementActivated>,
+    focusable_query: Query<(Entity, &Focusable)>,
+    keys: Res<ButtonInput<KeyCode>>,
+    active_menu_tab: Res<State<ActiveMenuTab>>,
+    drag_state: Res<State<DragState>>,
+    mut next_menu_open: ResMut<NextState<MenuOpen>>,
+    mut focused: ResMut<FocusedUiElement>,
+) {
+    // Escape cancels dragging items instead
+    if keys.just_pressed(KeyCode::Escape) && drag_state.get().0 == Dragging::None {
+        next_menu_open.set(MenuOpen(false));
+        return;
+    }
+
+    let arrows = active_menu_tab.get().0 != MenuTab::Map;
+    let forward = if keys.just_pressed(KeyCode::Tab) {
+        Some(!keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]))
+    } else if arrows && keys.any_just_pressed([KeyCode::ArrowDown, KeyCode::ArrowRight]) {
+        Some(true)
+    } else if arrows && keys.any_just_pressed([KeyCode::ArrowUp, KeyCode::ArrowLeft]) {
+        Some(false)
+    } else {
+        None
+    };
+
+    if let Some(forward) = forward {
+        let mut indices: Vec<Focusable> = focusable_query.iter().map(|(_, f)| *f).collect();
+        indices.sort();
+        indices.dedup();
+        focused.step(&indices, forward);
+    }
+
+    if keys.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter]) {
+        let entity = focusable_query
+            .iter()
+            .find(|(_, focusable)| focused.0 == Some(**focusable));
+
+        if let Some((entity, _)) = entity {
+            event_writer.send(UiElementActivated(entity));
+        }
+    }
+}
+
+// Uses an Outline rather than the BorderColor, which the slots already use for other things
+fn update_focus_outline(
+    mut commands: Commands,
+    focusable_query: Query<(Entity, &Focusable, Has<Outline>)>,
+    focused: Res<FocusedUiElement>,
+) {
+    for (entity, focusable, has_outline) in focusable_query.iter() {
+        let is_focused = focused.0 == Some(*focusable);
+        if is_focused && !has_outline {
+            commands.entity(entity).insert(Outline::new(
+                FOCUS_OUTLINE_WIDTH,
+                Val::ZERO,
+                FOCUS_OUTLINE_COLOR,
+            ));
+        } else if !is_focused && has_outline {
+            commands.entity(entity).remove::<Outline>();
+        }
+    }
+}
+
+fn activated_entities(event_reader: &mut EventReader<UiElementActivated>) -> Vec<Entity> {
+    event_reader.read().map(|event| event.0).collect()
+}
+
+// Pressed with the mouse, or activated from the keyboard
+fn is_pressed(entity: Entity, interaction: &Interaction, activated: &[Entity]) -> bool {
+    *interaction == Interaction::Pressed || activated.contains(&entity)
+}
+
 fn change_active_menu_tab(
-    menu_tab_query: Query<(&MenuTab, &Interaction)>,
+    mut event_reader: EventReader<UiElementActivated>,
+    menu_tab_query: Query<(Entity, &MenuTab, &Interaction)>,
     active_menu_tab: Res<State<ActiveMenuTab>>,
     mut next_active_menu_tab: ResMut<NextState<ActiveMenuTab>>,
 ) {
-    for (menu_tab, interaction) in menu_tab_query.iter() {
-        if *interaction == Interaction::Pressed {
-            if *menu_tab != active_menu_tab.get().0 {
-                next_active_menu_tab.set(ActiveMenuTab(menu_tab.clone()));
-                break;
-            }
+    let activated = activated_entities(&mut event_reader);
+
+    for (entity, menu_tab, interaction) in menu_tab_query.iter() {
+        if is_pressed(entity, interaction, &activated) && *menu_tab != active_menu_tab.get().0 {
+            next_active_menu_tab.set(ActiveMenuTab(menu_tab.clone()));
+            break;
         }
     }
 }
@@ -170,41 +394,130 @@ fn manage_menu_content(
     mut commands: Commands,
     mut event_reader: EventReader<StateTransitionEvent<ActiveMenuTab>>,
     menu_content_query: Query<Entity, With<MenuContent>>,
+    mut menu_query: Query<&mut Style, With<Menu>>,
     asset_server: Res<AssetServer>,
     inventory: Res<Inventory>,
     active_menu_tab: Res<State<ActiveMenuTab>>,
     game_settings: Res<State<GameSettings>>,
+    hud_layout: Res<HudLayout>,
+    player_stats: Res<PlayerStats>,
+    mut rendered_inventory: ResMut<RenderedInventory>,
+    localization: Res<Localization>,
 ) {
-    for _ in event_reader.read() {
-        if let Ok(entity) = menu_content_query.get_single() {
-            let mut entity_commands = commands.entity(entity);
-            entity_commands.despawn_descendants();
+    // The content is also respawned in the new language whenever it changes
+    let tab_changed = event_reader.read().count() > 0;
+    let language_changed = localization.is_changed() && !localization.is_added();
+    if !tab_changed && !language_changed {
+        return;
+    }
 
-            entity_commands.with_children(|parent| match active_menu_tab.get().0 {
-                MenuTab::Inventory => {
-                    spawn_inventory_menu_content(parent, &asset_server, &inventory);
-                }
-                MenuTab::Settings => spawn_settings_menu_content(parent, &game_settings),
-            });
-        }
+    if let Ok(mut style) = menu_query.get_single_mut() {
+        *style = menu_style(&active_menu_tab.get().0);
+    }
+
+    if let Ok(entity) = menu_content_query.get_single() {
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.despawn_descendants();
+
+        rendered_inventory.0 = Some(inventory.clone());
+        entity_commands.with_children(|parent| match active_menu_tab.get().0 {
+            MenuTab::Inventory => {
+                spawn_inventory_menu_content(parent, &asset_server, &inventory, &localization);
+            }
+            MenuTab::Map => spawn_automap_menu_content(parent),
+            MenuTab::Stats => spawn_stats_menu_content(parent, &player_stats, &localization),
+            MenuTab::Settings => {
+                spawn_settings_menu_content(parent, &game_settings, &hud_layout, &localization)
+            }
+        });
     }
 }
 
-fn update_inventory_menu_content(
+// The slots themselves stay the same, so that their hover state and focus survive,
+// and only the items in the ones that changed are respawned
+pub fn update_inventory_menu_content(
     mut commands: Commands,
     mut event_reader: EventReader<InventoryChanged>,
-    menu_content_query: Query<Entity, With<MenuContent>>,
+    inventory_slot_query: Query<(Entity, &InventorySlot)>,
+    equipment_slot_query: Query<(Entity, &EquipmentSlot)>,
+    mut equipment_slot_text_query: Query<(&EquipmentSlotItemText, &mut Text)>,
     asset_server: Res<AssetServer>,
     inventory: Res<Inventory>,
+    mut rendered_inventory: ResMut<RenderedInventory>,
+    active_menu_tab: Res<State<ActiveMenuTab>>,
+    localization: Res<Localization>,
 ) {
-    for _ in event_reader.read() {
-        if let Ok(entity) = menu_content_query.get_single() {
-            let mut entity_commands = commands.entity(entity);
-            entity_commands.despawn_descendants();
-            entity_commands.with_children(|parent| {
-                spawn_inventory_menu_content(parent, &asset_server, &inventory);
-            });
+    if event_reader.read().count() == 0 || active_menu_tab.get().0 != MenuTab::Inventory {
+        return;
+    }
+
+    // Nothing was spawned yet to update
+    let Some(rendered) = rendered_inventory.0.as_ref() else {
+        return;
+    };
+
+    for (entity, slot) in inventory_slot_query.iter() {
+        let item = &inventory.slots[slot.0];
+        if *item != rendered.slots[slot.0] {
+            respawn_slot_item(&mut commands, entity, item, &asset_server, &localization);
+        }
+    }
+
+    for (entity, slot) in equipment_slot_query.iter() {
+        let item = inventory.equipment.at(&slot.0);
+        if item != rendered.equipment.at(&slot.0) {
+            respawn_slot_item(&mut commands, entity, item, &asset_server, &localization);
+        }
+    }
+
+    for (slot_text, mut text) in equipment_slot_text_query.iter_mut() {
+        let item = inventory.equipment.at(&slot_text.0);
+        if item != rendered.equipment.at(&slot_text.0) {
+            text.sections[0].value = localization.tr(equipment_slot_item_key(item), &[]);
+        }
+    }
+
+    rendered_inventory.0 = Some(inventory.clone());
+}
+
+// The item being dragged stays in its slot, ghosted, until it is placed somewhere
+fn ghost_dragged_slot_item(
+    mut image_query: Query<(&Parent, &mut UiImage), With<SlotItemImage>>,
+    slot_query: Query<(Option<&InventorySlot>, Option<&EquipmentSlot>)>,
+    drag_state: Res<State<DragState>>,
+) {
+    for (parent, mut image) in image_query.iter_mut() {
+        let is_dragged = match (&drag_state.get().0, slot_query.get(parent.get())) {
+            (Dragging::InventorySlot(i), Ok((Some(slot), _))) => slot.0 == *i,
+            (Dragging::EquipmentSlot(name), Ok((_, Some(slot)))) => slot.0 == *name,
+            _ => false,
+        };
+
+        let color = if is_dragged {
+            DRAGGED_SLOT_ITEM_COLOR
+        } else {
+            Color::WHITE
+        };
+        // Only written when it differs, so that unchanged images are not marked as changed
+        if image.color != color {
+            image.color = color;
+        }
+    }
+}
+
+pub fn sort_inventory(
+    button_query: Query<&Interaction, (Changed<Interaction>, With<InventorySortButton>)>,
+    mut inventory: ResMut<Inventory>,
+    mut event_writer: EventWriter<InventoryChanged>,
+) {
+    for interaction in button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
         }
+
+        inventory.sort_and_consolidate();
+        event_writer.send(InventoryChanged);
+        break;
     }
 }
 
@@ -212,11 +525,12 @@ fn spawn_inventory_menu_content(
     child_builder: &mut ChildBuilder,
     asset_server: &Res<AssetServer>,
     inventory: &Res<Inventory>,
+    localization: &Localization,
 ) {
     child_builder.spawn(TextBundle {
         text: Text {
             sections: vec![TextSection::new(
-                "Inventory",
+                localization.tr("menu.inventory", &[]),
                 TextStyle {
                     font_size: 20.0,
                     color: Color::WHITE,
@@ -228,7 +542,36 @@ fn spawn_inventory_menu_content(
         ..default()
     });
 
-    // Inventory slots
+    child_builder
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    display: Display::Flex,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    height: Val::Px(20.0),
+                    padding: UiRect::horizontal(Val::Px(6.0)),
+                    margin: UiRect::top(Val::Px(4.0)),
+                    ..default()
+                },
+                background_color: Color::WHITE.into(),
+                ..default()
+            },
+            InventorySortButton,
+            Name::new("Inventory Sort Button"),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                localization.tr("menu.inventory.sort", &[]),
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::BLACK,
+                    ..default()
+                },
+            ));
+        });
+
+    // Inventory slots, which wrap onto as many rows as the width of the menu needs
     child_builder
         .spawn(NodeBundle {
             style: Style {
@@ -236,6 +579,7 @@ fn spawn_inventory_menu_content(
                 flex_wrap: FlexWrap::Wrap,
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
+                width: Val::Percent(100.0),
                 padding: UiRect::all(Val::Px(10.0)),
                 ..default()
             },
@@ -245,6 +589,7 @@ fn spawn_inventory_menu_content(
             for (i, slot) in inventory.slots.iter().enumerate() {
                 let mut entity_commands = parent.spawn((
                     InventorySlot(i),
+                    Focusable(INVENTORY_SLOT_FOCUS_START + i as u32),
                     RelativeCursorPosition::default(),
                     ButtonBundle {
                         style: Style {
@@ -266,65 +611,7 @@ fn spawn_inventory_menu_content(
 
                 if let Some(item) = slot {
                     entity_commands.with_children(|grandparent| {
-                        grandparent.spawn((
-                            RelativeCursorPosition::default(),
-                            ImageBundle {
-                                image: item.ui_image(asset_server),
-                                style: item_style(),
-                                ..default()
-                            },
-                        ));
-
-                        grandparent.spawn((
-                            VisibleOnParentHover::default(),
-                            TextBundle {
-                                visibility: Visibility::Hidden,
-                                text: Text {
-                                    sections: vec![TextSection::new(
-                                        item.name.to_string(),
-                                        TextStyle {
-                                            font_size: 22.0,
-                                            color: Color::WHITE,
-                                            ..default()
-                                        },
-                                    )],
-                                    ..default()
-                                },
-                                style: Style {
-                                    position_type: PositionType::Absolute,
-                                    top: Val::Percent(100.0),
-                                    right: Val::Percent(0.0),
-                                    ..default()
-                                },
-                                background_color: Color::BLACK.into(),
-                                z_index: ZIndex::Global(10),
-                                ..default()
-                            },
-                        ));
-
-                        if item.amt > 1 {
-                            grandparent.spawn(TextBundle {
-                                text: Text {
-                                    sections: vec![TextSection::new(
-                                        item.amt.to_string(),
-                                        TextStyle {
-                                            font_size: 22.0,
-                                            color: Color::WHITE,
-                                            ..default()
-                                        },
-                                    )],
-                                    ..default()
-                                },
-                                style: Style {
-                                    position_type: PositionType::Absolute,
-                                    bottom: Val::Px(2.0),
-                                    right: Val::Px(2.0),
-                                    ..default()
-                                },
-                                background_color: Color::BLACK.into(),
-                                ..default()
-                            });
-                        }
+                        spawn_slot_item(grandparent, item, asset_server, localization);
                     });
                 }
             }
@@ -333,7 +620,7 @@ fn spawn_inventory_menu_content(
     child_builder.spawn(TextBundle {
         text: Text {
             sections: vec![TextSection::new(
-                "Equipment",
+                localization.tr("menu.equipment", &[]),
                 TextStyle {
                     font_size: 20.0,
                     color: Color::WHITE,
@@ -359,121 +646,178 @@ fn spawn_inventory_menu_content(
             ..default()
         })
         .with_children(|parent| {
-            for name in EquipmentSlotName::iter() {
-                let mut entity_commands = parent.spawn((
-                    EquipmentSlot(name.clone()),
-                    RelativeCursorPosition::default(),
-                    ButtonBundle {
+            for (i, name) in EquipmentSlotName::iter().enumerate() {
+                let item = inventory.equipment.at(&name);
+
+                parent
+                    .spawn(NodeBundle {
                         style: Style {
-                            position_type: PositionType::Relative,
                             display: Display::Flex,
-                            justify_content: JustifyContent::Center,
+                            flex_direction: FlexDirection::Column,
                             align_items: AlignItems::Center,
-                            height: Val::Px(50.0),
-                            width: Val::Px(50.0),
-                            margin: UiRect::all(Val::Px(5.0)),
-                            border: UiRect::all(Val::Px(2.0)),
+                            margin: UiRect::horizontal(Val::Px(10.0)),
                             ..default()
                         },
-                        border_color: Color::WHITE.into(),
                         ..default()
-                    },
-                    Name::new(format!("Equipment slot {}", name)),
-                ));
-
-                if let Some(item) = inventory.equipment.at(&name) {
-                    entity_commands.with_children(|grandparent| {
-                        grandparent.spawn((
-                            RelativeCursorPosition::default(),
-                            ImageBundle {
-                                image: item.ui_image(asset_server),
-                                style: Style {
-                                    height: Val::Px(40.0),
-                                    width: Val::Px(40.0),
-                                    ..default()
-                                },
-                                ..default()
-                            },
+                    })
+                    .with_children(|column| {
+                        column.spawn(equipment_slot_text(
+                            &localization.tr(name.display_key(), &[]),
                         ));
 
-                        grandparent.spawn((
-                            VisibleOnParentHover::default(),
-                            TextBundle {
-                                visibility: Visibility::Hidden,
-                                text: Text {
-                                    sections: vec![TextSection::new(
-                                        item.name.to_string(),
-                                        TextStyle {
-                                            font_size: 22.0,
-                                            color: Color::WHITE,
-                                            ..default()
-                                        },
-                                    )],
-                                    ..default()
-                                },
+                        let mut entity_commands = column.spawn((
+                            EquipmentSlot(name.clone()),
+                            Focusable(EQUIPMENT_SLOT_FOCUS_START + i as u32),
+                            RelativeCursorPosition::default(),
+                            ButtonBundle {
                                 style: Style {
-                                    position_type: PositionType::Absolute,
-                                    top: Val::Percent(100.0),
-                                    right: Val::Percent(0.0),
+                                    position_type: PositionType::Relative,
+                                    display: Display::Flex,
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    height: Val::Px(50.0),
+                                    width: Val::Px(50.0),
+                                    margin: UiRect::all(Val::Px(5.0)),
+                                    border: UiRect::all(Val::Px(2.0)),
                                     ..default()
                                 },
-                                background_color: Color::BLACK.into(),
-                                z_index: ZIndex::Global(10),
+                                border_color: Color::WHITE.into(),
                                 ..default()
                             },
+                            Name::new(format!("Equipment slot {}", name)),
                         ));
 
-                        if item.amt > 1 {
-                            grandparent.spawn(TextBundle {
-                                text: Text {
-                                    sections: vec![TextSection::new(
-                                        item.amt.to_string(),
-                                        TextStyle {
-                                            font_size: 22.0,
-                                            color: Color::WHITE,
-                                            ..default()
-                                        },
-                                    )],
-                                    ..default()
-                                },
-                                style: Style {
-                                    position_type: PositionType::Absolute,
-                                    bottom: Val::Px(2.0),
-                                    right: Val::Px(2.0),
-                                    ..default()
-                                },
-                                background_color: Color::BLACK.into(),
-                                ..default()
+                        if let Some(item) = item {
+                            entity_commands.with_children(|grandparent| {
+                                spawn_slot_item(grandparent, item, asset_server, localization);
                             });
                         }
+
+                        column.spawn((
+                            EquipmentSlotItemText(name),
+                            equipment_slot_text(
+                                &localization.tr(equipment_slot_item_key(item), &[]),
+                            ),
+                        ));
                     });
-                }
             }
         });
 }
 
-fn spawn_settings_menu_content(
+fn equipment_slot_item_key(item: &Option<Item>) -> &'static str {
+    item.map_or("menu.equipment.empty", |item| item.name.display_key())
+}
+
+// The image, tooltip and amount of an item in an inventory or equipment slot
+fn spawn_slot_item(
     child_builder: &mut ChildBuilder,
-    game_settings: &Res<State<GameSettings>>,
+    item: &Item,
+    asset_server: &Res<AssetServer>,
+    localization: &Localization,
 ) {
-    child_builder.spawn(TextBundle {
-        text: Text {
-            sections: vec![TextSection::new(
-                "Settings",
-                TextStyle {
-                    font_size: 20.0,
-                    ..default()
-                },
-            )],
+    child_builder.spawn((
+        SlotItemImage,
+        RelativeCursorPosition::default(),
+        ImageBundle {
+            image: item.ui_image(asset_server),
+            style: item_style(),
             ..default()
         },
-        ..default()
-    });
+    ));
+
+    child_builder.spawn((
+        VisibleOnParentHover::default(),
+        TextBundle {
+            visibility: Visibility::Hidden,
+            text: Text {
+                sections: vec![TextSection::new(
+                    item_tooltip_text(item, localization),
+                    TextStyle {
+                        font_size: 22.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                )],
+                ..default()
+            },
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(100.0),
+                right: Val::Percent(0.0),
+                ..default()
+            },
+            background_color: Color::BLACK.into(),
+            z_index: ZIndex::Global(10),
+            ..default()
+        },
+    ));
+
+    if item.amt > 1 {
+        child_builder.spawn(TextBundle {
+            text: Text {
+                sections: vec![TextSection::new(
+                    item.amt.to_string(),
+                    TextStyle {
+                        font_size: 22.0,
+                        color: item_amt_color(item),
+                        ..default()
+                    },
+                )],
+                ..default()
+            },
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(2.0),
+                right: Val::Px(2.0),
+                ..default()
+            },
+            background_color: Color::BLACK.into(),
+            ..default()
+        });
+    }
+}
+
+fn respawn_slot_item(
+    commands: &mut Commands,
+    slot: Entity,
+    item: &Option<Item>,
+    asset_server: &Res<AssetServer>,
+    localization: &Localization,
+) {
+    let mut entity_commands = commands.entity(slot);
+    entity_commands.despawn_descendants();
+
+    if let Some(item) = item {
+        entity_commands.with_children(|parent| {
+            spawn_slot_item(parent, item, asset_server, localization);
+        });
+    }
+}
+
+fn equipment_slot_text(value: &str) -> TextBundle {
+    TextBundle::from_section(
+        value,
+        TextStyle {
+            font_size: 16.0,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+}
 
+// Label followed by a row of numbered buttons, one of which is highlighted
+fn spawn_dist_buttons<B: Bundle>(
+    child_builder: &mut ChildBuilder,
+    label: String,
+    range: RangeInclusive<u32>,
+    selected: u32,
+    focus_start: u32,
+    button: impl Fn(u32) -> B,
+) {
     child_builder.spawn(TextBundle {
         text: Text {
             sections: vec![TextSection::new(
-                "Render Distance:",
+                label,
                 TextStyle {
                     font_size: 16.0,
                     ..default()
@@ -503,13 +847,7 @@ fn spawn_settings_menu_content(
             ..default()
         })
         .with_children(|parent| {
-            for i in 0..=5u32 {
-                let background_color = if i == game_settings.get().chunk_render_dist.0 {
-                    Color::linear_rgba(0.0, 0.0, 0.4, 1.0).into()
-                } else {
-                    Color::WHITE.into()
-                };
-
+            for i in range {
                 parent
                     .spawn((
                         ButtonBundle {
@@ -521,10 +859,11 @@ fn spawn_settings_menu_content(
                                 width: Val::Px(20.0),
                                 ..default()
                             },
-                            background_color,
+                            background_color: dist_button_background_color(i == selected),
                             ..default()
                         },
-                        RenderDistButton(i),
+                        button(i),
+                        Focusable(focus_start + i),
                     ))
                     .with_children(|grandparent| {
                         grandparent.spawn(TextBundle {
@@ -546,58 +885,1485 @@ fn spawn_settings_menu_content(
         });
 }
 
-fn change_menu_tabs_background_color(
-    mut event_reader: EventReader<StateTransitionEvent<ActiveMenuTab>>,
-    mut menu_tab_query: Query<(&MenuTab, &mut BackgroundColor)>,
-    active_menu_tab: Res<State<ActiveMenuTab>>,
-) {
-    for _ in event_reader.read() {
-        for (tab, mut background_color) in menu_tab_query.iter_mut() {
-            *background_color = get_tab_background_color(tab, active_menu_tab.get());
-        }
+fn dist_button_background_color(selected: bool) -> BackgroundColor {
+    if selected {
+        Color::linear_rgba(0.0, 0.0, 0.4, 1.0).into()
+    } else {
+        Color::WHITE.into()
     }
 }
 
-fn get_tab_background_color(tab: &MenuTab, active_menu_tab: &ActiveMenuTab) -> BackgroundColor {
-    let red = if *tab == active_menu_tab.0 { 0.3 } else { 0.7 };
-    Color::linear_rgba(red, 0.0, 0.0, 1.0).into()
-}
-
-fn change_render_dist(
-    mut rd_event_writer: EventWriter<RenderDistChanged>,
-    button_query: Query<(&RenderDistButton, &Interaction)>,
-    game_settings: Res<State<GameSettings>>,
+fn spawn_settings_menu_content(
+    child_builder: &mut ChildBuilder,
+    game_settings: &Res<State<GameSettings>>,
+    hud_layout: &HudLayout,
+    localization: &Localization,
+) {
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                localization.tr("menu.settings", &[]),
+                TextStyle {
+                    font_size: 20.0,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        ..default()
+    });
+
+    spawn_dist_buttons(
+        child_builder,
+        localization.tr("menu.settings.render_distance", &[]),
+        0..=5,
+        game_settings.get().chunk_render_dist.0,
+        RENDER_DIST_BUTTON_FOCUS_START,
+        RenderDistButton,
+    );
+
+    // Starts at 1, since nothing could be stood on without any colliders
+    spawn_dist_buttons(
+        child_builder,
+        localization.tr("menu.settings.physics_distance", &[]),
+        1..=5,
+        game_settings.get().physics_dist().0,
+        PHYSICS_DIST_BUTTON_FOCUS_START,
+        PhysicsDistButton,
+    );
+
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                localization.tr("menu.settings.fov", &[]),
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        style: Style {
+            margin: UiRect {
+                top: Val::Px(10.0),
+                bottom: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        },
+        ..default()
+    });
+
+    spawn_stepper(
+        child_builder,
+        (FovButton(-1), FovButton(1)),
+        FovText,
+        &fov_text(game_settings.get()),
+    );
+
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                localization.tr("menu.settings.mouse_sensitivity", &[]),
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        style: Style {
+            margin: UiRect {
+                top: Val::Px(10.0),
+                bottom: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        },
+        ..default()
+    });
+
+    spawn_stepper(
+        child_builder,
+        (MouseSensitivityButton(-1), MouseSensitivityButton(1)),
+        MouseSensitivityText,
+        &mouse_sensitivity_text(game_settings.get()),
+    );
+
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                localization.tr("menu.settings.ui_scale", &[]),
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        style: Style {
+            margin: UiRect {
+                top: Val::Px(10.0),
+                bottom: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        },
+        ..default()
+    });
+
+    spawn_stepper(
+        child_builder,
+        (UiScaleButton(-1), UiScaleButton(1)),
+        UiScaleText,
+        &ui_scale_text(game_settings.get()),
+    );
+
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                localization.tr("menu.settings.autosave", &[]),
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        style: Style {
+            margin: UiRect {
+                top: Val::Px(10.0),
+                bottom: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        },
+        ..default()
+    });
+
+    spawn_stepper(
+        child_builder,
+        (AutosaveButton(-1), AutosaveButton(1)),
+        AutosaveText,
+        &autosave_interval_text(game_settings.get()),
+    );
+
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                localization.tr("menu.settings.structure_compass", &[]),
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        style: Style {
+            margin: UiRect {
+                top: Val::Px(10.0),
+                bottom: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        },
+        ..default()
+    });
+
+    child_builder
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    display: Display::Flex,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    height: Val::Px(20.0),
+                    width: Val::Px(50.0),
+                    ..default()
+                },
+                background_color: Color::WHITE.into(),
+                ..default()
+            },
+            StructureCompassButton,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text {
+                    sections: vec![TextSection::new(
+                        structure_compass_button_text(game_settings.get(), localization),
+                        TextStyle {
+                            font_size: 20.0,
+                            color: Color::BLACK,
+                            ..default()
+                        },
+                    )],
+                    ..default()
+                },
+                ..default()
+            });
+        });
+
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                localization.tr("menu.settings.spectator_mode", &[]),
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        style: Style {
+            margin: UiRect {
+                top: Val::Px(10.0),
+                bottom: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        },
+        ..default()
+    });
+
+    child_builder
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    display: Display::Flex,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    height: Val::Px(20.0),
+                    width: Val::Px(50.0),
+                    ..default()
+                },
+                background_color: Color::WHITE.into(),
+                ..default()
+            },
+            SpectatorModeButton,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text {
+                    sections: vec![TextSection::new(
+                        spectator_mode_button_text(game_settings.get(), localization),
+                        TextStyle {
+                            font_size: 20.0,
+                            color: Color::BLACK,
+                            ..default()
+                        },
+                    )],
+                    ..default()
+                },
+                ..default()
+            });
+        });
+
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                localization.tr("menu.settings.damage_effects", &[]),
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        style: Style {
+            margin: UiRect {
+                top: Val::Px(10.0),
+                bottom: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        },
+        ..default()
+    });
+
+    child_builder
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    display: Display::Flex,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    height: Val::Px(20.0),
+                    width: Val::Px(50.0),
+                    ..default()
+                },
+                background_color: Color::WHITE.into(),
+                ..default()
+            },
+            DamageEffectsButton,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text {
+                    sections: vec![TextSection::new(
+                        damage_effects_button_text(game_settings.get(), localization),
+                        TextStyle {
+                            font_size: 20.0,
+                            color: Color::BLACK,
+                            ..default()
+                        },
+                    )],
+                    ..default()
+                },
+                ..default()
+            });
+        });
+
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                localization.tr("menu.settings.hit_effects", &[]),
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        style: Style {
+            margin: UiRect {
+                top: Val::Px(10.0),
+                bottom: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        },
+        ..default()
+    });
+
+    child_builder
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    display: Display::Flex,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    height: Val::Px(20.0),
+                    width: Val::Px(50.0),
+                    ..default()
+                },
+                background_color: Color::WHITE.into(),
+                ..default()
+            },
+            HitEffectsButton,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text {
+                    sections: vec![TextSection::new(
+                        hit_effects_button_text(game_settings.get(), localization),
+                        TextStyle {
+                            font_size: 20.0,
+                            color: Color::BLACK,
+                            ..default()
+                        },
+                    )],
+                    ..default()
+                },
+                ..default()
+            });
+        });
+
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                localization.tr("menu.settings.auto_pick_up", &[]),
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        style: Style {
+            margin: UiRect {
+                top: Val::Px(10.0),
+                bottom: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        },
+        ..default()
+    });
+
+    child_builder
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    display: Display::Flex,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    height: Val::Px(20.0),
+                    width: Val::Px(50.0),
+                    ..default()
+                },
+                background_color: Color::WHITE.into(),
+                ..default()
+            },
+            AutoPickUpButton,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text {
+                    sections: vec![TextSection::new(
+                        auto_pick_up_button_text(game_settings.get(), localization),
+                        TextStyle {
+                            font_size: 20.0,
+                            color: Color::BLACK,
+                            ..default()
+                        },
+                    )],
+                    ..default()
+                },
+                ..default()
+            });
+        });
+
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                localization.tr("menu.settings.crosshair", &[]),
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        style: Style {
+            margin: UiRect {
+                top: Val::Px(10.0),
+                bottom: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        },
+        ..default()
+    });
+
+    child_builder
+        .spawn(NodeBundle {
+            style: Style {
+                display: Display::Flex,
+                column_gap: Val::Px(6.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            spawn_hud_layout_button(
+                parent,
+                CrosshairStyleButton,
+                &localization.tr(game_settings.get().crosshair_style.display_key(), &[]),
+                80.0,
+            );
+            spawn_hud_layout_button(
+                parent,
+                CrosshairSizeButton,
+                &localization.tr(game_settings.get().crosshair_size.display_key(), &[]),
+                80.0,
+            );
+        });
+
+    // Only affects chunks spawned after the change
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                localization.tr("menu.settings.decor", &[]),
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        style: Style {
+            margin: UiRect {
+                top: Val::Px(10.0),
+                bottom: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        },
+        ..default()
+    });
+
+    spawn_hud_layout_button(
+        child_builder,
+        DecorDensityButton,
+        &localization.tr(game_settings.get().decor_density.display_key(), &[]),
+        80.0,
+    );
+
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                localization.tr("menu.settings.light_fuel", &[]),
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        style: Style {
+            margin: UiRect {
+                top: Val::Px(10.0),
+                bottom: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        },
+        ..default()
+    });
+
+    spawn_hud_layout_button(
+        child_builder,
+        LightFuelModeButton,
+        &localization.tr(game_settings.get().light_fuel.display_key(), &[]),
+        80.0,
+    );
+
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                localization.tr("menu.settings.movement", &[]),
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        style: Style {
+            margin: UiRect {
+                top: Val::Px(10.0),
+                bottom: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        },
+        ..default()
+    });
+
+    spawn_hud_layout_button(
+        child_builder,
+        MovementStyleButton,
+        &localization.tr(game_settings.get().movement_style.display_key(), &[]),
+        80.0,
+    );
+
+    // Only affects chunks spawned and damage taken after the change
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                localization.tr("menu.settings.difficulty", &[]),
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        style: Style {
+            margin: UiRect {
+                top: Val::Px(10.0),
+                bottom: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        },
+        ..default()
+    });
+
+    spawn_hud_layout_button(
+        child_builder,
+        DifficultyButton,
+        &localization.tr(game_settings.get().difficulty.display_key(), &[]),
+        80.0,
+    );
+
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                localization.tr("menu.settings.language", &[]),
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        style: Style {
+            margin: UiRect {
+                top: Val::Px(10.0),
+                bottom: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        },
+        ..default()
+    });
+
+    spawn_hud_layout_button(
+        child_builder,
+        LanguageButton,
+        game_settings.get().language.label(),
+        80.0,
+    );
+
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                localization.tr("menu.settings.hud", &[]),
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        style: Style {
+            margin: UiRect {
+                top: Val::Px(10.0),
+                bottom: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        },
+        ..default()
+    });
+
+    for widget_layout in hud_layout.widgets.iter() {
+        child_builder
+            .spawn(NodeBundle {
+                style: Style {
+                    display: Display::Flex,
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(10.0),
+                    width: Val::Percent(90.0),
+                    margin: UiRect::bottom(Val::Px(4.0)),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                parent.spawn(TextBundle {
+                    text: Text {
+                        sections: vec![TextSection::new(
+                            localization.tr(widget_layout.widget.display_key(), &[]),
+                            TextStyle {
+                                font_size: 16.0,
+                                ..default()
+                            },
+                        )],
+                        ..default()
+                    },
+                    ..default()
+                });
+
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            display: Display::Flex,
+                            column_gap: Val::Px(6.0),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|grandparent| {
+                        spawn_hud_layout_button(
+                            grandparent,
+                            HudWidgetToggleButton(widget_layout.widget),
+                            &hud_widget_toggle_button_text(widget_layout, localization),
+                            50.0,
+                        );
+                        spawn_hud_layout_button(
+                            grandparent,
+                            HudWidgetAnchorButton(widget_layout.widget),
+                            &localization.tr(widget_layout.anchor.display_key(), &[]),
+                            130.0,
+                        );
+                    });
+            });
+    }
+}
+
+fn spawn_hud_layout_button(
+    child_builder: &mut ChildBuilder,
+    button: impl Component,
+    text: &str,
+    width: f32,
+) {
+    child_builder
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    display: Display::Flex,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    height: Val::Px(20.0),
+                    width: Val::Px(width),
+                    ..default()
+                },
+                background_color: Color::WHITE.into(),
+                ..default()
+            },
+            button,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text {
+                    sections: vec![TextSection::new(
+                        text,
+                        TextStyle {
+                            font_size: 16.0,
+                            color: Color::BLACK,
+                            ..default()
+                        },
+                    )],
+                    ..default()
+                },
+                ..default()
+            });
+        });
+}
+
+fn change_menu_tabs_background_color(
+    mut event_reader: EventReader<StateTransitionEvent<ActiveMenuTab>>,
+    mut menu_tab_query: Query<(&MenuTab, &mut BackgroundColor)>,
+    active_menu_tab: Res<State<ActiveMenuTab>>,
+) {
+    for _ in event_reader.read() {
+        for (tab, mut background_color) in menu_tab_query.iter_mut() {
+            *background_color = get_tab_background_color(tab, active_menu_tab.get());
+        }
+    }
+}
+
+fn get_tab_background_color(tab: &MenuTab, active_menu_tab: &ActiveMenuTab) -> BackgroundColor {
+    let red = if *tab == active_menu_tab.0 { 0.3 } else { 0.7 };
+    Color::linear_rgba(red, 0.0, 0.0, 1.0).into()
+}
+
+// Row of a - button, the current value, and a + button
+fn spawn_stepper(
+    child_builder: &mut ChildBuilder,
+    (minus_button, plus_button): (impl Component, impl Component),
+    text_marker: impl Component,
+    text: &str,
+) {
+    child_builder
+        .spawn(NodeBundle {
+            style: Style {
+                display: Display::Flex,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(6.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            spawn_hud_layout_button(parent, minus_button, "-", 20.0);
+            parent.spawn((
+                TextBundle {
+                    text: Text {
+                        sections: vec![TextSection::new(
+                            text,
+                            TextStyle {
+                                font_size: 16.0,
+                                ..default()
+                            },
+                        )],
+                        ..default()
+                    },
+                    style: Style {
+                        width: Val::Px(50.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                text_marker,
+            ));
+            spawn_hud_layout_button(parent, plus_button, "+", 20.0);
+        });
+}
+
+fn fov_text(game_settings: &GameSettings) -> String {
+    format!("{}°", game_settings.fov_degrees.get().round())
+}
+
+fn mouse_sensitivity_text(game_settings: &GameSettings) -> String {
+    format!("{:.1}x", game_settings.mouse_sensitivity.get())
+}
+
+fn change_camera_settings(
+    fov_button_query: Query<(&FovButton, &Interaction), Changed<Interaction>>,
+    ms_button_query: Query<(&MouseSensitivityButton, &Interaction), Changed<Interaction>>,
+    game_settings: Res<State<GameSettings>>,
+    mut next_game_settings: ResMut<NextState<GameSettings>>,
+) {
+    let mut new_game_settings = *game_settings.get();
+
+    for (button, interaction) in fov_button_query.iter() {
+        if *interaction == Interaction::Pressed {
+            new_game_settings.fov_degrees = new_game_settings.fov_degrees.step(button.0);
+        }
+    }
+    for (button, interaction) in ms_button_query.iter() {
+        if *interaction == Interaction::Pressed {
+            new_game_settings.mouse_sensitivity =
+                new_game_settings.mouse_sensitivity.step(button.0);
+        }
+    }
+
+    if new_game_settings != *game_settings.get() {
+        next_game_settings.set(new_game_settings);
+    }
+}
+
+fn update_camera_settings_text(
+    mut event_reader: EventReader<StateTransitionEvent<GameSettings>>,
+    mut fov_text_query: Query<&mut Text, (With<FovText>, Without<MouseSensitivityText>)>,
+    mut ms_text_query: Query<&mut Text, (With<MouseSensitivityText>, Without<FovText>)>,
+    game_settings: Res<State<GameSettings>>,
+) {
+    if event_reader.read().count() == 0 {
+        return;
+    }
+
+    for mut text in fov_text_query.iter_mut() {
+        text.sections[0].value = fov_text(game_settings.get());
+    }
+    for mut text in ms_text_query.iter_mut() {
+        text.sections[0].value = mouse_sensitivity_text(game_settings.get());
+    }
+}
+
+fn ui_scale_text(game_settings: &GameSettings) -> String {
+    format!("{:.2}x", game_settings.ui_scale.get())
+}
+
+fn change_ui_scale(
+    button_query: Query<(&UiScaleButton, &Interaction), Changed<Interaction>>,
+    game_settings: Res<State<GameSettings>>,
+    mut next_game_settings: ResMut<NextState<GameSettings>>,
+) {
+    let mut new_game_settings = *game_settings.get();
+
+    for (button, interaction) in button_query.iter() {
+        if *interaction == Interaction::Pressed {
+            new_game_settings.ui_scale = new_game_settings.ui_scale.step(button.0);
+        }
+    }
+
+    if new_game_settings != *game_settings.get() {
+        next_game_settings.set(new_game_settings);
+    }
+}
+
+fn update_ui_scale_text(
+    mut event_reader: EventReader<StateTransitionEvent<GameSettings>>,
+    mut text_query: Query<&mut Text, With<UiScaleText>>,
+    game_settings: Res<State<GameSettings>>,
+) {
+    if event_reader.read().count() == 0 {
+        return;
+    }
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = ui_scale_text(game_settings.get());
+    }
+}
+
+fn autosave_interval_text(game_settings: &GameSettings) -> String {
+    format!("{:.1} min", game_settings.autosave_minutes.get())
+}
+
+fn change_autosave_interval(
+    button_query: Query<(&AutosaveButton, &Interaction), Changed<Interaction>>,
+    game_settings: Res<State<GameSettings>>,
+    mut next_game_settings: ResMut<NextState<GameSettings>>,
+) {
+    let mut new_game_settings = *game_settings.get();
+
+    for (button, interaction) in button_query.iter() {
+        if *interaction == Interaction::Pressed {
+            new_game_settings.autosave_minutes = new_game_settings.autosave_minutes.step(button.0);
+        }
+    }
+
+    if new_game_settings != *game_settings.get() {
+        next_game_settings.set(new_game_settings);
+    }
+}
+
+fn update_autosave_interval_text(
+    mut event_reader: EventReader<StateTransitionEvent<GameSettings>>,
+    mut text_query: Query<&mut Text, With<AutosaveText>>,
+    game_settings: Res<State<GameSettings>>,
+) {
+    if event_reader.read().count() == 0 {
+        return;
+    }
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = autosave_interval_text(game_settings.get());
+    }
+}
+
+fn on_off_text(on: bool, localization: &Localization) -> String {
+    localization.tr(if on { "menu.on" } else { "menu.off" }, &[])
+}
+
+pub fn change_render_dist(
+    mut event_reader: EventReader<UiElementActivated>,
+    mut rd_event_writer: EventWriter<RenderDistChanged>,
+    render_button_query: Query<(Entity, &RenderDistButton, &Interaction)>,
+    physics_button_query: Query<(Entity, &PhysicsDistButton, &Interaction)>,
+    game_settings: Res<State<GameSettings>>,
+    mut next_game_settings: ResMut<NextState<GameSettings>>,
+) {
+    let activated = activated_entities(&mut event_reader);
+    let mut new_game_settings = *game_settings.get();
+
+    let render_button = render_button_query
+        .iter()
+        .find(|(entity, _, interaction)| is_pressed(*entity, interaction, &activated));
+    if let Some((_, button, _)) = render_button {
+        new_game_settings.chunk_render_dist =
+            ChunkRenderDist(button.0, button.0, button.0).clamped(MAX_CHUNK_RENDER_DIST);
+    }
+
+    // Never past the render distance, which is where it is used clamped to anyway
+    let physics_button = physics_button_query
+        .iter()
+        .find(|(entity, _, interaction)| is_pressed(*entity, interaction, &activated));
+    if let Some((_, button, _)) = physics_button {
+        new_game_settings.chunk_physics_dist =
+            ChunkPhysicsDist::from(button.0.min(new_game_settings.chunk_render_dist.0));
+    }
+
+    if new_game_settings != *game_settings.get() {
+        next_game_settings.set(new_game_settings);
+        rd_event_writer.send(RenderDistChanged);
+    }
+}
+
+fn change_render_dist_buttons_background_color(
+    mut event_reader: EventReader<StateTransitionEvent<GameSettings>>,
+    mut render_buttons_query: Query<(&RenderDistButton, &mut BackgroundColor)>,
+    mut physics_buttons_query: Query<
+        (&PhysicsDistButton, &mut BackgroundColor),
+        Without<RenderDistButton>,
+    >,
+    game_settings: Res<State<GameSettings>>,
+) {
+    for _ in event_reader.read() {
+        let game_settings = game_settings.get();
+        for (button, mut background_color) in render_buttons_query.iter_mut() {
+            *background_color =
+                dist_button_background_color(button.0 == game_settings.chunk_render_dist.0);
+        }
+        for (button, mut background_color) in physics_buttons_query.iter_mut() {
+            *background_color =
+                dist_button_background_color(button.0 == game_settings.physics_dist().0);
+        }
+    }
+}
+
+fn structure_compass_button_text(
+    game_settings: &GameSettings,
+    localization: &Localization,
+) -> String {
+    on_off_text(game_settings.show_structure_compass, localization)
+}
+
+fn toggle_structure_compass(
+    button_query: Query<&Interaction, (Changed<Interaction>, With<StructureCompassButton>)>,
+    game_settings: Res<State<GameSettings>>,
+    mut next_game_settings: ResMut<NextState<GameSettings>>,
+) {
+    for interaction in button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let mut new_game_settings = *game_settings.get();
+        new_game_settings.show_structure_compass = !new_game_settings.show_structure_compass;
+
+        next_game_settings.set(new_game_settings);
+        break;
+    }
+}
+
+fn update_structure_compass_button_text(
+    mut event_reader: EventReader<StateTransitionEvent<GameSettings>>,
+    button_query: Query<&Children, With<StructureCompassButton>>,
+    mut text_query: Query<&mut Text>,
+    game_settings: Res<State<GameSettings>>,
+    localization: Res<Localization>,
+) {
+    for _ in event_reader.read() {
+        for children in button_query.iter() {
+            let mut iter = text_query.iter_many_mut(children);
+            while let Some(mut text) = iter.fetch_next() {
+                for section in text.sections.iter_mut() {
+                    section.value =
+                        structure_compass_button_text(game_settings.get(), &localization);
+                }
+            }
+        }
+    }
+}
+
+fn spectator_mode_button_text(game_settings: &GameSettings, localization: &Localization) -> String {
+    on_off_text(game_settings.enable_spectator_mode, localization)
+}
+
+fn toggle_spectator_mode(
+    button_query: Query<&Interaction, (Changed<Interaction>, With<SpectatorModeButton>)>,
+    game_settings: Res<State<GameSettings>>,
+    mut next_game_settings: ResMut<NextState<GameSettings>>,
+) {
+    for interaction in button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let mut new_game_settings = *game_settings.get();
+        new_game_settings.enable_spectator_mode = !new_game_settings.enable_spectator_mode;
+
+        next_game_settings.set(new_game_settings);
+        break;
+    }
+}
+
+fn update_spectator_mode_button_text(
+    mut event_reader: EventReader<StateTransitionEvent<GameSettings>>,
+    button_query: Query<&Children, With<SpectatorModeButton>>,
+    mut text_query: Query<&mut Text>,
+    game_settings: Res<State<GameSettings>>,
+    localization: Res<Localization>,
+) {
+    for _ in event_reader.read() {
+        for children in button_query.iter() {
+            let mut iter = text_query.iter_many_mut(children);
+            while let Some(mut text) = iter.fetch_next() {
+                for section in text.sections.iter_mut() {
+                    section.value = spectator_mode_button_text(game_settings.get(), &localization);
+                }
+            }
+        }
+    }
+}
+
+fn damage_effects_button_text(game_settings: &GameSettings, localization: &Localization) -> String {
+    on_off_text(!game_settings.disable_damage_effects, localization)
+}
+
+fn toggle_damage_effects(
+    button_query: Query<&Interaction, (Changed<Interaction>, With<DamageEffectsButton>)>,
+    game_settings: Res<State<GameSettings>>,
+    mut next_game_settings: ResMut<NextState<GameSettings>>,
+) {
+    for interaction in button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let mut new_game_settings = *game_settings.get();
+        new_game_settings.disable_damage_effects = !new_game_settings.disable_damage_effects;
+
+        next_game_settings.set(new_game_settings);
+        break;
+    }
+}
+
+fn update_damage_effects_button_text(
+    mut event_reader: EventReader<StateTransitionEvent<GameSettings>>,
+    button_query: Query<&Children, With<DamageEffectsButton>>,
+    mut text_query: Query<&mut Text>,
+    game_settings: Res<State<GameSettings>>,
+    localization: Res<Localization>,
+) {
+    for _ in event_reader.read() {
+        for children in button_query.iter() {
+            let mut iter = text_query.iter_many_mut(children);
+            while let Some(mut text) = iter.fetch_next() {
+                for section in text.sections.iter_mut() {
+                    section.value = damage_effects_button_text(game_settings.get(), &localization);
+                }
+            }
+        }
+    }
+}
+
+fn hit_effects_button_text(game_settings: &GameSettings, localization: &Localization) -> String {
+    on_off_text(!game_settings.disable_hit_effects, localization)
+}
+
+fn toggle_hit_effects(
+    button_query: Query<&Interaction, (Changed<Interaction>, With<HitEffectsButton>)>,
+    game_settings: Res<State<GameSettings>>,
     mut next_game_settings: ResMut<NextState<GameSettings>>,
 ) {
-    let rd = game_settings.get().chunk_render_dist;
+    for interaction in button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let mut new_game_settings = *game_settings.get();
+        new_game_settings.disable_hit_effects = !new_game_settings.disable_hit_effects;
+
+        next_game_settings.set(new_game_settings);
+        break;
+    }
+}
+
+fn update_hit_effects_button_text(
+    mut event_reader: EventReader<StateTransitionEvent<GameSettings>>,
+    button_query: Query<&Children, With<HitEffectsButton>>,
+    mut text_query: Query<&mut Text>,
+    game_settings: Res<State<GameSettings>>,
+    localization: Res<Localization>,
+) {
+    for _ in event_reader.read() {
+        for children in button_query.iter() {
+            let mut iter = text_query.iter_many_mut(children);
+            while let Some(mut text) = iter.fetch_next() {
+                for section in text.sections.iter_mut() {
+                    section.value = hit_effects_button_text(game_settings.get(), &localization);
+                }
+            }
+        }
+    }
+}
 
-    for (button, interaction) in button_query.iter() {
-        let rd_does_match = button.0 == rd.0 && button.0 == rd.1 && button.0 == rd.2;
-        if *interaction != Interaction::Pressed || rd_does_match {
+fn auto_pick_up_button_text(game_settings: &GameSettings, localization: &Localization) -> String {
+    on_off_text(game_settings.auto_pick_up_items, localization)
+}
+
+fn toggle_auto_pick_up(
+    button_query: Query<&Interaction, (Changed<Interaction>, With<AutoPickUpButton>)>,
+    game_settings: Res<State<GameSettings>>,
+    mut next_game_settings: ResMut<NextState<GameSettings>>,
+) {
+    for interaction in button_query.iter() {
+        if *interaction != Interaction::Pressed {
             continue;
         }
 
-        let mut new_game_settings = game_settings.clone();
-        new_game_settings.chunk_render_dist = ChunkRenderDist(button.0, button.0, button.0);
+        let mut new_game_settings = *game_settings.get();
+        new_game_settings.auto_pick_up_items = !new_game_settings.auto_pick_up_items;
 
         next_game_settings.set(new_game_settings);
-        rd_event_writer.send(RenderDistChanged);
-
         break;
     }
 }
 
-fn change_render_dist_buttons_background_color(
+fn update_auto_pick_up_button_text(
+    mut event_reader: EventReader<StateTransitionEvent<GameSettings>>,
+    button_query: Query<&Children, With<AutoPickUpButton>>,
+    mut text_query: Query<&mut Text>,
+    game_settings: Res<State<GameSettings>>,
+    localization: Res<Localization>,
+) {
+    for _ in event_reader.read() {
+        for children in button_query.iter() {
+            let mut iter = text_query.iter_many_mut(children);
+            while let Some(mut text) = iter.fetch_next() {
+                for section in text.sections.iter_mut() {
+                    section.value = auto_pick_up_button_text(game_settings.get(), &localization);
+                }
+            }
+        }
+    }
+}
+
+fn change_crosshair(
+    style_button_query: Query<&Interaction, (Changed<Interaction>, With<CrosshairStyleButton>)>,
+    size_button_query: Query<&Interaction, (Changed<Interaction>, With<CrosshairSizeButton>)>,
+    game_settings: Res<State<GameSettings>>,
+    mut next_game_settings: ResMut<NextState<GameSettings>>,
+) {
+    let mut new_game_settings = *game_settings.get();
+
+    if style_button_query
+        .iter()
+        .any(|i| *i == Interaction::Pressed)
+    {
+        new_game_settings.crosshair_style = new_game_settings.crosshair_style.next();
+    }
+    if size_button_query.iter().any(|i| *i == Interaction::Pressed) {
+        new_game_settings.crosshair_size = new_game_settings.crosshair_size.next();
+    }
+
+    if new_game_settings != *game_settings.get() {
+        next_game_settings.set(new_game_settings);
+    }
+}
+
+fn update_crosshair_buttons_text(
+    mut event_reader: EventReader<StateTransitionEvent<GameSettings>>,
+    style_button_query: Query<&Children, With<CrosshairStyleButton>>,
+    size_button_query: Query<&Children, With<CrosshairSizeButton>>,
+    mut text_query: Query<&mut Text>,
+    game_settings: Res<State<GameSettings>>,
+    localization: Res<Localization>,
+) {
+    for _ in event_reader.read() {
+        let style_children = style_button_query
+            .iter()
+            .map(|children| (children, game_settings.get().crosshair_style.display_key()));
+        let size_children = size_button_query
+            .iter()
+            .map(|children| (children, game_settings.get().crosshair_size.display_key()));
+
+        for (children, key) in style_children.chain(size_children) {
+            let mut iter = text_query.iter_many_mut(children);
+            while let Some(mut text) = iter.fetch_next() {
+                for section in text.sections.iter_mut() {
+                    section.value = localization.tr(key, &[]);
+                }
+            }
+        }
+    }
+}
+
+fn change_decor_density(
+    button_query: Query<&Interaction, (Changed<Interaction>, With<DecorDensityButton>)>,
+    game_settings: Res<State<GameSettings>>,
+    mut next_game_settings: ResMut<NextState<GameSettings>>,
+) {
+    if button_query.iter().any(|i| *i == Interaction::Pressed) {
+        let mut new_game_settings = *game_settings.get();
+        new_game_settings.decor_density = new_game_settings.decor_density.next();
+        next_game_settings.set(new_game_settings);
+    }
+}
+
+fn update_decor_density_button_text(
+    mut event_reader: EventReader<StateTransitionEvent<GameSettings>>,
+    button_query: Query<&Children, With<DecorDensityButton>>,
+    mut text_query: Query<&mut Text>,
+    game_settings: Res<State<GameSettings>>,
+    localization: Res<Localization>,
+) {
+    for _ in event_reader.read() {
+        for children in button_query.iter() {
+            let mut iter = text_query.iter_many_mut(children);
+            while let Some(mut text) = iter.fetch_next() {
+                for section in text.sections.iter_mut() {
+                    section.value =
+                        localization.tr(game_settings.get().decor_density.display_key(), &[]);
+                }
+            }
+        }
+    }
+}
+
+fn change_light_fuel_mode(
+    button_query: Query<&Interaction, (Changed<Interaction>, With<LightFuelModeButton>)>,
+    game_settings: Res<State<GameSettings>>,
+    mut next_game_settings: ResMut<NextState<GameSettings>>,
+) {
+    if button_query.iter().any(|i| *i == Interaction::Pressed) {
+        let mut new_game_settings = *game_settings.get();
+        new_game_settings.light_fuel = new_game_settings.light_fuel.next();
+        next_game_settings.set(new_game_settings);
+    }
+}
+
+fn update_light_fuel_mode_button_text(
+    mut event_reader: EventReader<StateTransitionEvent<GameSettings>>,
+    button_query: Query<&Children, With<LightFuelModeButton>>,
+    mut text_query: Query<&mut Text>,
+    game_settings: Res<State<GameSettings>>,
+    localization: Res<Localization>,
+) {
+    for _ in event_reader.read() {
+        for children in button_query.iter() {
+            let mut iter = text_query.iter_many_mut(children);
+            while let Some(mut text) = iter.fetch_next() {
+                for section in text.sections.iter_mut() {
+                    section.value =
+                        localization.tr(game_settings.get().light_fuel.display_key(), &[]);
+                }
+            }
+        }
+    }
+}
+
+fn change_movement_style(
+    button_query: Query<&Interaction, (Changed<Interaction>, With<MovementStyleButton>)>,
+    game_settings: Res<State<GameSettings>>,
+    mut next_game_settings: ResMut<NextState<GameSettings>>,
+) {
+    if button_query.iter().any(|i| *i == Interaction::Pressed) {
+        let mut new_game_settings = *game_settings.get();
+        new_game_settings.movement_style = new_game_settings.movement_style.next();
+        next_game_settings.set(new_game_settings);
+    }
+}
+
+fn update_movement_style_button_text(
+    mut event_reader: EventReader<StateTransitionEvent<GameSettings>>,
+    button_query: Query<&Children, With<MovementStyleButton>>,
+    mut text_query: Query<&mut Text>,
+    game_settings: Res<State<GameSettings>>,
+    localization: Res<Localization>,
+) {
+    for _ in event_reader.read() {
+        for children in button_query.iter() {
+            let mut iter = text_query.iter_many_mut(children);
+            while let Some(mut text) = iter.fetch_next() {
+                for section in text.sections.iter_mut() {
+                    section.value =
+                        localization.tr(game_settings.get().movement_style.display_key(), &[]);
+                }
+            }
+        }
+    }
+}
+
+fn change_difficulty(
+    button_query: Query<&Interaction, (Changed<Interaction>, With<DifficultyButton>)>,
+    game_settings: Res<State<GameSettings>>,
+    mut next_game_settings: ResMut<NextState<GameSettings>>,
+) {
+    if button_query.iter().any(|i| *i == Interaction::Pressed) {
+        let mut new_game_settings = *game_settings.get();
+        new_game_settings.difficulty = new_game_settings.difficulty.next();
+        next_game_settings.set(new_game_settings);
+    }
+}
+
+fn update_difficulty_button_text(
     mut event_reader: EventReader<StateTransitionEvent<GameSettings>>,
-    mut buttons_query: Query<(&RenderDistButton, &mut BackgroundColor)>,
+    button_query: Query<&Children, With<DifficultyButton>>,
+    mut text_query: Query<&mut Text>,
     game_settings: Res<State<GameSettings>>,
+    localization: Res<Localization>,
 ) {
     for _ in event_reader.read() {
-        for (render_dist_button, mut background_color) in buttons_query.iter_mut() {
-            if render_dist_button.0 == game_settings.get().chunk_render_dist.0 {
-                *background_color = Color::linear_rgba(0.0, 0.0, 0.4, 1.0).into();
-            } else {
-                *background_color = Color::WHITE.into();
+        for children in button_query.iter() {
+            let mut iter = text_query.iter_many_mut(children);
+            while let Some(mut text) = iter.fetch_next() {
+                for section in text.sections.iter_mut() {
+                    section.value =
+                        localization.tr(game_settings.get().difficulty.display_key(), &[]);
+                }
+            }
+        }
+    }
+}
+
+// The rest of the menu is respawned in the new language once it is applied
+fn change_language(
+    button_query: Query<&Interaction, (Changed<Interaction>, With<LanguageButton>)>,
+    game_settings: Res<State<GameSettings>>,
+    mut next_game_settings: ResMut<NextState<GameSettings>>,
+) {
+    if button_query.iter().any(|i| *i == Interaction::Pressed) {
+        let mut new_game_settings = *game_settings.get();
+        new_game_settings.language = new_game_settings.language.next();
+        next_game_settings.set(new_game_settings);
+    }
+}
+
+fn update_menu_tabs_text(
+    tab_query: Query<(&MenuTab, &Children)>,
+    mut text_query: Query<&mut Text>,
+    localization: Res<Localization>,
+) {
+    for (tab, children) in tab_query.iter() {
+        let mut iter = text_query.iter_many_mut(children);
+        while let Some(mut text) = iter.fetch_next() {
+            for section in text.sections.iter_mut() {
+                section.value = localization.tr(tab.display_key(), &[]);
+            }
+        }
+    }
+}
+
+fn hud_widget_toggle_button_text(
+    widget_layout: &HudWidgetLayout,
+    localization: &Localization,
+) -> String {
+    on_off_text(widget_layout.enabled, localization)
+}
+
+fn change_hud_layout(
+    mut event_writer: EventWriter<HudLayoutChanged>,
+    toggle_button_query: Query<(&HudWidgetToggleButton, &Interaction), Changed<Interaction>>,
+    anchor_button_query: Query<(&HudWidgetAnchorButton, &Interaction), Changed<Interaction>>,
+    mut hud_layout: ResMut<HudLayout>,
+) {
+    for (button, interaction) in toggle_button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Some(widget_layout) = hud_layout.get_mut(&button.0) {
+            widget_layout.enabled = !widget_layout.enabled;
+            event_writer.send(HudLayoutChanged);
+        }
+    }
+
+    for (button, interaction) in anchor_button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Some(widget_layout) = hud_layout.get_mut(&button.0) {
+            widget_layout.anchor = widget_layout.anchor.next();
+            event_writer.send(HudLayoutChanged);
+        }
+    }
+}
+
+fn update_hud_layout_buttons_text(
+    mut event_reader: EventReader<HudLayoutChanged>,
+    toggle_button_query: Query<(&HudWidgetToggleButton, &Children)>,
+    anchor_button_query: Query<(&HudWidgetAnchorButton, &Children)>,
+    mut text_query: Query<&mut Text>,
+    hud_layout: Res<HudLayout>,
+    localization: Res<Localization>,
+) {
+    if event_reader.read().count() == 0 {
+        return;
+    }
+
+    for widget_layout in hud_layout.widgets.iter() {
+        let toggle_children = toggle_button_query
+            .iter()
+            .filter(|(button, _)| button.0 == widget_layout.widget)
+            .map(|(_, children)| {
+                (
+                    children,
+                    hud_widget_toggle_button_text(widget_layout, &localization),
+                )
+            });
+        let anchor_children = anchor_button_query
+            .iter()
+            .filter(|(button, _)| button.0 == widget_layout.widget)
+            .map(|(_, children)| {
+                (
+                    children,
+                    localization.tr(widget_layout.anchor.display_key(), &[]),
+                )
+            });
+
+        for (children, value) in toggle_children.chain(anchor_children) {
+            let mut iter = text_query.iter_many_mut(children);
+            while let Some(mut text) = iter.fetch_next() {
+                for section in text.sections.iter_mut() {
+                    section.value = value.clone();
+                }
             }
         }
     }
@@ -619,101 +2385,222 @@ fn update_visible_on_parent_hover(
     }
 }
 
-fn start_drag_inventory_item(
-    inventory_slot_query: Query<(&InventorySlot, &Interaction)>,
+// Drags only start on the frame the mouse is pressed, so that a slot that is still
+// held down after a drag was canceled does not start dragging again. Activating a slot
+// from the keyboard picks its item up the same way.
+pub fn start_drag_inventory_item(
+    mut event_reader: EventReader<UiElementActivated>,
+    inventory_slot_query: Query<(Entity, &InventorySlot, &Interaction)>,
+    mouse: Res<ButtonInput<MouseButton>>,
     drag_state: Res<State<DragState>>,
     mut next_drag_state: ResMut<NextState<DragState>>,
 ) {
-    for (slot, interaction) in inventory_slot_query.iter() {
-        if *interaction == Interaction::Pressed && drag_state.get().0 == Dragging::None {
+    let activated = activated_entities(&mut event_reader);
+    if !mouse.just_pressed(MouseButton::Left) && activated.is_empty() {
+        return;
+    }
+
+    for (entity, slot, interaction) in inventory_slot_query.iter() {
+        let pressed = (mouse.just_pressed(MouseButton::Left)
+            && *interaction == Interaction::Pressed)
+            || activated.contains(&entity);
+        if pressed && drag_state.get().0 == Dragging::None {
             next_drag_state.set(DragState(Dragging::InventorySlot(slot.0)));
             break;
         }
     }
 }
 
-fn start_drag_equipment_item(
-    equipment_slot_query: Query<(&EquipmentSlot, &Interaction)>,
+// Slots are dimmed while the weapon in them was just swapped and cannot attack yet
+fn change_equipment_slots_border_color(
+    mut equipment_slot_query: Query<(&EquipmentSlot, &mut BorderColor)>,
+    weapon_swap_cooldown: Res<WeaponSwapCooldown>,
+) {
+    for (slot, mut border_color) in equipment_slot_query.iter_mut() {
+        border_color.0 = if weapon_swap_cooldown.is_cooling_down(&AttackHand::from(&slot.0)) {
+            EQUIPMENT_SLOT_COOLDOWN_BORDER_COLOR
+        } else {
+            Color::WHITE
+        };
+    }
+}
+
+pub fn start_drag_equipment_item(
+    mut event_reader: EventReader<UiElementActivated>,
+    equipment_slot_query: Query<(Entity, &EquipmentSlot, &Interaction)>,
+    mouse: Res<ButtonInput<MouseButton>>,
     drag_state: Res<State<DragState>>,
     mut next_drag_state: ResMut<NextState<DragState>>,
 ) {
-    for (slot, interaction) in equipment_slot_query.iter() {
-        if *interaction == Interaction::Pressed && drag_state.get().0 == Dragging::None {
+    let activated = activated_entities(&mut event_reader);
+    if !mouse.just_pressed(MouseButton::Left) && activated.is_empty() {
+        return;
+    }
+
+    for (entity, slot, interaction) in equipment_slot_query.iter() {
+        let pressed = (mouse.just_pressed(MouseButton::Left)
+            && *interaction == Interaction::Pressed)
+            || activated.contains(&entity);
+        if pressed && drag_state.get().0 == Dragging::None {
             next_drag_state.set(DragState(Dragging::EquipmentSlot(slot.0.clone())));
             break;
         }
     }
 }
 
-fn stop_drag_item(
+// Escape or right clicking while dragging puts the item back where it was,
+// and so does closing the menu
+pub fn cancel_drag_item(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    menu_open: Res<State<MenuOpen>>,
+    drag_state: Res<State<DragState>>,
+    mut next_drag_state: ResMut<NextState<DragState>>,
+) {
+    if drag_state.get().0 != Dragging::None
+        && (keys.just_pressed(KeyCode::Escape)
+            || mouse.just_released(MouseButton::Right)
+            || !menu_open.get().0)
+    {
+        next_drag_state.set(DragState(Dragging::None));
+    }
+}
+
+// The equipment slot under the cursor, if it is over exactly one of them
+fn target_equipment_slot(
+    equipment_slot_query: &Query<(&EquipmentSlot, &RelativeCursorPosition)>,
+) -> Option<EquipmentSlotName> {
+    let slots = equipment_slot_query
+        .iter()
+        .filter(|(_, rel_cursor_position)| rel_cursor_position.mouse_over());
+
+    match find_one(slots, |_| true) {
+        Ok((equipment_slot, _)) => Some(equipment_slot.0),
+        Err(FindOneError::Zero) => None,
+        Err(err) => {
+            should_not_happen!("equipment slot under the cursor: {}", err);
+            None
+        }
+    }
+}
+
+// Items are placed where the mouse is released, or in the slot that is activated from
+// the keyboard while one is picked up
+pub fn stop_drag_item(
+    mut event_reader: EventReader<UiElementActivated>,
     mut event_writer: EventWriter<InventoryChanged>,
+    mut popup_event_writer: EventWriter<TextPopupEvent>,
+    mut dropped_event_writer: EventWriter<DraggedItemDropped>,
     inventory_slot_query: Query<(&InventorySlot, &RelativeCursorPosition)>,
     equipment_slot_query: Query<(&EquipmentSlot, &RelativeCursorPosition)>,
+    activated_slot_query: Query<(Option<&InventorySlot>, Option<&EquipmentSlot>)>,
+    menu_query: Query<&RelativeCursorPosition, With<Menu>>,
     mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
     mut inventory: ResMut<Inventory>,
     drag_state: Res<State<DragState>>,
     mut next_drag_state: ResMut<NextState<DragState>>,
+    localization: Res<Localization>,
 ) {
-    if mouse.just_released(MouseButton::Left) {
-        let mut inventory_changed = false;
+    let activated = activated_entities(&mut event_reader);
+    // Activating a slot while not dragging picks its item up instead
+    let from_keyboard = !activated.is_empty() && drag_state.get().0 != Dragging::None;
+    if !mouse.just_released(MouseButton::Left) && !from_keyboard {
+        return;
+    }
 
-        match drag_state.get().0 {
-            Dragging::None => {}
-            Dragging::InventorySlot(i) => {
-                // Swap inventory slots
-                for (inventory_slot, rel_cursor_position) in inventory_slot_query.iter() {
-                    if rel_cursor_position.mouse_over() {
-                        inventory.merge_swap_at(i, inventory_slot.0);
-                        inventory_changed = true;
-                        break;
-                    }
-                }
+    let (target_inventory_slot, target_equipment_slot) = if from_keyboard {
+        activated
+            .iter()
+            .find_map(|entity| activated_slot_query.get(*entity).ok())
+            .map_or((None, None), |(inventory_slot, equipment_slot)| {
+                (
+                    inventory_slot.map(|slot| slot.0),
+                    equipment_slot.map(|slot| slot.0),
+                )
+            })
+    } else {
+        (
+            inventory_slot_query
+                .iter()
+                .find(|(_, rel_cursor_position)| rel_cursor_position.mouse_over())
+                .map(|(inventory_slot, _)| inventory_slot.0),
+            target_equipment_slot(&equipment_slot_query),
+        )
+    };
 
-                // Move from inventory slot to equipment slot
-                if !inventory_changed {
-                    if let Some(item_a) = inventory.slots[i].as_ref() {
-                        for (equipment_slot, rel_cursor_position) in equipment_slot_query.iter() {
-                            if rel_cursor_position.mouse_over()
-                                && item_a.is_equipable_at(&equipment_slot.0)
-                            {
-                                inventory.equip_at(i, &equipment_slot.0);
-                                inventory_changed = true;
-                                break;
-                            }
-                        }
+    let mut inventory_changed = false;
+
+    match drag_state.get().0 {
+        Dragging::None => {}
+        Dragging::InventorySlot(i) => {
+            // Swap inventory slots
+            if let Some(target) = target_inventory_slot {
+                let outcome = inventory.merge_swap_at(i, target);
+                if let MergeSwapOutcome::Merged { remainder } = outcome {
+                    if remainder > 0 {
+                        popup_event_writer.send(TextPopupEvent {
+                            content: localization.tr("popup.stack_full", &[("amt", &remainder)]),
+                            location: TextPopupLocation::BottomLeft,
+                            timeout: TextPopupTimeout::Seconds(2),
+                            ..default()
+                        });
                     }
                 }
+                inventory_changed = outcome != MergeSwapOutcome::Noop;
             }
-            Dragging::EquipmentSlot(name) => {
-                // Swap equipment slots
-                for (equipment_slot, rel_cursor_position) in equipment_slot_query.iter() {
-                    if rel_cursor_position.mouse_over() {
-                        inventory.equipment.swap(&equipment_slot.0, &name);
+
+            // Move from inventory slot to equipment slot
+            if !inventory_changed {
+                if let (Some(item_a), Some(target)) =
+                    (inventory.slots[i].as_ref(), target_equipment_slot)
+                {
+                    if item_a.is_equipable_at(&target) {
+                        inventory.equip_at(i, &target);
                         inventory_changed = true;
-                        break;
                     }
                 }
+            }
+        }
+        Dragging::EquipmentSlot(name) => {
+            // Swap equipment slots
+            if let Some(target) = target_equipment_slot {
+                inventory.equipment.swap(&target, &name);
+                inventory_changed = true;
+            }
 
-                // Move from equipment slot to inventory slot
-                if !inventory_changed {
-                    for (inventory_slot, rel_cursor_position) in inventory_slot_query.iter() {
-                        if rel_cursor_position.mouse_over()
-                            && inventory.is_equipable_at(inventory_slot.0, &name)
-                        {
-                            inventory.equip_at(inventory_slot.0, &name);
-                            inventory_changed = true;
-                            break;
-                        }
-                    }
+            // Move from equipment slot to inventory slot
+            match target_inventory_slot {
+                Some(i) if !inventory_changed && inventory.slots[i].is_none() => {
+                    inventory_changed = inventory.unequip_to(i, &name);
+                }
+                Some(i) if !inventory_changed && inventory.is_equipable_at(i, &name) => {
+                    // Swaps the equipped item with the one in the inventory slot
+                    inventory_changed = inventory.equip_at(i, &name);
                 }
+                _ => {}
             }
         }
+    }
 
-        if inventory_changed {
-            event_writer.send(InventoryChanged);
-        }
-        next_drag_state.set(DragState(Dragging::None));
+    // Only releasing over the world drops the item. Releasing anywhere else
+    // in the menu, like the tab bar or the settings, cancels the drag instead,
+    // and so does placing an item from the keyboard anywhere but a slot.
+    let over_menu = from_keyboard
+        || menu_query
+            .get_single()
+            .map_or(true, |rel_cursor_position| rel_cursor_position.mouse_over());
+
+    if inventory_changed {
+        event_writer.send(InventoryChanged);
+    } else if !over_menu && drag_state.get().0 != Dragging::None {
+        let drop_amount = DropAmount::from_modifiers(
+            keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]),
+            keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]),
+        );
+        dropped_event_writer.send(DraggedItemDropped(drag_state.get().0.clone(), drop_amount));
     }
+    next_drag_state.set(DragState(Dragging::None));
 }
 
 fn use_inventory_item(
@@ -723,14 +2610,30 @@ fn use_inventory_item(
     player_query: Query<Entity, With<Player>>,
     mouse: Res<ButtonInput<MouseButton>>,
     mut inventory: ResMut<Inventory>,
+    drag_state: Res<State<DragState>>,
+    game_settings: Res<State<GameSettings>>,
 ) {
-    if mouse.just_released(MouseButton::Right) {
+    // Right clicking while dragging cancels the drag instead
+    if mouse.just_released(MouseButton::Right) && drag_state.get().0 == Dragging::None {
         for (inventory_slot, rel_cursor_position) in inventory_slot_query.iter() {
             if rel_cursor_position.mouse_over() {
+                // Light fuel would be wasted without the light burning any
+                let is_light_fuel = inventory.slots[inventory_slot.0]
+                    .is_some_and(|item| item.name.light_fuel().is_some());
+                if is_light_fuel && !game_settings.get().light_fuel.is_on() {
+                    break;
+                }
+
                 let (output, was_mutated) = inventory.use_at(inventory_slot.0);
                 if let Some(item) = output {
-                    let entity = player_query.get_single().unwrap();
-                    item_event_writer.send(ItemUsed(item, entity));
+                    match player_query.get_single() {
+                        Ok(entity) => {
+                            item_event_writer.send(ItemUsed(item, entity));
+                        }
+                        Err(err) => {
+                            should_not_happen!("retrieving player: {}", err);
+                        }
+                    }
                 }
                 if was_mutated {
                     inv_event_writer.send(InventoryChanged);
@@ -741,8 +2644,33 @@ fn use_inventory_item(
     }
 }
 
+fn item_tooltip_text(item: &Item, localization: &Localization) -> String {
+    let mut text = localization.tr(item.name.display_key(), &[]);
+    if let Some(effect) = item.name.consumable_effect() {
+        text += &format!("\n{}", effect.description(localization));
+    }
+    if item.name.is_stackable() {
+        let stack = localization.tr(
+            "menu.item.stack",
+            &[("amt", &item.amt), ("max", &item.max_amt())],
+        );
+        text += &format!("\n{}", stack);
+    }
+    text
+}
+
+// Full stacks stand out, so it is clear why nothing more can be merged into them
+fn item_amt_color(item: &Item) -> Color {
+    if item.is_full() {
+        FULL_STACK_AMT_COLOR
+    } else {
+        Color::WHITE
+    }
+}
+
 fn handle_item_used(
     mut event_reader: EventReader<ItemUsed>,
+    mut refuel_event_writer: EventWriter<RefuelLight>,
     mut heal_health_event_writer: EventWriter<HealHealth>,
     mut heal_stamina_event_writer: EventWriter<HealStamina>,
     mut take_dmg_event_writer: EventWriter<TakeDamage>,
@@ -750,98 +2678,78 @@ fn handle_item_used(
     mut stamina_query: Query<(Entity, &mut Stamina)>,
 ) {
     for event in event_reader.read() {
-        match event.0.name {
-            ItemName::HealthPotion => match health_query.iter_mut().find(|(e, _)| *e == event.1) {
-                Some((e, _)) => {
-                    heal_health_event_writer.send(HealHealth(30.0, e));
-                }
-                None => {
-                    should_not_happen!("using health potion on entity w/o health component");
-                }
-            },
-            ItemName::StaminaPotion => match health_query.iter_mut().find(|(e, _)| *e == event.1) {
-                Some((e, _)) => {
-                    heal_stamina_event_writer.send(HealStamina(30.0, e));
-                }
-                None => {
-                    should_not_happen!("using stamina potion on entity w/o stamina component");
-                }
-            },
-            ItemName::HealthRegenPotion => match health_query
-                .iter_mut()
-                .find(|(e, _)| *e == event.1)
-            {
-                Some((_, mut health)) => {
-                    health.add_temp_modifier(0.05, 90);
-                }
-                None => {
-                    should_not_happen!("using health regen potion on entity w/o health component");
-                }
-            },
-            ItemName::StaminaRegenPotion => {
-                match stamina_query.iter_mut().find(|(e, _)| *e == event.1) {
-                    Some((_, mut stamina)) => {
-                        stamina.add_temp_modifier(0.05, 90);
-                    }
-                    None => {
-                        should_not_happen!(
-                            "using stamina regen potion on entity w/o stamina component"
-                        );
-                    }
-                }
-            }
-            ItemName::HealthPoison => match health_query.iter_mut().find(|(e, _)| *e == event.1) {
-                Some((e, _)) => {
-                    take_dmg_event_writer.send(TakeDamage(vec![(DmgType::Poison, 30.0)], e));
-                }
-                None => {
-                    should_not_happen!("using health poison on entity w/o health component");
-                }
-            },
-            ItemName::StaminaPoison => {
-                match stamina_query.iter_mut().find(|(e, _)| *e == event.1) {
-                    Some((e, _)) => {
-                        take_dmg_event_writer.send(TakeDamage(vec![(DmgType::Stamina, 30.0)], e));
-                    }
-                    None => {
-                        should_not_happen!("using stamina poison on entity w/o stamina component");
+        if let Some(percent) = event.0.name.light_fuel() {
+            refuel_event_writer.send(RefuelLight(percent));
+            continue;
+        }
+
+        let Some(effect) = event.0.name.consumable_effect() else {
+            continue;
+        };
+
+        match effect.stat() {
+            ConsumableStat::Health => match health_query.iter_mut().find(|(e, _)| *e == event.1) {
+                Some((e, mut health)) => {
+                    let amt = effect.amt(health.max_value);
+                    match effect {
+                        ConsumableEffect::Instant(..) if amt >= 0.0 => {
+                            heal_health_event_writer.send(HealHealth(amt, e));
+                        }
+                        ConsumableEffect::Instant(..) => {
+                            take_dmg_event_writer.send(TakeDamage {
+                                amounts: vec![(DmgType::Poison, -amt)],
+                                target: e,
+                                source: None,
+                            });
+                        }
+                        ConsumableEffect::Regen(_, _, durr) => {
+                            health.add_temp_modifier(amt, durr);
+                        }
                     }
                 }
-            }
-            ItemName::HealthRegenPoison => match health_query
-                .iter_mut()
-                .find(|(e, _)| *e == event.1)
-            {
-                Some((_, mut health)) => {
-                    health.add_temp_modifier(-0.05, 90);
-                }
                 None => {
-                    should_not_happen!("using health regen poison on entity w/o health component");
+                    should_not_happen!("using {} on entity w/o health component", event.0.name);
                 }
             },
-            ItemName::StaminaRegenPoison => {
+            ConsumableStat::Stamina => {
                 match stamina_query.iter_mut().find(|(e, _)| *e == event.1) {
-                    Some((_, mut stamina)) => {
-                        stamina.add_temp_modifier(-0.05, 90);
+                    Some((e, mut stamina)) => {
+                        let amt = effect.amt(stamina.max_value);
+                        match effect {
+                            ConsumableEffect::Instant(..) if amt >= 0.0 => {
+                                heal_stamina_event_writer.send(HealStamina(amt, e));
+                            }
+                            ConsumableEffect::Instant(..) => {
+                                take_dmg_event_writer.send(TakeDamage {
+                                    amounts: vec![(DmgType::Stamina, -amt)],
+                                    target: e,
+                                    source: None,
+                                });
+                            }
+                            ConsumableEffect::Regen(_, _, durr) => {
+                                stamina.add_temp_modifier(amt, durr);
+                            }
+                        }
                     }
                     None => {
                         should_not_happen!(
-                            "using stamina regen poison on entity w/o stamina component"
+                            "using {} on entity w/o stamina component",
+                            event.0.name
                         );
                     }
                 }
             }
-            _ => {}
         }
     }
 }
 
-fn update_item_image_cursor_follower(
+pub fn update_item_image_cursor_follower(
     mut commands: Commands,
     mut event_reader: EventReader<StateTransitionEvent<DragState>>,
     cursor_follower_query: Query<Entity, (With<ItemImageCursorFollower>, With<CursorFollower>)>,
     asset_server: Res<AssetServer>,
     cursor_position: Res<CursorPosition>,
+    ui_scale: Res<UiScale>,
     inventory: Res<Inventory>,
     drag_state: Res<State<DragState>>,
 ) {
@@ -852,6 +2760,7 @@ fn update_item_image_cursor_follower(
                     spawn_item_image_cursor_follower(
                         &mut commands,
                         &cursor_position,
+                        &ui_scale,
                         &item.ui_image(&asset_server),
                         &item_style(),
                     );
@@ -862,6 +2771,7 @@ fn update_item_image_cursor_follower(
                     spawn_item_image_cursor_follower(
                         &mut commands,
                         &cursor_position,
+                        &ui_scale,
                         &item.ui_image(&asset_server),
                         &item_style(),
                     );
@@ -881,15 +2791,17 @@ fn update_item_image_cursor_follower(
 fn spawn_item_image_cursor_follower(
     commands: &mut Commands,
     cursor_position: &CursorPosition,
+    ui_scale: &UiScale,
     ui_image: &UiImage,
     ui_image_style: &Style,
 ) {
     let mut image = ui_image.clone();
     image.color = Color::srgba_u8(255, 255, 255, 200);
 
+    let position = cursor_to_ui_position(cursor_position.0, ui_scale.0);
     let mut style = ui_image_style.clone();
-    style.left = Val::Px(cursor_position.0.x);
-    style.top = Val::Px(cursor_position.0.y);
+    style.left = Val::Px(position.x);
+    style.top = Val::Px(position.y);
 
     commands.spawn((
         ItemImageCursorFollower,
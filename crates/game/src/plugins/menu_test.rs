@@ -0,0 +1,485 @@
+use crate::plugins::{
+    inventory::drop_dragged_item,
+    menu::{
+        cancel_drag_item, change_render_dist, navigate_menu_with_keyboard, sort_inventory,
+        start_drag_inventory_item, stop_drag_item, update_inventory_menu_content,
+        update_item_image_cursor_follower,
+    },
+};
+use bevy::{
+    ecs::event::ManualEventReader, prelude::*, state::app::StatesPlugin, ui::RelativeCursorPosition,
+};
+use bevy_text_popup::TextPopupEvent;
+use dungeon_maze_common::{
+    cursor::CursorPosition,
+    inventory::{
+        item::{Item, ItemName},
+        Inventory, InventoryChanged, PlayerDroppedItem,
+    },
+    localization::Localization,
+    menu::{
+        ActiveMenuTab, DragState, DraggedItemDropped, Dragging, Focusable, FocusedUiElement,
+        InventorySlot, InventorySortButton, ItemImageCursorFollower, Menu, MenuOpen,
+        PhysicsDistButton, RenderDistButton, RenderedInventory, UiElementActivated,
+    },
+    settings::{
+        ChunkPhysicsDist, ChunkRenderDist, GameSettings, RenderDistChanged, MAX_CHUNK_RENDER_DIST,
+    },
+};
+
+fn new_test_app() -> App {
+    let mut app = App::new();
+
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin {
+            watch_for_changes_override: Some(false),
+            ..default()
+        },
+        StatesPlugin,
+    ))
+    .init_asset::<Image>()
+    .add_event::<InventoryChanged>()
+    .add_event::<PlayerDroppedItem>()
+    .add_event::<DraggedItemDropped>()
+    .add_event::<TextPopupEvent>()
+    .add_event::<UiElementActivated>()
+    .init_resource::<Inventory>()
+    .init_resource::<Localization>()
+    .init_resource::<CursorPosition>()
+    .init_resource::<UiScale>()
+    .init_resource::<ButtonInput<MouseButton>>()
+    .init_resource::<ButtonInput<KeyCode>>()
+    .init_resource::<FocusedUiElement>()
+    .insert_state(MenuOpen(true))
+    .init_state::<ActiveMenuTab>()
+    .init_state::<DragState>()
+    .add_systems(
+        Update,
+        (
+            navigate_menu_with_keyboard,
+            start_drag_inventory_item,
+            cancel_drag_item,
+            stop_drag_item,
+            update_item_image_cursor_follower,
+            drop_dragged_item,
+        )
+            .chain(),
+    );
+
+    app.world_mut().resource_mut::<Inventory>().slots[0] = Some(Item::new(ItemName::Coal, 3));
+
+    app
+}
+
+fn rel_cursor_position(mouse_over: bool) -> RelativeCursorPosition {
+    RelativeCursorPosition {
+        normalized_visible_node_rect: Rect::new(0.0, 0.0, 1.0, 1.0),
+        normalized: mouse_over.then_some(Vec2::splat(0.5)),
+    }
+}
+
+// The menu, with a single inventory slot in it that the cursor starts out over
+fn spawn_menu(app: &mut App) -> (Entity, Entity) {
+    let menu = app
+        .world_mut()
+        .spawn((Menu, rel_cursor_position(true)))
+        .id();
+    let slot = app
+        .world_mut()
+        .spawn((
+            InventorySlot(0),
+            Focusable(0),
+            Interaction::None,
+            rel_cursor_position(true),
+        ))
+        .id();
+    (menu, slot)
+}
+
+fn update_with_keys(app: &mut App, keys: &[KeyCode]) {
+    for key in keys {
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(*key);
+    }
+    app.update();
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .release_all();
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .clear();
+    // Lets the drag and menu states transition
+    app.update();
+}
+
+fn focused(app: &App) -> Option<Focusable> {
+    app.world().resource::<FocusedUiElement>().0
+}
+
+fn set_mouse_over(app: &mut App, entity: Entity, mouse_over: bool) {
+    app.world_mut()
+        .entity_mut(entity)
+        .insert(rel_cursor_position(mouse_over));
+}
+
+fn update_with_mouse(app: &mut App, f: impl FnOnce(&mut ButtonInput<MouseButton>)) {
+    f(&mut app.world_mut().resource_mut::<ButtonInput<MouseButton>>());
+    app.update();
+    app.world_mut()
+        .resource_mut::<ButtonInput<MouseButton>>()
+        .clear();
+    // Lets the drag state transition
+    app.update();
+}
+
+fn start_drag(app: &mut App, slot: Entity) {
+    app.world_mut()
+        .entity_mut(slot)
+        .insert(Interaction::Pressed);
+    update_with_mouse(app, |mouse| mouse.press(MouseButton::Left));
+    app.world_mut().entity_mut(slot).insert(Interaction::None);
+
+    assert_eq!(drag_state(app), Dragging::InventorySlot(0));
+    assert_eq!(cursor_followers(app), 1);
+}
+
+fn drag_state(app: &App) -> Dragging {
+    app.world().resource::<State<DragState>>().get().0.clone()
+}
+
+fn cursor_followers(app: &mut App) -> usize {
+    let world = app.world_mut();
+    world
+        .query_filtered::<(), With<ItemImageCursorFollower>>()
+        .iter(world)
+        .count()
+}
+
+fn dropped_items(app: &App) -> usize {
+    let events = app.world().resource::<Events<PlayerDroppedItem>>();
+    events.get_reader().read(events).count()
+}
+
+// Amounts of the items dropped since the reader last read, so that every drop is only seen once
+fn dropped_amts(app: &App, reader: &mut ManualEventReader<PlayerDroppedItem>) -> Vec<u16> {
+    let events = app.world().resource::<Events<PlayerDroppedItem>>();
+    reader.read(events).map(|event| event.0.amt).collect()
+}
+
+fn assert_canceled(app: &mut App) {
+    assert_eq!(drag_state(app), Dragging::None);
+    assert_eq!(cursor_followers(app), 0);
+    assert_eq!(dropped_items(app), 0);
+    assert!(app.world().resource::<Inventory>().slots[0].is_some());
+}
+
+#[test]
+fn test_drag_canceled_with_escape() {
+    let mut app = new_test_app();
+    let (menu, slot) = spawn_menu(&mut app);
+    start_drag(&mut app, slot);
+
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::Escape);
+    app.update();
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .clear();
+    app.update();
+    assert_canceled(&mut app);
+
+    // Releasing the mouse afterwards, even over the world, does not drop anything
+    set_mouse_over(&mut app, slot, false);
+    set_mouse_over(&mut app, menu, false);
+    update_with_mouse(&mut app, |mouse| mouse.release(MouseButton::Left));
+    assert_canceled(&mut app);
+}
+
+#[test]
+fn test_drag_canceled_with_right_click() {
+    let mut app = new_test_app();
+    let (_, slot) = spawn_menu(&mut app);
+    start_drag(&mut app, slot);
+
+    update_with_mouse(&mut app, |mouse| {
+        mouse.press(MouseButton::Right);
+        mouse.release(MouseButton::Right);
+    });
+    assert_canceled(&mut app);
+}
+
+#[test]
+fn test_drag_canceled_when_menu_closes() {
+    let mut app = new_test_app();
+    let (_, slot) = spawn_menu(&mut app);
+    start_drag(&mut app, slot);
+
+    app.world_mut()
+        .resource_mut::<NextState<MenuOpen>>()
+        .set(MenuOpen(false));
+    app.update();
+    app.update();
+    assert_canceled(&mut app);
+}
+
+#[test]
+fn test_drag_released_over_menu_is_canceled() {
+    let mut app = new_test_app();
+    let (_, slot) = spawn_menu(&mut app);
+    start_drag(&mut app, slot);
+
+    // Over the menu, like the tab bar, but not over any slot
+    set_mouse_over(&mut app, slot, false);
+    update_with_mouse(&mut app, |mouse| mouse.release(MouseButton::Left));
+    assert_canceled(&mut app);
+}
+
+#[test]
+fn test_drag_released_over_world_drops_item() {
+    let mut app = new_test_app();
+    let (menu, slot) = spawn_menu(&mut app);
+    start_drag(&mut app, slot);
+
+    set_mouse_over(&mut app, slot, false);
+    set_mouse_over(&mut app, menu, false);
+    update_with_mouse(&mut app, |mouse| mouse.release(MouseButton::Left));
+
+    assert_eq!(drag_state(&app), Dragging::None);
+    assert_eq!(cursor_followers(&mut app), 0);
+    assert_eq!(dropped_items(&app), 1);
+    assert!(app.world().resource::<Inventory>().slots[0].is_none());
+}
+
+// Releases the dragged item over the world while holding a key
+fn drop_holding(app: &mut App, menu: Entity, slot: Entity, key: Option<KeyCode>) {
+    start_drag(app, slot);
+    set_mouse_over(app, slot, false);
+    set_mouse_over(app, menu, false);
+    if let Some(key) = key {
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(key);
+    }
+    update_with_mouse(app, |mouse| mouse.release(MouseButton::Left));
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .reset_all();
+
+    // Back over the slot for the next drag
+    set_mouse_over(app, slot, true);
+    set_mouse_over(app, menu, true);
+}
+
+#[test]
+fn test_drop_one_or_half_with_modifiers() {
+    let mut app = new_test_app();
+    app.world_mut().resource_mut::<Inventory>().slots[0] =
+        Some(Item::new(ItemName::HealthPotion, 64));
+    let (menu, slot) = spawn_menu(&mut app);
+    let mut reader = app
+        .world()
+        .resource::<Events<PlayerDroppedItem>>()
+        .get_reader();
+
+    drop_holding(&mut app, menu, slot, Some(KeyCode::ControlLeft));
+    assert_eq!(drag_state(&app), Dragging::None);
+    assert_eq!(dropped_amts(&app, &mut reader), vec![1]);
+    assert_eq!(
+        app.world().resource::<Inventory>().slots[0],
+        Some(Item::new(ItemName::HealthPotion, 63))
+    );
+
+    drop_holding(&mut app, menu, slot, Some(KeyCode::ShiftLeft));
+    assert_eq!(dropped_amts(&app, &mut reader), vec![32]);
+    assert_eq!(
+        app.world().resource::<Inventory>().slots[0].unwrap().amt,
+        31
+    );
+
+    drop_holding(&mut app, menu, slot, None);
+    assert_eq!(dropped_amts(&app, &mut reader), vec![31]);
+    assert!(app.world().resource::<Inventory>().slots[0].is_none());
+}
+
+#[test]
+fn test_sort_button_sorts_inventory() {
+    let mut app = new_test_app();
+    app.add_systems(Update, sort_inventory);
+    {
+        let mut inventory = app.world_mut().resource_mut::<Inventory>();
+        inventory.slots[5] = Some(Item::new(ItemName::Katana, 1));
+        inventory.slots[9] = Some(Item::new(ItemName::Coal, 4));
+    }
+
+    app.world_mut()
+        .spawn((InventorySortButton, Interaction::Pressed));
+    app.update();
+
+    let inventory = app.world().resource::<Inventory>();
+    assert_eq!(inventory.slots[0], Some(Item::new(ItemName::Katana, 1)));
+    assert_eq!(inventory.slots[1], Some(Item::new(ItemName::Coal, 7)));
+    assert!(inventory.slots[2..].iter().all(Option::is_none));
+
+    let events = app.world().resource::<Events<InventoryChanged>>();
+    assert_eq!(events.get_reader().read(events).count(), 1);
+}
+
+#[test]
+fn test_keyboard_focus_wraps_around_menu() {
+    let mut app = new_test_app();
+    spawn_menu(&mut app);
+    app.world_mut().spawn(Focusable(1000));
+    app.world_mut().spawn(Focusable(1001));
+
+    update_with_keys(&mut app, &[KeyCode::Tab]);
+    assert_eq!(focused(&app), Some(Focusable(0)));
+    update_with_keys(&mut app, &[KeyCode::ArrowDown]);
+    assert_eq!(focused(&app), Some(Focusable(1000)));
+    update_with_keys(&mut app, &[KeyCode::Tab]);
+    assert_eq!(focused(&app), Some(Focusable(1001)));
+
+    // Focus never leaves the menu
+    update_with_keys(&mut app, &[KeyCode::Tab]);
+    assert_eq!(focused(&app), Some(Focusable(0)));
+    update_with_keys(&mut app, &[KeyCode::ShiftLeft, KeyCode::Tab]);
+    assert_eq!(focused(&app), Some(Focusable(1001)));
+}
+
+#[test]
+fn test_keyboard_moves_item_between_slots() {
+    let mut app = new_test_app();
+    spawn_menu(&mut app);
+    app.world_mut().spawn((
+        InventorySlot(1),
+        Focusable(1),
+        Interaction::None,
+        rel_cursor_position(false),
+    ));
+
+    // Picks the item up
+    update_with_keys(&mut app, &[KeyCode::Tab]);
+    update_with_keys(&mut app, &[KeyCode::Enter]);
+    assert_eq!(drag_state(&app), Dragging::InventorySlot(0));
+    assert_eq!(cursor_followers(&mut app), 1);
+
+    // Places it in the next slot
+    update_with_keys(&mut app, &[KeyCode::Tab]);
+    update_with_keys(&mut app, &[KeyCode::Enter]);
+    assert_eq!(drag_state(&app), Dragging::None);
+    assert_eq!(cursor_followers(&mut app), 0);
+    assert_eq!(dropped_items(&app), 0);
+
+    let inventory = app.world().resource::<Inventory>();
+    assert!(inventory.slots[0].is_none());
+    assert_eq!(inventory.slots[1], Some(Item::new(ItemName::Coal, 3)));
+}
+
+#[test]
+fn test_escape_closes_menu_when_not_dragging() {
+    let mut app = new_test_app();
+    spawn_menu(&mut app);
+
+    update_with_keys(&mut app, &[KeyCode::Escape]);
+    assert!(!app.world().resource::<State<MenuOpen>>().get().0);
+}
+
+fn slot_children(app: &App, slot: Entity) -> Vec<Entity> {
+    app.world()
+        .get::<Children>(slot)
+        .map_or(Vec::new(), |children| children.to_vec())
+}
+
+fn update_with_inventory_changed(app: &mut App) {
+    app.world_mut().send_event(InventoryChanged);
+    app.update();
+}
+
+#[test]
+fn test_inventory_menu_content_only_updates_changed_slots() {
+    let mut app = new_test_app();
+    app.init_resource::<RenderedInventory>()
+        .add_systems(Update, update_inventory_menu_content);
+
+    let slots = [0, 1].map(|i| app.world_mut().spawn(InventorySlot(i)).id());
+    // As if the menu was just spawned with the inventory as it is now
+    let inventory = app.world().resource::<Inventory>().clone();
+    app.world_mut().resource_mut::<RenderedInventory>().0 = Some(inventory);
+
+    update_with_inventory_changed(&mut app);
+    update_with_inventory_changed(&mut app);
+    assert!(slot_children(&app, slots[0]).is_empty());
+    assert!(slot_children(&app, slots[1]).is_empty());
+
+    app.world_mut().resource_mut::<Inventory>().slots.swap(0, 1);
+    update_with_inventory_changed(&mut app);
+    let children = slot_children(&app, slots[1]);
+    assert!(slot_children(&app, slots[0]).is_empty());
+    // Image, tooltip and amount
+    assert_eq!(children.len(), 3);
+
+    // The slots and their items are left alone while the inventory stays the same
+    update_with_inventory_changed(&mut app);
+    update_with_inventory_changed(&mut app);
+    assert!(slot_children(&app, slots[0]).is_empty());
+    assert_eq!(slot_children(&app, slots[1]), children);
+    assert!(children
+        .iter()
+        .all(|child| app.world().get_entity(*child).is_some()));
+}
+
+#[test]
+fn test_render_dist_button_clamped() {
+    let mut app = new_test_app();
+    app.init_state::<GameSettings>()
+        .add_event::<RenderDistChanged>()
+        .add_systems(Update, change_render_dist);
+
+    app.world_mut()
+        .spawn((RenderDistButton(50), Interaction::Pressed));
+    app.update();
+    app.update();
+
+    let dist = MAX_CHUNK_RENDER_DIST;
+    assert_eq!(
+        app.world()
+            .resource::<State<GameSettings>>()
+            .get()
+            .chunk_render_dist,
+        ChunkRenderDist(dist, dist, dist)
+    );
+}
+
+#[test]
+fn test_physics_dist_button_clamped_to_render_dist() {
+    let mut app = new_test_app();
+    app.insert_state(GameSettings {
+        chunk_render_dist: ChunkRenderDist(3, 3, 3),
+        ..default()
+    })
+    .add_event::<RenderDistChanged>()
+    .add_systems(Update, change_render_dist);
+
+    let button = app
+        .world_mut()
+        .spawn((PhysicsDistButton(5), Interaction::Pressed))
+        .id();
+    app.update();
+    app.update();
+
+    let game_settings = *app.world().resource::<State<GameSettings>>().get();
+    assert_eq!(game_settings.chunk_physics_dist, ChunkPhysicsDist(3));
+    assert_eq!(game_settings.physics_dist(), ChunkRenderDist(3, 3, 3));
+
+    // Lowering the render distance lowers the physics distance that is used along with it
+    app.world_mut().entity_mut(button).despawn();
+    app.world_mut()
+        .spawn((RenderDistButton(1), Interaction::Pressed));
+    app.update();
+    app.update();
+
+    let game_settings = *app.world().resource::<State<GameSettings>>().get();
+    assert_eq!(game_settings.physics_dist(), ChunkRenderDist(1, 1, 1));
+}
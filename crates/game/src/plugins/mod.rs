@@ -1,14 +1,48 @@
 pub mod animation;
+pub mod automap;
 pub mod camera;
 pub mod cursor;
+pub mod death_marker;
 pub mod hud;
 pub mod interaction;
 pub mod inventory;
+pub mod loading;
+pub mod main_menu;
 pub mod menu;
 pub mod player;
 pub mod save;
 pub mod settings;
+pub mod spectator;
+pub mod stats;
+pub mod throw;
 pub mod world;
 
+#[cfg(test)]
+mod camera_test;
+#[cfg(test)]
+mod cursor_test;
+#[cfg(test)]
+mod death_marker_test;
+#[cfg(test)]
+mod interaction_test;
+#[cfg(test)]
+mod inventory_test;
+#[cfg(test)]
+mod loading_test;
+#[cfg(test)]
+mod menu_test;
+#[cfg(test)]
+mod player_test;
+#[cfg(test)]
+mod stats_test;
+#[cfg(test)]
+mod throw_test;
+
 #[cfg(debug_assertions)]
 pub mod debug;
+#[cfg(all(test, debug_assertions))]
+mod debug_test;
+#[cfg(debug_assertions)]
+pub mod input_replay;
+#[cfg(all(test, debug_assertions))]
+mod input_replay_test;
@@ -1,104 +1,222 @@
-use bevy::prelude::*;
+use crate::plugins::{
+    interaction::execute_pending_interaction,
+    world::{physics::weapon_collision_groups, safe_spawn_position},
+};
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+        view::NoFrustumCulling,
+    },
+};
 use bevy_rapier3d::prelude::*;
 use bevy_third_person_camera::*;
 use dungeon_maze_common::{
     animation::{ContinuousAnimation, PlayerAnimation},
+    app_state::{AppState, GameLoaded},
     camera::MainCamera,
-    inventory::{equipment::EquipmentSlotName, item::Item, Inventory, InventoryChanged},
+    interaction::InputIntent,
+    inventory::{
+        equipment::{Equipment, EquipmentSlotName, EquipmentStats},
+        item::{Item, ItemType},
+        Inventory, InventoryChanged,
+    },
     menu::MenuOpen,
     player::{
-        attack::{AttackChargeUp, AttackHand, EntitiesHit},
-        DmgImmune, DmgResist, DmgTarget, DmgType, HealHealth, HealModifier, HealStamina, Health,
-        Killable, Player, PlayerState, Regenerator, Speed, Stamina, TakeDamage,
+        attack::{
+            AttackChargeCancelled, AttackChargeUp, AttackHand, AttackProgress, AttackRelease,
+            BufferedAttack, EntitiesHit, WeaponSwapCooldown,
+        },
+        calc_direction_speed_multiplier,
+        impact::{AttackImpact, ImpactDebris, ImpactVfx, SwingImpacts},
+        light::{LightFuel, RefuelLight},
+        status_effect::{ActiveStatusEffects, StatusEffect},
+        swing_trail::{
+            build_swing_trail_ribbon, swing_trail_color, SwingTrail, SwingTrailMesh,
+            SwingTrailRibbon, SwingTrailSample, SWING_TRAIL_WEAPON_LENGTH,
+        },
+        DamageApplied, DmgImmune, DmgResist, DmgTarget, DmgType, EntityKilled, FacingTarget,
+        HealHealth, HealModifier, HealStamina, Health, Killable, Player, PlayerSpawnConfig,
+        PlayerState, Regenerator, Speed, Stamina, TakeDamage,
+    },
+    settings::{Difficulty, GameSettings, LightFuelMode, MovementStyle},
+    should_not_happen, should_not_happen_once,
+    utils::{_max, debug::WarnOnce, direction::snap_to_compass},
+    world::{
+        hazard::{Hazard, HazardDamaged, HazardOccupants},
+        world_structure::WorldStructureRegistry,
+        WorldConfig, WorldDims, WorldSeed,
     },
-    should_not_happen,
-    utils::_max,
 };
 use std::f32::consts::PI;
 use strum::IntoEnumIterator;
 
-const PLAYER_COLLIDER_HX: f32 = 0.4;
-const PLAYER_COLLIDER_HY: f32 = 0.85;
-const PLAYER_COLLIDER_HZ: f32 = 0.4;
+const WEAPON_SWAP_COOLDOWN_FRAMES: u32 = 30;
+const ATTACK_MOUSE_BUTTONS: [(MouseButton, AttackHand); 2] = [
+    (MouseButton::Left, AttackHand::Left),
+    (MouseButton::Right, AttackHand::Right),
+];
+// Drops a charging attack without swinging
+pub const ATTACK_CANCEL_KEY: KeyCode = KeyCode::KeyQ;
+// About 2 seconds of damage immunity whenever the player is spawned or moved somewhere new
+pub const SPAWN_PROTECTION_FRAMES: u32 = 120;
+
+// Knockback speed added per point of damage, up to the max
+const KNOCKBACK_PER_DMG: f32 = 0.2;
+const MAX_KNOCKBACK: f32 = 8.0;
 
-const PLAYER_MAX_HEALTH: f32 = 100.0;
-const PLAYER_BASE_HEALTH_REGEN: f32 = 0.1;
+// Ticks that letting go of one hand waits on the other when dual wielding, before
+// attacking with just that hand. Letting go of both within it attacks with both.
+pub const DUAL_RELEASE_WINDOW_TICKS: u32 = 6;
+// Swinging both weapons at once costs stamina, which single attacks do not
+pub const DUAL_ATTACK_STAMINA_COST: f32 = 15.0;
 
-const PLAYER_MAX_STAMINA: f32 = 100.0;
-const PLAYER_BASE_STAMINA_REGEN: f32 = 1.0;
+// Lets the player shuffle slowly while swinging. Charging up an attack keeps the normal speed.
+pub const PLAYER_ATTACKING_SPEED: f32 = 80.0;
 
-const PLAYER_WALKING_SPEED: f32 = 200.0;
-const PLAYER_SPRINTING_SPEED: f32 = 400.0;
+// Getting hit this hard throws off an attack buffered during the current one
+pub const BUFFERED_ATTACK_HEAVY_HIT_DMG: f32 = 20.0;
 
-const DEFAULT_PLAYER_GRAVITY_SCALE: f32 = 2.0;
-const PLAYER_SPAWN_XYZ: (f32, f32, f32) = (2.0, 1.0, 2.0);
+const IMPACT_VFX_RADIUS: f32 = 0.15;
+const IMPACT_VFX_COLOR: Color = Color::srgba(1.0, 0.9, 0.6, 0.6);
+const IMPACT_DEBRIS_COUNT: usize = 4;
+const IMPACT_DEBRIS_SIZE: f32 = 0.05;
+const IMPACT_DEBRIS_SPEED: f32 = 2.0;
+const IMPACT_DEBRIS_COLOR: Color = Color::srgb(0.35, 0.3, 0.25);
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Speed>()
+            .register_type::<Health>()
+            .register_type::<Stamina>()
+            .register_type::<DmgResist>()
             .add_event::<TakeDamage>()
+            .add_event::<EntityKilled>()
+            .add_event::<DamageApplied>()
             .add_event::<HealHealth>()
             .add_event::<HealStamina>()
+            .add_event::<HazardDamaged>()
+            .add_event::<RefuelLight>()
+            .add_event::<AttackChargeCancelled>()
+            .add_event::<AttackImpact>()
             .init_state::<PlayerState>()
-            .insert_resource(AttackChargeUp::new(10, 15, None))
-            .add_systems(Startup, spawn_player)
+            .insert_resource(AttackChargeUp::new(10, 15, DUAL_RELEASE_WINDOW_TICKS))
+            .init_resource::<AttackProgress>()
+            .init_resource::<BufferedAttack>()
+            .init_resource::<SwingImpacts>()
+            .init_resource::<PlayerSpawnConfig>()
+            .init_resource::<EquipmentStats>()
+            .insert_resource(WeaponSwapCooldown::new(WEAPON_SWAP_COOLDOWN_FRAMES))
+            .init_resource::<InputIntent>()
+            .add_systems(OnEnter(GameLoaded), spawn_player)
+            .add_systems(OnEnter(MenuOpen(true)), clear_buffered_attack)
             .add_systems(
-                Update,
+                FixedUpdate,
                 (
-                    spawn_starting_equiped_items,
-                    spawn_new_equiped_items,
-                    toggle_player_sprinting,
-                    player_ground_movement,
                     temp_health_regen,
-                    temp_stamina_regen,
+                    (
+                        drain_stamina_while_sprinting.run_if(in_state(PlayerState::Sprinting)),
+                        temp_stamina_regen,
+                    )
+                        .chain(),
                     temp_dmg_resists,
                     temp_heal_health_modifiers,
                     temp_heal_stamina_modifiers,
                     tick_dmg_immune,
-                    drain_stamina_while_sprinting.run_if(in_state(PlayerState::Sprinting)),
+                    tick_status_effects.after(temp_heal_health_modifiers),
+                    tick_attack_charge_up.run_if(in_state(MenuOpen(false))),
+                    tick_weapon_swap_cooldown,
+                )
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                Update,
+                (
+                    spawn_starting_equiped_items,
+                    spawn_new_equiped_items,
+                    toggle_player_sprinting,
+                    player_ground_movement,
                     handle_take_damage,
+                    clear_buffered_attack_on_heavy_hit,
+                    apply_knockback,
                     handle_heal_health,
                     handle_heal_stamina,
-                    despawn_dead_entities,
-                    charge_up_and_release_attack.run_if(in_state(MenuOpen(false))),
-                    equipment_attack_collisions,
+                    despawn_killed_entities.after(handle_take_damage),
+                    (
+                        handle_weapon_swaps,
+                        charge_up_and_release_attack
+                            .after(execute_pending_interaction)
+                            .run_if(in_state(MenuOpen(false))),
+                    )
+                        .chain(),
+                    (
+                        reset_attack_progress,
+                        equipment_attack_collisions.run_if(attack_in_hit_window),
+                        handle_attack_impacts,
+                        apply_hit_stop,
+                        tick_attack_progress,
+                    )
+                        .chain(),
                     reset_entities_hit,
-                ),
+                    update_impact_vfx,
+                    (add_swing_trails, update_swing_trails).chain(),
+                    (auto_refuel_light, handle_refuel_light, burn_light_fuel).chain(),
+                )
+                    .run_if(in_state(AppState::InGame)),
             )
-            .add_systems(OnEnter(PlayerState::Walking), change_player_speed)
-            .add_systems(OnEnter(PlayerState::Sprinting), change_player_speed);
+            .add_systems(
+                Update,
+                apply_hazard_damage
+                    .before(handle_take_damage)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_equipment_stats.before(change_player_speed),
+                    update_equipment_dmg_resists,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                Update,
+                change_player_speed.before(player_ground_movement).run_if(
+                    state_changed::<PlayerState>.or_else(resource_changed::<EquipmentStats>),
+                ),
+            );
     }
 }
 
-fn spawn_player(
-    mut commands: Commands,
-    name_query: Query<
-        (Entity, &Name),
-        (Without<Player>, Without<EquipmentSlotName>, Without<Item>),
-    >,
-    asset_server: Res<AssetServer>,
-    inventory: Res<Inventory>,
-) {
+pub fn spawn_player_with_config(
+    commands: &mut Commands,
+    config: &PlayerSpawnConfig,
+    difficulty: Difficulty,
+    asset_server: &AssetServer,
+) -> Entity {
+    let half_extents = config.collider_half_extents;
+
+    // Difficulty is added on top of the base regen, so it shows up like any other regen modifier
+    let regen_multiplier = difficulty.modifiers().player_regen;
+    let mut health = Health::new(config.max_health, config.max_health, config.health_regen);
+    health._add_static_modifier(config.health_regen * (regen_multiplier - 1.0));
+    let mut stamina = Stamina::new(config.max_stamina, config.max_stamina, config.stamina_regen);
+    stamina._add_static_modifier(config.stamina_regen * (regen_multiplier - 1.0));
+
     let player_bundle = (
         Player,
-        Health::new(
-            PLAYER_MAX_HEALTH,
-            PLAYER_MAX_HEALTH,
-            PLAYER_BASE_HEALTH_REGEN,
-        ),
-        Stamina::new(
-            PLAYER_MAX_STAMINA,
-            PLAYER_MAX_STAMINA,
-            PLAYER_BASE_STAMINA_REGEN,
-        ),
+        health,
+        stamina,
         DmgResist::new(),
-        Speed(PLAYER_WALKING_SPEED),
+        ActiveStatusEffects::new(),
+        Speed(config.walking_speed),
         RigidBody::Dynamic,
         Velocity::default(),
-        GravityScale(DEFAULT_PLAYER_GRAVITY_SCALE),
-        Collider::cuboid(PLAYER_COLLIDER_HX, PLAYER_COLLIDER_HY, PLAYER_COLLIDER_HZ),
+        GravityScale(config.gravity_scale),
+        Collider::cuboid(half_extents.x, half_extents.y, half_extents.z),
         KinematicCharacterController {
             autostep: Some(CharacterAutostep {
                 max_height: CharacterLength::Absolute(1.0),
@@ -108,11 +226,7 @@ fn spawn_player(
             ..default()
         },
         SpatialBundle {
-            transform: Transform::from_xyz(
-                PLAYER_SPAWN_XYZ.0,
-                PLAYER_SPAWN_XYZ.1,
-                PLAYER_SPAWN_XYZ.2,
-            ),
+            transform: Transform::from_translation(config.spawn_position),
             ..default()
         },
         ContinuousAnimation,
@@ -120,28 +234,70 @@ fn spawn_player(
         Name::new("Player"),
     );
 
-    commands.spawn(player_bundle).with_children(|parent| {
-        parent.spawn((
-            SceneBundle {
-                scene: asset_server.load(
-                    GltfAssetLabel::Scene(PlayerAnimation::Idle.index())
-                        .from_asset("embedded://models/man.glb"),
-                ),
-                transform: Transform::from_xyz(0.0, -PLAYER_COLLIDER_HY, 0.0),
-                ..default()
-            },
-            Name::new("Player Model"),
-        ));
+    commands
+        .spawn((
+            player_bundle,
+            DmgImmune::new(Some(SPAWN_PROTECTION_FRAMES)),
+            FacingTarget::default(),
+            StateScoped(GameLoaded),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                SceneBundle {
+                    scene: asset_server.load(
+                        GltfAssetLabel::Scene(PlayerAnimation::Idle.index())
+                            .from_asset("embedded://models/man.glb"),
+                    ),
+                    transform: Transform::from_xyz(0.0, -half_extents.y, 0.0),
+                    ..default()
+                },
+                Name::new("Player Model"),
+            ));
 
-        parent.spawn((
-            SpotLightBundle {
-                transform: Transform::from_xyz(0.0, 0.0, 0.5)
-                    .with_rotation(Quat::from_rotation_y(PI)),
-                ..default()
-            },
-            Name::new("Spotlight"),
-        ));
-    });
+            let spot_light = SpotLight::default();
+            parent.spawn((
+                LightFuel::new(spot_light.intensity, spot_light.range),
+                SpotLightBundle {
+                    spot_light,
+                    transform: Transform::from_xyz(0.0, 0.0, 0.5)
+                        .with_rotation(Quat::from_rotation_y(PI)),
+                    ..default()
+                },
+                Name::new("Spotlight"),
+            ));
+        })
+        .id()
+}
+
+fn spawn_player(
+    mut commands: Commands,
+    name_query: Query<
+        (Entity, &Name),
+        (Without<Player>, Without<EquipmentSlotName>, Without<Item>),
+    >,
+    asset_server: Res<AssetServer>,
+    inventory: Res<Inventory>,
+    player_spawn_config: Res<PlayerSpawnConfig>,
+    game_settings: Res<State<GameSettings>>,
+    world_seed: Res<WorldSeed>,
+    world_config: Res<WorldConfig>,
+    world_dims: Res<WorldDims>,
+    world_structures: Res<WorldStructureRegistry>,
+) {
+    let difficulty = game_settings.get().difficulty;
+    let config = PlayerSpawnConfig {
+        spawn_position: safe_spawn_position(
+            world_seed.0,
+            difficulty,
+            &world_config,
+            &world_dims,
+            &world_structures,
+            player_spawn_config.spawn_position,
+        ),
+        ..player_spawn_config.clone()
+    };
+
+    spawn_player_with_config(&mut commands, &config, difficulty, &asset_server);
 
     // TODO: Refactor to run this logic once player model has been spawned:
     for slot_name in EquipmentSlotName::iter() {
@@ -286,6 +442,8 @@ fn spawn_equipment_model_bundle(
                 Sensor,
                 // TODO: impliment colliders in the shape of the held weapon
                 Collider::cuboid(0.1, 0.1, 0.1),
+                // Swings go through the gaps of windows, and reach their glass through its sensor
+                weapon_collision_groups(),
                 SceneBundle {
                     scene: asset_server.load(path),
                     ..default()
@@ -296,23 +454,41 @@ fn spawn_equipment_model_bundle(
     }
 }
 
-fn player_ground_movement(
+pub fn player_ground_movement(
     camera_query: Query<&Transform, (With<MainCamera>, Without<Player>)>,
-    mut player_query: Query<(&mut Transform, &mut Velocity, &Speed), With<Player>>,
+    mut player_query: Query<
+        (
+            &mut Transform,
+            &mut Velocity,
+            &Speed,
+            Option<&mut FacingTarget>,
+        ),
+        With<Player>,
+    >,
     player_state: Res<State<PlayerState>>,
+    game_settings: Res<State<GameSettings>>,
     keys: Res<ButtonInput<KeyCode>>,
     time: Res<Time>,
+    mut warn_once: Local<WarnOnce>,
 ) {
-    if !player_state.get().is_ground_movement() {
+    let is_attacking = player_state.get().is_attacking();
+    if !player_state.get().is_ground_movement() && !is_attacking {
         return;
     }
 
-    for (mut player_transform, mut player_velocity, player_speed) in player_query.iter_mut() {
-        let camera_transform = match camera_query.get_single() {
-            Ok(ct) => ct,
-            Err(err) => Err(format!("Error retrieving camera: {}", err)).unwrap(),
-        };
+    let camera_transform = match camera_query.get_single() {
+        Ok(ct) => ct,
+        Err(err) => {
+            should_not_happen_once!(warn_once, "retrieving camera: {}", err);
+            return;
+        }
+    };
 
+    let snapped = game_settings.get().movement_style == MovementStyle::Snapped;
+
+    for (mut player_transform, mut player_velocity, player_speed, mut facing_target) in
+        player_query.iter_mut()
+    {
         let mut direction = Vec3::default();
 
         // Forward
@@ -340,9 +516,18 @@ fn player_ground_movement(
             direction.z += d.z;
         }
 
-        let movement = direction.normalize_or_zero() * player_speed.0 * time.delta_seconds();
+        // Along the world axes rather than the camera's
+        if snapped {
+            direction = snap_to_compass(direction);
+        }
+
+        // Moving sideways or backwards relative to the camera is slower than moving forward
+        let speed = player_speed.0
+            * calc_direction_speed_multiplier(direction, *camera_transform.forward());
+        let movement = direction.normalize_or_zero() * speed * time.delta_seconds();
 
-        if direction.length_squared() > 0.0 {
+        // Facing stays locked to the direction the attack started in
+        if direction.length_squared() > 0.0 && !is_attacking {
             // Face player in inverse direction of impulse
             let inv = direction
                 * Vec3 {
@@ -350,7 +535,23 @@ fn player_ground_movement(
                     y: -1.0,
                     z: -1.0,
                 };
-            player_transform.look_to(inv, Vec3::Y);
+            match facing_target.as_deref_mut().filter(|_| snapped) {
+                Some(facing_target) => {
+                    facing_target.set(player_transform.looking_to(inv, Vec3::Y).rotation);
+                }
+                None => player_transform.look_to(inv, Vec3::Y),
+            }
+        }
+
+        if let Some(facing_target) = facing_target.as_deref_mut() {
+            // Switching to free movement or starting an attack lets go of any turn in progress,
+            // so that it does not pick back up later on
+            if !snapped || is_attacking {
+                facing_target.clear();
+            } else if facing_target.get().is_some() {
+                player_transform.rotation =
+                    facing_target.turn(player_transform.rotation, time.delta_seconds());
+            }
         }
 
         if player_velocity.linvel.y > 0.0 {
@@ -368,12 +569,19 @@ fn toggle_player_sprinting(
     keys: Res<ButtonInput<KeyCode>>,
     player_state: Res<State<PlayerState>>,
     mut next_player_state: ResMut<NextState<PlayerState>>,
+    mut warn_once: Local<WarnOnce>,
 ) {
     // Using just_pressed() here instead of pressed() because if
     // the player runs out of stamina, they are forced to release
     // ShiftLeft and press it again to resume sprinting.
     if keys.just_pressed(KeyCode::ShiftLeft) && *player_state.get() == PlayerState::Walking {
-        let player_stamina = player_query.get_single().unwrap();
+        let player_stamina = match player_query.get_single() {
+            Ok(stamina) => stamina,
+            Err(err) => {
+                should_not_happen_once!(warn_once, "retrieving player: {}", err);
+                return;
+            }
+        };
         if player_stamina.value > player_stamina.max_value * 0.1 {
             next_player_state.set(PlayerState::Sprinting);
         }
@@ -382,27 +590,73 @@ fn toggle_player_sprinting(
     }
 }
 
-fn change_player_speed(
-    mut player_query: Query<&mut Speed, With<Player>>,
+pub fn calc_player_speed(
+    player_state: &PlayerState,
+    active_status_effects: Option<&ActiveStatusEffects>,
+    player_spawn_config: &PlayerSpawnConfig,
+    equipment_stats: &EquipmentStats,
+) -> f32 {
+    let base_speed = match player_state {
+        PlayerState::Walking => player_spawn_config.walking_speed,
+        PlayerState::Sprinting => player_spawn_config.sprinting_speed,
+        PlayerState::Attacking(..) | PlayerState::DualAttacking(..) => PLAYER_ATTACKING_SPEED,
+    };
+
+    base_speed
+        * active_status_effects.map_or(1.0, |ase| ase.speed_multiplier())
+        * equipment_stats.speed_multiplier
+}
+
+pub fn change_player_speed(
+    mut player_query: Query<(&mut Speed, Option<&ActiveStatusEffects>), With<Player>>,
     player_state: Res<State<PlayerState>>,
+    player_spawn_config: Res<PlayerSpawnConfig>,
+    equipment_stats: Res<EquipmentStats>,
 ) {
-    if let Ok(mut player_speed) = player_query.get_single_mut() {
-        match *player_state.get() {
-            PlayerState::Walking => *player_speed = Speed(PLAYER_WALKING_SPEED),
-            PlayerState::Sprinting => *player_speed = Speed(PLAYER_SPRINTING_SPEED),
-            PlayerState::Attacking(..) => {}
-        };
+    if let Ok((mut player_speed, ase)) = player_query.get_single_mut() {
+        *player_speed = Speed(calc_player_speed(
+            player_state.get(),
+            ase,
+            &player_spawn_config,
+            &equipment_stats,
+        ));
     }
 }
 
-fn temp_health_regen(mut health_query: Query<&mut Health>) {
+// Also picks up inventories that were inserted without an event, like when a save is loaded.
+// Only marked as changed when the stats actually differ, since that respeeds the player.
+pub fn update_equipment_stats(
+    mut event_reader: EventReader<InventoryChanged>,
+    mut equipment_stats: ResMut<EquipmentStats>,
+    inventory: Res<Inventory>,
+) {
+    if event_reader.read().count() == 0 && !inventory.is_added() {
+        return;
+    }
+    equipment_stats.set_if_neq(EquipmentStats::from_equipment(&inventory.equipment));
+}
+
+// The player's resists from the equipment only ever come from EquipmentStats,
+// and are given again whenever those change or the player is spawned
+pub fn update_equipment_dmg_resists(
+    mut player_query: Query<&mut DmgResist, With<Player>>,
+    equipment_stats: Res<EquipmentStats>,
+) {
+    for mut dmg_resist in player_query.iter_mut() {
+        if equipment_stats.is_changed() || dmg_resist.is_added() {
+            dmg_resist.set_equipment_resists(equipment_stats.dmg_resists());
+        }
+    }
+}
+
+pub fn temp_health_regen(mut health_query: Query<&mut Health>) {
     for mut health in health_query.iter_mut() {
         health.tick_temp_modifiers();
         health.do_regen();
     }
 }
 
-fn temp_stamina_regen(mut stamina_query: Query<&mut Stamina>) {
+pub fn temp_stamina_regen(mut stamina_query: Query<&mut Stamina>) {
     for mut stamina in stamina_query.iter_mut() {
         stamina.tick_temp_modifiers();
         stamina.do_regen();
@@ -427,25 +681,39 @@ fn temp_heal_stamina_modifiers(mut stamina_query: Query<&mut Stamina>) {
     }
 }
 
-fn tick_dmg_immune(mut dmg_immune_query: Query<&mut DmgImmune>) {
-    for mut dmg_immune in dmg_immune_query.iter_mut() {
+fn tick_dmg_immune(mut commands: Commands, mut dmg_immune_query: Query<(Entity, &mut DmgImmune)>) {
+    for (entity, mut dmg_immune) in dmg_immune_query.iter_mut() {
         dmg_immune.tick();
+        if dmg_immune.is_expired() {
+            commands.entity(entity).remove::<DmgImmune>();
+        }
     }
 }
 
-fn drain_stamina_while_sprinting(
+pub fn drain_stamina_while_sprinting(
     mut player_query: Query<&mut Stamina, With<Player>>,
     player_state: Res<State<PlayerState>>,
+    equipment_stats: Res<EquipmentStats>,
     mut next_player_state: ResMut<NextState<PlayerState>>,
+    mut warn_once: Local<WarnOnce>,
 ) {
     if *player_state.get() != PlayerState::Sprinting {
         return;
     }
 
-    let mut player_stamina = player_query.get_single_mut().unwrap();
+    let mut player_stamina = match player_query.get_single_mut() {
+        Ok(stamina) => stamina,
+        Err(err) => {
+            should_not_happen_once!(warn_once, "retrieving player: {}", err);
+            return;
+        }
+    };
 
     if player_stamina.value > 0.0 {
-        player_stamina.value = _max(player_stamina.value - 1.0, 0.0);
+        player_stamina.value = _max(
+            player_stamina.value - equipment_stats.sprint_stamina_drain,
+            0.0,
+        );
         let regen = -player_stamina.get_regen();
         player_stamina.add_temp_modifier(regen, 1);
     } else {
@@ -454,30 +722,49 @@ fn drain_stamina_while_sprinting(
     }
 }
 
-fn handle_take_damage(
+pub fn handle_take_damage(
     mut event_reader: EventReader<TakeDamage>,
+    mut event_writer: EventWriter<EntityKilled>,
+    mut da_event_writer: EventWriter<DamageApplied>,
     mut query: Query<(
         Entity,
         Option<&mut Health>,
         Option<&mut Stamina>,
         Option<&DmgResist>,
         Option<&DmgImmune>,
+        Option<&mut ActiveStatusEffects>,
+        Option<&mut Speed>,
+        Has<Killable>,
+        Has<Player>,
     )>,
+    game_settings: Res<State<GameSettings>>,
 ) {
     for event in event_reader.read() {
-        if let Some((_, mut h, mut s, dr, di)) =
-            query.iter_mut().find(|(e, _, _, _, _)| *e == event.1)
+        if let Some((_, mut h, mut s, dr, di, mut ase, mut sp, is_killable, is_player)) = query
+            .iter_mut()
+            .find(|(e, _, _, _, _, _, _, _, _)| *e == event.target)
         {
             if di.is_some() {
                 continue;
             }
 
+            let dmg_multiplier = if is_player {
+                game_settings.get().difficulty.modifiers().player_dmg_taken
+            } else {
+                1.0
+            };
+
+            let health_before = h.as_ref().map(|health| health.value);
+            let was_alive = health_before.is_some_and(|value| value > 0.0);
+
             let dmg_resist = match dr {
                 Some(d) => d,
                 None => &DmgResist::new(),
             };
 
-            for (dmg_type, amt) in &event.0 {
+            for (dmg_type, amt) in &event.amounts {
+                let amt = &(amt * dmg_multiplier);
+
                 match dmg_type {
                     DmgType::Blunt
                     | DmgType::Slash
@@ -497,16 +784,97 @@ fn handle_take_damage(
                         });
                     }
                 }
+
+                if let (Some(status_effect), Some(active_status_effects)) =
+                    (StatusEffect::from_dmg(dmg_type, *amt), ase.as_mut())
+                {
+                    if active_status_effects.apply(status_effect) {
+                        if let Some(speed) = sp.as_mut() {
+                            speed.0 *= status_effect.speed_multiplier();
+                        }
+                    }
+                }
+            }
+
+            if let Some((before, health)) = health_before.zip(h.as_ref()) {
+                let amt = before - health.value;
+                if amt > 0.0 {
+                    da_event_writer.send(DamageApplied {
+                        target: event.target,
+                        source: event.source,
+                        amt,
+                    });
+                }
+            }
+
+            let is_dead = h.as_ref().is_some_and(|health| health.value <= 0.0);
+            if is_killable && was_alive && is_dead {
+                event_writer.send(EntityKilled {
+                    target: event.target,
+                    source: event.source,
+                });
             }
         } else {
             should_not_happen!(
                 "received TakeDamage event on entity that does not exist: {}",
-                event.1,
+                event.target,
             );
         }
     }
 }
 
+fn tick_status_effects(
+    mut event_writer: EventWriter<TakeDamage>,
+    mut query: Query<(
+        Entity,
+        &mut ActiveStatusEffects,
+        Option<&mut Health>,
+        Option<&mut Speed>,
+        Has<Player>,
+    )>,
+    player_state: Res<State<PlayerState>>,
+    player_spawn_config: Res<PlayerSpawnConfig>,
+    equipment_stats: Res<EquipmentStats>,
+) {
+    for (entity, mut active_status_effects, health, speed, is_player) in query.iter_mut() {
+        if active_status_effects.is_empty() {
+            continue;
+        }
+
+        for status_effect in active_status_effects.iter() {
+            if let Some(dmg) = status_effect.dmg_per_frame() {
+                event_writer.send(TakeDamage {
+                    amounts: vec![dmg],
+                    target: entity,
+                    source: None,
+                });
+            }
+        }
+
+        if let Some(mut health) = health {
+            let modifier = active_status_effects.heal_health_modifier();
+            if modifier != 0.0 {
+                // Only lasts until the next time temp heal modifiers are ticked,
+                // so it is re-added every tick for as long as the status effect is active
+                health.heal_modifier.add_temp_modifier(modifier, 0);
+            }
+        }
+
+        let expired = active_status_effects.tick();
+
+        if is_player && expired.iter().any(|se| se.speed_multiplier() != 1.0) {
+            if let Some(mut speed) = speed {
+                *speed = Speed(calc_player_speed(
+                    player_state.get(),
+                    Some(&active_status_effects),
+                    &player_spawn_config,
+                    &equipment_stats,
+                ));
+            }
+        }
+    }
+}
+
 fn handle_heal_health(
     mut event_reader: EventReader<HealHealth>,
     mut health_query: Query<(Entity, &mut Health)>,
@@ -543,52 +911,248 @@ fn handle_heal_stamina(
     }
 }
 
-fn despawn_dead_entities(
+// Pushes damaged entities away from whoever damaged them
+pub fn apply_knockback(
+    mut event_reader: EventReader<TakeDamage>,
+    mut target_query: Query<(&GlobalTransform, &mut Velocity), Without<DmgImmune>>,
+    source_query: Query<&GlobalTransform>,
+) {
+    for event in event_reader.read() {
+        let Some(source_gt) = event
+            .source
+            .and_then(|source| source_query.get(source).ok())
+        else {
+            continue;
+        };
+        let Ok((target_gt, mut velocity)) = target_query.get_mut(event.target) else {
+            continue;
+        };
+
+        let mut direction = target_gt.translation() - source_gt.translation();
+        direction.y = 0.0;
+
+        let strength = (event.total_health_dmg() * KNOCKBACK_PER_DMG).clamp(0.0, MAX_KNOCKBACK);
+        velocity.linvel += direction.normalize_or_zero() * strength;
+    }
+}
+
+pub fn despawn_killed_entities(
     mut commands: Commands,
-    killable_query: Query<(Entity, &Health), With<Killable>>,
+    mut event_reader: EventReader<EntityKilled>,
 ) {
-    for (entity, health) in killable_query.iter() {
-        if health.value <= 0.0 {
-            commands.entity(entity).despawn_recursive();
+    for event in event_reader.read() {
+        if let Some(entity_commands) = commands.get_entity(event.target) {
+            entity_commands.despawn_recursive();
         }
     }
 }
 
+pub fn tick_weapon_swap_cooldown(mut weapon_swap_cooldown: ResMut<WeaponSwapCooldown>) {
+    weapon_swap_cooldown.tick();
+}
+
+// Swapping the weapon in a hand cancels any attack with that hand,
+// and keeps the hand from attacking again for a moment
+pub fn handle_weapon_swaps(
+    mut event_reader: EventReader<InventoryChanged>,
+    mut prev_equipment: Local<Option<Equipment>>,
+    mut next_player_state: ResMut<NextState<PlayerState>>,
+    mut attack_charge_up: ResMut<AttackChargeUp>,
+    mut weapon_swap_cooldown: ResMut<WeaponSwapCooldown>,
+    player_state: Res<State<PlayerState>>,
+    inventory: Res<Inventory>,
+) {
+    let prev = prev_equipment.get_or_insert_with(|| inventory.equipment.clone());
+
+    if !event_reader.is_empty() {
+        event_reader.clear();
+
+        for slot_name in EquipmentSlotName::iter() {
+            if inventory.equipment.at(&slot_name) == prev.at(&slot_name) {
+                continue;
+            }
+
+            let attack_hand = AttackHand::from(&slot_name);
+            weapon_swap_cooldown.start(&attack_hand);
+
+            attack_charge_up.reset_hand(&attack_hand);
+
+            // Entities hit are cleared by reset_entities_hit once the attack is exited
+            if player_state.get().is_attacking_with(&attack_hand) {
+                next_player_state.set(PlayerState::Walking);
+            }
+        }
+    }
+
+    // Also picks up equipment that changed without an event, like when a save is loaded
+    *prev = inventory.equipment.clone();
+}
+
 pub fn charge_up_and_release_attack(
     mut next_player_state: ResMut<NextState<PlayerState>>,
+    mut cancel_event_writer: EventWriter<AttackChargeCancelled>,
+    mut stamina_query: Query<&mut Stamina, With<Player>>,
     mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
     mut attack_charge_up: ResMut<AttackChargeUp>,
+    weapon_swap_cooldown: Res<WeaponSwapCooldown>,
+    mut input_intent: ResMut<InputIntent>,
+    inventory: Res<Inventory>,
+    equipment_stats: Res<EquipmentStats>,
+    player_state: Res<State<PlayerState>>,
+    mut buffered_attack: ResMut<BufferedAttack>,
+    time: Res<Time>,
 ) {
-    for (mouse_button, attack_hand) in [
-        (MouseButton::Left, AttackHand::Left),
-        (MouseButton::Right, AttackHand::Right),
-    ] {
+    let dual_wield = inventory.equipment.is_dual_wielding();
+    let is_attacking = player_state.get().is_attacking();
+
+    if keys.just_pressed(ATTACK_CANCEL_KEY) && attack_charge_up.cancel(None) {
+        cancel_event_writer.send(AttackChargeCancelled);
+    }
+
+    // A press from near the end of the last attack starts charging up as soon as it is over.
+    // If its button was let go by then, it is released right away below, as a light attack.
+    if !is_attacking {
+        if let Some(attack_hand) = buffered_attack.take(time.elapsed_seconds()) {
+            if !weapon_swap_cooldown.is_cooling_down(&attack_hand) {
+                attack_charge_up.start(&attack_hand);
+            }
+        }
+    }
+
+    // Hands are let go of before any are pressed, so that switching from one hand
+    // to the other on the same frame does not count as holding both
+    for (mouse_button, attack_hand) in ATTACK_MOUSE_BUTTONS {
         if mouse.pressed(mouse_button) {
-            if mouse.just_pressed(mouse_button) {
-                attack_charge_up.reset_to(attack_hand);
-            } else if attack_charge_up.is_charging_hand(&attack_hand) {
-                attack_charge_up.tick();
+            continue;
+        }
+        attack_charge_up.release_cancel(&attack_hand);
+        if weapon_swap_cooldown.is_cooling_down(&attack_hand) {
+            continue;
+        }
+
+        if let Some(release) = attack_charge_up.release(&attack_hand, dual_wield) {
+            let mut stamina = stamina_query.get_single_mut().ok();
+            let weight_cost = equipment_stats.attack_stamina_cost;
+
+            let player_state = match release {
+                AttackRelease::Single(attack_type, attack_hand) => {
+                    PlayerState::Attacking(attack_type, attack_hand)
+                }
+                // Too tired to swing both, so only the hand let go of last attacks
+                AttackRelease::Dual(attack_type) => match stamina.as_mut() {
+                    Some(stamina) if stamina.value >= DUAL_ATTACK_STAMINA_COST + weight_cost => {
+                        stamina.subtract(DUAL_ATTACK_STAMINA_COST);
+                        PlayerState::DualAttacking(attack_type)
+                    }
+                    _ => PlayerState::Attacking(attack_type, attack_hand),
+                },
+            };
+            // Heavier equipment is more tiring to swing, even without the stamina for it
+            if let Some(stamina) = stamina.as_mut() {
+                stamina.subtract(weight_cost);
             }
-            break;
+            next_player_state.set(player_state);
         }
+    }
 
-        if attack_charge_up.is_charging_hand(&attack_hand) {
-            let attack_type = attack_charge_up.release();
-            next_player_state.set(PlayerState::Attacking(attack_type, attack_hand));
-            break;
+    // Holding the button down is counted by tick_attack_charge_up
+    for (mouse_button, attack_hand) in ATTACK_MOUSE_BUTTONS {
+        if weapon_swap_cooldown.is_cooling_down(&attack_hand) || !mouse.just_pressed(mouse_button) {
+            continue;
+        }
+
+        // The press was meant for whatever was just interacted with
+        if !input_intent.can_attack() {
+            continue;
+        }
+
+        // Held on to until the current attack is over, instead of being lost to it
+        if is_attacking {
+            buffered_attack.record(attack_hand, time.elapsed_seconds());
+            continue;
+        }
+
+        // Without a weapon in each hand, pressing the other button cancels the hand that is
+        // charging. That button then has to be let go before it can charge up itself.
+        if attack_charge_up.is_charging() && !dual_wield {
+            if !attack_charge_up.is_charging_hand(&attack_hand)
+                && attack_charge_up.cancel(Some(attack_hand))
+            {
+                cancel_event_writer.send(AttackChargeCancelled);
+            }
+            continue;
+        }
+        attack_charge_up.start(&attack_hand);
+    }
+
+    input_intent.set_attack_claim(attack_charge_up.is_charging());
+}
+
+pub fn clear_buffered_attack(mut buffered_attack: ResMut<BufferedAttack>) {
+    buffered_attack.clear();
+}
+
+pub fn clear_buffered_attack_on_heavy_hit(
+    mut event_reader: EventReader<TakeDamage>,
+    player_query: Query<Entity, With<Player>>,
+    mut buffered_attack: ResMut<BufferedAttack>,
+) {
+    for event in event_reader.read() {
+        if event.total_health_dmg() >= BUFFERED_ATTACK_HEAVY_HIT_DMG
+            && player_query.contains(event.target)
+        {
+            buffered_attack.clear();
+        }
+    }
+}
+
+// Charging up is counted in ticks rather than frames,
+// so heavy attacks take just as long to charge up at any frame rate
+fn tick_attack_charge_up(
+    mut next_player_state: ResMut<NextState<PlayerState>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut attack_charge_up: ResMut<AttackChargeUp>,
+    weapon_swap_cooldown: Res<WeaponSwapCooldown>,
+) {
+    for (mouse_button, attack_hand) in ATTACK_MOUSE_BUTTONS {
+        if !weapon_swap_cooldown.is_cooling_down(&attack_hand) && mouse.pressed(mouse_button) {
+            attack_charge_up.tick(&attack_hand);
         }
     }
+
+    // A hand let go while dual wielding gave up on waiting for the other one
+    if let Some(AttackRelease::Single(attack_type, attack_hand)) =
+        attack_charge_up.tick_release_window()
+    {
+        next_player_state.set(PlayerState::Attacking(attack_type, attack_hand));
+    }
+}
+
+// Attacks only deal damage during the hit window of their animation
+fn attack_in_hit_window(
+    player_animation: Res<State<PlayerAnimation>>,
+    attack_progress: Res<AttackProgress>,
+) -> bool {
+    attack_progress.is_in_hit_window(player_animation.get())
 }
 
 pub fn equipment_attack_collisions(
     mut commands: Commands,
     mut event_writer: EventWriter<TakeDamage>,
     mut item_query: Query<
-        (Entity, &EquipmentSlotName, &Item, Option<&mut EntitiesHit>),
+        (
+            Entity,
+            &EquipmentSlotName,
+            &Item,
+            &GlobalTransform,
+            Option<&mut EntitiesHit>,
+        ),
         (With<Collider>, Without<Player>),
     >,
+    mut impact_event_writer: EventWriter<AttackImpact>,
     dmg_target_query: Query<
-        Entity,
+        (Entity, &GlobalTransform),
         (
             With<DmgTarget>,
             With<Collider>,
@@ -598,16 +1162,23 @@ pub fn equipment_attack_collisions(
             Without<Item>,
         ),
     >,
+    player_query: Query<Entity, With<Player>>,
     rapier_context: Res<RapierContext>,
     player_state: Res<State<PlayerState>>,
 ) {
-    if let PlayerState::Attacking(attack_type, attack_hand) = *player_state.get() {
-        for (item_entity, slot_name, item, mut eh) in item_query.iter_mut() {
-            if *slot_name != EquipmentSlotName::from(&attack_hand) {
+    if let Some(attack_type) = player_state.get().attack_type() {
+        let player = player_query.get_single().ok();
+
+        // Dual attacks let the weapons in both hands deal damage
+        for (item_entity, slot_name, item, item_gt, mut eh) in item_query.iter_mut() {
+            if !player_state
+                .get()
+                .is_attacking_with(&AttackHand::from(slot_name))
+            {
                 continue;
             }
 
-            for entity in dmg_target_query.iter() {
+            for (entity, target_gt) in dmg_target_query.iter() {
                 if rapier_context
                     .intersection_pair(entity, item_entity)
                     .unwrap_or(false)
@@ -625,23 +1196,382 @@ pub fn equipment_attack_collisions(
                             .insert(EntitiesHit::new(vec![entity]));
                     }
 
-                    event_writer.send(TakeDamage(item.calc_dmg(&attack_type), entity));
+                    event_writer.send(TakeDamage {
+                        amounts: item.calc_dmg(&attack_type),
+                        target: entity,
+                        source: player,
+                    });
+                    // Intersections have no contact point, so the midpoint
+                    // between the weapon and the target stands in for one
+                    impact_event_writer.send(AttackImpact {
+                        position: item_gt.translation().lerp(target_gt.translation(), 0.5),
+                        target: entity,
+                    });
                 }
             }
         }
     }
 }
 
+// Damages entities with health that are inside of a hazard's sensor
+pub fn apply_hazard_damage(
+    mut take_damage_writer: EventWriter<TakeDamage>,
+    mut hazard_damaged_writer: EventWriter<HazardDamaged>,
+    mut hazard_query: Query<(Entity, &Hazard, &mut HazardOccupants)>,
+    health_query: Query<(), With<Health>>,
+    rapier_context: Res<RapierContext>,
+) {
+    for (hazard_entity, hazard, mut occupants) in hazard_query.iter_mut() {
+        let inside = rapier_context
+            .intersection_pairs_with(hazard_entity)
+            .filter(|(_, _, intersecting)| *intersecting)
+            .map(|(e1, e2, _)| if e1 == hazard_entity { e2 } else { e1 })
+            .filter(|entity| health_query.contains(*entity));
+
+        for target in occupants.tick(inside, hazard.tick_interval()) {
+            take_damage_writer.send(TakeDamage {
+                amounts: hazard.dmg(),
+                target,
+                source: None,
+            });
+            hazard_damaged_writer.send(HazardDamaged { target });
+        }
+    }
+}
+
+pub fn reset_attack_progress(
+    mut event_reader: EventReader<StateTransitionEvent<PlayerState>>,
+    mut attack_progress: ResMut<AttackProgress>,
+    mut swing_impacts: ResMut<SwingImpacts>,
+) {
+    for event in event_reader.read() {
+        if event
+            .entered
+            .as_ref()
+            .is_some_and(PlayerState::is_attacking)
+        {
+            attack_progress.reset();
+            swing_impacts.reset();
+        }
+    }
+}
+
+// Progress is held back along with the animation during a hit-stop,
+// so that the hit window stays lined up with the swing
+pub fn tick_attack_progress(
+    player_state: Res<State<PlayerState>>,
+    mut attack_progress: ResMut<AttackProgress>,
+    mut swing_impacts: ResMut<SwingImpacts>,
+    time: Res<Time>,
+) {
+    if player_state.get().is_attacking() {
+        attack_progress.tick(time.delta_seconds() * swing_impacts.hit_stop_speed());
+    }
+    swing_impacts.tick_hit_stop();
+}
+
+// The first few hits of each swing get an impact effect and a hit-stop
+pub fn handle_attack_impacts(
+    mut commands: Commands,
+    mut event_reader: EventReader<AttackImpact>,
+    mut swing_impacts: ResMut<SwingImpacts>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    game_settings: Res<State<GameSettings>>,
+) {
+    if game_settings.get().disable_hit_effects {
+        event_reader.clear();
+        return;
+    }
+
+    for event in event_reader.read() {
+        if !swing_impacts.register() {
+            continue;
+        }
+
+        commands.spawn((
+            ImpactVfx::default(),
+            PbrBundle {
+                mesh: meshes.add(Sphere::new(IMPACT_VFX_RADIUS)),
+                material: materials.add(StandardMaterial {
+                    base_color: IMPACT_VFX_COLOR,
+                    alpha_mode: AlphaMode::Blend,
+                    unlit: true,
+                    ..default()
+                }),
+                transform: Transform::from_translation(event.position),
+                ..default()
+            },
+            Name::new("Impact VFX"),
+        ));
+
+        let debris_mesh = meshes.add(Cuboid::from_length(IMPACT_DEBRIS_SIZE));
+        let debris_material = materials.add(StandardMaterial {
+            base_color: IMPACT_DEBRIS_COLOR,
+            ..default()
+        });
+        for i in 0..IMPACT_DEBRIS_COUNT {
+            let angle = i as f32 / IMPACT_DEBRIS_COUNT as f32 * PI * 2.0;
+            let velocity = Vec3::new(angle.cos(), 1.5, angle.sin()) * IMPACT_DEBRIS_SPEED;
+
+            commands.spawn((
+                ImpactDebris::new(velocity),
+                PbrBundle {
+                    mesh: debris_mesh.clone(),
+                    material: debris_material.clone(),
+                    transform: Transform::from_translation(event.position),
+                    ..default()
+                },
+                Name::new("Impact Debris"),
+            ));
+        }
+    }
+}
+
+pub fn apply_hit_stop(
+    mut animation_player_query: Query<&mut AnimationPlayer, With<AnimationTransitions>>,
+    swing_impacts: Res<SwingImpacts>,
+) {
+    let speed = swing_impacts.hit_stop_speed();
+    for mut animation_player in animation_player_query.iter_mut() {
+        for (_, active_animation) in animation_player.playing_animations_mut() {
+            if active_animation.speed() != speed {
+                active_animation.set_speed(speed);
+            }
+        }
+    }
+}
+
+pub fn update_impact_vfx(
+    mut commands: Commands,
+    mut vfx_query: Query<(
+        Entity,
+        &mut ImpactVfx,
+        &mut Transform,
+        &Handle<StandardMaterial>,
+    )>,
+    mut debris_query: Query<(Entity, &mut ImpactDebris, &mut Transform), Without<ImpactVfx>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    time: Res<Time>,
+) {
+    let delta_secs = time.delta_seconds();
+
+    for (entity, mut vfx, mut transform, material_handle) in vfx_query.iter_mut() {
+        let Some(frame) = vfx.tick(delta_secs) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+
+        transform.scale = Vec3::splat(frame.scale);
+        if let Some(material) = materials.get_mut(material_handle) {
+            material
+                .base_color
+                .set_alpha(frame.alpha * IMPACT_VFX_COLOR.alpha());
+        }
+    }
+
+    for (entity, mut debris, mut transform) in debris_query.iter_mut() {
+        if !debris.tick(delta_secs, &mut transform.translation) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+// Weapons with a model leave a trail behind them while they swing,
+// in a color that goes with the damage they deal
+pub fn add_swing_trails(
+    mut commands: Commands,
+    item_query: Query<(Entity, &Item), (Added<EquipmentSlotName>, With<Collider>)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (entity, item) in item_query.iter() {
+        if !matches!(item.name.item_type(), ItemType::Weapon) {
+            continue;
+        }
+
+        let color = item
+            .name
+            .base_dmg()
+            .first()
+            .map_or(Color::WHITE, |(dmg_type, _)| swing_trail_color(dmg_type));
+
+        commands
+            .entity(entity)
+            .insert(SwingTrail::default())
+            .with_children(|parent| {
+                parent.spawn((
+                    SwingTrailMesh,
+                    // The mesh changes every frame, so its bounds would always be stale
+                    NoFrustumCulling,
+                    PbrBundle {
+                        mesh: meshes.add(Mesh::new(
+                            PrimitiveTopology::TriangleList,
+                            RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+                        )),
+                        material: materials.add(StandardMaterial {
+                            base_color: color,
+                            alpha_mode: AlphaMode::Blend,
+                            unlit: true,
+                            double_sided: true,
+                            cull_mode: None,
+                            ..default()
+                        }),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                    Name::new("Swing Trail"),
+                ));
+            });
+    }
+}
+
+// Samples where the weapon is every frame of an attack with its hand,
+// and clears the trail once the attack ends
+pub fn update_swing_trails(
+    mut trail_query: Query<(
+        &EquipmentSlotName,
+        &GlobalTransform,
+        &mut SwingTrail,
+        &Children,
+    )>,
+    mut trail_mesh_query: Query<(&Handle<Mesh>, &mut Visibility), With<SwingTrailMesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    player_state: Res<State<PlayerState>>,
+    time: Res<Time>,
+) {
+    for (slot_name, gt, mut swing_trail, children) in trail_query.iter_mut() {
+        if player_state
+            .get()
+            .is_attacking_with(&AttackHand::from(slot_name))
+        {
+            swing_trail.tick(time.delta_seconds());
+            swing_trail.push(
+                gt.translation(),
+                gt.transform_point(Vec3::Y * SWING_TRAIL_WEAPON_LENGTH),
+            );
+        } else if !swing_trail.is_empty() {
+            swing_trail.clear();
+        } else {
+            continue;
+        }
+
+        // The mesh is a child of the weapon, so the samples are moved into its space
+        let inverse = gt.affine().inverse();
+        let samples: Vec<SwingTrailSample> = swing_trail
+            .samples()
+            .map(|sample| SwingTrailSample {
+                base: inverse.transform_point3(sample.base),
+                tip: inverse.transform_point3(sample.tip),
+                ..*sample
+            })
+            .collect();
+        let ribbon = build_swing_trail_ribbon(&samples);
+
+        let Some(child) = children
+            .iter()
+            .find(|child| trail_mesh_query.contains(**child))
+        else {
+            continue;
+        };
+        let Ok((mesh_handle, mut visibility)) = trail_mesh_query.get_mut(*child) else {
+            continue;
+        };
+
+        *visibility = if ribbon.is_empty() {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+
+        if let Some(mesh) = meshes.get_mut(mesh_handle) {
+            let SwingTrailRibbon {
+                positions,
+                colors,
+                indices,
+            } = ribbon;
+            mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+            mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+            mesh.insert_indices(Indices::U32(indices));
+        }
+    }
+}
+
 pub fn reset_entities_hit(
     mut commands: Commands,
     mut event_reader: EventReader<StateTransitionEvent<PlayerState>>,
     entities_hit_query: Query<Entity, (With<EntitiesHit>, With<EquipmentSlotName>)>,
 ) {
     for event in event_reader.read() {
-        if let Some(PlayerState::Attacking(..)) = &event.exited {
+        if event.exited.as_ref().is_some_and(PlayerState::is_attacking) {
             for entity in entities_hit_query.iter() {
                 commands.entity(entity).remove::<EntitiesHit>();
             }
         }
     }
 }
+
+// Dims the light as it runs low on fuel. With the setting off, no fuel is burnt
+// and the light is kept at full brightness.
+pub fn burn_light_fuel(
+    mut light_query: Query<(&mut LightFuel, &mut SpotLight)>,
+    time: Res<Time>,
+    game_settings: Res<State<GameSettings>>,
+) {
+    let is_on = game_settings.get().light_fuel.is_on();
+
+    for (mut fuel, mut spot_light) in light_query.iter_mut() {
+        let (intensity, range) = if is_on {
+            fuel.burn(time.delta_seconds());
+            (fuel.intensity(), fuel.range())
+        } else {
+            (fuel.full_intensity, fuel.full_range)
+        };
+
+        if spot_light.intensity != intensity || spot_light.range != range {
+            spot_light.intensity = intensity;
+            spot_light.range = range;
+        }
+    }
+}
+
+pub fn handle_refuel_light(
+    mut event_reader: EventReader<RefuelLight>,
+    mut light_query: Query<&mut LightFuel>,
+) {
+    for event in event_reader.read() {
+        for mut fuel in light_query.iter_mut() {
+            fuel.refuel(event.0);
+        }
+    }
+}
+
+// Uses up the first light fuel item in the inventory once the light runs out
+pub fn auto_refuel_light(
+    mut refuel_event_writer: EventWriter<RefuelLight>,
+    mut inv_event_writer: EventWriter<InventoryChanged>,
+    light_query: Query<&LightFuel>,
+    mut inventory: ResMut<Inventory>,
+    game_settings: Res<State<GameSettings>>,
+) {
+    if game_settings.get().light_fuel != LightFuelMode::Auto
+        || !light_query.iter().any(LightFuel::is_empty)
+    {
+        return;
+    }
+
+    let Some(i) = inventory
+        .slots
+        .iter()
+        .position(|slot| slot.is_some_and(|item| item.name.light_fuel().is_some()))
+    else {
+        return;
+    };
+
+    if let (Some(item), true) = inventory.use_at(i) {
+        if let Some(percent) = item.name.light_fuel() {
+            refuel_event_writer.send(RefuelLight(percent));
+        }
+        inv_event_writer.send(InventoryChanged);
+    }
+}
@@ -0,0 +1,1163 @@
+use crate::plugins::{
+    inventory::InventoryPlugin,
+    player::{
+        apply_knockback, auto_refuel_light, burn_light_fuel, change_player_speed,
+        charge_up_and_release_attack, despawn_killed_entities, drain_stamina_while_sprinting,
+        handle_attack_impacts, handle_refuel_light, handle_take_damage, handle_weapon_swaps,
+        player_ground_movement, spawn_player_with_config, temp_stamina_regen,
+        tick_weapon_swap_cooldown, update_equipment_dmg_resists, update_equipment_stats,
+        update_impact_vfx, PlayerPlugin, ATTACK_CANCEL_KEY, DUAL_ATTACK_STAMINA_COST,
+        PLAYER_ATTACKING_SPEED,
+    },
+    settings::SettingsPlugin,
+};
+use bevy::{
+    ecs::system::RunSystemOnce, prelude::*, state::app::StatesPlugin, time::TimeUpdateStrategy,
+};
+use bevy_rapier3d::prelude::{Collider, GravityScale, Velocity};
+use dungeon_maze_common::{
+    camera::MainCamera,
+    interaction::InputIntent,
+    inventory::{
+        equipment::{Equipment, EquipmentSlotName, EquipmentStats},
+        item::{Item, ItemName, PairBonus},
+        Inventory, InventoryChanged,
+    },
+    menu::MenuOpen,
+    player::{
+        attack::{
+            AttackChargeCancelled, AttackChargeUp, AttackHand, AttackType, BufferedAttack,
+            WeaponSwapCooldown,
+        },
+        impact::{
+            AttackImpact, ImpactDebris, ImpactVfx, SwingImpacts, HIT_STOP_SPEED,
+            MAX_IMPACTS_PER_SWING,
+        },
+        light::{LightFuel, RefuelLight},
+        status_effect::{ActiveStatusEffects, StatusEffect},
+        DamageApplied, DmgImmune, DmgResist, DmgType, EntityKilled, FacingTarget, Health, Killable,
+        Player, PlayerSpawnConfig, PlayerState, Regenerator, Speed, Stamina, TakeDamage,
+        PLAYER_TICK_HZ,
+    },
+    settings::{Difficulty, GameSettings, LightFuelMode, MovementStyle},
+};
+use std::{any::TypeId, time::Duration};
+
+const COOLDOWN_FRAMES: u32 = 5;
+const TICK: Duration = Duration::from_millis(16);
+
+fn new_test_app() -> App {
+    let mut app = App::new();
+
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .add_event::<InventoryChanged>()
+        .add_event::<AttackChargeCancelled>()
+        .init_state::<PlayerState>()
+        .init_state::<MenuOpen>()
+        .init_resource::<Inventory>()
+        .init_resource::<ButtonInput<MouseButton>>()
+        .init_resource::<ButtonInput<KeyCode>>()
+        .init_resource::<EquipmentStats>()
+        .insert_resource(AttackChargeUp::new(10, 15, 6))
+        .insert_resource(WeaponSwapCooldown::new(COOLDOWN_FRAMES))
+        .init_resource::<InputIntent>()
+        .init_resource::<BufferedAttack>()
+        // One fixed tick every update, so that cooldowns can be counted in updates
+        .insert_resource(Time::<Fixed>::from_duration(TICK))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(TICK))
+        .add_systems(FixedUpdate, tick_weapon_swap_cooldown)
+        .add_systems(
+            Update,
+            (
+                handle_weapon_swaps,
+                charge_up_and_release_attack.run_if(in_state(MenuOpen(false))),
+            )
+                .chain(),
+        );
+
+    // Lets handle_weapon_swaps see the starting equipment
+    app.update();
+    app
+}
+
+fn equip(app: &mut App, slot_name: &EquipmentSlotName, item_name: ItemName) {
+    *app.world_mut()
+        .resource_mut::<Inventory>()
+        .equipment
+        .at_mut(slot_name) = Some(Item::new(item_name, 1));
+    app.world_mut().send_event(InventoryChanged);
+}
+
+fn set_player_state(app: &mut App, player_state: PlayerState) {
+    app.world_mut()
+        .resource_mut::<NextState<PlayerState>>()
+        .set(player_state);
+    app.update();
+}
+
+fn player_state(app: &App) -> PlayerState {
+    app.world().resource::<State<PlayerState>>().get().clone()
+}
+
+#[test]
+fn test_weapon_swap_cancels_attack_with_same_hand() {
+    let mut app = new_test_app();
+
+    let attacking = PlayerState::Attacking(AttackType::Heavy, AttackHand::Left);
+    set_player_state(&mut app, attacking.clone());
+
+    // Swapping the other hand does not interrupt the attack
+    equip(
+        &mut app,
+        &EquipmentSlotName::RightHand,
+        ItemName::Broadsword,
+    );
+    app.update();
+    app.update();
+    assert_eq!(player_state(&app), attacking);
+
+    equip(&mut app, &EquipmentSlotName::LeftHand, ItemName::Katana);
+    app.update();
+    app.update();
+    assert_eq!(player_state(&app), PlayerState::Walking);
+    assert!(!app
+        .world()
+        .resource::<AttackChargeUp>()
+        .is_charging_hand(&AttackHand::Left));
+}
+
+#[test]
+fn test_weapon_swap_cooldown_blocks_attacks_with_that_hand() {
+    let mut app = new_test_app();
+
+    equip(&mut app, &EquipmentSlotName::LeftHand, ItemName::Katana);
+    app.update();
+
+    let press = |app: &mut App, mouse_button: MouseButton| {
+        let mut mouse = app.world_mut().resource_mut::<ButtonInput<MouseButton>>();
+        mouse.reset_all();
+        mouse.press(mouse_button);
+    };
+    let is_charging = |app: &App, attack_hand: AttackHand| {
+        app.world()
+            .resource::<AttackChargeUp>()
+            .is_charging_hand(&attack_hand)
+    };
+
+    // The swapped hand cannot start charging an attack
+    press(&mut app, MouseButton::Left);
+    app.update();
+    assert!(!is_charging(&app, AttackHand::Left));
+
+    // The other hand is unaffected
+    press(&mut app, MouseButton::Right);
+    app.update();
+    assert!(is_charging(&app, AttackHand::Right));
+
+    for _ in 0..COOLDOWN_FRAMES {
+        app.update();
+    }
+
+    press(&mut app, MouseButton::Left);
+    app.update();
+    assert!(is_charging(&app, AttackHand::Left));
+}
+
+fn set_mouse(app: &mut App, mouse_buttons: &[MouseButton]) {
+    let mut mouse = app.world_mut().resource_mut::<ButtonInput<MouseButton>>();
+    mouse.reset_all();
+    for mouse_button in mouse_buttons {
+        mouse.press(*mouse_button);
+    }
+}
+
+fn is_charging_hand(app: &App, attack_hand: AttackHand) -> bool {
+    app.world()
+        .resource::<AttackChargeUp>()
+        .is_charging_hand(&attack_hand)
+}
+
+fn cancelled_events(app: &App) -> usize {
+    app.world()
+        .resource::<Events<AttackChargeCancelled>>()
+        .iter_current_update_events()
+        .count()
+}
+
+#[test]
+fn test_opposite_button_cancels_charging_attack() {
+    let mut app = new_test_app();
+
+    set_mouse(&mut app, &[MouseButton::Left]);
+    app.update();
+    assert!(is_charging_hand(&app, AttackHand::Left));
+
+    set_mouse(&mut app, &[MouseButton::Left, MouseButton::Right]);
+    app.update();
+    assert!(!is_charging_hand(&app, AttackHand::Left));
+    assert!(!is_charging_hand(&app, AttackHand::Right));
+    assert_eq!(cancelled_events(&app), 1);
+
+    // Letting go of the cancelled hand does not attack
+    set_mouse(&mut app, &[MouseButton::Right]);
+    app.update();
+    app.update();
+    assert_eq!(player_state(&app), PlayerState::Walking);
+
+    // The cancelling button has to be let go before it charges up
+    assert!(!is_charging_hand(&app, AttackHand::Right));
+    set_mouse(&mut app, &[]);
+    app.update();
+    set_mouse(&mut app, &[MouseButton::Right]);
+    app.update();
+    assert!(is_charging_hand(&app, AttackHand::Right));
+}
+
+#[test]
+fn test_cancel_key_cancels_charging_attack() {
+    let mut app = new_test_app();
+
+    // Nothing to cancel
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(ATTACK_CANCEL_KEY);
+    app.update();
+    assert_eq!(cancelled_events(&app), 0);
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .reset_all();
+
+    set_mouse(&mut app, &[MouseButton::Left]);
+    app.update();
+    assert!(is_charging_hand(&app, AttackHand::Left));
+
+    // Still held down, but no longer just pressed
+    app.world_mut()
+        .resource_mut::<ButtonInput<MouseButton>>()
+        .clear();
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(ATTACK_CANCEL_KEY);
+    app.update();
+    assert!(!is_charging_hand(&app, AttackHand::Left));
+    assert_eq!(cancelled_events(&app), 1);
+
+    set_mouse(&mut app, &[]);
+    app.update();
+    app.update();
+    assert_eq!(player_state(&app), PlayerState::Walking);
+}
+
+fn click(app: &mut App, mouse_button: MouseButton) {
+    set_mouse(app, &[mouse_button]);
+    app.update();
+    set_mouse(app, &[]);
+    app.update();
+}
+
+#[test]
+fn test_click_during_attack_is_buffered() {
+    let mut app = new_test_app();
+    let attacking = PlayerState::Attacking(AttackType::Light, AttackHand::Left);
+
+    click(&mut app, MouseButton::Left);
+    app.update();
+    assert_eq!(player_state(&app), attacking);
+
+    // Clicked again before the swing is over
+    click(&mut app, MouseButton::Left);
+    assert!(!is_charging_hand(&app, AttackHand::Left));
+    assert!(app.world().resource::<BufferedAttack>().is_buffered());
+
+    // Like on_finish_attack_animation once the swing's animation is over
+    set_player_state(&mut app, PlayerState::Walking);
+    assert_eq!(player_state(&app), PlayerState::Walking);
+    app.update();
+    assert_eq!(player_state(&app), attacking);
+    assert!(!app.world().resource::<BufferedAttack>().is_buffered());
+}
+
+fn dual_attack(app: &mut App, stamina: f32) -> (PlayerState, f32) {
+    let player = app
+        .world_mut()
+        .spawn((Player, Stamina::new(stamina, 100.0, 0.0)))
+        .id();
+
+    let mut mouse = app.world_mut().resource_mut::<ButtonInput<MouseButton>>();
+    mouse.press(MouseButton::Left);
+    mouse.press(MouseButton::Right);
+    app.update();
+
+    // Both let go of on the same frame
+    app.world_mut()
+        .resource_mut::<ButtonInput<MouseButton>>()
+        .reset_all();
+    app.update();
+    app.update();
+
+    let stamina = app.world().get::<Stamina>(player).unwrap().value;
+    (player_state(app), stamina)
+}
+
+#[test]
+fn test_dual_wield_attacks_with_both_hands() {
+    let mut app = new_test_app();
+    equip(&mut app, &EquipmentSlotName::LeftHand, ItemName::Katana);
+    equip(&mut app, &EquipmentSlotName::RightHand, ItemName::Katana);
+    for _ in 0..=COOLDOWN_FRAMES {
+        app.update();
+    }
+
+    let (state, stamina) = dual_attack(&mut app, 50.0);
+    assert_eq!(state, PlayerState::DualAttacking(AttackType::Light));
+    assert!(state.is_attacking_with(&AttackHand::Left));
+    assert!(state.is_attacking_with(&AttackHand::Right));
+    assert_eq!(stamina, 50.0 - DUAL_ATTACK_STAMINA_COST);
+}
+
+#[test]
+fn test_dual_attack_without_stamina_attacks_with_one_hand() {
+    let mut app = new_test_app();
+    equip(&mut app, &EquipmentSlotName::LeftHand, ItemName::Katana);
+    equip(&mut app, &EquipmentSlotName::RightHand, ItemName::Katana);
+    for _ in 0..=COOLDOWN_FRAMES {
+        app.update();
+    }
+
+    let (state, stamina) = dual_attack(&mut app, DUAL_ATTACK_STAMINA_COST - 1.0);
+    assert!(matches!(
+        state,
+        PlayerState::Attacking(AttackType::Light, _)
+    ));
+    assert_eq!(stamina, DUAL_ATTACK_STAMINA_COST - 1.0);
+}
+
+fn new_dmg_test_app() -> App {
+    let mut app = App::new();
+
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .init_state::<GameSettings>()
+        .add_event::<TakeDamage>()
+        .add_event::<EntityKilled>()
+        .add_event::<DamageApplied>()
+        .add_systems(
+            Update,
+            (
+                handle_take_damage,
+                apply_knockback,
+                despawn_killed_entities.after(handle_take_damage),
+            ),
+        );
+
+    app
+}
+
+fn spawn_target(app: &mut App, translation: Vec3, health: f32) -> Entity {
+    app.world_mut()
+        .spawn((
+            Health::new(health, health, 0.0),
+            Killable,
+            Velocity::zero(),
+            GlobalTransform::from_translation(translation),
+        ))
+        .id()
+}
+
+fn set_difficulty(app: &mut App, difficulty: Difficulty) {
+    let mut game_settings = *app.world().resource::<State<GameSettings>>().get();
+    game_settings.difficulty = difficulty;
+    app.world_mut()
+        .resource_mut::<NextState<GameSettings>>()
+        .set(game_settings);
+    app.update();
+}
+
+fn deal_dmg(app: &mut App, amt: f32, target: Entity, source: Option<Entity>) {
+    app.world_mut().send_event(TakeDamage {
+        amounts: vec![(DmgType::Slash, amt)],
+        target,
+        source,
+    });
+    app.update();
+}
+
+fn killed_events(app: &App) -> Vec<(Entity, Option<Entity>)> {
+    let events = app.world().resource::<Events<EntityKilled>>();
+    events
+        .iter_current_update_events()
+        .map(|event| (event.target, event.source))
+        .collect()
+}
+
+#[test]
+fn test_killing_blow_sends_entity_killed() {
+    let mut app = new_dmg_test_app();
+
+    let source = app.world_mut().spawn(GlobalTransform::IDENTITY).id();
+    let target = spawn_target(&mut app, Vec3::X, 20.0);
+
+    deal_dmg(&mut app, 5.0, target, Some(source));
+    assert!(killed_events(&app).is_empty());
+    assert!(app.world().get_entity(target).is_some());
+
+    deal_dmg(&mut app, 15.0, target, Some(source));
+    assert_eq!(killed_events(&app), vec![(target, Some(source))]);
+    assert!(app.world().get_entity(target).is_none());
+}
+
+#[test]
+fn test_knockback_pushes_away_from_source() {
+    let mut app = new_dmg_test_app();
+
+    let source = app
+        .world_mut()
+        .spawn(GlobalTransform::from_translation(Vec3::new(0.0, 5.0, 0.0)))
+        .id();
+    let target = spawn_target(&mut app, Vec3::new(0.0, 0.0, 2.0), 1000.0);
+
+    // Damage without a source does not knock back
+    deal_dmg(&mut app, 10.0, target, None);
+    assert_eq!(
+        app.world().get::<Velocity>(target).unwrap().linvel,
+        Vec3::ZERO
+    );
+
+    deal_dmg(&mut app, 10.0, target, Some(source));
+    let linvel = app.world().get::<Velocity>(target).unwrap().linvel;
+    assert!(linvel.z > 0.0);
+    assert_eq!(linvel.x, 0.0);
+    assert_eq!(linvel.y, 0.0);
+
+    // Knockback is capped, no matter how big the hit
+    deal_dmg(&mut app, 900.0, target, Some(source));
+    let capped_linvel = app.world().get::<Velocity>(target).unwrap().linvel;
+    assert!(capped_linvel.z - linvel.z <= 10.0);
+}
+
+fn new_spawn_test_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin {
+            watch_for_changes_override: Some(false),
+            ..default()
+        },
+    ))
+    .init_asset::<Scene>();
+
+    app
+}
+
+fn spawn_player(app: &mut App, config: PlayerSpawnConfig, difficulty: Difficulty) -> Entity {
+    app.world_mut().run_system_once(
+        move |mut commands: Commands, asset_server: Res<AssetServer>| {
+            spawn_player_with_config(&mut commands, &config, difficulty, &asset_server)
+        },
+    )
+}
+
+#[test]
+fn test_spawn_player_with_config() {
+    let mut app = new_spawn_test_app();
+
+    let default_config = PlayerSpawnConfig::default();
+    let custom_config = PlayerSpawnConfig {
+        collider_half_extents: Vec3::new(0.5, 1.0, 0.5),
+        max_health: 250.0,
+        max_stamina: 50.0,
+        walking_speed: 120.0,
+        spawn_position: Vec3::new(-6.0, 5.0, 10.0),
+        gravity_scale: 1.0,
+        ..default()
+    };
+
+    let default_player = spawn_player(&mut app, default_config.clone(), Difficulty::Normal);
+    let custom_player = spawn_player(&mut app, custom_config.clone(), Difficulty::Normal);
+
+    let world = app.world();
+    for (entity, config) in [
+        (default_player, default_config),
+        (custom_player, custom_config),
+    ] {
+        let health = world.get::<Health>(entity).unwrap();
+        assert_eq!(health.value, config.max_health);
+        assert_eq!(health.max_value, config.max_health);
+        let stamina = world.get::<Stamina>(entity).unwrap();
+        assert_eq!(stamina.max_value, config.max_stamina);
+        assert_eq!(world.get::<Speed>(entity).unwrap().0, config.walking_speed);
+        assert_eq!(
+            world.get::<GravityScale>(entity).unwrap().0,
+            config.gravity_scale
+        );
+        assert_eq!(
+            world.get::<Transform>(entity).unwrap().translation,
+            config.spawn_position
+        );
+        assert_eq!(
+            world
+                .get::<Collider>(entity)
+                .unwrap()
+                .as_cuboid()
+                .unwrap()
+                .half_extents(),
+            config.collider_half_extents
+        );
+    }
+
+    assert_ne!(
+        world.get::<Health>(default_player).unwrap().max_value,
+        world.get::<Health>(custom_player).unwrap().max_value
+    );
+}
+
+#[test]
+fn test_difficulty_scales_dmg_to_player_only() {
+    let mut app = new_dmg_test_app();
+    set_difficulty(&mut app, Difficulty::Hard);
+
+    let player = spawn_target(&mut app, Vec3::X, 100.0);
+    app.world_mut().entity_mut(player).insert(Player);
+    let other = spawn_target(&mut app, Vec3::Z, 100.0);
+
+    let multiplier = Difficulty::Hard.modifiers().player_dmg_taken;
+    deal_dmg(&mut app, 10.0, player, None);
+    deal_dmg(&mut app, 10.0, other, None);
+
+    let health = |app: &App, entity| app.world().get::<Health>(entity).unwrap().value;
+    assert_eq!(health(&app, player), 100.0 - 10.0 * multiplier);
+    assert_eq!(health(&app, other), 90.0);
+
+    // Changing difficulty affects the next hit, without touching health already lost
+    set_difficulty(&mut app, Difficulty::Easy);
+    assert_eq!(health(&app, player), 100.0 - 10.0 * multiplier);
+
+    deal_dmg(&mut app, 10.0, player, None);
+    assert_eq!(
+        health(&app, player),
+        100.0 - 10.0 * multiplier - 10.0 * Difficulty::Easy.modifiers().player_dmg_taken
+    );
+}
+
+#[test]
+fn test_damage_applied_is_health_actually_lost() {
+    let mut app = new_dmg_test_app();
+    set_difficulty(&mut app, Difficulty::Hard);
+
+    let player = spawn_target(&mut app, Vec3::X, 100.0);
+    app.world_mut().entity_mut(player).insert(Player);
+    let mut reader = app.world().resource::<Events<DamageApplied>>().get_reader();
+    let mut applied = |app: &App| {
+        let events = app.world().resource::<Events<DamageApplied>>();
+        reader
+            .read(events)
+            .map(|event| (event.target, event.amt))
+            .collect::<Vec<_>>()
+    };
+
+    deal_dmg(&mut app, 10.0, player, None);
+    assert_eq!(
+        applied(&app),
+        vec![(player, 10.0 * Difficulty::Hard.modifiers().player_dmg_taken)]
+    );
+
+    // Nothing is lost while immune
+    app.world_mut()
+        .entity_mut(player)
+        .insert(DmgImmune::new(None));
+    deal_dmg(&mut app, 10.0, player, None);
+    assert!(applied(&app).is_empty());
+}
+
+#[test]
+fn test_difficulty_scales_player_regen() {
+    let mut app = new_spawn_test_app();
+    let config = PlayerSpawnConfig::default();
+
+    for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+        let player = spawn_player(&mut app, config.clone(), difficulty);
+        let multiplier = difficulty.modifiers().player_regen;
+
+        let mut health = app.world_mut().get_mut::<Health>(player).unwrap();
+        assert!((health.get_regen() - config.health_regen * multiplier).abs() < 0.0001);
+        let mut stamina = app.world_mut().get_mut::<Stamina>(player).unwrap();
+        assert!((stamina.get_regen() - config.stamina_regen * multiplier).abs() < 0.0001);
+    }
+}
+
+#[test]
+fn test_player_speed_matrix() {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .init_state::<PlayerState>()
+        .init_resource::<PlayerSpawnConfig>()
+        .init_resource::<EquipmentStats>()
+        .add_systems(
+            Update,
+            change_player_speed.run_if(state_changed::<PlayerState>),
+        );
+
+    let player = app
+        .world_mut()
+        .spawn((Player, Speed(0.0), ActiveStatusEffects::new()))
+        .id();
+
+    let config = PlayerSpawnConfig::default();
+    let chilled = StatusEffect::Chilled.speed_multiplier();
+    assert!(PLAYER_ATTACKING_SPEED < config.walking_speed);
+
+    for (is_chilled, multiplier) in [(false, 1.0), (true, chilled)] {
+        if is_chilled {
+            app.world_mut()
+                .get_mut::<ActiveStatusEffects>(player)
+                .unwrap()
+                .apply(StatusEffect::Chilled);
+        }
+
+        for (player_state, base_speed) in [
+            (PlayerState::Sprinting, config.sprinting_speed),
+            (
+                PlayerState::Attacking(AttackType::Light, AttackHand::Right),
+                PLAYER_ATTACKING_SPEED,
+            ),
+            (PlayerState::Walking, config.walking_speed),
+        ] {
+            set_player_state(&mut app, player_state.clone());
+            let speed = app.world().get::<Speed>(player).unwrap().0;
+            assert_eq!(speed, base_speed * multiplier, "{:?}", player_state);
+        }
+    }
+}
+
+fn new_equipment_stats_test_app() -> App {
+    let mut app = App::new();
+
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .add_event::<InventoryChanged>()
+        .init_state::<PlayerState>()
+        .init_resource::<Inventory>()
+        .init_resource::<PlayerSpawnConfig>()
+        .init_resource::<EquipmentStats>()
+        .add_systems(
+            Update,
+            (
+                update_equipment_stats,
+                change_player_speed.run_if(
+                    state_changed::<PlayerState>.or_else(resource_changed::<EquipmentStats>),
+                ),
+                update_equipment_dmg_resists,
+            )
+                .chain(),
+        );
+
+    app
+}
+
+#[test]
+fn test_equipment_stats_follow_equipment() {
+    let mut app = new_equipment_stats_test_app();
+    let player = app.world_mut().spawn((Player, Speed(0.0))).id();
+    app.update();
+
+    let unarmed = *app.world().resource::<EquipmentStats>();
+    assert_eq!(unarmed, EquipmentStats::default());
+    let walking_speed = app.world().get::<Speed>(player).unwrap().0;
+    assert_eq!(walking_speed, PlayerSpawnConfig::default().walking_speed);
+
+    equip(
+        &mut app,
+        &EquipmentSlotName::RightHand,
+        ItemName::Broadsword,
+    );
+    app.update();
+
+    let broadsword = *app.world().resource::<EquipmentStats>();
+    assert!(broadsword.speed_multiplier < 1.0);
+    assert!(broadsword.sprint_stamina_drain > unarmed.sprint_stamina_drain);
+    assert!(broadsword.attack_stamina_cost > unarmed.attack_stamina_cost);
+    assert!(app.world().get::<Speed>(player).unwrap().0 < walking_speed);
+
+    // Swapping twice in a row ends up the same as equipping once
+    equip(&mut app, &EquipmentSlotName::RightHand, ItemName::Katana);
+    app.update();
+    let katana = *app.world().resource::<EquipmentStats>();
+    assert!(katana.speed_multiplier > broadsword.speed_multiplier);
+
+    equip(
+        &mut app,
+        &EquipmentSlotName::RightHand,
+        ItemName::Broadsword,
+    );
+    app.update();
+    assert_eq!(*app.world().resource::<EquipmentStats>(), broadsword);
+
+    // Unequipping removes every penalty
+    *app.world_mut()
+        .resource_mut::<Inventory>()
+        .equipment
+        .at_mut(&EquipmentSlotName::RightHand) = None;
+    app.world_mut().send_event(InventoryChanged);
+    app.update();
+
+    assert_eq!(*app.world().resource::<EquipmentStats>(), unarmed);
+    assert_eq!(app.world().get::<Speed>(player).unwrap().0, walking_speed);
+}
+
+#[test]
+fn test_pair_bonus_needs_the_same_weapon_in_both_hands() {
+    let mut app = new_equipment_stats_test_app();
+    let player = app
+        .world_mut()
+        .spawn((Player, Speed(0.0), DmgResist::new()))
+        .id();
+    app.update();
+    let walking_speed = app.world().get::<Speed>(player).unwrap().0;
+
+    equip(&mut app, &EquipmentSlotName::LeftHand, ItemName::Katana);
+    app.update();
+    let one_katana = *app.world().resource::<EquipmentStats>();
+    assert_eq!(one_katana.pair_bonus, None);
+
+    equip(&mut app, &EquipmentSlotName::RightHand, ItemName::Katana);
+    app.update();
+    let dual_katana = *app.world().resource::<EquipmentStats>();
+    assert_eq!(dual_katana.pair_bonus, ItemName::Katana.pair_bonus());
+    // Faster than with one of them, despite the weight of the second one
+    assert!(dual_katana.speed_multiplier > one_katana.speed_multiplier);
+    assert_eq!(
+        app.world().get::<Speed>(player).unwrap().0,
+        walking_speed * dual_katana.speed_multiplier
+    );
+
+    // Swapping one of them for a different weapon takes the bonus away
+    equip(&mut app, &EquipmentSlotName::LeftHand, ItemName::Broadsword);
+    app.update();
+    assert_eq!(app.world().resource::<EquipmentStats>().pair_bonus, None);
+    assert_eq!(
+        app.world()
+            .get::<DmgResist>(player)
+            .unwrap()
+            .get_resist(&DmgType::Slash),
+        0.0
+    );
+
+    equip(
+        &mut app,
+        &EquipmentSlotName::RightHand,
+        ItemName::Broadsword,
+    );
+    app.update();
+    let Some(PairBonus::SlashResist(slash_resist)) = ItemName::Broadsword.pair_bonus() else {
+        panic!("dual broadswords should resist slash damage");
+    };
+    let dmg_resist = app.world().get::<DmgResist>(player).unwrap();
+    assert_eq!(dmg_resist.get_resist(&DmgType::Slash), slash_resist);
+    assert_eq!(dmg_resist.get_resist(&DmgType::Pierce), 0.0);
+
+    // However many times the inventory changes, the bonus is only applied once
+    for _ in 0..3 {
+        app.world_mut().send_event(InventoryChanged);
+        app.update();
+    }
+    let dmg_resist = app.world().get::<DmgResist>(player).unwrap();
+    assert_eq!(dmg_resist.get_resist(&DmgType::Slash), slash_resist);
+
+    // Unequipping either one removes it, leaving everything as it was
+    *app.world_mut()
+        .resource_mut::<Inventory>()
+        .equipment
+        .at_mut(&EquipmentSlotName::LeftHand) = None;
+    app.world_mut().send_event(InventoryChanged);
+    app.update();
+    assert_eq!(app.world().resource::<EquipmentStats>().pair_bonus, None);
+    let dmg_resist = app.world().get::<DmgResist>(player).unwrap();
+    assert_eq!(dmg_resist.get_resist(&DmgType::Slash), 0.0);
+}
+
+#[test]
+fn test_pair_bonus_resist_is_given_to_a_newly_spawned_player() {
+    let mut app = new_equipment_stats_test_app();
+    equip(&mut app, &EquipmentSlotName::LeftHand, ItemName::Broadsword);
+    equip(
+        &mut app,
+        &EquipmentSlotName::RightHand,
+        ItemName::Broadsword,
+    );
+    app.update();
+
+    // Like after dying, with nothing about the equipment changing
+    let player = app
+        .world_mut()
+        .spawn((Player, Speed(0.0), DmgResist::new()))
+        .id();
+    app.update();
+
+    let dmg_resist = app.world().get::<DmgResist>(player).unwrap();
+    assert!(dmg_resist.get_resist(&DmgType::Slash) > 0.0);
+}
+
+// Stamina used up by sprinting for one second, at a frame rate
+fn stamina_drained_in_one_second(fps: u32) -> f32 {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .insert_resource(Time::<Fixed>::from_hz(PLAYER_TICK_HZ))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(
+            Duration::from_secs(1) / fps,
+        ))
+        .insert_state(PlayerState::Sprinting)
+        .init_resource::<EquipmentStats>()
+        .add_systems(
+            FixedUpdate,
+            (drain_stamina_while_sprinting, temp_stamina_regen).chain(),
+        );
+
+    let player = app
+        .world_mut()
+        .spawn((Player, Stamina::new(1000.0, 1000.0, 1.0)))
+        .id();
+    // Time only starts counting from the first update
+    app.update();
+
+    for _ in 0..fps {
+        app.update();
+    }
+    1000.0 - app.world().get::<Stamina>(player).unwrap().value
+}
+
+#[test]
+fn test_stamina_drain_independent_of_frame_rate() {
+    let drained = stamina_drained_in_one_second(60);
+    assert!(drained > 0.0);
+
+    for fps in [30, 144] {
+        assert_eq!(stamina_drained_in_one_second(fps), drained, "{} fps", fps);
+    }
+}
+
+#[test]
+fn test_save_critical_types_are_reflected() {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        StatesPlugin,
+        PlayerPlugin,
+        InventoryPlugin,
+        SettingsPlugin,
+    ));
+
+    let type_registry = app.world().resource::<AppTypeRegistry>().read();
+    for type_id in [
+        TypeId::of::<Health>(),
+        TypeId::of::<Stamina>(),
+        TypeId::of::<DmgResist>(),
+        TypeId::of::<Inventory>(),
+        TypeId::of::<Item>(),
+        TypeId::of::<Equipment>(),
+        TypeId::of::<GameSettings>(),
+    ] {
+        assert!(type_registry.contains(type_id));
+    }
+
+    // Components and resources can be looked up by the inspector
+    assert!(type_registry
+        .get_type_data::<ReflectComponent>(TypeId::of::<Health>())
+        .is_some());
+    assert!(type_registry
+        .get_type_data::<ReflectResource>(TypeId::of::<Inventory>())
+        .is_some());
+}
+
+fn new_light_fuel_test_app(light_fuel: LightFuelMode) -> (App, Entity) {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .add_event::<InventoryChanged>()
+        .add_event::<RefuelLight>()
+        .init_resource::<Inventory>()
+        .insert_state(GameSettings {
+            light_fuel,
+            ..default()
+        })
+        .add_systems(
+            Update,
+            (auto_refuel_light, handle_refuel_light, burn_light_fuel).chain(),
+        );
+
+    let spot_light = SpotLight::default();
+    let light = app
+        .world_mut()
+        .spawn((
+            LightFuel::new(spot_light.intensity, spot_light.range),
+            spot_light,
+        ))
+        .id();
+    (app, light)
+}
+
+fn set_light_fuel(app: &mut App, light: Entity, value: f32) {
+    app.world_mut().get_mut::<LightFuel>(light).unwrap().value = value;
+}
+
+#[test]
+fn test_refuel_light_event() {
+    let (mut app, light) = new_light_fuel_test_app(LightFuelMode::Manual);
+    set_light_fuel(&mut app, light, 0.0);
+    app.update();
+
+    let full_intensity = app.world().get::<LightFuel>(light).unwrap().full_intensity;
+    assert!(app.world().get::<SpotLight>(light).unwrap().intensity < full_intensity);
+
+    app.world_mut().send_event(RefuelLight(50.0));
+    app.update();
+
+    let fuel = app.world().get::<LightFuel>(light).unwrap();
+    assert!(fuel.fraction() > 0.49 && fuel.fraction() <= 0.5);
+    assert_eq!(
+        app.world().get::<SpotLight>(light).unwrap().intensity,
+        full_intensity
+    );
+}
+
+#[test]
+fn test_light_auto_refuels_with_coal() {
+    let (mut app, light) = new_light_fuel_test_app(LightFuelMode::Auto);
+    app.world_mut().resource_mut::<Inventory>().slots[3] = Some(Item::new(ItemName::Coal, 2));
+    app.update();
+
+    // Nothing is used up while there is still fuel left
+    assert_eq!(
+        app.world().resource::<Inventory>().slots[3],
+        Some(Item::new(ItemName::Coal, 2))
+    );
+
+    set_light_fuel(&mut app, light, 0.0);
+    app.update();
+
+    assert_eq!(
+        app.world().resource::<Inventory>().slots[3],
+        Some(Item::new(ItemName::Coal, 1))
+    );
+    assert!(!app.world().get::<LightFuel>(light).unwrap().is_empty());
+}
+
+#[test]
+fn test_light_fuel_off_restores_full_brightness() {
+    let (mut app, light) = new_light_fuel_test_app(LightFuelMode::Manual);
+    set_light_fuel(&mut app, light, 0.0);
+    app.update();
+
+    app.world_mut()
+        .resource_mut::<NextState<GameSettings>>()
+        .set(GameSettings::default());
+    app.update();
+    app.update();
+
+    let fuel = *app.world().get::<LightFuel>(light).unwrap();
+    let spot_light = app.world().get::<SpotLight>(light).unwrap();
+    assert_eq!(spot_light.intensity, fuel.full_intensity);
+    assert_eq!(spot_light.range, fuel.full_range);
+    // No fuel is burnt, or used up, with the setting off
+    assert!(fuel.is_empty());
+}
+
+fn new_impact_test_app(disable_hit_effects: bool) -> App {
+    let mut app = App::new();
+
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin {
+            watch_for_changes_override: Some(false),
+            ..default()
+        },
+        HierarchyPlugin,
+        StatesPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<StandardMaterial>()
+    .add_event::<AttackImpact>()
+    .init_resource::<SwingImpacts>()
+    .insert_state(GameSettings {
+        disable_hit_effects,
+        ..default()
+    })
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+        100,
+    )))
+    .add_systems(Update, (handle_attack_impacts, update_impact_vfx).chain());
+
+    app
+}
+
+fn send_impacts(app: &mut App, amt: usize) {
+    let target = app.world_mut().spawn_empty().id();
+    for _ in 0..amt {
+        app.world_mut().send_event(AttackImpact {
+            position: Vec3::new(1.0, 1.0, 1.0),
+            target,
+        });
+    }
+}
+
+#[test]
+fn test_attack_impacts_capped_per_swing() {
+    let mut app = new_impact_test_app(false);
+    send_impacts(&mut app, MAX_IMPACTS_PER_SWING as usize + 2);
+    app.update();
+
+    let world = app.world_mut();
+    let vfx = world.query::<&ImpactVfx>().iter(world).count();
+    assert_eq!(vfx, MAX_IMPACTS_PER_SWING as usize);
+    assert!(world.query::<&ImpactDebris>().iter(world).count() > 0);
+    assert_eq!(
+        app.world().resource::<SwingImpacts>().hit_stop_speed(),
+        HIT_STOP_SPEED
+    );
+
+    // Everything is despawned once the effect is over
+    for _ in 0..10 {
+        app.update();
+    }
+    let world = app.world_mut();
+    assert_eq!(world.query::<&ImpactVfx>().iter(world).count(), 0);
+    assert_eq!(world.query::<&ImpactDebris>().iter(world).count(), 0);
+}
+
+#[test]
+fn test_attack_impacts_disabled_in_settings() {
+    let mut app = new_impact_test_app(true);
+    send_impacts(&mut app, 1);
+    app.update();
+
+    let world = app.world_mut();
+    assert_eq!(world.query::<&ImpactVfx>().iter(world).count(), 0);
+    assert_eq!(app.world().resource::<SwingImpacts>().hit_stop_speed(), 1.0);
+}
+
+fn new_movement_test_app(movement_style: MovementStyle) -> (App, Entity) {
+    let mut app = App::new();
+
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            1.0 / 60.0,
+        )))
+        .init_resource::<ButtonInput<KeyCode>>()
+        .init_state::<PlayerState>()
+        .insert_state(GameSettings {
+            movement_style,
+            ..default()
+        })
+        .add_systems(Update, player_ground_movement);
+
+    // Turned a little away from looking straight down -z
+    app.world_mut().spawn((
+        MainCamera,
+        Transform::from_rotation(Quat::from_rotation_y(0.3)),
+    ));
+    let player = app
+        .world_mut()
+        .spawn((
+            Player,
+            Transform::default(),
+            Velocity::default(),
+            Speed(1.0),
+            FacingTarget::default(),
+        ))
+        .id();
+
+    // The first update has no delta
+    app.update();
+
+    (app, player)
+}
+
+fn set_movement_style(app: &mut App, movement_style: MovementStyle) {
+    let mut game_settings = *app.world().resource::<State<GameSettings>>().get();
+    game_settings.movement_style = movement_style;
+    app.world_mut()
+        .resource_mut::<NextState<GameSettings>>()
+        .set(game_settings);
+}
+
+fn move_forward(app: &mut App, frames: usize) {
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::KeyW);
+    for _ in 0..frames {
+        app.update();
+    }
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .release_all();
+}
+
+fn movement_direction(app: &App, player: Entity) -> Vec3 {
+    let linvel = app.world().get::<Velocity>(player).unwrap().linvel;
+    Vec3::new(linvel.x, 0.0, linvel.z).normalize_or_zero()
+}
+
+#[test]
+fn test_free_movement_follows_the_camera() {
+    let (mut app, player) = new_movement_test_app(MovementStyle::Free);
+    move_forward(&mut app, 1);
+
+    let camera_forward = Quat::from_rotation_y(0.3) * Vec3::NEG_Z;
+    assert!(movement_direction(&app, player).abs_diff_eq(camera_forward, 0.0001));
+
+    // Turned to the direction of movement straight away, which the model faces away from
+    let player_transform = app.world().get::<Transform>(player).unwrap();
+    assert!(player_transform.back().abs_diff_eq(camera_forward, 0.0001));
+    assert_eq!(app.world().get::<FacingTarget>(player).unwrap().get(), None);
+}
+
+#[test]
+fn test_snapped_movement_follows_the_world_axes() {
+    let (mut app, player) = new_movement_test_app(MovementStyle::Snapped);
+
+    move_forward(&mut app, 1);
+    assert_eq!(movement_direction(&app, player), Vec3::NEG_Z);
+
+    // Turns all the way around over a few frames, instead of all at once
+    let target = app
+        .world()
+        .get::<FacingTarget>(player)
+        .unwrap()
+        .get()
+        .unwrap();
+    let rotation = app.world().get::<Transform>(player).unwrap().rotation;
+    assert!(rotation.angle_between(target) > 0.1);
+
+    // Keeps turning after letting go
+    for _ in 0..10 {
+        app.update();
+    }
+    assert_eq!(
+        app.world().get::<Transform>(player).unwrap().rotation,
+        target
+    );
+    assert_eq!(app.world().get::<FacingTarget>(player).unwrap().get(), None);
+}
+
+#[test]
+fn test_switching_movement_style_mid_turn_lets_go_of_the_turn() {
+    let (mut app, player) = new_movement_test_app(MovementStyle::Snapped);
+    move_forward(&mut app, 1);
+    assert!(app
+        .world()
+        .get::<FacingTarget>(player)
+        .unwrap()
+        .get()
+        .is_some());
+
+    set_movement_style(&mut app, MovementStyle::Free);
+    app.update();
+    let rotation = app.world().get::<Transform>(player).unwrap().rotation;
+    assert_eq!(app.world().get::<FacingTarget>(player).unwrap().get(), None);
+
+    // Nothing left over to turn toward once snapped again
+    set_movement_style(&mut app, MovementStyle::Snapped);
+    for _ in 0..10 {
+        app.update();
+    }
+    assert_eq!(
+        app.world().get::<Transform>(player).unwrap().rotation,
+        rotation
+    );
+}
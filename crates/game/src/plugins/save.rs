@@ -1,10 +1,22 @@
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_text_popup::{TextPopupEvent, TextPopupLocation, TextPopupTimeout};
 use dungeon_maze_common::{
+    app_state::{AppState, GameLoaded},
+    automap::{RevealedCells, VisitedCells, VisitedCellsChanged},
+    death_marker::{DeathMarkers, DeathMarkersChanged},
     error::Error,
-    inventory::{Inventory, InventoryChanged},
-    save::{GameSave, GameSaveRead, WorldDataChanged},
+    hud::{HudLayout, HudLayoutChanged},
+    inventory::{Inventory, InventoryChanged, SavedInventory},
+    localization::Localization,
+    menu::MenuOpen,
+    player::PlayerState,
+    save::{
+        AutosaveTimer, GameSave, GameSaveRead, SaveDebounce, SaveLoadReport, SaveRequested,
+        WorldDataChanged,
+    },
     settings::GameSettings,
-    world::data::WorldData,
+    stats::{PlayerStats, PlayerStatsChanged},
+    world::{data::WorldData, WorldConfig, WorldSeed},
 };
 use platform_dirs::AppDirs;
 use std::{
@@ -15,41 +27,193 @@ use std::{
 const DATA_DIR_NAME: &str = "dungeon_maze";
 const SAVE_DIR_NAME: &str = "saves";
 const SAVE_FILE_NAME: &str = "dungeon_maze_save.json";
+const SAVE_KEY: KeyCode = KeyCode::F5;
 
 pub struct GameSavePlugin;
 
 impl Plugin for GameSavePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<WorldData>()
+            .init_resource::<WorldSeed>()
+            .init_resource::<WorldConfig>()
+            .init_resource::<SaveLoadReport>()
+            .init_resource::<SaveDebounce>()
+            .init_resource::<AutosaveTimer>()
             .add_event::<WorldDataChanged>()
+            .add_event::<SaveRequested>()
             .add_systems(Startup, load_save_data)
-            .add_systems(Update, save_game_automatically);
+            .add_systems(OnEnter(GameLoaded), show_save_load_report)
+            .add_systems(
+                Update,
+                (
+                    (
+                        request_save_on_change,
+                        (request_autosave, request_manual_save).run_if(in_state(AppState::InGame)),
+                    ),
+                    handle_save_requests,
+                )
+                    .chain(),
+            );
     }
 }
 
 fn load_save_data(mut commands: Commands, mut next_game_settings: ResMut<NextState<GameSettings>>) {
     let game_save = read_game_save().unwrap_or_default();
     next_game_settings.set(game_save.game_settings);
-    commands.insert_resource(game_save.inventory);
+    let (inventory, unrestored_items) = game_save.inventory.restore();
+    for item in unrestored_items.iter() {
+        warn!(
+            "Failed to restore item from save: ({}) {}",
+            item.amt, item.name
+        );
+    }
+    commands.insert_resource(inventory);
+    commands.insert_resource(SaveLoadReport { unrestored_items });
     commands.insert_resource(game_save.world_data);
+    commands.insert_resource(game_save.visited_cells);
+    commands.insert_resource(game_save.revealed_cells);
+    commands.insert_resource(game_save.world_seed);
+    commands.insert_resource(game_save.world_config);
+    commands.insert_resource(game_save.hud_layout);
+    commands.insert_resource(game_save.player_stats);
+    commands.insert_resource(game_save.death_markers);
+}
+
+pub fn show_save_load_report(
+    mut popup_event_writer: EventWriter<TextPopupEvent>,
+    mut report: ResMut<SaveLoadReport>,
+    localization: Res<Localization>,
+) {
+    if report.is_empty() {
+        return;
+    }
+
+    let report = std::mem::take(report.as_mut());
+    popup_event_writer.send(TextPopupEvent {
+        content: localization.tr(
+            "popup.items_not_restored",
+            &[("amt", &report.unrestored_items.len())],
+        ),
+        location: TextPopupLocation::BottomLeft,
+        timeout: TextPopupTimeout::Seconds(6),
+        ..default()
+    });
+}
+
+// Everything that goes into the save
+#[derive(SystemParam)]
+struct SavedResources<'w> {
+    game_settings: Res<'w, State<GameSettings>>,
+    inventory: Res<'w, Inventory>,
+    world_data: Res<'w, WorldData>,
+    visited_cells: Res<'w, VisitedCells>,
+    revealed_cells: Res<'w, RevealedCells>,
+    world_seed: Res<'w, WorldSeed>,
+    world_config: Res<'w, WorldConfig>,
+    hud_layout: Res<'w, HudLayout>,
+    player_stats: Res<'w, PlayerStats>,
+    death_markers: Res<'w, DeathMarkers>,
 }
 
-fn save_game_automatically(
-    gs_event_reader: EventReader<StateTransitionEvent<GameSettings>>,
-    inv_event_reader: EventReader<InventoryChanged>,
-    wd_event_reader: EventReader<WorldDataChanged>,
+impl SavedResources<'_> {
+    fn game_save(&self) -> GameSave {
+        GameSave {
+            game_settings: *self.game_settings.get(),
+            inventory: SavedInventory::from(self.inventory.as_ref()),
+            world_data: self.world_data.clone(),
+            visited_cells: self.visited_cells.clone(),
+            revealed_cells: self.revealed_cells.clone(),
+            world_seed: *self.world_seed,
+            world_config: *self.world_config,
+            hud_layout: self.hud_layout.clone(),
+            player_stats: self.player_stats.clone(),
+            death_markers: self.death_markers.clone(),
+        }
+    }
+}
+
+fn request_save_on_change(
+    mut event_writer: EventWriter<SaveRequested>,
+    mut gs_event_reader: EventReader<StateTransitionEvent<GameSettings>>,
+    mut inv_event_reader: EventReader<InventoryChanged>,
+    mut wd_event_reader: EventReader<WorldDataChanged>,
+    // Revealed cells only change along with the visited cells
+    mut vc_event_reader: EventReader<VisitedCellsChanged>,
+    mut hl_event_reader: EventReader<HudLayoutChanged>,
+    mut ps_event_reader: EventReader<PlayerStatsChanged>,
+    mut dm_event_reader: EventReader<DeathMarkersChanged>,
+) {
+    let changes = gs_event_reader.read().count()
+        + inv_event_reader.read().count()
+        + wd_event_reader.read().count()
+        + vc_event_reader.read().count()
+        + hl_event_reader.read().count()
+        + ps_event_reader.read().count()
+        + dm_event_reader.read().count();
+
+    if changes > 0 {
+        event_writer.send(SaveRequested::Changed);
+    }
+}
+
+// Held off while the menu is open or the player is attacking, where a save could hitch
+fn request_autosave(
+    mut event_writer: EventWriter<SaveRequested>,
+    mut autosave_timer: ResMut<AutosaveTimer>,
+    time: Res<Time>,
     game_settings: Res<State<GameSettings>>,
-    inventory: Res<Inventory>,
-    world_data: Res<WorldData>,
+    menu_open: Res<State<MenuOpen>>,
+    player_state: Res<State<PlayerState>>,
+) {
+    let held_off = menu_open.get().0 || player_state.get().is_attacking();
+    let interval = game_settings.autosave_minutes.interval();
+    if autosave_timer.tick(time.delta(), interval, held_off) {
+        event_writer.send(SaveRequested::Auto);
+    }
+}
+
+fn request_manual_save(
+    mut event_writer: EventWriter<SaveRequested>,
+    keys: Res<ButtonInput<KeyCode>>,
+) {
+    if keys.just_pressed(SAVE_KEY) {
+        event_writer.send(SaveRequested::Manual);
+    }
+}
+
+fn handle_save_requests(
+    mut event_reader: EventReader<SaveRequested>,
+    mut popup_event_writer: EventWriter<TextPopupEvent>,
+    mut save_debounce: ResMut<SaveDebounce>,
+    saved_resources: SavedResources,
+    localization: Res<Localization>,
+    // Debounced on real time, so that it still goes by while the game is paused
+    time: Res<Time<Real>>,
 ) {
-    if !gs_event_reader.is_empty() || !inv_event_reader.is_empty() || !wd_event_reader.is_empty() {
-        write_game_save(GameSave {
-            game_settings: game_settings.clone(),
-            inventory: inventory.clone(),
-            world_data: world_data.clone(),
-        })
-        .unwrap();
+    for save_requested in event_reader.read() {
+        save_debounce.request(*save_requested);
+    }
+    // Any number of requests waiting on the debounce are saved once, as the one with a popup
+    let Some(save_requested) = save_debounce.take_due(time.elapsed()) else {
+        return;
+    };
+
+    if let Err(err) = write_game_save(saved_resources.game_save()) {
+        error!("Failed to save the game: {}", err);
+        return;
     }
+    if let Some(popup_key) = save_requested.popup_key() {
+        popup_event_writer.send(TextPopupEvent {
+            content: localization.tr(popup_key, &[]),
+            location: TextPopupLocation::BottomLeft,
+            timeout: TextPopupTimeout::Seconds(2),
+            ..default()
+        });
+    }
+}
+
+pub fn save_file_exists() -> bool {
+    fs::exists(get_save_file_path(SAVE_FILE_NAME)).unwrap_or(false)
 }
 
 fn read_game_save() -> Result<GameSave, Error> {
@@ -65,6 +229,20 @@ fn read_game_save() -> Result<GameSave, Error> {
             game_settings: r.game_settings.unwrap_or_default(),
             inventory: r.inventory.unwrap_or_default(),
             world_data: r.world_data.unwrap_or_default(),
+            visited_cells: r.visited_cells.unwrap_or_default(),
+            revealed_cells: r.revealed_cells.unwrap_or_default(),
+            world_seed: r.world_seed.unwrap_or_default(),
+            // Saves from before the surface level existed keep every level enclosed
+            world_config: r.world_config.unwrap_or_else(WorldConfig::enclosed),
+            hud_layout: r
+                .hud_layout
+                .map(|mut hud_layout| {
+                    hud_layout.fill_missing_widgets();
+                    hud_layout
+                })
+                .unwrap_or_default(),
+            player_stats: r.player_stats.unwrap_or_default(),
+            death_markers: r.death_markers.unwrap_or_default(),
         }),
         Err(err) => return Err(Error::loading(err)),
     }
@@ -1,11 +1,42 @@
 use bevy::prelude::*;
-use dungeon_maze_common::settings::*;
+use dungeon_maze_common::{localization::Localization, settings::*};
 
 pub struct SettingsPlugin;
 
 impl Plugin for SettingsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<RenderDistChanged>()
-            .init_state::<GameSettings>();
+        app.register_type::<GameSettings>()
+            .register_type::<State<GameSettings>>()
+            .add_event::<RenderDistChanged>()
+            .init_state::<GameSettings>()
+            .init_resource::<Localization>()
+            .init_resource::<UiScale>()
+            .add_systems(Update, (update_localization, update_ui_scale));
+    }
+}
+
+fn update_localization(
+    mut event_reader: EventReader<StateTransitionEvent<GameSettings>>,
+    mut localization: ResMut<Localization>,
+    game_settings: Res<State<GameSettings>>,
+) {
+    for _ in event_reader.read() {
+        let language = game_settings.get().language;
+        if localization.language() != language {
+            *localization = Localization::new(language);
+        }
+    }
+}
+
+fn update_ui_scale(
+    mut event_reader: EventReader<StateTransitionEvent<GameSettings>>,
+    mut ui_scale: ResMut<UiScale>,
+    game_settings: Res<State<GameSettings>>,
+) {
+    for _ in event_reader.read() {
+        let scale = game_settings.get().ui_scale.get();
+        if ui_scale.0 != scale {
+            ui_scale.0 = scale;
+        }
     }
 }
@@ -0,0 +1,233 @@
+use crate::plugins::camera::{activate_camera, deactivate_camera};
+use bevy::{
+    input::mouse::{MouseMotion, MouseWheel},
+    prelude::*,
+};
+use bevy_rapier3d::plugin::RapierConfiguration;
+use bevy_third_person_camera::ThirdPersonCamera;
+use dungeon_maze_common::{
+    app_state::AppState,
+    camera::{AltCamera, MainCamera},
+    menu::MenuOpen,
+    settings::GameSettings,
+    spectator::{HiddenBySpectator, SpectatorCamera},
+};
+use std::f32::consts::FRAC_PI_2;
+
+const SPECTATOR_MODE_KEY: KeyCode = KeyCode::F8;
+const SPECTATOR_DEFAULT_SPEED: f32 = 10.0;
+const SPECTATOR_MIN_SPEED: f32 = 1.0;
+const SPECTATOR_MAX_SPEED: f32 = 100.0;
+// Speed is multiplied or divided by this for each scroll wheel step
+const SPECTATOR_SPEED_SCROLL_FACTOR: f32 = 1.2;
+const SPECTATOR_MOUSE_SENSITIVITY: f32 = 0.003;
+// Keeps the camera from flipping over when looking straight up or down
+const SPECTATOR_MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+pub struct SpectatorPlugin;
+
+impl Plugin for SpectatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::Spectating),
+            (detach_main_camera, hide_ui_nodes, freeze_physics),
+        )
+        .add_systems(
+            OnExit(AppState::Spectating),
+            (reattach_main_camera, show_ui_nodes, unfreeze_physics),
+        )
+        .add_systems(
+            Update,
+            (
+                toggle_spectating.run_if(
+                    spectator_mode_allowed.and_then(
+                        in_state(AppState::Spectating).or_else(
+                            in_state(AppState::InGame).and_then(in_state(MenuOpen(false))),
+                        ),
+                    ),
+                ),
+                (
+                    spectator_mouse_look,
+                    spectator_movement,
+                    change_spectator_speed,
+                )
+                    .run_if(in_state(AppState::Spectating)),
+            ),
+        );
+    }
+}
+
+// The ThirdPersonCamera component is moved here while spectating,
+// so the camera stops following the player without losing its zoom or settings
+#[derive(Component)]
+struct DetachedThirdPersonCamera(ThirdPersonCamera);
+
+fn spectator_mode_allowed(game_settings: Res<State<GameSettings>>) -> bool {
+    cfg!(debug_assertions) || game_settings.get().enable_spectator_mode
+}
+
+fn toggle_spectating(
+    keys: Res<ButtonInput<KeyCode>>,
+    app_state: Res<State<AppState>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    if keys.just_pressed(SPECTATOR_MODE_KEY) {
+        match app_state.get() {
+            AppState::InGame => next_app_state.set(AppState::Spectating),
+            AppState::Spectating => next_app_state.set(AppState::InGame),
+            AppState::MainMenu | AppState::Loading | AppState::Paused => {}
+        }
+    }
+}
+
+fn detach_main_camera(
+    mut commands: Commands,
+    mut main_camera_query: Query<(Entity, &Transform, &mut Camera), With<MainCamera>>,
+    mut alt_camera_query: Query<&mut Camera, (With<AltCamera>, Without<MainCamera>)>,
+) {
+    for mut alt_camera in alt_camera_query.iter_mut() {
+        deactivate_camera(&mut alt_camera);
+    }
+
+    for (entity, transform, mut camera) in main_camera_query.iter_mut() {
+        activate_camera(&mut camera);
+
+        let (yaw, pitch, _) = transform.rotation.to_euler(EulerRot::YXZ);
+        commands.entity(entity).insert(SpectatorCamera {
+            yaw,
+            pitch,
+            speed: SPECTATOR_DEFAULT_SPEED,
+            attached_transform: *transform,
+        });
+
+        commands.add(move |world: &mut World| {
+            let mut entity_mut = world.entity_mut(entity);
+            if let Some(third_person_camera) = entity_mut.take::<ThirdPersonCamera>() {
+                entity_mut.insert(DetachedThirdPersonCamera(third_person_camera));
+            }
+        });
+    }
+}
+
+fn reattach_main_camera(
+    mut commands: Commands,
+    mut camera_query: Query<(Entity, &SpectatorCamera, &mut Transform)>,
+) {
+    for (entity, spectator_camera, mut transform) in camera_query.iter_mut() {
+        *transform = spectator_camera.attached_transform;
+
+        commands.add(move |world: &mut World| {
+            let mut entity_mut = world.entity_mut(entity);
+            entity_mut.remove::<SpectatorCamera>();
+            if let Some(DetachedThirdPersonCamera(third_person_camera)) =
+                entity_mut.take::<DetachedThirdPersonCamera>()
+            {
+                entity_mut.insert(third_person_camera);
+            }
+        });
+    }
+}
+
+fn hide_ui_nodes(
+    mut commands: Commands,
+    mut ui_query: Query<(Entity, &mut Visibility, Has<Parent>), With<Node>>,
+) {
+    // Hiding root nodes is enough, since their descendants inherit their visibility
+    for (entity, mut visibility, has_parent) in ui_query.iter_mut() {
+        if has_parent {
+            continue;
+        }
+
+        commands
+            .entity(entity)
+            .insert(HiddenBySpectator(*visibility));
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn show_ui_nodes(
+    mut commands: Commands,
+    mut ui_query: Query<(Entity, &HiddenBySpectator, &mut Visibility)>,
+) {
+    for (entity, hidden_by_spectator, mut visibility) in ui_query.iter_mut() {
+        *visibility = hidden_by_spectator.0;
+        commands.entity(entity).remove::<HiddenBySpectator>();
+    }
+}
+
+// Keeps the player from falling out of the world once the chunks around them are unloaded
+fn freeze_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = false;
+}
+
+fn unfreeze_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = true;
+}
+
+fn spectator_mouse_look(
+    mut event_reader: EventReader<MouseMotion>,
+    mut camera_query: Query<(&mut SpectatorCamera, &mut Transform)>,
+) {
+    let delta: Vec2 = event_reader.read().map(|event| event.delta).sum();
+    if delta == Vec2::ZERO {
+        return;
+    }
+
+    for (mut spectator_camera, mut transform) in camera_query.iter_mut() {
+        spectator_camera.yaw -= delta.x * SPECTATOR_MOUSE_SENSITIVITY;
+        spectator_camera.pitch = (spectator_camera.pitch - delta.y * SPECTATOR_MOUSE_SENSITIVITY)
+            .clamp(-SPECTATOR_MAX_PITCH, SPECTATOR_MAX_PITCH);
+
+        transform.rotation = Quat::from_euler(
+            EulerRot::YXZ,
+            spectator_camera.yaw,
+            spectator_camera.pitch,
+            0.0,
+        );
+    }
+}
+
+fn spectator_movement(
+    mut camera_query: Query<(&SpectatorCamera, &mut Transform)>,
+    keys: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+) {
+    for (spectator_camera, mut transform) in camera_query.iter_mut() {
+        let mut direction = Vec3::ZERO;
+
+        if keys.pressed(KeyCode::KeyW) {
+            direction += *transform.forward();
+        }
+        if keys.pressed(KeyCode::KeyS) {
+            direction += *transform.back();
+        }
+        if keys.pressed(KeyCode::KeyA) {
+            direction += *transform.left();
+        }
+        if keys.pressed(KeyCode::KeyD) {
+            direction += *transform.right();
+        }
+
+        transform.translation +=
+            direction.normalize_or_zero() * spectator_camera.speed * time.delta_seconds();
+    }
+}
+
+fn change_spectator_speed(
+    mut event_reader: EventReader<MouseWheel>,
+    mut camera_query: Query<&mut SpectatorCamera>,
+) {
+    for event in event_reader.read() {
+        if event.y == 0.0 {
+            continue;
+        }
+
+        // Line and pixel scroll units differ wildly, so only the direction is used
+        let factor = SPECTATOR_SPEED_SCROLL_FACTOR.powf(event.y.signum());
+
+        for mut spectator_camera in camera_query.iter_mut() {
+            spectator_camera.speed =
+                (spectator_camera.speed * factor).clamp(SPECTATOR_MIN_SPEED, SPECTATOR_MAX_SPEED);
+        }
+    }
+}
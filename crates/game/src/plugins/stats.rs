@@ -0,0 +1,208 @@
+use crate::plugins::world::bundle::special::TREASURE_CHEST_OPEN_ANIMATION;
+use bevy::prelude::*;
+use dungeon_maze_common::{
+    animation::CyclicAnimationCycled,
+    app_state::AppState,
+    inventory::{ItemPickedUp, PlayerDroppedItem},
+    localization::Localization,
+    player::{DamageApplied, Player},
+    stats::{PlayerStats, PlayerStatsChanged},
+    world::{ActiveChunk, OCItemContainer},
+};
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerStats>()
+            .add_event::<PlayerStatsChanged>()
+            .add_systems(
+                Update,
+                (
+                    record_distance_traveled,
+                    record_chunks_visited,
+                    record_chests_opened,
+                    record_items_picked_up_and_dropped,
+                    record_damage,
+                )
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+pub fn record_distance_traveled(
+    player_query: Query<&GlobalTransform, With<Player>>,
+    mut player_stats: ResMut<PlayerStats>,
+    mut prev_translation: Local<Option<Vec3>>,
+) {
+    // The player is missing while loading and respawning, which starts the count over
+    let Ok(gt) = player_query.get_single() else {
+        *prev_translation = None;
+        return;
+    };
+    let translation = gt.translation();
+
+    if let Some(prev) = *prev_translation {
+        player_stats.record_movement(prev, translation);
+    }
+    *prev_translation = Some(translation);
+}
+
+pub fn record_chunks_visited(
+    mut event_writer: EventWriter<PlayerStatsChanged>,
+    active_chunk: Res<State<ActiveChunk>>,
+    mut player_stats: ResMut<PlayerStats>,
+) {
+    if !active_chunk.is_changed() {
+        return;
+    }
+
+    if player_stats.visit_chunk(active_chunk.get().to_tuple()) {
+        event_writer.send(PlayerStatsChanged);
+    }
+}
+
+// Containers cycle between closed and open with every interaction,
+// and only playing the open clip counts
+pub fn record_chests_opened(
+    mut event_reader: EventReader<CyclicAnimationCycled>,
+    mut event_writer: EventWriter<PlayerStatsChanged>,
+    container_query: Query<(), With<OCItemContainer>>,
+    mut player_stats: ResMut<PlayerStats>,
+) {
+    for event in event_reader.read() {
+        if event.clip == TREASURE_CHEST_OPEN_ANIMATION && container_query.contains(event.entity) {
+            player_stats.chests_opened += 1;
+            event_writer.send(PlayerStatsChanged);
+        }
+    }
+}
+
+pub fn record_items_picked_up_and_dropped(
+    mut ipu_event_reader: EventReader<ItemPickedUp>,
+    mut pdi_event_reader: EventReader<PlayerDroppedItem>,
+    mut event_writer: EventWriter<PlayerStatsChanged>,
+    mut player_stats: ResMut<PlayerStats>,
+) {
+    let picked_up: u32 = ipu_event_reader
+        .read()
+        .map(|event| event.0.amt as u32)
+        .sum();
+    let dropped: u32 = pdi_event_reader
+        .read()
+        .map(|event| event.0.amt as u32)
+        .sum();
+
+    if picked_up == 0 && dropped == 0 {
+        return;
+    }
+
+    player_stats.items_picked_up += picked_up;
+    player_stats.items_dropped += dropped;
+    event_writer.send(PlayerStatsChanged);
+}
+
+// Damage to the player counts as taken, and damage the player deals
+// to anything else counts as dealt. Only health that was actually lost counts,
+// so damage that was resisted or dealt while immune does not.
+pub fn record_damage(
+    mut event_reader: EventReader<DamageApplied>,
+    mut event_writer: EventWriter<PlayerStatsChanged>,
+    player_query: Query<Entity, With<Player>>,
+    mut player_stats: ResMut<PlayerStats>,
+) {
+    let Ok(player_entity) = player_query.get_single() else {
+        return;
+    };
+
+    let mut changed = false;
+    for event in event_reader.read() {
+        if event.target == player_entity {
+            player_stats.damage_taken += event.amt;
+            changed = true;
+        } else if event.source == Some(player_entity) {
+            player_stats.damage_dealt += event.amt;
+            changed = true;
+        }
+    }
+
+    if changed {
+        event_writer.send(PlayerStatsChanged);
+    }
+}
+
+pub fn spawn_stats_menu_content(
+    child_builder: &mut ChildBuilder,
+    player_stats: &PlayerStats,
+    localization: &Localization,
+) {
+    child_builder.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(
+                localization.tr("menu.stats", &[]),
+                TextStyle {
+                    font_size: 20.0,
+                    ..default()
+                },
+            )],
+            ..default()
+        },
+        ..default()
+    });
+
+    let rows = [
+        (
+            "menu.stats.distance_traveled",
+            player_stats.format_distance(),
+        ),
+        (
+            "menu.stats.chunks_visited",
+            player_stats.chunks_visited().to_string(),
+        ),
+        (
+            "menu.stats.chests_opened",
+            player_stats.chests_opened.to_string(),
+        ),
+        (
+            "menu.stats.items_picked_up",
+            player_stats.items_picked_up.to_string(),
+        ),
+        (
+            "menu.stats.items_dropped",
+            player_stats.items_dropped.to_string(),
+        ),
+        (
+            "menu.stats.damage_dealt",
+            PlayerStats::format_damage(player_stats.damage_dealt),
+        ),
+        (
+            "menu.stats.damage_taken",
+            PlayerStats::format_damage(player_stats.damage_taken),
+        ),
+    ];
+
+    for (key, value) in rows {
+        child_builder
+            .spawn(NodeBundle {
+                style: Style {
+                    display: Display::Flex,
+                    justify_content: JustifyContent::SpaceBetween,
+                    width: Val::Percent(90.0),
+                    margin: UiRect::top(Val::Px(10.0)),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                for text in [localization.tr(key, &[]), value] {
+                    parent.spawn(TextBundle::from_section(
+                        text,
+                        TextStyle {
+                            font_size: 16.0,
+                            ..default()
+                        },
+                    ));
+                }
+            });
+    }
+}
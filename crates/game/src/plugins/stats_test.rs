@@ -0,0 +1,144 @@
+use crate::plugins::{
+    stats::StatsPlugin,
+    world::bundle::special::{TREASURE_CHEST_MAX_ANIMATION, TREASURE_CHEST_OPEN_ANIMATION},
+};
+use bevy::{prelude::*, state::app::StatesPlugin};
+use dungeon_maze_common::{
+    animation::CyclicAnimationCycled,
+    app_state::AppState,
+    inventory::{
+        item::{Item, ItemName},
+        ItemPickedUp, PlayerDroppedItem,
+    },
+    player::{DamageApplied, Player},
+    stats::PlayerStats,
+    world::{ActiveChunk, OCItemContainer},
+};
+
+fn new_test_app() -> App {
+    let mut app = App::new();
+
+    app.add_plugins((MinimalPlugins, StatesPlugin, StatsPlugin))
+        .add_event::<CyclicAnimationCycled>()
+        .add_event::<ItemPickedUp>()
+        .add_event::<PlayerDroppedItem>()
+        .add_event::<DamageApplied>()
+        .init_state::<ActiveChunk>()
+        .insert_state(AppState::InGame);
+
+    app
+}
+
+fn stats(app: &App) -> &PlayerStats {
+    app.world().resource::<PlayerStats>()
+}
+
+#[test]
+fn test_distance_ignores_teleports() {
+    let mut app = new_test_app();
+    let player = app
+        .world_mut()
+        .spawn((Player, GlobalTransform::default()))
+        .id();
+    app.update();
+
+    for translation in [
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(1.0, 0.0, 2.0),
+        // Respawning far away
+        Vec3::new(500.0, 0.0, 2.0),
+        Vec3::new(500.0, 0.0, 5.0),
+    ] {
+        *app.world_mut().get_mut::<GlobalTransform>(player).unwrap() =
+            GlobalTransform::from_translation(translation);
+        app.update();
+    }
+
+    assert_eq!(stats(&app).distance_traveled, 6.0);
+}
+
+#[test]
+fn test_chunks_visited_on_active_chunk_change() {
+    let mut app = new_test_app();
+    app.update();
+
+    for active_chunk in [
+        ActiveChunk(1, 0, 0),
+        ActiveChunk(0, 0, 0),
+        ActiveChunk(1, 0, 0),
+    ] {
+        app.world_mut()
+            .resource_mut::<NextState<ActiveChunk>>()
+            .set(active_chunk);
+        app.update();
+    }
+
+    assert_eq!(stats(&app).chunks_visited(), 2);
+}
+
+#[test]
+fn test_chests_opened_counts_only_opening() {
+    let mut app = new_test_app();
+    let chest = app.world_mut().spawn(OCItemContainer).id();
+    let door = app.world_mut().spawn_empty().id();
+
+    // Open, close, open again, along with something that is not a container
+    for (entity, clip) in [
+        (chest, TREASURE_CHEST_OPEN_ANIMATION),
+        (chest, TREASURE_CHEST_MAX_ANIMATION),
+        (door, TREASURE_CHEST_OPEN_ANIMATION),
+        (chest, TREASURE_CHEST_OPEN_ANIMATION),
+    ] {
+        app.world_mut()
+            .send_event(CyclicAnimationCycled { entity, clip });
+        app.update();
+    }
+
+    assert_eq!(stats(&app).chests_opened, 2);
+}
+
+#[test]
+fn test_items_picked_up_and_dropped() {
+    let mut app = new_test_app();
+
+    app.world_mut()
+        .send_event(ItemPickedUp(Item::new(ItemName::Coal, 3)));
+    app.world_mut()
+        .send_event(PlayerDroppedItem(Item::new(ItemName::Coal, 1)));
+    app.update();
+
+    assert_eq!(stats(&app).items_picked_up, 3);
+    assert_eq!(stats(&app).items_dropped, 1);
+}
+
+#[test]
+fn test_damage_partitioned_by_target() {
+    let mut app = new_test_app();
+    let player = app.world_mut().spawn(Player).id();
+    let enemy = app.world_mut().spawn_empty().id();
+
+    for event in [
+        DamageApplied {
+            target: player,
+            source: Some(enemy),
+            amt: 10.0,
+        },
+        DamageApplied {
+            target: enemy,
+            source: Some(player),
+            amt: 25.0,
+        },
+        // Neither dealt by nor dealt to the player
+        DamageApplied {
+            target: enemy,
+            source: None,
+            amt: 5.0,
+        },
+    ] {
+        app.world_mut().send_event(event);
+    }
+    app.update();
+
+    assert_eq!(stats(&app).damage_taken, 10.0);
+    assert_eq!(stats(&app).damage_dealt, 25.0);
+}
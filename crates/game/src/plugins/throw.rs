@@ -0,0 +1,181 @@
+use crate::plugins::world::{
+    bundle::item::spawn_item_bundle, physics::projectile_collision_groups,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use dungeon_maze_common::{
+    app_state::AppState,
+    camera::MainCamera,
+    inventory::{Inventory, InventoryChanged},
+    menu::MenuOpen,
+    player::{
+        impact::ImpactVfx,
+        throw::{throw_origin, throw_velocity, NoiseEvent, Thrown, THROWN_ITEM_DMG},
+        DmgTarget, DmgType, Player, TakeDamage,
+    },
+    world::{ChunkCellMarker, ChunkIndex, WorldDims},
+};
+
+pub const THROW_KEY: KeyCode = KeyCode::KeyG;
+
+const NOISE_VFX_INNER_RADIUS: f32 = 0.1;
+const NOISE_VFX_OUTER_RADIUS: f32 = 0.15;
+const NOISE_VFX_COLOR: Color = Color::srgba(0.9, 0.9, 1.0, 0.5);
+
+pub struct ThrowPlugin;
+
+impl Plugin for ThrowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<NoiseEvent>().add_systems(
+            Update,
+            (
+                throw_item.run_if(in_state(MenuOpen(false))),
+                land_thrown_items,
+                despawn_thrown_items,
+                handle_noise_events,
+            )
+                .chain()
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+// Throws one of the first throwable item in the inventory along the aim of the camera
+pub fn throw_item(
+    mut commands: Commands,
+    mut inv_event_writer: EventWriter<InventoryChanged>,
+    camera_query: Query<&GlobalTransform, With<MainCamera>>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut inventory: ResMut<Inventory>,
+    keys: Res<ButtonInput<KeyCode>>,
+) {
+    if !keys.just_pressed(THROW_KEY) {
+        return;
+    }
+    let Ok(camera_gt) = camera_query.get_single() else {
+        return;
+    };
+    let Some(item) = inventory.take_throwable() else {
+        return;
+    };
+
+    spawn_item_bundle(
+        item,
+        &mut commands,
+        &asset_server,
+        &mut meshes,
+        &mut materials,
+        Some(Transform::from_translation(throw_origin(camera_gt))),
+        false,
+        true,
+        true,
+    )
+    .insert((
+        Thrown::default(),
+        Velocity::linear(throw_velocity(camera_gt)),
+        Ccd::enabled(),
+        // Small enough to fit through the gaps of a window
+        projectile_collision_groups(),
+        Name::new("Thrown Item"),
+    ));
+    inv_event_writer.send(InventoryChanged);
+}
+
+// The first time a thrown item touches anything solid, it hurts whatever it hit directly,
+// makes a noise, and is parented to the chunk it landed in
+pub fn land_thrown_items(
+    mut commands: Commands,
+    mut take_damage_writer: EventWriter<TakeDamage>,
+    mut noise_event_writer: EventWriter<NoiseEvent>,
+    mut thrown_query: Query<(Entity, &mut Thrown, &GlobalTransform)>,
+    dmg_target_query: Query<Entity, With<DmgTarget>>,
+    player_query: Query<Entity, With<Player>>,
+    rapier_context: Res<RapierContext>,
+    chunk_index: Res<ChunkIndex>,
+    world_dims: Res<WorldDims>,
+) {
+    let player = player_query.get_single().ok();
+
+    for (entity, mut thrown, gt) in thrown_query.iter_mut() {
+        if thrown.has_landed() {
+            continue;
+        }
+
+        // Targets can be sensors, which are only ever intersected
+        let target = dmg_target_query.iter().find(|target| {
+            rapier_context
+                .intersection_pair(entity, *target)
+                .unwrap_or(false)
+                || rapier_context
+                    .contact_pair(entity, *target)
+                    .is_some_and(|pair| pair.has_any_active_contact())
+        });
+        let touching = target.is_some()
+            || rapier_context
+                .contact_pairs_with(entity)
+                .any(|pair| pair.has_any_active_contact());
+
+        if !touching || !thrown.land() {
+            continue;
+        }
+
+        if let Some(target) = target {
+            take_damage_writer.send(TakeDamage {
+                amounts: vec![(DmgType::Blunt, THROWN_ITEM_DMG)],
+                target,
+                source: player,
+            });
+        }
+        noise_event_writer.send(NoiseEvent {
+            position: gt.translation(),
+            source: player,
+        });
+
+        let ccm = ChunkCellMarker::from_global_transform(gt, &world_dims);
+        if let Some(chunk_entity) = chunk_index.entity(ccm.chunk_xyz()) {
+            commands.entity(entity).set_parent_in_place(chunk_entity);
+        }
+    }
+}
+
+pub fn despawn_thrown_items(
+    mut commands: Commands,
+    mut thrown_query: Query<(Entity, &mut Thrown, &Velocity)>,
+    time: Res<Time>,
+) {
+    for (entity, mut thrown, velocity) in thrown_query.iter_mut() {
+        if !thrown.tick(time.delta_seconds()) || thrown.is_at_rest(velocity.linvel.length()) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+// Nothing listens for noises yet, so they are only logged, and shown as a ring that fades out
+pub fn handle_noise_events(
+    mut commands: Commands,
+    mut event_reader: EventReader<NoiseEvent>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for event in event_reader.read() {
+        info!("Noise at {}", event.position);
+
+        commands.spawn((
+            ImpactVfx::default(),
+            PbrBundle {
+                mesh: meshes.add(Torus::new(NOISE_VFX_INNER_RADIUS, NOISE_VFX_OUTER_RADIUS)),
+                material: materials.add(StandardMaterial {
+                    base_color: NOISE_VFX_COLOR,
+                    alpha_mode: AlphaMode::Blend,
+                    unlit: true,
+                    ..default()
+                }),
+                transform: Transform::from_translation(event.position),
+                ..default()
+            },
+            Name::new("Noise VFX"),
+        ));
+    }
+}
@@ -0,0 +1,129 @@
+use crate::plugins::throw::{despawn_thrown_items, throw_item, THROW_KEY};
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use bevy_rapier3d::prelude::Velocity;
+use dungeon_maze_common::{
+    camera::MainCamera,
+    inventory::{
+        item::{Item, ItemName},
+        Inventory, InventoryChanged,
+    },
+    player::throw::{throw_velocity, Thrown, THROWN_ITEM_LIFETIME_SECS},
+};
+use std::time::Duration;
+
+fn new_test_app() -> App {
+    let mut app = App::new();
+
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin {
+            watch_for_changes_override: Some(false),
+            ..default()
+        },
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<StandardMaterial>()
+    .init_asset::<Scene>()
+    .init_asset::<Image>()
+    .add_event::<InventoryChanged>()
+    .init_resource::<Inventory>()
+    .init_resource::<ButtonInput<KeyCode>>()
+    .add_systems(Update, (throw_item, despawn_thrown_items).chain());
+
+    app
+}
+
+fn press_throw(app: &mut App) {
+    let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+    keys.reset_all();
+    keys.press(THROW_KEY);
+    app.update();
+
+    // Without the input plugin, nothing else clears just_pressed, and the key stays held
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .clear();
+}
+
+fn thrown_count(app: &mut App) -> usize {
+    let world = app.world_mut();
+    world.query::<&Thrown>().iter(world).count()
+}
+
+fn thrown(app: &mut App) -> Vec<(Item, Velocity)> {
+    let world = app.world_mut();
+    world
+        .query_filtered::<(&Item, &Velocity), With<Thrown>>()
+        .iter(world)
+        .map(|(item, velocity)| (*item, *velocity))
+        .collect()
+}
+
+#[test]
+fn test_throwing_takes_one_from_the_inventory() {
+    let mut app = new_test_app();
+    let camera_gt = GlobalTransform::from(
+        Transform::from_xyz(0.0, 1.5, 0.0).looking_at(Vec3::new(3.0, 1.5, 4.0), Vec3::Y),
+    );
+    app.world_mut().spawn((MainCamera, camera_gt));
+    app.world_mut().resource_mut::<Inventory>().slots[0] = Some(Item::new(ItemName::Flint, 2));
+
+    press_throw(&mut app);
+    let thrown_items = thrown(&mut app);
+    assert_eq!(thrown_items.len(), 1);
+    assert_eq!(thrown_items[0].0, Item::new(ItemName::Flint, 1));
+    assert_eq!(thrown_items[0].1.linvel, throw_velocity(&camera_gt));
+    assert_eq!(
+        app.world().resource::<Inventory>().slots[0],
+        Some(Item::new(ItemName::Flint, 1))
+    );
+
+    // Holding the key does not throw again
+    app.update();
+    assert_eq!(thrown(&mut app).len(), 1);
+
+    press_throw(&mut app);
+    assert_eq!(thrown(&mut app).len(), 2);
+    assert!(app.world().resource::<Inventory>().slots[0].is_none());
+
+    // Nothing left to throw
+    press_throw(&mut app);
+    assert_eq!(thrown(&mut app).len(), 2);
+}
+
+#[test]
+fn test_non_throwable_items_are_not_thrown() {
+    let mut app = new_test_app();
+    app.world_mut()
+        .spawn((MainCamera, GlobalTransform::default()));
+    app.world_mut().resource_mut::<Inventory>().slots[0] = Some(Item::new(ItemName::Cotton, 3));
+
+    press_throw(&mut app);
+    assert!(thrown(&mut app).is_empty());
+    assert_eq!(
+        app.world().resource::<Inventory>().slots[0],
+        Some(Item::new(ItemName::Cotton, 3))
+    );
+}
+
+#[test]
+fn test_thrown_items_despawn_after_their_lifetime() {
+    let mut app = new_test_app();
+    let step = Duration::from_millis(125);
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(step));
+    // Still flying, so that only the lifetime can despawn it
+    app.world_mut().spawn((
+        Thrown::default(),
+        Velocity::linear(Vec3::new(0.0, 0.0, 1.0)),
+    ));
+
+    // The first update has no delta
+    let updates = (THROWN_ITEM_LIFETIME_SECS / step.as_secs_f32()) as usize;
+    for _ in 0..updates {
+        app.update();
+    }
+    assert_eq!(thrown_count(&mut app), 1);
+
+    app.update();
+    assert_eq!(thrown_count(&mut app), 0);
+}
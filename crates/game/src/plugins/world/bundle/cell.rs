@@ -1,28 +1,42 @@
-use crate::{
-    plugins::world::{
-        bundle::{
-            door::spawn_door_bundle,
-            special::{
-                spawn_chair_bundle, spawn_staircase_bundle, spawn_stairs_bundle,
-                spawn_treasure_chest_bundle,
-            },
-            wall::{spawn_solid_wall_bundle, spawn_wall_bundle},
-            window::spawn_window_bundle,
-            WALL_THICKNESS,
-        },
-        CELL_SIZE, CHUNK_SIZE, GRID_SIZE,
+use crate::plugins::world::bundle::{
+    decor::spawn_decor_bundles,
+    door::spawn_door_bundle,
+    particle::spawn_particle_emitter_bundle,
+    railing::{new_railing_mesh, spawn_railing_bundle},
+    special::{
+        spawn_chair_bundle, spawn_hazard_bundle, spawn_staircase_bundle, spawn_stairs_bundle,
+        spawn_treasure_chest_bundle,
     },
-    SEED,
+    wall::{spawn_solid_wall_bundle, spawn_wall_bundle},
+    window::spawn_window_bundle,
+    WALL_THICKNESS,
 };
 use bevy::prelude::*;
 use dungeon_maze_common::{
-    utils::noise::noise_from_xyz_seed,
-    world::{data::WorldData, Cell, CellSpecial, CellWall, ChunkCellMarker, EntitySpawner, Side},
+    settings::DecorDensity,
+    utils::noise::noise_at_world_pos,
+    world::{
+        data::WorldData, hazard::Hazard, world_structure::StructureTheme, Cell, CellSpecial,
+        CellWall, ChunkCellMarker, EntitySpawner, Side, WorldDims, DEFAULT_CELL_SIZE,
+    },
 };
 
+pub const WALL_TEXTURE_PATHS: [&str; 4] = [
+    "embedded://images/wall-1.png",
+    "embedded://images/wall-2.png",
+    "embedded://images/wall-3.png",
+    "embedded://images/wall-4.png",
+];
+
+// Everything inside of a cell is built for cells of DEFAULT_CELL_SIZE,
+// and scaled to the cell size of the world along with the cell itself
 pub fn spawn_cell_bundle(
+    seed: u32,
+    dims: &WorldDims,
+    decor_density: DecorDensity,
     cell: &Cell,
     ccm: ChunkCellMarker,
+    theme: Option<&StructureTheme>,
     entity_spawner: &mut impl EntitySpawner,
     asset_server: &Res<AssetServer>,
     meshes: &mut ResMut<Assets<Mesh>>,
@@ -31,7 +45,12 @@ pub fn spawn_cell_bundle(
 ) {
     let cell_bundle = (
         SpatialBundle {
-            transform: Transform::from_xyz(calc_floor_pos(ccm.x), 0.0, calc_floor_pos(ccm.z)),
+            transform: Transform::from_xyz(
+                calc_floor_pos(dims, ccm.x),
+                0.0,
+                calc_floor_pos(dims, ccm.z),
+            )
+            .with_scale(Vec3::splat(cell_scale(dims))),
             ..default()
         },
         cell.clone(),
@@ -42,9 +61,9 @@ pub fn spawn_cell_bundle(
     entity_spawner.spawn(cell_bundle).with_children(|parent| {
         let mesh = meshes.add(
             Cuboid::from_size(Vec3 {
-                x: CELL_SIZE,
+                x: DEFAULT_CELL_SIZE,
                 y: WALL_THICKNESS,
-                z: CELL_SIZE,
+                z: DEFAULT_CELL_SIZE,
             })
             .mesh(),
         );
@@ -55,7 +74,7 @@ pub fn spawn_cell_bundle(
                 Side::Down,
                 parent,
                 &mesh,
-                &materials.add(Color::linear_rgba(0.55, 0.0, 0.0, 1.0)),
+                &materials.add(floor_color(theme)),
             );
         }
 
@@ -69,28 +88,9 @@ pub fn spawn_cell_bundle(
             );
         }
 
-        let noise_xyz = noise_from_xyz_seed(
-            SEED,
-            ccm.chunk_x,
-            ccm.chunk_y,
-            ccm.chunk_z,
-            CHUNK_SIZE,
-            CELL_SIZE,
-        );
-
-        let path = if noise_xyz < -0.2 {
-            "embedded://images/wall-1.png"
-        } else if noise_xyz < 0.0 {
-            "embedded://images/wall-2.png"
-        } else if noise_xyz < 0.2 {
-            "embedded://images/wall-3.png"
-        } else {
-            "embedded://images/wall-4.png"
-        };
-
-        let wall_texture_handle = asset_server.load(path);
+        let wall_texture_handle = asset_server.load(wall_texture_path(seed, dims, &ccm));
         let material = materials.add(StandardMaterial {
-            base_color: Color::WHITE,
+            base_color: wall_base_color(theme),
             base_color_texture: Some(wall_texture_handle),
             ..Default::default()
         });
@@ -125,7 +125,27 @@ pub fn spawn_cell_bundle(
             (Side::Right, cell.window_right),
         ] {
             if window {
-                spawn_window_bundle(side, parent, &asset_server);
+                spawn_window_bundle(
+                    side,
+                    &ccm,
+                    parent,
+                    asset_server,
+                    meshes,
+                    materials,
+                    world_data,
+                );
+            }
+        }
+
+        // Railings
+        let railing_sides: Vec<Side> = [Side::Top, Side::Bottom, Side::Left, Side::Right]
+            .into_iter()
+            .filter(|side| cell.railing(*side))
+            .collect();
+        if !railing_sides.is_empty() {
+            let railing_mesh = meshes.add(new_railing_mesh());
+            for side in railing_sides {
+                spawn_railing_bundle(side, parent, &railing_mesh, &material);
             }
         }
 
@@ -136,19 +156,83 @@ pub fn spawn_cell_bundle(
                 spawn_chair_bundle(parent, asset_server);
             }
             CellSpecial::TreasureChest => {
-                spawn_treasure_chest_bundle(parent, asset_server, meshes, world_data, &ccm);
+                spawn_treasure_chest_bundle(
+                    parent,
+                    asset_server,
+                    meshes,
+                    materials,
+                    world_data,
+                    &ccm,
+                    theme,
+                );
             }
             CellSpecial::Staircase => spawn_staircase_bundle(parent, meshes),
             CellSpecial::Stairs => spawn_stairs_bundle(parent, meshes),
+            CellSpecial::SpikeTrap => {
+                spawn_hazard_bundle(Hazard::SpikeTrap, parent, meshes, materials)
+            }
+            CellSpecial::PoisonPool => {
+                spawn_hazard_bundle(Hazard::PoisonPool, parent, meshes, materials)
+            }
         }
+
+        // Decor
+        spawn_decor_bundles(cell, &ccm, decor_density, parent, meshes, materials);
+
+        // Particles
+        spawn_particle_emitter_bundle(cell, &ccm, decor_density, parent, meshes, materials);
     });
 }
 
-fn calc_floor_pos(index: usize) -> f32 {
-    let mut positions = vec![CELL_SIZE / 2.0, -CELL_SIZE / 2.0];
-    while positions.len() < GRID_SIZE {
-        positions.insert(0, positions[0] + CELL_SIZE);
-        positions.push(positions.last().unwrap() - CELL_SIZE);
+pub fn floor_color(theme: Option<&StructureTheme>) -> Color {
+    theme
+        .and_then(StructureTheme::floor_color)
+        .unwrap_or(Color::linear_rgba(0.55, 0.0, 0.0, 1.0))
+}
+
+// Multiplied with the wall texture
+pub fn wall_base_color(theme: Option<&StructureTheme>) -> Color {
+    theme
+        .and_then(StructureTheme::wall_color)
+        .unwrap_or(Color::WHITE)
+}
+
+// Sampled at the cell's world position rather than once per chunk, so the noise changes
+// gradually from cell to cell and a corridor crossing a chunk seam keeps its texture
+pub fn wall_texture_path(seed: u32, dims: &WorldDims, ccm: &ChunkCellMarker) -> &'static str {
+    let noise = noise_at_world_pos(seed, calc_cell_translation(dims, ccm));
+
+    if noise < -0.2 {
+        WALL_TEXTURE_PATHS[0]
+    } else if noise < 0.0 {
+        WALL_TEXTURE_PATHS[1]
+    } else if noise < 0.2 {
+        WALL_TEXTURE_PATHS[2]
+    } else {
+        WALL_TEXTURE_PATHS[3]
     }
-    positions.get(index).unwrap().to_owned()
+}
+
+pub fn calc_cell_translation(dims: &WorldDims, ccm: &ChunkCellMarker) -> Vec3 {
+    cell_world_position(dims, ccm.chunk_xyz(), ccm.cell_xz())
+}
+
+// World position of the center of a cell's floor.
+// The inverse of ChunkCellMarker::from_global_transform.
+pub fn cell_world_position(
+    dims: &WorldDims,
+    chunk_xyz: (i64, i64, i64),
+    cell_xz: (usize, usize),
+) -> Vec3 {
+    ChunkCellMarker::new(chunk_xyz, cell_xz).world_position(dims)
+}
+
+// Offset of the center of a cell from the center of its chunk, along the x or z axis.
+// Cells are stored from +x to -x (and +z to -z), so index 0 has the largest offset.
+pub fn calc_floor_pos(dims: &WorldDims, index: usize) -> f32 {
+    (dims.grid_size() as f32 / 2.0 - index as f32 - 0.5) * dims.cell_size()
+}
+
+pub fn cell_scale(dims: &WorldDims) -> f32 {
+    dims.cell_size() / DEFAULT_CELL_SIZE
 }
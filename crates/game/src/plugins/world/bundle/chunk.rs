@@ -1,39 +1,54 @@
-use crate::{
-    plugins::world::{
-        bundle::cell::spawn_cell_bundle,
-        {chunk_from_xyz_seed, CELL_SIZE, CHUNK_SIZE},
-    },
-    SEED,
-};
+use crate::plugins::world::{bundle::cell::spawn_cell_bundle, chunk_from_xyz_seed};
 use bevy::prelude::*;
-use dungeon_maze_common::world::{
-    data::WorldData, Chunk, ChunkCellMarker, ChunkMarker, EntitySpawner,
+use dungeon_maze_common::{
+    settings::{DecorDensity, Difficulty},
+    world::{
+        data::WorldData, world_structure::WorldStructureRegistry, Chunk, ChunkCellMarker,
+        ChunkMarker, ChunkSpawned, ChunkWorldStructure, EntitySpawner, WorldConfig, WorldDims,
+    },
 };
 
+// World position of the center of a chunk's floor
+pub fn chunk_world_position(dims: &WorldDims, chunk_xyz: (i64, i64, i64)) -> Vec3 {
+    let (x, y, z) = chunk_xyz;
+    Vec3::new(
+        x as f32 * dims.chunk_size(),
+        y as f32 * dims.cell_size(),
+        z as f32 * dims.chunk_size(),
+    )
+}
+
 pub fn spawn_chunk_bundle(
+    seed: u32,
+    dims: &WorldDims,
+    decor_density: DecorDensity,
     chunk: &Chunk,
+    world_structures: &WorldStructureRegistry,
     entity_spawner: &mut impl EntitySpawner,
     asset_server: &Res<AssetServer>,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
     world_data: &Res<WorldData>,
-) {
+) -> Entity {
+    let theme = world_structures.theme(&chunk.world_structure);
     let chunk_bundle = (
         SpatialBundle {
-            transform: Transform::from_xyz(
-                chunk.x as f32 * CHUNK_SIZE,
-                chunk.y as f32 * CELL_SIZE,
-                chunk.z as f32 * CHUNK_SIZE,
-            ),
+            transform: Transform::from_translation(chunk_world_position(
+                dims,
+                (chunk.x, chunk.y, chunk.z),
+            )),
             ..default()
         },
         ChunkMarker((chunk.x, chunk.y, chunk.z)),
+        ChunkWorldStructure(chunk.world_structure.clone()),
         Name::new(format!("Chunk_({},{},{})", chunk.x, chunk.y, chunk.z)),
     );
 
-    entity_spawner.spawn(chunk_bundle).with_children(|parent| {
+    let mut entity_commands = entity_spawner.spawn(chunk_bundle);
+    entity_commands.with_children(|parent| {
         for (z, row) in chunk.cells.iter().enumerate() {
             for (x, cell) in row.iter().enumerate() {
+                let _span = info_span!("spawn_cell_bundle", x, z).entered();
                 let ccm = ChunkCellMarker {
                     chunk_x: chunk.x,
                     chunk_y: chunk.y,
@@ -43,8 +58,12 @@ pub fn spawn_chunk_bundle(
                 };
 
                 spawn_cell_bundle(
+                    seed,
+                    dims,
+                    decor_density,
                     cell,
                     ccm,
+                    theme,
                     parent,
                     asset_server,
                     meshes,
@@ -54,24 +73,61 @@ pub fn spawn_chunk_bundle(
             }
         }
     });
+
+    entity_commands.id()
 }
 
 pub fn spawn_chunk_bundle_from_xyz_seed(
+    seed: u32,
+    difficulty: Difficulty,
+    world_config: &WorldConfig,
+    dims: &WorldDims,
+    world_structures: &WorldStructureRegistry,
+    decor_density: DecorDensity,
     (chunk_x, chunk_y, chunk_z): (i64, i64, i64),
     entity_spawner: &mut impl EntitySpawner,
     asset_server: &Res<AssetServer>,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
     world_data: &Res<WorldData>,
+    chunk_spawned_event_writer: &mut EventWriter<ChunkSpawned>,
 ) {
-    let chunk = chunk_from_xyz_seed(SEED, chunk_x, chunk_y, chunk_z);
+    let _span = info_span!(
+        "spawn_chunk_bundle_from_xyz_seed",
+        chunk_x,
+        chunk_y,
+        chunk_z
+    )
+    .entered();
+
+    let mut chunk = chunk_from_xyz_seed(
+        seed,
+        difficulty,
+        world_config,
+        dims,
+        world_structures,
+        chunk_x,
+        chunk_y,
+        chunk_z,
+    );
+    world_data.apply_cell_mutations(&mut chunk);
 
-    spawn_chunk_bundle(
+    let entity = spawn_chunk_bundle(
+        seed,
+        dims,
+        decor_density,
         &chunk,
+        world_structures,
         entity_spawner,
         asset_server,
         meshes,
         materials,
         world_data,
     );
+
+    chunk_spawned_event_writer.send(ChunkSpawned {
+        entity,
+        xyz: (chunk_x, chunk_y, chunk_z),
+        chunk,
+    });
 }
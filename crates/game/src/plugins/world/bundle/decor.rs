@@ -0,0 +1,188 @@
+use crate::plugins::world::bundle::{INNER_HALF_SIZE, WALL_THICKNESS};
+use bevy::prelude::*;
+use dungeon_maze_common::{
+    settings::DecorDensity,
+    world::{
+        Cell, CellSpecial, CellWall, ChunkCellMarker, Decor, EntitySpawner, Side, DEFAULT_CELL_SIZE,
+    },
+};
+use rand::Rng;
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+// Keeps wall cracks from z-fighting with the wall behind them
+const DECAL_OFFSET: f32 = 0.01;
+const COBWEB_WIDTH: f32 = 1.2;
+
+struct DecorChances {
+    max_rubble: u32,
+    cobweb: f64,
+    crack: f64,
+}
+
+fn decor_chances(decor_density: DecorDensity) -> Option<DecorChances> {
+    match decor_density {
+        DecorDensity::Off => None,
+        DecorDensity::Low => Some(DecorChances {
+            max_rubble: 2,
+            cobweb: 0.15,
+            crack: 0.1,
+        }),
+        DecorDensity::High => Some(DecorChances {
+            max_rubble: 5,
+            cobweb: 0.4,
+            crack: 0.25,
+        }),
+    }
+}
+
+// Decor is generated from the cell's own rng, so a chunk that is despawned
+// and spawned again ends up with exactly the same props in the same places.
+// Every mesh is a unit shape, sized by the scale of its transform.
+pub fn gen_cell_decor(
+    cell: &Cell,
+    ccm: &ChunkCellMarker,
+    decor_density: DecorDensity,
+) -> Vec<(Decor, Transform)> {
+    let Some(chances) = decor_chances(decor_density) else {
+        return vec![];
+    };
+
+    let mut rng = ccm.to_rng();
+    let mut decor = vec![];
+
+    // Rubble, kept out of cells that already have something in them
+    if cell.floor == CellWall::Solid && cell.special == CellSpecial::None {
+        for _ in 0..rng.gen_range(0..=chances.max_rubble) {
+            let size = Vec3::new(
+                rng.gen_range(0.05..0.2),
+                rng.gen_range(0.03..0.1),
+                rng.gen_range(0.05..0.2),
+            );
+            let max = INNER_HALF_SIZE - size.x.max(size.z);
+            let transform = Transform::from_xyz(
+                rng.gen_range(-max..max),
+                WALL_THICKNESS + size.y / 2.0,
+                rng.gen_range(-max..max),
+            )
+            .with_rotation(Quat::from_rotation_y(rng.gen_range(0.0..PI)))
+            .with_scale(size);
+
+            decor.push((Decor::Rubble, transform));
+        }
+    }
+
+    // Cobweb, stretched across an upper corner between two solid walls
+    if rng.gen_bool(chances.cobweb) {
+        let corners: Vec<(f32, f32)> = [
+            (&cell.wall_top, &cell.wall_left, 1.0, 1.0),
+            (&cell.wall_left, &cell.wall_bottom, -1.0, 1.0),
+            (&cell.wall_bottom, &cell.wall_right, -1.0, -1.0),
+            (&cell.wall_right, &cell.wall_top, 1.0, -1.0),
+        ]
+        .into_iter()
+        .filter(|(a, b, _, _)| **a == CellWall::Solid && **b == CellWall::Solid)
+        .map(|(_, _, x, z)| (x, z))
+        .collect();
+
+        if !corners.is_empty() {
+            let (x, z) = corners[rng.gen_range(0..corners.len())];
+            let height = COBWEB_WIDTH * rng.gen_range(0.5..1.0);
+
+            // A quad cutting across a square corner is twice as wide as its distance
+            // from the corner, which is split evenly between the x and z axes
+            let offset = COBWEB_WIDTH / 2.0 * FRAC_1_SQRT_2;
+            let transform = Transform::from_xyz(
+                x * (INNER_HALF_SIZE - offset),
+                DEFAULT_CELL_SIZE - WALL_THICKNESS - height / 2.0,
+                z * (INNER_HALF_SIZE - offset),
+            )
+            .with_rotation(Quat::from_rotation_y(f32::atan2(-x, -z)))
+            .with_scale(Vec3::new(COBWEB_WIDTH, height, 1.0));
+
+            decor.push((Decor::Cobweb, transform));
+        }
+    }
+
+    // Wall cracks, facing into the cell
+    for (side, wall) in [
+        (Side::Top, &cell.wall_top),
+        (Side::Bottom, &cell.wall_bottom),
+        (Side::Left, &cell.wall_left),
+        (Side::Right, &cell.wall_right),
+    ] {
+        if *wall != CellWall::Solid || !rng.gen_bool(chances.crack) {
+            continue;
+        }
+
+        let size = Vec3::new(rng.gen_range(0.4..1.2), rng.gen_range(0.3..1.0), 1.0);
+        let along = rng.gen_range(-(INNER_HALF_SIZE - size.x)..(INNER_HALF_SIZE - size.x));
+        let y = rng.gen_range(size.y..(DEFAULT_CELL_SIZE - size.y));
+        let tilt = rng.gen_range(-PI / 4.0..PI / 4.0);
+
+        let out = INNER_HALF_SIZE - DECAL_OFFSET;
+        let (x, z, r) = match side {
+            Side::Top => (out, along, -PI / 2.0),
+            Side::Bottom => (-out, along, PI / 2.0),
+            Side::Left => (along, out, PI),
+            Side::Right => (along, -out, 0.0),
+            Side::Up | Side::Down => unreachable!(),
+        };
+        let transform = Transform::from_xyz(x, y, z)
+            .with_rotation(Quat::from_rotation_y(r) * Quat::from_rotation_z(tilt))
+            .with_scale(size);
+
+        decor.push((Decor::Crack, transform));
+    }
+
+    decor
+}
+
+pub fn spawn_decor_bundles(
+    cell: &Cell,
+    ccm: &ChunkCellMarker,
+    decor_density: DecorDensity,
+    entity_spawner: &mut impl EntitySpawner,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+) {
+    let decor = gen_cell_decor(cell, ccm, decor_density);
+    if decor.is_empty() {
+        return;
+    }
+
+    let cuboid_mesh = meshes.add(Cuboid::from_length(1.0));
+    let quad_mesh = meshes.add(Rectangle::new(1.0, 1.0));
+
+    let rubble_material = materials.add(Color::srgb(0.35, 0.32, 0.3));
+    let cobweb_material = materials.add(StandardMaterial {
+        base_color: Color::srgba(0.9, 0.9, 0.9, 0.35),
+        alpha_mode: AlphaMode::Blend,
+        cull_mode: None,
+        double_sided: true,
+        ..default()
+    });
+    let crack_material = materials.add(StandardMaterial {
+        base_color: Color::srgba(0.05, 0.05, 0.05, 0.8),
+        alpha_mode: AlphaMode::Blend,
+        ..default()
+    });
+
+    for (d, transform) in decor {
+        let (mesh, material) = match d {
+            Decor::Rubble => (&cuboid_mesh, &rubble_material),
+            Decor::Cobweb => (&quad_mesh, &cobweb_material),
+            Decor::Crack => (&quad_mesh, &crack_material),
+        };
+
+        entity_spawner.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform,
+                ..default()
+            },
+            d,
+            Name::new(format!("Decor_{:?}", d)),
+        ));
+    }
+}
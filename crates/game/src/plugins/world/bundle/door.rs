@@ -1,14 +1,22 @@
-use crate::plugins::world::{bundle::WALL_THICKNESS, CELL_SIZE};
+use crate::plugins::world::bundle::WALL_THICKNESS;
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::Collider;
 use dungeon_maze_common::{
-    interaction::Interactable,
-    utils::entity::incr_betw_transforms,
-    world::{CyclicTransform, EntitySpawner, Side},
+    interaction::{Interactable, InteractableKind},
+    world::{AutoClose, CyclicTransform, EntitySpawner, Side, DEFAULT_CELL_SIZE},
 };
 use std::f32::consts::PI;
 
-const DOOR_CLOSE_FRAMES: usize = 24;
+const DOOR_CLOSE_SECS: f32 = 0.4;
+// About 10 seconds at 60 fps
+pub const DOOR_AUTO_CLOSE_FRAMES: u32 = 600;
+// The first animation swings the door open, so the cycle value after it is open
+const DOOR_OPEN_STATE: u32 = 0;
+// Half extents of the region around a doorway that keeps the door from closing on the player,
+// reaching across the wall to both sides of it
+const DOOR_SENSOR_DEPTH: f32 = 1.0;
+const DOOR_SENSOR_WIDTH: f32 = 1.0;
+const DOOR_SENSOR_HEIGHT: f32 = 1.5;
 const DOOR_SCALE: Vec3 = Vec3 {
     x: 0.8,
     y: 0.88,
@@ -36,20 +44,37 @@ pub fn spawn_door_bundle(
         .with_scale(DOOR_SCALE)
         .with_rotation(Quat::from_rotation_y(er));
 
-    let transforms = incr_betw_transforms(start, end, DOOR_CLOSE_FRAMES);
-    let mut clone = transforms.clone();
-    clone.reverse();
+    // Centered on the closed door, which sits in the doorway
+    let sensor_half_extents = match side {
+        Side::Top | Side::Bottom => {
+            Vec3::new(DOOR_SENSOR_DEPTH, DOOR_SENSOR_HEIGHT, DOOR_SENSOR_WIDTH)
+        }
+        _ => Vec3::new(DOOR_SENSOR_WIDTH, DOOR_SENSOR_HEIGHT, DOOR_SENSOR_DEPTH),
+    };
 
     entity_spawner.spawn((
         SceneBundle {
             scene: asset_server
                 .load(GltfAssetLabel::Scene(0).from_asset("embedded://models/door.glb")),
-            transform: transforms[0],
+            transform: start,
             ..default()
         },
-        Collider::cuboid(CELL_SIZE / 8.0, CELL_SIZE / 4.0, WALL_THICKNESS / 2.0),
-        Interactable { range: 2.0 },
-        CyclicTransform::new_cycled(vec![transforms, clone]),
+        Collider::cuboid(
+            DEFAULT_CELL_SIZE / 8.0,
+            DEFAULT_CELL_SIZE / 4.0,
+            WALL_THICKNESS / 2.0,
+        ),
+        Interactable::new(InteractableKind::Door),
+        CyclicTransform::new_cycled_from_keyframes(vec![
+            vec![(start, 0.0), (end, DOOR_CLOSE_SECS)],
+            vec![(end, 0.0), (start, DOOR_CLOSE_SECS)],
+        ]),
+        AutoClose::new(
+            DOOR_AUTO_CLOSE_FRAMES,
+            DOOR_OPEN_STATE,
+            start.translation - sensor_half_extents,
+            start.translation + sensor_half_extents,
+        ),
         Name::new(format!("{} Wall Door", side)),
     ));
 }
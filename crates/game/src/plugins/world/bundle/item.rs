@@ -1,35 +1,63 @@
-use bevy::prelude::*;
+use bevy::{ecs::system::EntityCommands, prelude::*, render::render_resource::Face};
 use bevy_rapier3d::prelude::*;
-use dungeon_maze_common::{inventory::item::Item, world::EntitySpawner};
+use dungeon_maze_common::{
+    inventory::item::{Item, ItemBillboard, ItemModel, ItemType},
+    world::EntitySpawner,
+};
 
-pub fn spawn_item_bundle(
+const ITEM_FALLBACK_MODEL_RADIUS: f32 = 0.1;
+const ITEM_BILLBOARD_SIZE: f32 = 0.25;
+// Weapons glow just enough to be spotted from across a dark cell
+const ITEM_LIGHT_INTENSITY: f32 = 20_000.0;
+const ITEM_LIGHT_RANGE: f32 = 2.0;
+
+pub fn spawn_item_bundle<'a>(
     item: Item,
-    entity_spawner: &mut impl EntitySpawner,
+    entity_spawner: &'a mut impl EntitySpawner,
+    asset_server: &Res<AssetServer>,
     meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
     transform: Option<Transform>,
     interactable: bool,
     collider: bool,
     rigid_body: bool,
-) {
-    let mesh = meshes.add(
-        Cuboid::from_size(Vec3 {
-            x: 0.2,
-            y: 0.2,
-            z: 0.2,
-        })
-        .mesh(),
-    );
-
+) -> EntityCommands<'a> {
     let mut entity_commands = entity_spawner.spawn((
         item,
-        PbrBundle {
-            mesh,
-            transform: transform.unwrap_or_default(),
-            ..default()
-        },
+        SpatialBundle::from_transform(transform.unwrap_or_default()),
         Name::new("Item"),
     ));
 
+    entity_commands.with_children(|parent| {
+        match item.model_path() {
+            Some(path) => {
+                parent.spawn((
+                    ItemModel,
+                    SceneBundle {
+                        scene: asset_server.load(path),
+                        ..default()
+                    },
+                    Name::new("Item Model"),
+                ));
+            }
+            None => spawn_item_fallback_model(item, parent, asset_server, meshes, materials),
+        }
+
+        if let ItemType::Weapon = item.name.item_type() {
+            parent.spawn((
+                PointLightBundle {
+                    point_light: PointLight {
+                        intensity: ITEM_LIGHT_INTENSITY,
+                        range: ITEM_LIGHT_RANGE,
+                        ..default()
+                    },
+                    ..default()
+                },
+                Name::new("Item Light"),
+            ));
+        }
+    });
+
     if interactable {
         entity_commands.insert(Item::interactable());
     }
@@ -39,4 +67,51 @@ pub fn spawn_item_bundle(
     if rigid_body {
         entity_commands.insert(RigidBody::Dynamic);
     }
+
+    entity_commands
+}
+
+// A small sphere colored after the item, with its ui image floating above it
+fn spawn_item_fallback_model(
+    item: Item,
+    parent: &mut ChildBuilder,
+    asset_server: &Res<AssetServer>,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+) {
+    parent.spawn((
+        ItemModel,
+        PbrBundle {
+            mesh: meshes.add(
+                Sphere::new(ITEM_FALLBACK_MODEL_RADIUS)
+                    .mesh()
+                    .ico(2)
+                    .expect("Error creating item fallback mesh"),
+            ),
+            material: materials.add(item.name.fallback_model_color()),
+            ..default()
+        },
+        Name::new("Item Fallback Model"),
+    ));
+
+    parent.spawn((
+        ItemBillboard,
+        PbrBundle {
+            mesh: meshes.add(Rectangle::new(ITEM_BILLBOARD_SIZE, ITEM_BILLBOARD_SIZE)),
+            material: materials.add(StandardMaterial {
+                base_color_texture: Some(asset_server.load(item.name.ui_image_path())),
+                alpha_mode: AlphaMode::Blend,
+                cull_mode: Some(Face::Back),
+                unlit: true,
+                ..default()
+            }),
+            transform: Transform::from_xyz(
+                0.0,
+                ITEM_FALLBACK_MODEL_RADIUS + ITEM_BILLBOARD_SIZE,
+                0.0,
+            ),
+            ..default()
+        },
+        Name::new("Item Billboard"),
+    ));
 }
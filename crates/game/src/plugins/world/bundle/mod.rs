@@ -1,9 +1,17 @@
 pub mod cell;
 pub mod chunk;
+pub mod decor;
 pub mod door;
 pub mod item;
+pub mod particle;
+pub mod railing;
 pub mod special;
 pub mod wall;
 pub mod window;
 
-pub const WALL_THICKNESS: f32 = 0.1;
+pub use dungeon_maze_common::world::WALL_THICKNESS;
+
+use dungeon_maze_common::world::DEFAULT_CELL_SIZE;
+
+// Distance from the center of a cell to the inside face of its walls
+pub(super) const INNER_HALF_SIZE: f32 = DEFAULT_CELL_SIZE / 2.0 - WALL_THICKNESS;
@@ -0,0 +1,141 @@
+use crate::plugins::world::bundle::{INNER_HALF_SIZE, WALL_THICKNESS};
+use bevy::prelude::*;
+use dungeon_maze_common::{
+    settings::DecorDensity,
+    world::{
+        particle::{DripRingHandles, EmitterParticle, ParticleEmitter, ParticleKind},
+        Cell, CellWall, ChunkCellMarker, EntitySpawner, DEFAULT_CELL_SIZE,
+    },
+};
+use rand::Rng;
+use std::f32::consts::PI;
+
+const DUST_SIZE: f32 = 0.02;
+const DRIP_SIZE: f32 = 0.04;
+const DRIP_RING_RADIUS: f32 = 0.05;
+// Keeps drip rings from z-fighting with the floor below them
+const RING_OFFSET: f32 = 0.01;
+
+fn emitter_chance(decor_density: DecorDensity) -> Option<f64> {
+    match decor_density {
+        DecorDensity::Off => None,
+        DecorDensity::Low => Some(0.25),
+        DecorDensity::High => Some(0.6),
+    }
+}
+
+// Decided by the cell's own rng, the same way decor is, so an emitter comes back in
+// the same place whenever its chunk is spawned again. Only cells open to whatever is
+// above them get one, and drips only fall in cells with a floor for them to land on.
+pub fn gen_cell_particle_emitter(
+    cell: &Cell,
+    ccm: &ChunkCellMarker,
+    decor_density: DecorDensity,
+) -> Option<ParticleEmitter> {
+    let chance = emitter_chance(decor_density)?;
+    if cell.ceiling != CellWall::None {
+        return None;
+    }
+
+    let mut rng = ccm.to_rng();
+    if !rng.gen_bool(chance) {
+        return None;
+    }
+
+    let kind = if cell.floor == CellWall::Solid && rng.gen_bool(0.5) {
+        ParticleKind::Drip
+    } else {
+        ParticleKind::Dust
+    };
+    let spread = INNER_HALF_SIZE / 2.0;
+    let origin = match kind {
+        ParticleKind::Dust => Vec3::new(0.0, DEFAULT_CELL_SIZE, 0.0),
+        ParticleKind::Drip => Vec3::new(
+            rng.gen_range(-spread..spread),
+            DEFAULT_CELL_SIZE,
+            rng.gen_range(-spread..spread),
+        ),
+    };
+
+    Some(ParticleEmitter::new(
+        kind,
+        ccm.chunk_xyz(),
+        origin,
+        spread,
+        WALL_THICKNESS,
+        rng,
+    ))
+}
+
+pub fn spawn_particle_emitter_bundle(
+    cell: &Cell,
+    ccm: &ChunkCellMarker,
+    decor_density: DecorDensity,
+    entity_spawner: &mut impl EntitySpawner,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+) {
+    let Some(emitter) = gen_cell_particle_emitter(cell, ccm, decor_density) else {
+        return;
+    };
+    let kind = emitter.kind;
+
+    let (mesh, material) = match kind {
+        ParticleKind::Dust => (
+            meshes.add(Cuboid::from_length(DUST_SIZE)),
+            materials.add(StandardMaterial {
+                base_color: Color::srgba(0.8, 0.75, 0.65, 0.5),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            }),
+        ),
+        ParticleKind::Drip => (
+            meshes.add(Cuboid::new(DRIP_SIZE / 3.0, DRIP_SIZE, DRIP_SIZE / 3.0)),
+            materials.add(StandardMaterial {
+                base_color: Color::srgba(0.6, 0.75, 0.9, 0.7),
+                alpha_mode: AlphaMode::Blend,
+                ..default()
+            }),
+        ),
+    };
+
+    let mut emitter_entity = entity_spawner.spawn((
+        SpatialBundle::default(),
+        Name::new(format!("Particle Emitter ({:?})", kind)),
+    ));
+
+    if kind == ParticleKind::Drip {
+        emitter_entity.insert(DripRingHandles {
+            mesh: meshes.add(
+                Annulus::new(DRIP_RING_RADIUS * 0.8, DRIP_RING_RADIUS)
+                    .mesh()
+                    .build()
+                    .rotated_by(Quat::from_rotation_x(-PI / 2.0))
+                    .translated_by(Vec3::Y * RING_OFFSET),
+            ),
+            material: materials.add(StandardMaterial {
+                base_color: Color::srgba(0.6, 0.75, 0.9, 0.5),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            }),
+        });
+    }
+
+    // Every particle in the pool gets an entity up front, hidden until it is let out
+    emitter_entity.with_children(|parent| {
+        for i in 0..kind.pool_size() {
+            parent.spawn((
+                PbrBundle {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                EmitterParticle(i),
+            ));
+        }
+    });
+    emitter_entity.insert(emitter);
+}
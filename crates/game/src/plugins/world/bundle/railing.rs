@@ -0,0 +1,44 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Collider;
+use dungeon_maze_common::world::{EntitySpawner, Railing, Side, DEFAULT_CELL_SIZE};
+use std::f32::consts::PI;
+
+// About waist high on the player, so it stops them without blocking the view
+const RAILING_HEIGHT: f32 = 1.0;
+const RAILING_THICKNESS: f32 = 0.1;
+
+pub fn new_railing_mesh() -> Mesh {
+    Cuboid::new(DEFAULT_CELL_SIZE, RAILING_HEIGHT, RAILING_THICKNESS).into()
+}
+
+pub fn spawn_railing_bundle(
+    side: Side,
+    entity_spawner: &mut impl EntitySpawner,
+    mesh: &Handle<Mesh>,
+    material: &Handle<StandardMaterial>,
+) {
+    let edge = DEFAULT_CELL_SIZE / 2.0 - RAILING_THICKNESS / 2.0;
+    let (x, z, r) = match side {
+        Side::Top => (edge, 0.0, Quat::from_rotation_y(PI / 2.0)),
+        Side::Bottom => (-edge, 0.0, Quat::from_rotation_y(PI / 2.0)),
+        Side::Left => (0.0, edge, Quat::IDENTITY),
+        Side::Right => (0.0, -edge, Quat::IDENTITY),
+        Side::Up | Side::Down => return,
+    };
+
+    entity_spawner.spawn((
+        PbrBundle {
+            mesh: mesh.clone(),
+            material: material.clone(),
+            transform: Transform::from_xyz(x, RAILING_HEIGHT / 2.0, z).with_rotation(r),
+            ..default()
+        },
+        Collider::cuboid(
+            DEFAULT_CELL_SIZE / 2.0,
+            RAILING_HEIGHT / 2.0,
+            RAILING_THICKNESS / 2.0,
+        ),
+        Railing(side),
+        Name::new(format!("{} Railing", side)),
+    ));
+}
@@ -1,12 +1,18 @@
-use crate::plugins::world::bundle::item::spawn_item_bundle;
+use crate::plugins::world::bundle::{item::spawn_item_bundle, WALL_THICKNESS};
 use bevy::prelude::*;
-use bevy_rapier3d::prelude::{Collider, ComputedColliderShape, RigidBody};
+use bevy_rapier3d::prelude::{Collider, ComputedColliderShape, RigidBody, Sensor};
 use dungeon_maze_common::{
-    animation::CyclicAnimation,
-    interaction::Interactable,
-    inventory::item::Item,
+    animation::{CyclicAnimation, PLAYER_ANIMATION_CLIPS},
+    interaction::{Interactable, InteractableKind},
+    inventory::item::{Item, ItemName},
     meshes::{new_staircase_mesh, new_stairs_mesh},
-    world::{data::WorldData, ChunkCellMarker, EntitySpawner, OCItemContainer},
+    utils::rng::rng_from_str,
+    world::{
+        data::{CellData, WorldData},
+        hazard::{Hazard, HazardOccupants},
+        world_structure::StructureTheme,
+        ChunkCellMarker, EntitySpawner, Locked, OCItemContainer, DEFAULT_CELL_SIZE,
+    },
 };
 use rand::Rng;
 
@@ -17,9 +23,23 @@ const CHAIR_COLLIDER_HZ: f32 = 0.2;
 const TREASURE_CHEST_COLLIDER_HX: f32 = 0.5;
 const TREASURE_CHEST_COLLIDER_HY: f32 = 0.3;
 const TREASURE_CHEST_COLLIDER_HZ: f32 = 0.3;
-const TREASURE_CHEST_MIN_ANIMATION: u32 = 8; // TODO: refactor
-const TREASURE_CHEST_MAX_ANIMATION: u32 = 9; // TODO: refactor
-const TREASURE_CHEST_INTERACTABLE_RANGE: f32 = 2.0;
+// Treasure chest clips come right after the player's in the animation graph,
+// the open clip followed by the close clip
+pub const TREASURE_CHEST_MIN_ANIMATION: u32 = PLAYER_ANIMATION_CLIPS.len() as u32;
+pub const TREASURE_CHEST_MAX_ANIMATION: u32 = TREASURE_CHEST_MIN_ANIMATION + 1;
+pub const TREASURE_CHEST_OPEN_ANIMATION: u32 = TREASURE_CHEST_MIN_ANIMATION;
+pub const LOCKED_TREASURE_CHEST_PROB: f64 = 0.25;
+// Chance of a chest holding a key instead of whatever it would have held otherwise
+pub const TREASURE_CHEST_KEY_PROB: f64 = 0.08;
+
+// Hazards cover the floor of the cell, up to the inside face of its walls
+const HAZARD_HALF_SIZE: f32 = DEFAULT_CELL_SIZE / 2.0 - WALL_THICKNESS;
+// Tall enough for the sensor to reach into the collider of anything standing on it
+const HAZARD_SENSOR_HY: f32 = 0.25;
+const HAZARD_PLATE_HEIGHT: f32 = 0.02;
+const SPIKES_PER_ROW: u32 = 5;
+const SPIKE_RADIUS: f32 = 0.08;
+const SPIKE_HEIGHT: f32 = 0.3;
 
 pub fn spawn_chair_bundle(
     entity_spawner: &mut impl EntitySpawner,
@@ -52,62 +72,82 @@ pub fn spawn_treasure_chest_bundle(
     entity_spawner: &mut impl EntitySpawner,
     asset_server: &Res<AssetServer>,
     meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
     world_data: &Res<WorldData>,
     ccm: &ChunkCellMarker,
+    theme: Option<&StructureTheme>,
 ) {
-    entity_spawner
-        .spawn((
-            OCItemContainer,
-            CyclicAnimation::new(TREASURE_CHEST_MIN_ANIMATION, TREASURE_CHEST_MAX_ANIMATION),
-            Interactable {
-                range: TREASURE_CHEST_INTERACTABLE_RANGE,
-            },
-            SpatialBundle {
-                transform: Transform::from_xyz(0.0, TREASURE_CHEST_COLLIDER_HY, 0.0),
+    let cell_data = world_data.at_cell(ccm.chunk_xyz(), ccm.cell_xz());
+    let treasure_chest_data = cell_data.map(|cell_data| &cell_data.treasure_chest_data);
+    let opened = cell_data.is_some_and(CellData::chest_opened);
+    // Chests left open have been unlocked, including those from saves from before locks
+    let locked = treasure_chest_lock(ccm)
+        .filter(|_| !opened && !cell_data.is_some_and(CellData::chest_unlocked));
+
+    // Chests left open are spawned open, with the close clip up next
+    let cyclic_animation = if opened {
+        CyclicAnimation::new_at(
+            TREASURE_CHEST_MIN_ANIMATION,
+            TREASURE_CHEST_MAX_ANIMATION,
+            TREASURE_CHEST_OPEN_ANIMATION + 1,
+        )
+    } else {
+        CyclicAnimation::new(TREASURE_CHEST_MIN_ANIMATION, TREASURE_CHEST_MAX_ANIMATION)
+    };
+
+    let mut entity_commands = entity_spawner.spawn((
+        OCItemContainer,
+        cyclic_animation,
+        Interactable::new(InteractableKind::Container),
+        SpatialBundle {
+            transform: Transform::from_xyz(0.0, TREASURE_CHEST_COLLIDER_HY, 0.0),
+            ..default()
+        },
+        Collider::cuboid(
+            TREASURE_CHEST_COLLIDER_HX,
+            TREASURE_CHEST_COLLIDER_HY,
+            TREASURE_CHEST_COLLIDER_HZ,
+        ),
+        Name::new("Treasure Chest"),
+    ));
+    if let Some(locked) = locked {
+        entity_commands.insert(locked);
+    }
+
+    entity_commands.with_children(|parent| {
+        parent.spawn((
+            SceneBundle {
+                scene: asset_server.load(
+                    GltfAssetLabel::Scene(0).from_asset("embedded://models/treasure_chest.glb"),
+                ),
+                transform: Transform::from_xyz(0.0, -TREASURE_CHEST_COLLIDER_HY, 0.0),
                 ..default()
             },
-            Collider::cuboid(
-                TREASURE_CHEST_COLLIDER_HX,
-                TREASURE_CHEST_COLLIDER_HY,
-                TREASURE_CHEST_COLLIDER_HZ,
-            ),
-            Name::new("Treasure Chest"),
-        ))
-        .with_children(|parent| {
-            parent.spawn((
-                SceneBundle {
-                    scene: asset_server.load(
-                        GltfAssetLabel::Scene(0).from_asset("embedded://models/treasure_chest.glb"),
-                    ),
-                    transform: Transform::from_xyz(0.0, -TREASURE_CHEST_COLLIDER_HY, 0.0),
-                    ..default()
-                },
-                Name::new("Treasure Chest Model"),
-            ));
+            Name::new("Treasure Chest Model"),
+        ));
 
-            let item = if let Some(cell_data) = world_data.at_cell(ccm.chunk_xyz(), ccm.cell_xz()) {
-                match &cell_data.treasure_chest_data.item {
-                    Some(i) => i.clone(),
-                    None => return,
-                }
-            } else {
-                let mut rng = ccm.to_rng();
-                // TODO: items with a max stack size of 1
-                // should only be able to spawn with an amt of 1
-                let amt = rng.gen_range(1..=3);
-                Item::choose(&mut rng, amt)
-            };
-
-            spawn_item_bundle(
-                item,
-                parent,
-                meshes,
-                Some(Transform::from_xyz(0.0, 0.2, 0.0)),
-                false,
-                false,
-                false,
-            );
-        });
+        let remaining = treasure_chest_data.and_then(|tcd| tcd.remaining());
+
+        let item = match remaining {
+            Some(Some(i)) => i,
+            // Emptied out
+            Some(None) => return,
+            None => treasure_chest_loot(ccm, theme),
+        };
+
+        spawn_item_bundle(
+            item,
+            parent,
+            asset_server,
+            meshes,
+            materials,
+            Some(Transform::from_xyz(0.0, 0.2, 0.0)),
+            // Items can only be picked up out of open chests
+            opened,
+            false,
+            false,
+        );
+    });
 }
 
 pub fn spawn_staircase_bundle(
@@ -167,3 +207,108 @@ pub fn spawn_stairs_bundle(
         Name::new("Stairs"),
     ));
 }
+
+pub fn spawn_hazard_bundle(
+    hazard: Hazard,
+    entity_spawner: &mut impl EntitySpawner,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+) {
+    let plate_material = match hazard {
+        Hazard::SpikeTrap => materials.add(Color::srgb(0.2, 0.2, 0.22)),
+        Hazard::PoisonPool => materials.add(StandardMaterial {
+            base_color: Color::srgba(0.25, 0.7, 0.1, 0.8),
+            emissive: LinearRgba::rgb(0.05, 0.2, 0.0),
+            alpha_mode: AlphaMode::Blend,
+            perceptual_roughness: 0.1,
+            ..default()
+        }),
+    };
+    let plate_mesh = meshes.add(Cuboid::new(
+        HAZARD_HALF_SIZE * 2.0,
+        HAZARD_PLATE_HEIGHT,
+        HAZARD_HALF_SIZE * 2.0,
+    ));
+    let plate_y = HAZARD_PLATE_HEIGHT / 2.0 - HAZARD_SENSOR_HY;
+
+    entity_spawner
+        .spawn((
+            hazard,
+            HazardOccupants::default(),
+            SpatialBundle {
+                transform: Transform::from_xyz(0.0, WALL_THICKNESS + HAZARD_SENSOR_HY, 0.0),
+                ..default()
+            },
+            Collider::cuboid(HAZARD_HALF_SIZE, HAZARD_SENSOR_HY, HAZARD_HALF_SIZE),
+            Sensor,
+            Name::new(format!("Hazard_{:?}", hazard)),
+        ))
+        .with_children(|parent| {
+            parent.spawn(PbrBundle {
+                mesh: plate_mesh,
+                material: plate_material,
+                transform: Transform::from_xyz(0.0, plate_y, 0.0),
+                ..default()
+            });
+
+            if hazard != Hazard::SpikeTrap {
+                return;
+            }
+
+            let spike_mesh = meshes.add(Cone {
+                radius: SPIKE_RADIUS,
+                height: SPIKE_HEIGHT,
+            });
+            let spike_material = materials.add(Color::srgb(0.6, 0.6, 0.65));
+            let spacing = HAZARD_HALF_SIZE * 2.0 / SPIKES_PER_ROW as f32;
+
+            for i in 0..SPIKES_PER_ROW {
+                for j in 0..SPIKES_PER_ROW {
+                    parent.spawn(PbrBundle {
+                        mesh: spike_mesh.clone(),
+                        material: spike_material.clone(),
+                        transform: Transform::from_xyz(
+                            (i as f32 + 0.5) * spacing - HAZARD_HALF_SIZE,
+                            plate_y + SPIKE_HEIGHT / 2.0,
+                            (j as f32 + 0.5) * spacing - HAZARD_HALF_SIZE,
+                        ),
+                        ..default()
+                    });
+                }
+            }
+        });
+}
+
+// What a treasure chest holds before anything is taken out of it
+pub fn treasure_chest_loot(ccm: &ChunkCellMarker, theme: Option<&StructureTheme>) -> Item {
+    let mut rng = ccm.to_rng();
+    if let Some(item) = theme.and_then(|theme| theme.choose_loot(&mut rng)) {
+        return item;
+    }
+
+    // TODO: items with a max stack size of 1
+    // should only be able to spawn with an amt of 1
+    let amt = rng.gen_range(1..=3);
+    let item = Item::choose(&mut rng, amt);
+
+    // Rolled last, so that the rest of the loot stays the same either way
+    if rng.gen_bool(TREASURE_CHEST_KEY_PROB) {
+        return Item::new(ItemName::Key, 1);
+    }
+    item
+}
+
+// Whether a treasure chest is generated locked, from an rng of its own
+// so that it has no bearing on the loot inside
+pub fn treasure_chest_lock(ccm: &ChunkCellMarker) -> Option<Locked> {
+    let (chunk_x, chunk_y, chunk_z) = ccm.chunk_xyz();
+    let (x, z) = ccm.cell_xz();
+    let mut rng = rng_from_str(format!(
+        "lock_{},{},{}_{},{}",
+        chunk_x, chunk_y, chunk_z, x, z
+    ));
+
+    // There is only the one kind of key so far
+    rng.gen_bool(LOCKED_TREASURE_CHEST_PROB)
+        .then_some(Locked { tier: 1 })
+}
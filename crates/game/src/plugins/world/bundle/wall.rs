@@ -1,9 +1,9 @@
-use crate::plugins::world::{bundle::WALL_THICKNESS, CELL_SIZE};
+use crate::plugins::world::bundle::WALL_THICKNESS;
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::{Collider, ComputedColliderShape};
 use dungeon_maze_common::{
     meshes::{new_wall_with_door_gap_mesh, new_wall_with_window_gap_mesh},
-    world::{CellWall, EntitySpawner, Side},
+    world::{CellWall, CellWallSide, EntitySpawner, Side, DEFAULT_CELL_SIZE},
 };
 use std::f32::consts::PI;
 
@@ -41,32 +41,32 @@ pub fn spawn_solid_wall_bundle(
 ) {
     let (x, y, z, r) = match side {
         Side::Top => (
-            CELL_SIZE / 2.0 - WALL_THICKNESS / 2.0,
-            CELL_SIZE / 2.0,
+            DEFAULT_CELL_SIZE / 2.0 - WALL_THICKNESS / 2.0,
+            DEFAULT_CELL_SIZE / 2.0,
             0.0,
             Quat::from_rotation_z(PI / 2.0),
         ),
         Side::Bottom => (
-            -CELL_SIZE / 2.0 + WALL_THICKNESS / 2.0,
-            CELL_SIZE / 2.0,
+            -DEFAULT_CELL_SIZE / 2.0 + WALL_THICKNESS / 2.0,
+            DEFAULT_CELL_SIZE / 2.0,
             0.0,
             Quat::from_rotation_z(PI * 3.0 / 2.0),
         ),
         Side::Left => (
             0.0,
-            CELL_SIZE / 2.0,
-            CELL_SIZE / 2.0 - WALL_THICKNESS / 2.0,
+            DEFAULT_CELL_SIZE / 2.0,
+            DEFAULT_CELL_SIZE / 2.0 - WALL_THICKNESS / 2.0,
             Quat::from_rotation_x(PI * 3.0 / 2.0),
         ),
         Side::Right => (
             0.0,
-            CELL_SIZE / 2.0,
-            -CELL_SIZE / 2.0 + WALL_THICKNESS / 2.0,
+            DEFAULT_CELL_SIZE / 2.0,
+            -DEFAULT_CELL_SIZE / 2.0 + WALL_THICKNESS / 2.0,
             Quat::from_rotation_x(PI / 2.0),
         ),
         Side::Up => (
             0.0,
-            CELL_SIZE - WALL_THICKNESS / 2.0,
+            DEFAULT_CELL_SIZE - WALL_THICKNESS / 2.0,
             0.0,
             Quat::from_rotation_x(PI),
         ),
@@ -80,7 +80,12 @@ pub fn spawn_solid_wall_bundle(
             transform: Transform::from_xyz(x, y, z).with_rotation(r),
             ..default()
         },
-        Collider::cuboid(CELL_SIZE / 2.0, WALL_THICKNESS / 2.0, CELL_SIZE / 2.0),
+        Collider::cuboid(
+            DEFAULT_CELL_SIZE / 2.0,
+            WALL_THICKNESS / 2.0,
+            DEFAULT_CELL_SIZE / 2.0,
+        ),
+        CellWallSide(side),
         Name::new(format!("{} Wall", side)),
     ));
 }
@@ -105,6 +110,7 @@ pub fn spawn_wall_with_door_gap_bundle(
             ..default()
         },
         Collider::from_bevy_mesh(mesh, &ComputedColliderShape::TriMesh).unwrap(),
+        CellWallSide(side),
         Name::new(format!("{} Wall With Door Gap", side)),
     ));
 }
@@ -129,6 +135,7 @@ pub fn spawn_wall_with_window_gap_bundle(
             ..default()
         },
         Collider::from_bevy_mesh(mesh, &ComputedColliderShape::TriMesh).unwrap(),
+        CellWallSide(side),
         Name::new(format!("{} Wall With Window Gap", side)),
     ));
 }
@@ -136,27 +143,27 @@ pub fn spawn_wall_with_window_gap_bundle(
 fn wall_dims(side: &Side) -> (f32, f32, f32, Quat) {
     match side {
         Side::Top => (
-            CELL_SIZE / 2.0 - WALL_THICKNESS,
-            CELL_SIZE / 2.0,
+            DEFAULT_CELL_SIZE / 2.0 - WALL_THICKNESS,
+            DEFAULT_CELL_SIZE / 2.0,
             0.0,
             Quat::from_rotation_y(PI / 2.0),
         ),
         Side::Bottom => (
-            -CELL_SIZE / 2.0,
-            CELL_SIZE / 2.0,
+            -DEFAULT_CELL_SIZE / 2.0,
+            DEFAULT_CELL_SIZE / 2.0,
             0.0,
             Quat::from_rotation_y(PI / 2.0),
         ),
         Side::Left => (
             0.0,
-            CELL_SIZE / 2.0,
-            CELL_SIZE / 2.0,
+            DEFAULT_CELL_SIZE / 2.0,
+            DEFAULT_CELL_SIZE / 2.0,
             Quat::from_rotation_x(0.0),
         ),
         Side::Right => (
             0.0,
-            CELL_SIZE / 2.0,
-            -CELL_SIZE / 2.0,
+            DEFAULT_CELL_SIZE / 2.0,
+            -DEFAULT_CELL_SIZE / 2.0,
             Quat::from_rotation_x(0.0),
         ),
         _ => panic!("unexpected side: {}", side),
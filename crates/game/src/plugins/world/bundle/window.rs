@@ -1,24 +1,64 @@
-use crate::plugins::world::{bundle::WALL_THICKNESS, CELL_SIZE};
+use crate::plugins::world::{bundle::WALL_THICKNESS, physics::window_collision_groups};
 use bevy::prelude::*;
-use bevy_rapier3d::prelude::Collider;
+use bevy_rapier3d::prelude::{Collider, Sensor};
 use dungeon_maze_common::{
-    interaction::Interactable,
-    utils::entity::incr_betw_transforms,
-    world::{CyclicTransform, EntitySpawner, Side},
+    interaction::{Interactable, InteractableKind},
+    player::{DmgImmune, DmgTarget, Health},
+    utils::rng::rng_from_str,
+    world::{
+        data::WorldData, ChunkCellMarker, CyclicTransform, EntitySpawner, Side, WindowPane,
+        DEFAULT_CELL_SIZE,
+    },
 };
+use rand::Rng;
 use std::f32::consts::PI;
 
-const WINDOW_CLOSE_FRAMES: usize = 24;
+const WINDOW_CLOSE_SECS: f32 = 0.4;
 const WINDOW_SCALE: Vec3 = Vec3 {
     x: 0.8,
     y: 0.88,
     z: 1.0,
 };
+const WINDOW_COLLIDER_HX: f32 = DEFAULT_CELL_SIZE / 8.0;
+const WINDOW_COLLIDER_HY: f32 = DEFAULT_CELL_SIZE / 8.0;
+const WINDOW_COLLIDER_HZ: f32 = WALL_THICKNESS / 2.0;
+const GLASS_WINDOW_PROB: f64 = 0.5;
+// Blunt and Slash damage that the glass of a window takes before it breaks
+pub const WINDOW_PANE_HEALTH: f32 = 20.0;
+const WINDOW_PANE_COLOR: Color = Color::srgba(0.7, 0.85, 0.9, 0.3);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WindowStyle {
+    Bars,
+    Glass,
+}
+
+impl WindowStyle {
+    // Each window in the world is always the same style
+    pub fn at(ccm: &ChunkCellMarker, side: Side) -> Self {
+        let (chunk_x, chunk_y, chunk_z) = ccm.chunk_xyz();
+        let (x, z) = ccm.cell_xz();
+        let mut rng = rng_from_str(format!(
+            "window_{},{},{}_{},{}_{}",
+            chunk_x, chunk_y, chunk_z, x, z, side
+        ));
+
+        if rng.gen_bool(GLASS_WINDOW_PROB) {
+            Self::Glass
+        } else {
+            Self::Bars
+        }
+    }
+}
 
 pub fn spawn_window_bundle(
     side: Side,
+    ccm: &ChunkCellMarker,
     entity_spawner: &mut impl EntitySpawner,
     asset_server: &Res<AssetServer>,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    world_data: &Res<WorldData>,
 ) {
     // TODO: refine window open/close start & end positions for animation:
     let (sx, sy, sz, sr, ex, ey, ez, er) = match side {
@@ -36,20 +76,62 @@ pub fn spawn_window_bundle(
         .with_scale(WINDOW_SCALE)
         .with_rotation(Quat::from_rotation_y(er));
 
-    let transforms = incr_betw_transforms(start, end, WINDOW_CLOSE_FRAMES);
-    let mut clone = transforms.clone();
-    clone.reverse();
+    let style = WindowStyle::at(ccm, side);
+    let broken = style == WindowStyle::Glass
+        && world_data
+            .at_cell(ccm.chunk_xyz(), ccm.cell_xz())
+            .is_some_and(|cell_data| cell_data.window_broken(side));
 
-    entity_spawner.spawn((
+    let mut entity_commands = entity_spawner.spawn((
         SceneBundle {
             scene: asset_server
                 .load(GltfAssetLabel::Scene(0).from_asset("embedded://models/window.glb")),
-            transform: transforms[0],
+            transform: start,
             ..default()
         },
-        Collider::cuboid(CELL_SIZE / 8.0, CELL_SIZE / 8.0, WALL_THICKNESS / 2.0),
-        Interactable { range: 2.0 },
-        CyclicTransform::new_cycled(vec![transforms, clone]),
+        Interactable::new(InteractableKind::Window),
+        CyclicTransform::new_cycled_from_keyframes(vec![
+            vec![(start, 0.0), (end, WINDOW_CLOSE_SECS)],
+            vec![(end, 0.0), (start, WINDOW_CLOSE_SECS)],
+        ]),
         Name::new(format!("{} Wall Window", side)),
     ));
+
+    // Nothing is left to stop the player once the glass is broken
+    if broken {
+        return;
+    }
+    entity_commands.insert((
+        Collider::cuboid(WINDOW_COLLIDER_HX, WINDOW_COLLIDER_HY, WINDOW_COLLIDER_HZ),
+        window_collision_groups(),
+    ));
+
+    if style == WindowStyle::Glass {
+        entity_commands.with_children(|parent| {
+            parent.spawn((
+                WindowPane(side),
+                Health::new(WINDOW_PANE_HEALTH, WINDOW_PANE_HEALTH, 0.0),
+                DmgTarget,
+                // Kept out of handle_take_damage, since break_window_panes
+                // only lets Blunt and Slash damage wear down the glass
+                DmgImmune::new(None),
+                Sensor,
+                Collider::cuboid(WINDOW_COLLIDER_HX, WINDOW_COLLIDER_HY, WINDOW_COLLIDER_HZ),
+                PbrBundle {
+                    mesh: meshes.add(Cuboid::new(
+                        WINDOW_COLLIDER_HX * 2.0,
+                        WINDOW_COLLIDER_HY * 2.0,
+                        WINDOW_COLLIDER_HZ,
+                    )),
+                    material: materials.add(StandardMaterial {
+                        base_color: WINDOW_PANE_COLOR,
+                        alpha_mode: AlphaMode::Blend,
+                        ..default()
+                    }),
+                    ..default()
+                },
+                Name::new("Window Pane"),
+            ));
+        });
+    }
 }
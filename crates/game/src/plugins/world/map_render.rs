@@ -0,0 +1,441 @@
+use crate::plugins::world::chunk_from_xyz_seed;
+use dungeon_maze_common::{
+    settings::Difficulty,
+    world::{
+        world_structure::{WorldStructureRegistry, NO_WORLD_STRUCTURE},
+        Cell, CellSpecial, CellWall, Chunk, WorldConfig, WorldDims,
+    },
+};
+use image::{Rgb, RgbImage};
+use std::collections::HashMap;
+
+// Size in pixels of a single cell in png maps
+const PNG_CELL_SIZE: u32 = 12;
+// Gap in pixels between y levels in png maps
+const PNG_LEVEL_GAP: u32 = PNG_CELL_SIZE * 2;
+
+const FLOOR_COLOR: [u8; 3] = [245, 245, 245];
+// Cells without a floor open up to the level below
+const NO_FLOOR_COLOR: [u8; 3] = [120, 120, 120];
+const WORLD_STRUCTURE_FLOOR_COLOR: [u8; 3] = [255, 236, 200];
+const WALL_COLOR: [u8; 3] = [0, 0, 0];
+const WINDOW_COLOR: [u8; 3] = [80, 170, 255];
+const BACKGROUND_COLOR: [u8; 3] = [255, 255, 255];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MapMarker {
+    Chair,
+    TreasureChest,
+    Staircase,
+    Stairs,
+    SpikeTrap,
+    PoisonPool,
+    WorldStructure,
+}
+
+pub const MAP_MARKERS: [MapMarker; 7] = [
+    MapMarker::Chair,
+    MapMarker::TreasureChest,
+    MapMarker::Staircase,
+    MapMarker::Stairs,
+    MapMarker::SpikeTrap,
+    MapMarker::PoisonPool,
+    MapMarker::WorldStructure,
+];
+
+impl MapMarker {
+    pub fn from_special(special: &CellSpecial) -> Option<Self> {
+        match special {
+            CellSpecial::None => None,
+            CellSpecial::Chair => Some(Self::Chair),
+            CellSpecial::TreasureChest => Some(Self::TreasureChest),
+            CellSpecial::Staircase => Some(Self::Staircase),
+            CellSpecial::Stairs => Some(Self::Stairs),
+            CellSpecial::SpikeTrap => Some(Self::SpikeTrap),
+            CellSpecial::PoisonPool => Some(Self::PoisonPool),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Chair => "Chair",
+            Self::TreasureChest => "Treasure Chest",
+            Self::Staircase => "Staircase",
+            Self::Stairs => "Stairs",
+            Self::SpikeTrap => "Spike Trap",
+            Self::PoisonPool => "Poison Pool",
+            Self::WorldStructure => "World Structure",
+        }
+    }
+
+    pub fn class_name(&self) -> &'static str {
+        match self {
+            Self::Chair => "chair",
+            Self::TreasureChest => "treasure-chest",
+            Self::Staircase => "staircase",
+            Self::Stairs => "stairs",
+            Self::SpikeTrap => "spike-trap",
+            Self::PoisonPool => "poison-pool",
+            Self::WorldStructure => "world-structure",
+        }
+    }
+
+    pub fn color(&self) -> [u8; 3] {
+        match self {
+            Self::Chair => [150, 90, 40],
+            Self::TreasureChest => [230, 180, 0],
+            Self::Staircase => [200, 40, 40],
+            Self::Stairs => [150, 40, 200],
+            Self::SpikeTrap => [90, 90, 110],
+            Self::PoisonPool => [60, 170, 40],
+            Self::WorldStructure => WORLD_STRUCTURE_FLOOR_COLOR,
+        }
+    }
+}
+
+// A square area of chunks around a center chunk, over one or more y levels
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapArea {
+    pub seed: u32,
+    pub difficulty: Difficulty,
+    pub world_config: WorldConfig,
+    pub dims: WorldDims,
+    pub center: (i64, i64, i64),
+    pub radius: u32,
+    pub y_levels: Vec<i64>,
+}
+
+// Chunks of a single y level. Maps are drawn looking down, with +x to the left and +z
+// to the top, which is the same order that cells are stored in within a chunk.
+pub struct MapLevel {
+    pub y: i64,
+    pub grid_size: usize,
+    pub rows: Vec<Vec<Chunk>>,
+}
+
+impl MapLevel {
+    fn width_in_cells(&self) -> usize {
+        self.rows
+            .first()
+            .map_or(0, |row| row.len() * self.grid_size)
+    }
+
+    fn height_in_cells(&self) -> usize {
+        self.rows.len() * self.grid_size
+    }
+
+    // Rows of cells spanning the whole level, from top to bottom
+    fn cell_rows(&self) -> impl Iterator<Item = Vec<(&Chunk, &Cell)>> {
+        self.rows.iter().flat_map(|row| {
+            (0..self.grid_size).map(move |z| {
+                row.iter()
+                    .flat_map(|chunk| chunk.cells[z].iter().map(move |cell| (chunk, cell)))
+                    .collect()
+            })
+        })
+    }
+}
+
+pub fn gen_map_levels(area: &MapArea, world_structures: &WorldStructureRegistry) -> Vec<MapLevel> {
+    let (center_x, _, center_z) = area.center;
+    let r = area.radius as i64;
+
+    area.y_levels
+        .iter()
+        .map(|y| MapLevel {
+            y: *y,
+            grid_size: area.dims.grid_size(),
+            rows: (center_z - r..=center_z + r)
+                .rev()
+                .map(|z| {
+                    (center_x - r..=center_x + r)
+                        .rev()
+                        .map(|x| {
+                            chunk_from_xyz_seed(
+                                area.seed,
+                                area.difficulty,
+                                &area.world_config,
+                                &area.dims,
+                                world_structures,
+                                x,
+                                *y,
+                                z,
+                            )
+                        })
+                        .collect()
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+fn has_world_structure(chunk: &Chunk) -> bool {
+    chunk.world_structure != NO_WORLD_STRUCTURE
+}
+
+fn css_color([r, g, b]: [u8; 3]) -> String {
+    format!("rgb({},{},{})", r, g, b)
+}
+
+fn cell_floor_color(chunk: &Chunk, cell: &Cell) -> [u8; 3] {
+    if cell.floor == CellWall::None {
+        NO_FLOOR_COLOR
+    } else if has_world_structure(chunk) {
+        WORLD_STRUCTURE_FLOOR_COLOR
+    } else {
+        FLOOR_COLOR
+    }
+}
+
+// Class names of a single cell's div, which the style sheet in chunks_to_html colors in
+fn cell_classes(chunk: &Chunk, cell: &Cell) -> Vec<String> {
+    let mut classes = vec!["cell".to_owned()];
+
+    if cell.floor == CellWall::None {
+        classes.push("no-floor".to_owned());
+    } else if has_world_structure(chunk) {
+        classes.push("world-structure".to_owned());
+    }
+
+    if let Some(marker) = MapMarker::from_special(&cell.special) {
+        classes.push(format!("special-{}", marker.class_name()));
+    }
+
+    for (wall, side) in [
+        (&cell.wall_top, "top"),
+        (&cell.wall_bottom, "bottom"),
+        (&cell.wall_left, "left"),
+        (&cell.wall_right, "right"),
+    ] {
+        match wall {
+            CellWall::None => {}
+            CellWall::Solid => classes.push(format!("wall-{}", side)),
+            CellWall::SolidWithDoorGap => classes.push(format!("door-{}", side)),
+            CellWall::SolidWithWindowGap => classes.push(format!("window-{}", side)),
+        }
+    }
+
+    classes
+}
+
+fn html_style_sheet() -> String {
+    let mut css = format!(
+        "body {{ font-family: sans-serif; }}\n\
+         .level {{ display: grid; margin-bottom: 24px; }}\n\
+         .cell {{ width: 16px; height: 16px; box-sizing: border-box; border: 2px solid transparent; background: {}; }}\n\
+         .missing {{ background: {}; }}\n\
+         .no-floor {{ background: {}; }}\n\
+         .world-structure {{ background: {}; }}\n\
+         .swatch {{ display: inline-block; width: 12px; height: 12px; margin-right: 6px; border: 1px solid black; }}\n",
+        css_color(FLOOR_COLOR),
+        css_color(BACKGROUND_COLOR),
+        css_color(NO_FLOOR_COLOR),
+        css_color(WORLD_STRUCTURE_FLOOR_COLOR),
+    );
+
+    // Specials come after floors, so that they take priority
+    for marker in MAP_MARKERS {
+        css += &format!(
+            ".special-{} {{ background: {}; }}\n",
+            marker.class_name(),
+            css_color(marker.color())
+        );
+    }
+
+    for side in ["top", "bottom", "left", "right"] {
+        css += &format!(
+            ".wall-{side} {{ border-{side}-color: {wall}; }}\n\
+             .door-{side} {{ border-{side}-style: dashed; border-{side}-color: {wall}; }}\n\
+             .window-{side} {{ border-{side}-style: dashed; border-{side}-color: {window}; }}\n",
+            side = side,
+            wall = css_color(WALL_COLOR),
+            window = css_color(WINDOW_COLOR),
+        );
+    }
+
+    css
+}
+
+fn html_legend() -> String {
+    let mut html = String::from("<ul>\n");
+    for marker in MAP_MARKERS {
+        html += &format!(
+            "<li><span class=\"swatch\" style=\"background: {}\"></span>{}</li>\n",
+            css_color(marker.color()),
+            marker.label()
+        );
+    }
+    html += &format!(
+        "<li><span class=\"swatch\" style=\"background: {}\"></span>No Floor</li>\n",
+        css_color(NO_FLOOR_COLOR)
+    );
+    html += &format!(
+        "<li><span class=\"swatch\" style=\"border: 2px dashed {}\"></span>Door</li>\n",
+        css_color(WALL_COLOR)
+    );
+    html += &format!(
+        "<li><span class=\"swatch\" style=\"border: 2px dashed {}\"></span>Window</li>\n",
+        css_color(WINDOW_COLOR)
+    );
+    html += "</ul>\n";
+    html
+}
+
+// Renders any set of chunks, one grid per y level in the order that each y level first
+// shows up in. Chunks are laid out by their coordinates, the same way as MapLevel,
+// and anywhere in between that has no chunk is left blank.
+pub fn chunks_to_html(heading: &str, chunks: &[Chunk]) -> String {
+    let mut html = format!(
+        "<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Dungeon Maze Map</title>\n<style>\n{}</style>\n</head>\n<body>\n",
+        html_style_sheet()
+    );
+    html += heading;
+    html += &html_legend();
+
+    let grid_size = chunks.first().map_or(0, |chunk| chunk.cells.len());
+
+    let mut y_levels: Vec<i64> = Vec::new();
+    for chunk in chunks {
+        if !y_levels.contains(&chunk.y) {
+            y_levels.push(chunk.y);
+        }
+    }
+
+    for y in y_levels {
+        let level: HashMap<(i64, i64), &Chunk> = chunks
+            .iter()
+            .filter(|chunk| chunk.y == y)
+            .map(|chunk| ((chunk.x, chunk.z), chunk))
+            .collect();
+        let (min_x, max_x) = min_max(level.keys().map(|(x, _)| *x));
+        let (min_z, max_z) = min_max(level.keys().map(|(_, z)| *z));
+
+        html += &format!(
+            "<h2>Y {}</h2>\n<div class=\"level\" style=\"grid-template-columns: repeat({}, 16px)\">\n",
+            y,
+            (max_x - min_x + 1) as usize * grid_size
+        );
+
+        for chunk_z in (min_z..=max_z).rev() {
+            for z in 0..grid_size {
+                for chunk_x in (min_x..=max_x).rev() {
+                    for x in 0..grid_size {
+                        let classes = match level.get(&(chunk_x, chunk_z)) {
+                            Some(chunk) => cell_classes(chunk, &chunk.cells[z][x]),
+                            None => vec!["cell".to_owned(), "missing".to_owned()],
+                        };
+                        html += &format!("<div class=\"{}\"></div>", classes.join(" "));
+                    }
+                }
+                html += "\n";
+            }
+        }
+
+        html += "</div>\n";
+    }
+
+    html += "</body>\n</html>\n";
+    html
+}
+
+fn min_max(values: impl Iterator<Item = i64>) -> (i64, i64) {
+    values.fold((i64::MAX, i64::MIN), |(min, max), value| {
+        (min.min(value), max.max(value))
+    })
+}
+
+pub fn map_to_html(area: &MapArea, levels: &[MapLevel]) -> String {
+    let chunks: Vec<Chunk> = levels
+        .iter()
+        .flat_map(|level| level.rows.iter().flatten().cloned())
+        .collect();
+
+    chunks_to_html(
+        &format!(
+            "<h1>Seed {}</h1>\n<p>Center chunk {:?}, radius {}</p>\n",
+            area.seed, area.center, area.radius
+        ),
+        &chunks,
+    )
+}
+
+// Levels are stacked on top of each other, in the order they were generated in
+pub fn map_to_png(levels: &[MapLevel]) -> RgbImage {
+    let width = levels
+        .iter()
+        .map(|level| level.width_in_cells() as u32 * PNG_CELL_SIZE)
+        .max()
+        .unwrap_or(0);
+    let height = levels
+        .iter()
+        .map(|level| level.height_in_cells() as u32 * PNG_CELL_SIZE)
+        .sum::<u32>()
+        + PNG_LEVEL_GAP * (levels.len() as u32).saturating_sub(1);
+
+    let mut img = RgbImage::from_pixel(width, height, Rgb(BACKGROUND_COLOR));
+
+    let mut offset_y = 0;
+    for level in levels {
+        for (y, row) in level.cell_rows().enumerate() {
+            for (x, (chunk, cell)) in row.into_iter().enumerate() {
+                draw_png_cell(
+                    &mut img,
+                    x as u32 * PNG_CELL_SIZE,
+                    offset_y + y as u32 * PNG_CELL_SIZE,
+                    chunk,
+                    cell,
+                );
+            }
+        }
+        offset_y += level.height_in_cells() as u32 * PNG_CELL_SIZE + PNG_LEVEL_GAP;
+    }
+
+    img
+}
+
+fn draw_png_cell(img: &mut RgbImage, left: u32, top: u32, chunk: &Chunk, cell: &Cell) {
+    let last = PNG_CELL_SIZE - 1;
+
+    fill_rect(img, left, top, PNG_CELL_SIZE, cell_floor_color(chunk, cell));
+
+    if let Some(marker) = MapMarker::from_special(&cell.special) {
+        let inset = PNG_CELL_SIZE / 4;
+        fill_rect(
+            img,
+            left + inset,
+            top + inset,
+            PNG_CELL_SIZE - inset * 2,
+            marker.color(),
+        );
+    }
+
+    for (wall, horizontal, edge) in [
+        (&cell.wall_top, true, 0),
+        (&cell.wall_bottom, true, last),
+        (&cell.wall_left, false, 0),
+        (&cell.wall_right, false, last),
+    ] {
+        for i in 0..PNG_CELL_SIZE {
+            // Gaps for doors and windows take up the middle half of the wall
+            let in_gap = (PNG_CELL_SIZE / 4..PNG_CELL_SIZE - PNG_CELL_SIZE / 4).contains(&i);
+            let color = match wall {
+                CellWall::None => continue,
+                CellWall::Solid => WALL_COLOR,
+                CellWall::SolidWithDoorGap if in_gap => continue,
+                CellWall::SolidWithWindowGap if in_gap => WINDOW_COLOR,
+                CellWall::SolidWithDoorGap | CellWall::SolidWithWindowGap => WALL_COLOR,
+            };
+
+            let (x, y) = if horizontal { (i, edge) } else { (edge, i) };
+            img.put_pixel(left + x, top + y, Rgb(color));
+        }
+    }
+}
+
+fn fill_rect(img: &mut RgbImage, left: u32, top: u32, size: u32, color: [u8; 3]) {
+    for x in left..left + size {
+        for y in top..top + size {
+            img.put_pixel(x, y, Rgb(color));
+        }
+    }
+}
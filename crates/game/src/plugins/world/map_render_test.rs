@@ -0,0 +1,141 @@
+use crate::plugins::world::{
+    map_render::{chunks_to_html, gen_map_levels, map_to_html, map_to_png, MapArea, MAP_MARKERS},
+    world_test::world_structures,
+};
+use dungeon_maze_common::{
+    settings::Difficulty,
+    world::{
+        world_structure::{WorldStructureRegistry, NO_WORLD_STRUCTURE},
+        Cell, CellSpecial, CellWall, Chunk, WorldConfig, WorldDims, DEFAULT_SURFACE_LEVEL,
+        DEFAULT_WORLD_SEED,
+    },
+};
+
+fn default_area() -> MapArea {
+    MapArea {
+        seed: DEFAULT_WORLD_SEED,
+        difficulty: Difficulty::Normal,
+        world_config: WorldConfig {
+            surface_level: Some(DEFAULT_SURFACE_LEVEL),
+            starting_room: false,
+        },
+        dims: WorldDims::default(),
+        center: (0, 0, 0),
+        radius: 1,
+        y_levels: vec![0, -1],
+    }
+}
+
+#[test]
+fn test_gen_map_levels() {
+    let levels = gen_map_levels(&default_area(), &world_structures());
+
+    assert_eq!(levels.len(), 2);
+    assert_eq!(levels[0].y, 0);
+    assert_eq!(levels[1].y, -1);
+
+    for level in &levels {
+        assert_eq!(level.rows.len(), 3);
+        assert!(level.rows.iter().all(|row| row.len() == 3));
+    }
+
+    // +x is drawn to the left and +z to the top
+    let top_left = &levels[0].rows[0][0];
+    assert_eq!((top_left.x, top_left.y, top_left.z), (1, 0, 1));
+    let bottom_right = &levels[1].rows[2][2];
+    assert_eq!(
+        (bottom_right.x, bottom_right.y, bottom_right.z),
+        (-1, -1, -1)
+    );
+}
+
+#[test]
+fn test_map_to_html() {
+    let area = default_area();
+    let html = map_to_html(&area, &gen_map_levels(&area, &world_structures()));
+
+    assert_eq!(html.matches("class=\"level\"").count(), 2);
+    let grid_size = area.dims.grid_size();
+    assert_eq!(
+        html.matches("class=\"cell").count(),
+        2 * 9 * grid_size * grid_size
+    );
+    assert!(!html.contains("missing\""));
+
+    for marker in MAP_MARKERS {
+        assert!(html.contains(marker.label()));
+    }
+}
+
+#[test]
+fn test_map_to_png() {
+    let area = default_area();
+    let levels = gen_map_levels(&area, &world_structures());
+    let img = map_to_png(&levels);
+
+    let cells_across = 3 * area.dims.grid_size() as u32;
+    assert_eq!(img.width() % cells_across, 0);
+
+    let cell_size = img.width() / cells_across;
+    // Both levels, plus a gap between them
+    assert!(img.height() > cell_size * cells_across * 2);
+}
+
+#[test]
+fn test_map_with_larger_chunks() {
+    let area = MapArea {
+        dims: WorldDims::new(32.0, 4.0).unwrap(),
+        ..default_area()
+    };
+    // World structure files are made for the default chunk size
+    let levels = gen_map_levels(&area, &WorldStructureRegistry::default());
+
+    for chunk in levels.iter().flat_map(|level| level.rows.iter().flatten()) {
+        assert_eq!(chunk.cells.len(), 8);
+        assert!(chunk.cells.iter().all(|row| row.len() == 8));
+    }
+
+    let html = map_to_html(&area, &levels);
+    assert_eq!(html.matches("class=\"cell").count(), 2 * 9 * 8 * 8);
+}
+
+fn hand_built_chunk(x: i64, world_structure: &str) -> Chunk {
+    let cell = Cell {
+        floor: CellWall::Solid,
+        ..Cell::default()
+    };
+    let mut cells = vec![vec![cell; 2]; 2];
+    cells[0][0].special = CellSpecial::TreasureChest;
+    cells[0][0].wall_top = CellWall::Solid;
+    cells[0][1].wall_left = CellWall::SolidWithDoorGap;
+    cells[1][1].wall_bottom = CellWall::SolidWithWindowGap;
+
+    Chunk {
+        x,
+        y: 0,
+        z: 0,
+        cells,
+        world_structure: world_structure.to_owned(),
+    }
+}
+
+#[test]
+fn test_chunks_to_html() {
+    let chunks = [
+        hand_built_chunk(0, NO_WORLD_STRUCTURE),
+        hand_built_chunk(2, "Tower"),
+    ];
+    let html = chunks_to_html("<h1>Test</h1>\n", &chunks);
+
+    assert!(html.contains("<h1>Test</h1>"));
+    assert_eq!(html.matches("class=\"level\"").count(), 1);
+    // Two chunks of 2x2 cells, with a chunk wide gap between them
+    assert_eq!(html.matches("class=\"cell").count(), 3 * 4);
+    assert_eq!(html.matches("class=\"cell missing\"").count(), 4);
+    assert_eq!(html.matches(" special-treasure-chest").count(), 2);
+    assert_eq!(html.matches(" wall-top").count(), 2);
+    assert_eq!(html.matches(" door-left").count(), 2);
+    assert_eq!(html.matches(" window-bottom").count(), 2);
+    assert_eq!(html.matches("class=\"cell world-structure").count(), 4);
+    assert_eq!(html.matches("class=\"cell no-floor").count(), 0);
+}
@@ -1,57 +1,173 @@
 pub mod bundle;
-pub mod chunk_generator;
+pub mod map_render;
+pub mod physics;
+pub mod sky;
 
 #[cfg(test)]
-pub mod chunk_generator_test;
+pub mod map_render_test;
+#[cfg(test)]
+pub mod world_test;
 
 use crate::plugins::world::{
-    bundle::{chunk::spawn_chunk_bundle_from_xyz_seed, item::spawn_item_bundle},
-    chunk_generator::ChunkGenerator,
+    bundle::{
+        cell::{calc_cell_translation, cell_scale, cell_world_position, spawn_cell_bundle},
+        chunk::{chunk_world_position, spawn_chunk_bundle, spawn_chunk_bundle_from_xyz_seed},
+        item::spawn_item_bundle,
+        special::TREASURE_CHEST_OPEN_ANIMATION,
+    },
+    physics::{deactivate_new_colliders, update_chunk_physics, InactiveCollider},
+    sky::{remove_sky, update_sky},
+};
+use bevy::{
+    prelude::*,
+    tasks::{block_on, futures_lite::future, AsyncComputeTaskPool},
 };
-use bevy::prelude::*;
+use bevy_rapier3d::prelude::{Collider, PhysicsSet};
 use dungeon_maze_common::{
+    animation::CyclicAnimationCycled,
+    app_state::{AppState, GameLoaded},
+    camera::MainCamera,
     interaction::{Interactable, PendingInteractionExecuted},
-    inventory::{item::Item, ItemRemovedFromOCItemContainer, PlayerDroppedItem},
-    player::Player,
+    inventory::{
+        item::{Item, ItemBillboard, ItemModel},
+        DroppedItemPickedUp, ItemRemovedFromOCItemContainer, PlayerDroppedItem,
+    },
+    player::{DmgType, Health, Player, TakeDamage},
     save::WorldDataChanged,
-    settings::{GameSettings, RenderDistChanged},
+    settings::{Difficulty, GameSettings, RenderDistChanged, MAX_CHUNK_RENDER_DIST},
+    should_not_happen, should_not_happen_once,
     utils::{
+        debug::WarnOnce,
         maze::maze_from_rng,
         rng::{rng_from_str, rng_from_xyz_seed},
+        IncrCounter,
     },
     world::{
-        data::WorldData, world_structure::WorldStructureName, ActiveChunk, CellSpecial, CellWall,
-        Chunk, ChunkCellMarker, ChunkMarker, CyclicTransform, OCItemContainer,
+        data::WorldData,
+        is_surface_level,
+        nav::NavGraph,
+        particle::{
+            is_near_active_chunk, DripRing, DripRingHandles, EmitterParticle, ParticleEmitter,
+        },
+        structure_origin_allowed,
+        world_structure::{
+            WorldStructureAssetPlugin, WorldStructureFile, WorldStructureRegistry,
+            NO_WORLD_STRUCTURE,
+        },
+        ActiveChunk, AutoClose, Cell, CellMutated, CellSpecial, CellWall, Chunk, ChunkCellMarker,
+        ChunkDespawned, ChunkIndex, ChunkMarker, ChunkSpawned, ChunkWorldStructure,
+        ContainerUnlocked, CyclicTransform, DroppedItem, OCItemContainer, PendingChunks,
+        PickUpGrace, StaleChunk, StructureRolls, WindowPane, WorldConfig, WorldDims, WorldSeed,
     },
 };
 use rand::{rngs::StdRng, Rng};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use strum::IntoEnumIterator;
 
-pub const CELL_SIZE: f32 = 4.0;
-pub const CHUNK_SIZE: f32 = 16.0;
-pub const GRID_SIZE: usize = (CHUNK_SIZE / CELL_SIZE) as usize;
+#[cfg(debug_assertions)]
+use dungeon_maze_common::debug::ChunkSpawnTimings;
+#[cfg(debug_assertions)]
+use std::time::Instant;
 
 const WALL_BREAK_PROB: f64 = 0.2;
-const WORLD_STRUCTURE_GEN_PROB: f64 = 0.18;
+// How long the ring left by a drip takes to grow to its full size, and then disappear
+const DRIP_RING_SECS: f32 = 0.6;
+const DRIP_RING_MAX_SCALE: f32 = 4.0;
+// Chance for each wall between two cells of the same chunk to be knocked out after
+// the maze is generated, which opens up 2-wide corridors and rooms
+pub const WIDE_PASSAGE_PROB: f64 = 0.08;
+const MAX_DROPPED_ITEMS: usize = 128;
+// Chunks below the spawn chunk that are searched for somewhere safe to spawn
+const SAFE_SPAWN_MAX_DEPTH: i64 = 4;
+// Frames that chunks outside of render distance are kept for before being despawned
+const STALE_CHUNK_DESPAWN_DELAY: i32 = 2;
+// Chunks around the active chunk at the max render distance, past which the settings are clamped
+const MAX_NEI_CHUNKS: u64 = (MAX_CHUNK_RENDER_DIST as u64 * 2 - 1).pow(3);
+// Radians per second
+const DROPPED_ITEM_SPIN_SPEED: f32 = 1.0;
+// Frames before an item the player dropped can be picked up automatically
+const DROPPED_ITEM_PICK_UP_GRACE_FRAMES: u32 = 60;
+// Every .json file in here is loaded into the WorldStructureRegistry
+pub const WORLD_STRUCTURES_FOLDER: &str = "embedded://world_structures";
 
 pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
-        app.init_state::<ActiveChunk>()
-            .add_systems(Startup, spawn_initial_chunks)
+        // Dimensions other than the default ones are inserted before this plugin is added
+        app.init_resource::<WorldDims>();
+        let grid_size = app.world().resource::<WorldDims>().grid_size();
+
+        #[cfg(debug_assertions)]
+        app.init_resource::<ChunkSpawnTimings>();
+
+        app.add_plugins(WorldStructureAssetPlugin { grid_size })
+            .register_type::<ActiveChunk>()
+            .register_type::<State<ActiveChunk>>()
+            .init_state::<ActiveChunk>()
+            .init_resource::<WorldStructureRegistry>()
+            .init_resource::<PendingChunks>()
+            .init_resource::<ChunkIndex>()
+            .add_event::<ChunkSpawned>()
+            .add_event::<ChunkDespawned>()
+            .add_event::<CellMutated>()
+            .add_event::<ContainerUnlocked>()
+            .add_event::<TakeDamage>()
+            .add_systems(OnEnter(GameLoaded), spawn_initial_chunks)
+            .add_systems(OnExit(GameLoaded), (despawn_chunks, remove_sky))
+            .add_systems(
+                Update,
+                update_world_structure_registry
+                    .run_if(on_event::<AssetEvent<WorldStructureFile>>()),
+            )
+            // Runs in every app state, so that chunks despawned on the way out of
+            // the game are removed from the index too
+            .add_systems(
+                Update,
+                update_chunk_index
+                    .after(spawn_generated_chunks)
+                    .after(despawn_stale_chunks),
+            )
             .add_systems(
                 Update,
                 (
                     manage_active_chunk,
+                    record_dropped_item_positions.before(update_spawned_chunks),
                     update_spawned_chunks,
+                    spawn_generated_chunks.after(update_spawned_chunks),
+                    despawn_stale_chunks.after(update_spawned_chunks),
+                    restore_dropped_items.after(spawn_generated_chunks),
+                    respawn_mutated_cells.after(spawn_generated_chunks),
+                    update_sky,
+                    face_item_billboards_to_camera,
+                    update_chunk_physics,
+                )
+                    .run_if(in_state(AppState::InGame).or_else(in_state(AppState::Spectating))),
+            )
+            .add_systems(
+                PostUpdate,
+                deactivate_new_colliders
+                    .before(PhysicsSet::SyncBackend)
+                    .run_if(in_state(AppState::InGame).or_else(in_state(AppState::Spectating))),
+            )
+            .add_systems(
+                Update,
+                (
                     advance_cyclic_transforms,
                     handle_cyclic_transform_interactions.after(advance_cyclic_transforms),
+                    auto_close_cyclic_transforms.after(handle_cyclic_transform_interactions),
                     activate_items_inside_containers.after(advance_cyclic_transforms),
                     remove_item_from_oc_item_containers,
+                    record_treasure_chest_open_state,
+                    record_treasure_chest_lock_state,
+                    break_window_panes,
                     spawn_dropped_item,
-                ),
+                    remove_picked_up_dropped_items,
+                    spin_dropped_items,
+                    update_particle_emitters,
+                    update_drip_rings,
+                )
+                    .run_if(in_state(AppState::InGame)),
             );
     }
 }
@@ -64,6 +180,12 @@ pub fn spawn_initial_chunks(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     world_data: Res<WorldData>,
+    world_seed: Res<WorldSeed>,
+    world_config: Res<WorldConfig>,
+    world_dims: Res<WorldDims>,
+    world_structures: Res<WorldStructureRegistry>,
+    mut event_writer: EventWriter<ChunkSpawned>,
+    #[cfg(debug_assertions)] mut chunk_spawn_timings: ResMut<ChunkSpawnTimings>,
 ) {
     let render_dist = game_settings.chunk_render_dist;
     let chunks = make_nei_chunks_xyz(
@@ -73,83 +195,381 @@ pub fn spawn_initial_chunks(
         render_dist.2,
     );
     for xyz in chunks {
+        #[cfg(debug_assertions)]
+        let start = Instant::now();
+
         spawn_chunk_bundle_from_xyz_seed(
+            world_seed.0,
+            game_settings.difficulty,
+            &world_config,
+            &world_dims,
+            &world_structures,
+            game_settings.decor_density,
             xyz,
             &mut commands,
             &asset_server,
             &mut meshes,
             &mut materials,
             &world_data,
+            &mut event_writer,
         );
+
+        #[cfg(debug_assertions)]
+        chunk_spawn_timings.record(start.elapsed());
     }
 }
 
+pub fn despawn_chunks(
+    mut commands: Commands,
+    mut event_writer: EventWriter<ChunkDespawned>,
+    chunks_query: Query<(Entity, &ChunkMarker)>,
+    mut next_active_chunk: ResMut<NextState<ActiveChunk>>,
+    mut pending_chunks: ResMut<PendingChunks>,
+) {
+    pending_chunks.0.clear();
+    for (entity, chunk_marker) in chunks_query.iter() {
+        commands.entity(entity).despawn_recursive();
+        event_writer.send(ChunkDespawned {
+            xyz: chunk_marker.0,
+        });
+    }
+    next_active_chunk.set(ActiveChunk::default());
+}
+
+// Rebuilt from every loaded world structure file whenever one of them is loaded or changed
+pub fn update_world_structure_registry(
+    mut commands: Commands,
+    world_structure_files: Res<Assets<WorldStructureFile>>,
+) {
+    commands.insert_resource(WorldStructureRegistry::from_assets(&world_structure_files));
+}
+
+// Chunks are loaded around the player, or around the camera while spectating
 pub fn manage_active_chunk(
     player_query: Query<&GlobalTransform, With<Player>>,
+    camera_query: Query<&GlobalTransform, (With<MainCamera>, Without<Player>)>,
+    app_state: Res<State<AppState>>,
     active_chunk: Res<State<ActiveChunk>>,
     mut next_active_chunk: ResMut<NextState<ActiveChunk>>,
+    world_dims: Res<WorldDims>,
+    mut warn_once: Local<WarnOnce>,
 ) {
-    let gt = player_query.get_single().expect("Error retrieving player");
-    let (x, y, z) = ChunkCellMarker::from_global_transform(gt, CHUNK_SIZE, CELL_SIZE).chunk_xyz();
+    let gt = match app_state.get() {
+        AppState::Spectating => camera_query.get_single(),
+        _ => player_query.get_single(),
+    };
+    let gt = match gt {
+        Ok(gt) => gt,
+        Err(err) => {
+            should_not_happen_once!(
+                warn_once,
+                "retrieving {:?} transform: {}",
+                app_state.get(),
+                err
+            );
+            return;
+        }
+    };
+    let (x, y, z) = ChunkCellMarker::from_global_transform(gt, &world_dims).chunk_xyz();
 
     if x != active_chunk.0 || y != active_chunk.1 || z != active_chunk.2 {
         next_active_chunk.set(ActiveChunk(x, y, z));
     }
 }
 
+// Chunks that come into render distance are generated in the background,
+// and spawned by spawn_generated_chunks once they are ready
 pub fn update_spawned_chunks(
     mut commands: Commands,
     ac_event_reader: EventReader<StateTransitionEvent<ActiveChunk>>,
     rd_event_reader: EventReader<RenderDistChanged>,
-    chunks_query: Query<(Entity, &ChunkMarker)>,
+    chunks_query: Query<(Entity, &ChunkMarker, Has<StaleChunk>)>,
     active_chunk: Res<State<ActiveChunk>>,
     game_settings: Res<State<GameSettings>>,
-    asset_server: Res<AssetServer>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    world_data: Res<WorldData>,
+    world_seed: Res<WorldSeed>,
+    world_config: Res<WorldConfig>,
+    world_dims: Res<WorldDims>,
+    world_structures: Res<WorldStructureRegistry>,
+    mut pending_chunks: ResMut<PendingChunks>,
 ) {
     if !ac_event_reader.is_empty() || !rd_event_reader.is_empty() {
         let rend_dist = game_settings.chunk_render_dist;
-        let new_chunks = make_nei_chunks_xyz(
+        let new_chunks: HashSet<(i64, i64, i64)> = make_nei_chunks_xyz(
             active_chunk.to_tuple(),
             rend_dist.0,
             rend_dist.1,
             rend_dist.2,
-        );
+        )
+        .collect();
 
+        let diff_span = info_span!("update_spawned_chunks_diff").entered();
         let mut existing_chunks: HashSet<(i64, i64, i64)> = HashSet::new();
 
-        // Despawn chunks that are not among new chunks
-        for (chunk_entity, chunk_marker) in chunks_query.iter() {
-            if !new_chunks.contains(&chunk_marker.0) {
-                commands.entity(chunk_entity).despawn_recursive();
+        // Chunks that are not among new chunks are only marked as stale here,
+        // and are despawned later by despawn_stale_chunks, so that the world
+        // never has holes in it while new chunks are being spawned
+        for (chunk_entity, chunk_marker, is_stale) in chunks_query.iter() {
+            let is_needed = new_chunks.contains(&chunk_marker.0);
+            if !is_needed && !is_stale {
+                commands
+                    .entity(chunk_entity)
+                    .insert(StaleChunk(IncrCounter::new(STALE_CHUNK_DESPAWN_DELAY, -1)));
+            } else if is_needed && is_stale {
+                commands.entity(chunk_entity).remove::<StaleChunk>();
             }
             existing_chunks.insert(chunk_marker.0);
         }
 
-        // Spawn new chunks that do not currently exist
+        // Chunks that left render distance before they finished generating are cancelled
+        pending_chunks.0.retain(|xyz, _| new_chunks.contains(xyz));
+        drop(diff_span);
+
+        // Generate new chunks that do not currently exist. Generation only depends on
+        // the seed and coordinates, so it gives the same chunks as chunk_from_xyz_seed.
+        let task_pool = AsyncComputeTaskPool::get();
+
         for (x, y, z) in new_chunks {
-            if !existing_chunks.contains(&(x, y, z)) {
-                spawn_chunk_bundle_from_xyz_seed(
-                    (x, y, z),
-                    &mut commands,
+            if existing_chunks.contains(&(x, y, z)) || pending_chunks.0.contains_key(&(x, y, z)) {
+                continue;
+            }
+
+            let seed = world_seed.0;
+            let difficulty = game_settings.difficulty;
+            let world_config = *world_config;
+            let dims = *world_dims;
+            let world_structures = world_structures.clone();
+
+            let task = task_pool.spawn(async move {
+                chunk_from_xyz_seed(
+                    seed,
+                    difficulty,
+                    &world_config,
+                    &dims,
+                    &world_structures,
+                    x,
+                    y,
+                    z,
+                )
+            });
+            pending_chunks.0.insert((x, y, z), task);
+        }
+    };
+}
+
+// Spawns the entities of every chunk that has finished generating
+pub fn spawn_generated_chunks(
+    mut commands: Commands,
+    mut pending_chunks: ResMut<PendingChunks>,
+    game_settings: Res<State<GameSettings>>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    world_data: Res<WorldData>,
+    world_seed: Res<WorldSeed>,
+    world_dims: Res<WorldDims>,
+    world_structures: Res<WorldStructureRegistry>,
+    mut event_writer: EventWriter<ChunkSpawned>,
+    #[cfg(debug_assertions)] mut chunk_spawn_timings: ResMut<ChunkSpawnTimings>,
+) {
+    pending_chunks.0.retain(|xyz, task| {
+        let Some(mut chunk) = block_on(future::poll_once(task)) else {
+            return true;
+        };
+
+        let (x, y, z) = *xyz;
+        let _span = info_span!("spawn_generated_chunk", x, y, z).entered();
+        #[cfg(debug_assertions)]
+        let start = Instant::now();
+
+        world_data.apply_cell_mutations(&mut chunk);
+
+        let entity = spawn_chunk_bundle(
+            world_seed.0,
+            &world_dims,
+            game_settings.decor_density,
+            &chunk,
+            &world_structures,
+            &mut commands,
+            &asset_server,
+            &mut meshes,
+            &mut materials,
+            &world_data,
+        );
+
+        event_writer.send(ChunkSpawned {
+            entity,
+            xyz: *xyz,
+            chunk,
+        });
+
+        #[cfg(debug_assertions)]
+        chunk_spawn_timings.record(start.elapsed());
+        false
+    });
+}
+
+pub fn despawn_stale_chunks(
+    mut commands: Commands,
+    mut event_writer: EventWriter<ChunkDespawned>,
+    mut stale_chunks_query: Query<(Entity, &ChunkMarker, &mut StaleChunk)>,
+) {
+    for (chunk_entity, chunk_marker, mut stale_chunk) in stale_chunks_query.iter_mut() {
+        if stale_chunk.0.tick() == 0 {
+            commands.entity(chunk_entity).despawn_recursive();
+            event_writer.send(ChunkDespawned {
+                xyz: chunk_marker.0,
+            });
+        }
+    }
+}
+
+// Despawns are handled before spawns, since a chunk can only be spawned
+// again after it was despawned, like when a save is loaded
+pub fn update_chunk_index(
+    mut spawned_event_reader: EventReader<ChunkSpawned>,
+    mut despawned_event_reader: EventReader<ChunkDespawned>,
+    mut chunk_index: ResMut<ChunkIndex>,
+) {
+    for event in despawned_event_reader.read() {
+        chunk_index.remove(event.xyz);
+    }
+    for event in spawned_event_reader.read() {
+        chunk_index.insert(event.xyz, event.entity);
+    }
+}
+
+// Mutations are recorded in WorldData so that they are applied whenever the chunk is
+// spawned again. Only the mutated cells of chunks that are already spawned are respawned,
+// rather than the whole chunk they are in.
+pub fn respawn_mutated_cells(
+    mut commands: Commands,
+    mut event_reader: EventReader<CellMutated>,
+    mut event_writer: EventWriter<WorldDataChanged>,
+    cell_query: Query<(Entity, &Cell, &ChunkCellMarker, &Parent, Option<&Children>)>,
+    chunk_query: Query<&ChunkWorldStructure>,
+    dropped_item_query: Query<(), With<DroppedItem>>,
+    game_settings: Res<State<GameSettings>>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    world_data: Res<WorldData>,
+    world_seed: Res<WorldSeed>,
+    world_dims: Res<WorldDims>,
+    world_structures: Res<WorldStructureRegistry>,
+) {
+    if event_reader.is_empty() {
+        return;
+    }
+
+    let mut new_world_data = world_data.clone();
+    let mut changed = false;
+    // Several mutations of the same cell in one frame are all applied before it is respawned
+    let mut mutated_cells: HashMap<ChunkCellMarker, Cell> = HashMap::new();
+
+    for event in event_reader.read() {
+        let ccm = &event.ccm;
+        if !new_world_data.add_cell_mutation(ccm.chunk_xyz(), ccm.cell_xz(), event.mutation) {
+            continue;
+        }
+        changed = true;
+
+        let Some((_, cell, _, _, _)) = cell_query.iter().find(|(_, _, c, _, _)| *c == ccm) else {
+            continue;
+        };
+        let cell = mutated_cells
+            .entry(ccm.clone())
+            .or_insert_with(|| cell.clone());
+        event.mutation.apply(cell);
+    }
+
+    for (entity, _, ccm, parent, children) in cell_query.iter() {
+        let Some(cell) = mutated_cells.get(ccm) else {
+            continue;
+        };
+
+        // Items dropped inside of the cell are kept, and handed over to its chunk
+        for child in children.into_iter().flatten() {
+            if dropped_item_query.contains(*child) {
+                commands.entity(*child).set_parent_in_place(parent.get());
+            }
+        }
+        commands.entity(entity).despawn_recursive();
+
+        let theme = chunk_query
+            .get(parent.get())
+            .ok()
+            .and_then(|cws| world_structures.theme(&cws.0));
+
+        commands.entity(parent.get()).with_children(|parent| {
+            spawn_cell_bundle(
+                world_seed.0,
+                &world_dims,
+                game_settings.decor_density,
+                cell,
+                ccm.clone(),
+                theme,
+                parent,
+                &asset_server,
+                &mut meshes,
+                &mut materials,
+                &world_data,
+            );
+        });
+    }
+
+    if changed {
+        commands.insert_resource(new_world_data);
+        event_writer.send(WorldDataChanged);
+    }
+}
+
+// Items previously dropped by the player are spawned back in once their chunk is
+pub fn restore_dropped_items(
+    mut commands: Commands,
+    mut event_reader: EventReader<ChunkSpawned>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    world_data: Res<WorldData>,
+    world_dims: Res<WorldDims>,
+) {
+    for event in event_reader.read() {
+        let Some(chunk_data) = world_data.at_chunk(event.xyz) else {
+            continue;
+        };
+        let Some(mut entity_commands) = commands.get_entity(event.entity) else {
+            continue;
+        };
+
+        let chunk_translation = chunk_world_position(&world_dims, event.xyz);
+        entity_commands.with_children(|parent| {
+            for did in chunk_data.dropped_items() {
+                spawn_item_bundle(
+                    did.item,
+                    parent,
                     &asset_server,
                     &mut meshes,
                     &mut materials,
-                    &world_data,
-                );
+                    Some(Transform::from_translation(
+                        Vec3::from(did.translation) - chunk_translation,
+                    )),
+                    true,
+                    true,
+                    true,
+                )
+                .insert(DroppedItem(did.id));
             }
-        }
-    };
+        });
+    }
 }
 
 pub fn advance_cyclic_transforms(
     mut cyclic_transforms_query: Query<(&mut CyclicTransform, &mut Transform)>,
+    time: Res<Time>,
 ) {
     for (mut ct, mut transform) in cyclic_transforms_query.iter_mut() {
-        if let Some(t) = ct.tick() {
-            *transform = t.clone();
+        if let Some(t) = ct.tick(time.delta()) {
+            *transform = t;
         }
     }
 }
@@ -167,6 +587,111 @@ pub fn handle_cyclic_transform_interactions(
     }
 }
 
+// Interacting with an open door cycles it shut by hand, which takes it out of the open
+// state and cancels its count, so the two never cycle the same door on the same frame
+pub fn auto_close_cyclic_transforms(
+    mut auto_close_query: Query<(&mut AutoClose, &mut CyclicTransform, &Parent)>,
+    parent_query: Query<&GlobalTransform>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+) {
+    for (mut auto_close, mut cyclic_transform, parent) in auto_close_query.iter_mut() {
+        let is_open = cyclic_transform.is_in_state(auto_close.open_state);
+        let blocked = parent_query
+            .get(parent.get())
+            .is_ok_and(|parent_gl_transform| {
+                let to_local = parent_gl_transform.affine().inverse();
+                player_query.iter().any(|player_gl_transform| {
+                    auto_close.sensor_contains(
+                        to_local.transform_point3(player_gl_transform.translation()),
+                    )
+                })
+            });
+
+        if auto_close.tick(is_open, blocked) {
+            cyclic_transform.cycle();
+        }
+    }
+}
+
+pub fn update_particle_emitters(
+    mut commands: Commands,
+    mut emitter_query: Query<(
+        Entity,
+        &mut ParticleEmitter,
+        &mut Visibility,
+        Option<&Children>,
+    )>,
+    drip_ring_handles_query: Query<&DripRingHandles>,
+    mut particle_query: Query<
+        (&EmitterParticle, &mut Transform, &mut Visibility),
+        Without<ParticleEmitter>,
+    >,
+    active_chunk: Res<State<ActiveChunk>>,
+    time: Res<Time>,
+) {
+    for (entity, mut emitter, mut visibility, children) in emitter_query.iter_mut() {
+        // Emitters further away are left exactly as they are, and hidden
+        // so that no particles hang frozen in the air
+        if !is_near_active_chunk(emitter.chunk_xyz, active_chunk.get()) {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        }
+        visibility.set_if_neq(Visibility::Inherited);
+
+        let landed = emitter.tick(time.delta_seconds());
+
+        for child in children.into_iter().flatten() {
+            let Ok((particle, mut transform, mut visibility)) = particle_query.get_mut(*child)
+            else {
+                continue;
+            };
+            let Some(particle) = emitter.particles().get(particle.0) else {
+                continue;
+            };
+
+            if particle.alive {
+                transform.translation = particle.position;
+                visibility.set_if_neq(Visibility::Inherited);
+            } else {
+                visibility.set_if_neq(Visibility::Hidden);
+            }
+        }
+
+        if let Ok(handles) = drip_ring_handles_query.get(entity) {
+            for position in landed {
+                commands.entity(entity).with_children(|parent| {
+                    parent.spawn((
+                        PbrBundle {
+                            mesh: handles.mesh.clone(),
+                            material: handles.material.clone(),
+                            transform: Transform::from_translation(position),
+                            ..default()
+                        },
+                        DripRing::default(),
+                    ));
+                });
+            }
+        }
+    }
+}
+
+pub fn update_drip_rings(
+    mut commands: Commands,
+    mut drip_ring_query: Query<(Entity, &mut DripRing, &mut Transform)>,
+    time: Res<Time>,
+) {
+    for (entity, mut drip_ring, mut transform) in drip_ring_query.iter_mut() {
+        drip_ring.age_secs += time.delta_seconds();
+        if drip_ring.age_secs >= DRIP_RING_SECS {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let scale = 1.0 + drip_ring.age_secs / DRIP_RING_SECS * (DRIP_RING_MAX_SCALE - 1.0);
+        transform.scale = Vec3::new(scale, 1.0, scale);
+    }
+}
+
 pub fn activate_items_inside_containers(
     mut commands: Commands,
     mut event_reader: EventReader<PendingInteractionExecuted>,
@@ -196,26 +721,184 @@ pub fn activate_items_inside_containers(
 pub fn spawn_dropped_item(
     mut commands: Commands,
     mut event_reader: EventReader<PlayerDroppedItem>,
+    mut event_writer: EventWriter<WorldDataChanged>,
     player_query: Query<&GlobalTransform, With<Player>>,
+    children_query: Query<&Children>,
+    ccm_query: Query<&ChunkCellMarker>,
+    dropped_item_query: Query<(Entity, &DroppedItem)>,
+    asset_server: Res<AssetServer>,
     mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    chunk_index: Res<ChunkIndex>,
+    world_data: Res<WorldData>,
+    world_dims: Res<WorldDims>,
+    mut warn_once: Local<WarnOnce>,
 ) {
     for event in event_reader.read() {
-        let player_gl_transform = player_query.get_single().unwrap();
-        spawn_item_bundle(
-            event.0.clone(),
-            &mut commands,
-            &mut meshes,
-            Some(Transform::from_translation(
-                player_gl_transform.translation(),
-            )),
-            true,
-            true,
-            true,
+        let player_gl_transform = match player_query.get_single() {
+            Ok(gt) => gt,
+            Err(err) => {
+                should_not_happen_once!(warn_once, "retrieving player: {}", err);
+                return;
+            }
+        };
+        let translation = player_gl_transform.translation();
+        let ccm = ChunkCellMarker::from_global_transform(player_gl_transform, &world_dims);
+
+        let mut new_world_data = world_data.clone();
+        let id = new_world_data.add_dropped_item(
+            ccm.chunk_xyz(),
+            ccm.cell_xz(),
+            event.0,
+            translation.into(),
         );
+
+        // Parent the item to the cell it was dropped in, so that it
+        // is despawned along with the chunk the cell belongs to.
+        // Cells are scaled to the cell size of the world, and the item should not be.
+        let scale = cell_scale(&world_dims);
+        if let Some(cell_entity) = chunk_index.cell_entity(&ccm, &children_query, &ccm_query) {
+            commands.entity(cell_entity).with_children(|parent| {
+                spawn_item_bundle(
+                    event.0,
+                    parent,
+                    &asset_server,
+                    &mut meshes,
+                    &mut materials,
+                    Some(
+                        Transform::from_translation(
+                            (translation - calc_cell_translation(&world_dims, &ccm)) / scale,
+                        )
+                        .with_scale(Vec3::splat(1.0 / scale)),
+                    ),
+                    true,
+                    true,
+                    true,
+                )
+                .insert((
+                    DroppedItem(id),
+                    PickUpGrace(DROPPED_ITEM_PICK_UP_GRACE_FRAMES),
+                ));
+            });
+        } else {
+            should_not_happen!(
+                "dropped item inside of a cell that does not exist: {:?}",
+                ccm
+            );
+        }
+
+        // Evict the oldest dropped items once there are too many
+        while new_world_data.dropped_items_count() > MAX_DROPPED_ITEMS {
+            let Some(oldest_id) = new_world_data.oldest_dropped_item_id() else {
+                break;
+            };
+            new_world_data.remove_dropped_item(oldest_id);
+
+            for (entity, dropped_item) in dropped_item_query.iter() {
+                if dropped_item.0 == oldest_id {
+                    commands.entity(entity).despawn_recursive();
+                }
+            }
+        }
+
+        commands.insert_resource(new_world_data);
+        event_writer.send(WorldDataChanged);
         break;
     }
 }
 
+pub fn record_dropped_item_positions(
+    mut ac_event_reader: EventReader<StateTransitionEvent<ActiveChunk>>,
+    mut event_writer: EventWriter<WorldDataChanged>,
+    dropped_item_query: Query<(&DroppedItem, &GlobalTransform)>,
+    mut world_data: ResMut<WorldData>,
+) {
+    if ac_event_reader.read().count() == 0 {
+        return;
+    }
+
+    // Dropped items can move after being spawned, so their positions are
+    // recorded before any chunks (and the items inside of them) are despawned
+    let mut changed = false;
+    for (dropped_item, gt) in dropped_item_query.iter() {
+        let translation = gt.translation();
+
+        if let Some(did) = world_data
+            .bypass_change_detection()
+            .dropped_item_mut(dropped_item.0)
+        {
+            if Vec3::from(did.translation).distance(translation) > 0.01 {
+                did.translation = translation.into();
+                changed = true;
+            }
+        }
+    }
+
+    if changed {
+        world_data.set_changed();
+        event_writer.send(WorldDataChanged);
+    }
+}
+
+pub fn remove_picked_up_dropped_items(
+    mut commands: Commands,
+    mut event_reader: EventReader<DroppedItemPickedUp>,
+    mut event_writer: EventWriter<WorldDataChanged>,
+    world_data: Res<WorldData>,
+) {
+    for event in event_reader.read() {
+        let mut new_world_data = world_data.clone();
+        match &event.rem_item {
+            Some(rem_item) => {
+                if let Some(did) = new_world_data.dropped_item_mut(event.id) {
+                    did.item = *rem_item;
+                }
+            }
+            None => {
+                new_world_data.remove_dropped_item(event.id);
+            }
+        }
+        commands.insert_resource(new_world_data);
+        event_writer.send(WorldDataChanged);
+    }
+}
+
+pub fn spin_dropped_items(
+    mut model_query: Query<(&Parent, &mut Transform), With<ItemModel>>,
+    dropped_item_query: Query<(), With<DroppedItem>>,
+    time: Res<Time>,
+) {
+    for (parent, mut transform) in model_query.iter_mut() {
+        if dropped_item_query.contains(parent.get()) {
+            transform.rotate_y(DROPPED_ITEM_SPIN_SPEED * time.delta_seconds());
+        }
+    }
+}
+
+pub fn face_item_billboards_to_camera(
+    camera_query: Query<&GlobalTransform, With<MainCamera>>,
+    mut billboard_query: Query<(&GlobalTransform, &mut Transform), With<ItemBillboard>>,
+) {
+    let Ok(camera_gt) = camera_query.get_single() else {
+        return;
+    };
+
+    for (gt, mut transform) in billboard_query.iter_mut() {
+        let direction = gt.translation() - camera_gt.translation();
+        if direction == Vec3::ZERO {
+            continue;
+        }
+
+        // Items tumble as they fall, so the parent's rotation has to be undone
+        let (_, world_rotation, _) = gt.to_scale_rotation_translation();
+        let parent_rotation = world_rotation * transform.rotation.inverse();
+        // Quads face +Z, so looking away from the camera shows their front to it
+        let target_rotation = Transform::default().looking_to(direction, Vec3::Y).rotation;
+
+        transform.rotation = parent_rotation.inverse() * target_rotation;
+    }
+}
+
 pub fn remove_item_from_oc_item_containers(
     mut commands: Commands,
     mut event_reader: EventReader<ItemRemovedFromOCItemContainer>,
@@ -226,83 +909,301 @@ pub fn remove_item_from_oc_item_containers(
         let mut new_world_data = world_data.clone();
         let cell_data =
             new_world_data.at_cell_or_create_mut(event.ccm.chunk_xyz(), event.ccm.cell_xz());
-        cell_data.treasure_chest_data.item = None;
+        cell_data.treasure_chest_data.set_remaining(event.rem_item);
         commands.insert_resource(new_world_data);
         event_writer.send(WorldDataChanged);
     }
 }
 
-pub fn chunk_from_xyz_seed(seed: u32, x: i64, y: i64, z: i64) -> Chunk {
-    let mut rng = rng_from_xyz_seed(seed, x, y, z);
+// Chests are spawned back in open if they were left open
+pub fn record_treasure_chest_open_state(
+    mut event_reader: EventReader<CyclicAnimationCycled>,
+    mut event_writer: EventWriter<WorldDataChanged>,
+    container_query: Query<&GlobalTransform, With<OCItemContainer>>,
+    mut world_data: ResMut<WorldData>,
+    world_dims: Res<WorldDims>,
+) {
+    for event in event_reader.read() {
+        let Ok(gt) = container_query.get(event.entity) else {
+            continue;
+        };
 
-    if chunk_has_world_structure(seed, x, y, z) {
-        return WorldStructureName::choose(&mut rng).gen_origin_chunk(x, y, z);
+        let ccm = ChunkCellMarker::from_global_transform(gt, &world_dims);
+        let cell_data = world_data.at_cell_or_create_mut(ccm.chunk_xyz(), ccm.cell_xz());
+        cell_data.set_chest_opened(event.clip == TREASURE_CHEST_OPEN_ANIMATION);
+        event_writer.send(WorldDataChanged);
     }
+}
 
-    let mut cells = maze_from_rng(&mut rng, GRID_SIZE, GRID_SIZE);
+// Chests stay unlocked once a key has been used on them
+pub fn record_treasure_chest_lock_state(
+    mut event_reader: EventReader<ContainerUnlocked>,
+    mut event_writer: EventWriter<WorldDataChanged>,
+    container_query: Query<&GlobalTransform, With<OCItemContainer>>,
+    mut world_data: ResMut<WorldData>,
+    world_dims: Res<WorldDims>,
+) {
+    for event in event_reader.read() {
+        let Ok(gt) = container_query.get(event.0) else {
+            continue;
+        };
 
-    let h = GRID_SIZE / 2;
-    let w = GRID_SIZE / 2;
+        let ccm = ChunkCellMarker::from_global_transform(gt, &world_dims);
+        let cell_data = world_data.at_cell_or_create_mut(ccm.chunk_xyz(), ccm.cell_xz());
+        cell_data.set_chest_unlocked(true);
+        event_writer.send(WorldDataChanged);
+    }
+}
+
+// Only Blunt and Slash damage wears down the glass of a window. Once it breaks, the glass goes
+// along with the window's collider, and stays gone whenever the cell is spawned again.
+pub fn break_window_panes(
+    mut commands: Commands,
+    mut event_reader: EventReader<TakeDamage>,
+    mut event_writer: EventWriter<WorldDataChanged>,
+    mut pane_query: Query<(&WindowPane, &mut Health, &Parent)>,
+    parent_query: Query<&Parent>,
+    cell_query: Query<&ChunkCellMarker>,
+    mut world_data: ResMut<WorldData>,
+) {
+    for event in event_reader.read() {
+        let Ok((pane, mut health, window)) = pane_query.get_mut(event.target) else {
+            continue;
+        };
+        // Already broken earlier this frame
+        if health.value <= 0.0 {
+            continue;
+        }
+
+        let dmg: f32 = event
+            .amounts
+            .iter()
+            .filter(|(dmg_type, _)| matches!(dmg_type, DmgType::Blunt | DmgType::Slash))
+            .map(|(_, amt)| amt)
+            .sum();
+        health.subtract(dmg);
+        if health.value > 0.0 {
+            continue;
+        }
+
+        commands.entity(event.target).despawn_recursive();
+        // Windows in chunks outside of physics distance keep their collider aside
+        commands
+            .entity(window.get())
+            .remove::<(Collider, InactiveCollider)>();
+
+        let ccm = parent_query
+            .get(window.get())
+            .and_then(|cell| cell_query.get(cell.get()));
+        let Ok(ccm) = ccm else {
+            should_not_happen!("window of pane {} is not in a cell", event.target);
+            continue;
+        };
+        world_data
+            .at_cell_or_create_mut(ccm.chunk_xyz(), ccm.cell_xz())
+            .set_window_broken(pane.0, true);
+        event_writer.send(WorldDataChanged);
+    }
+}
+
+pub fn chunk_from_xyz_seed(
+    seed: u32,
+    difficulty: Difficulty,
+    world_config: &WorldConfig,
+    dims: &WorldDims,
+    world_structures: &WorldStructureRegistry,
+    x: i64,
+    y: i64,
+    z: i64,
+) -> Chunk {
+    let _span = info_span!("chunk_from_xyz_seed", seed, x, y, z).entered();
+    let surface_level = world_config.surface_level;
+
+    if let Some(mut chunk) =
+        structure_chunk_from_xyz_seed(seed, world_config, dims, world_structures, x, y, z)
+    {
+        open_to_sky(&mut chunk.cells, surface_level, y);
+        return chunk;
+    }
+
+    let grid_size = dims.grid_size();
+    let mut rng = rng_from_xyz_seed(seed, x, y, z);
+    let mut cells = maze_from_rng(&mut rng, grid_size, grid_size);
+    widen_passages(seed, (x, y, z), &mut cells, WIDE_PASSAGE_PROB);
+
+    let h = grid_size / 2;
+    let w = grid_size / 2;
 
     // left and right walls
     cells[h][0].wall_left = CellWall::None;
-    cells[h][GRID_SIZE - 1].wall_right = CellWall::None;
+    cells[h][grid_size - 1].wall_right = CellWall::None;
 
     // top and bottom walls
     cells[0][w].wall_top = CellWall::None;
-    cells[GRID_SIZE - 1][w].wall_bottom = CellWall::None;
-
-    // ceiling and floor (y axis)
-    for h in 0..GRID_SIZE {
-        for w in 0..GRID_SIZE {
-            let mut y_minus_1_rng = rng_from_str(seed_str_from_neis(
-                seed,
-                (x, y - 1, z, w, h),
-                (x, y, z, w, h),
-            ));
-            if y_minus_1_rng.gen_bool(WALL_BREAK_PROB) {
-                cells[h][w].floor = CellWall::None;
+    cells[grid_size - 1][w].wall_bottom = CellWall::None;
+
+    // ceiling and floor (y axis). Holes between levels are skipped where open_to_sky decides
+    // them instead, which leaves the rest alone since each pair of cells has its own rng.
+    let open_floor = is_surface_level(surface_level, y - 1);
+    let open_ceiling = is_surface_level(surface_level, y);
+    for h in 0..grid_size {
+        for w in 0..grid_size {
+            if !open_floor {
+                let mut y_minus_1_rng = vertical_pair_rng(seed, (x, y - 1, z), (x, y, z), (w, h));
+                if y_minus_1_rng.gen_bool(WALL_BREAK_PROB) {
+                    cells[h][w].floor = CellWall::None;
+                }
             }
 
-            let mut y_plus_1_rng = rng_from_str(seed_str_from_neis(
-                seed,
-                (x, y, z, w, h),
-                (x, y + 1, z, w, h),
-            ));
-            if y_plus_1_rng.gen_bool(WALL_BREAK_PROB) {
-                cells[h][w].ceiling = CellWall::None;
+            if !open_ceiling {
+                let mut y_plus_1_rng = vertical_pair_rng(seed, (x, y, z), (x, y + 1, z), (w, h));
+                if y_plus_1_rng.gen_bool(WALL_BREAK_PROB) {
+                    cells[h][w].ceiling = CellWall::None;
+                }
             }
         }
     }
 
+    // World structures define their own floors and ceilings,
+    // so match them if there is one directly above or below
+    if let Some(below) =
+        structure_chunk_from_xyz_seed(seed, world_config, dims, world_structures, x, y - 1, z)
+    {
+        for (row, below_row) in cells.iter_mut().zip(below.cells.iter()) {
+            for (cell, below_cell) in row.iter_mut().zip(below_row.iter()) {
+                cell.floor = below_cell.ceiling.clone();
+            }
+        }
+    }
+    if let Some(above) =
+        structure_chunk_from_xyz_seed(seed, world_config, dims, world_structures, x, y + 1, z)
+    {
+        for (row, above_row) in cells.iter_mut().zip(above.cells.iter()) {
+            for (cell, above_cell) in row.iter_mut().zip(above_row.iter()) {
+                cell.ceiling = above_cell.floor.clone();
+            }
+        }
+    }
+    open_to_sky(&mut cells, surface_level, y);
+
     let mut floored_cells: Vec<(usize, usize)> = Vec::new();
-    for h in 0..GRID_SIZE {
-        for w in 0..GRID_SIZE {
+    for h in 0..grid_size {
+        for w in 0..grid_size {
             if cells[h][w].floor == CellWall::Solid {
                 floored_cells.push((w, h));
             }
         }
     }
 
-    let rand_floored_cell = |r: &mut StdRng, fc: &mut Vec<(usize, usize)>| {
-        let i = r.gen_range(0..fc.len());
-        let (w, h) = fc[i];
-        fc.splice(i..i + 1, []);
-        (w, h)
-    };
+    let rand_floored_cell =
+        |r: &mut StdRng,
+         fc: &mut Vec<(usize, usize)>,
+         allowed: &dyn Fn(&(usize, usize)) -> bool| {
+            let indexes: Vec<usize> = (0..fc.len()).filter(|i| allowed(&fc[*i])).collect();
+            if indexes.is_empty() {
+                return None;
+            }
+            Some(fc.remove(indexes[r.gen_range(0..indexes.len())]))
+        };
+
+    // Difficulty only scales the probabilities, so the same seed and difficulty
+    // always generate the same chunk
+    let spawn_prob_multiplier = difficulty.modifiers().spawn_prob;
 
     for spec in CellSpecial::iter() {
         if floored_cells.is_empty() {
             break;
         }
 
-        if rng.gen_bool(spec.spawn_prob()) {
-            let (w, h) = rand_floored_cell(&mut rng, &mut floored_cells);
-            cells[h][w].special = spec;
+        if rng.gen_bool((spec.spawn_prob() * spawn_prob_multiplier).min(1.0)) {
+            // Hazards are kept off of entrance cells, so that walking
+            // into a chunk never means walking straight into a hazard
+            let allowed =
+                |cell: &(usize, usize)| !spec.is_hazard() || !is_entrance_cell(grid_size, *cell);
+            if let Some((w, h)) = rand_floored_cell(&mut rng, &mut floored_cells, &allowed) {
+                cells[h][w].special = spec;
+            }
         }
     }
 
-    let search_radius = WorldStructureName::max_radius() as i64 - 1;
+    Chunk {
+        x,
+        y,
+        z,
+        cells,
+        world_structure: NO_WORLD_STRUCTURE.to_owned(),
+    }
+}
+
+// Chunks on the surface level have no ceilings, and the ones above it have
+// no floors to match the open ceilings below them
+pub fn open_to_sky(cells: &mut [Vec<Cell>], surface_level: Option<i64>, y: i64) {
+    let open_floor = is_surface_level(surface_level, y - 1);
+    let open_ceiling = is_surface_level(surface_level, y);
+
+    for cell in cells.iter_mut().flatten() {
+        if open_floor {
+            cell.floor = CellWall::None;
+        }
+        if open_ceiling {
+            cell.ceiling = CellWall::None;
+        }
+    }
+}
+
+// Cells in the middle of each edge of a chunk, whose outer walls are always
+// knocked out to connect the chunk to its neighbors
+pub fn is_entrance_cell(grid_size: usize, (w, h): (usize, usize)) -> bool {
+    let mid = grid_size / 2;
+    (h == mid && (w == 0 || w == grid_size - 1)) || (w == mid && (h == 0 || h == grid_size - 1))
+}
+
+// The starting room at the origin chunk of worlds that have one, or else the chunk at x, y, z
+// if it is part of a world structure. The maze has no say in how either of them turns out.
+fn structure_chunk_from_xyz_seed(
+    seed: u32,
+    world_config: &WorldConfig,
+    dims: &WorldDims,
+    world_structures: &WorldStructureRegistry,
+    x: i64,
+    y: i64,
+    z: i64,
+) -> Option<Chunk> {
+    if world_config.starting_room && (x, y, z) == (0, 0, 0) {
+        if let Some(chunk) = world_structures
+            .starting_room(dims.grid_size())
+            .and_then(|ws| ws.gen_origin_chunk(x, y, z))
+        {
+            return Some(chunk);
+        }
+    }
+
+    world_structure_chunk_from_xyz_seed(seed, world_structures, x, y, z)
+}
+
+// Returns the chunk at x, y, z if it is part of a world structure,
+// either originating in this chunk or in one of the surrounding chunks
+pub fn world_structure_chunk_from_xyz_seed(
+    seed: u32,
+    world_structures: &WorldStructureRegistry,
+    x: i64,
+    y: i64,
+    z: i64,
+) -> Option<Chunk> {
+    let min_origin_dist = world_structures.min_origin_dist();
+    let mut rolls = StructureRolls::new(seed);
+
+    if structure_origin_allowed(&mut rolls, x, y, z, min_origin_dist) {
+        let mut rng = rng_from_xyz_seed(seed, x, y, z);
+        if let Some(chunk) = world_structures
+            .choose(&mut rng)
+            .and_then(|ws| ws.gen_origin_chunk(x, y, z))
+        {
+            return Some(chunk);
+        }
+    }
+
+    let search_radius = world_structures.max_radius() as i64 - 1;
     if search_radius > 0 {
         // Reach out on all sides equal to max world structure radius
         // to see if any surrounding chunks have world structures.
@@ -323,14 +1224,17 @@ pub fn chunk_from_xyz_seed(seed: u32, x: i64, y: i64, z: i64) -> Chunk {
                         continue;
                     }
 
-                    if chunk_has_world_structure(seed, _x, _y, _z) {
-                        let ws_chunk = chunk_from_xyz_seed(seed, _x, _y, _z);
-                        let ws_chunks = ws_chunk.world_structure.gen_chunks(_x, _y, _z);
+                    if structure_origin_allowed(&mut rolls, _x, _y, _z, min_origin_dist) {
+                        let mut rng = rng_from_xyz_seed(seed, _x, _y, _z);
+                        let Some(ws) = world_structures.choose(&mut rng) else {
+                            continue;
+                        };
+                        let ws_chunks = ws.gen_chunks(_x, _y, _z);
 
                         if let Some(ch) =
                             ws_chunks.iter().find(|c| c.x == x && c.y == y && c.z == z)
                         {
-                            return ch.clone();
+                            return Some(ch.clone());
                         }
                     }
                 }
@@ -338,24 +1242,122 @@ pub fn chunk_from_xyz_seed(seed: u32, x: i64, y: i64, z: i64) -> Chunk {
         }
     }
 
-    Chunk {
-        x,
-        y,
-        z,
-        cells,
-        world_structure: WorldStructureName::None,
+    None
+}
+
+// Center of the cell closest to spawn_position that the player can safely spawn in,
+// meaning it has a floor and nothing (a chest, stairs, etc.) is taking up the space.
+// If the whole chunk is unsafe the player would fall through it anyway,
+// so the chunks below it are searched next.
+// Keeps the same height above the floor as spawn_position.
+pub fn safe_spawn_position(
+    seed: u32,
+    difficulty: Difficulty,
+    world_config: &WorldConfig,
+    dims: &WorldDims,
+    world_structures: &WorldStructureRegistry,
+    spawn_position: Vec3,
+) -> Vec3 {
+    safe_spawn_position_with(dims, spawn_position, |(x, y, z)| {
+        chunk_from_xyz_seed(
+            seed,
+            difficulty,
+            world_config,
+            dims,
+            world_structures,
+            x,
+            y,
+            z,
+        )
+    })
+}
+
+// Same as safe_spawn_position, with the chunks searched coming from gen_chunk
+pub fn safe_spawn_position_with(
+    dims: &WorldDims,
+    spawn_position: Vec3,
+    mut gen_chunk: impl FnMut((i64, i64, i64)) -> Chunk,
+) -> Vec3 {
+    let (x, spawn_y, z) = ChunkCellMarker::from_global_transform(
+        &GlobalTransform::from_translation(spawn_position),
+        dims,
+    )
+    .chunk_xyz();
+    let height = spawn_position.y - chunk_world_position(dims, (x, spawn_y, z)).y;
+    let dist = |position: &Vec3| {
+        Vec2::new(position.x - spawn_position.x, position.z - spawn_position.z).length()
+    };
+
+    for y in (spawn_y - SAFE_SPAWN_MAX_DEPTH..=spawn_y).rev() {
+        let chunk = gen_chunk((x, y, z));
+
+        let closest = chunk
+            .cells
+            .iter()
+            .enumerate()
+            .flat_map(|(cell_z, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(move |(cell_x, cell)| ((cell_x, cell_z), cell))
+            })
+            .filter(|(_, cell)| cell.floor == CellWall::Solid && cell.special == CellSpecial::None)
+            .map(|(cell_xz, _)| cell_world_position(dims, (x, y, z), cell_xz))
+            .min_by(|a, b| dist(a).total_cmp(&dist(b)));
+
+        if let Some(position) = closest {
+            return position + Vec3::Y * height;
+        }
     }
+
+    spawn_position
 }
 
+// Waypoints from the center of one cell to another, for enemies to follow through the maze.
+// Chunks along the way are generated the same way as when they are spawned, mutations included,
+// and only chunks within max_chunk_radius of the start are searched.
+pub fn find_nav_path(
+    seed: u32,
+    difficulty: Difficulty,
+    world_config: &WorldConfig,
+    dims: &WorldDims,
+    world_structures: &WorldStructureRegistry,
+    world_data: &WorldData,
+    start: &ChunkCellMarker,
+    goal: &ChunkCellMarker,
+    max_chunk_radius: u32,
+) -> Option<Vec<Vec3>> {
+    let mut nav_graph = NavGraph::new(*dims, start.chunk_xyz(), max_chunk_radius, |(x, y, z)| {
+        let mut chunk = chunk_from_xyz_seed(
+            seed,
+            difficulty,
+            world_config,
+            dims,
+            world_structures,
+            x,
+            y,
+            z,
+        );
+        world_data.apply_cell_mutations(&mut chunk);
+        chunk
+    });
+    nav_graph.find_path(start, goal)
+}
+
+// Lazy, so that callers can stop early. Render distances of 0 give no chunks at all.
 pub fn make_nei_chunks_xyz(
     chunk: (i64, i64, i64),
     x_rend_dist: u32,
     y_rend_dist: u32,
     z_rend_dist: u32,
-) -> Vec<(i64, i64, i64)> {
-    if x_rend_dist == 0 || y_rend_dist == 0 || z_rend_dist == 0 {
-        return Vec::new();
-    }
+) -> impl Iterator<Item = (i64, i64, i64)> {
+    let extent = |rend_dist: u32| (rend_dist as u64 * 2).saturating_sub(1);
+    debug_assert!(
+        extent(x_rend_dist) * extent(y_rend_dist) * extent(z_rend_dist) <= MAX_NEI_CHUNKS,
+        "render distance of ({}, {}, {}) is past MAX_CHUNK_RENDER_DIST",
+        x_rend_dist,
+        y_rend_dist,
+        z_rend_dist,
+    );
 
     let (x, y, z) = chunk;
 
@@ -363,16 +1365,85 @@ pub fn make_nei_chunks_xyz(
     let y_r = y_rend_dist as i64 - 1;
     let z_r = z_rend_dist as i64 - 1;
 
-    (x - x_r..=x + x_r)
-        .flat_map(|i| {
-            (y - y_r..=y + y_r).flat_map(move |j| (z - z_r..=z + z_r).map(move |k| (i, j, k)))
-        })
-        .collect()
+    (x - x_r..=x + x_r).flat_map(move |i| {
+        (y - y_r..=y + y_r).flat_map(move |j| (z - z_r..=z + z_r).map(move |k| (i, j, k)))
+    })
 }
 
-fn chunk_has_world_structure(seed: u32, x: i64, y: i64, z: i64) -> bool {
-    let mut rng = rng_from_xyz_seed(seed, x, y, z);
-    rng.gen_bool(WORLD_STRUCTURE_GEN_PROB)
+// Rng shared by the floor of a cell and the ceiling of the cell directly below it,
+// so both chunks agree on whether there is a hole between them. The chunks are put
+// into a canonical (lower, upper) order, so the order they are passed in does not matter.
+pub fn vertical_pair_rng(
+    seed: u32,
+    lower_chunk: (i64, i64, i64),
+    upper_chunk: (i64, i64, i64),
+    cell: (usize, usize),
+) -> StdRng {
+    let (lower_chunk, upper_chunk) = if lower_chunk.1 <= upper_chunk.1 {
+        (lower_chunk, upper_chunk)
+    } else {
+        (upper_chunk, lower_chunk)
+    };
+
+    let (w, h) = cell;
+    rng_from_str(seed_str_from_neis(
+        seed,
+        (lower_chunk.0, lower_chunk.1, lower_chunk.2, w, h),
+        (upper_chunk.0, upper_chunk.1, upper_chunk.2, w, h),
+    ))
+}
+
+// Rng shared by two adjacent cells of the same chunk, used to decide whether the wall
+// between them is knocked out. Like vertical_pair_rng, the cells are put into a
+// canonical order, so the result is the same no matter which cell asks.
+pub fn horizontal_pair_rng(
+    seed: u32,
+    chunk: (i64, i64, i64),
+    cell_a: (usize, usize),
+    cell_b: (usize, usize),
+) -> StdRng {
+    let (cell_a, cell_b) = if (cell_a.1, cell_a.0) <= (cell_b.1, cell_b.0) {
+        (cell_a, cell_b)
+    } else {
+        (cell_b, cell_a)
+    };
+
+    let (x, y, z) = chunk;
+    rng_from_str(seed_str_from_neis(
+        seed,
+        (x, y, z, cell_a.0, cell_a.1),
+        (x, y, z, cell_b.0, cell_b.1),
+    ))
+}
+
+// Occasionally knocks out the wall between two adjacent cells of a maze.
+// Only walls shared by two cells of the chunk are considered, so the outer boundary
+// is left untouched for the entrance logic to handle.
+pub fn widen_passages(seed: u32, chunk: (i64, i64, i64), cells: &mut [Vec<Cell>], prob: f64) {
+    let height = cells.len();
+    let width = cells.first().map_or(0, |row| row.len());
+
+    for h in 0..height {
+        for w in 0..width {
+            // Right neighbor
+            if w + 1 < width
+                && cells[h][w].wall_right != CellWall::None
+                && horizontal_pair_rng(seed, chunk, (w, h), (w + 1, h)).gen_bool(prob)
+            {
+                cells[h][w].wall_right = CellWall::None;
+                cells[h][w + 1].wall_left = CellWall::None;
+            }
+
+            // Bottom neighbor
+            if h + 1 < height
+                && cells[h][w].wall_bottom != CellWall::None
+                && horizontal_pair_rng(seed, chunk, (w, h), (w, h + 1)).gen_bool(prob)
+            {
+                cells[h][w].wall_bottom = CellWall::None;
+                cells[h + 1][w].wall_top = CellWall::None;
+            }
+        }
+    }
 }
 
 fn seed_str_from_neis(
@@ -0,0 +1,128 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use bevy_rapier3d::prelude::*;
+use dungeon_maze_common::{
+    settings::{GameSettings, RenderDistChanged},
+    world::{ActiveChunk, ChunkMarker},
+};
+
+// Windows block the player, but not thrown items or weapon swings, which reach the glass of
+// a window through a sensor of its own instead. Everything else is in every group.
+pub const WINDOW_GROUP: Group = Group::GROUP_2;
+pub const PROJECTILE_GROUP: Group = Group::GROUP_3;
+pub const WEAPON_GROUP: Group = Group::GROUP_4;
+
+pub fn window_collision_groups() -> CollisionGroups {
+    CollisionGroups::new(
+        WINDOW_GROUP,
+        Group::ALL.difference(PROJECTILE_GROUP.union(WEAPON_GROUP)),
+    )
+}
+
+pub fn projectile_collision_groups() -> CollisionGroups {
+    CollisionGroups::new(PROJECTILE_GROUP, Group::ALL.difference(WINDOW_GROUP))
+}
+
+pub fn weapon_collision_groups() -> CollisionGroups {
+    CollisionGroups::new(WEAPON_GROUP, Group::ALL.difference(WINDOW_GROUP))
+}
+
+// Collider of an entity in a chunk outside of physics distance, kept around
+// so that it can be put back as is once the chunk comes back into it
+#[derive(Component)]
+pub struct InactiveCollider(pub Collider);
+
+// Chunks outside of physics distance, whose colliders were set aside
+#[derive(Component)]
+pub struct PhysicsInactiveChunk;
+
+fn deactivate_collider(
+    entity_commands: &mut EntityCommands,
+    collider: &Collider,
+    rigid_body: Option<&RigidBody>,
+) {
+    entity_commands
+        .remove::<Collider>()
+        .insert(InactiveCollider(collider.clone()));
+    // Otherwise items lying around would fall through the floor
+    if rigid_body == Some(&RigidBody::Dynamic) {
+        entity_commands.insert(RigidBodyDisabled);
+    }
+}
+
+// Sets aside the colliders of chunks that leave physics distance,
+// and puts them back on the ones that come back into it
+pub fn update_chunk_physics(
+    mut commands: Commands,
+    ac_event_reader: EventReader<StateTransitionEvent<ActiveChunk>>,
+    rd_event_reader: EventReader<RenderDistChanged>,
+    chunks_query: Query<(Entity, &ChunkMarker, Has<PhysicsInactiveChunk>)>,
+    children_query: Query<&Children>,
+    collider_query: Query<(&Collider, Option<&RigidBody>)>,
+    inactive_collider_query: Query<&InactiveCollider>,
+    active_chunk: Res<State<ActiveChunk>>,
+    game_settings: Res<State<GameSettings>>,
+) {
+    // Events are left unread, like in update_spawned_chunks, so that this runs again the frame
+    // after the distance changes, once the new settings have been applied
+    if ac_event_reader.is_empty() && rd_event_reader.is_empty() {
+        return;
+    }
+
+    let physics_dist = game_settings.physics_dist();
+
+    for (chunk_entity, chunk_marker, is_inactive) in chunks_query.iter() {
+        let in_range = physics_dist.covers(active_chunk.to_tuple(), chunk_marker.0);
+
+        if in_range && is_inactive {
+            commands
+                .entity(chunk_entity)
+                .remove::<PhysicsInactiveChunk>();
+            for entity in children_query.iter_descendants(chunk_entity) {
+                if let Ok(inactive_collider) = inactive_collider_query.get(entity) {
+                    commands
+                        .entity(entity)
+                        .remove::<(InactiveCollider, RigidBodyDisabled)>()
+                        .insert(inactive_collider.0.clone());
+                }
+            }
+        } else if !in_range && !is_inactive {
+            commands.entity(chunk_entity).insert(PhysicsInactiveChunk);
+            for entity in children_query.iter_descendants(chunk_entity) {
+                if let Ok((collider, rigid_body)) = collider_query.get(entity) {
+                    deactivate_collider(&mut commands.entity(entity), collider, rigid_body);
+                }
+            }
+        }
+    }
+}
+
+// Colliders that are spawned into chunks outside of physics distance, along with the chunk
+// or later on, are set aside before rapier ever sees them
+pub fn deactivate_new_colliders(
+    mut commands: Commands,
+    collider_query: Query<(Entity, &Collider, Option<&RigidBody>), Added<Collider>>,
+    parent_query: Query<&Parent>,
+    chunk_query: Query<(&ChunkMarker, Has<PhysicsInactiveChunk>)>,
+    active_chunk: Res<State<ActiveChunk>>,
+    game_settings: Res<State<GameSettings>>,
+) {
+    let physics_dist = game_settings.physics_dist();
+
+    for (entity, collider, rigid_body) in collider_query.iter() {
+        let chunk = parent_query
+            .iter_ancestors(entity)
+            .find_map(|ancestor| Some((ancestor, chunk_query.get(ancestor).ok()?)));
+        let Some((chunk_entity, (chunk_marker, is_inactive))) = chunk else {
+            continue;
+        };
+
+        if physics_dist.covers(active_chunk.to_tuple(), chunk_marker.0) {
+            continue;
+        }
+
+        deactivate_collider(&mut commands.entity(entity), collider, rigid_body);
+        if !is_inactive {
+            commands.entity(chunk_entity).insert(PhysicsInactiveChunk);
+        }
+    }
+}
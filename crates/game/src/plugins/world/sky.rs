@@ -0,0 +1,44 @@
+use bevy::{pbr::light_consts::lux, prelude::*};
+use dungeon_maze_common::world::{is_surface_level, ActiveChunk, SkyLight, WorldConfig};
+
+const SKY_COLOR: Color = Color::srgb(0.5, 0.72, 0.95);
+
+// The sky is only shown while the active chunk is at or above the surface level,
+// since the levels below it are enclosed and would only be lit through holes in the ceiling
+pub fn update_sky(
+    mut commands: Commands,
+    sky_light_query: Query<Entity, With<SkyLight>>,
+    active_chunk: Res<State<ActiveChunk>>,
+    world_config: Res<WorldConfig>,
+) {
+    let on_surface = is_surface_level(world_config.surface_level, active_chunk.1);
+
+    match (on_surface, sky_light_query.get_single()) {
+        (true, Err(_)) => {
+            commands.spawn((
+                DirectionalLightBundle {
+                    directional_light: DirectionalLight {
+                        illuminance: lux::OVERCAST_DAY,
+                        shadows_enabled: true,
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(0.0, 1.0, 0.0)
+                        .looking_at(Vec3::new(0.4, 0.0, 0.3), Vec3::Y),
+                    ..default()
+                },
+                SkyLight,
+                Name::new("Sky Light"),
+            ));
+            commands.insert_resource(ClearColor(SKY_COLOR));
+        }
+        (false, Ok(_)) => remove_sky(commands, sky_light_query),
+        _ => (),
+    }
+}
+
+pub fn remove_sky(mut commands: Commands, sky_light_query: Query<Entity, With<SkyLight>>) {
+    for entity in sky_light_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.insert_resource(ClearColor::default());
+}
@@ -0,0 +1,2564 @@
+use crate::plugins::{
+    player::PlayerPlugin,
+    world::{
+        bundle::{
+            cell::{
+                calc_cell_translation, cell_world_position, floor_color, wall_base_color,
+                wall_texture_path,
+            },
+            chunk::{chunk_world_position, spawn_chunk_bundle},
+            decor::gen_cell_decor,
+            door::spawn_door_bundle,
+            item::spawn_item_bundle,
+            particle::gen_cell_particle_emitter,
+            special::{
+                treasure_chest_lock, treasure_chest_loot, LOCKED_TREASURE_CHEST_PROB,
+                TREASURE_CHEST_MAX_ANIMATION, TREASURE_CHEST_MIN_ANIMATION,
+                TREASURE_CHEST_OPEN_ANIMATION,
+            },
+            window::{spawn_window_bundle, WindowStyle, WINDOW_PANE_HEALTH},
+        },
+        chunk_from_xyz_seed, find_nav_path, horizontal_pair_rng, is_entrance_cell,
+        make_nei_chunks_xyz,
+        physics::{
+            projectile_collision_groups, weapon_collision_groups, window_collision_groups,
+            InactiveCollider, PhysicsInactiveChunk,
+        },
+        safe_spawn_position, safe_spawn_position_with, vertical_pair_rng, widen_passages,
+        world_structure_chunk_from_xyz_seed, WorldPlugin,
+    },
+};
+use bevy::{
+    ecs::system::{RunSystemOnce, SystemState},
+    prelude::*,
+    scene::ScenePlugin,
+    state::app::StatesPlugin,
+    tasks::{futures_lite::future, AsyncComputeTaskPool},
+    time::TimeUpdateStrategy,
+    utils::{HashMap, HashSet},
+};
+use bevy_rapier3d::{
+    prelude::{Collider, CollisionGroups, NoUserData, RapierPhysicsPlugin},
+    rapier::geometry::InteractionGroups,
+};
+use dungeon_maze_common::{
+    animation::{CyclicAnimation, CyclicAnimationCycled, PlayerAnimation},
+    app_state::{AppState, GameLoaded},
+    camera::MainCamera,
+    interaction::{Interactable, InteractableKind, PendingInteractionExecuted},
+    inventory::{
+        item::{Item, ItemBillboard, ItemModel, ItemName, ItemType},
+        DroppedItemPickedUp, Inventory, InventoryChanged, ItemRemovedFromOCItemContainer,
+        PlayerDroppedItem,
+    },
+    menu::MenuOpen,
+    player::{DmgType, Player, TakeDamage},
+    save::WorldDataChanged,
+    settings::{
+        ChunkPhysicsDist, ChunkRenderDist, DecorDensity, Difficulty, GameSettings,
+        RenderDistChanged, MAX_CHUNK_RENDER_DIST,
+    },
+    utils::{
+        maze::maze_from_rng,
+        noise::noise_at_world_pos,
+        rng::{rng_from_str, rng_from_xyz_seed},
+    },
+    world::{
+        data::WorldData,
+        nearest_structure_chunk,
+        particle::{DripRing, DripRingHandles, ParticleEmitter, ParticleKind},
+        world_structure::{
+            parse_world_structures, WorldStructureRegistry, NO_WORLD_STRUCTURE, STARTING_ROOM,
+        },
+        ActiveChunk, AutoClose, Cell, CellMutated, CellMutation, CellSpecial, CellWall,
+        CellWallSide, Chunk, ChunkCellMarker, ChunkDespawned, ChunkIndex, ChunkMarker,
+        ChunkSpawned, ContainerUnlocked, CyclicTransform, Decor, DroppedItem, Locked,
+        OCItemContainer, PendingChunks, Railing, Side, SkyLight, WindowPane, WorldConfig,
+        WorldDims, WorldSeed, DEFAULT_CELL_SIZE, DEFAULT_CHUNK_SIZE, DEFAULT_SURFACE_LEVEL,
+    },
+};
+use rand::Rng;
+use std::time::Duration;
+use strum::IntoEnumIterator;
+
+const CHUNK_SIZE: f32 = DEFAULT_CHUNK_SIZE;
+const CELL_SIZE: f32 = DEFAULT_CELL_SIZE;
+const GRID_SIZE: usize = (CHUNK_SIZE / CELL_SIZE) as usize;
+
+fn new_test_app() -> App {
+    new_test_app_with_dims(WorldDims::default())
+}
+
+fn new_test_app_with_dims(dims: WorldDims) -> App {
+    let mut app = App::new();
+
+    app.insert_resource(dims)
+        .add_plugins((
+            MinimalPlugins,
+            AssetPlugin {
+                watch_for_changes_override: Some(false),
+                ..default()
+            },
+            TransformPlugin,
+            HierarchyPlugin,
+            StatesPlugin,
+            WorldPlugin,
+        ))
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>()
+        .init_asset::<Image>()
+        .init_asset::<Scene>()
+        .init_resource::<WorldData>()
+        .init_resource::<WorldSeed>()
+        // Tests look for seeds with something in particular at the origin chunk
+        .insert_resource(WorldConfig {
+            starting_room: false,
+            ..default()
+        })
+        .init_state::<GameSettings>()
+        .insert_state(AppState::InGame)
+        .add_computed_state::<GameLoaded>()
+        .add_event::<PendingInteractionExecuted>()
+        .add_event::<PlayerDroppedItem>()
+        .add_event::<DroppedItemPickedUp>()
+        .add_event::<ItemRemovedFromOCItemContainer>()
+        .add_event::<CyclicAnimationCycled>()
+        .add_event::<RenderDistChanged>()
+        .add_event::<WorldDataChanged>();
+
+    app.world_mut().spawn((
+        Player,
+        SpatialBundle::from_transform(Transform::from_xyz(2.0, 1.0, 2.0)),
+    ));
+
+    app
+}
+
+// Every world structure in the assets folder, same as the game loads
+pub fn world_structures() -> WorldStructureRegistry {
+    WorldStructureRegistry::from_dir(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../../assets/world_structures"),
+        GRID_SIZE,
+    )
+    .unwrap()
+}
+
+// Chunks are generated in the background, so every frame waits for the chunks that are
+// being generated to finish first, to spawn them in the same frame every time
+fn update_n(app: &mut App, n: usize) {
+    for _ in 0..n {
+        while app
+            .world()
+            .resource::<PendingChunks>()
+            .0
+            .values()
+            .any(|task| !task.is_finished())
+        {
+            std::thread::yield_now();
+        }
+        app.update();
+    }
+}
+
+fn move_player(app: &mut App, translation: Vec3) {
+    let world = app.world_mut();
+    let mut query = world.query_filtered::<&mut Transform, With<Player>>();
+    query.single_mut(world).translation = translation;
+}
+
+fn dropped_item_translations(app: &mut App) -> Vec<Vec3> {
+    let world = app.world_mut();
+    let mut query = world.query_filtered::<&GlobalTransform, With<DroppedItem>>();
+    query.iter(world).map(|gt| gt.translation()).collect()
+}
+
+fn set_render_dist(app: &mut App, render_dist: u32) {
+    let mut game_settings = *app.world().resource::<State<GameSettings>>().get();
+    game_settings.chunk_render_dist = ChunkRenderDist(render_dist, render_dist, render_dist);
+
+    app.world_mut()
+        .resource_mut::<NextState<GameSettings>>()
+        .set(game_settings);
+    app.world_mut().send_event(RenderDistChanged);
+}
+
+fn chunk_entities(app: &mut App) -> HashMap<(i64, i64, i64), Entity> {
+    let world = app.world_mut();
+    let mut query = world.query::<(Entity, &ChunkMarker)>();
+    query
+        .iter(world)
+        .map(|(entity, chunk_marker)| (chunk_marker.0, entity))
+        .collect()
+}
+
+#[test]
+fn test_active_chunk_is_reflected() {
+    let app = new_test_app();
+    let type_registry = app.world().resource::<AppTypeRegistry>().read();
+
+    assert!(type_registry.contains(std::any::TypeId::of::<ActiveChunk>()));
+    assert!(type_registry.contains(std::any::TypeId::of::<State<ActiveChunk>>()));
+}
+
+#[test]
+fn test_dropped_item_restored_after_chunk_respawn() {
+    let mut app = new_test_app();
+    update_n(&mut app, 3);
+
+    let drop_translation = Vec3::new(2.0, 1.0, 2.0);
+    app.world_mut()
+        .send_event(PlayerDroppedItem(Item::new(ItemName::Coal, 3)));
+    update_n(&mut app, 3);
+
+    assert_eq!(dropped_item_translations(&mut app), vec![drop_translation]);
+    assert_eq!(app.world().resource::<WorldData>().dropped_items_count(), 1);
+
+    // Walk two chunks away, so the chunk the item was dropped in is despawned
+    move_player(&mut app, drop_translation + Vec3::X * CHUNK_SIZE * 2.0);
+    update_n(&mut app, 5);
+
+    assert!(dropped_item_translations(&mut app).is_empty());
+
+    // Walk back, so the chunk is spawned again
+    move_player(&mut app, drop_translation);
+    update_n(&mut app, 5);
+
+    let translations = dropped_item_translations(&mut app);
+    assert_eq!(translations.len(), 1);
+    assert!(translations[0].distance(drop_translation) < 0.001);
+}
+
+// Seed that generates a treasure chest in the first chunk, holding more than one item
+// so that some of it can be left over, along with the cell that the chest is in
+fn treasure_chest_seed(app: &App) -> (u32, ChunkCellMarker) {
+    find_treasure_chest_seed(app, |ccm| treasure_chest_loot(ccm, None).amt > 1)
+}
+
+// Seed with a chest in the origin chunk that is generated locked
+fn locked_treasure_chest_seed(app: &App) -> (u32, ChunkCellMarker) {
+    find_treasure_chest_seed(app, |ccm| treasure_chest_lock(ccm).is_some())
+}
+
+fn find_treasure_chest_seed(
+    app: &App,
+    predicate: impl Fn(&ChunkCellMarker) -> bool,
+) -> (u32, ChunkCellMarker) {
+    let difficulty = app.world().resource::<State<GameSettings>>().difficulty;
+    let world_config = *app.world().resource::<WorldConfig>();
+    let world_structures = app.world().resource::<WorldStructureRegistry>().clone();
+
+    (0..)
+        .find_map(|seed| {
+            let chunk = chunk_from_xyz_seed(
+                seed,
+                difficulty,
+                &world_config,
+                &WorldDims::default(),
+                &world_structures,
+                0,
+                0,
+                0,
+            );
+            chunk.cells.iter().enumerate().find_map(|(z, row)| {
+                row.iter().enumerate().find_map(|(x, cell)| {
+                    let ccm = ChunkCellMarker::new((0, 0, 0), (x, z));
+                    (cell.special == CellSpecial::TreasureChest && predicate(&ccm))
+                        .then_some((seed, ccm))
+                })
+            })
+        })
+        .unwrap()
+}
+
+// Chest in a cell, and the item inside of it
+fn treasure_chest(app: &mut App, ccm: &ChunkCellMarker) -> Option<(Entity, Option<Item>)> {
+    let world = app.world_mut();
+    let dims = *world.resource::<WorldDims>();
+    let chest = world
+        .query_filtered::<(Entity, &GlobalTransform), With<OCItemContainer>>()
+        .iter(world)
+        .find(|(_, gt)| ChunkCellMarker::from_global_transform(gt, &dims) == *ccm)
+        .map(|(entity, _)| entity)?;
+
+    let item = world.get::<Children>(chest).and_then(|children| {
+        children
+            .iter()
+            .find_map(|child| world.get::<Item>(*child).copied())
+    });
+    Some((chest, item))
+}
+
+#[test]
+fn test_treasure_chest_restored_after_chunk_respawn() {
+    let seed = treasure_chest_seed(&new_test_app()).0;
+
+    // Left alone, partly picked up, and fully picked up
+    for picked_up in [None, Some(false), Some(true)] {
+        let mut app = new_test_app();
+        app.insert_resource(WorldSeed(seed));
+        let ccm = treasure_chest_seed(&app).1;
+        update_n(&mut app, 3);
+
+        let (chest, generated) = treasure_chest(&mut app, &ccm).unwrap();
+        let generated = generated.unwrap();
+
+        let expected = match picked_up {
+            Some(fully) => {
+                let rem_item = (!fully).then(|| Item::new(generated.name, generated.amt - 1));
+                app.world_mut().send_event(ItemRemovedFromOCItemContainer {
+                    ccm: ccm.clone(),
+                    _item: generated,
+                    _entity: chest,
+                    rem_item,
+                });
+                rem_item
+            }
+            None => Some(generated),
+        };
+        update_n(&mut app, 2);
+
+        // Walk two chunks away, so the chunk the chest is in is despawned
+        let start = Vec3::new(2.0, 1.0, 2.0);
+        move_player(&mut app, start + Vec3::X * CHUNK_SIZE * 2.0);
+        update_n(&mut app, 5);
+        assert!(treasure_chest(&mut app, &ccm).is_none());
+
+        move_player(&mut app, start);
+        update_n(&mut app, 5);
+
+        let (_, item) = treasure_chest(&mut app, &ccm).unwrap();
+        assert_eq!(item, expected, "{:?}", picked_up);
+    }
+}
+
+#[test]
+fn test_treasure_chest_spawned_open_after_chunk_respawn() {
+    let seed = treasure_chest_seed(&new_test_app()).0;
+
+    for opened in [true, false] {
+        let mut app = new_test_app();
+        app.insert_resource(WorldSeed(seed));
+        let ccm = treasure_chest_seed(&app).1;
+        update_n(&mut app, 3);
+
+        // Open the chest, then close it again unless it is meant to be left open
+        let (chest, _) = treasure_chest(&mut app, &ccm).unwrap();
+        let cycles = if opened { 1 } else { 2 };
+        for clip in [TREASURE_CHEST_OPEN_ANIMATION, TREASURE_CHEST_MAX_ANIMATION]
+            .into_iter()
+            .take(cycles)
+        {
+            app.world_mut().send_event(CyclicAnimationCycled {
+                entity: chest,
+                clip,
+            });
+            update_n(&mut app, 1);
+        }
+
+        let world_data = app.world().resource::<WorldData>();
+        let cell_data = world_data.at_cell(ccm.chunk_xyz(), ccm.cell_xz()).unwrap();
+        assert_eq!(cell_data.chest_opened(), opened);
+
+        let start = Vec3::new(2.0, 1.0, 2.0);
+        move_player(&mut app, start + Vec3::X * CHUNK_SIZE * 2.0);
+        update_n(&mut app, 5);
+        move_player(&mut app, start);
+        update_n(&mut app, 5);
+
+        let (chest, _) = treasure_chest(&mut app, &ccm).unwrap();
+        assert_eq!(
+            app.world().get::<Interactable>(chest),
+            Some(&Interactable::new(InteractableKind::Container))
+        );
+        let cyclic_animation = app.world().get::<CyclicAnimation>(chest).unwrap();
+        let (value, rest_clip) = match opened {
+            true => (
+                TREASURE_CHEST_MAX_ANIMATION,
+                Some(TREASURE_CHEST_OPEN_ANIMATION),
+            ),
+            false => (TREASURE_CHEST_MIN_ANIMATION, None),
+        };
+        assert_eq!(cyclic_animation.value(), value);
+        assert_eq!(cyclic_animation.rest_clip(), rest_clip);
+
+        // Whatever is inside can be picked up straight away from an open chest
+        let children = app.world().get::<Children>(chest).unwrap();
+        let item = children
+            .iter()
+            .find(|child| app.world().get::<Item>(**child).is_some())
+            .unwrap();
+        assert_eq!(
+            app.world().get::<Interactable>(*item).is_some(),
+            opened,
+            "{}",
+            opened
+        );
+    }
+}
+
+#[test]
+fn test_treasure_chest_locks_are_deterministic() {
+    let ccms = (-4..4)
+        .flat_map(|chunk_x| (-4..4).map(move |chunk_z| (chunk_x, 0, chunk_z)))
+        .flat_map(|xyz| {
+            (0..GRID_SIZE)
+                .flat_map(move |x| (0..GRID_SIZE).map(move |z| ChunkCellMarker::new(xyz, (x, z))))
+        })
+        .collect::<Vec<_>>();
+
+    let locks = ccms.iter().map(treasure_chest_lock).collect::<Vec<_>>();
+    assert_eq!(
+        locks,
+        ccms.iter().map(treasure_chest_lock).collect::<Vec<_>>()
+    );
+    assert!(locks.iter().flatten().all(|locked| locked.tier == 1));
+
+    // Seeds from rng_from_str only take so many values, so it is only roughly the probability
+    let fraction = locks.iter().flatten().count() as f64 / locks.len() as f64;
+    assert!(
+        (fraction - LOCKED_TREASURE_CHEST_PROB).abs() < 0.1,
+        "{}",
+        fraction
+    );
+
+    // Keys turn up in some chests, one at a time
+    let keys = ccms
+        .iter()
+        .map(|ccm| treasure_chest_loot(ccm, None))
+        .filter(|item| item.name == ItemName::Key)
+        .collect::<Vec<_>>();
+    assert!(!keys.is_empty() && keys.len() < ccms.len() / 10);
+    assert!(keys.iter().all(|key| key.amt == 1));
+}
+
+#[test]
+fn test_unlocked_treasure_chest_stays_unlocked_after_chunk_respawn() {
+    let seed = locked_treasure_chest_seed(&new_test_app()).0;
+
+    for unlocked in [true, false] {
+        let mut app = new_test_app();
+        app.insert_resource(WorldSeed(seed));
+        let ccm = locked_treasure_chest_seed(&app).1;
+        update_n(&mut app, 3);
+
+        let (chest, _) = treasure_chest(&mut app, &ccm).unwrap();
+        assert_eq!(app.world().get::<Locked>(chest), Some(&Locked { tier: 1 }));
+
+        if unlocked {
+            app.world_mut().entity_mut(chest).remove::<Locked>();
+            app.world_mut().send_event(ContainerUnlocked(chest));
+            update_n(&mut app, 1);
+        }
+        let world_data = app.world().resource::<WorldData>();
+        let cell_data = world_data.at_cell(ccm.chunk_xyz(), ccm.cell_xz());
+        assert_eq!(cell_data.is_some_and(|cd| cd.chest_unlocked()), unlocked);
+
+        let start = Vec3::new(2.0, 1.0, 2.0);
+        move_player(&mut app, start + Vec3::X * CHUNK_SIZE * 2.0);
+        update_n(&mut app, 5);
+        move_player(&mut app, start);
+        update_n(&mut app, 5);
+
+        let (chest, _) = treasure_chest(&mut app, &ccm).unwrap();
+        assert_eq!(app.world().get::<Locked>(chest).is_none(), unlocked);
+    }
+}
+
+#[test]
+fn test_doors_windows_and_items_set_their_interactable_kind() {
+    let mut app = new_test_app();
+
+    let (door, window, item) = app.world_mut().run_system_once(
+        |mut commands: Commands,
+         asset_server: Res<AssetServer>,
+         mut meshes: ResMut<Assets<Mesh>>,
+         mut materials: ResMut<Assets<StandardMaterial>>,
+         world_data: Res<WorldData>| {
+            let door = commands.spawn_empty().id();
+            commands.entity(door).with_children(|parent| {
+                spawn_door_bundle(Side::Top, parent, &asset_server);
+            });
+            let window = commands.spawn_empty().id();
+            commands.entity(window).with_children(|parent| {
+                spawn_window_bundle(
+                    Side::Top,
+                    &ChunkCellMarker::default(),
+                    parent,
+                    &asset_server,
+                    &mut meshes,
+                    &mut materials,
+                    &world_data,
+                );
+            });
+            let item = spawn_item_bundle(
+                Item::new(ItemName::Coal, 1),
+                &mut commands,
+                &asset_server,
+                &mut meshes,
+                &mut materials,
+                None,
+                true,
+                true,
+                true,
+            )
+            .id();
+            (door, window, item)
+        },
+    );
+
+    let world = app.world_mut();
+    let child_kind = |world: &World, parent: Entity| {
+        world
+            .get::<Children>(parent)
+            .unwrap()
+            .iter()
+            .find_map(|child| world.get::<Interactable>(*child))
+            .map(|interactable| interactable.kind)
+    };
+    assert_eq!(child_kind(world, door), Some(InteractableKind::Door));
+    assert_eq!(child_kind(world, window), Some(InteractableKind::Window));
+    assert_eq!(
+        world
+            .get::<Interactable>(item)
+            .map(|interactable| interactable.kind),
+        Some(InteractableKind::Item)
+    );
+}
+
+fn cell_entities(app: &mut App) -> HashMap<ChunkCellMarker, (Entity, Cell)> {
+    let world = app.world_mut();
+    let mut query = world.query::<(Entity, &Cell, &ChunkCellMarker)>();
+    query
+        .iter(world)
+        .map(|(entity, cell, ccm)| (ccm.clone(), (entity, cell.clone())))
+        .collect()
+}
+
+fn wall_sides(app: &mut App, cell_entity: Entity) -> Vec<Side> {
+    let world = app.world_mut();
+    let mut query = world.query::<(&CellWallSide, &Parent)>();
+    query
+        .iter(world)
+        .filter(|(_, parent)| parent.get() == cell_entity)
+        .map(|(wall_side, _)| wall_side.0)
+        .collect()
+}
+
+#[test]
+fn test_cell_mutation_respawns_only_that_cell() {
+    let mut app = new_test_app();
+    update_n(&mut app, 3);
+
+    // Dropped items are parented to the cell they were dropped in
+    app.world_mut()
+        .send_event(PlayerDroppedItem(Item::new(ItemName::Coal, 3)));
+    update_n(&mut app, 3);
+
+    let ccm = ChunkCellMarker::from_global_transform(
+        &GlobalTransform::from_xyz(2.0, 1.0, 2.0),
+        &WorldDims::default(),
+    );
+    let cells_before = cell_entities(&mut app);
+    let (cell_entity, cell) = cells_before[&ccm].clone();
+
+    let side = [
+        (Side::Top, &cell.wall_top),
+        (Side::Bottom, &cell.wall_bottom),
+        (Side::Left, &cell.wall_left),
+        (Side::Right, &cell.wall_right),
+    ]
+    .into_iter()
+    .find(|(_, wall)| **wall != CellWall::None)
+    .map(|(side, _)| side)
+    .unwrap();
+    assert!(wall_sides(&mut app, cell_entity).contains(&side));
+
+    app.world_mut().send_event(CellMutated {
+        ccm: ccm.clone(),
+        mutation: CellMutation::from_side(side),
+    });
+    update_n(&mut app, 2);
+
+    let cells_after = cell_entities(&mut app);
+    assert_eq!(cells_before.len(), cells_after.len());
+    for (c, (entity, _)) in cells_after.iter() {
+        assert_eq!(*c == ccm, *entity != cells_before[c].0);
+    }
+
+    let (cell_entity, _) = cells_after[&ccm];
+    assert!(!wall_sides(&mut app, cell_entity).contains(&side));
+    assert_eq!(dropped_item_translations(&mut app).len(), 1);
+
+    // Walk two chunks away and back, so the chunk is generated and spawned again
+    move_player(
+        &mut app,
+        Vec3::new(2.0, 1.0, 2.0) + Vec3::X * CHUNK_SIZE * 2.0,
+    );
+    update_n(&mut app, 5);
+    move_player(&mut app, Vec3::new(2.0, 1.0, 2.0));
+    update_n(&mut app, 5);
+
+    let (cell_entity, cell) = cell_entities(&mut app)[&ccm].clone();
+    assert!(!wall_sides(&mut app, cell_entity).contains(&side));
+    let mut expected = cells_before[&ccm].1.clone();
+    CellMutation::from_side(side).apply(&mut expected);
+    assert_eq!(cell, expected);
+}
+
+#[test]
+fn test_chunk_events_sent_on_active_chunk_change() {
+    let mut app = new_test_app();
+    let mut spawned_reader = app.world().resource::<Events<ChunkSpawned>>().get_reader();
+    let mut despawned_reader = app
+        .world()
+        .resource::<Events<ChunkDespawned>>()
+        .get_reader();
+
+    update_n(&mut app, 3);
+
+    let spawned: Vec<(Entity, (i64, i64, i64))> = spawned_reader
+        .read(app.world().resource::<Events<ChunkSpawned>>())
+        .map(|event| (event.entity, event.xyz))
+        .collect();
+    assert_eq!(
+        spawned,
+        vec![(chunk_entities(&mut app)[&(0, 0, 0)], (0, 0, 0))]
+    );
+
+    // Walk into the next chunk over
+    move_player(&mut app, Vec3::new(2.0 + CHUNK_SIZE, 1.0, 2.0));
+    update_n(&mut app, 5);
+
+    let spawned: Vec<(Entity, (i64, i64, i64))> = spawned_reader
+        .read(app.world().resource::<Events<ChunkSpawned>>())
+        .map(|event| {
+            assert_eq!((event.chunk.x, event.chunk.y, event.chunk.z), event.xyz);
+            (event.entity, event.xyz)
+        })
+        .collect();
+    assert_eq!(
+        spawned,
+        vec![(chunk_entities(&mut app)[&(1, 0, 0)], (1, 0, 0))]
+    );
+
+    let despawned: Vec<(i64, i64, i64)> = despawned_reader
+        .read(app.world().resource::<Events<ChunkDespawned>>())
+        .map(|event| event.xyz)
+        .collect();
+    assert_eq!(despawned, vec![(0, 0, 0)]);
+}
+
+fn chunk_index(app: &App) -> HashMap<(i64, i64, i64), Entity> {
+    let chunk_index = app.world().resource::<ChunkIndex>();
+    chunk_index
+        .chunks()
+        .map(|xyz| (xyz, chunk_index.entity(xyz).unwrap()))
+        .collect()
+}
+
+#[test]
+fn test_chunk_index_follows_spawned_chunks() {
+    let mut app = new_test_app();
+    set_render_dist(&mut app, 2);
+    update_n(&mut app, 3);
+
+    assert_eq!(chunk_index(&app).len(), 27);
+    assert_eq!(chunk_index(&app), chunk_entities(&mut app));
+
+    move_player(&mut app, Vec3::new(2.0 + CHUNK_SIZE * 3.0, 1.0, 2.0));
+    update_n(&mut app, 5);
+
+    // Chunks left behind are gone from the index as soon as they are despawned
+    let index = chunk_index(&app);
+    assert_eq!(index, chunk_entities(&mut app));
+    assert!(!index.contains_key(&(0, 0, 0)));
+    assert!(index.contains_key(&(3, 0, 0)));
+    assert!(index
+        .values()
+        .all(|entity| app.world().get_entity(*entity).is_some()));
+
+    // And so is everything else once the game is left
+    app.world_mut()
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::MainMenu);
+    update_n(&mut app, 1);
+    assert!(chunk_index(&app).is_empty());
+}
+
+#[test]
+fn test_chunk_index_cell_entity() {
+    let mut app = new_test_app();
+    set_render_dist(&mut app, 2);
+    update_n(&mut app, 3);
+
+    let ccm = ChunkCellMarker {
+        chunk_x: 1,
+        chunk_y: 0,
+        chunk_z: -1,
+        x: 2,
+        z: 3,
+    };
+    let mut system_state: SystemState<(
+        Res<ChunkIndex>,
+        Query<&Children>,
+        Query<&ChunkCellMarker>,
+    )> = SystemState::new(app.world_mut());
+    let (chunk_index, children_query, ccm_query) = system_state.get(app.world());
+
+    let cell_entity = chunk_index
+        .cell_entity(&ccm, &children_query, &ccm_query)
+        .unwrap();
+    assert_eq!(ccm_query.get(cell_entity).ok(), Some(&ccm));
+    assert_eq!(
+        app.world()
+            .get::<Parent>(cell_entity)
+            .map(|parent| parent.get()),
+        chunk_index.entity((1, 0, -1))
+    );
+
+    // Outside of render distance
+    let far_ccm = ChunkCellMarker { chunk_x: 5, ..ccm };
+    assert_eq!(
+        chunk_index.cell_entity(&far_ccm, &children_query, &ccm_query),
+        None
+    );
+}
+
+#[test]
+fn test_generated_chunks_match_chunk_from_xyz_seed() {
+    let mut app = new_test_app();
+    update_n(&mut app, 3);
+    let mut spawned_reader = app.world().resource::<Events<ChunkSpawned>>().get_reader();
+
+    set_render_dist(&mut app, 2);
+
+    let seed = app.world().resource::<WorldSeed>().0;
+    let difficulty = app.world().resource::<State<GameSettings>>().difficulty;
+    let world_config = *app.world().resource::<WorldConfig>();
+    let world_structures = app.world().resource::<WorldStructureRegistry>().clone();
+    let mut spawned = HashSet::new();
+
+    for _ in 0..5 {
+        update_n(&mut app, 1);
+
+        for event in spawned_reader.read(app.world().resource::<Events<ChunkSpawned>>()) {
+            let (x, y, z) = event.xyz;
+            let chunk = chunk_from_xyz_seed(
+                seed,
+                difficulty,
+                &world_config,
+                &WorldDims::default(),
+                &world_structures,
+                x,
+                y,
+                z,
+            );
+            assert_eq!(event.chunk, chunk, "{:?}", event.xyz);
+            spawned.insert(event.xyz);
+        }
+    }
+
+    // Every chunk around the first one
+    let chunks = chunk_entities(&mut app);
+    assert_eq!(chunks.len(), 27);
+    for xyz in chunks.keys().filter(|xyz| **xyz != (0, 0, 0)) {
+        assert!(spawned.contains(xyz), "{:?}", xyz);
+    }
+    assert!(app.world().resource::<PendingChunks>().0.is_empty());
+}
+
+#[test]
+fn test_pending_chunks_cancelled_outside_render_dist() {
+    let mut app = new_test_app();
+    update_n(&mut app, 3);
+
+    // Never finishes, like a chunk that is still being generated
+    let far_chunk = (10, 0, 0);
+    let task = AsyncComputeTaskPool::get().spawn(future::pending());
+    app.world_mut()
+        .resource_mut::<PendingChunks>()
+        .0
+        .insert(far_chunk, task);
+
+    app.world_mut().send_event(RenderDistChanged);
+    app.update();
+
+    assert!(!app
+        .world()
+        .resource::<PendingChunks>()
+        .0
+        .contains_key(&far_chunk));
+    assert!(!chunk_entities(&mut app).contains_key(&far_chunk));
+}
+
+#[test]
+fn test_active_chunk_follows_camera_while_spectating() {
+    let mut app = new_test_app();
+    app.world_mut().spawn((
+        MainCamera,
+        SpatialBundle::from_transform(Transform::from_xyz(CHUNK_SIZE * 3.0, 1.0, 0.0)),
+    ));
+    update_n(&mut app, 3);
+
+    assert_eq!(
+        *app.world().resource::<State<ActiveChunk>>().get(),
+        ActiveChunk(0, 0, 0)
+    );
+
+    app.world_mut()
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Spectating);
+    update_n(&mut app, 3);
+
+    assert_eq!(
+        *app.world().resource::<State<ActiveChunk>>().get(),
+        ActiveChunk(3, 0, 0)
+    );
+
+    app.world_mut()
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::InGame);
+    update_n(&mut app, 3);
+
+    assert_eq!(
+        *app.world().resource::<State<ActiveChunk>>().get(),
+        ActiveChunk(0, 0, 0)
+    );
+}
+
+#[test]
+fn test_vertical_pair_rng_order_independent() {
+    for seed in 0..20 {
+        let lower = (seed as i64 - 5, -3, 7);
+        let upper = (seed as i64 - 5, -2, 7);
+        let cell = (seed as usize % GRID_SIZE, 1);
+
+        let a: u64 = vertical_pair_rng(seed, lower, upper, cell).gen();
+        let b: u64 = vertical_pair_rng(seed, upper, lower, cell).gen();
+        assert_eq!(a, b);
+    }
+}
+
+#[test]
+fn test_horizontal_pair_rng_order_independent() {
+    for seed in 0..20 {
+        let chunk = (seed as i64 - 5, 2, -4);
+        let cell = (seed as usize % (GRID_SIZE - 1), 1);
+        let right = (cell.0 + 1, cell.1);
+        let below = (cell.0, cell.1 + 1);
+
+        let a: u64 = horizontal_pair_rng(seed, chunk, cell, right).gen();
+        let b: u64 = horizontal_pair_rng(seed, chunk, right, cell).gen();
+        assert_eq!(a, b);
+
+        let a: u64 = horizontal_pair_rng(seed, chunk, cell, below).gen();
+        let b: u64 = horizontal_pair_rng(seed, chunk, below, cell).gen();
+        assert_eq!(a, b);
+    }
+}
+
+#[test]
+fn test_widen_passages_leaves_boundary_walls() {
+    let last = GRID_SIZE - 1;
+
+    for seed in 0..10 {
+        let chunk = (seed as i64, 0, -(seed as i64));
+        let maze = maze_from_rng(&mut rng_from_xyz_seed(seed, 0, 0, 0), GRID_SIZE, GRID_SIZE);
+
+        // Every shared wall is knocked out, and no boundary wall is
+        let mut cells = maze.clone();
+        widen_passages(seed, chunk, &mut cells, 1.0);
+
+        for h in 0..GRID_SIZE {
+            for w in 0..GRID_SIZE {
+                let (cell, original) = (&cells[h][w], &maze[h][w]);
+
+                for (wall, original_wall, is_boundary) in [
+                    (&cell.wall_top, &original.wall_top, h == 0),
+                    (&cell.wall_bottom, &original.wall_bottom, h == last),
+                    (&cell.wall_left, &original.wall_left, w == 0),
+                    (&cell.wall_right, &original.wall_right, w == last),
+                ] {
+                    if is_boundary {
+                        assert_eq!(wall, original_wall);
+                    } else {
+                        assert_eq!(*wall, CellWall::None);
+                    }
+                }
+            }
+        }
+
+        // Both sides of every shared wall agree
+        let mut cells = maze.clone();
+        widen_passages(seed, chunk, &mut cells, 0.5);
+
+        for h in 0..GRID_SIZE {
+            for w in 0..GRID_SIZE {
+                if w < last {
+                    assert_eq!(cells[h][w].wall_right, cells[h][w + 1].wall_left);
+                }
+                if h < last {
+                    assert_eq!(cells[h][w].wall_bottom, cells[h + 1][w].wall_top);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_widen_passages_deterministic() {
+    for seed in 0..10 {
+        let chunk = (-2, seed as i64, 3);
+        let maze = maze_from_rng(&mut rng_from_xyz_seed(seed, 0, 0, 0), GRID_SIZE, GRID_SIZE);
+
+        let mut a = maze.clone();
+        let mut b = maze.clone();
+        widen_passages(seed, chunk, &mut a, 0.5);
+        widen_passages(seed, chunk, &mut b, 0.5);
+
+        for (row_a, row_b) in a.iter().zip(b.iter()) {
+            for (cell_a, cell_b) in row_a.iter().zip(row_b.iter()) {
+                assert_eq!(cell_a.wall_top, cell_b.wall_top);
+                assert_eq!(cell_a.wall_bottom, cell_b.wall_bottom);
+                assert_eq!(cell_a.wall_left, cell_b.wall_left);
+                assert_eq!(cell_a.wall_right, cell_b.wall_right);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_world_structures_folder() {
+    let world_structures = world_structures();
+
+    // Files with a single world structure and files with a list of them are both loaded
+    let names: Vec<&str> = world_structures.iter().map(|ws| ws.name.as_str()).collect();
+    for name in [
+        "EmptySpace1",
+        "FilledWithChairs1",
+        "House1",
+        "StaircaseTower2",
+    ] {
+        assert!(names.contains(&name), "{}", name);
+    }
+
+    for ws in world_structures.iter() {
+        let chunk = ws.gen_origin_chunk(3, -2, 5).unwrap();
+        assert_eq!(chunk.world_structure, ws.name);
+        assert_eq!((chunk.x, chunk.y, chunk.z), (3, -2, 5));
+
+        for chunk in ws.gen_chunks(3, -2, 5) {
+            assert!(chunk.cells.len() == GRID_SIZE);
+        }
+    }
+}
+
+#[test]
+fn test_structure_theme_only_applied_to_structure_chunks() {
+    let world_structures = world_structures();
+    let tower = world_structures.get("StaircaseTower2").unwrap();
+
+    for chunk in tower.gen_chunks(3, -2, 5) {
+        let theme = world_structures.theme(&chunk.world_structure);
+        assert!(theme.is_some());
+        assert_ne!(wall_base_color(theme), wall_base_color(None));
+        assert_ne!(floor_color(theme), floor_color(None));
+
+        // Any chest in the tower would hold a weapon
+        for (z, row) in chunk.cells.iter().enumerate() {
+            for x in 0..row.len() {
+                let ccm = ChunkCellMarker::new((chunk.x, chunk.y, chunk.z), (x, z));
+                let item = treasure_chest_loot(&ccm, theme);
+                assert!(matches!(item.name.item_type(), ItemType::Weapon));
+                assert_eq!(item.amt, 1);
+            }
+        }
+    }
+
+    // Chunks outside of any world structure look the same as always
+    let dims = WorldDims::default();
+    let chunk = (0..100)
+        .map(|seed| {
+            chunk_from_xyz_seed(
+                seed,
+                Difficulty::Normal,
+                &WorldConfig::enclosed(),
+                &dims,
+                &world_structures,
+                40,
+                0,
+                40,
+            )
+        })
+        .find(|chunk| chunk.world_structure == NO_WORLD_STRUCTURE)
+        .unwrap();
+    let theme = world_structures.theme(&chunk.world_structure);
+    assert!(theme.is_none());
+    assert_eq!(wall_base_color(theme), Color::WHITE);
+
+    let ccm = ChunkCellMarker::new((40, 0, 40), (1, 2));
+    assert_eq!(
+        treasure_chest_loot(&ccm, None),
+        treasure_chest_loot(&ccm, theme)
+    );
+}
+
+#[test]
+fn test_staircase_tower_loft_railings() {
+    let mut app = new_test_app();
+    let world_structures = world_structures();
+    let tower = world_structures.get("StaircaseTower2").unwrap();
+    let loft = tower
+        .gen_chunks(0, 0, 0)
+        .into_iter()
+        .find(|chunk| chunk.y == 1)
+        .unwrap();
+
+    app.world_mut().run_system_once(
+        move |mut commands: Commands,
+              asset_server: Res<AssetServer>,
+              mut meshes: ResMut<Assets<Mesh>>,
+              mut materials: ResMut<Assets<StandardMaterial>>,
+              world_data: Res<WorldData>| {
+            spawn_chunk_bundle(
+                0,
+                &WorldDims::default(),
+                DecorDensity::default(),
+                &loft,
+                &world_structures,
+                &mut commands,
+                &asset_server,
+                &mut meshes,
+                &mut materials,
+                &world_data,
+            );
+        },
+    );
+
+    let world = app.world_mut();
+    let mut railings: Vec<((usize, usize), Side)> = world
+        .query::<(&Railing, &Parent)>()
+        .iter(world)
+        .map(|(railing, parent)| {
+            let ccm = world.get::<ChunkCellMarker>(parent.get()).unwrap();
+            ((ccm.x, ccm.z), railing.0)
+        })
+        .collect();
+    railings.sort_by_key(|(xz, _)| *xz);
+
+    // The loft runs along the last row of cells, and drops off into the tower on one side
+    // of it, apart from where the staircase comes up
+    assert_eq!(
+        railings,
+        vec![
+            ((3, 0), Side::Top),
+            ((3, 1), Side::Top),
+            ((3, 3), Side::Top),
+        ]
+    );
+}
+
+#[test]
+fn test_chunk_floor_matches_ceiling_below() {
+    let dims = WorldDims::default();
+    let world_structures = world_structures();
+    let mut checked_pairs = 0;
+
+    for seed in 0..10 {
+        for x in -3..3 {
+            for y in -3..3 {
+                for z in -3..3 {
+                    // World structures define their own floors and ceilings,
+                    // so there is nothing to match when both chunks are part of one
+                    if world_structure_chunk_from_xyz_seed(seed, &world_structures, x, y, z)
+                        .is_some()
+                        && world_structure_chunk_from_xyz_seed(seed, &world_structures, x, y - 1, z)
+                            .is_some()
+                    {
+                        continue;
+                    }
+
+                    let upper = chunk_from_xyz_seed(
+                        seed,
+                        Difficulty::Normal,
+                        &WorldConfig::enclosed(),
+                        &dims,
+                        &world_structures,
+                        x,
+                        y,
+                        z,
+                    );
+                    let lower = chunk_from_xyz_seed(
+                        seed,
+                        Difficulty::Normal,
+                        &WorldConfig::enclosed(),
+                        &dims,
+                        &world_structures,
+                        x,
+                        y - 1,
+                        z,
+                    );
+
+                    for h in 0..GRID_SIZE {
+                        for w in 0..GRID_SIZE {
+                            assert_eq!(
+                                upper.cells[h][w].floor == CellWall::None,
+                                lower.cells[h][w].ceiling == CellWall::None,
+                                "seed: {}, chunk: {:?}, cell: {:?}",
+                                seed,
+                                (x, y, z),
+                                (w, h),
+                            );
+                        }
+                    }
+                    checked_pairs += 1;
+                }
+            }
+        }
+    }
+
+    assert!(checked_pairs > 0);
+}
+
+#[test]
+fn test_starting_room_at_origin_chunk() {
+    let dims = WorldDims::default();
+    let world_structures = world_structures();
+    let starting_room = world_structures.get(STARTING_ROOM).unwrap();
+    let on = WorldConfig::default();
+    let off = WorldConfig {
+        starting_room: false,
+        ..default()
+    };
+    let chunk = |world_config: &WorldConfig, seed, (x, y, z)| {
+        chunk_from_xyz_seed(
+            seed,
+            Difficulty::Normal,
+            world_config,
+            &dims,
+            &world_structures,
+            x,
+            y,
+            z,
+        )
+    };
+    let mid = GRID_SIZE / 2;
+    let last = GRID_SIZE - 1;
+
+    for seed in 0..10 {
+        let room = chunk(&on, seed, (0, 0, 0));
+        assert_eq!(Some(room.clone()), starting_room.gen_origin_chunk(0, 0, 0));
+
+        let maze = chunk(&off, seed, (0, 0, 0));
+        assert_eq!(maze.world_structure, NO_WORLD_STRUCTURE);
+        assert_ne!(maze.cells, room.cells);
+
+        // Every side of the room has an exit where the maze has its entrances
+        for wall in [
+            &room.cells[0][mid].wall_top,
+            &room.cells[last][mid].wall_bottom,
+            &room.cells[mid][0].wall_left,
+            &room.cells[mid][last].wall_right,
+        ] {
+            assert_ne!(*wall, CellWall::Solid);
+        }
+        for (x, y, z) in [(1, 0, 0), (-1, 0, 0), (0, 0, 1), (0, 0, -1)] {
+            let neighbor = chunk(&on, seed, (x, y, z));
+            assert_eq!(neighbor, chunk(&off, seed, (x, y, z)));
+            if world_structure_chunk_from_xyz_seed(seed, &world_structures, x, y, z).is_some() {
+                continue;
+            }
+            assert_eq!(neighbor.cells[0][mid].wall_top, CellWall::None);
+            assert_eq!(neighbor.cells[last][mid].wall_bottom, CellWall::None);
+            assert_eq!(neighbor.cells[mid][0].wall_left, CellWall::None);
+            assert_eq!(neighbor.cells[mid][last].wall_right, CellWall::None);
+        }
+
+        // The chunks above and below close up against the room
+        let above = chunk(&on, seed, (0, 1, 0));
+        let below = chunk(&on, seed, (0, -1, 0));
+        for (h, row) in room.cells.iter().enumerate() {
+            for (w, cell) in row.iter().enumerate() {
+                assert_eq!(above.cells[h][w].floor, cell.ceiling);
+                assert_eq!(below.cells[h][w].ceiling, cell.floor);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_surface_level_open_to_sky() {
+    let dims = WorldDims::default();
+    let world_structures = world_structures();
+    let level = DEFAULT_SURFACE_LEVEL;
+    let chunk = |seed, (x, y, z), surface_level| {
+        chunk_from_xyz_seed(
+            seed,
+            Difficulty::Normal,
+            &WorldConfig {
+                surface_level,
+                ..WorldConfig::enclosed()
+            },
+            &dims,
+            &world_structures,
+            x,
+            y,
+            z,
+        )
+    };
+
+    for seed in 0..10 {
+        for x in -3..3 {
+            for z in -3..3 {
+                // No ceilings at or above the surface level,
+                // and no floors above it to match the open ceilings below them
+                for y in level..level + 3 {
+                    let cells = chunk(seed, (x, y, z), Some(level)).cells;
+                    assert!(cells
+                        .iter()
+                        .flatten()
+                        .all(|cell| cell.ceiling == CellWall::None));
+                    if y > level {
+                        assert!(cells
+                            .iter()
+                            .flatten()
+                            .all(|cell| cell.floor == CellWall::None));
+                    }
+                }
+
+                // Below it, chunks are the same as in a world without a surface
+                for y in level - 3..level {
+                    assert_eq!(
+                        chunk(seed, (x, y, z), Some(level)),
+                        chunk(seed, (x, y, z), None),
+                        "seed: {}, chunk: {:?}",
+                        seed,
+                        (x, y, z),
+                    );
+                }
+
+                // And the surface level still has floors that match the ceilings below them
+                let surface = chunk(seed, (x, level, z), Some(level));
+                let below = chunk(seed, (x, level - 1, z), Some(level));
+                for (row, below_row) in surface.cells.iter().zip(below.cells.iter()) {
+                    for (cell, below_cell) in row.iter().zip(below_row.iter()) {
+                        assert_eq!(
+                            cell.floor == CellWall::None,
+                            below_cell.ceiling == CellWall::None
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn sky_light_count(app: &mut App) -> usize {
+    let world = app.world_mut();
+    world
+        .query_filtered::<(), With<SkyLight>>()
+        .iter(world)
+        .count()
+}
+
+#[test]
+fn test_sky_shown_on_surface_level() {
+    let mut app = new_test_app();
+    update_n(&mut app, 3);
+    assert_eq!(sky_light_count(&mut app), 0);
+
+    let surface_y = CELL_SIZE * DEFAULT_SURFACE_LEVEL as f32 + 1.0;
+    move_player(&mut app, Vec3::new(2.0, surface_y, 2.0));
+    update_n(&mut app, 3);
+    assert_eq!(sky_light_count(&mut app), 1);
+    assert_ne!(
+        app.world().resource::<ClearColor>().0,
+        ClearColor::default().0
+    );
+
+    move_player(&mut app, Vec3::new(2.0, 1.0, 2.0));
+    update_n(&mut app, 3);
+    assert_eq!(sky_light_count(&mut app), 0);
+    assert_eq!(
+        app.world().resource::<ClearColor>().0,
+        ClearColor::default().0
+    );
+
+    // Worlds without a surface never show it
+    app.insert_resource(WorldConfig::enclosed());
+    move_player(&mut app, Vec3::new(2.0, surface_y, 2.0));
+    update_n(&mut app, 3);
+    assert_eq!(sky_light_count(&mut app), 0);
+}
+
+fn test_dims() -> [WorldDims; 3] {
+    [
+        WorldDims::default(),
+        WorldDims::new(32.0, 4.0).unwrap(),
+        WorldDims::new(15.0, 2.5).unwrap(),
+    ]
+}
+
+#[test]
+fn test_cell_world_position_round_trip() {
+    for dims in test_dims() {
+        for chunk_xyz in [(0, 0, 0), (1, -2, 3), (-4, 5, -6), (17, 0, -23)] {
+            for x in 0..dims.grid_size() {
+                for z in 0..dims.grid_size() {
+                    let pos = cell_world_position(&dims, chunk_xyz, (x, z));
+                    let ccm = ChunkCellMarker::from_global_transform(
+                        &GlobalTransform::from_translation(pos),
+                        &dims,
+                    );
+
+                    assert_eq!(ccm.chunk_xyz(), chunk_xyz, "{:?}", dims);
+                    assert_eq!(ccm.cell_xz(), (x, z), "{:?}", dims);
+
+                    // Anywhere inside of the cell leads back to the center of its floor
+                    let inside = pos + Vec3::new(0.45, 0.9, -0.45) * dims.cell_size();
+                    let ccm = ChunkCellMarker::from_global_transform(
+                        &GlobalTransform::from_translation(inside),
+                        &dims,
+                    );
+                    assert!(
+                        calc_cell_translation(&dims, &ccm).abs_diff_eq(pos, 0.001),
+                        "{:?}",
+                        dims
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_chunk_world_position_is_center_of_cells() {
+    for dims in test_dims() {
+        let grid_size = dims.grid_size();
+        for chunk_xyz in [(0, 0, 0), (2, -1, -3)] {
+            let sum: Vec3 = (0..grid_size)
+                .flat_map(|x| {
+                    (0..grid_size).map(move |z| cell_world_position(&dims, chunk_xyz, (x, z)))
+                })
+                .sum();
+            let center = sum / (grid_size * grid_size) as f32;
+
+            assert!(center.abs_diff_eq(chunk_world_position(&dims, chunk_xyz), 0.001));
+        }
+    }
+}
+
+#[test]
+fn test_larger_chunks() {
+    let dims = WorldDims::new(32.0, 4.0).unwrap();
+    let mut app = new_test_app_with_dims(dims);
+    update_n(&mut app, 3);
+
+    // World structure files are made for the default chunk size
+    let world_structures = WorldStructureRegistry::default();
+    for seed in 0..10 {
+        let chunk = chunk_from_xyz_seed(
+            seed,
+            Difficulty::Normal,
+            &WorldConfig::enclosed(),
+            &dims,
+            &world_structures,
+            0,
+            0,
+            0,
+        );
+        assert_eq!(chunk.cells.len(), 8);
+        assert!(chunk.cells.iter().all(|row| row.len() == 8));
+    }
+
+    let world = app.world_mut();
+    let cells = world
+        .query_filtered::<&ChunkCellMarker, With<Cell>>()
+        .iter(world)
+        .filter(|ccm| ccm.chunk_xyz() == (0, 0, 0))
+        .count();
+    assert_eq!(cells, 64);
+
+    // Still in the same chunk, which would be the next chunk over with the default dims
+    move_player(&mut app, Vec3::new(DEFAULT_CHUNK_SIZE * 0.75, 1.0, 2.0));
+    update_n(&mut app, 3);
+    assert_eq!(
+        app.world()
+            .resource::<State<ActiveChunk>>()
+            .get()
+            .to_tuple(),
+        (0, 0, 0)
+    );
+
+    move_player(&mut app, Vec3::new(2.0 + dims.chunk_size(), 1.0, 2.0));
+    update_n(&mut app, 3);
+    assert_eq!(
+        app.world()
+            .resource::<State<ActiveChunk>>()
+            .get()
+            .to_tuple(),
+        (1, 0, 0)
+    );
+}
+
+#[test]
+fn test_wall_texture_consistent_across_chunk_seam() {
+    let dims = WorldDims::default();
+    let wall_band = |noise: f64| [-0.2, 0.0, 0.2].iter().filter(|t| noise >= **t).count();
+
+    let mut checked_pairs = 0;
+
+    for seed in 0..50 {
+        for chunk_x in -3..3 {
+            for z in 0..GRID_SIZE {
+                // Cell 0 sits on the positive x edge of its chunk,
+                // and the last cell of the next chunk over sits right across from it
+                let left = ChunkCellMarker {
+                    chunk_x,
+                    chunk_y: 0,
+                    chunk_z: 0,
+                    x: 0,
+                    z,
+                };
+                let right = ChunkCellMarker {
+                    chunk_x: chunk_x + 1,
+                    chunk_y: 0,
+                    chunk_z: 0,
+                    x: GRID_SIZE - 1,
+                    z,
+                };
+
+                let left_noise = noise_at_world_pos(seed, calc_cell_translation(&dims, &left));
+                let right_noise = noise_at_world_pos(seed, calc_cell_translation(&dims, &right));
+
+                // Neighboring cells should only be a small step apart in the noise
+                assert!((left_noise - right_noise).abs() < 0.1);
+
+                if wall_band(left_noise) == wall_band(right_noise) {
+                    assert_eq!(
+                        wall_texture_path(seed, &dims, &left),
+                        wall_texture_path(seed, &dims, &right)
+                    );
+                    checked_pairs += 1;
+                }
+            }
+        }
+    }
+
+    assert!(checked_pairs > 0);
+}
+
+#[test]
+fn test_every_item_name_spawns_a_model_or_fallback() {
+    let mut app = new_test_app();
+
+    for name in ItemName::iter() {
+        let item = Item::new(name, 1);
+        let entity = app.world_mut().run_system_once(
+            move |mut commands: Commands,
+                  asset_server: Res<AssetServer>,
+                  mut meshes: ResMut<Assets<Mesh>>,
+                  mut materials: ResMut<Assets<StandardMaterial>>| {
+                spawn_item_bundle(
+                    item,
+                    &mut commands,
+                    &asset_server,
+                    &mut meshes,
+                    &mut materials,
+                    None,
+                    true,
+                    true,
+                    true,
+                )
+                .id()
+            },
+        );
+
+        let world = app.world_mut();
+        let children: Vec<Entity> = world.get::<Children>(entity).unwrap().to_vec();
+        let has = |component: fn(&World, Entity) -> bool| {
+            children.iter().any(|child| component(world, *child))
+        };
+
+        assert!(has(|w, e| w.get::<ItemModel>(e).is_some()), "{}", name);
+        if name.model_path().is_some() {
+            assert!(has(|w, e| w.get::<Handle<Scene>>(e).is_some()), "{}", name);
+        } else {
+            assert!(has(|w, e| w.get::<ItemBillboard>(e).is_some()), "{}", name);
+        }
+    }
+}
+
+#[test]
+fn test_render_dist_shrink_and_restore_keeps_chunks() {
+    let mut app = new_test_app();
+    set_render_dist(&mut app, 2);
+    update_n(&mut app, 3);
+
+    let chunks_before = chunk_entities(&mut app);
+    assert_eq!(chunks_before.len(), 27);
+
+    // Shrinking and restoring the render distance within two frames
+    // should keep every chunk around, rather than respawning them
+    set_render_dist(&mut app, 1);
+    app.update();
+    set_render_dist(&mut app, 2);
+    app.update();
+    update_n(&mut app, 3);
+
+    assert_eq!(chunk_entities(&mut app), chunks_before);
+}
+
+// Colliders in the hierarchy of a chunk, and the ones set aside for being outside of physics distance
+fn chunk_collider_counts(app: &mut App, chunk_entity: Entity) -> (usize, usize) {
+    let world = app.world_mut();
+    let mut query = world.query::<(Entity, Has<Collider>, Has<InactiveCollider>)>();
+    let colliders: Vec<(Entity, bool, bool)> = query
+        .iter(world)
+        .filter(|(_, active, inactive)| *active || *inactive)
+        .collect();
+
+    let mut counts = (0, 0);
+    for (entity, active, inactive) in colliders {
+        let mut current = entity;
+        while let Some(parent) = world.get::<Parent>(current) {
+            current = parent.get();
+            if current == chunk_entity {
+                counts.0 += active as usize;
+                counts.1 += inactive as usize;
+                break;
+            }
+        }
+    }
+    counts
+}
+
+#[test]
+fn test_colliders_only_within_physics_dist() {
+    let mut app = new_test_app();
+    let mut game_settings = *app.world().resource::<State<GameSettings>>().get();
+    game_settings.chunk_render_dist = ChunkRenderDist(2, 2, 2);
+    game_settings.chunk_physics_dist = ChunkPhysicsDist(1);
+    app.world_mut()
+        .resource_mut::<NextState<GameSettings>>()
+        .set(game_settings);
+    app.world_mut().send_event(RenderDistChanged);
+    update_n(&mut app, 3);
+
+    let chunks = chunk_entities(&mut app);
+    assert_eq!(chunks.len(), 27);
+
+    // Only the active chunk is within a physics distance of 1, and the ring around it is not
+    for (xyz, entity) in chunks.iter() {
+        let (active, inactive) = chunk_collider_counts(&mut app, *entity);
+        let is_inactive = app.world().get::<PhysicsInactiveChunk>(*entity).is_some();
+        if *xyz == (0, 0, 0) {
+            assert!(active > 0);
+            assert_eq!(inactive, 0);
+            assert!(!is_inactive);
+        } else {
+            assert_eq!(active, 0, "{:?}", xyz);
+            assert!(is_inactive, "{:?}", xyz);
+        }
+    }
+    let (origin_colliders, _) = chunk_collider_counts(&mut app, chunks[&(0, 0, 0)]);
+
+    // Walking into the next chunk over swaps which one has colliders
+    move_player(&mut app, Vec3::new(2.0 + CHUNK_SIZE, 1.0, 2.0));
+    update_n(&mut app, 5);
+
+    let chunks = chunk_entities(&mut app);
+    let (active, inactive) = chunk_collider_counts(&mut app, chunks[&(1, 0, 0)]);
+    assert!(active > 0);
+    assert_eq!(inactive, 0);
+    assert_eq!(
+        chunk_collider_counts(&mut app, chunks[&(0, 0, 0)]),
+        (0, origin_colliders)
+    );
+    assert_eq!(chunk_collider_counts(&mut app, chunks[&(2, 0, 0)]).0, 0);
+}
+
+#[test]
+fn test_make_nei_chunks_xyz() {
+    let chunks: Vec<_> = make_nei_chunks_xyz((3, -1, 0), 2, 1, 3).collect();
+    assert_eq!(chunks.len(), 15);
+
+    let mut expected = HashSet::new();
+    for x in 2..=4 {
+        for z in -2..=2 {
+            expected.insert((x, -1, z));
+        }
+    }
+    assert_eq!(chunks.into_iter().collect::<HashSet<_>>(), expected);
+
+    assert_eq!(make_nei_chunks_xyz((0, 0, 0), 1, 1, 1).count(), 1);
+    assert_eq!(make_nei_chunks_xyz((0, 0, 0), 0, 2, 2).count(), 0);
+    let dist = MAX_CHUNK_RENDER_DIST;
+    assert_eq!(
+        make_nei_chunks_xyz((0, 0, 0), dist, dist, dist).count(),
+        (dist as usize * 2 - 1).pow(3)
+    );
+}
+
+#[test]
+fn test_chunk_render_dist_clamped_when_loaded() {
+    let game_settings: GameSettings =
+        serde_json::from_str(r#"{"chunk_render_dist": [50, 2, 9]}"#).unwrap();
+    assert_eq!(
+        game_settings.chunk_render_dist,
+        ChunkRenderDist(MAX_CHUNK_RENDER_DIST, 2, MAX_CHUNK_RENDER_DIST)
+    );
+
+    // Saved the same way as before
+    assert_eq!(
+        serde_json::to_string(&ChunkRenderDist(2, 2, 2)).unwrap(),
+        "[2,2,2]"
+    );
+    assert_eq!(
+        ChunkRenderDist(1, 20, 3).clamped(4),
+        ChunkRenderDist(1, 4, 3)
+    );
+}
+
+#[test]
+fn test_render_dist_shrink_despawns_chunks_after_delay() {
+    let mut app = new_test_app();
+    set_render_dist(&mut app, 2);
+    update_n(&mut app, 3);
+
+    let center_chunk = chunk_entities(&mut app)[&(0, 0, 0)];
+
+    set_render_dist(&mut app, 1);
+    app.update();
+
+    // Chunks outside of render distance are still around for the frame the distance changed
+    assert_eq!(chunk_entities(&mut app).len(), 27);
+
+    update_n(&mut app, 3);
+
+    let chunks = chunk_entities(&mut app);
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[&(0, 0, 0)], center_chunk);
+}
+
+fn set_decor_density(app: &mut App, decor_density: DecorDensity) {
+    let mut game_settings = *app.world().resource::<State<GameSettings>>().get();
+    game_settings.decor_density = decor_density;
+
+    app.world_mut()
+        .resource_mut::<NextState<GameSettings>>()
+        .set(game_settings);
+}
+
+fn decor_in_chunk(app: &mut App, xyz: (i64, i64, i64)) -> Vec<(Decor, Vec3)> {
+    let world = app.world_mut();
+    let mut query = world.query::<(&Decor, &GlobalTransform)>();
+    let mut decor: Vec<(Decor, Vec3)> = query
+        .iter(world)
+        .map(|(decor, gt)| (*decor, gt.translation()))
+        .filter(|(_, translation)| {
+            ChunkCellMarker::from_global_transform(
+                &GlobalTransform::from_translation(*translation),
+                &WorldDims::default(),
+            )
+            .chunk_xyz()
+                == xyz
+        })
+        .collect();
+    decor.sort_by(|a, b| a.1.to_array().partial_cmp(&b.1.to_array()).unwrap());
+    decor
+}
+
+#[test]
+fn test_gen_cell_decor_deterministic() {
+    let dims = WorldDims::default();
+    let world_structures = world_structures();
+    for density in [DecorDensity::Low, DecorDensity::High] {
+        let mut total = 0;
+
+        for (chunk_x, chunk_z) in [(1, 0), (0, 1), (-1, -1)] {
+            let chunk = chunk_from_xyz_seed(
+                1,
+                Difficulty::Normal,
+                &WorldConfig::enclosed(),
+                &dims,
+                &world_structures,
+                chunk_x,
+                0,
+                chunk_z,
+            );
+
+            for (z, row) in chunk.cells.iter().enumerate() {
+                for (x, cell) in row.iter().enumerate() {
+                    let ccm = ChunkCellMarker {
+                        chunk_x,
+                        chunk_y: 0,
+                        chunk_z,
+                        x,
+                        z,
+                    };
+
+                    let decor = gen_cell_decor(cell, &ccm, density);
+                    assert_eq!(decor, gen_cell_decor(cell, &ccm, density));
+                    assert!(gen_cell_decor(cell, &ccm, DecorDensity::Off).is_empty());
+                    total += decor.len();
+                }
+            }
+        }
+
+        assert!(total > 0);
+    }
+}
+
+#[test]
+fn test_decor_restored_after_chunk_respawn() {
+    let mut app = new_test_app();
+    set_decor_density(&mut app, DecorDensity::High);
+    update_n(&mut app, 3);
+
+    let decor_before = decor_in_chunk(&mut app, (0, 0, 0));
+    assert!(!decor_before.is_empty());
+
+    // Walk two chunks away and back, so the chunk is despawned and spawned again
+    move_player(
+        &mut app,
+        Vec3::new(2.0, 1.0, 2.0) + Vec3::X * CHUNK_SIZE * 2.0,
+    );
+    update_n(&mut app, 5);
+    assert!(decor_in_chunk(&mut app, (0, 0, 0)).is_empty());
+
+    move_player(&mut app, Vec3::new(2.0, 1.0, 2.0));
+    update_n(&mut app, 5);
+
+    let decor_after = decor_in_chunk(&mut app, (0, 0, 0));
+    assert_eq!(decor_before.len(), decor_after.len());
+    for ((decor_a, translation_a), (decor_b, translation_b)) in
+        decor_before.iter().zip(decor_after.iter())
+    {
+        assert_eq!(decor_a, decor_b);
+        assert!(translation_a.distance(*translation_b) < 0.001);
+    }
+}
+
+#[test]
+fn test_decor_density_off_spawns_no_decor() {
+    let mut app = new_test_app();
+    set_decor_density(&mut app, DecorDensity::Off);
+    update_n(&mut app, 3);
+
+    assert!(!chunk_entities(&mut app).is_empty());
+    let world = app.world_mut();
+    assert_eq!(world.query::<&Decor>().iter(world).count(), 0);
+}
+
+#[test]
+fn test_hazards_never_on_entrance_cells() {
+    let world_structures = WorldStructureRegistry::default();
+    let mut hazards = 0;
+
+    for dims in test_dims() {
+        let grid_size = dims.grid_size();
+        let entrances: Vec<(usize, usize)> = (0..grid_size)
+            .flat_map(|h| (0..grid_size).map(move |w| (w, h)))
+            .filter(|cell| is_entrance_cell(grid_size, *cell))
+            .collect();
+        assert_eq!(entrances.len(), 4);
+
+        for x in -12..12 {
+            for z in -12..12 {
+                let chunk = chunk_from_xyz_seed(
+                    11,
+                    Difficulty::Hard,
+                    &WorldConfig::enclosed(),
+                    &dims,
+                    &world_structures,
+                    x,
+                    0,
+                    z,
+                );
+                for (h, row) in chunk.cells.iter().enumerate() {
+                    for (w, cell) in row.iter().enumerate() {
+                        if !cell.special.is_hazard() {
+                            continue;
+                        }
+                        hazards += 1;
+                        assert!(
+                            !entrances.contains(&(w, h)),
+                            "{:?} at entrance cell {:?} of chunk {:?}",
+                            cell.special,
+                            (w, h),
+                            (x, z)
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    // Otherwise there was nothing to check
+    assert!(hazards > 0);
+}
+
+#[test]
+fn test_difficulty_scales_special_spawn_prob() {
+    let dims = WorldDims::default();
+    let world_structures = world_structures();
+    let specials = |difficulty: Difficulty| {
+        let mut specials = vec![];
+        for x in -6..6 {
+            for z in -6..6 {
+                let chunk = chunk_from_xyz_seed(
+                    7,
+                    difficulty,
+                    &WorldConfig::enclosed(),
+                    &dims,
+                    &world_structures,
+                    x,
+                    0,
+                    z,
+                );
+                let walls: Vec<Vec<CellWall>> = chunk
+                    .cells
+                    .iter()
+                    .flatten()
+                    .map(|cell| {
+                        vec![
+                            cell.wall_top.clone(),
+                            cell.wall_bottom.clone(),
+                            cell.wall_left.clone(),
+                            cell.wall_right.clone(),
+                            cell.floor.clone(),
+                            cell.ceiling.clone(),
+                        ]
+                    })
+                    .collect();
+                let chunk_specials: Vec<CellSpecial> = chunk
+                    .cells
+                    .iter()
+                    .flatten()
+                    .map(|cell| cell.special.clone())
+                    .collect();
+                specials.push((walls, chunk_specials));
+            }
+        }
+        specials
+    };
+
+    let easy = specials(Difficulty::Easy);
+    let hard = specials(Difficulty::Hard);
+
+    // Same seed and difficulty always generates the same specials
+    assert_eq!(hard, specials(Difficulty::Hard));
+
+    // Difficulty only affects specials, never the layout of the maze
+    for ((easy_walls, _), (hard_walls, _)) in easy.iter().zip(hard.iter()) {
+        assert_eq!(easy_walls, hard_walls);
+    }
+
+    let count = |specials: &Vec<(Vec<Vec<CellWall>>, Vec<CellSpecial>)>| {
+        specials
+            .iter()
+            .flat_map(|(_, chunk_specials)| chunk_specials)
+            .filter(|special| **special != CellSpecial::None)
+            .count()
+    };
+    assert!(count(&easy) < count(&hard));
+}
+
+fn is_safe_spawn_cell(
+    world_structures: &WorldStructureRegistry,
+    seed: u32,
+    position: Vec3,
+) -> bool {
+    let dims = WorldDims::default();
+    let ccm = ChunkCellMarker::from_global_transform(
+        &GlobalTransform::from_translation(position),
+        &WorldDims::default(),
+    );
+    let (x, y, z) = ccm.chunk_xyz();
+    let chunk = chunk_from_xyz_seed(
+        seed,
+        Difficulty::Normal,
+        &WorldConfig::enclosed(),
+        &dims,
+        world_structures,
+        x,
+        y,
+        z,
+    );
+    let cell = &chunk.cells[ccm.z][ccm.x];
+    cell.floor == CellWall::Solid && cell.special == CellSpecial::None
+}
+
+// Far enough away from the origin that world structures spawn as often as they ever do
+const SAFE_SPAWN_TEST_CHUNK: (i64, i64, i64) = (8, 0, 8);
+
+fn safe_spawn_test_position(dims: &WorldDims) -> Vec3 {
+    chunk_world_position(dims, SAFE_SPAWN_TEST_CHUNK) + Vec3::new(2.0, 1.0, 2.0)
+}
+
+#[test]
+fn test_safe_spawn_position_avoids_unsafe_cells() {
+    let dims = WorldDims::default();
+    let world_structures = world_structures();
+    let spawn = safe_spawn_test_position(&dims);
+
+    // Seed 15 puts stairs at the spawn, and seed 1 a hole
+    for seed in [15, 1] {
+        assert!(!is_safe_spawn_cell(&world_structures, seed, spawn));
+
+        let position = safe_spawn_position(
+            seed,
+            Difficulty::Normal,
+            &WorldConfig::enclosed(),
+            &dims,
+            &world_structures,
+            spawn,
+        );
+        assert!(
+            is_safe_spawn_cell(&world_structures, seed, position),
+            "{}",
+            seed
+        );
+        assert_eq!(position.y, spawn.y);
+
+        // No safe cell in the chunk is any closer
+        let dist = Vec2::new(position.x - spawn.x, position.z - spawn.z).length();
+        for x in 0..GRID_SIZE {
+            for z in 0..GRID_SIZE {
+                let cell_position =
+                    cell_world_position(&dims, SAFE_SPAWN_TEST_CHUNK, (x, z)) + Vec3::Y * spawn.y;
+                if is_safe_spawn_cell(&world_structures, seed, cell_position) {
+                    let cell_dist =
+                        Vec2::new(cell_position.x - spawn.x, cell_position.z - spawn.z).length();
+                    assert!(dist <= cell_dist, "{}", seed);
+                }
+            }
+        }
+    }
+
+    // Stairs are right next to the spawn in seed 15
+    let position = safe_spawn_position(
+        15,
+        Difficulty::Normal,
+        &WorldConfig::enclosed(),
+        &dims,
+        &world_structures,
+        spawn,
+    );
+    assert_eq!(
+        Vec2::new(position.x - spawn.x, position.z - spawn.z).length(),
+        CELL_SIZE
+    );
+}
+
+#[test]
+fn test_safe_spawn_position_searches_below_floorless_chunk() {
+    let dims = WorldDims::default();
+    let spawn = safe_spawn_test_position(&dims);
+    let (x, y, z) = SAFE_SPAWN_TEST_CHUNK;
+
+    // No floors at all in the spawn chunk, and a single floored cell in the chunk below it
+    let gen_chunk = |xyz: (i64, i64, i64)| {
+        let mut cells = vec![vec![Cell::default(); GRID_SIZE]; GRID_SIZE];
+        if xyz == (x, y - 1, z) {
+            cells[3][1] = Cell::new_floored();
+        }
+        Chunk {
+            x: xyz.0,
+            y: xyz.1,
+            z: xyz.2,
+            cells,
+            world_structure: NO_WORLD_STRUCTURE.to_owned(),
+        }
+    };
+
+    let height = spawn.y - chunk_world_position(&dims, SAFE_SPAWN_TEST_CHUNK).y;
+    assert_eq!(
+        safe_spawn_position_with(&dims, spawn, gen_chunk),
+        cell_world_position(&dims, (x, y - 1, z), (1, 3)) + Vec3::Y * height
+    );
+}
+
+// Checks every world structure file the same way the asset loader does, so that a broken
+// file fails the tests instead of being skipped with an error at runtime
+#[test]
+fn test_world_structure_assets_are_valid() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../../assets/world_structures");
+    let mut names = Vec::new();
+
+    let paths = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"));
+
+    for path in paths {
+        let content = std::fs::read_to_string(&path).unwrap();
+        let file_stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap();
+        let parsed = parse_world_structures(&content, file_stem, GRID_SIZE)
+            .unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+
+        for ws in parsed {
+            assert!(
+                !names.contains(&ws.name),
+                "{}: {} is already used by another world structure",
+                path.display(),
+                ws.name
+            );
+            names.push(ws.name);
+        }
+    }
+
+    assert!(names.iter().any(|name| name == STARTING_ROOM));
+}
+
+#[test]
+fn test_safe_spawn_position_keeps_safe_default() {
+    let dims = WorldDims::default();
+    let world_structures = world_structures();
+    let spawn = Vec3::new(2.0, 1.0, 2.0);
+    for seed in 0..20 {
+        if is_safe_spawn_cell(&world_structures, seed, spawn) {
+            assert_eq!(
+                safe_spawn_position(
+                    seed,
+                    Difficulty::Normal,
+                    &WorldConfig::enclosed(),
+                    &dims,
+                    &world_structures,
+                    spawn
+                ),
+                spawn
+            );
+        }
+    }
+}
+
+fn floored_cells(chunk: &Chunk) -> Vec<ChunkCellMarker> {
+    let mut cells = Vec::new();
+    for (z, row) in chunk.cells.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            if cell.floor == CellWall::Solid {
+                cells.push(ChunkCellMarker::new((chunk.x, chunk.y, chunk.z), (x, z)));
+            }
+        }
+    }
+    cells
+}
+
+#[test]
+fn test_nav_path_waypoints_are_adjacent_cells() {
+    let dims = WorldDims::default();
+    let world_structures = world_structures();
+    let world_data = WorldData::default();
+    let mut found = 0;
+
+    for seed in 0..2 {
+        let chunk = chunk_from_xyz_seed(
+            seed,
+            Difficulty::Normal,
+            &WorldConfig::enclosed(),
+            &dims,
+            &world_structures,
+            0,
+            0,
+            0,
+        );
+        let cells = floored_cells(&chunk);
+
+        // Plain mazes near the origin wall most cells off from each other,
+        // so paths are searched for between every pair of cells
+        for (start, goal) in cells.iter().flat_map(|a| cells.iter().map(move |b| (a, b))) {
+            if start == goal {
+                continue;
+            }
+
+            let Some(path) = find_nav_path(
+                seed,
+                Difficulty::Normal,
+                &WorldConfig::enclosed(),
+                &dims,
+                &world_structures,
+                &world_data,
+                start,
+                goal,
+                1,
+            ) else {
+                continue;
+            };
+            found += 1;
+
+            assert_eq!(path.first(), Some(&start.world_position(&dims)));
+            assert_eq!(path.last(), Some(&goal.world_position(&dims)));
+            for step in path.windows(2) {
+                let diff = (step[1] - step[0]).abs();
+                assert!(diff.x + diff.z <= CELL_SIZE + 0.001);
+                assert!(diff.y <= CELL_SIZE + 0.001);
+            }
+        }
+    }
+
+    assert!(found > 5);
+}
+
+#[test]
+fn test_nav_path_follows_cell_mutations() {
+    let dims = WorldDims::default();
+    let world_structures = world_structures();
+    let seed = WorldSeed::default().0;
+    let chunk = chunk_from_xyz_seed(
+        seed,
+        Difficulty::Normal,
+        &WorldConfig::enclosed(),
+        &dims,
+        &world_structures,
+        0,
+        0,
+        0,
+    );
+    let is_floored = |ccm: &ChunkCellMarker| chunk.cells[ccm.z][ccm.x].floor == CellWall::Solid;
+
+    // Two floored cells of the chunk with a solid wall between them
+    let (start, goal) = floored_cells(&chunk)
+        .into_iter()
+        .filter(|ccm| ccm.x > 0 && chunk.cells[ccm.z][ccm.x].wall_top == CellWall::Solid)
+        .map(|ccm| (ccm.clone(), ccm.neighbor(Side::Top, GRID_SIZE)))
+        .find(|(_, nei)| is_floored(nei))
+        .unwrap();
+
+    let find = |world_data: &WorldData| {
+        find_nav_path(
+            seed,
+            Difficulty::Normal,
+            &WorldConfig::enclosed(),
+            &dims,
+            &world_structures,
+            world_data,
+            &start,
+            &goal,
+            1,
+        )
+    };
+
+    let mut world_data = WorldData::default();
+    assert_ne!(find(&world_data).map(|path| path.len()), Some(2));
+
+    world_data.add_cell_mutation((0, 0, 0), start.cell_xz(), CellMutation::RemoveWallTop);
+    world_data.add_cell_mutation((0, 0, 0), goal.cell_xz(), CellMutation::RemoveWallBottom);
+    assert_eq!(find(&world_data).map(|path| path.len()), Some(2));
+}
+
+// Door with instant animations, so only the auto close frames matter, inside of a cell at x = 10
+fn spawn_auto_close_door(app: &mut App, after_frames: u32) -> Entity {
+    let door = app
+        .world_mut()
+        .spawn((
+            TransformBundle::default(),
+            Interactable::new(InteractableKind::Door),
+            CyclicTransform::new_cycled_from_keyframes(vec![
+                vec![
+                    (Transform::IDENTITY, 0.0),
+                    (Transform::from_xyz(0.0, 0.0, 1.0), 0.0),
+                ],
+                vec![
+                    (Transform::from_xyz(0.0, 0.0, 1.0), 0.0),
+                    (Transform::IDENTITY, 0.0),
+                ],
+            ]),
+            AutoClose::new(after_frames, 0, Vec3::splat(-1.0), Vec3::splat(1.0)),
+        ))
+        .id();
+    app.world_mut()
+        .spawn(TransformBundle::from_transform(Transform::from_xyz(
+            10.0, 0.0, 0.0,
+        )))
+        .add_child(door);
+    door
+}
+
+fn set_player_translation(app: &mut App, translation: Vec3) {
+    let world = app.world_mut();
+    let player = world.query_filtered::<Entity, With<Player>>().single(world);
+    world
+        .entity_mut(player)
+        .insert(Transform::from_translation(translation));
+}
+
+fn is_door_open(app: &App, door: Entity) -> bool {
+    app.world()
+        .get::<CyclicTransform>(door)
+        .unwrap()
+        .is_in_state(0)
+}
+
+fn is_door_closed(app: &App, door: Entity) -> bool {
+    app.world()
+        .get::<CyclicTransform>(door)
+        .unwrap()
+        .is_in_state(1)
+}
+
+fn interact(app: &mut App, door: Entity) {
+    app.world_mut().send_event(PendingInteractionExecuted(door));
+    app.update();
+}
+
+#[test]
+fn test_door_auto_closes_unless_player_in_doorway() {
+    let mut app = new_test_app();
+    set_player_translation(&mut app, Vec3::new(50.0, 0.0, 0.0));
+    let door = spawn_auto_close_door(&mut app, 3);
+    app.update();
+    assert!(is_door_closed(&app, door));
+
+    interact(&mut app, door);
+    app.update();
+    assert!(is_door_open(&app, door));
+
+    // Standing in the doorway, in the cell's local space, holds the door open
+    set_player_translation(&mut app, Vec3::new(10.5, 0.0, -0.5));
+    for _ in 0..10 {
+        app.update();
+    }
+    assert!(is_door_open(&app, door));
+
+    // Then it closes right after the player steps out
+    set_player_translation(&mut app, Vec3::new(12.0, 0.0, 0.0));
+    app.update();
+    app.update();
+    app.update();
+    assert!(is_door_closed(&app, door));
+    for _ in 0..10 {
+        app.update();
+    }
+    assert!(is_door_closed(&app, door));
+}
+
+#[test]
+fn test_door_auto_close_canceled_by_interaction() {
+    let mut app = new_test_app();
+    set_player_translation(&mut app, Vec3::new(50.0, 0.0, 0.0));
+    let door = spawn_auto_close_door(&mut app, 5);
+    app.update();
+
+    interact(&mut app, door);
+    app.update();
+    app.update();
+    assert!(is_door_open(&app, door));
+    assert!(app.world().get::<AutoClose>(door).unwrap().is_counting());
+
+    // Closed by hand right away instead of waiting on the timer, which does not reopen it
+    interact(&mut app, door);
+    assert!(!app.world().get::<AutoClose>(door).unwrap().is_counting());
+    app.update();
+    assert!(is_door_closed(&app, door));
+    for _ in 0..10 {
+        app.update();
+    }
+    assert!(is_door_closed(&app, door));
+}
+
+fn open_ceiling_cell() -> Cell {
+    Cell {
+        ceiling: CellWall::None,
+        floor: CellWall::Solid,
+        ..default()
+    }
+}
+
+// Where each particle in the pool is after a few seconds
+fn emitter_snapshot(emitter: &mut ParticleEmitter) -> Vec<Vec3> {
+    for _ in 0..100 {
+        emitter.tick(0.05);
+    }
+    emitter.particles().iter().map(|p| p.position).collect()
+}
+
+#[test]
+fn test_particle_emitter_placement_deterministic() {
+    let cell = open_ceiling_cell();
+    let closed_cell = Cell {
+        ceiling: CellWall::Solid,
+        ..open_ceiling_cell()
+    };
+    let ccms: Vec<ChunkCellMarker> = (0..GRID_SIZE)
+        .flat_map(|x| (0..GRID_SIZE).map(move |z| ChunkCellMarker::new((2, -1, 7), (x, z))))
+        .collect();
+
+    let mut placed = 0;
+    for ccm in ccms.iter() {
+        let a = gen_cell_particle_emitter(&cell, ccm, DecorDensity::High);
+        let b = gen_cell_particle_emitter(&cell, ccm, DecorDensity::High);
+        match (a, b) {
+            (None, None) => {}
+            (Some(mut a), Some(mut b)) => {
+                placed += 1;
+                assert_eq!(a.kind, b.kind);
+                assert_eq!(a.chunk_xyz, (2, -1, 7));
+                assert_eq!(emitter_snapshot(&mut a), emitter_snapshot(&mut b));
+            }
+            _ => panic!("expected the same placement for {:?}", ccm),
+        }
+
+        // Off, or a ceiling to block the view, means no emitter at all
+        assert!(gen_cell_particle_emitter(&cell, ccm, DecorDensity::Off).is_none());
+        assert!(gen_cell_particle_emitter(&closed_cell, ccm, DecorDensity::High).is_none());
+    }
+
+    assert!(placed > 0);
+    let placed_low = ccms
+        .iter()
+        .filter(|ccm| gen_cell_particle_emitter(&cell, ccm, DecorDensity::Low).is_some())
+        .count();
+    assert!(placed_low <= placed);
+}
+
+fn spawn_particle_emitter(app: &mut App, chunk_xyz: (i64, i64, i64)) -> Entity {
+    let emitter = ParticleEmitter::new(
+        ParticleKind::Drip,
+        chunk_xyz,
+        Vec3::new(0.0, CELL_SIZE, 0.0),
+        0.0,
+        0.1,
+        rng_from_str("emitter"),
+    );
+    let handles = DripRingHandles {
+        mesh: Handle::default(),
+        material: Handle::default(),
+    };
+    app.world_mut()
+        .spawn((SpatialBundle::default(), emitter, handles))
+        .id()
+}
+
+fn alive_particles(app: &App, entity: Entity) -> usize {
+    let emitter = app.world().get::<ParticleEmitter>(entity).unwrap();
+    emitter.particles().iter().filter(|p| p.alive).count()
+}
+
+fn drip_rings(app: &App, entity: Entity) -> usize {
+    app.world().get::<Children>(entity).map_or(0, |children| {
+        children
+            .iter()
+            .filter(|child| app.world().get::<DripRing>(**child).is_some())
+            .count()
+    })
+}
+
+#[test]
+fn test_particle_emitters_only_update_near_active_chunk() {
+    let mut app = new_test_app();
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+        50,
+    )));
+    app.update();
+
+    let near = spawn_particle_emitter(&mut app, (1, 0, -1));
+    let far = spawn_particle_emitter(&mut app, (3, 0, 0));
+    app.update();
+
+    assert_eq!(alive_particles(&app, near), 1);
+    assert_eq!(alive_particles(&app, far), 0);
+    assert_eq!(
+        app.world().get::<Visibility>(far),
+        Some(&Visibility::Hidden)
+    );
+
+    // Falling a whole cell takes about a second, after which the drip leaves a ring behind
+    let mut landed = false;
+    for _ in 0..40 {
+        app.update();
+        landed |= drip_rings(&app, near) > 0;
+    }
+    assert!(landed);
+    assert_eq!(drip_rings(&app, far), 0);
+    assert_eq!(alive_particles(&app, far), 0);
+
+    // Moving on wakes up the emitters around the new active chunk
+    let dims = *app.world().resource::<WorldDims>();
+    set_player_translation(&mut app, cell_world_position(&dims, (3, 0, 1), (0, 0)));
+    app.update();
+    app.update();
+    app.update();
+    assert_eq!(alive_particles(&app, far), 1);
+    assert_eq!(
+        app.world().get::<Visibility>(far),
+        Some(&Visibility::Inherited)
+    );
+}
+
+#[test]
+fn test_ticks_without_camera_or_player() {
+    let mut app = new_test_app();
+    app.add_plugins((
+        ScenePlugin,
+        RapierPhysicsPlugin::<NoUserData>::default(),
+        PlayerPlugin,
+    ))
+    .insert_resource(world_structures())
+    .init_state::<PlayerAnimation>()
+    .init_resource::<Inventory>()
+    .init_resource::<ButtonInput<KeyCode>>()
+    .init_resource::<ButtonInput<MouseButton>>()
+    .init_state::<MenuOpen>()
+    .add_event::<InventoryChanged>();
+
+    // The camera is spawned separately, so it can be missing for a few frames
+    assert!(app
+        .world_mut()
+        .query_filtered::<(), With<MainCamera>>()
+        .iter(app.world())
+        .next()
+        .is_none());
+    for _ in 0..5 {
+        app.update();
+    }
+
+    // Neither is there a player to follow once it is gone
+    let world = app.world_mut();
+    let players: Vec<Entity> = world
+        .query_filtered::<Entity, With<Player>>()
+        .iter(world)
+        .collect();
+    for player in players {
+        world.entity_mut(player).despawn_recursive();
+    }
+    for _ in 0..5 {
+        app.update();
+    }
+
+    app.world_mut()
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Spectating);
+    for _ in 0..5 {
+        app.update();
+    }
+}
+
+#[test]
+fn test_windows_only_block_what_is_not_thrown_or_swung() {
+    let interacts = |a: CollisionGroups, b: CollisionGroups| {
+        InteractionGroups::from(a).test(InteractionGroups::from(b))
+    };
+    let window = window_collision_groups();
+    let projectile = projectile_collision_groups();
+    let weapon = weapon_collision_groups();
+    // The player, walls and glass panes are left in every group
+    let other = CollisionGroups::default();
+
+    assert!(interacts(window, other));
+    assert!(!interacts(window, projectile));
+    assert!(!interacts(window, weapon));
+    assert!(interacts(projectile, other));
+    assert!(interacts(weapon, other));
+}
+
+// Spawns a glass window on the top side of a cell, and returns the window and its pane
+fn spawn_glass_window(app: &mut App, ccm: ChunkCellMarker) -> (Entity, Option<Entity>) {
+    let window = app.world_mut().run_system_once(
+        move |mut commands: Commands,
+              asset_server: Res<AssetServer>,
+              mut meshes: ResMut<Assets<Mesh>>,
+              mut materials: ResMut<Assets<StandardMaterial>>,
+              world_data: Res<WorldData>| {
+            let cell = commands.spawn((ccm.clone(), SpatialBundle::default())).id();
+            commands.entity(cell).with_children(|parent| {
+                spawn_window_bundle(
+                    Side::Top,
+                    &ccm,
+                    parent,
+                    &asset_server,
+                    &mut meshes,
+                    &mut materials,
+                    &world_data,
+                );
+            });
+            cell
+        },
+    );
+    let world = app.world();
+    let window = world.get::<Children>(window).unwrap()[0];
+    let pane = world.get::<Children>(window).and_then(|children| {
+        children
+            .iter()
+            .copied()
+            .find(|child| world.get::<WindowPane>(*child).is_some())
+    });
+    (window, pane)
+}
+
+#[test]
+fn test_glass_window_breaks_from_enough_blunt_and_slash_damage() {
+    let mut app = new_test_app();
+    let ccm = (0..GRID_SIZE)
+        .map(|x| ChunkCellMarker::new((0, 0, 0), (x, 0)))
+        .chain((0..GRID_SIZE).map(|z| ChunkCellMarker::new((0, 0, 1), (0, z))))
+        .find(|ccm| WindowStyle::at(ccm, Side::Top) == WindowStyle::Glass)
+        .unwrap();
+
+    let (window, pane) = spawn_glass_window(&mut app, ccm.clone());
+    let pane = pane.unwrap();
+    assert!(app.world().get::<Collider>(window).is_some());
+
+    let hit = |app: &mut App, amounts: Vec<(DmgType, f32)>| {
+        app.world_mut().send_event(TakeDamage {
+            amounts,
+            target: pane,
+            source: None,
+        });
+        app.update();
+    };
+
+    // Neither fire nor piercing wears down the glass
+    hit(
+        &mut app,
+        vec![(DmgType::Fire, 100.0), (DmgType::Pierce, 100.0)],
+    );
+    hit(&mut app, vec![(DmgType::Blunt, WINDOW_PANE_HEALTH / 2.0)]);
+    assert!(app.world().get_entity(pane).is_some());
+    assert!(app.world().get::<Collider>(window).is_some());
+
+    hit(&mut app, vec![(DmgType::Slash, WINDOW_PANE_HEALTH / 2.0)]);
+    assert!(app.world().get_entity(pane).is_none());
+    assert!(app.world().get::<Collider>(window).is_none());
+    let world_data = app.world().resource::<WorldData>();
+    let cell_data = world_data.at_cell(ccm.chunk_xyz(), ccm.cell_xz()).unwrap();
+    assert!(cell_data.window_broken(Side::Top));
+    assert!(!cell_data.window_broken(Side::Bottom));
+
+    // Spawned again already broken
+    let (window, pane) = spawn_glass_window(&mut app, ccm);
+    assert!(pane.is_none());
+    assert!(app.world().get::<Collider>(window).is_none());
+    assert_eq!(
+        app.world().get::<Interactable>(window),
+        Some(&Interactable::new(InteractableKind::Window))
+    );
+}
+
+#[test]
+fn test_structure_compass_points_at_a_structure_that_generates() {
+    let world_structures = world_structures();
+    let mut pointed_at = 0;
+
+    for seed in 0..20 {
+        let Some((x, y, z)) = nearest_structure_chunk(seed, (12, 0, -9), 8, &world_structures)
+        else {
+            continue;
+        };
+        pointed_at += 1;
+
+        let mut rng = rng_from_xyz_seed(seed, x, y, z);
+        let expected = world_structures.choose(&mut rng).unwrap();
+        let chunk = world_structure_chunk_from_xyz_seed(seed, &world_structures, x, y, z);
+        assert_eq!(
+            chunk.map(|chunk| chunk.world_structure),
+            Some(expected.name.clone()),
+            "seed: {}, chunk: {:?}",
+            seed,
+            (x, y, z)
+        );
+    }
+
+    assert!(pointed_at > 0);
+}
@@ -1,5 +1,4 @@
 use bevy::prelude::*;
-use bevy_common_assets::json::JsonAssetPlugin;
 use bevy_embedded_assets::EmbeddedAssetPlugin;
 use bevy_inspector_egui::{
     bevy_egui::{egui, EguiContexts},
@@ -7,22 +6,131 @@ use bevy_inspector_egui::{
 };
 use bevy_third_person_camera::*;
 use dungeon_maze_common::{
-    utils::io::read_dir_to_vec,
-    world::{data::WorldData, world_structure::WorldStructure, ChunkMarker},
+    meshes::obj::ObjAssetPlugin,
+    settings::DecorDensity,
+    should_not_happen_once,
+    utils::{debug::WarnOnce, io::read_dir_to_vec},
+    world::{
+        data::WorldData,
+        world_structure::{
+            StructureTheme, WorldStructureAssetPlugin, WorldStructureFile, WorldStructureRegistry,
+        },
+        CellSpecial, Chunk, WorldDims, DEFAULT_WORLD_SEED,
+    },
+};
+use dungeon_maze_game::plugins::world::{
+    bundle::{
+        cell::cell_world_position,
+        chunk::{chunk_world_position, spawn_chunk_bundle},
+    },
+    update_world_structure_registry,
 };
-use dungeon_maze_game::plugins::world::bundle::chunk::spawn_chunk_bundle;
 use std::{collections::HashMap, env, path::Path};
 
+#[cfg(test)]
+mod main_test;
+
 const MOVEMENT_SPEED: f32 = 4.0;
+const SNAP_TWEEN_SECS: f32 = 0.5;
 
 #[derive(Component)]
 struct Player;
 
+// Moves the player (and the camera following it) smoothly to a new position
+#[derive(Component)]
+struct PlayerTween {
+    start: Vec3,
+    end: Vec3,
+    elapsed_secs: f32,
+}
+
+// Box in world space that the camera is snapped to
+struct SnapTarget {
+    min: Vec3,
+    max: Vec3,
+}
+
+impl SnapTarget {
+    fn chunk(dims: &WorldDims, chunk_xyz: (i64, i64, i64)) -> Self {
+        let half = Vec3::new(dims.chunk_size() / 2.0, 0.0, dims.chunk_size() / 2.0);
+        let floor = chunk_world_position(dims, chunk_xyz);
+        Self {
+            min: floor - half,
+            max: floor + half + Vec3::Y * dims.cell_size(),
+        }
+    }
+
+    fn cell(dims: &WorldDims, chunk_xyz: (i64, i64, i64), cell_xz: (usize, usize)) -> Self {
+        let half = Vec3::new(dims.cell_size() / 2.0, 0.0, dims.cell_size() / 2.0);
+        let floor = cell_world_position(dims, chunk_xyz, cell_xz);
+        Self {
+            min: floor - half,
+            max: floor + half + Vec3::Y * dims.cell_size(),
+        }
+    }
+
+    fn structure(
+        dims: &WorldDims,
+        chunks_xyz: impl IntoIterator<Item = (i64, i64, i64)>,
+    ) -> Option<Self> {
+        chunks_xyz
+            .into_iter()
+            .map(|xyz| Self::chunk(dims, xyz))
+            .reduce(|a, b| Self {
+                min: a.min.min(b.min),
+                max: a.max.max(b.max),
+            })
+    }
+
+    // Hovers in the middle of the box, so its floor stays in view
+    fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    // Distance from the center at which the whole box fits on screen
+    fn fit_radius(&self) -> f32 {
+        let half_fov = PerspectiveProjection::default().fov / 2.0;
+        (self.max - self.min).length() / 2.0 / half_fov.sin()
+    }
+}
+
 #[derive(Clone, Default, Resource)]
 struct AssetLib {
-    ws_handles: HashMap<String, (Handle<WorldStructure>, bool)>,
+    // Only held on to, so that the files stay loaded
+    _ws_files: Vec<Handle<WorldStructureFile>>,
+    // Keyed by world structure name, since one file can hold more than one world structure
+    ws_entries: HashMap<String, WorldStructureEntry>,
+}
+
+#[derive(Clone, Default)]
+struct WorldStructureEntry {
+    active: bool,
+    // Added to the coordinates of every chunk in the world structure before spawning
+    offset: (i64, i64, i64),
+}
+
+// Chunks spawned by update_chunks, along with what they were spawned from,
+// so that only the ones that changed are spawned again
+#[derive(Default, Resource)]
+struct SpawnedChunks(HashMap<(i64, i64, i64), SpawnedChunk>);
+
+struct SpawnedChunk {
+    entity: Entity,
+    chunk: Chunk,
+    theme: Option<StructureTheme>,
+}
+
+// Chunk left out of a world structure, since an earlier active one already has a chunk there
+#[derive(Clone, Debug, PartialEq)]
+struct ChunkCollision {
+    xyz: (i64, i64, i64),
+    kept: String,
+    skipped: String,
 }
 
+#[derive(Default, Resource)]
+struct ChunkCollisions(Vec<ChunkCollision>);
+
 fn get_assets_dir_path() -> String {
     env::current_dir()
         .unwrap()
@@ -33,7 +141,11 @@ fn get_assets_dir_path() -> String {
 }
 
 fn main() {
+    // World structure files are made for the default world dimensions
+    let world_dims = WorldDims::default();
+
     App::new()
+        .insert_resource(world_dims)
         .add_plugins((
             DefaultPlugins.set(AssetPlugin {
                 watch_for_changes_override: Some(true),
@@ -43,19 +155,31 @@ fn main() {
             }),
             EmbeddedAssetPlugin::default(),
             ThirdPersonCameraPlugin,
-            JsonAssetPlugin::<WorldStructure>::new(&["json"]),
+            WorldStructureAssetPlugin {
+                grid_size: world_dims.grid_size(),
+            },
+            ObjAssetPlugin,
             WorldInspectorPlugin::default(),
         ))
         .init_resource::<WorldData>()
         .init_resource::<AssetLib>()
+        .init_resource::<WorldStructureRegistry>()
+        .init_resource::<SpawnedChunks>()
+        .init_resource::<ChunkCollisions>()
         .add_systems(Startup, setup)
         .add_systems(
             Update,
             (
                 player_movement,
+                tween_player,
                 render_gui,
                 handle_assets_modified,
-                update_chunks.run_if(resource_changed::<AssetLib>),
+                update_world_structure_registry
+                    .run_if(on_event::<AssetEvent<WorldStructureFile>>()),
+                update_chunks.after(update_world_structure_registry).run_if(
+                    resource_changed::<AssetLib>
+                        .or_else(resource_changed::<WorldStructureRegistry>),
+                ),
             ),
         )
         .run();
@@ -103,13 +227,17 @@ fn player_movement(
     keys: Res<ButtonInput<KeyCode>>,
     mut player_q: Query<&mut Transform, With<Player>>,
     cam_q: Query<&Transform, (With<Camera3d>, Without<Player>)>,
+    mut warn_once: Local<WarnOnce>,
 ) {
-    for mut player_transform in player_q.iter_mut() {
-        let cam = match cam_q.get_single() {
-            Ok(c) => c,
-            Err(e) => Err(format!("Error retrieving camera: {}", e)).unwrap(),
-        };
+    let cam = match cam_q.get_single() {
+        Ok(c) => c,
+        Err(e) => {
+            should_not_happen_once!(warn_once, "retrieving camera: {}", e);
+            return;
+        }
+    };
 
+    for mut player_transform in player_q.iter_mut() {
         let mut direction = Vec3::ZERO;
 
         // forward
@@ -153,25 +281,206 @@ fn player_movement(
     }
 }
 
-fn render_gui(mut commands: Commands, mut contexts: EguiContexts, asset_lib: Res<AssetLib>) {
+fn tween_player(
+    mut commands: Commands,
+    mut player_query: Query<(Entity, &mut Transform, &mut PlayerTween), With<Player>>,
+    time: Res<Time>,
+) {
+    for (entity, mut transform, mut tween) in player_query.iter_mut() {
+        tween.elapsed_secs += time.delta_seconds();
+
+        let t = (tween.elapsed_secs / SNAP_TWEEN_SECS).min(1.0);
+        // Smoothstep, so the camera eases in and out
+        let t = t * t * (3.0 - 2.0 * t);
+        transform.translation = tween.start.lerp(tween.end, t);
+
+        if tween.elapsed_secs >= SNAP_TWEEN_SECS {
+            commands.entity(entity).remove::<PlayerTween>();
+        }
+    }
+}
+
+fn snap_to(
+    commands: &mut Commands,
+    snap_target: &SnapTarget,
+    player_query: &Query<(Entity, &Transform), With<Player>>,
+    camera_query: &mut Query<&mut ThirdPersonCamera>,
+) {
+    for (entity, transform) in player_query.iter() {
+        commands.entity(entity).insert(PlayerTween {
+            start: transform.translation,
+            end: snap_target.center(),
+            elapsed_secs: 0.0,
+        });
+    }
+
+    let radius = snap_target.fit_radius();
+    for mut camera in camera_query.iter_mut() {
+        // Leaves room to zoom in and out from the fitted radius
+        camera.zoom = Zoom::new(radius * 0.5, radius * 1.5);
+    }
+}
+
+fn render_gui(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    player_query: Query<(Entity, &Transform), With<Player>>,
+    mut camera_query: Query<&mut ThirdPersonCamera>,
+    asset_lib: Res<AssetLib>,
+    world_structures: Res<WorldStructureRegistry>,
+    chunk_collisions: Res<ChunkCollisions>,
+    world_dims: Res<WorldDims>,
+) {
     let ctx = contexts.ctx_mut();
+    let mut snap_target: Option<SnapTarget> = None;
 
     egui::SidePanel::right("side_panel")
         .default_width(400.0)
         .show(ctx, |ui| {
-            for (path, (handle, active)) in &asset_lib.ws_handles {
-                let text = format!("[{}] {}", if *active { "on" } else { "off" }, path);
+            if ui.button("auto-layout").clicked() {
+                let mut new_asset_lib = asset_lib.clone();
+                auto_layout(&mut new_asset_lib, &world_structures);
+                commands.insert_resource(new_asset_lib);
+            }
+
+            if !chunk_collisions.0.is_empty() {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    "overlapping chunks, only the first active world structure's are shown:",
+                );
+                for collision in chunk_collisions.0.iter() {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        format!(
+                            "{:?} {} over {}",
+                            collision.xyz, collision.kept, collision.skipped
+                        ),
+                    );
+                }
+            }
+
+            ui.separator();
+
+            for ws in world_structures.iter() {
+                let name = &ws.name;
+                let entry = asset_lib.ws_entries.get(name).cloned().unwrap_or_default();
+                let text = format!("[{}] {}", if entry.active { "on" } else { "off" }, name);
 
                 if ui.button(text).clicked() {
                     let mut new_asset_lib = asset_lib.clone();
                     new_asset_lib
-                        .ws_handles
-                        .insert(path.clone(), (handle.clone(), !active));
+                        .ws_entries
+                        .entry(name.clone())
+                        .or_default()
+                        .active = !entry.active;
 
                     commands.insert_resource(new_asset_lib);
                 }
+
+                let mut offset = entry.offset;
+                let changed = ui
+                    .horizontal(|ui| {
+                        ui.label("offset");
+                        ui.add(egui::DragValue::new(&mut offset.0).prefix("x: "))
+                            .changed()
+                            | ui.add(egui::DragValue::new(&mut offset.1).prefix("y: "))
+                                .changed()
+                            | ui.add(egui::DragValue::new(&mut offset.2).prefix("z: "))
+                                .changed()
+                    })
+                    .inner;
+
+                if changed {
+                    let mut new_asset_lib = asset_lib.clone();
+                    new_asset_lib
+                        .ws_entries
+                        .entry(name.clone())
+                        .or_default()
+                        .offset = offset;
+
+                    commands.insert_resource(new_asset_lib);
+                }
+
+                if !entry.active {
+                    continue;
+                }
+
+                let (offset_x, offset_y, offset_z) = entry.offset;
+                let chunks_xyz: Vec<(i64, i64, i64)> = ws
+                    .chunks
+                    .iter()
+                    .map(|c| (c.x + offset_x, c.y + offset_y, c.z + offset_z))
+                    .collect();
+
+                egui::CollapsingHeader::new("chunks")
+                    .id_salt(name)
+                    .show(ui, |ui| {
+                        if ui.button("frame structure").clicked() {
+                            snap_target =
+                                SnapTarget::structure(&world_dims, chunks_xyz.iter().copied());
+                        }
+
+                        for (chunk, chunk_xyz) in ws.chunks.iter().zip(chunks_xyz.iter().copied()) {
+                            let response = egui::CollapsingHeader::new(format!("{:?}", chunk_xyz))
+                                .id_salt((name, chunk.x, chunk.y, chunk.z))
+                                .show(ui, |ui| {
+                                    for (z, row) in chunk.cells.iter().enumerate() {
+                                        for (x, cell) in row.iter().enumerate() {
+                                            if cell.special == CellSpecial::None {
+                                                continue;
+                                            }
+
+                                            let text = format!("({}, {}) {}", x, z, cell.special);
+                                            if ui.button(text).clicked() {
+                                                snap_target = Some(SnapTarget::cell(
+                                                    &world_dims,
+                                                    chunk_xyz,
+                                                    (x, z),
+                                                ));
+                                            }
+                                        }
+                                    }
+                                });
+
+                            if response.header_response.clicked() {
+                                snap_target = Some(SnapTarget::chunk(&world_dims, chunk_xyz));
+                            }
+                        }
+                    });
             }
         });
+
+    if let Some(snap_target) = snap_target {
+        snap_to(
+            &mut commands,
+            &snap_target,
+            &player_query,
+            &mut camera_query,
+        );
+    }
+}
+
+// Spaces active world structures out along the x axis so that none of them overlap
+fn auto_layout(asset_lib: &mut AssetLib, world_structures: &WorldStructureRegistry) {
+    let mut next_x = 0;
+
+    // World structures are already sorted by name
+    for ws in world_structures.iter() {
+        let Some(entry) = asset_lib
+            .ws_entries
+            .get_mut(&ws.name)
+            .filter(|entry| entry.active)
+        else {
+            continue;
+        };
+
+        let min_x = ws.chunks.iter().map(|c| c.x).min().unwrap_or(0);
+        let max_x = ws.chunks.iter().map(|c| c.x).max().unwrap_or(0);
+
+        entry.offset = (next_x - min_x, 0, 0);
+        // Leave a gap of one chunk between neighboring world structures
+        next_x += max_x - min_x + 2;
+    }
 }
 
 fn update_assets_lib(
@@ -186,26 +495,21 @@ fn update_assets_lib(
             .map(|file_name| format!("world_structures/{}", file_name))
             .collect();
 
-    let mut ws_handles: HashMap<String, (Handle<WorldStructure>, bool)> = HashMap::new();
-
-    for path in ws_paths {
-        let ws_handle: Handle<WorldStructure> = asset_server.load(&path);
-
-        let active = asset_lib
-            .ws_handles
-            .get(&path)
-            .map(|(_, a)| *a)
-            .unwrap_or(false);
+    let _ws_files = ws_paths
+        .iter()
+        .map(|path| asset_server.load(path))
+        .collect();
 
-        ws_handles.insert(path, (ws_handle, active));
-    }
-
-    commands.insert_resource(AssetLib { ws_handles });
+    // Keep the previous settings of world structures that are being reloaded
+    commands.insert_resource(AssetLib {
+        _ws_files,
+        ws_entries: asset_lib.ws_entries.clone(),
+    });
 }
 
 fn handle_assets_modified(
     mut commands: Commands,
-    mut ws_event_reader: EventReader<AssetEvent<WorldStructure>>,
+    mut ws_event_reader: EventReader<AssetEvent<WorldStructureFile>>,
     asset_server: Res<AssetServer>,
     asset_lib: Res<AssetLib>,
 ) {
@@ -218,34 +522,98 @@ fn handle_assets_modified(
     }
 }
 
+// Chunks of every active world structure by coordinates. When more than one has a chunk at the
+// same coordinates, the first one by name wins, and the rest are returned as collisions.
+fn desired_chunks(
+    world_structures: &WorldStructureRegistry,
+    asset_lib: &AssetLib,
+) -> (HashMap<(i64, i64, i64), Chunk>, Vec<ChunkCollision>) {
+    let mut chunks: HashMap<(i64, i64, i64), Chunk> = HashMap::new();
+    let mut collisions = Vec::new();
+
+    // World structures are already sorted by name
+    for ws in world_structures.iter() {
+        let Some(entry) = asset_lib.ws_entries.get(&ws.name) else {
+            continue;
+        };
+        if !entry.active {
+            continue;
+        }
+
+        let (offset_x, offset_y, offset_z) = entry.offset;
+        for chunk in ws.gen_chunks(offset_x, offset_y, offset_z) {
+            let xyz = (chunk.x, chunk.y, chunk.z);
+            match chunks.get(&xyz) {
+                // Chunks are labeled with the name of the world structure they came from
+                Some(kept) => collisions.push(ChunkCollision {
+                    xyz,
+                    kept: kept.world_structure.clone(),
+                    skipped: ws.name.clone(),
+                }),
+                None => {
+                    chunks.insert(xyz, chunk);
+                }
+            }
+        }
+    }
+
+    (chunks, collisions)
+}
+
+// Only despawns and respawns the chunks that changed, so that editing stays
+// responsive with several large world structures active
 fn update_chunks(
     mut commands: Commands,
-    chunk_marker_query: Query<Entity, With<ChunkMarker>>,
     asset_server: Res<AssetServer>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    world_structures: Res<Assets<WorldStructure>>,
+    world_structures: Res<WorldStructureRegistry>,
     asset_lib: Res<AssetLib>,
     world_data: Res<WorldData>,
+    world_dims: Res<WorldDims>,
+    mut spawned_chunks: ResMut<SpawnedChunks>,
+    mut chunk_collisions: ResMut<ChunkCollisions>,
 ) {
-    for entity in chunk_marker_query.iter() {
-        commands.entity(entity).despawn_recursive();
-    }
+    let (chunks, collisions) = desired_chunks(&world_structures, &asset_lib);
+    chunk_collisions.0 = collisions;
+
+    spawned_chunks.0.retain(|xyz, spawned| {
+        // Themes are held by the world structure, not the chunk, but still change how it looks
+        let unchanged = chunks.get(xyz).is_some_and(|chunk| {
+            *chunk == spawned.chunk
+                && world_structures.theme(&chunk.world_structure) == spawned.theme.as_ref()
+        });
+        if !unchanged {
+            commands.entity(spawned.entity).despawn_recursive();
+        }
+        unchanged
+    });
 
-    for (_, (handle, active)) in &asset_lib.ws_handles {
-        if *active {
-            let ws = world_structures.get(handle.id()).unwrap();
-
-            for chunk in &ws.chunks {
-                spawn_chunk_bundle(
-                    &chunk,
-                    &mut commands,
-                    &asset_server,
-                    &mut meshes,
-                    &mut materials,
-                    &world_data,
-                );
-            }
+    for (xyz, chunk) in chunks {
+        if spawned_chunks.0.contains_key(&xyz) {
+            continue;
         }
+
+        // Decor would only get in the way of editing structures
+        let entity = spawn_chunk_bundle(
+            DEFAULT_WORLD_SEED,
+            &world_dims,
+            DecorDensity::Off,
+            &chunk,
+            &world_structures,
+            &mut commands,
+            &asset_server,
+            &mut meshes,
+            &mut materials,
+            &world_data,
+        );
+        spawned_chunks.0.insert(
+            xyz,
+            SpawnedChunk {
+                entity,
+                theme: world_structures.theme(&chunk.world_structure).cloned(),
+                chunk,
+            },
+        );
     }
 }
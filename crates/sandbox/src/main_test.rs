@@ -0,0 +1,121 @@
+use crate::{
+    update_chunks, AssetLib, ChunkCollision, ChunkCollisions, SpawnedChunks, WorldStructureEntry,
+};
+use bevy::{prelude::*, utils::HashSet};
+use dungeon_maze_common::world::{
+    data::WorldData, world_structure::WorldStructureRegistry, ChunkMarker, WorldDims,
+};
+
+fn world_structures() -> WorldStructureRegistry {
+    WorldStructureRegistry::from_dir(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../../assets/world_structures"),
+        WorldDims::default().grid_size(),
+    )
+    .unwrap()
+}
+
+fn new_test_app(world_structures: WorldStructureRegistry) -> App {
+    let mut app = App::new();
+
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin {
+            watch_for_changes_override: Some(false),
+            ..default()
+        },
+        HierarchyPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<StandardMaterial>()
+    .init_asset::<Image>()
+    .init_asset::<Scene>()
+    .init_resource::<WorldDims>()
+    .init_resource::<WorldData>()
+    .init_resource::<AssetLib>()
+    .init_resource::<SpawnedChunks>()
+    .init_resource::<ChunkCollisions>()
+    .insert_resource(world_structures)
+    .add_systems(Update, update_chunks);
+
+    app
+}
+
+fn set_active(app: &mut App, names: &[&str]) {
+    let mut asset_lib = app.world_mut().resource_mut::<AssetLib>();
+    asset_lib.ws_entries.clear();
+    for name in names {
+        asset_lib.ws_entries.insert(
+            name.to_string(),
+            WorldStructureEntry {
+                active: true,
+                offset: (0, 0, 0),
+            },
+        );
+    }
+}
+
+fn chunk_entities(app: &mut App, xyz: (i64, i64, i64)) -> Vec<Entity> {
+    let world = app.world_mut();
+    world
+        .query::<(Entity, &ChunkMarker)>()
+        .iter(world)
+        .filter(|(_, chunk_marker)| chunk_marker.0 == xyz)
+        .map(|(entity, _)| entity)
+        .collect()
+}
+
+#[test]
+fn test_overlapping_chunks_are_only_spawned_once() {
+    let world_structures = world_structures();
+    let chunks_xyz = |name: &str| -> HashSet<(i64, i64, i64)> {
+        world_structures
+            .get(name)
+            .unwrap()
+            .chunks
+            .iter()
+            .map(|chunk| (chunk.x, chunk.y, chunk.z))
+            .collect()
+    };
+
+    // Any two world structures that share a chunk, which those that start at the origin do
+    let names: Vec<String> = world_structures.iter().map(|ws| ws.name.clone()).collect();
+    let (first, second, shared) = names
+        .iter()
+        .enumerate()
+        .flat_map(|(i, a)| names[i + 1..].iter().map(move |b| (a, b)))
+        .find_map(|(a, b)| {
+            let shared = *chunks_xyz(a).intersection(&chunks_xyz(b)).next()?;
+            Some((a.clone(), b.clone(), shared))
+        })
+        .unwrap();
+
+    let mut app = new_test_app(world_structures);
+    set_active(&mut app, &[&first, &second]);
+    app.update();
+
+    let entities = chunk_entities(&mut app, shared);
+    assert_eq!(entities.len(), 1);
+    assert!(app
+        .world()
+        .resource::<ChunkCollisions>()
+        .0
+        .contains(&ChunkCollision {
+            xyz: shared,
+            kept: first.clone(),
+            skipped: second.clone(),
+        }));
+
+    // Nothing changed at the shared coordinates once the second one is turned off,
+    // so the chunk that was already there is left alone
+    set_active(&mut app, &[&first]);
+    app.update();
+    assert_eq!(chunk_entities(&mut app, shared), entities);
+    assert!(app.world().resource::<ChunkCollisions>().0.is_empty());
+
+    // Whereas the second one's chunk replaces it once it is the only one active
+    set_active(&mut app, &[&second]);
+    app.update();
+    let replaced = chunk_entities(&mut app, shared);
+    assert_eq!(replaced.len(), 1);
+    assert_ne!(replaced, entities);
+}